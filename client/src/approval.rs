@@ -0,0 +1,316 @@
+// LARGE FILE APPROVAL ---------------------------------------------------------
+//
+// new files above `large_files.threshold_mb` (vm images, disk dumps, ...) aren't uploaded until
+// someone approves them via `client approve` (or the timeout policy decides)
+
+use serde::{Deserialize, Serialize};
+use shared::dtos::FileDescription;
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::SyncInstruction;
+use shared::utc_millis::UtcMillis;
+use tracing::{error, info, warn};
+
+use crate::WatchGroup;
+use crate::config::{LargeFilesConfig, TimeoutPolicy};
+use crate::status::StatusHandle;
+use crate::syncignore;
+
+/// a held upload - listed on the control port and in the web UI (as scan warning)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub wg_id: i64,
+    pub watch_group: String,
+    /// unix-delimited, relative to the watch group's folder
+    pub path: String,
+    pub size_in_bytes: u64,
+    pub since: UtcMillis,
+    /// set via the control port - applied with the next poll cycle
+    pub decision: Option<Decision>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Upload,
+    /// added to the .syncignore of the watch group
+    Ignore,
+}
+
+/// drops the uploads of large files nobody approved yet - files the daemon saw before (or
+/// approved) go through, just like the ones of a disabled threshold
+pub fn hold_large_uploads(
+    config: &LargeFilesConfig,
+    wg_id: i64,
+    watch_group: &WatchGroup,
+    instructions: Vec<SyncInstruction>,
+    descriptions: &[FileDescription],
+    last_scan: Option<&[FileDescription]>,
+    status: &StatusHandle,
+) -> Vec<SyncInstruction> {
+    if config.threshold_mb == 0 {
+        return instructions;
+    }
+    let threshold = config.threshold_mb * 1024 * 1024;
+    let now = UtcMillis::now();
+    let mut ignored = Vec::new();
+    let instructions = instructions
+        .into_iter()
+        .filter(|instruction| {
+            let SyncInstruction::Upload(path) = instruction else {
+                return true;
+            };
+            let Some(description) = descriptions.iter().find(|d| d.relative_path == *path) else {
+                return true;
+            };
+            if description.size_in_bytes < threshold {
+                return true;
+            }
+            let key = path.to_serialized_string();
+            let is_new =
+                last_scan.is_none_or(|last| !last.iter().any(|d| d.relative_path == *path));
+            let mut upload = true;
+            status.update(|s| {
+                if s.approved.contains(&(wg_id, key.clone())) {
+                    return;
+                }
+                let pending = s
+                    .pending_approvals
+                    .iter()
+                    .position(|p| p.wg_id == wg_id && p.path == key);
+                let decision = match pending {
+                    None if !is_new => return,
+                    None => {
+                        s.pending_approvals.push(PendingApproval {
+                            wg_id,
+                            watch_group: watch_group.name.clone(),
+                            path: key.clone(),
+                            size_in_bytes: description.size_in_bytes,
+                            since: now.clone(),
+                            decision: None,
+                        });
+                        warn!(
+                            path = key,
+                            "'{key}' is larger than {} MB - waiting for `client approve {}/{key}`",
+                            config.threshold_mb,
+                            watch_group.name
+                        );
+                        None
+                    }
+                    Some(idx) => {
+                        let pending = &mut s.pending_approvals[idx];
+                        pending.size_in_bytes = description.size_in_bytes;
+                        pending.decision.or_else(|| {
+                            timed_out(config, &pending.since, &now).map(|policy| {
+                                info!(path = key, "No decision on '{key}' - {:?}", policy);
+                                match policy {
+                                    TimeoutPolicy::Upload => Decision::Upload,
+                                    TimeoutPolicy::Ignore => Decision::Ignore,
+                                }
+                            })
+                        })
+                    }
+                };
+                if decision.is_some() {
+                    s.pending_approvals
+                        .retain(|p| p.wg_id != wg_id || p.path != key);
+                }
+                match decision {
+                    None => upload = false,
+                    Some(Decision::Upload) => {
+                        s.approved.insert((wg_id, key.clone()));
+                    }
+                    Some(Decision::Ignore) => {
+                        upload = false;
+                        ignored.push(path.clone());
+                    }
+                }
+            });
+            upload
+        })
+        .collect();
+
+    // deleted or shrunk meanwhile
+    status.update(|s| {
+        s.pending_approvals.retain(|p| {
+            p.wg_id != wg_id
+                || descriptions.iter().any(|d| {
+                    d.size_in_bytes >= threshold && d.relative_path.to_serialized_string() == p.path
+                })
+        })
+    });
+    for path in ignored {
+        match syncignore::add(&watch_group.path_to_monitor, &path) {
+            Ok(_) => info!(
+                path = path.to_serialized_string(),
+                "Ignoring '{}' from now on",
+                path.to_serialized_string()
+            ),
+            Err(e) => error!(
+                "Could not add '{}' to .syncignore - {e}",
+                path.to_serialized_string()
+            ),
+        }
+    }
+    instructions
+}
+
+/// the policy to apply if the file waited longer than `timeout_secs` (0 = forever)
+fn timed_out(
+    config: &LargeFilesConfig,
+    since: &UtcMillis,
+    now: &UtcMillis,
+) -> Option<TimeoutPolicy> {
+    let waited = now.as_u64().saturating_sub(since.as_u64());
+    (config.timeout_secs > 0 && waited >= config.timeout_secs * 1000).then_some(config.on_timeout)
+}
+
+/// decides about a held upload (see [`PendingApproval::decision`]) - false if there's none
+pub fn decide(status: &StatusHandle, wg_id: i64, path: &str, decision: Decision) -> bool {
+    let mut found = false;
+    status.update(|s| {
+        if let Some(pending) = s
+            .pending_approvals
+            .iter_mut()
+            .find(|p| p.wg_id == wg_id && p.path == path)
+        {
+            pending.decision = Some(decision);
+            found = true;
+        }
+    });
+    if found {
+        status.request_sync();
+    }
+    found
+}
+
+/// shown as scan warning of the watch group in the web UI
+pub fn warning(status: &StatusHandle, wg_id: i64) -> Option<String> {
+    let files = status.read(|s| {
+        s.pending_approvals
+            .iter()
+            .filter(|p| p.wg_id == wg_id && p.decision.is_none())
+            .map(|p| format!("{} ({} MB)", p.path, p.size_in_bytes / (1024 * 1024)))
+            .collect::<Vec<_>>()
+    });
+    (!files.is_empty()).then(|| {
+        format!(
+            "{} large file(s) waiting for approval on the client (`client approve`): {}",
+            files.len(),
+            files.join(", ")
+        )
+    })
+}
+
+/// `<watch group name or id>/<path>` of a pending approval
+pub fn find<'a>(pending: &'a [PendingApproval], path: &str) -> Option<&'a PendingApproval> {
+    let (wg, path) = path.trim_start_matches('/').split_once('/')?;
+    let path = MatchablePath::from(path.split('/').collect::<Vec<_>>()).to_serialized_string();
+    pending
+        .iter()
+        .find(|p| (p.watch_group == wg || p.wg_id.to_string() == wg) && p.path == path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn description(path: &str, size: u64) -> FileDescription {
+        FileDescription {
+            file_name: path.to_string(),
+            relative_path: MatchablePath::from(path),
+            size_in_bytes: size,
+            file_type: "img".to_string(),
+            last_updated_utc_millis: UtcMillis::from(1000),
+        }
+    }
+
+    fn uploads(paths: &[&str]) -> Vec<SyncInstruction> {
+        paths
+            .iter()
+            .map(|p| SyncInstruction::Upload(MatchablePath::from(*p)))
+            .collect()
+    }
+
+    #[test]
+    fn should_hold_new_large_files_until_decided() {
+        let root = std::env::temp_dir().join(format!("approval-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let watch_group = WatchGroup {
+            name: "vms".to_string(),
+            path_to_monitor: PathBuf::from(&root),
+            exclude_dirs: vec![],
+            exclude_dot_dirs: true,
+            priority: 0,
+        };
+        let config = LargeFilesConfig {
+            threshold_mb: 1024,
+            ..LargeFilesConfig::default()
+        };
+        let status = StatusHandle::default();
+        let last = vec![description("known.img", 3 * GB)];
+        let current = vec![
+            description("known.img", 3 * GB),
+            description("new.img", 3 * GB),
+            description("other.img", 3 * GB),
+            description("small.txt", 10),
+        ];
+        let hold = |instructions| {
+            hold_large_uploads(
+                &config,
+                1,
+                &watch_group,
+                instructions,
+                &current,
+                Some(&last),
+                &status,
+            )
+            .len()
+        };
+
+        assert_eq!(
+            2,
+            hold(uploads(&["known.img", "new.img", "other.img", "small.txt"]))
+        );
+        assert!(warning(&status, 1).is_some_and(|w| w.contains("new.img (3072 MB)")));
+        let pending = status.read(|s| s.pending_approvals.clone());
+        assert_eq!(Some(1), find(&pending, "vms/new.img").map(|p| p.wg_id));
+        assert_eq!(None, find(&pending, "vms/known.img"));
+
+        assert!(decide(&status, 1, "new.img", Decision::Upload));
+        assert!(decide(&status, 1, "other.img", Decision::Ignore));
+        assert!(!decide(&status, 1, "small.txt", Decision::Upload));
+        assert_eq!(1, hold(uploads(&["new.img", "other.img"])));
+        assert_eq!(None, warning(&status, 1));
+        assert!(syncignore::SyncIgnore::load(&root).is_ignored(&MatchablePath::from("other.img")));
+        // approved once - not held again
+        assert_eq!(1, hold(uploads(&["new.img"])));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn should_apply_timeout_policy() {
+        let config = LargeFilesConfig {
+            timeout_secs: 60,
+            on_timeout: TimeoutPolicy::Ignore,
+            ..LargeFilesConfig::default()
+        };
+        let since = UtcMillis::from(1_000_000);
+        assert_eq!(
+            None,
+            timed_out(&config, &since, &UtcMillis::from(1_059_999))
+        );
+        assert_eq!(
+            Some(TimeoutPolicy::Ignore),
+            timed_out(&config, &since, &UtcMillis::from(1_060_000))
+        );
+        let forever = LargeFilesConfig::default();
+        assert_eq!(
+            None,
+            timed_out(&forever, &since, &UtcMillis::from(u64::MAX))
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use reqwest::Client;
+use shared::dtos::{BandwidthUsageDto, BandwidthUsageReportDto};
+use shared::endpoint::ServerEndpoint;
+use tracing::{error, info, warn};
+
+use crate::config::BandwidthCapConfig;
+use crate::stats::CycleStats;
+use crate::status::StatusHandle;
+
+const MB: u64 = 1024 * 1024;
+
+/// where the transfers of the month stand against the caps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapState {
+    Below,
+    /// warned about
+    Soft,
+    /// syncing stops until the next month
+    Hard,
+}
+
+/// counts the transfers of the daemon per month - the server keeps the totals, so a restarted
+/// daemon continues where it stopped
+pub struct BandwidthMeter {
+    caps: BandwidthCapConfig,
+    usage: BandwidthUsageDto,
+    /// counted but not reported yet (server unreachable) - (uploaded, downloaded)
+    unreported: (u64, u64),
+    /// crossing a cap is logged once
+    logged: CapState,
+}
+
+impl BandwidthMeter {
+    fn new(caps: BandwidthCapConfig, month: String) -> Self {
+        Self {
+            caps,
+            usage: BandwidthUsageDto {
+                month,
+                uploaded_bytes: 0,
+                downloaded_bytes: 0,
+                soft_cap_bytes: caps.soft_cap_mb * MB,
+                hard_cap_bytes: caps.hard_cap_mb * MB,
+            },
+            unreported: (0, 0),
+            logged: CapState::Below,
+        }
+    }
+
+    /// starts with what the server has on record for the current month (0 if unreachable)
+    pub async fn restore(http: &Client, server_url: &str, caps: BandwidthCapConfig) -> Self {
+        let mut meter = Self::new(caps, current_month());
+        match fetch_usage(http, server_url, &meter.usage.month).await {
+            Ok(usage) => {
+                meter.usage.uploaded_bytes = usage.uploaded_bytes;
+                meter.usage.downloaded_bytes = usage.downloaded_bytes;
+            }
+            Err(e) => warn!("Could not fetch the bandwidth usage of this month - {e}"),
+        }
+        meter
+    }
+
+    /// logs crossing a cap - true while the hard cap is reached; a new month starts from zero
+    pub fn check(&mut self, month: &str, status: &StatusHandle) -> bool {
+        if self.usage.month != month {
+            info!("New month - bandwidth usage starts from zero");
+            // bytes the server never got belong to the old month - they don't count for the new one
+            *self = Self::new(self.caps, month.to_string());
+        }
+        let state = cap_state(&self.usage);
+        if state != self.logged {
+            let used = self.usage.total_bytes() / MB;
+            match state {
+                CapState::Below => info!("Bandwidth usage ({used} MB) is below the caps again"),
+                CapState::Soft => warn!(
+                    "Transferred {used} MB this month - above the soft cap of {} MB",
+                    self.caps.soft_cap_mb
+                ),
+                CapState::Hard => {
+                    let message = format!(
+                        "Transferred {used} MB this month - hard cap of {} MB reached, syncing \
+                         stops until next month",
+                        self.caps.hard_cap_mb
+                    );
+                    error!("{message}");
+                    status.error(message);
+                }
+            }
+            self.logged = state;
+        }
+        state == CapState::Hard
+    }
+
+    pub fn record(&mut self, cycle: &CycleStats) {
+        self.usage.uploaded_bytes += cycle.bytes_uploaded;
+        self.usage.downloaded_bytes += cycle.bytes_downloaded;
+        self.unreported.0 += cycle.bytes_uploaded;
+        self.unreported.1 += cycle.bytes_downloaded;
+    }
+
+    /// sends what was counted since the last report - retried next cycle if that fails
+    pub async fn report(&mut self, http: &Client, server_url: &str) {
+        if self.unreported == (0, 0) {
+            return;
+        }
+        let report = BandwidthUsageReportDto {
+            month: self.usage.month.clone(),
+            uploaded_bytes: self.unreported.0,
+            downloaded_bytes: self.unreported.1,
+            soft_cap_bytes: self.usage.soft_cap_bytes,
+            hard_cap_bytes: self.usage.hard_cap_bytes,
+        };
+        let reply = http
+            .post(ServerEndpoint::BandwidthUsage.to_uri(server_url))
+            .json(&report)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let usage: Result<BandwidthUsageDto, _> = match reply {
+            Ok(response) => response.json().await,
+            Err(e) => Err(e),
+        };
+        match usage {
+            Ok(usage) => {
+                self.unreported = (0, 0);
+                // the server's totals win (e.g. usage of a claimed installation)
+                if usage.month == self.usage.month {
+                    self.usage.uploaded_bytes = usage.uploaded_bytes;
+                    self.usage.downloaded_bytes = usage.downloaded_bytes;
+                }
+            }
+            Err(e) => warn!("Could not report bandwidth usage to server - {e}"),
+        }
+    }
+}
+
+pub fn cap_state(usage: &BandwidthUsageDto) -> CapState {
+    let used = usage.total_bytes();
+    if usage.hard_cap_bytes > 0 && used >= usage.hard_cap_bytes {
+        CapState::Hard
+    } else if usage.soft_cap_bytes > 0 && used >= usage.soft_cap_bytes {
+        CapState::Soft
+    } else {
+        CapState::Below
+    }
+}
+
+/// what the server has on record for `month` - zero if nothing
+pub async fn fetch_usage(
+    http: &Client,
+    server_url: &str,
+    month: &str,
+) -> Result<BandwidthUsageDto, String> {
+    http.get(ServerEndpoint::BandwidthUsage.to_uri(server_url))
+        .query(&[("month", month)])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// YYYY-MM of now (UTC)
+pub fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(up: u64, down: u64) -> CycleStats {
+        CycleStats {
+            bytes_uploaded: up * MB,
+            bytes_downloaded: down * MB,
+            ..CycleStats::default()
+        }
+    }
+
+    #[test]
+    fn should_stop_at_the_hard_cap_until_next_month() {
+        let caps = BandwidthCapConfig {
+            soft_cap_mb: 100,
+            hard_cap_mb: 150,
+        };
+        let status = StatusHandle::default();
+        let mut meter = BandwidthMeter::new(caps, "2026-10".to_string());
+
+        meter.record(&cycle(60, 30));
+        assert!(!meter.check("2026-10", &status));
+        assert_eq!(CapState::Below, cap_state(&meter.usage));
+        meter.record(&cycle(0, 10));
+        assert!(!meter.check("2026-10", &status));
+        assert_eq!(CapState::Soft, cap_state(&meter.usage));
+        meter.record(&cycle(50, 0));
+        assert!(meter.check("2026-10", &status));
+        assert_eq!(1, status.read(|s| s.recent_errors.len()));
+
+        assert!(!meter.check("2026-11", &status));
+        assert_eq!(0, meter.usage.total_bytes());
+        assert_eq!((0, 0), meter.unreported);
+    }
+
+    #[test]
+    fn should_ignore_caps_of_zero() {
+        let meter = BandwidthMeter::new(BandwidthCapConfig::default(), "2026-10".to_string());
+        let usage = BandwidthUsageDto {
+            uploaded_bytes: u64::MAX / 2,
+            ..meter.usage
+        };
+        assert_eq!(CapState::Below, cap_state(&usage));
+    }
+}
@@ -0,0 +1,176 @@
+// BENCH -----------------------------------------------------------------------
+//
+// `client bench` - round trips and synthetic transfers against /api/bench, to tell a slow link
+// from a slow sync (nothing is stored on the server)
+
+use bytes::Bytes;
+use futures_util::{StreamExt, stream};
+use reqwest::Body;
+use reqwest::header::CONTENT_LENGTH;
+use serde::Serialize;
+use shared::dtos::BenchUploadDto;
+use shared::endpoint::ServerEndpoint;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config::fetch_watch_config;
+use crate::oneshot::{OutputFormat, print};
+use crate::setup::connect;
+
+/// round trips to `/ping` before the transfers
+const PINGS: u32 = 10;
+const CHUNK_BYTES: u64 = 1024 * 1024;
+
+/// outcome of `client bench` - MB are 1024 * 1024 bytes (like `--size`)
+#[derive(Debug, Serialize)]
+struct BenchOutput {
+    size_in_bytes: u64,
+    latency_min_ms: f64,
+    latency_avg_ms: f64,
+    latency_max_ms: f64,
+    upload_mb_per_sec: f64,
+    download_mb_per_sec: f64,
+}
+
+/// `512K`, `100M`, `1G` or plain bytes - binary units
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Could not parse '{value}' - expected e.g. 100M or 1G"))?;
+    let factor: u64 = match unit.trim().to_uppercase().trim_end_matches(['B', 'I']) {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return Err(format!("Unknown unit '{unit}' - expected K, M or G")),
+    };
+    number
+        .checked_mul(factor)
+        .filter(|bytes| *bytes > 0)
+        .ok_or_else(|| format!("'{value}' is out of range"))
+}
+
+fn mb_per_sec(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// latency (`/ping`), then uploads and downloads `size` bytes of synthetic data
+pub async fn bench(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    size: u64,
+) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    let http = &transport.http;
+    // registers a new client - the bench is refused to unknown ones
+    fetch_watch_config(http, &config.server_url).await;
+
+    let mut latencies = Vec::new();
+    for _ in 0..PINGS {
+        let start = Instant::now();
+        http.get(ServerEndpoint::Ping.to_uri(&config.server_url))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Ping failed - {e}"))?;
+        latencies.push(start.elapsed());
+    }
+
+    let chunk = Bytes::from(vec![0x5a; CHUNK_BYTES as usize]);
+    let chunks = stream::iter((0..size.div_ceil(CHUNK_BYTES)).map(move |i| {
+        let len = CHUNK_BYTES.min(size - i * CHUNK_BYTES) as usize;
+        Ok::<_, io::Error>(chunk.slice(..len))
+    }));
+    let start = Instant::now();
+    let uploaded: BenchUploadDto = http
+        .post(ServerEndpoint::ApiBenchUpload.to_uri(&config.server_url))
+        .header(CONTENT_LENGTH, size)
+        .body(Body::wrap_stream(chunks))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Upload failed - {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Upload failed - {e}"))?;
+    let upload = start.elapsed();
+    if uploaded.received_bytes != size {
+        return Err(format!(
+            "Upload incomplete - server received {} of {size} bytes",
+            uploaded.received_bytes
+        ));
+    }
+
+    let start = Instant::now();
+    let mut body = http
+        .get(format!(
+            "{}?bytes={size}",
+            ServerEndpoint::ApiBenchDownload.to_uri(&config.server_url)
+        ))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Download failed - {e}"))?
+        .bytes_stream();
+    let mut downloaded = 0;
+    while let Some(chunk) = body.next().await {
+        downloaded += chunk.map_err(|e| format!("Download failed - {e}"))?.len() as u64;
+    }
+    let download = start.elapsed();
+    if downloaded != size {
+        return Err(format!(
+            "Download incomplete - received {downloaded} of {size} bytes"
+        ));
+    }
+
+    let result = BenchOutput {
+        size_in_bytes: size,
+        latency_min_ms: latencies.iter().min().copied().map_or(0.0, millis),
+        latency_avg_ms: millis(latencies.iter().sum::<Duration>() / PINGS),
+        latency_max_ms: latencies.iter().max().copied().map_or(0.0, millis),
+        upload_mb_per_sec: mb_per_sec(size, upload),
+        download_mb_per_sec: mb_per_sec(size, download),
+    };
+    print(output, &result, |result| {
+        [
+            format!(
+                "latency:  {:.1} ms avg ({:.1} - {:.1} ms, {PINGS} pings)",
+                result.latency_avg_ms, result.latency_min_ms, result.latency_max_ms
+            ),
+            format!("upload:   {:.1} MB/s", result.upload_mb_per_sec),
+            format!("download: {:.1} MB/s", result.download_mb_per_sec),
+        ]
+        .join("\n")
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_sizes() {
+        assert_eq!(Ok(4096), parse_size("4096"));
+        assert_eq!(Ok(512 * 1024), parse_size("512K"));
+        assert_eq!(Ok(100 * 1024 * 1024), parse_size("100m"));
+        assert_eq!(Ok(1024 * 1024 * 1024), parse_size("1G"));
+        assert_eq!(Ok(2 * 1024 * 1024 * 1024), parse_size("2GiB"));
+        assert_eq!(Ok(3 * 1024 * 1024), parse_size("3 MB"));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("1T").is_err());
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("99999999999G").is_err());
+    }
+}
@@ -0,0 +1,348 @@
+use futures_util::StreamExt;
+use reqwest::Client;
+use reqwest::multipart::{Form, Part};
+use shared::dtos::{BulkUploadResultDto, FileDescription};
+use shared::endpoint::{SNAPSHOT_FILES_DIR, SNAPSHOT_MANIFEST_ENTRY, SysRoute};
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::SyncInstruction;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::BulkUploadConfig;
+use crate::execute::{self, NOT_LATEST_REPLY};
+use crate::hooks::{HookEvent, HooksConfig};
+use crate::status::{StatusHandle, TransferDirection};
+
+/// splits the upload instructions into tar batches, everything else is executed one by one
+///
+/// only kicks in for many pending uploads (initial sync) - see [`BulkUploadConfig`]
+pub fn batch_uploads(
+    instructions: Vec<SyncInstruction>,
+    descriptions: &[FileDescription],
+    config: &BulkUploadConfig,
+) -> (Vec<Vec<FileDescription>>, Vec<SyncInstruction>) {
+    let upload_count = instructions
+        .iter()
+        .filter(|i| matches!(i, SyncInstruction::Upload(_)))
+        .count();
+    if config.min_files == 0 || upload_count < config.min_files {
+        return (Vec::new(), instructions);
+    }
+
+    let max_batch_bytes = config.batch_size_mb * 1024 * 1024;
+    let by_path: HashMap<&MatchablePath, &FileDescription> =
+        descriptions.iter().map(|d| (&d.relative_path, d)).collect();
+    let mut batches: Vec<Vec<FileDescription>> = Vec::new();
+    let mut batch_bytes = 0;
+    let mut remaining = Vec::new();
+
+    for instruction in instructions {
+        let description = match &instruction {
+            SyncInstruction::Upload(path) => by_path
+                .get(path)
+                .filter(|d| d.size_in_bytes <= max_batch_bytes),
+            _ => None,
+        };
+        let Some(description) = description else {
+            remaining.push(instruction);
+            continue;
+        };
+
+        match batches.last_mut() {
+            Some(batch) if batch_bytes + description.size_in_bytes <= max_batch_bytes => {
+                batch.push((*description).clone());
+                batch_bytes += description.size_in_bytes;
+            }
+            _ => {
+                batches.push(vec![(*description).clone()]);
+                batch_bytes = description.size_in_bytes;
+            }
+        }
+    }
+
+    (batches, remaining)
+}
+
+/// sends the files of the batch as one tar archive
+pub async fn upload_batch(
+    client: &Client,
+    batch: Vec<FileDescription>,
+    root: &Path,
+    base: &str,
+    wg_id: i64,
+    hooks: &HooksConfig,
+    status: &StatusHandle,
+) -> Result<(), String> {
+    let bytes_total = batch.iter().map(|d| d.size_in_bytes).sum();
+    let transfer = status.start_transfer(
+        wg_id,
+        format!("{} files (bulk)", batch.len()),
+        TransferDirection::Upload,
+        bytes_total,
+    );
+
+    let manifest = serde_json::to_string(&batch).unwrap();
+    let archive_root = root.to_path_buf();
+    let archive = tokio::task::spawn_blocking(move || build_archive(&archive_root, &batch))
+        .await
+        .map_err(|e| format!("Building bulk archive failed - {e}"))?
+        .map_err(|e| format!("Building bulk archive failed - {e}"))?;
+
+    let form = Form::new()
+        .text("manifest", manifest)
+        .part("archive", Part::bytes(archive).file_name("batch.tar"));
+    let response = client
+        .post(SysRoute::UploadBulk { wg_id }.to_uri(base))
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| format!("Bulk upload failed - {e}"))?;
+    let response_status = response.status();
+    if !response_status.is_success() {
+        return Err(format!(
+            "Bulk upload failed - {response_status} - {}",
+            response.text().await.unwrap_or_default()
+        ));
+    }
+    let result: BulkUploadResultDto = response
+        .json()
+        .await
+        .map_err(|e| format!("Bulk upload reply could not be parsed - {e}"))?;
+    transfer.advance(bytes_total);
+
+    for path in &result.stored {
+        info!(path, event_type = "change", "Upload successful (bulk)");
+    }
+    for rejection in result.rejected {
+        warn!(
+            path = rejection.relative_path,
+            "Bulk upload of '{}' refused - {}", rejection.relative_path, rejection.reason
+        );
+        if rejection.reason == NOT_LATEST_REPLY {
+            let path = MatchablePath::from(rejection.relative_path.as_str());
+            status.conflict(rejection.relative_path);
+            if let Err(e) = hooks
+                .run(HookEvent::Conflict, wg_id, root, Some(&path))
+                .await
+            {
+                status.error(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// fetches the whole watch group as one archive - for clients without any local files
+///
+/// returns the number of restored files
+pub async fn download_snapshot(
+    client: &Client,
+    root: &Path,
+    base: &str,
+    wg_id: i64,
+    status: &StatusHandle,
+) -> Result<usize, String> {
+    let response = client
+        .get(SysRoute::DownloadSnapshot { wg_id }.to_uri(base))
+        .send()
+        .await
+        .map_err(|e| format!("Snapshot request failed - {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Snapshot request failed - {e}"))?;
+    let transfer = status.start_transfer(
+        wg_id,
+        "snapshot".to_string(),
+        TransferDirection::Download,
+        response.content_length().unwrap_or(0),
+    );
+
+    let archive_path = std::env::temp_dir().join(format!("{}_snapshot.tar", Uuid::new_v4()));
+    let result = async {
+        let mut archive = tokio::fs::File::create(&archive_path)
+            .await
+            .map_err(|e| format!("Could not save snapshot - {e}"))?;
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let bytes = chunk.map_err(|e| format!("Snapshot download failed - {e}"))?;
+            archive
+                .write_all(&bytes)
+                .await
+                .map_err(|e| format!("Could not save snapshot - {e}"))?;
+            transfer.advance(bytes.len() as u64);
+        }
+        archive
+            .flush()
+            .await
+            .map_err(|e| format!("Could not save snapshot - {e}"))?;
+
+        let (archive_path, root) = (archive_path.clone(), root.to_path_buf());
+        tokio::task::spawn_blocking(move || unpack_snapshot(&archive_path, &root))
+            .await
+            .map_err(|e| format!("Unpacking snapshot failed - {e}"))?
+            .map_err(|e| format!("Unpacking snapshot failed - {e}"))
+    }
+    .await;
+    let _ = tokio::fs::remove_file(&archive_path).await;
+
+    let restored = result?;
+    info!(
+        event_type = "change",
+        "Restored {} files from snapshot",
+        restored.len()
+    );
+    Ok(restored.len())
+}
+
+/// writes every archived file listed in the manifest below `root` - never overwrites local files
+fn unpack_snapshot(archive_path: &Path, root: &Path) -> io::Result<Vec<FileDescription>> {
+    let mut archive = tar::Archive::new(fs::File::open(archive_path)?);
+    let mut entries = archive.entries()?;
+    let mut manifest: HashMap<MatchablePath, FileDescription> = match entries.next() {
+        Some(entry)
+            if entry.as_ref().is_ok_and(|e| {
+                e.path()
+                    .is_ok_and(|p| p.as_ref() == Path::new(SNAPSHOT_MANIFEST_ENTRY))
+            }) =>
+        {
+            serde_json::from_reader::<_, Vec<FileDescription>>(entry?)?
+                .into_iter()
+                .map(|d| (d.relative_path.clone(), d))
+                .collect()
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "snapshot without manifest",
+            ));
+        }
+    };
+
+    let mut restored = Vec::new();
+    for entry in entries {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        let Ok(relative_path) = entry_path.strip_prefix(SNAPSHOT_FILES_DIR) else {
+            continue;
+        };
+        // only normal components are kept - an entry can't point outside the root
+        let relative_path = MatchablePath::from(relative_path);
+        let Some(description) = manifest.remove(&relative_path) else {
+            continue;
+        };
+        let file_path = relative_path.resolve(root);
+        if file_path.exists() {
+            continue;
+        }
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut fs::File::create(&file_path)?)?;
+        execute::stamp(&file_path, &description.last_updated_utc_millis)?;
+        restored.push(description);
+    }
+    Ok(restored)
+}
+
+fn build_archive(root: &Path, batch: &[FileDescription]) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for description in batch {
+        let file: PathBuf = description.relative_path.resolve(root);
+        builder.append_path_with_name(file, description.relative_path.get().join("/"))?;
+    }
+    builder.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::utc_millis::UtcMillis;
+
+    fn description(path: &str, size_in_bytes: u64) -> FileDescription {
+        FileDescription {
+            file_name: path.to_string(),
+            relative_path: MatchablePath::from(path),
+            size_in_bytes,
+            file_type: "txt".to_string(),
+            last_updated_utc_millis: UtcMillis::from(1),
+        }
+    }
+
+    #[test]
+    fn should_restore_files_of_snapshot() {
+        let dir = std::env::temp_dir().join(format!("snapshot-{}", Uuid::new_v4()));
+        let root = dir.join("root");
+        fs::create_dir_all(&root).unwrap();
+        let manifest = serde_json::to_vec(&vec![description("docs/a.txt", 1)]).unwrap();
+
+        let archive_path = dir.join("snapshot.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive_path).unwrap());
+        let mut append = |name: &str, data: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, data).unwrap();
+        };
+        append(SNAPSHOT_MANIFEST_ENTRY, &manifest);
+        append("files/docs/a.txt", b"a");
+        append("files/not-in-manifest.txt", b"b");
+        builder.finish().unwrap();
+        drop(builder);
+
+        let restored = unpack_snapshot(&archive_path, &root).unwrap();
+
+        assert_eq!(1, restored.len());
+        assert_eq!("a", fs::read_to_string(root.join("docs/a.txt")).unwrap());
+        assert!(!root.join("not-in-manifest.txt").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_batch_uploads_up_to_batch_size() {
+        let mb = 1024 * 1024;
+        let descriptions = vec![
+            description("a", mb),
+            description("b", mb),
+            description("c", mb),
+            description("huge", 3 * mb),
+        ];
+        let instructions = || {
+            vec![
+                SyncInstruction::Upload(MatchablePath::from("a")),
+                SyncInstruction::Upload(MatchablePath::from("b")),
+                SyncInstruction::Download(MatchablePath::from("d")),
+                SyncInstruction::Upload(MatchablePath::from("c")),
+                SyncInstruction::Upload(MatchablePath::from("huge")),
+            ]
+        };
+        let config = BulkUploadConfig {
+            min_files: 2,
+            batch_size_mb: 2,
+        };
+
+        let (batches, remaining) = batch_uploads(instructions(), &descriptions, &config);
+
+        let batch_paths: Vec<Vec<String>> = batches
+            .iter()
+            .map(|b| b.iter().map(|d| d.file_name.clone()).collect())
+            .collect();
+        assert_eq!(vec![vec!["a", "b"], vec!["c"]], batch_paths);
+        assert_eq!(2, remaining.len());
+
+        let (batches, remaining) = batch_uploads(
+            instructions(),
+            &descriptions,
+            &BulkUploadConfig {
+                min_files: 0,
+                ..config
+            },
+        );
+        assert!(batches.is_empty());
+        assert_eq!(5, remaining.len());
+    }
+}
@@ -0,0 +1,10 @@
+use tokio::process::Command;
+
+/// stdout of the program - None if it can't be run or fails
+pub(crate) async fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
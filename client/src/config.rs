@@ -1,31 +1,261 @@
-use reqwest::Client;
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy};
 use serde::{Deserialize, Serialize};
-use shared::{dtos::WatchConfigDto, endpoint::ServerEndpoint};
-use std::{collections::VecDeque, env, fs, path::PathBuf};
+use shared::{
+    dtos::WatchConfigDto, endpoint::ServerEndpoint, get_files_of_directory::ScanLimits,
+    temp_files::TempFilePatterns,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fs,
+    path::{Path, PathBuf},
+};
 use tracing::{info, warn};
 use uuid::Uuid;
 
+use crate::control::DEFAULT_CONTROL_PORT;
+use crate::hooks::HooksConfig;
+use crate::network::NetworkConfig;
+use crate::power::PowerConfig;
+
 /// local config (config.yaml)
 #[derive(Debug, Deserialize, Serialize)]
 struct LocalConfig {
     client_id: Option<String>,
     server_url: String,
+    #[serde(default)]
+    bulk_upload: BulkUploadConfig,
+    #[serde(default)]
+    hooks: HooksConfig,
+    #[serde(default = "default_control_port")]
+    control_port: u16,
+    #[serde(default = "default_max_poll_interval_in_ms")]
+    max_poll_interval_in_ms: u64,
+    #[serde(default)]
+    temp_files: TempFilesConfig,
+    #[serde(default)]
+    scan_limits: ScanLimits,
+    #[serde(default)]
+    transport: TransportKind,
+    #[serde(default)]
+    proxy: ProxyConfig,
+    #[serde(default)]
+    conflict_policy: ConflictPolicy,
+    #[serde(default)]
+    large_files: LargeFilesConfig,
+    #[serde(default = "default_min_free_disk_mb")]
+    min_free_disk_mb: u64,
+    #[serde(default)]
+    bandwidth_cap: BandwidthCapConfig,
+    #[serde(default)]
+    networks: NetworkConfig,
+    #[serde(default)]
+    power: PowerConfig,
+    #[serde(default = "default_slow_instruction_ms")]
+    slow_instruction_ms: u64,
+}
+
+fn default_control_port() -> u16 {
+    DEFAULT_CONTROL_PORT
+}
+
+fn default_min_free_disk_mb() -> u64 {
+    1024
+}
+
+fn default_slow_instruction_ms() -> u64 {
+    10_000
+}
+
+fn default_max_poll_interval_in_ms() -> u64 {
+    60_000
 }
 
 #[derive(Debug)]
 pub struct Config {
     pub client_id: Uuid,
     pub server_url: String,
+    pub bulk_upload: BulkUploadConfig,
+    pub hooks: HooksConfig,
+    /// local port serving the sync stats of the daemon (0 = off)
+    pub control_port: u16,
+    /// idle clients poll less often - up to this interval (at most the server's minimum = off)
+    pub max_poll_interval_in_ms: u64,
+    pub temp_files: TempFilesConfig,
+    /// scans of watch groups with more files / deeper trees are aborted
+    pub scan_limits: ScanLimits,
+    pub transport: TransportKind,
+    pub proxy: ProxyConfig,
+    pub conflict_policy: ConflictPolicy,
+    pub large_files: LargeFilesConfig,
+    /// downloads are deferred if they'd leave less free space on the disk of a watch group
+    pub min_free_disk_mb: u64,
+    pub bandwidth_cap: BandwidthCapConfig,
+    /// syncing pauses while connected through a network these lists don't allow
+    pub networks: NetworkConfig,
+    /// syncing pauses or slows down on low battery
+    pub power: PowerConfig,
+    /// instructions taking longer are logged at WARN (0 = off)
+    pub slow_instruction_ms: u64,
+}
+
+/// how sync / upload / download / delete reach the server - everything else stays plain http
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportKind {
+    #[default]
+    Http,
+    /// one multiplexed http/2 connection - for proxies that only let grpc through
+    Grpc,
+    /// uploads / downloads over QUIC (lossy wifi / wan) - falls back to http if unreachable
+    Http3,
+}
+
+/// what happens to local changes the server has a newer version of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// the newer version wins - the other one is only left in the server's history
+    #[default]
+    Overwrite,
+    /// the server's version is saved as `name (conflicted copy from HOST 2024-05-01).ext` next to
+    /// the local one, which is uploaded under its original name
+    KeepBoth,
+}
+
+/// outbound http(s) proxy - without `url` the HTTP_PROXY / HTTPS_PROXY / NO_PROXY env vars apply
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// e.g. `http://proxy.corp:3128` - for http and https requests
+    pub url: Option<String>,
+    /// basic auth against the proxy
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// hosts reached directly, same format as NO_PROXY (default: NO_PROXY)
+    pub no_proxy: Option<String>,
+}
+
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
+
+impl ProxyConfig {
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, String> {
+        // reqwest picks up the env vars itself
+        let Some(url) = &self.url else {
+            return Ok(builder);
+        };
+        let mut proxy = Proxy::all(url).map_err(|e| format!("Invalid proxy url '{url}' - {e}"))?;
+        if let Some(username) = &self.username {
+            proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or_default());
+        }
+        let no_proxy = match &self.no_proxy {
+            Some(hosts) => NoProxy::from_string(hosts),
+            None => NoProxy::from_env(),
+        };
+        Ok(builder.proxy(proxy.no_proxy(no_proxy)))
+    }
+
+    /// explicitly or via env - grpc and http3 connect directly either way
+    pub fn is_set(&self) -> bool {
+        self.url.is_some()
+            || PROXY_ENV_VARS
+                .iter()
+                .any(|key| env::var(key).is_ok_and(|v| !v.is_empty()))
+    }
+}
+
+/// initial sync - many pending uploads are sent as tar archives instead of one request per file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BulkUploadConfig {
+    /// minimum number of pending uploads to switch to bulk uploads (0 = never)
+    pub min_files: usize,
+    /// upper bound of one archive - larger files are uploaded one by one
+    pub batch_size_mb: u64,
+}
+
+/// new files this large wait for `client approve` before they're uploaded (see `approval`)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LargeFilesConfig {
+    /// 0 = upload everything right away
+    pub threshold_mb: u64,
+    /// how long a file waits for a decision (0 = forever)
+    pub timeout_secs: u64,
+    pub on_timeout: TimeoutPolicy,
+}
+
+impl Default for LargeFilesConfig {
+    fn default() -> Self {
+        Self {
+            threshold_mb: 2048,
+            timeout_secs: 0,
+            on_timeout: TimeoutPolicy::Upload,
+        }
+    }
+}
+
+/// monthly limits for metered connections - up- and downloads count together (0 = none)
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BandwidthCapConfig {
+    /// a warning once it's exceeded
+    pub soft_cap_mb: u64,
+    /// syncing stops until the next month
+    pub hard_cap_mb: u64,
 }
 
-fn read_local_config() -> Option<(PathBuf, LocalConfig)> {
+/// what happens to a large file nobody decided about in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutPolicy {
+    Upload,
+    /// added to the .syncignore of its watch group
+    Ignore,
+}
+
+/// editor temp files (`~$doc.docx`, `.swp`, ...) are neither scanned nor downloaded
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TempFilesConfig {
+    #[serde(flatten)]
+    pub patterns: TempFilePatterns,
+    /// by watch group name (or id) - replaces the patterns above for that watch group
+    pub watch_groups: HashMap<String, TempFilePatterns>,
+}
+
+impl TempFilesConfig {
+    pub fn for_watch_group(&self, wg_id: i64, name: &str) -> &TempFilePatterns {
+        self.watch_groups
+            .get(name)
+            .or_else(|| self.watch_groups.get(&wg_id.to_string()))
+            .unwrap_or(&self.patterns)
+    }
+}
+
+impl Default for BulkUploadConfig {
+    fn default() -> Self {
+        Self {
+            min_files: 50,
+            batch_size_mb: 64,
+        }
+    }
+}
+
+fn read_local_config(explicit_path: Option<PathBuf>) -> Option<(PathBuf, LocalConfig)> {
     let mut paths: VecDeque<PathBuf> = ["./config.yaml", "./config.yml"]
         .into_iter()
         .map(PathBuf::from)
         .collect();
 
-    if let Some(arg1) = env::args().nth(1) {
-        paths.push_front(PathBuf::from(arg1));
+    if let Some(path) = explicit_path {
+        paths.push_front(path);
     }
 
     paths.into_iter().find_map(|path| {
@@ -49,9 +279,9 @@ fn read_local_config() -> Option<(PathBuf, LocalConfig)> {
     })
 }
 
-pub fn read_config() -> Result<Config, String> {
-    match read_local_config() {
-        None => Err("no config.yaml found".to_string()),
+pub fn read_config(explicit_path: Option<PathBuf>) -> Result<Config, String> {
+    match read_local_config(explicit_path) {
+        None => Err("no config.yaml found - create one with `client init`".to_string()),
         Some((ref config_path, mut config)) => {
             Ok(match config.client_id {
                 Some(client_id) => {
@@ -61,6 +291,21 @@ pub fn read_config() -> Result<Config, String> {
                     Config {
                         client_id,
                         server_url,
+                        bulk_upload: config.bulk_upload,
+                        hooks: config.hooks,
+                        control_port: config.control_port,
+                        max_poll_interval_in_ms: config.max_poll_interval_in_ms,
+                        temp_files: config.temp_files,
+                        scan_limits: config.scan_limits,
+                        transport: config.transport,
+                        proxy: config.proxy,
+                        conflict_policy: config.conflict_policy,
+                        large_files: config.large_files,
+                        min_free_disk_mb: config.min_free_disk_mb,
+                        bandwidth_cap: config.bandwidth_cap,
+                        networks: config.networks,
+                        power: config.power,
+                        slow_instruction_ms: config.slow_instruction_ms,
                     }
                 }
                 // Generate and persist client_id if missing
@@ -81,6 +326,21 @@ pub fn read_config() -> Result<Config, String> {
                     Config {
                         client_id: new_id,
                         server_url: config.server_url,
+                        bulk_upload: config.bulk_upload,
+                        hooks: config.hooks,
+                        control_port: config.control_port,
+                        max_poll_interval_in_ms: config.max_poll_interval_in_ms,
+                        temp_files: config.temp_files,
+                        scan_limits: config.scan_limits,
+                        transport: config.transport,
+                        proxy: config.proxy,
+                        conflict_policy: config.conflict_policy,
+                        large_files: config.large_files,
+                        min_free_disk_mb: config.min_free_disk_mb,
+                        bandwidth_cap: config.bandwidth_cap,
+                        networks: config.networks,
+                        power: config.power,
+                        slow_instruction_ms: config.slow_instruction_ms,
                     }
                 }
             })
@@ -88,6 +348,18 @@ pub fn read_config() -> Result<Config, String> {
     }
 }
 
+/// config.yaml of `client init` - a new client id, everything else keeps its default
+pub fn write_initial_config(path: &Path, server_url: &str) -> Result<Uuid, String> {
+    let client_id = Uuid::new_v4();
+    let mut config = serde_yaml::Mapping::new();
+    config.insert("client_id".into(), client_id.to_string().into());
+    config.insert("server_url".into(), server_url.into());
+    let content =
+        serde_yaml::to_string(&config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(client_id)
+}
+
 /// Fetch config from server
 pub async fn fetch_watch_config(client: &Client, server_url: &str) -> WatchConfigDto {
     let config_endpoint = ServerEndpoint::Config.to_uri(server_url);
@@ -114,3 +386,26 @@ pub async fn fetch_watch_config(client: &Client, server_url: &str) -> WatchConfi
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_proxy_settings() {
+        let config: LocalConfig = serde_yaml::from_str(
+            "server_url: https://sync.example.com\n\
+             proxy:\n  url: http://proxy.corp:3128\n  username: alice\n  password: secret\n",
+        )
+        .unwrap();
+        assert_eq!(Some("http://proxy.corp:3128"), config.proxy.url.as_deref());
+        assert!(config.proxy.apply(Client::builder()).is_ok());
+
+        let invalid = ProxyConfig {
+            url: Some("proxy corp".to_string()),
+            ..ProxyConfig::default()
+        };
+        assert!(invalid.apply(Client::builder()).is_err());
+        assert!(ProxyConfig::default().apply(Client::builder()).is_ok());
+    }
+}
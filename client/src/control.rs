@@ -0,0 +1,129 @@
+// CONTROL PORT ----------------------------------------------------------------
+//
+// local tcp port of the running daemon - every connection sends one command line and gets its
+// reply as json before it's closed:
+//   stats (or nothing)             current sync stats (read by `client status --stats`)
+//   approvals                      uploads held for approval (read by `client approve`)
+//   power                          battery and what syncing does about it (`client status --power`)
+//   approve|ignore <wg_id> <path>  decision about a held upload - replies with `true` if it was held
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::approval::{self, Decision, PendingApproval};
+use crate::power::PowerState;
+use crate::stats::SyncStats;
+use crate::status::StatusHandle;
+
+/// connections that don't send a command (e.g. `nc localhost 47123`) get the stats after this
+const COMMAND_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// used if `control_port` is missing in the config.yaml (0 disables the port)
+pub const DEFAULT_CONTROL_PORT: u16 = 47_123;
+
+/// serves the stats of `status` in the background - a taken port only costs the stats
+pub fn serve(port: u16, status: StatusHandle) {
+    if port == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!(
+                    "Control port {port} unavailable - no stats for `client status --stats`: {e}"
+                );
+                return;
+            }
+        };
+        info!("Serving sync stats on 127.0.0.1:{port}");
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let status = status.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, &status).await {
+                    debug!("Control connection closed early: {e}");
+                }
+            });
+        }
+    });
+}
+
+async fn handle(mut stream: TcpStream, status: &StatusHandle) -> std::io::Result<()> {
+    let mut line = String::new();
+    let _ = tokio::time::timeout(
+        COMMAND_TIMEOUT,
+        BufReader::new(&mut stream).read_line(&mut line),
+    )
+    .await;
+    let json = reply(line.trim(), status);
+    stream.write_all(&json).await
+}
+
+fn reply(command: &str, status: &StatusHandle) -> Vec<u8> {
+    let (command, args) = command.split_once(' ').unwrap_or((command, ""));
+    let decision = match command {
+        "" | "stats" => return status.read(|s| to_json(&s.stats)),
+        "approvals" => return status.read(|s| to_json(&s.pending_approvals)),
+        "power" => return status.read(|s| to_json(&s.power)),
+        "approve" => Decision::Upload,
+        "ignore" => Decision::Ignore,
+        _ => return to_json(&format!("Unknown command '{command}'")),
+    };
+    let decided = args
+        .split_once(' ')
+        .and_then(|(wg_id, path)| Some((wg_id.parse::<i64>().ok()?, path)))
+        .is_some_and(|(wg_id, path)| approval::decide(status, wg_id, path, decision));
+    to_json(&decided)
+}
+
+fn to_json<T: Serialize>(value: &T) -> Vec<u8> {
+    serde_json::to_vec(value).expect("replies should be serializable")
+}
+
+/// stats of the daemon listening on `port`
+pub async fn fetch_stats(port: u16) -> Result<SyncStats, String> {
+    request(port, "stats").await
+}
+
+/// uploads the daemon on `port` holds for approval
+pub async fn fetch_approvals(port: u16) -> Result<Vec<PendingApproval>, String> {
+    request(port, "approvals").await
+}
+
+/// None if the daemon doesn't watch its battery (`power.on_low_battery: sync`)
+pub async fn fetch_power(port: u16) -> Result<Option<PowerState>, String> {
+    request(port, "power").await
+}
+
+/// false if the daemon doesn't hold the upload (anymore)
+pub async fn decide(port: u16, wg_id: i64, path: &str, decision: Decision) -> Result<bool, String> {
+    let command = match decision {
+        Decision::Upload => "approve",
+        Decision::Ignore => "ignore",
+    };
+    request(port, &format!("{command} {wg_id} {path}")).await
+}
+
+async fn request<T: DeserializeOwned>(port: u16, command: &str) -> Result<T, String> {
+    let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+        .await
+        .map_err(|e| format!("No client daemon on control port {port} - {e}"))?;
+    stream
+        .write_all(format!("{command}\n").as_bytes())
+        .await
+        .map_err(|e| format!("Sending '{command}' to control port {port} failed - {e}"))?;
+    let mut json = Vec::new();
+    stream
+        .read_to_end(&mut json)
+        .await
+        .map_err(|e| format!("Reading from control port {port} failed - {e}"))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Invalid reply on control port {port} - {e}"))
+}
@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use mdns_sd::{ResolvedService, ServiceDaemon, ServiceEvent};
+use reqwest::Client;
+use shared::endpoint::{MDNS_SCHEME_KEY, MDNS_SERVICE_TYPE, MDNS_VERSION_KEY, ServerEndpoint};
+use tracing::warn;
+
+use crate::config;
+
+/// a server advertising itself on the LAN (`MDNS_DISCOVERY=true`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredServer {
+    pub name: String,
+    pub version: Option<String>,
+    pub url: String,
+}
+
+impl DiscoveredServer {
+    fn from_resolved(service: &ResolvedService) -> Option<Self> {
+        let scheme = service
+            .get_property_val_str(MDNS_SCHEME_KEY)
+            .unwrap_or("http");
+        // certificates are issued for names - plain http gets the ip (.local may not resolve)
+        let host = match scheme {
+            "https" => service.host.trim_end_matches('.').to_string(),
+            _ => match service
+                .addresses
+                .iter()
+                .map(|ip| ip.to_ip_addr())
+                .min_by_key(IpAddr::is_ipv6)?
+            {
+                IpAddr::V4(ip) => ip.to_string(),
+                IpAddr::V6(ip) => format!("[{ip}]"),
+            },
+        };
+        Some(DiscoveredServer {
+            name: service
+                .fullname
+                .strip_suffix(MDNS_SERVICE_TYPE)
+                .unwrap_or(&service.fullname)
+                .trim_end_matches('.')
+                .to_string(),
+            version: service
+                .get_property_val_str(MDNS_VERSION_KEY)
+                .map(str::to_string),
+            url: format!("{scheme}://{host}:{}", service.port),
+        })
+    }
+}
+
+/// servers answering within `timeout` (blocking)
+pub fn discover(timeout: Duration) -> Result<Vec<DiscoveredServer>, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS unavailable - {e}"))?;
+    let events = daemon
+        .browse(MDNS_SERVICE_TYPE)
+        .map_err(|e| format!("mDNS browsing failed - {e}"))?;
+    let deadline = Instant::now() + timeout;
+    let mut servers = BTreeMap::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match events.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(service)) => {
+                if let Some(server) = DiscoveredServer::from_resolved(&service) {
+                    servers.insert(service.fullname.clone(), server);
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    if let Err(e) = daemon.shutdown() {
+        warn!("mDNS daemon not shut down - {e}");
+    }
+    Ok(servers.into_values().collect())
+}
+
+/// setup wizard - picks a server (found via mDNS or entered) and writes a new config.yaml
+pub async fn init(
+    config_path: Option<PathBuf>,
+    server_url: Option<String>,
+    timeout: Duration,
+) -> Result<(), String> {
+    let path = config_path.unwrap_or_else(|| PathBuf::from("./config.yaml"));
+    if path.exists() {
+        return Err(format!("{} exists already", path.display()));
+    }
+
+    let server_url = match server_url {
+        Some(url) => url,
+        None => {
+            eprintln!(
+                "Searching the LAN for servers ({})...",
+                humantime::format_duration(timeout)
+            );
+            let servers = tokio::task::spawn_blocking(move || discover(timeout))
+                .await
+                .map_err(|e| e.to_string())?
+                .unwrap_or_else(|e| {
+                    warn!("{e}");
+                    Vec::new()
+                });
+            if servers.is_empty() {
+                eprintln!("No server found - is MDNS_DISCOVERY=true set on the server?");
+            }
+            for (i, server) in servers.iter().enumerate() {
+                eprintln!(
+                    "  {}) {} {} - {}",
+                    i + 1,
+                    server.name,
+                    server.version.as_deref().unwrap_or("?"),
+                    server.url
+                );
+            }
+            eprint!("Server (number or url): ");
+            io::stderr().flush().map_err(|e| e.to_string())?;
+            let mut input = String::new();
+            io::stdin()
+                .lock()
+                .read_line(&mut input)
+                .map_err(|e| e.to_string())?;
+            pick_server(input.trim(), &servers)?
+        }
+    };
+    if !server_url.starts_with("http://") && !server_url.starts_with("https://") {
+        return Err(format!("'{server_url}' is no http(s) url"));
+    }
+
+    let reachable = Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .map_err(|e| e.to_string())?
+        .get(ServerEndpoint::Ping.to_uri(&server_url))
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success());
+    if !reachable {
+        warn!("{server_url} doesn't answer right now - writing the config anyway");
+    }
+
+    let client_id = config::write_initial_config(&path, &server_url)?;
+    println!(
+        "Wrote {} (client_id {client_id}) - assign watch groups in the web ui, then run `client`",
+        path.display()
+    );
+    Ok(())
+}
+
+/// number of a listed server, an url - or nothing if there's just one server
+fn pick_server(input: &str, servers: &[DiscoveredServer]) -> Result<String, String> {
+    match (input, servers) {
+        ("", [single]) => Ok(single.url.clone()),
+        ("", _) => Err("No server chosen".to_string()),
+        (input, servers) if input.chars().all(|c| c.is_ascii_digit()) => input
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| servers.get(n.checked_sub(1)?))
+            .map(|server| server.url.clone())
+            .ok_or_else(|| format!("No server {input}")),
+        (url, _) => Ok(url.trim_end_matches('/').to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_pick_server_by_number_or_url() {
+        let server = |url: &str| DiscoveredServer {
+            name: "nas".to_string(),
+            version: None,
+            url: url.to_string(),
+        };
+        let servers = [
+            server("http://10.0.0.2:3000"),
+            server("http://10.0.0.3:3000"),
+        ];
+
+        assert_eq!(
+            Ok("http://10.0.0.3:3000".to_string()),
+            pick_server("2", &servers)
+        );
+        assert!(pick_server("3", &servers).is_err());
+        assert!(pick_server("0", &servers).is_err());
+        assert!(pick_server("", &servers).is_err());
+        assert_eq!(
+            Ok("http://10.0.0.2:3000".to_string()),
+            pick_server("", &servers[..1])
+        );
+        assert_eq!(
+            Ok("https://sync.example.com".to_string()),
+            pick_server("https://sync.example.com/", &servers)
+        );
+    }
+}
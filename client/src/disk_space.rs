@@ -0,0 +1,53 @@
+use std::path::Path;
+use sysinfo::Disks;
+
+const MB: u64 = 1024 * 1024;
+
+/// free bytes of the disk `path` is on (or would be on once created) - None if unknown
+pub fn available_bytes(path: &Path) -> Option<u64> {
+    let path = path.ancestors().find(|p| p.exists())?.canonicalize().ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        // the most specific mount point
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+/// error if writing `needed` bytes below `path` would leave less than `reserve` bytes free -
+/// fine if the free space can't be determined
+pub fn ensure(path: &Path, needed: u64, reserve: u64) -> Result<(), String> {
+    match available_bytes(path) {
+        Some(available) => check(needed, available, reserve),
+        None => Ok(()),
+    }
+}
+
+fn check(needed: u64, available: u64, reserve: u64) -> Result<(), String> {
+    if needed.saturating_add(reserve) <= available {
+        return Ok(());
+    }
+    Err(format!(
+        "Not enough disk space - {} MB needed, {} MB available ({} MB kept free)",
+        needed.div_ceil(MB),
+        available / MB,
+        reserve / MB
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_reserve_free() {
+        assert_eq!(Ok(()), check(10 * MB, 30 * MB, 20 * MB));
+        assert_eq!(
+            Err("Not enough disk space - 11 MB needed, 30 MB available (20 MB kept free)".into()),
+            check(10 * MB + 1, 30 * MB, 20 * MB)
+        );
+        assert!(check(u64::MAX, 30 * MB, 20 * MB).is_err());
+    }
+}
@@ -1,52 +1,183 @@
+use futures_util::StreamExt;
 use futures_util::future::join_all;
-use reqwest::Client;
-use reqwest::multipart::Form;
-use shared::dtos::FileDescription;
-use shared::endpoint::ServerEndpoint;
+use reqwest::{Client, StatusCode};
+use shared::conflict_copy::{conflict_copy_path, is_conflict_copy};
+use shared::dtos::{FileDescription, ScanWarningDto};
+use shared::endpoint::{QUARANTINED_REPLY_PREFIX, SysRoute};
 use shared::get_files_of_directory::get_all_file_descriptions;
 use shared::get_files_of_directory::get_file_description;
-use shared::sync_instruction::SyncInstruction;
-use std::path::Path;
-use tokio::fs;
-use tokio::fs::{create_dir_all, remove_file};
-use tracing::{error, info};
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::{SyncInstruction, determine_deleted_files};
+use shared::utc_millis::UtcMillis;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::fs::{
+    File, OpenOptions, create_dir_all, metadata, read_to_string, remove_file, rename, write,
+};
+use tokio::io::AsyncWriteExt;
+use tokio::time::Instant;
+use tracing::{Instrument, debug, error, info, warn};
 
-use crate::WatchGroup;
+use crate::approval;
+use crate::bulk;
+use crate::config::ConflictPolicy;
+use crate::disk_space;
+use crate::hooks::{HookEvent, HooksConfig};
+use crate::status::{StatusHandle, TransferDirection};
+use crate::syncignore::SyncIgnore;
+use crate::timing::InstructionTimer;
+use crate::transport::{Resume, Synced, Transport};
+use crate::{ClientState, WatchGroup};
+
+/// reply of the server if it already holds a newer version of an uploaded file
+pub(crate) const NOT_LATEST_REPLY: &str = "not latest";
 
 pub async fn loop_scan(
-    server_url: &str,
+    state: &ClientState,
     wg_id: i64,
     watch_group: &WatchGroup,
-    client: &Client,
+    transport: &Transport,
     last_scan: Option<Vec<FileDescription>>,
+    status: &StatusHandle,
 ) -> Vec<FileDescription> {
-    match get_all_file_descriptions(
-        watch_group.path_to_monitor.as_path(),
-        &watch_group.exclude_dirs,
-        watch_group.exclude_dot_dirs,
+    let server_url = state.server_url.as_str();
+    let hooks = &state.hooks;
+    let root = watch_group.path_to_monitor.as_path();
+    let temp_files = state.temp_files.for_watch_group(wg_id, &watch_group.name);
+    let ignore = SyncIgnore::load(root);
+    // otherwise newly ignored files would be sent as delete events
+    let last_scan = last_scan.map(|last| ignore.retain(last));
+    if let Err(e) = hooks.run(HookEvent::BeforeScan, wg_id, root, None).await {
+        error!("{e} - skipping {} this time", watch_group.name);
+        status.error(format!("{}: {}", watch_group.name, e));
+        return last_scan.unwrap_or_default();
+    }
+    let scan = || {
+        get_all_file_descriptions(
+            watch_group.path_to_monitor.as_path(),
+            &watch_group.exclude_dirs,
+            watch_group.exclude_dot_dirs,
+            temp_files,
+            state.scan_limits,
+        )
+        .map_err(|e| format!("Could not scan directory - {}", e))
+    };
+    let mut scanned = scan();
+    if last_scan.is_none() && scanned.as_ref().is_ok_and(|d| d.is_empty()) {
+        // brand-new client - one archive instead of one download per file
+        match bulk::download_snapshot(
+            &transport.http,
+            watch_group.path_to_monitor.as_path(),
+            server_url,
+            wg_id,
+            status,
+        )
+        .await
+        {
+            Ok(0) => {}
+            Ok(files) => {
+                status.executed(files);
+                scanned = scan()
+            }
+            // the regular sync below downloads whatever is missing
+            Err(e) => {
+                error!("{e}");
+                status.error(format!("{}: {}", watch_group.name, e));
+            }
+        }
+    }
+
+    let scan_warning = match &scanned {
+        Err(error) => Some(error.clone()),
+        Ok(descriptions) => state.scan_limits.warning(descriptions.len()),
+    };
+    // held uploads and deferred downloads of the last poll cycle
+    let disk_space_warning = status.read(|s| {
+        s.watch_groups
+            .get(&wg_id)
+            .and_then(|wg| wg.disk_space_warning.clone())
+    });
+    let warnings: Vec<String> = [
+        scan_warning,
+        approval::warning(status, wg_id),
+        disk_space_warning,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let scan_warning = (!warnings.is_empty()).then(|| warnings.join(" - "));
+    report_scan_warning(
+        &transport.http,
+        server_url,
+        wg_id,
+        watch_group,
+        scan_warning,
+        status,
     )
-    .map_err(|e| format!("Could not scan directory - {}", e))
-    {
+    .await;
+
+    match scanned {
         Err(error) => {
             error!(
                 "Scanning directory for {} failed - {}",
                 watch_group.name, error
             );
+            status.error(format!("{}: {}", watch_group.name, error));
+            status.update_watch_group(wg_id, |wg| wg.last_error = Some(error));
             last_scan.unwrap_or_default()
         }
         Ok(descriptions) => {
+            let mut descriptions = ignore.retain(descriptions);
+            status.scanned(descriptions.len());
+            status.update_watch_group(wg_id, |wg| {
+                wg.name = watch_group.name.clone();
+                wg.file_count = descriptions.len();
+                wg.last_scan = Some(UtcMillis::now());
+                wg.last_error = None;
+            });
+
             let mut deleted_files = Vec::new();
             if let Some(ref last) = last_scan {
                 deleted_files =
-                    send_potential_delete_events(server_url, wg_id, last, client, &descriptions)
+                    send_potential_delete_events(server_url, wg_id, last, transport, &descriptions)
                         .await;
+                status.deletes_sent(deleted_files.len());
             }
 
-            match send_to_server_and_receive_instructions(client, &descriptions, server_url, wg_id)
-                .await
-            {
-                Err(err) => error!("Error - failed to get instructions from server: {:?}", err),
-                Ok(instructions) => {
+            match transport.sync(server_url, wg_id, &descriptions).await {
+                Err(err) => {
+                    error!("Error - failed to get instructions from server: {}", err);
+                    status.error(format!(
+                        "{}: sync request failed - {}",
+                        watch_group.name, err
+                    ));
+                }
+                Ok(Synced {
+                    instructions,
+                    paused: paused_by_server,
+                    download_bytes,
+                }) => {
+                    let received = Instant::now();
+                    let was_paused = status.read(|s| {
+                        s.watch_groups
+                            .get(&wg_id)
+                            .is_some_and(|wg| wg.paused_by_server)
+                    });
+                    if paused_by_server != was_paused {
+                        warn!(
+                            "Syncing of {} was {} by the server",
+                            watch_group.name,
+                            if paused_by_server {
+                                "paused"
+                            } else {
+                                "resumed"
+                            }
+                        );
+                    }
+                    status.update_watch_group(wg_id, |wg| {
+                        wg.last_instruction_count = instructions.len();
+                        wg.paused_by_server = paused_by_server;
+                    });
                     if !instructions.is_empty() {
                         info!(
                             "{} Instructions received {:?}",
@@ -54,7 +185,67 @@ pub async fn loop_scan(
                             instructions
                         );
                     }
+                    let instructions = defer_downloads(
+                        instructions,
+                        download_bytes,
+                        state.min_free_disk_mb,
+                        wg_id,
+                        watch_group,
+                        status,
+                    );
+                    let instructions = approval::hold_large_uploads(
+                        &state.large_files,
+                        wg_id,
+                        watch_group,
+                        instructions,
+                        &descriptions,
+                        last_scan.as_deref(),
+                        status,
+                    );
+                    let (batches, instructions) =
+                        bulk::batch_uploads(instructions, &descriptions, &state.bulk_upload);
+                    for batch in batches {
+                        let files = batch.len();
+                        // a failed batch is retried at next poll cycle (possibly as single uploads)
+                        match bulk::upload_batch(
+                            &transport.http,
+                            batch,
+                            watch_group.path_to_monitor.as_path(),
+                            server_url,
+                            wg_id,
+                            hooks,
+                            status,
+                        )
+                        .await
+                        {
+                            Ok(()) => status.executed(files),
+                            Err(e) => {
+                                error!("{e}");
+                                status.error(format!("{}: {}", watch_group.name, e));
+                            }
+                        }
+                    }
                     for instruction in instructions {
+                        if ignore.is_ignored(instruction.path()) {
+                            debug!(
+                                path = instruction.path().to_serialized_string(),
+                                "Skipping instruction for ignored file"
+                            );
+                            continue;
+                        }
+                        if let SyncInstruction::Download(path) = &instruction
+                            && path
+                                .get()
+                                .last()
+                                .is_some_and(|name| temp_files.is_temp_file(name))
+                        {
+                            // synced before it was ignored - would be downloaded again every cycle
+                            debug!(
+                                path = path.to_serialized_string(),
+                                "Skipping download of temp file"
+                            );
+                            continue;
+                        }
                         if let SyncInstruction::Download(path) = &instruction
                             && deleted_files
                                 .iter()
@@ -65,18 +256,58 @@ pub async fn loop_scan(
                             continue;
                         }
 
-                        match execute(
-                            client,
-                            instruction,
-                            watch_group.path_to_monitor.as_path(),
-                            server_url,
-                            wg_id,
-                        )
-                        .await
-                        {
-                            Ok(msg) => info!("{msg}"),
+                        let path = instruction.path().to_serialized_string();
+                        let timer = InstructionTimer::start(&instruction, received, status);
+                        let (delete, download) = match &instruction {
+                            SyncInstruction::Delete(p) => (Some(p.clone()), None),
+                            SyncInstruction::Download(p) => (None, Some(p.clone())),
+                            _ => (None, None),
+                        };
+                        let result = match &download {
+                            Some(p)
+                                if state.conflict_policy == ConflictPolicy::KeepBoth
+                                    && is_locally_modified(
+                                        last_scan.as_deref(),
+                                        &descriptions,
+                                        p,
+                                    ) =>
+                            {
+                                keep_both(transport, p, root, server_url, wg_id, hooks, status)
+                                    .instrument(timer.span())
+                                    .await
+                            }
+                            _ => {
+                                execute(
+                                    transport,
+                                    instruction,
+                                    root,
+                                    server_url,
+                                    wg_id,
+                                    hooks,
+                                    status,
+                                )
+                                .instrument(timer.span())
+                                .await
+                            }
+                        };
+                        timer.finish(state.slow_instruction_ms, status);
+                        match result {
+                            Ok(()) => {
+                                status.executed(1);
+                                if let Some(deleted) = delete {
+                                    // otherwise the next scan would send it back as delete event
+                                    descriptions.retain(|d| d.relative_path != deleted);
+                                }
+                                if let Some(downloaded) = download {
+                                    // otherwise the next scan would take it for a local change
+                                    refresh(&mut descriptions, &downloaded, root);
+                                }
+                            }
                             // logging is fine if something went wrong, we just try again at next poll cycle
-                            Err(e) => error!("{e}"),
+                            Err(e) => {
+                                error!(path, "{e}");
+                                status.error(format!("{}: {}", watch_group.name, e));
+                            }
                         }
                     }
                 }
@@ -87,22 +318,124 @@ pub async fn loop_scan(
     }
 }
 
+/// drops all downloads if they don't fit on the disk of the watch group (keeping
+/// `min_free_disk_mb` free) - retried with the next poll cycle, after deletes freed up space
+fn defer_downloads(
+    instructions: Vec<SyncInstruction>,
+    download_bytes: Option<u64>,
+    min_free_disk_mb: u64,
+    wg_id: i64,
+    watch_group: &WatchGroup,
+    status: &StatusHandle,
+) -> Vec<SyncInstruction> {
+    let downloads = instructions
+        .iter()
+        .filter(|i| matches!(i, SyncInstruction::Download(_)))
+        .count();
+    let checked = match download_bytes {
+        Some(bytes) if downloads > 0 => disk_space::ensure(
+            &watch_group.path_to_monitor,
+            bytes,
+            min_free_disk_mb * 1024 * 1024,
+        ),
+        _ => Ok(()),
+    };
+    let warning = checked.err().map(|e| {
+        format!(
+            "{e} - {downloads} download(s) of {} deferred",
+            watch_group.name
+        )
+    });
+    if let Some(warning) = &warning {
+        error!("{warning}");
+        status.error(warning.clone());
+    }
+    let deferred = warning.is_some();
+    status.update_watch_group(wg_id, |wg| wg.disk_space_warning = warning);
+    if !deferred {
+        return instructions;
+    }
+    instructions
+        .into_iter()
+        .filter(|i| !matches!(i, SyncInstruction::Download(_)))
+        .collect()
+}
+
+/// changed since the last scan (or new) - a download would overwrite it
+///
+/// conflicted copies never count - the server's version simply wins
+fn is_locally_modified(
+    last_scan: Option<&[FileDescription]>,
+    descriptions: &[FileDescription],
+    path: &MatchablePath,
+) -> bool {
+    let Some(last_scan) = last_scan else {
+        return false;
+    };
+    let Some(current) = descriptions.iter().find(|d| d.relative_path == *path) else {
+        return false;
+    };
+    !is_conflict_copy(path)
+        && !last_scan.iter().any(|last| {
+            last.relative_path == *path
+                && last.size_in_bytes == current.size_in_bytes
+                && last.last_updated_utc_millis == current.last_updated_utc_millis
+        })
+}
+
+fn refresh(descriptions: &mut Vec<FileDescription>, path: &MatchablePath, root: &Path) {
+    descriptions.retain(|d| d.relative_path != *path);
+    if let Ok(description) = get_file_description(path.resolve(root).as_path(), root) {
+        descriptions.push(description);
+    }
+}
+
+/// shown in the admin ui - only sent when it changed, not every poll cycle
+async fn report_scan_warning(
+    client: &Client,
+    server_url: &str,
+    wg_id: i64,
+    watch_group: &WatchGroup,
+    warning: Option<String>,
+    status: &StatusHandle,
+) {
+    let reported = status.read(|s| {
+        s.watch_groups
+            .get(&wg_id)
+            .and_then(|wg| wg.reported_scan_warning.clone())
+    });
+    if reported.as_ref() == Some(&warning) {
+        return;
+    }
+    if let Some(message) = &warning {
+        warn!("{}: {message}", watch_group.name);
+    }
+    let result = client
+        .post(SysRoute::ScanWarning { wg_id }.to_uri(server_url))
+        .json(&ScanWarningDto {
+            message: warning.clone(),
+        })
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    match result {
+        Ok(_) => status.update_watch_group(wg_id, |wg| wg.reported_scan_warning = Some(warning)),
+        // retried next poll cycle
+        Err(e) => warn!("Could not report scan warning to server - {e}"),
+    }
+}
+
 async fn send_potential_delete_events(
     server_url: &str,
     wg_id: i64,
     last_scan: &[FileDescription],
-    client: &Client,
+    transport: &Transport,
     descriptions: &[FileDescription],
 ) -> Vec<FileDescription> {
     let last_deleted_files = determine_deleted_files(last_scan, descriptions);
     let futures = last_deleted_files
         .iter()
-        .map(|deleted| {
-            client
-                .post(ServerEndpoint::Delete.to_uri_with_wg(server_url, wg_id))
-                .body(deleted.relative_path.to_serialized_string())
-                .send()
-        })
+        .map(|deleted| transport.delete(server_url, wg_id, &deleted.relative_path))
         .collect::<Vec<_>>();
     let results = join_all(futures).await;
     let c = last_deleted_files.len();
@@ -116,141 +449,347 @@ async fn send_potential_delete_events(
                 .collect::<Vec<String>>()
                 .join(", ")
         );
-        results
+        last_deleted_files
             .iter()
-            .for_each(|r| info!("Server received delete-event and replied with: {:?}", r));
+            .zip(results.iter())
+            .for_each(|(deleted, r)| {
+                info!(
+                    path = deleted.relative_path.to_serialized_string(),
+                    event_type = "delete",
+                    "Server received delete-event and replied with: {:?}",
+                    r
+                )
+            });
     }
     last_deleted_files
 }
 
-async fn send_to_server_and_receive_instructions(
-    client: &Client,
-    scanned: &Vec<FileDescription>,
-    base: &str,
-    wg_id: i64,
-) -> Result<Vec<SyncInstruction>, reqwest::Error> {
-    client
-        .post(ServerEndpoint::Sync.to_uri_with_wg(base, wg_id))
-        .json(scanned)
-        .send()
-        .await?
-        .json()
-        .await
-}
-
-/// on os level files are just there or not so we got to keep track of the last state
-/// and diff it with the new one in order to determine file deletion and propagate the event accordingly
-/// [`https://docs.rs/notify/latest/notify/index.html`] could also be an option for later, but
-/// I prefer a more native approach for now
-///
-/// Note: Deletes will reverb one time as server instructed deletes are again sent to the server as delete events.
-/// This is due to the stateless nature of the client.
-/// Since it doesn't do harm, it doesn't make sense to introduce state in order to deal with this reverb.
-fn determine_deleted_files(
-    last: &[FileDescription],
-    curr: &[FileDescription],
-) -> Vec<FileDescription> {
-    last.iter()
-        .filter(|prev_description| {
-            !curr.iter().any(|curr_description| {
-                prev_description.relative_path == curr_description.relative_path
-            })
-        })
-        .cloned()
-        .collect()
-}
-
 /// executes an instruction of the server (see [`SyncInstruction`])
-async fn execute(
-    client: &Client,
+pub(crate) async fn execute(
+    transport: &Transport,
     instruction: SyncInstruction,
     root: &Path,
     base: &str,
     wg_id: i64,
-) -> Result<String, String> {
+    hooks: &HooksConfig,
+    status: &StatusHandle,
+) -> Result<(), String> {
     match instruction {
         SyncInstruction::Upload(p) => {
-            let file_path = p.resolve(root);
-            let description = get_file_description(file_path.as_path(), root)?;
+            let (description, response_status, reply) =
+                upload(transport, &p, root, base, wg_id, status, None).await?;
             let relative_path_to_send = description.relative_path.get().join("/");
-            let form: Form = Form::new()
-                .text(
-                    "utc_millis",
-                    serde_json::to_string(&description.last_updated_utc_millis).unwrap(),
-                )
-                .text("relative_path", relative_path_to_send)
-                .file("file", file_path)
-                .await
-                .map_err(|e| e.to_string())?;
 
-            client
-                .post(ServerEndpoint::Upload.to_uri_with_wg(base, wg_id))
-                .multipart(form)
-                .send()
-                .await
-                .map_err(|e| format!("Upload failed - {e}"))?
-                .text()
-                .await
-                .map_err(|e| format!("BOM sniffing failed - {e}"))
-                .map(|response| format!("Upload successful - server replied with '{response}'",))
+            if response_status == StatusCode::BAD_REQUEST && reply == NOT_LATEST_REPLY {
+                warn!(
+                    path = relative_path_to_send,
+                    "Server holds a newer version of '{relative_path_to_send}' - upload refused"
+                );
+                status.conflict(relative_path_to_send.clone());
+                hooks
+                    .run(
+                        HookEvent::Conflict,
+                        wg_id,
+                        root,
+                        Some(&description.relative_path),
+                    )
+                    .await?;
+            }
+            if response_status == StatusCode::UNPROCESSABLE_ENTITY
+                && reply.starts_with(QUARANTINED_REPLY_PREFIX)
+            {
+                warn!(
+                    path = relative_path_to_send,
+                    "Server quarantined '{relative_path_to_send}' - {reply}"
+                );
+            }
+            if !response_status.is_success() {
+                return Err(format!(
+                    "Upload of '{relative_path_to_send}' failed - {response_status} - {reply}"
+                ));
+            }
+            info!(
+                path = relative_path_to_send,
+                event_type = "change",
+                bytes = description.size_in_bytes,
+                "Upload successful - server replied with '{reply}'"
+            );
+            Ok(())
         }
 
         SyncInstruction::Download(p) => {
-            let file_path = p.resolve(root);
-
-            let response = client
-                .get(ServerEndpoint::Download.to_uri_with_wg(base, wg_id))
-                .body(p.to_serialized_string())
-                .send()
+            download(transport, base, wg_id, &p, &p.resolve(root), status).await?;
+            hooks
+                .run(HookEvent::AfterDownload, wg_id, root, Some(&p))
                 .await
-                .map_err(|e| format!("Download request failed - {e}",))?
-                .error_for_status()
-                .map_err(|e| {
-                    format!("Download request failed - {} - {}", e.status().unwrap(), e)
-                })?;
-
-            let bytes = response
-                .bytes()
-                .await
-                .map_err(|e| format!("Download failed - cannot read response body - {e}"))?;
+        }
 
-            create_dir_all(file_path.parent().unwrap())
-                .await
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "Should be able to create parent directory of file ({:?})",
-                        &file_path
-                    )
-                });
+        SyncInstruction::Delete(p) => {
+            let file_path = p.resolve(root);
 
-            fs::write(&file_path, bytes)
+            remove_file(&file_path)
                 .await
-                .map_err(|e| format!("Could not save downloaded file ({:?}): {}", &file_path, e))?;
-
-            Ok(format!(
-                "Downloaded {} successfully",
-                file_path
+                .map_err(|e| format!("Deleting file failed - {e}"))?;
+            info!(
+                path = p.to_serialized_string(),
+                event_type = "delete",
+                "Deleted file '{}'",
+                &file_path
                     .file_name()
                     .map(|osstr| osstr.to_string_lossy().to_string())
-                    .unwrap_or_else(|| "?".to_string())
-            ))
+                    .unwrap_or("?".to_string())
+            );
+            Ok(())
         }
+    }
+}
 
-        SyncInstruction::Delete(p) => {
-            let file_path = p.resolve(root);
+/// sends the local version of `p` - returns the status + reply of the server
+async fn upload(
+    transport: &Transport,
+    p: &MatchablePath,
+    root: &Path,
+    base: &str,
+    wg_id: i64,
+    status: &StatusHandle,
+    conflict_kept: Option<&UtcMillis>,
+) -> Result<(FileDescription, StatusCode, String), String> {
+    let file_path = p.resolve(root);
+    let description = get_file_description(file_path.as_path(), root)?;
+    let transfer = status.start_transfer(
+        wg_id,
+        description.relative_path.get().join("/"),
+        TransferDirection::Upload,
+        description.size_in_bytes,
+    );
+    let file = File::open(&file_path)
+        .await
+        .map_err(|e| format!("Could not open {:?} for upload - {e}", &file_path))?;
+    let (response_status, reply) = transport
+        .upload(base, wg_id, &description, file, transfer, conflict_kept)
+        .await?;
+    Ok((description, response_status, reply))
+}
 
-            remove_file(&file_path)
-                .await
-                .map_err(|e| format!("Deleting file failed - {e}"))
-                .map(|_| {
-                    format!(
-                        "Deleted file '{}'",
-                        &file_path
-                            .file_name()
-                            .map(|osstr| osstr.to_string_lossy().to_string())
-                            .unwrap_or("?".to_string())
-                    )
-                })
+/// download instruction for a file that was changed locally as well (keep-both conflict policy) -
+/// the server's version is saved as conflicted copy next to it, the local one is uploaded again
+pub(crate) async fn keep_both(
+    transport: &Transport,
+    p: &MatchablePath,
+    root: &Path,
+    base: &str,
+    wg_id: i64,
+    hooks: &HooksConfig,
+    status: &StatusHandle,
+) -> Result<(), String> {
+    let file_path = p.resolve(root);
+    // `*.part` - not picked up by scans until it's renamed
+    let incoming = with_suffix(&file_path, ".conflict.part");
+    let downloaded = download(transport, base, wg_id, p, &incoming, status).await?;
+    let kept = downloaded.utc_millis.unwrap_or_else(UtcMillis::now);
+    let host = downloaded.client_host.as_deref().unwrap_or("server");
+    let copy = (1..)
+        .map(|attempt| conflict_copy_path(p, host, &kept, attempt))
+        .find(|copy| !copy.resolve(root).exists())
+        .expect("Some attempt should be free");
+    rename(&incoming, copy.resolve(root))
+        .await
+        .map_err(|e| format!("Could not save conflicted copy of {:?} - {e}", &file_path))?;
+
+    // the local version replaces the kept one - so it has to be newer
+    let newer = UtcMillis::now().max(UtcMillis::from(kept.as_u64() + 1));
+    stamp(&file_path, &newer).map_err(|e| format!("Could not touch {:?} - {e}", &file_path))?;
+    let (_, response_status, reply) =
+        upload(transport, p, root, base, wg_id, status, Some(&kept)).await?;
+
+    let relative_path = p.to_serialized_string();
+    let copy_path = copy.to_serialized_string();
+    warn!(
+        path = relative_path,
+        "'{relative_path}' was changed here and on {host} - kept theirs as '{copy_path}'"
+    );
+    status.conflict(relative_path.clone());
+    hooks.run(HookEvent::Conflict, wg_id, root, Some(p)).await?;
+    // refused if yet another version arrived meanwhile - that one is handled next poll cycle
+    if !response_status.is_success() {
+        return Err(format!(
+            "Upload of '{relative_path}' failed - {response_status} - {reply}"
+        ));
+    }
+    info!(
+        path = relative_path,
+        event_type = "change",
+        "Upload successful - server replied with '{reply}'"
+    );
+    Ok(())
+}
+
+/// what the server told about a downloaded file
+pub(crate) struct Downloaded {
+    pub utc_millis: Option<UtcMillis>,
+    /// client the version came from
+    pub client_host: Option<String>,
+}
+
+/// streams `p` of the watch group into `file_path` (parent directories are created)
+///
+/// the content goes to `<file>.part` first and replaces `file_path` once complete - an
+/// interrupted download continues from there the next time if the file didn't change meanwhile
+pub(crate) async fn download(
+    transport: &Transport,
+    base: &str,
+    wg_id: i64,
+    p: &MatchablePath,
+    file_path: &Path,
+    status: &StatusHandle,
+) -> Result<Downloaded, String> {
+    let save_error =
+        |e: std::io::Error| format!("Could not save downloaded file ({:?}): {}", &file_path, e);
+    let part_path = with_suffix(file_path, ".part");
+    let etag_path = with_suffix(file_path, ".etag.part");
+    let resume = match (metadata(&part_path).await, read_to_string(&etag_path).await) {
+        (Ok(part), Ok(etag)) if part.len() > 0 => Some(Resume {
+            offset: part.len(),
+            etag,
+        }),
+        _ => None,
+    };
+    let download = transport
+        .download(base, wg_id, p, resume.as_ref())
+        .await
+        .map_err(|e| format!("Download request failed - {e}"))?;
+    let bytes_total = download.size;
+    // older servers don't announce the size of the downloads with the sync, and space may have
+    // run out since
+    disk_space::ensure(file_path, bytes_total.saturating_sub(download.offset), 0)
+        .map_err(|e| format!("Download of {} skipped - {e}", p.to_serialized_string()))?;
+    let transfer = status.start_transfer(
+        wg_id,
+        p.to_serialized_string(),
+        TransferDirection::Download,
+        bytes_total.saturating_sub(download.offset),
+    );
+
+    create_dir_all(file_path.parent().unwrap())
+        .await
+        .unwrap_or_else(|_| {
+            panic!(
+                "Should be able to create parent directory of file ({:?})",
+                &file_path
+            )
+        });
+
+    let mut file = if download.offset > 0 {
+        if resume.as_ref().map(|r| r.offset) != Some(download.offset) {
+            return Err(format!(
+                "Download failed - server continued at byte {} instead of the end of {:?}",
+                download.offset, &part_path
+            ));
+        }
+        info!(
+            offset = download.offset,
+            "Resuming download of {}",
+            p.to_serialized_string()
+        );
+        OpenOptions::new()
+            .append(true)
+            .open(&part_path)
+            .await
+            .map_err(save_error)?
+    } else {
+        let file = File::create(&part_path).await.map_err(save_error)?;
+        match &download.etag {
+            Some(etag) => write(&etag_path, etag).await.map_err(save_error)?,
+            None => {
+                let _ = remove_file(&etag_path).await;
+            }
         }
+        file
+    };
+    let mut body = download.body;
+    while let Some(chunk) = body.next().await {
+        let bytes =
+            chunk.map_err(|e| format!("Download failed - cannot read response body - {e}"))?;
+        file.write_all(&bytes).await.map_err(save_error)?;
+        transfer.advance(bytes.len() as u64);
+    }
+    file.flush().await.map_err(save_error)?;
+    drop(file);
+    rename(&part_path, file_path).await.map_err(save_error)?;
+    let _ = remove_file(&etag_path).await;
+    if let Some(utc_millis) = &download.utc_millis {
+        stamp(file_path, utc_millis).map_err(save_error)?;
+    }
+
+    info!(
+        path = p.to_serialized_string(),
+        event_type = "change",
+        bytes = bytes_total,
+        "Downloaded {} successfully",
+        file_path
+            .file_name()
+            .map(|osstr| osstr.to_string_lossy().to_string())
+            .unwrap_or_else(|| "?".to_string())
+    );
+    Ok(Downloaded {
+        utc_millis: download.utc_millis,
+        client_host: download.client_host,
+    })
+}
+
+/// sets the modification time of a downloaded file to the time of its event on the server -
+/// with the time of the download it would look newer than edits made elsewhere meanwhile (and
+/// overwrite them with the next sync)
+pub(crate) fn stamp(file: &Path, utc_millis: &UtcMillis) -> std::io::Result<()> {
+    std::fs::File::options()
+        .write(true)
+        .open(file)?
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_millis(utc_millis.as_u64()))
+}
+
+/// `file.txt` -> `file.txt<suffix>` (next to it)
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description(path: &str, size: u64, millis: u64) -> FileDescription {
+        FileDescription {
+            file_name: path.to_string(),
+            relative_path: MatchablePath::from(path),
+            size_in_bytes: size,
+            file_type: "txt".to_string(),
+            last_updated_utc_millis: UtcMillis::from(millis),
+        }
+    }
+
+    #[test]
+    fn should_detect_local_modifications() {
+        let last = vec![description("a.txt", 5, 1000), description("b.txt", 5, 1000)];
+        let current = vec![
+            description("a.txt", 5, 1000),
+            description("b.txt", 5, 2000),
+            description("c.txt", 5, 1000),
+            description("d (conflicted copy from laptop 2024-05-01).txt", 5, 1000),
+        ];
+        let modified = |last: Option<&[FileDescription]>, path: &str| {
+            is_locally_modified(last, &current, &MatchablePath::from(path))
+        };
+
+        assert!(!modified(Some(&last), "a.txt"));
+        assert!(modified(Some(&last), "b.txt"));
+        assert!(modified(Some(&last), "c.txt"));
+        assert!(!modified(Some(&last), "missing.txt"));
+        assert!(!modified(
+            Some(&last),
+            "d (conflicted copy from laptop 2024-05-01).txt"
+        ));
+        // nothing to compare with right after start
+        assert!(!modified(None, "b.txt"));
     }
 }
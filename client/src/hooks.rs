@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use shared::matchable_path::MatchablePath;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// external commands run around syncing - see [`HookConfig`]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// before a watch group is scanned (e.g. export a database into the synced folder)
+    pub on_before_scan: Option<HookConfig>,
+    /// after a file was downloaded (e.g. reload an app)
+    pub on_after_download: Option<HookConfig>,
+    /// after the server refused an upload because it holds a newer version
+    pub on_conflict: Option<HookConfig>,
+}
+
+/// the command runs via `sh -c` (`cmd /C` on windows) with the event context in `RFS_*` env vars
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HookConfig {
+    pub command: String,
+    /// the command gets killed after the timeout (counts as failure)
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// log a warning and carry on
+    #[default]
+    Warn,
+    /// `on_before_scan`: skip the watch group this poll cycle - other hooks: report as sync error
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HookEvent {
+    BeforeScan,
+    AfterDownload,
+    Conflict,
+}
+
+impl HookEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::BeforeScan => "on_before_scan",
+            HookEvent::AfterDownload => "on_after_download",
+            HookEvent::Conflict => "on_conflict",
+        }
+    }
+}
+
+impl HooksConfig {
+    fn get(&self, event: HookEvent) -> Option<&HookConfig> {
+        match event {
+            HookEvent::BeforeScan => self.on_before_scan.as_ref(),
+            HookEvent::AfterDownload => self.on_after_download.as_ref(),
+            HookEvent::Conflict => self.on_conflict.as_ref(),
+        }
+    }
+
+    /// runs the hook of the event (if configured)
+    ///
+    /// Err only if the hook failed and its policy is [`FailurePolicy::Abort`]
+    pub async fn run(
+        &self,
+        event: HookEvent,
+        wg_id: i64,
+        root: &Path,
+        path: Option<&MatchablePath>,
+    ) -> Result<(), String> {
+        let Some(hook) = self.get(event) else {
+            return Ok(());
+        };
+        match run_hook(hook, event, wg_id, root, path).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let message = format!("Hook {} failed - {}", event.as_str(), e);
+                match hook.on_failure {
+                    FailurePolicy::Warn => {
+                        warn!("{message}");
+                        Ok(())
+                    }
+                    FailurePolicy::Abort => Err(message),
+                }
+            }
+        }
+    }
+}
+
+async fn run_hook(
+    hook: &HookConfig,
+    event: HookEvent,
+    wg_id: i64,
+    root: &Path,
+    path: Option<&MatchablePath>,
+) -> Result<(), String> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command
+        .arg(&hook.command)
+        .env("RFS_HOOK", event.as_str())
+        .env("RFS_WATCH_GROUP_ID", wg_id.to_string())
+        .env("RFS_ROOT", root)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    if let Some(path) = path {
+        command
+            .env("RFS_PATH", path.to_serialized_string())
+            .env("RFS_FILE", path.resolve(root));
+    }
+
+    let timeout = Duration::from_secs(hook.timeout_secs);
+    let output = tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| format!("timed out after {}s", hook.timeout_secs))?
+        .map_err(|e| format!("could not be started - {e}"))?;
+
+    if output.status.success() {
+        info!("Hook {} ran for watch group {}", event.as_str(), wg_id);
+        Ok(())
+    } else {
+        Err(format!(
+            "{} - {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn hooks(command: &str, timeout_secs: u64, on_failure: FailurePolicy) -> HooksConfig {
+        HooksConfig {
+            on_after_download: Some(HookConfig {
+                command: command.to_string(),
+                timeout_secs,
+                on_failure,
+            }),
+            ..HooksConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn should_pass_event_context_as_env_vars() {
+        let root = std::env::temp_dir().join(format!("hooks-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        let hooks = hooks(
+            "echo \"$RFS_HOOK $RFS_WATCH_GROUP_ID $RFS_PATH\" > \"$RFS_ROOT/env.txt\"",
+            5,
+            FailurePolicy::Abort,
+        );
+
+        let path = MatchablePath::from("docs/a.txt");
+        hooks
+            .run(HookEvent::AfterDownload, 7, &root, Some(&path))
+            .await
+            .unwrap();
+        hooks
+            .run(HookEvent::BeforeScan, 7, &root, None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            "on_after_download 7 docs/a.txt\n",
+            std::fs::read_to_string(root.join("env.txt")).unwrap()
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_apply_failure_policy() {
+        let root = std::env::temp_dir();
+
+        assert!(
+            hooks("exit 3", 5, FailurePolicy::Warn)
+                .run(HookEvent::AfterDownload, 1, &root, None)
+                .await
+                .is_ok()
+        );
+        let failed = hooks("echo broken >&2; exit 3", 5, FailurePolicy::Abort)
+            .run(HookEvent::AfterDownload, 1, &root, None)
+            .await;
+        assert!(failed.unwrap_err().contains("broken"));
+        let timed_out = hooks("sleep 5", 0, FailurePolicy::Abort)
+            .run(HookEvent::AfterDownload, 1, &root, None)
+            .await;
+        assert!(timed_out.unwrap_err().contains("timed out"));
+    }
+}
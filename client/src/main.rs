@@ -1,24 +1,193 @@
+use clap::{Parser, Subcommand};
 use shared::dtos::FileDescription;
+use shared::get_files_of_directory::ScanLimits;
+use shared::log_format::LogFormat;
 use std::collections::HashMap;
+use std::fs::File;
 use std::ops::Add;
 use std::path::PathBuf;
+use std::sync::Mutex;
 use std::time::Duration;
 use tokio::time::Instant;
-use tracing::trace;
+use tracing::{Instrument, debug, info, info_span, trace};
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use uuid::Uuid;
 
-use crate::config::fetch_watch_config;
+use crate::bandwidth::BandwidthMeter;
+use crate::config::{
+    BandwidthCapConfig, BulkUploadConfig, ConflictPolicy, LargeFilesConfig, TempFilesConfig,
+    fetch_watch_config,
+};
 use crate::execute::loop_scan;
+use crate::hooks::HooksConfig;
+use crate::network::NetworkConfig;
+use crate::oneshot::OutputFormat;
+use crate::poll::AdaptiveInterval;
+use crate::power::{LowBatteryAction, PowerConfig};
 use crate::setup::setup;
+use crate::status::StatusHandle;
+use crate::transport::Transport;
 
+mod approval;
+mod bandwidth;
+mod bench;
+mod bulk;
+mod command;
 mod config;
+mod control;
+mod discovery;
+mod disk_space;
 mod execute;
+mod hooks;
+mod network;
+mod oneshot;
+mod poll;
+mod power;
+mod priority;
+mod quic;
 mod setup;
+mod stats;
+mod status;
+mod syncignore;
+mod timing;
+mod transport;
+mod tui;
+
+/// log file used while the tui owns the terminal
+const TUI_LOG_FILE: &str = "./client-tui.log";
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Path to the config.yaml (default: ./config.yaml or ./config.yml)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Path to the config.yaml - positional form of `--config` (`client path/to/config.yaml`)
+    #[arg(value_name = "CONFIG", conflicts_with = "config")]
+    config_path: Option<PathBuf>,
+
+    /// Log output: pretty or json (default: env LOG_FORMAT or pretty)
+    #[arg(long, global = true)]
+    log_format: Option<LogFormat>,
+
+    /// Result output of push / pull / ls / seed / verify / status / bench: text or json
+    #[arg(long, global = true, default_value_t)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Default)]
+enum Command {
+    /// Run the sync daemon (default)
+    #[default]
+    Run,
+    /// Run the sync daemon with an interactive terminal dashboard
+    Tui,
+    /// Upload a single file and exit
+    Push {
+        file: PathBuf,
+        /// Watch group (name or id) - default: the one whose folder contains the file
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Download a single file (`<watch group>/<path>`) and exit
+    Pull {
+        path: String,
+        /// Directory to save the file in (default: current directory)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// List files on the server (below `<watch group>/<path>` if given)
+    Ls { path: Option<String> },
+    /// Check a copied disk against a manifest of the server (`/api/watch-groups/{id}/manifest`)
+    /// so the next sync only transfers what differs
+    Seed {
+        manifest: PathBuf,
+        /// Folder of the copy (default: the watch group's folder - asks the server)
+        #[arg(long)]
+        root: Option<PathBuf>,
+        /// Only report - don't touch any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Compare the local files of each watch group with the server's (paths, sizes and hashes)
+    /// and report missing, extra and differing ones
+    Verify {
+        /// Up- or download the differing files right away (the newer side wins, the server's on
+        /// a tie)
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Stop syncing a file or folder of a watch group (via its .syncignore) - it stays on disk and on
+    /// the server, leftovers of interrupted downloads are deleted
+    Ignore { path: PathBuf },
+    /// Sync an ignored file or folder again
+    Unignore { path: PathBuf },
+    /// List the large new files the daemon holds back - or upload one of them
+    /// (`<watch group>/<path>`) with its next sync
+    Approve {
+        path: Option<String>,
+        /// Add the file to the .syncignore instead
+        #[arg(long, requires = "path")]
+        ignore: bool,
+    },
+    /// Create a config.yaml - lists the servers on the LAN (mDNS) to pick from
+    Init {
+        /// Skip the search and use this server
+        #[arg(long)]
+        server_url: Option<String>,
+        /// How long to search the LAN for servers
+        #[arg(long, default_value_t = 3)]
+        timeout_secs: u64,
+    },
+    /// Show what the next sync of each watch group would transfer and exit
+    Status {
+        /// Stats of the running daemon (from its control port) instead
+        #[arg(long)]
+        stats: bool,
+        /// Battery and power state of the running daemon (from its control port) instead
+        #[arg(long, conflicts_with = "stats")]
+        power: bool,
+    },
+    /// Measure latency and throughput to the server with synthetic data (nothing is stored)
+    Bench {
+        /// Bytes to upload and download - e.g. 512K, 100M, 1G
+        #[arg(long, default_value = "100M", value_parser = bench::parse_size)]
+        size: u64,
+    },
+    /// Take over the watch groups and history of the client id this machine had before (e.g.
+    /// before reinstalling the OS) - the old id is deregistered
+    Claim {
+        #[arg(long)]
+        old_id: Uuid,
+        /// Admin token of the server (if it has one)
+        #[arg(long)]
+        token: Option<String>,
+    },
+}
 
 struct ClientState {
+    pub client_id: Uuid,
     pub server_url: String,
     pub min_poll_interval_in_ms: u16,
     pub watch_groups: HashMap<i64, WatchGroup>,
+    pub bulk_upload: BulkUploadConfig,
+    pub hooks: HooksConfig,
+    pub control_port: u16,
+    pub max_poll_interval_in_ms: u64,
+    pub temp_files: TempFilesConfig,
+    pub scan_limits: ScanLimits,
+    pub conflict_policy: ConflictPolicy,
+    pub large_files: LargeFilesConfig,
+    pub min_free_disk_mb: u64,
+    pub bandwidth_cap: BandwidthCapConfig,
+    pub networks: NetworkConfig,
+    pub power: PowerConfig,
+    pub slow_instruction_ms: u64,
 }
 
 struct WatchGroup {
@@ -26,35 +195,214 @@ struct WatchGroup {
     pub path_to_monitor: PathBuf,
     pub exclude_dirs: Vec<String>,
     pub exclude_dot_dirs: bool,
+    /// higher priorities are synced first
+    pub priority: i64,
 }
 
 #[tokio::main]
 async fn main() {
+    let mut cli = Cli::parse();
+    cli.config = cli.config.or(cli.config_path.take());
+    let command = cli.command.unwrap_or_default();
+
+    let log_format = cli.log_format.unwrap_or_else(LogFormat::from_env);
+    match command {
+        Command::Run => init_tracing(log_format, std::io::stdout, true),
+        // stdout is the result of the command - logs go to stderr
+        Command::Push { .. }
+        | Command::Pull { .. }
+        | Command::Ls { .. }
+        | Command::Seed { .. }
+        | Command::Verify { .. }
+        | Command::Ignore { .. }
+        | Command::Unignore { .. }
+        | Command::Approve { .. }
+        | Command::Init { .. }
+        | Command::Status { .. }
+        | Command::Bench { .. }
+        | Command::Claim { .. } => init_tracing(log_format, std::io::stderr, true),
+        // the terminal belongs to the dashboard - logs go to a file instead
+        Command::Tui => {
+            let log_file = File::create(TUI_LOG_FILE).expect("Should be able to create log file");
+            init_tracing(log_format, Mutex::new(log_file), false)
+        }
+    }
+
+    match command {
+        Command::Run => {
+            let (state, transport) = setup(cli.config).await;
+            let status = StatusHandle::default();
+            control::serve(state.control_port, status.clone());
+            sync_loop(state, transport, status).await
+        }
+        Command::Tui => {
+            let (state, transport) = setup(cli.config).await;
+            let status = StatusHandle::default();
+            control::serve(state.control_port, status.clone());
+            tui::run(state, transport, status).await
+        }
+        Command::Push { file, to } => {
+            exit_on_error(oneshot::push(cli.config, cli.output, &file, to.as_deref()).await)
+        }
+        Command::Pull { path, out } => {
+            exit_on_error(oneshot::pull(cli.config, cli.output, &path, out).await)
+        }
+        Command::Ls { path } => {
+            exit_on_error(oneshot::ls(cli.config, cli.output, path.as_deref()).await)
+        }
+        Command::Seed {
+            manifest,
+            root,
+            dry_run,
+        } => exit_on_error(oneshot::seed(cli.config, cli.output, &manifest, root, dry_run).await),
+        Command::Verify { fix } => {
+            exit_on_error(oneshot::verify(cli.config, cli.output, fix).await)
+        }
+        Command::Ignore { path } => {
+            exit_on_error(oneshot::ignore(cli.config, cli.output, &path, true).await)
+        }
+        Command::Unignore { path } => {
+            exit_on_error(oneshot::ignore(cli.config, cli.output, &path, false).await)
+        }
+        Command::Approve { path, ignore } => {
+            exit_on_error(oneshot::approve(cli.config, cli.output, path.as_deref(), ignore).await)
+        }
+        Command::Init {
+            server_url,
+            timeout_secs,
+        } => exit_on_error(
+            discovery::init(cli.config, server_url, Duration::from_secs(timeout_secs)).await,
+        ),
+        Command::Status { stats: true, .. } => {
+            exit_on_error(oneshot::stats(cli.config, cli.output).await)
+        }
+        Command::Status { power: true, .. } => {
+            exit_on_error(oneshot::power(cli.config, cli.output).await)
+        }
+        Command::Status { .. } => exit_on_error(oneshot::status(cli.config, cli.output).await),
+        Command::Bench { size } => exit_on_error(bench::bench(cli.config, cli.output, size).await),
+        Command::Claim { old_id, token } => {
+            exit_on_error(oneshot::claim(cli.config, cli.output, old_id, token.as_deref()).await)
+        }
+    }
+}
+
+/// one-shot commands report failures through the exit code - for scripts
+fn exit_on_error(result: Result<(), String>) {
+    if let Err(e) = result {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn init_tracing<W>(log_format: LogFormat, writer: W, ansi: bool)
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
     let log_level = EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("info"));
-    tracing_subscriber::fmt().with_env_filter(log_level).init();
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(log_level)
+        .with_writer(writer);
+    match log_format {
+        LogFormat::Pretty => builder.with_ansi(ansi).init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+    }
+}
 
-    let (mut state, client) = setup().await;
+async fn sync_loop(mut state: ClientState, transport: Transport, status: StatusHandle) {
     let mut last_scans: HashMap<i64, Vec<FileDescription>> = HashMap::new();
+    let mut interval = AdaptiveInterval::default();
+    let mut bandwidth =
+        BandwidthMeter::restore(&transport.http, &state.server_url, state.bandwidth_cap).await;
 
     loop {
         let loop_start = Instant::now();
+        let capped = bandwidth.check(&bandwidth::current_month(), &status);
+        let off_network = network::is_blocked(&state.networks, &status).await;
+        let low_battery = power::check(&state.power, &status).await;
+
+        if !status.is_paused() && !capped && !off_network && low_battery != LowBatteryAction::Pause
+        {
+            let dto = fetch_watch_config(&transport.http, &state.server_url).await;
+            if dto.watch_groups.len() != state.watch_groups.len()
+                || dto
+                    .watch_groups
+                    .keys()
+                    .any(|id| !state.watch_groups.contains_key(id))
+            {
+                // newly assigned watch groups shouldn't wait for the backed off interval
+                interval.reset();
+            }
+            state.watch_groups = setup::to_watch_group(dto.watch_groups);
+            state.min_poll_interval_in_ms = dto.min_poll_interval_in_ms;
 
-        let dto = fetch_watch_config(&client, &state.server_url).await;
-        state.watch_groups = setup::to_watch_group(dto.watch_groups);
-        state.min_poll_interval_in_ms = dto.min_poll_interval_in_ms;
+            for wg_id in priority::sync_order(&state.watch_groups) {
+                let wg = &state.watch_groups[&wg_id];
+                let last_scan = last_scans.remove(&wg_id);
+                let span = info_span!("sync", client_id = %state.client_id, watch_group_id = wg_id);
+                let next_scan = loop_scan(&state, wg_id, wg, &transport, last_scan, &status)
+                    .instrument(span)
+                    .await;
+                // last_scan state should only be updated when everything runs through otherwise we
+                // risk losing information (delete)
+                last_scans.insert(wg_id, next_scan);
+            }
 
-        for (wg_id, wg) in &state.watch_groups {
-            let last_scan = last_scans.remove(wg_id);
-            let next_scan = loop_scan(&state.server_url, *wg_id, wg, &client, last_scan).await;
-            // last_scan state should only be updated when everything runs through otherwise we
-            // risk losing information (delete)
-            last_scans.insert(*wg_id, next_scan);
+            // idle cycles only at debug - a poll every few seconds would bury everything else
+            let cycle = status.finish_cycle(loop_start.elapsed());
+            if cycle.is_idle() {
+                debug!("Cycle: {cycle}");
+            } else {
+                info!("Cycle: {cycle}");
+            }
+            interval.record_cycle(cycle.is_idle());
+            bandwidth.record(&cycle);
+            bandwidth.report(&transport.http, &state.server_url).await;
         }
 
         trace!("Loop took {:?}", Instant::now().duration_since(loop_start));
-        tokio::time::sleep_until(
-            loop_start.add(Duration::from_millis(state.min_poll_interval_in_ms as u64)),
-        )
-        .await;
+        let mut poll_interval = interval.current(
+            state.min_poll_interval_in_ms as u64,
+            state.max_poll_interval_in_ms,
+        );
+        if low_battery == LowBatteryAction::Throttle {
+            poll_interval = poll_interval.max(Duration::from_millis(
+                state.power.throttled_poll_interval_in_ms,
+            ));
+        }
+        tokio::select! {
+            _ = tokio::time::sleep_until(loop_start.add(poll_interval)) => {}
+            _ = status.sync_requested() => {
+                trace!("Sync requested before poll interval elapsed");
+                interval.reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_accept_positional_config_path() {
+        let cli = Cli::try_parse_from(["client", "/etc/sync/config.yaml"]).unwrap();
+        assert_eq!(
+            Some(PathBuf::from("/etc/sync/config.yaml")),
+            cli.config_path
+        );
+        assert!(cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["client", "config.yaml", "tui"]).unwrap();
+        assert_eq!(Some(PathBuf::from("config.yaml")), cli.config_path);
+        assert!(matches!(cli.command, Some(Command::Tui)));
+
+        let cli = Cli::try_parse_from(["client", "status", "--config", "c.yaml"]).unwrap();
+        assert_eq!(Some(PathBuf::from("c.yaml")), cli.config);
+        assert!(Cli::try_parse_from(["client", "a.yaml", "--config", "b.yaml"]).is_err());
     }
 }
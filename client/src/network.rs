@@ -0,0 +1,255 @@
+// NETWORK AWARENESS ------------------------------------------------------------
+//
+// laptops shouldn't sync over hotspots - the sync loop skips its cycles while the default route
+// goes through a network the `networks:` lists of the config.yaml don't allow
+
+use serde::{Deserialize, Serialize};
+use shared::glob::matches_glob;
+use tracing::{debug, info, warn};
+
+use crate::command::output;
+use crate::status::StatusHandle;
+
+/// glob patterns (case-insensitive) - empty lists allow everything
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Wi-Fi names syncing is allowed on - wired connections aren't restricted by it
+    pub allow_ssids: Vec<String>,
+    pub deny_ssids: Vec<String>,
+    /// interface of the default route (`eth0`, `en*`, `Wi-Fi`, ...)
+    pub allow_interfaces: Vec<String>,
+    pub deny_interfaces: Vec<String>,
+}
+
+impl NetworkConfig {
+    pub fn is_set(&self) -> bool {
+        !(self.allow_ssids.is_empty()
+            && self.deny_ssids.is_empty()
+            && self.allow_interfaces.is_empty()
+            && self.deny_interfaces.is_empty())
+    }
+}
+
+/// what the machine is connected through right now - None where it can't be determined
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActiveNetwork {
+    pub interface: Option<String>,
+    /// None unless connected to a Wi-Fi
+    pub ssid: Option<String>,
+}
+
+impl ActiveNetwork {
+    pub async fn detect() -> Self {
+        let interface = default_interface().await;
+        let ssid = ssid(interface.as_deref()).await;
+        debug!(?interface, ?ssid, "Detected network");
+        Self { interface, ssid }
+    }
+}
+
+/// why syncing isn't allowed on `network` - None if it is
+///
+/// an allow list of interfaces fails closed if the interface can't be determined
+pub fn disallowed(config: &NetworkConfig, network: &ActiveNetwork) -> Option<String> {
+    let any = |patterns: &[String], value: &str| patterns.iter().any(|p| matches_glob(p, value));
+    match &network.interface {
+        Some(interface) if any(&config.deny_interfaces, interface) => {
+            return Some(format!("interface '{interface}' is denied"));
+        }
+        Some(interface)
+            if !config.allow_interfaces.is_empty() && !any(&config.allow_interfaces, interface) =>
+        {
+            return Some(format!("interface '{interface}' is not allowed"));
+        }
+        None if !config.allow_interfaces.is_empty() => {
+            return Some("the network interface could not be determined".to_string());
+        }
+        _ => {}
+    }
+    match &network.ssid {
+        Some(ssid) if any(&config.deny_ssids, ssid) => Some(format!("Wi-Fi '{ssid}' is denied")),
+        Some(ssid) if !config.allow_ssids.is_empty() && !any(&config.allow_ssids, ssid) => {
+            Some(format!("Wi-Fi '{ssid}' is not allowed"))
+        }
+        _ => None,
+    }
+}
+
+/// true while syncing isn't allowed - logs (and shows in the tui) when that changes
+pub async fn is_blocked(config: &NetworkConfig, status: &StatusHandle) -> bool {
+    let reason = match config.is_set() {
+        true => disallowed(config, &ActiveNetwork::detect().await),
+        false => None,
+    };
+    let before = status.read(|s| s.network_blocked.clone());
+    if reason != before {
+        match &reason {
+            Some(reason) => {
+                warn!("Pausing sync - {reason}");
+                status.error(format!("Sync paused - {reason}"));
+            }
+            None => info!("Network allowed again - resuming sync"),
+        }
+        let blocked = reason.clone();
+        status.update(|s| s.network_blocked = blocked);
+    }
+    reason.is_some()
+}
+
+async fn default_interface() -> Option<String> {
+    if cfg!(target_os = "linux") {
+        let routes = tokio::fs::read_to_string("/proc/net/route").await.ok()?;
+        parse_proc_net_route(&routes)
+    } else if cfg!(target_os = "macos") {
+        parse_route_get(&output("route", &["-n", "get", "default"]).await?)
+    } else if cfg!(windows) {
+        let command = "Get-NetRoute -DestinationPrefix 0.0.0.0/0 | Sort-Object RouteMetric | \
+                       Select-Object -First 1 -ExpandProperty InterfaceAlias";
+        let alias = output("powershell", &["-NoProfile", "-Command", command]).await?;
+        Some(alias.trim().to_string()).filter(|a| !a.is_empty())
+    } else {
+        None
+    }
+}
+
+async fn ssid(interface: Option<&str>) -> Option<String> {
+    if cfg!(target_os = "linux") {
+        if let Some(nmcli) = output("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi"]).await {
+            return parse_nmcli(&nmcli);
+        }
+        let ssid = output("iwgetid", &["-r"]).await?;
+        Some(ssid.trim().to_string()).filter(|s| !s.is_empty())
+    } else if cfg!(target_os = "macos") {
+        let interface = interface.unwrap_or("en0");
+        parse_airport(&output("networksetup", &["-getairportnetwork", interface]).await?)
+    } else if cfg!(windows) {
+        parse_netsh(&output("netsh", &["wlan", "show", "interfaces"]).await?)
+    } else {
+        None
+    }
+}
+
+/// interface of the default route with the lowest metric
+fn parse_proc_net_route(routes: &str) -> Option<String> {
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let metric: u32 = columns.get(6)?.parse().ok()?;
+            (columns.get(1) == Some(&"00000000")).then(|| (metric, columns[0].to_string()))
+        })
+        .min()
+        .map(|(_, interface)| interface)
+}
+
+/// `route -n get default` (macOS)
+fn parse_route_get(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("interface:"))
+        .map(|interface| interface.trim().to_string())
+}
+
+/// `nmcli -t -f active,ssid dev wifi` - `yes:<ssid>` marks the connected one
+fn parse_nmcli(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("yes:"))
+        .map(|ssid| ssid.replace("\\:", ":"))
+        .filter(|ssid| !ssid.is_empty())
+}
+
+/// `networksetup -getairportnetwork en0` (macOS)
+fn parse_airport(output: &str) -> Option<String> {
+    output
+        .trim()
+        .strip_prefix("Current Wi-Fi Network:")
+        .map(|ssid| ssid.trim().to_string())
+}
+
+/// `netsh wlan show interfaces` (Windows) - not to be confused with the BSSID line
+fn parse_netsh(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(key, _)| key.trim() == "SSID")
+        .map(|(_, ssid)| ssid.trim().to_string())
+        .filter(|ssid| !ssid.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(interface: Option<&str>, ssid: Option<&str>) -> ActiveNetwork {
+        ActiveNetwork {
+            interface: interface.map(str::to_string),
+            ssid: ssid.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn should_allow_and_deny_networks() {
+        let config = NetworkConfig {
+            allow_ssids: vec!["Home*".to_string(), "Office".to_string()],
+            deny_interfaces: vec!["usb*".to_string()],
+            ..NetworkConfig::default()
+        };
+
+        assert_eq!(
+            None,
+            disallowed(&config, &network(Some("wlan0"), Some("home-5G")))
+        );
+        assert_eq!(None, disallowed(&config, &network(Some("eth0"), None)));
+        assert_eq!(
+            Some("Wi-Fi 'iPhone' is not allowed".to_string()),
+            disallowed(&config, &network(Some("wlan0"), Some("iPhone")))
+        );
+        assert_eq!(
+            Some("interface 'usb0' is denied".to_string()),
+            disallowed(&config, &network(Some("usb0"), None))
+        );
+
+        let wired_only = NetworkConfig {
+            allow_interfaces: vec!["eth*".to_string()],
+            ..NetworkConfig::default()
+        };
+        assert!(disallowed(&wired_only, &network(Some("wlan0"), Some("Home"))).is_some());
+        assert!(disallowed(&wired_only, &network(None, None)).is_some());
+        assert!(!NetworkConfig::default().is_set());
+    }
+
+    #[test]
+    fn should_parse_platform_outputs() {
+        let routes = "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+                      wlan0\t00000000\t0102A8C0\t0003\t0\t0\t600\t00000000\n\
+                      eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\n\
+                      eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\n";
+        assert_eq!(Some("eth0".to_string()), parse_proc_net_route(routes));
+        assert_eq!(
+            Some("en0".to_string()),
+            parse_route_get("   route to: default\n  gateway: 192.168.1.1\n  interface: en0\n")
+        );
+        assert_eq!(
+            Some("Home:5G".to_string()),
+            parse_nmcli("no:Neighbour\nyes:Home\\:5G\n")
+        );
+        assert_eq!(None, parse_nmcli("no:Neighbour\n"));
+        assert_eq!(
+            Some("Office".to_string()),
+            parse_airport("Current Wi-Fi Network: Office\n")
+        );
+        assert_eq!(
+            None,
+            parse_airport("You are not associated with an AirPort network.\n")
+        );
+        assert_eq!(
+            Some("Home".to_string()),
+            parse_netsh(
+                "    Name                   : Wi-Fi\n    SSID                   : Home\n    BSSID                  : 01:02:03:04:05:06\n"
+            )
+        );
+    }
+}
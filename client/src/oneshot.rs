@@ -0,0 +1,1159 @@
+// ONE-SHOT COMMANDS -----------------------------------------------------------
+//
+// push / pull / ls / status / seed / verify / ignore / approve / claim for scripts and cron jobs - they talk to the same endpoints as
+// the sync daemon, but exit after one transfer
+
+use reqwest::Client;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use shared::dtos::{
+    BandwidthUsageDto, ClientClaimDto, ClientClaimedDto, FileDescription, ManifestDto,
+    ManifestEntryDto,
+};
+use shared::endpoint::{ServerEndpoint, SysRoute};
+use shared::get_files_of_directory::get_all_file_descriptions;
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::SyncInstruction;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+use crate::WatchGroup;
+use crate::approval::{self, Decision, PendingApproval};
+use crate::bandwidth::{self, CapState, current_month};
+use crate::config::{Config, fetch_watch_config, read_config};
+use crate::control::{self, fetch_approvals, fetch_power, fetch_stats};
+use crate::execute::{download, execute};
+use crate::setup::{connect, to_watch_group};
+use crate::status::StatusHandle;
+use crate::syncignore::{self, SYNCIGNORE_FILE, SyncIgnore};
+use crate::transport::Synced;
+
+/// what the one-shot commands print on stdout - lines for humans or one json document (for jq)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Could not parse '{}' - expected 'text' or 'json'",
+                value
+            )),
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// a file on the server (pushed, pulled or listed)
+#[derive(Debug, Serialize)]
+struct FileOutput {
+    watch_group_id: i64,
+    watch_group: String,
+    path: String,
+    size_in_bytes: u64,
+    /// set for pushed / pulled files - hex sha256 of the local copy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    /// set for pulled files - where the download was saved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_updated_utc_millis: Option<UtcMillis>,
+}
+
+/// outcome of `client status`
+#[derive(Debug, Serialize)]
+struct StatusOutput {
+    watch_groups: Vec<WatchGroupStatusOutput>,
+    /// of the current month with the caps of the config.yaml - None if the server has no record
+    bandwidth_usage: Option<BandwidthUsageDto>,
+}
+
+/// pending work of a watch group - what the next sync would do
+#[derive(Debug, Serialize)]
+struct WatchGroupStatusOutput {
+    watch_group_id: i64,
+    watch_group: String,
+    path_to_monitor: PathBuf,
+    local_files: usize,
+    uploads: usize,
+    downloads: usize,
+    deletes: usize,
+    paused_by_server: bool,
+}
+
+/// uploads `file` - into the watch group whose folder contains it (keeping its relative path)
+/// or into the root of `to`
+pub async fn push(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    file: &Path,
+    to: Option<&str>,
+) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    let file = file
+        .canonicalize()
+        .map_err(|e| format!("Cannot push {file:?} - {e}"))?;
+    if !file.is_file() {
+        return Err(format!("{file:?} is not a file"));
+    }
+    let watch_groups = watch_groups(&config, &transport.http).await;
+    let wg_id = match to {
+        Some(wg) => resolve_watch_group(&watch_groups, wg)?,
+        None => containing_watch_group(&watch_groups, &file)
+            .or_else(|| single_watch_group(&watch_groups))
+            .ok_or("Cannot tell which watch group to push to - pass --to")?,
+    };
+    let root = watch_groups
+        .get(&wg_id)
+        .and_then(|wg| wg.path_to_monitor.canonicalize().ok())
+        .filter(|root| file.starts_with(root))
+        .or_else(|| file.parent().map(Path::to_path_buf))
+        .ok_or_else(|| format!("{file:?} has no parent directory"))?;
+    let relative_path = MatchablePath::from(file.strip_prefix(&root).unwrap_or(&file));
+
+    execute(
+        &transport,
+        SyncInstruction::Upload(relative_path.clone()),
+        &root,
+        &config.server_url,
+        wg_id,
+        &config.hooks,
+        &StatusHandle::default(),
+    )
+    .await?;
+    let pushed = FileOutput {
+        sha256: Some(sha256_of(&file).map_err(|e| format!("Cannot hash {file:?} - {e}"))?),
+        ..file_output(&watch_groups, wg_id, &relative_path, file_size(&file))
+    };
+    print(output, &pushed, |pushed| {
+        format!("{}/{}", pushed.watch_group, pushed.path)
+    });
+    Ok(())
+}
+
+/// downloads `<watch group>/<path>` into `out` (keeping the file name)
+pub async fn pull(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    path: &str,
+    out: Option<PathBuf>,
+) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    let watch_groups = watch_groups(&config, &transport.http).await;
+    let (wg_id, relative_path) = split_path(&watch_groups, path)?;
+    if relative_path.get().is_empty() {
+        return Err(format!("{path} is a watch group, not a file"));
+    }
+    let target = out
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(relative_path.tail());
+
+    download(
+        &transport,
+        &config.server_url,
+        wg_id,
+        &relative_path,
+        &target,
+        &StatusHandle::default(),
+    )
+    .await?;
+    let pulled = FileOutput {
+        sha256: Some(sha256_of(&target).map_err(|e| format!("Cannot hash {target:?} - {e}"))?),
+        ..file_output(&watch_groups, wg_id, &relative_path, file_size(&target))
+    };
+    print(
+        output,
+        &FileOutput {
+            local_path: Some(target),
+            ..pulled
+        },
+        |pulled| {
+            pulled
+                .local_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        },
+    );
+    Ok(())
+}
+
+/// lists every file below `path` (everything if missing) - `<size>  <watch group>/<path>`
+pub async fn ls(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    path: Option<&str>,
+) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    let watch_groups = watch_groups(&config, &transport.http).await;
+    let filter = path.map(|p| split_path(&watch_groups, p)).transpose()?;
+    let files: Vec<FileDescription> = transport
+        .http
+        .get(ServerEndpoint::Scan.to_uri(&config.server_url))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Listing failed - {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Listing failed - {e}"))?;
+
+    let mut listed: Vec<FileOutput> = files
+        .into_iter()
+        .filter_map(|file| {
+            // the scan covers all watch groups - the first segment is the watch group id
+            let (wg, rest) = file.relative_path.get().split_first()?;
+            let wg_id: i64 = wg.parse().ok()?;
+            if !watch_groups.contains_key(&wg_id) {
+                return None;
+            }
+            let relative_path = MatchablePath::from(rest.to_vec());
+            let matches = filter.as_ref().is_none_or(|(filter_wg, prefix)| {
+                *filter_wg == wg_id && relative_path.get().starts_with(prefix.get())
+            });
+            matches.then(|| FileOutput {
+                last_updated_utc_millis: Some(file.last_updated_utc_millis),
+                ..file_output(&watch_groups, wg_id, &relative_path, file.size_in_bytes)
+            })
+        })
+        .collect();
+    listed.sort_by(|a, b| (&a.watch_group, &a.path).cmp(&(&b.watch_group, &b.path)));
+    print(output, &listed, |listed| {
+        listed
+            .iter()
+            .map(|f| format!("{:>12}  {}/{}", f.size_in_bytes, f.watch_group, f.path))
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    Ok(())
+}
+
+/// what the next sync of every watch group would do - nothing gets transferred
+pub async fn status(config_path: Option<PathBuf>, output: OutputFormat) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    let watch_groups = watch_groups(&config, &transport.http).await;
+    let mut statuses = Vec::new();
+    for (wg_id, wg) in &watch_groups {
+        let descriptions = get_all_file_descriptions(
+            &wg.path_to_monitor,
+            &wg.exclude_dirs,
+            wg.exclude_dot_dirs,
+            config.temp_files.for_watch_group(*wg_id, &wg.name),
+            config.scan_limits,
+        )
+        .map_err(|e| format!("Could not scan {:?} - {e}", wg.path_to_monitor))?;
+        let ignore = SyncIgnore::load(&wg.path_to_monitor);
+        let descriptions = ignore.retain(descriptions);
+        let Synced {
+            mut instructions,
+            paused: paused_by_server,
+            ..
+        } = transport
+            .sync(&config.server_url, *wg_id, &descriptions)
+            .await
+            .map_err(|e| format!("Sync request for {} failed - {e}", wg.name))?;
+        instructions.retain(|i| !ignore.is_ignored(i.path()));
+        let count = |f: fn(&SyncInstruction) -> bool| instructions.iter().filter(|i| f(i)).count();
+        statuses.push(WatchGroupStatusOutput {
+            watch_group_id: *wg_id,
+            watch_group: wg.name.clone(),
+            path_to_monitor: wg.path_to_monitor.clone(),
+            local_files: descriptions.len(),
+            uploads: count(|i| matches!(i, SyncInstruction::Upload(_))),
+            downloads: count(|i| matches!(i, SyncInstruction::Download(_))),
+            deletes: count(|i| matches!(i, SyncInstruction::Delete(_))),
+            paused_by_server,
+        });
+    }
+    statuses.sort_by_key(|s| s.watch_group_id);
+    let bandwidth_usage =
+        bandwidth::fetch_usage(&transport.http, &config.server_url, &current_month())
+            .await
+            .ok()
+            .map(|usage| BandwidthUsageDto {
+                soft_cap_bytes: config.bandwidth_cap.soft_cap_mb * 1024 * 1024,
+                hard_cap_bytes: config.bandwidth_cap.hard_cap_mb * 1024 * 1024,
+                ..usage
+            });
+    let status = StatusOutput {
+        watch_groups: statuses,
+        bandwidth_usage,
+    };
+    print(output, &status, |status| {
+        let usage = status.bandwidth_usage.as_ref().map(|usage| {
+            let mb = |bytes: u64| bytes / (1024 * 1024);
+            let cap = |bytes: u64| match bytes {
+                0 => "none".to_string(),
+                bytes => format!("{} MB", mb(bytes)),
+            };
+            format!(
+                "bandwidth {}: {} MB up, {} MB down (soft cap {}, hard cap {}){}",
+                usage.month,
+                mb(usage.uploaded_bytes),
+                mb(usage.downloaded_bytes),
+                cap(usage.soft_cap_bytes),
+                cap(usage.hard_cap_bytes),
+                match bandwidth::cap_state(usage) {
+                    CapState::Below => "",
+                    CapState::Soft => " - soft cap exceeded",
+                    CapState::Hard => " - hard cap reached, sync stopped",
+                }
+            )
+        });
+        status
+            .watch_groups
+            .iter()
+            .map(|s| {
+                format!(
+                    "{} ({:?}): {} files, {} to upload, {} to download, {} to delete{}",
+                    s.watch_group,
+                    s.path_to_monitor,
+                    s.local_files,
+                    s.uploads,
+                    s.downloads,
+                    s.deletes,
+                    if s.paused_by_server { " - paused" } else { "" }
+                )
+            })
+            .chain(usage)
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    Ok(())
+}
+
+/// outcome of `client seed`
+#[derive(Debug, Default, Serialize)]
+struct SeedOutput {
+    watch_group_id: i64,
+    watch_group: String,
+    root: PathBuf,
+    /// same content as on the server - not transferred again
+    matching: usize,
+    /// different content - downloaded from the server on the next sync
+    stale: Vec<String>,
+    /// not on the disk - downloaded on the next sync
+    missing: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Seeded {
+    Matching,
+    Stale,
+    Missing,
+}
+
+/// checks a copied disk against the manifest of the server - the server compares sizes and
+/// modification times only, so matching files get the modification time the server knows them by
+/// and differing files an older one (the server's version wins)
+pub async fn seed(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    manifest: &Path,
+    root: Option<PathBuf>,
+    dry_run: bool,
+) -> Result<(), String> {
+    let manifest: ManifestDto = fs::read(manifest)
+        .map_err(|e| format!("Cannot read {manifest:?} - {e}"))
+        .and_then(|bytes| {
+            serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Invalid manifest {manifest:?} - {e}"))
+        })?;
+    let root = match root {
+        // offline: no need to ask the server where the watch group lives
+        Some(root) => root,
+        None => {
+            let (config, transport) = connect(config_path)?;
+            watch_groups(&config, &transport.http)
+                .await
+                .remove(&manifest.watch_group_id)
+                .map(|wg| wg.path_to_monitor)
+                .ok_or_else(|| {
+                    format!(
+                        "Watch group {} ({}) is not assigned to this client - pass --root",
+                        manifest.watch_group_name, manifest.watch_group_id
+                    )
+                })?
+        }
+    };
+
+    let mut seeded = SeedOutput {
+        watch_group_id: manifest.watch_group_id,
+        watch_group: manifest.watch_group_name,
+        root,
+        ..SeedOutput::default()
+    };
+    for entry in &manifest.files {
+        let file = entry.path.resolve(&seeded.root);
+        match seed_file(&file, entry, dry_run).map_err(|e| format!("Cannot seed {file:?} - {e}"))? {
+            Seeded::Matching => seeded.matching += 1,
+            Seeded::Stale => seeded.stale.push(entry.path.to_serialized_string()),
+            Seeded::Missing => seeded.missing += 1,
+        }
+    }
+    print(output, &seeded, |seeded| {
+        let mut lines: Vec<String> = seeded
+            .stale
+            .iter()
+            .map(|path| format!("differs: {path}"))
+            .collect();
+        lines.push(format!(
+            "{}{} matching, {} differing, {} missing in {:?}",
+            if dry_run { "(dry run) " } else { "" },
+            seeded.matching,
+            seeded.stale.len(),
+            seeded.missing,
+            seeded.root
+        ));
+        lines.join("\n")
+    });
+    Ok(())
+}
+
+fn seed_file(file: &Path, entry: &ManifestEntryDto, dry_run: bool) -> io::Result<Seeded> {
+    if !file.is_file() {
+        return Ok(Seeded::Missing);
+    }
+    let matching = file_size(file) == entry.size_in_bytes && sha256_of(file)? == entry.sha256;
+    let millis = match matching {
+        true => entry.utc_millis.as_u64(),
+        false => entry.utc_millis.as_u64().saturating_sub(1),
+    };
+    if !dry_run {
+        File::options()
+            .write(true)
+            .open(file)?
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_millis(millis))?;
+    }
+    Ok(if matching {
+        Seeded::Matching
+    } else {
+        Seeded::Stale
+    })
+}
+
+/// outcome of `client verify` for one watch group
+#[derive(Debug, Default, Serialize)]
+struct VerifyOutput {
+    watch_group_id: i64,
+    watch_group: String,
+    path_to_monitor: PathBuf,
+    /// same size and hash on both sides
+    matching: usize,
+    /// on the server only
+    missing: Vec<String>,
+    /// local only
+    extra: Vec<String>,
+    /// on both sides with different content
+    mismatched: Vec<String>,
+    /// with `--fix` - corrections that didn't go through
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<String>,
+}
+
+/// local files compared to the manifest of the server
+#[derive(Debug, Default, PartialEq)]
+struct Comparison {
+    matching: usize,
+    missing: Vec<MatchablePath>,
+    extra: Vec<MatchablePath>,
+    mismatched: Vec<MatchablePath>,
+}
+
+/// compares the local tree of every watch group with the server's manifest (paths, sizes and
+/// hashes) - unlike a sync this finds files that differ despite same size & modification time.
+/// With `fix` the differing files are up- or downloaded right away instead of waiting for the
+/// diff of a sync (which might never see them)
+pub async fn verify(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    fix: bool,
+) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    let watch_groups = watch_groups(&config, &transport.http).await;
+    let mut wg_ids: Vec<i64> = watch_groups.keys().copied().collect();
+    wg_ids.sort();
+    let mut verified = Vec::new();
+    for wg_id in wg_ids {
+        let wg = &watch_groups[&wg_id];
+        let scanned = get_all_file_descriptions(
+            &wg.path_to_monitor,
+            &wg.exclude_dirs,
+            wg.exclude_dot_dirs,
+            config.temp_files.for_watch_group(wg_id, &wg.name),
+            config.scan_limits,
+        )
+        .map_err(|e| format!("Could not scan {:?} - {e}", wg.path_to_monitor))?;
+        let ignore = SyncIgnore::load(&wg.path_to_monitor);
+        let scanned = ignore.retain(scanned);
+        let mut manifest: ManifestDto = transport
+            .http
+            .get(SysRoute::Manifest { wg_id }.to_uri(&config.server_url))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Could not fetch the manifest of {} - {e}", wg.name))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid manifest of {} - {e}", wg.name))?;
+        manifest.files.retain(|e| !ignore.is_ignored(&e.path));
+        let comparison = compare(&wg.path_to_monitor, &scanned, &manifest.files)
+            .map_err(|e| format!("Could not hash the files of {} - {e}", wg.name))?;
+
+        let mut failed = Vec::new();
+        if fix {
+            let corrections = comparison
+                .missing
+                .iter()
+                .chain(&comparison.extra)
+                .chain(&comparison.mismatched);
+            for path in corrections {
+                let local = scanned.iter().find(|d| &d.relative_path == path);
+                let remote = manifest.files.iter().find(|e| &e.path == path);
+                let Some(instruction) = correction(local, remote) else {
+                    continue;
+                };
+                if let Err(e) = execute(
+                    &transport,
+                    instruction,
+                    &wg.path_to_monitor,
+                    &config.server_url,
+                    wg_id,
+                    &config.hooks,
+                    &StatusHandle::default(),
+                )
+                .await
+                {
+                    failed.push(format!("{} - {e}", path.to_serialized_string()));
+                }
+            }
+        }
+
+        let serialized = |paths: &[MatchablePath]| {
+            paths
+                .iter()
+                .map(MatchablePath::to_serialized_string)
+                .collect()
+        };
+        verified.push(VerifyOutput {
+            watch_group_id: wg_id,
+            watch_group: wg.name.clone(),
+            path_to_monitor: wg.path_to_monitor.clone(),
+            matching: comparison.matching,
+            missing: serialized(&comparison.missing),
+            extra: serialized(&comparison.extra),
+            mismatched: serialized(&comparison.mismatched),
+            failed,
+        });
+    }
+
+    print(output, &verified, |verified| {
+        let mut lines = Vec::new();
+        for v in verified {
+            let listed = [
+                ("missing", &v.missing),
+                ("extra", &v.extra),
+                ("differs", &v.mismatched),
+            ];
+            for (label, paths) in listed {
+                lines.extend(
+                    paths
+                        .iter()
+                        .map(|p| format!("{label}: {}/{p}", v.watch_group)),
+                );
+            }
+            lines.extend(
+                v.failed
+                    .iter()
+                    .map(|f| format!("failed: {}/{f}", v.watch_group)),
+            );
+            lines.push(format!(
+                "{} ({:?}): {} matching, {} missing, {} extra, {} differing{}",
+                v.watch_group,
+                v.path_to_monitor,
+                v.matching,
+                v.missing.len(),
+                v.extra.len(),
+                v.mismatched.len(),
+                if fix { " - fixed" } else { "" }
+            ));
+        }
+        lines.join("\n")
+    });
+
+    // non-zero exit code for scripts - unless everything got fixed
+    let unresolved: usize = verified
+        .iter()
+        .map(|v| match fix {
+            true => v.failed.len(),
+            false => v.missing.len() + v.extra.len() + v.mismatched.len(),
+        })
+        .sum();
+    match unresolved {
+        0 => Ok(()),
+        n if fix => Err(format!("{n} files could not be fixed")),
+        n => Err(format!("{n} files differ from the server")),
+    }
+}
+
+/// local files are only hashed if their size matches
+fn compare(
+    root: &Path,
+    scanned: &[FileDescription],
+    manifest: &[ManifestEntryDto],
+) -> io::Result<Comparison> {
+    let local: HashMap<&MatchablePath, &FileDescription> =
+        scanned.iter().map(|d| (&d.relative_path, d)).collect();
+    let mut comparison = Comparison::default();
+    for entry in manifest {
+        match local.get(&entry.path) {
+            None => comparison.missing.push(entry.path.clone()),
+            Some(description)
+                if description.size_in_bytes == entry.size_in_bytes
+                    && sha256_of(&entry.path.resolve(root))? == entry.sha256 =>
+            {
+                comparison.matching += 1
+            }
+            Some(_) => comparison.mismatched.push(entry.path.clone()),
+        }
+    }
+    comparison.extra = scanned
+        .iter()
+        .map(|d| &d.relative_path)
+        .filter(|path| !manifest.iter().any(|e| &e.path == *path))
+        .cloned()
+        .collect();
+    for paths in [
+        &mut comparison.missing,
+        &mut comparison.extra,
+        &mut comparison.mismatched,
+    ] {
+        paths.sort_by_key(|p| p.to_serialized_string());
+    }
+    Ok(comparison)
+}
+
+/// the newer side wins - the server's on a tie (same modification time but different content is
+/// exactly what a sync can't tell apart)
+fn correction(
+    local: Option<&FileDescription>,
+    remote: Option<&ManifestEntryDto>,
+) -> Option<SyncInstruction> {
+    match (local, remote) {
+        (Some(local), None) => Some(SyncInstruction::Upload(local.relative_path.clone())),
+        (None, Some(remote)) => Some(SyncInstruction::Download(remote.path.clone())),
+        (Some(local), Some(remote)) if local.last_updated_utc_millis > remote.utc_millis => {
+            Some(SyncInstruction::Upload(local.relative_path.clone()))
+        }
+        (Some(_), Some(remote)) => Some(SyncInstruction::Download(remote.path.clone())),
+        (None, None) => None,
+    }
+}
+
+/// totals and last cycle of the daemon running with the same config.yaml
+pub async fn stats(config_path: Option<PathBuf>, output: OutputFormat) -> Result<(), String> {
+    let config = read_config(config_path)?;
+    let stats = fetch_stats(config.control_port).await?;
+    print(output, &stats, |stats| {
+        let mut lines = vec![
+            format!(
+                "running since {} - {} cycles",
+                stats.running_since, stats.cycles
+            ),
+            format!("total: {}", stats.totals),
+        ];
+        lines.extend(stats.recent.front().map(|last| format!("last:  {last}")));
+        lines.extend(stats.timings.iter().map(|(operation, t)| {
+            format!(
+                "{operation}: {} timed - p50 {}ms, p90 {}ms, p99 {}ms, max {}ms",
+                t.count, t.p50_ms, t.p90_ms, t.p99_ms, t.max_ms
+            )
+        }));
+        lines.join("\n")
+    });
+    Ok(())
+}
+
+/// battery of the daemon running with the same config.yaml and whether it pauses / throttles syncing
+pub async fn power(config_path: Option<PathBuf>, output: OutputFormat) -> Result<(), String> {
+    let config = read_config(config_path)?;
+    let power = fetch_power(config.control_port).await?;
+    print(output, &power, |power| match power {
+        Some(power) => power.describe(),
+        None => "battery not watched (power.on_low_battery: sync)".to_string(),
+    });
+    Ok(())
+}
+
+/// lists the uploads of large files the daemon holds - or decides about `path` (`<watch group>/<path>`):
+/// uploaded with its next poll cycle or added to the .syncignore (`ignore`)
+pub async fn approve(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    path: Option<&str>,
+    ignore: bool,
+) -> Result<(), String> {
+    let config = read_config(config_path)?;
+    let pending = fetch_approvals(config.control_port).await?;
+    let Some(path) = path else {
+        print(output, &pending, |pending| {
+            pending
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{}/{} ({} MB) - waiting since {}",
+                        p.watch_group,
+                        p.path,
+                        p.size_in_bytes / (1024 * 1024),
+                        p.since
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+        return Ok(());
+    };
+    let held = approval::find(&pending, path)
+        .ok_or_else(|| format!("{path} isn't waiting for approval"))?;
+    let decision = match ignore {
+        true => Decision::Ignore,
+        false => Decision::Upload,
+    };
+    if !control::decide(config.control_port, held.wg_id, &held.path, decision).await? {
+        return Err(format!("{path} isn't waiting for approval anymore"));
+    }
+    let decided = PendingApproval {
+        decision: Some(decision),
+        ..held.clone()
+    };
+    print(output, &decided, |d| match decision {
+        Decision::Upload => format!("Uploading {}/{} with the next sync", d.watch_group, d.path),
+        Decision::Ignore => format!("Ignoring {}/{} from now on", d.watch_group, d.path),
+    });
+    Ok(())
+}
+
+/// takes over the watch groups and history of the client this machine was before (e.g. before
+/// reinstalling its OS) - needs the admin token if the server has one
+pub async fn claim(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    old_id: Uuid,
+    token: Option<&str>,
+) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    // registers this client if it never ran
+    fetch_watch_config(&transport.http, &config.server_url).await;
+    let endpoint = ServerEndpoint::ApiClientClaim
+        .to_uri(&config.server_url)
+        .replace("{id}", &config.client_id.to_string());
+    let mut request = transport.http.post(endpoint).json(&ClientClaimDto {
+        old_client_id: old_id.to_string(),
+    });
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Claim failed - {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Claim failed - {} {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        ));
+    }
+    let claimed: ClientClaimedDto = response
+        .json()
+        .await
+        .map_err(|e| format!("Claim failed - {e}"))?;
+    print(output, &claimed, |claimed| {
+        format!(
+            "took over {} watch groups ({} kept as they were) and {} file events of {}",
+            claimed.watch_groups, claimed.skipped_watch_groups, claimed.file_events, old_id
+        )
+    });
+    Ok(())
+}
+
+/// json for automation, otherwise whatever `text` makes of the result
+/// outcome of `client ignore` / `client unignore`
+#[derive(Debug, Serialize)]
+struct IgnoreOutput {
+    watch_group_id: i64,
+    watch_group: String,
+    path: String,
+    ignored: bool,
+    /// false if the .syncignore said so already
+    changed: bool,
+    /// leftovers of interrupted downloads that were deleted
+    removed_partials: usize,
+}
+
+/// stops syncing `path` (a file or folder inside a watch group) via the watch group's .syncignore -
+/// it stays on disk and on the server; with `ignored = false` it's synced again next poll cycle
+pub async fn ignore(
+    config_path: Option<PathBuf>,
+    output: OutputFormat,
+    path: &Path,
+    ignored: bool,
+) -> Result<(), String> {
+    let (config, transport) = connect(config_path)?;
+    let path = absolute(path)?;
+    let watch_groups = watch_groups(&config, &transport.http).await;
+    let wg_id = containing_watch_group(&watch_groups, &path)
+        .ok_or_else(|| format!("{path:?} is not inside a watch group"))?;
+    let wg = &watch_groups[&wg_id];
+    let root = wg
+        .path_to_monitor
+        .canonicalize()
+        .map_err(|e| format!("Cannot open {:?} - {e}", wg.path_to_monitor))?;
+    let relative_path = MatchablePath::from(path.strip_prefix(&root).unwrap_or(&path));
+    if relative_path.get().is_empty() {
+        return Err(format!(
+            "{path:?} is the folder of watch group {} - it can't be ignored",
+            wg.name
+        ));
+    }
+
+    let update = match ignored {
+        true => syncignore::add(&root, &relative_path),
+        false => syncignore::remove(&root, &relative_path),
+    };
+    let changed =
+        update.map_err(|e| format!("Could not update {SYNCIGNORE_FILE} of {} - {e}", wg.name))?;
+    let mut removed_partials = 0;
+    if ignored {
+        for partial in syncignore::partial_downloads(&path) {
+            fs::remove_file(&partial).map_err(|e| format!("Cannot delete {partial:?} - {e}"))?;
+            removed_partials += 1;
+        }
+    }
+    let result = IgnoreOutput {
+        watch_group_id: wg_id,
+        watch_group: wg.name.clone(),
+        path: relative_path.to_serialized_string(),
+        ignored,
+        changed,
+        removed_partials,
+    };
+    print(output, &result, |r| {
+        let path = format!("{}/{}", r.watch_group, r.path);
+        match (r.ignored, r.changed) {
+            (true, true) if r.removed_partials > 0 => format!(
+                "Ignoring {path} - removed {} partial download(s)",
+                r.removed_partials
+            ),
+            (true, true) => format!("Ignoring {path}"),
+            (true, false) => format!("{path} is ignored already"),
+            (false, true) => format!("Syncing {path} again"),
+            (false, false) => format!("{path} wasn't ignored"),
+        }
+    });
+    Ok(())
+}
+
+pub(crate) fn print<T: Serialize>(
+    output: OutputFormat,
+    result: &T,
+    text: impl FnOnce(&T) -> String,
+) {
+    match output {
+        OutputFormat::Text => {
+            let text = text(result);
+            if !text.is_empty() {
+                println!("{text}");
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string(result).expect("output should be serializable")
+        ),
+    }
+}
+
+fn file_output(
+    watch_groups: &HashMap<i64, WatchGroup>,
+    wg_id: i64,
+    relative_path: &MatchablePath,
+    size_in_bytes: u64,
+) -> FileOutput {
+    FileOutput {
+        watch_group_id: wg_id,
+        watch_group: watch_groups
+            .get(&wg_id)
+            .map_or_else(|| wg_id.to_string(), |wg| wg.name.clone()),
+        path: relative_path.to_serialized_string(),
+        size_in_bytes,
+        sha256: None,
+        local_path: None,
+        last_updated_utc_millis: None,
+    }
+}
+
+/// canonical - also for files that don't exist (yet), e.g. a download that never finished
+fn absolute(path: &Path) -> Result<PathBuf, String> {
+    path.canonicalize()
+        .or_else(|e| {
+            let name = path.file_name().ok_or(e)?;
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            Ok(parent.canonicalize()?.join(name))
+        })
+        .map_err(|e: io::Error| format!("Cannot resolve {path:?} - {e}"))
+}
+
+fn file_size(file: &Path) -> u64 {
+    file.metadata().map(|m| m.len()).unwrap_or(0)
+}
+
+fn sha256_of(file: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(file)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+async fn watch_groups(config: &Config, client: &Client) -> HashMap<i64, WatchGroup> {
+    to_watch_group(
+        fetch_watch_config(client, &config.server_url)
+            .await
+            .watch_groups,
+    )
+}
+
+/// by id or name
+fn resolve_watch_group(watch_groups: &HashMap<i64, WatchGroup>, wg: &str) -> Result<i64, String> {
+    wg.parse::<i64>()
+        .ok()
+        .filter(|id| watch_groups.contains_key(id))
+        .or_else(|| {
+            watch_groups
+                .iter()
+                .find(|(_, group)| group.name == wg)
+                .map(|(id, _)| *id)
+        })
+        .ok_or_else(|| format!("No watch group '{wg}' assigned to this client"))
+}
+
+fn containing_watch_group(watch_groups: &HashMap<i64, WatchGroup>, file: &Path) -> Option<i64> {
+    watch_groups
+        .iter()
+        .find(|(_, wg)| {
+            wg.path_to_monitor
+                .canonicalize()
+                .is_ok_and(|root| file.starts_with(root))
+        })
+        .map(|(id, _)| *id)
+}
+
+fn single_watch_group(watch_groups: &HashMap<i64, WatchGroup>) -> Option<i64> {
+    match watch_groups.keys().collect::<Vec<_>>().as_slice() {
+        [id] => Some(**id),
+        _ => None,
+    }
+}
+
+/// `<watch group>/<path within the watch group>`
+fn split_path(
+    watch_groups: &HashMap<i64, WatchGroup>,
+    path: &str,
+) -> Result<(i64, MatchablePath), String> {
+    let mut segments = path.split('/').filter(|s| !s.is_empty());
+    let wg = segments
+        .next()
+        .ok_or("Path must start with a watch group (name or id)")?;
+    Ok((
+        resolve_watch_group(watch_groups, wg)?,
+        MatchablePath::from(segments.collect::<Vec<&str>>()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch_groups() -> HashMap<i64, WatchGroup> {
+        [(1, "docs"), (2, "photos")]
+            .into_iter()
+            .map(|(id, name)| {
+                (
+                    id,
+                    WatchGroup {
+                        name: name.to_string(),
+                        path_to_monitor: PathBuf::from(format!("/sync/{name}")),
+                        exclude_dirs: vec![],
+                        exclude_dot_dirs: true,
+                        priority: 0,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_output_format() {
+        assert_eq!(Ok(OutputFormat::Json), "JSON".parse());
+        assert_eq!(Ok(OutputFormat::Text), " text".parse());
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn should_resolve_watch_group_by_id_or_name() {
+        let watch_groups = watch_groups();
+        assert_eq!(Ok(2), resolve_watch_group(&watch_groups, "photos"));
+        assert_eq!(Ok(1), resolve_watch_group(&watch_groups, "1"));
+        assert!(resolve_watch_group(&watch_groups, "3").is_err());
+        assert!(resolve_watch_group(&watch_groups, "music").is_err());
+    }
+
+    #[test]
+    fn should_split_watch_group_from_path() {
+        let watch_groups = watch_groups();
+        let (wg_id, path) = split_path(&watch_groups, "photos/2024/a.jpg").unwrap();
+        assert_eq!(2, wg_id);
+        assert_eq!(MatchablePath::from("2024/a.jpg"), path);
+        let listed = file_output(&watch_groups, 2, &path, 7);
+        assert_eq!(
+            ("photos", "2024/a.jpg"),
+            (listed.watch_group.as_str(), listed.path.as_str())
+        );
+
+        let (wg_id, path) = split_path(&watch_groups, "/docs/").unwrap();
+        assert_eq!(1, wg_id);
+        assert!(path.get().is_empty());
+        assert!(split_path(&watch_groups, "").is_err());
+    }
+
+    #[test]
+    fn should_seed_matching_files_as_synced_and_others_as_stale() {
+        let root = std::env::temp_dir().join("rfs_test_seed");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let file = root.join("a.txt");
+        fs::write(&file, b"hello").unwrap();
+        let entry = ManifestEntryDto {
+            path: MatchablePath::from("a.txt"),
+            size_in_bytes: 5,
+            sha256: sha256_of(&file).unwrap(),
+            utc_millis: UtcMillis::from(1_700_000_000_000),
+        };
+        let modified = || UtcMillis::from(file.metadata().unwrap().modified().unwrap()).as_u64();
+
+        assert_eq!(Seeded::Matching, seed_file(&file, &entry, true).unwrap());
+        assert_ne!(1_700_000_000_000, modified());
+        assert_eq!(Seeded::Matching, seed_file(&file, &entry, false).unwrap());
+        assert_eq!(1_700_000_000_000, modified());
+
+        fs::write(&file, b"world").unwrap();
+        assert_eq!(Seeded::Stale, seed_file(&file, &entry, false).unwrap());
+        assert_eq!(1_699_999_999_999, modified());
+
+        let missing = root.join("b.txt");
+        assert_eq!(Seeded::Missing, seed_file(&missing, &entry, false).unwrap());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn description(path: &str, size_in_bytes: u64, utc_millis: u64) -> FileDescription {
+        let relative_path = MatchablePath::from(path);
+        FileDescription {
+            file_name: relative_path.tail(),
+            relative_path,
+            size_in_bytes,
+            file_type: ".txt".to_string(),
+            last_updated_utc_millis: UtcMillis::from(utc_millis),
+        }
+    }
+
+    fn manifest_entry(path: &str, content: &[u8], utc_millis: u64) -> ManifestEntryDto {
+        ManifestEntryDto {
+            path: MatchablePath::from(path),
+            size_in_bytes: content.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(content)),
+            utc_millis: UtcMillis::from(utc_millis),
+        }
+    }
+
+    #[test]
+    fn should_compare_local_files_with_manifest() {
+        let root = std::env::temp_dir().join("rfs_test_verify");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::write(root.join("same.txt"), b"hello").unwrap();
+        // same size, different content - invisible to a sync
+        fs::write(root.join("dir/flipped.txt"), b"hellO").unwrap();
+        fs::write(root.join("local.txt"), b"new").unwrap();
+        let scanned = [
+            description("same.txt", 5, 1),
+            description("dir/flipped.txt", 5, 1),
+            description("local.txt", 3, 1),
+        ];
+        let manifest = [
+            manifest_entry("same.txt", b"hello", 1),
+            manifest_entry("dir/flipped.txt", b"hello", 1),
+            manifest_entry("remote.txt", b"server", 1),
+        ];
+
+        assert_eq!(
+            Comparison {
+                matching: 1,
+                missing: vec![MatchablePath::from("remote.txt")],
+                extra: vec![MatchablePath::from("local.txt")],
+                mismatched: vec![MatchablePath::from("dir/flipped.txt")],
+            },
+            compare(&root, &scanned, &manifest).unwrap()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn should_correct_towards_the_newer_side() {
+        let kind = |instruction: Option<SyncInstruction>| match instruction {
+            Some(SyncInstruction::Upload(p)) => format!("upload {}", p.to_serialized_string()),
+            Some(SyncInstruction::Download(p)) => format!("download {}", p.to_serialized_string()),
+            Some(SyncInstruction::Delete(p)) => format!("delete {}", p.to_serialized_string()),
+            None => "nothing".to_string(),
+        };
+        let local = description("a.txt", 5, 10);
+        let older = manifest_entry("a.txt", b"older", 9);
+        let tie = manifest_entry("a.txt", b"tie..", 10);
+        let newer = manifest_entry("a.txt", b"newer", 11);
+
+        assert_eq!("upload a.txt", kind(correction(Some(&local), None)));
+        assert_eq!("upload a.txt", kind(correction(Some(&local), Some(&older))));
+        assert_eq!("download a.txt", kind(correction(None, Some(&older))));
+        assert_eq!("download a.txt", kind(correction(Some(&local), Some(&tie))));
+        assert_eq!(
+            "download a.txt",
+            kind(correction(Some(&local), Some(&newer)))
+        );
+        assert_eq!("nothing", kind(correction(None, None)));
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+/// poll interval doubling with every idle cycle in a row - from the minimum of the server up to
+/// the maximum of the config.yaml
+#[derive(Debug, Default)]
+pub struct AdaptiveInterval {
+    idle_cycles: u32,
+}
+
+impl AdaptiveInterval {
+    pub fn record_cycle(&mut self, idle: bool) {
+        match idle {
+            true => self.idle_cycles = self.idle_cycles.saturating_add(1),
+            false => self.reset(),
+        }
+    }
+
+    /// back to the minimum right away (e.g. a sync was requested)
+    pub fn reset(&mut self) {
+        self.idle_cycles = 0;
+    }
+
+    /// a maximum below the minimum turns the backoff off
+    pub fn current(&self, min_ms: u64, max_ms: u64) -> Duration {
+        let factor = 1u64 << self.idle_cycles.min(32);
+        Duration::from_millis(min_ms.saturating_mul(factor).min(max_ms.max(min_ms)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_back_off_while_idle_and_reset_on_activity() {
+        let mut interval = AdaptiveInterval::default();
+        assert_eq!(Duration::from_secs(3), interval.current(3000, 60_000));
+
+        interval.record_cycle(true);
+        interval.record_cycle(true);
+        assert_eq!(Duration::from_secs(12), interval.current(3000, 60_000));
+
+        (0..100).for_each(|_| interval.record_cycle(true));
+        assert_eq!(Duration::from_secs(60), interval.current(3000, 60_000));
+
+        interval.record_cycle(false);
+        assert_eq!(Duration::from_secs(3), interval.current(3000, 60_000));
+    }
+
+    #[test]
+    fn should_not_back_off_if_max_is_below_min() {
+        let mut interval = AdaptiveInterval::default();
+        interval.record_cycle(true);
+        assert_eq!(Duration::from_secs(3), interval.current(3000, 0));
+    }
+}
@@ -0,0 +1,239 @@
+// POWER -----------------------------------------------------------------------
+//
+// laptops running on a draining battery - below `low_battery_percent` the sync loop pauses or polls
+// less often (the `power:` section of the config.yaml) until it's plugged in again
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::command::output;
+use crate::status::StatusHandle;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LowBatteryAction {
+    /// battery doesn't matter
+    #[default]
+    Sync,
+    Pause,
+    /// poll every `throttled_poll_interval_in_ms` at most
+    Throttle,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    pub on_low_battery: LowBatteryAction,
+    pub low_battery_percent: u8,
+    pub throttled_poll_interval_in_ms: u64,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            on_low_battery: LowBatteryAction::Sync,
+            low_battery_percent: 30,
+            throttled_poll_interval_in_ms: 300_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Battery {
+    /// false while on AC power
+    pub discharging: bool,
+    pub percent: Option<u8>,
+}
+
+/// served on the control port (`client status --power`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerState {
+    /// None on machines without (detectable) battery
+    pub battery: Option<Battery>,
+    /// what the sync loop does about it right now
+    pub action: LowBatteryAction,
+}
+
+impl PowerState {
+    pub fn describe(&self) -> String {
+        let power = match self.battery {
+            None => "no battery".to_string(),
+            Some(Battery {
+                discharging: false, ..
+            }) => "on AC power".to_string(),
+            Some(Battery {
+                discharging: true,
+                percent: Some(percent),
+            }) => format!("on battery ({percent}%)"),
+            Some(Battery {
+                discharging: true,
+                percent: None,
+            }) => "on battery".to_string(),
+        };
+        let action = match self.action {
+            LowBatteryAction::Sync => "syncing",
+            LowBatteryAction::Pause => "sync paused",
+            LowBatteryAction::Throttle => "sync throttled",
+        };
+        format!("{power} - {action}")
+    }
+}
+
+/// what the low battery means for the sync loop - unknown charge levels don't count as low
+pub fn action(config: &PowerConfig, battery: Option<Battery>) -> LowBatteryAction {
+    match battery {
+        Some(Battery {
+            discharging: true,
+            percent: Some(percent),
+        }) if percent < config.low_battery_percent => config.on_low_battery,
+        _ => LowBatteryAction::Sync,
+    }
+}
+
+/// reads the battery (only if the config cares) and keeps the status up to date - logs when the
+/// action changes
+pub async fn check(config: &PowerConfig, status: &StatusHandle) -> LowBatteryAction {
+    if config.on_low_battery == LowBatteryAction::Sync {
+        return LowBatteryAction::Sync;
+    }
+    let battery = read_battery().await;
+    debug!(?battery, "Read battery");
+    let state = PowerState {
+        battery,
+        action: action(config, battery),
+    };
+    let before = status.read(|s| s.power.map(|p| p.action));
+    if before.unwrap_or_default() != state.action {
+        match state.action {
+            LowBatteryAction::Sync => info!("Power: {} - resuming sync", state.describe()),
+            _ => warn!("Low battery: {}", state.describe()),
+        }
+    }
+    status.update(|s| s.power = Some(state));
+    state.action
+}
+
+async fn read_battery() -> Option<Battery> {
+    if cfg!(target_os = "linux") {
+        let mut supplies = tokio::fs::read_dir("/sys/class/power_supply").await.ok()?;
+        while let Ok(Some(supply)) = supplies.next_entry().await {
+            let read = |file: &str| tokio::fs::read_to_string(supply.path().join(file));
+            if read("type").await.is_ok_and(|t| t.trim() == "Battery") {
+                let status = read("status").await.unwrap_or_default();
+                let capacity = read("capacity").await.unwrap_or_default();
+                return Some(parse_sysfs(&status, &capacity));
+            }
+        }
+        None
+    } else if cfg!(target_os = "macos") {
+        parse_pmset(&output("pmset", &["-g", "batt"]).await?)
+    } else if cfg!(windows) {
+        let command = "Get-CimInstance Win32_Battery | Select-Object -First 1 | \
+                       ForEach-Object { \"$($_.BatteryStatus) $($_.EstimatedChargeRemaining)\" }";
+        parse_win32_battery(&output("powershell", &["-NoProfile", "-Command", command]).await?)
+    } else {
+        None
+    }
+}
+
+/// `status` and `capacity` of a battery below /sys/class/power_supply (Linux)
+fn parse_sysfs(status: &str, capacity: &str) -> Battery {
+    Battery {
+        discharging: status.trim() == "Discharging",
+        percent: capacity.trim().parse().ok(),
+    }
+}
+
+/// `pmset -g batt` (macOS)
+fn parse_pmset(output: &str) -> Option<Battery> {
+    let battery = output
+        .lines()
+        .find(|line| line.contains("InternalBattery"))?;
+    let percent = battery
+        .split_whitespace()
+        .find_map(|word| word.strip_suffix("%;"))
+        .and_then(|percent| percent.parse().ok());
+    Some(Battery {
+        discharging: output.contains("'Battery Power'"),
+        percent,
+    })
+}
+
+/// `<BatteryStatus> <EstimatedChargeRemaining>` of Win32_Battery - status 1 is discharging
+fn parse_win32_battery(output: &str) -> Option<Battery> {
+    let (status, percent) = output.trim().split_once(' ')?;
+    Some(Battery {
+        discharging: status == "1",
+        percent: percent.parse().ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_act_only_on_low_battery() {
+        let config = PowerConfig {
+            on_low_battery: LowBatteryAction::Pause,
+            ..PowerConfig::default()
+        };
+        let battery = |discharging, percent| {
+            Some(Battery {
+                discharging,
+                percent,
+            })
+        };
+
+        assert_eq!(
+            LowBatteryAction::Pause,
+            action(&config, battery(true, Some(29)))
+        );
+        assert_eq!(
+            LowBatteryAction::Sync,
+            action(&config, battery(true, Some(30)))
+        );
+        assert_eq!(
+            LowBatteryAction::Sync,
+            action(&config, battery(false, Some(5)))
+        );
+        assert_eq!(LowBatteryAction::Sync, action(&config, battery(true, None)));
+        assert_eq!(LowBatteryAction::Sync, action(&config, None));
+    }
+
+    #[test]
+    fn should_parse_platform_outputs() {
+        assert_eq!(
+            Battery {
+                discharging: true,
+                percent: Some(42)
+            },
+            parse_sysfs("Discharging\n", "42\n")
+        );
+        assert_eq!(
+            Some(Battery {
+                discharging: true,
+                percent: Some(85)
+            }),
+            parse_pmset(
+                "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t85%; discharging; 4:20 remaining present: true\n"
+            )
+        );
+        assert_eq!(
+            Some(false),
+            parse_pmset(
+                "Now drawing from 'AC Power'\n -InternalBattery-0 (id=1234)\t100%; charged;\n"
+            )
+            .map(|b| b.discharging)
+        );
+        assert_eq!(None, parse_pmset("Now drawing from 'AC Power'\n"));
+        assert_eq!(
+            Some(Battery {
+                discharging: false,
+                percent: Some(97)
+            }),
+            parse_win32_battery("2 97\r\n")
+        );
+        assert_eq!(None, parse_win32_battery(""));
+    }
+}
@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+use crate::WatchGroup;
+
+/// watch groups in the order they get synced - highest priority first, ties by id
+///
+/// transfers run one after another, so the first watch group gets the connection until all of its
+/// instructions are done (e.g. documents before videos on a slow link)
+pub fn sync_order(watch_groups: &HashMap<i64, WatchGroup>) -> Vec<i64> {
+    let mut ids: Vec<i64> = watch_groups.keys().copied().collect();
+    ids.sort_by_key(|id| (std::cmp::Reverse(watch_groups[id].priority), *id));
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_order_by_priority_then_id() {
+        let watch_groups = [(1, 0), (2, 10), (3, 0), (4, -5), (5, 10)]
+            .into_iter()
+            .map(|(id, priority)| {
+                (
+                    id,
+                    WatchGroup {
+                        name: format!("wg{id}"),
+                        path_to_monitor: PathBuf::from(format!("/sync/{id}")),
+                        exclude_dirs: vec![],
+                        exclude_dot_dirs: true,
+                        priority,
+                    },
+                )
+            })
+            .collect();
+        assert_eq!(vec![2, 5, 1, 3, 4], sync_order(&watch_groups));
+    }
+}
@@ -0,0 +1,315 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::{Buf, Bytes};
+use futures_util::StreamExt;
+use futures_util::future::poll_fn;
+use futures_util::stream;
+use h3::client::SendRequest;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, HeaderMap};
+use reqwest::{Method, StatusCode, Url};
+use shared::dtos::FileDescription;
+use shared::endpoint::{CONFLICT_KEPT_HEADER_KEY, SysRoute};
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use tokio::fs::File;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::status::TransferGuard;
+use crate::transport::{
+    Download, Resume, client_host_of, etag_of, progress_stream, served_range, utc_millis_of,
+};
+
+/// QUIC needs a reply within this time - otherwise the transfer goes over http
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// no new attempts in between - every transfer would wait for the timeout otherwise
+const RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+type Connection = SendRequest<h3_quinn::OpenStreams, Bytes>;
+
+/// uploads / downloads over HTTP/3 - [`Http3Client::connection`] is `None` if the server can't
+/// be reached that way, the caller falls back to http then
+pub struct Http3Client {
+    host: String,
+    port: u16,
+    /// default headers of the http client (client id / hostname)
+    headers: HeaderMap,
+    tls: quinn::ClientConfig,
+    state: Mutex<ConnectionState>,
+}
+
+#[derive(Default)]
+struct ConnectionState {
+    /// quinn handle to tell if the connection is still alive
+    connection: Option<(quinn::Connection, Connection)>,
+    unreachable_until: Option<Instant>,
+}
+
+impl Http3Client {
+    pub fn new(server_url: &str, headers: HeaderMap) -> Result<Self, String> {
+        let url = Url::parse(server_url).map_err(|e| format!("Invalid server_url - {e}"))?;
+        if url.scheme() != "https" {
+            return Err("transport http3 needs an https server_url".to_string());
+        }
+        let host = url
+            .host_str()
+            .ok_or("server_url without host")?
+            .trim_matches(['[', ']'])
+            .to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+
+        let mut roots = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+        let mut tls = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(|e| format!("Could not set up tls for http3 - {e}"))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        tls.alpn_protocols = vec![b"h3".to_vec()];
+        let quic = quinn::crypto::rustls::QuicClientConfig::try_from(tls)
+            .map_err(|e| format!("Could not set up tls for http3 - {e}"))?;
+
+        Ok(Http3Client {
+            host,
+            port,
+            headers,
+            tls: quinn::ClientConfig::new(Arc::new(quic)),
+            state: Mutex::new(ConnectionState::default()),
+        })
+    }
+
+    /// the open connection or a new one - `None` while the server is unreachable via QUIC
+    pub async fn connection(&self) -> Option<Connection> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state
+                .unreachable_until
+                .is_some_and(|until| until > Instant::now())
+            {
+                return None;
+            }
+            match &state.connection {
+                Some((quic, connection)) if quic.close_reason().is_none() => {
+                    return Some(connection.clone());
+                }
+                Some(_) => state.connection = None,
+                None => {}
+            }
+        }
+        let connected = tokio::time::timeout(CONNECT_TIMEOUT, self.connect())
+            .await
+            .unwrap_or_else(|_| Err("timeout".to_string()));
+        match connected {
+            Ok((quic, connection)) => {
+                let mut state = self.state.lock().unwrap();
+                state.connection = Some((quic, connection.clone()));
+                state.unreachable_until = None;
+                Some(connection)
+            }
+            Err(error) => {
+                warn!(
+                    "HTTP/3 unreachable ({error}) - transfers use http for the next {}",
+                    humantime::format_duration(RETRY_AFTER)
+                );
+                self.state.lock().unwrap().unreachable_until = Some(Instant::now() + RETRY_AFTER);
+                None
+            }
+        }
+    }
+
+    async fn connect(&self) -> Result<(quinn::Connection, Connection), String> {
+        let addr = tokio::net::lookup_host((self.host.as_str(), self.port))
+            .await
+            .map_err(|e| e.to_string())?
+            .next()
+            .ok_or_else(|| format!("{} not resolvable", self.host))?;
+        let bind = match addr {
+            SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+            SocketAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+        };
+        let endpoint = quinn::Endpoint::client(bind).map_err(|e| e.to_string())?;
+        let quic = endpoint
+            .connect_with(self.tls.clone(), addr, &self.host)
+            .map_err(|e| e.to_string())?
+            .await
+            .map_err(|e| e.to_string())?;
+        let (mut driver, connection) = h3::client::new(h3_quinn::Connection::new(quic.clone()))
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::spawn(async move {
+            let reason = poll_fn(|cx| driver.poll_close(cx)).await;
+            debug!("HTTP/3 connection closed - {reason}");
+        });
+        info!("HTTP/3 connected to {addr}");
+        Ok((quic, connection))
+    }
+
+    /// same multipart request as the http upload (to `uri`) - status + reply of the server
+    pub async fn upload(
+        &self,
+        mut connection: Connection,
+        uri: String,
+        description: &FileDescription,
+        file: File,
+        transfer: TransferGuard,
+        conflict_kept: Option<&UtcMillis>,
+    ) -> Result<(StatusCode, String), String> {
+        let boundary = Uuid::new_v4().simple().to_string();
+        let (head, tail) = multipart_frame(&boundary, description);
+        let mut request = self
+            .request(Method::POST, uri)
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .header(
+                CONTENT_LENGTH,
+                head.len() as u64 + description.size_in_bytes + tail.len() as u64,
+            );
+        if let Some(kept) = conflict_kept {
+            request = request.header(CONFLICT_KEPT_HEADER_KEY, kept.as_u64());
+        }
+        let request = request
+            .body(())
+            .map_err(|e| format!("Upload failed - {e}"))?;
+        let mut stream = connection
+            .send_request(request)
+            .await
+            .map_err(|e| format!("Upload failed - {e}"))?;
+
+        let mut chunks = stream::once(async { Ok(head) })
+            .chain(progress_stream(file, transfer).map(|chunk| chunk.map(Bytes::from)))
+            .chain(stream::once(async { Ok(tail) }))
+            .boxed();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.map_err(|e| format!("Upload failed - cannot read file - {e}"))?;
+            stream
+                .send_data(chunk)
+                .await
+                .map_err(|e| format!("Upload failed - {e}"))?;
+        }
+        stream
+            .finish()
+            .await
+            .map_err(|e| format!("Upload failed - {e}"))?;
+
+        let status = stream
+            .recv_response()
+            .await
+            .map_err(|e| format!("Upload failed - {e}"))?
+            .status();
+        let mut reply = Vec::new();
+        while let Some(mut data) = stream
+            .recv_data()
+            .await
+            .map_err(|e| format!("Upload failed - {e}"))?
+        {
+            reply.extend_from_slice(&data.copy_to_bytes(data.remaining()));
+        }
+        Ok((status, String::from_utf8_lossy(&reply).to_string()))
+    }
+
+    /// same as the http download - see [`Transport::download`](crate::transport::Transport::download)
+    pub async fn download(
+        &self,
+        mut connection: Connection,
+        base: &str,
+        wg_id: i64,
+        path: &MatchablePath,
+        resume: Option<&Resume>,
+    ) -> Result<Download, String> {
+        let mut resume = resume;
+        let (response, stream) = loop {
+            let mut request = self.request(Method::GET, SysRoute::Download { wg_id }.to_uri(base));
+            if let Some(resume) = resume {
+                for (key, value) in &resume.headers() {
+                    request = request.header(key, value);
+                }
+            }
+            let request = request.body(()).map_err(|e| e.to_string())?;
+            let mut stream = connection
+                .send_request(request)
+                .await
+                .map_err(|e| e.to_string())?;
+            stream
+                .send_data(Bytes::from(path.to_serialized_string()))
+                .await
+                .map_err(|e| e.to_string())?;
+            stream.finish().await.map_err(|e| e.to_string())?;
+
+            let response = stream.recv_response().await.map_err(|e| e.to_string())?;
+            // the earlier attempt already got everything - start over
+            if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && resume.is_some() {
+                resume = None;
+                continue;
+            }
+            break (response, stream);
+        };
+        let content_length = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let body = stream::unfold(stream, |mut stream| async move {
+            match stream.recv_data().await {
+                Ok(Some(mut data)) => {
+                    Some((Ok(data.copy_to_bytes(data.remaining()).to_vec()), stream))
+                }
+                Ok(None) => None,
+                Err(e) => Some((Err(e.to_string()), stream)),
+            }
+        });
+        if !response.status().is_success() {
+            let reply = body.filter_map(|chunk| async { chunk.ok() }).concat().await;
+            return Err(format!(
+                "{} - {}",
+                response.status(),
+                String::from_utf8_lossy(&reply)
+            ));
+        }
+        let (offset, size) = served_range(response.status(), response.headers(), content_length)?;
+        Ok(Download {
+            offset,
+            size,
+            etag: etag_of(response.headers()),
+            utc_millis: utc_millis_of(response.headers()),
+            client_host: client_host_of(response.headers()),
+            body: Box::pin(body),
+        })
+    }
+
+    fn request(&self, method: Method, uri: String) -> http::request::Builder {
+        let mut request = http::Request::builder().method(method).uri(uri);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        request
+    }
+}
+
+/// everything of the multipart body but the file content
+fn multipart_frame(boundary: &str, description: &FileDescription) -> (Bytes, Bytes) {
+    let utc_millis = serde_json::to_string(&description.last_updated_utc_millis).unwrap();
+    let relative_path = description.relative_path.to_serialized_string();
+    let file_name = description
+        .file_name
+        .replace('"', "%22")
+        .replace(['\r', '\n'], "");
+    let head = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"utc_millis\"\r\n\r\n{utc_millis}\r\n\
+         --{boundary}\r\nContent-Disposition: form-data; name=\"relative_path\"\r\n\r\n{relative_path}\r\n\
+         --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n"
+    );
+    (
+        Bytes::from(head),
+        Bytes::from(format!("\r\n--{boundary}--\r\n")),
+    )
+}
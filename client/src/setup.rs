@@ -4,7 +4,8 @@ use std::{collections::HashMap, path::PathBuf, process::Command, thread::sleep,
 
 use crate::{
     ClientState, WatchGroup,
-    config::{self, fetch_watch_config},
+    config::{self, Config, ProxyConfig, TransportKind, fetch_watch_config},
+    transport::Transport,
 };
 use reqwest::{
     Client,
@@ -17,22 +18,18 @@ use shared::{
 use tracing::{info, warn};
 use uuid::Uuid;
 
-pub async fn setup() -> (ClientState, Client) {
-    let config = match config::read_config() {
+pub async fn setup(config_path: Option<PathBuf>) -> (ClientState, Transport) {
+    let config = match config::read_config(config_path) {
         Ok(config) => config,
         Err(error) => panic!("Config could not be processed: {:?}", error),
     };
 
-    check_server_reachable(&config.server_url).await;
+    check_server_reachable(&config.server_url, &config.proxy).await;
 
-    let hostname = Command::new("hostname")
-        .output()
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-        .ok();
-
-    let client = build_http_client(&hostname, &config.client_id);
+    let hostname = hostname();
+    let transport = build_transport(&config, &hostname).unwrap_or_else(|e| panic!("{e}"));
 
-    let watch_config = fetch_watch_config(&client, &config.server_url).await;
+    let watch_config = fetch_watch_config(&transport.http, &config.server_url).await;
 
     watch_config.watch_groups.values().for_each(|wg| {
         info!(
@@ -46,14 +43,53 @@ pub async fn setup() -> (ClientState, Client) {
     });
 
     info!("Poll_interval={}ms", watch_config.min_poll_interval_in_ms);
+    info!("Transport={:?}", config.transport);
 
     (
         ClientState {
+            client_id: config.client_id,
             server_url: config.server_url,
             min_poll_interval_in_ms: watch_config.min_poll_interval_in_ms,
             watch_groups: to_watch_group(watch_config.watch_groups),
+            bulk_upload: config.bulk_upload,
+            hooks: config.hooks,
+            control_port: config.control_port,
+            max_poll_interval_in_ms: config.max_poll_interval_in_ms,
+            temp_files: config.temp_files,
+            scan_limits: config.scan_limits,
+            conflict_policy: config.conflict_policy,
+            large_files: config.large_files,
+            min_free_disk_mb: config.min_free_disk_mb,
+            bandwidth_cap: config.bandwidth_cap,
+            networks: config.networks,
+            power: config.power,
+            slow_instruction_ms: config.slow_instruction_ms,
         },
-        client,
+        transport,
+    )
+}
+
+/// config + transport without waiting for the server - for the one-shot commands, which
+/// should fail fast instead of retrying
+pub fn connect(config_path: Option<PathBuf>) -> Result<(Config, Transport), String> {
+    let config = config::read_config(config_path)?;
+    let transport = build_transport(&config, &hostname())?;
+    Ok((config, transport))
+}
+
+fn build_transport(config: &Config, hostname: &Option<String>) -> Result<Transport, String> {
+    let headers = client_headers(hostname, &config.client_id);
+    if config.proxy.is_set() && config.transport != TransportKind::Http {
+        warn!(
+            "Proxy only applies to plain http - {:?} transfers connect directly",
+            config.transport
+        );
+    }
+    Transport::new(
+        config.transport,
+        &config.server_url,
+        build_http_client(headers.clone(), &config.proxy)?,
+        &headers,
     )
 }
 
@@ -69,13 +105,22 @@ pub fn to_watch_group(
                     path_to_monitor: PathBuf::from(value.path_to_monitor),
                     exclude_dirs: value.exclude_dirs,
                     exclude_dot_dirs: value.exclude_dot_dirs,
+                    priority: value.priority,
                 },
             )
         })
         .collect()
 }
 
-fn build_http_client(hostname: &Option<String>, client_id: &Uuid) -> Client {
+fn hostname() -> Option<String> {
+    Command::new("hostname")
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .ok()
+}
+
+/// sent with every request - so the server knows who's syncing
+fn client_headers(hostname: &Option<String>, client_id: &Uuid) -> HeaderMap {
     let mut headers = HeaderMap::new();
     if let Some(h) = hostname {
         headers.insert(
@@ -87,18 +132,22 @@ fn build_http_client(hostname: &Option<String>, client_id: &Uuid) -> Client {
         CLIENT_ID_HEADER_KEY,
         HeaderValue::from_str(&client_id.to_string()).expect("Invalid client_id for header"),
     );
-    Client::builder()
-        .default_headers(headers)
+    headers
+}
+
+fn build_http_client(headers: HeaderMap, proxy: &ProxyConfig) -> Result<Client, String> {
+    proxy
+        .apply(Client::builder().default_headers(headers))?
         .build()
-        .expect("Failed to build HTTP client")
+        .map_err(|e| format!("Failed to build HTTP client - {e}"))
 }
 
-async fn check_server_reachable(server_url: &str) {
+async fn check_server_reachable(server_url: &str, proxy: &ProxyConfig) {
     let hello_endpoint = ServerEndpoint::Ping.to_uri(server_url);
-    let client = Client::builder()
-        .timeout(Duration::from_secs(1))
-        .build()
-        .unwrap();
+    let client = proxy
+        .apply(Client::builder().timeout(Duration::from_secs(1)))
+        .and_then(|builder| builder.build().map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| panic!("{e}"));
     info!("Testing server at '{}'", &hello_endpoint);
 
     let mut confirmed_availablity = false;
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use shared::dtos::TimingPercentilesDto;
+use shared::utc_millis::UtcMillis;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use crate::timing::Operation;
+
+/// how many cycles are kept around for `client status --stats`
+const MAX_RECENT_CYCLES: usize = 100;
+
+/// percentiles are taken over the latest this many instructions per operation
+const MAX_TIMING_SAMPLES: usize = 1000;
+
+/// what one poll cycle did - or, summed up, all cycles since the daemon started
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CycleStats {
+    pub files_scanned: u64,
+    pub bytes_uploaded: u64,
+    pub bytes_downloaded: u64,
+    pub instructions_executed: u64,
+    /// local deletes reported to the server
+    pub deletes_sent: u64,
+    pub errors: u64,
+    pub duration_ms: u64,
+}
+
+impl CycleStats {
+    /// nothing was transferred and nothing went wrong
+    pub fn is_idle(&self) -> bool {
+        self.instructions_executed == 0
+            && self.deletes_sent == 0
+            && self.errors == 0
+            && self.bytes_uploaded == 0
+            && self.bytes_downloaded == 0
+    }
+
+    fn add(&mut self, other: &CycleStats) {
+        self.files_scanned += other.files_scanned;
+        self.bytes_uploaded += other.bytes_uploaded;
+        self.bytes_downloaded += other.bytes_downloaded;
+        self.instructions_executed += other.instructions_executed;
+        self.deletes_sent += other.deletes_sent;
+        self.errors += other.errors;
+        self.duration_ms += other.duration_ms;
+    }
+}
+
+impl Display for CycleStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files scanned, {} instructions, {} deletes sent, {} B up, {} B down, {} errors in {}ms",
+            self.files_scanned,
+            self.instructions_executed,
+            self.deletes_sent,
+            self.bytes_uploaded,
+            self.bytes_downloaded,
+            self.errors,
+            self.duration_ms
+        )
+    }
+}
+
+/// rolling window of the cycles of the running daemon - served on the control port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncStats {
+    pub running_since: UtcMillis,
+    pub cycles: u64,
+    pub totals: CycleStats,
+    /// newest first
+    pub recent: VecDeque<CycleStats>,
+    /// transfer times of the executed instructions - refreshed with every finished cycle
+    #[serde(default)]
+    pub timings: BTreeMap<Operation, TimingPercentilesDto>,
+    #[serde(skip)]
+    timing_samples: BTreeMap<Operation, VecDeque<u64>>,
+    /// the cycle in progress - counted into the others by [`SyncStats::finish_cycle`]
+    #[serde(skip)]
+    pub current: CycleStats,
+}
+
+impl Default for SyncStats {
+    fn default() -> Self {
+        Self {
+            running_since: UtcMillis::now(),
+            cycles: 0,
+            totals: CycleStats::default(),
+            recent: VecDeque::new(),
+            timings: BTreeMap::new(),
+            timing_samples: BTreeMap::new(),
+            current: CycleStats::default(),
+        }
+    }
+}
+
+impl SyncStats {
+    /// closes the cycle in progress and returns it
+    pub fn finish_cycle(&mut self, duration: Duration) -> CycleStats {
+        let mut cycle = std::mem::take(&mut self.current);
+        cycle.duration_ms = duration.as_millis() as u64;
+        self.cycles += 1;
+        self.totals.add(&cycle);
+        self.recent.push_front(cycle);
+        self.recent.truncate(MAX_RECENT_CYCLES);
+        self.timings = self
+            .timing_samples
+            .iter()
+            .map(|(operation, samples)| (*operation, TimingPercentilesDto::of(samples.clone())))
+            .collect();
+        cycle
+    }
+
+    pub fn record_timing(&mut self, operation: Operation, duration: Duration) {
+        let samples = self.timing_samples.entry(operation).or_default();
+        if samples.len() == MAX_TIMING_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(duration.as_millis() as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_sum_up_finished_cycles() {
+        let mut stats = SyncStats::default();
+        stats.current.files_scanned = 10;
+        stats.current.bytes_uploaded = 100;
+        let first = stats.finish_cycle(Duration::from_millis(20));
+        assert_eq!(10, first.files_scanned);
+        assert_eq!(20, first.duration_ms);
+        assert!(!first.is_idle());
+
+        stats.current.files_scanned = 10;
+        assert!(stats.finish_cycle(Duration::from_millis(5)).is_idle());
+
+        assert_eq!(2, stats.cycles);
+        assert_eq!(20, stats.totals.files_scanned);
+        assert_eq!(100, stats.totals.bytes_uploaded);
+        assert_eq!(25, stats.totals.duration_ms);
+        assert_eq!(CycleStats::default(), stats.current);
+    }
+
+    #[test]
+    fn should_keep_bounded_number_of_cycles() {
+        let mut stats = SyncStats::default();
+        (0..MAX_RECENT_CYCLES + 10).for_each(|_| {
+            stats.finish_cycle(Duration::ZERO);
+        });
+        assert_eq!(MAX_RECENT_CYCLES, stats.recent.len());
+        assert_eq!((MAX_RECENT_CYCLES + 10) as u64, stats.cycles);
+    }
+
+    #[test]
+    fn should_take_timings_with_the_finished_cycle() {
+        let mut stats = SyncStats::default();
+        (1..=MAX_TIMING_SAMPLES as u64 + 1).for_each(|ms| {
+            stats.record_timing(Operation::Upload, Duration::from_millis(ms));
+        });
+        assert!(stats.timings.is_empty());
+
+        stats.finish_cycle(Duration::ZERO);
+        let upload = stats.timings[&Operation::Upload];
+        assert_eq!(MAX_TIMING_SAMPLES, upload.count);
+        assert_eq!(MAX_TIMING_SAMPLES as u64 + 1, upload.max_ms);
+        assert!(!stats.timings.contains_key(&Operation::Download));
+    }
+}
@@ -0,0 +1,237 @@
+use shared::utc_millis::UtcMillis;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::approval::PendingApproval;
+use crate::power::PowerState;
+use crate::stats::{CycleStats, SyncStats};
+
+/// how many errors / conflicts are kept around for display
+const MAX_RECENT_ENTRIES: usize = 50;
+
+/// live picture of the sync loop - written by the loop, read by the tui
+#[derive(Default)]
+pub struct SyncStatus {
+    pub watch_groups: BTreeMap<i64, WatchGroupStatus>,
+    pub transfers: BTreeMap<u64, Transfer>,
+    /// newest first
+    pub recent_errors: VecDeque<(UtcMillis, String)>,
+    /// uploads the server refused because it holds a newer version (newest first)
+    pub conflicts: VecDeque<(UtcMillis, String)>,
+    pub paused: bool,
+    /// why the current network isn't allowed to sync on (None = allowed)
+    pub network_blocked: Option<String>,
+    /// None unless the config.yaml pauses or throttles syncing on low battery
+    pub power: Option<PowerState>,
+    pub stats: SyncStats,
+    /// uploads of large new files waiting for `client approve`
+    pub pending_approvals: Vec<PendingApproval>,
+    /// (watch group, path) of approved large files - uploaded like any other file from now on
+    pub approved: HashSet<(i64, String)>,
+    next_transfer_id: u64,
+}
+
+#[derive(Default)]
+pub struct WatchGroupStatus {
+    pub name: String,
+    pub file_count: usize,
+    pub last_scan: Option<UtcMillis>,
+    pub last_instruction_count: usize,
+    pub last_error: Option<String>,
+    /// an admin paused the client (or this watch group) on the server
+    pub paused_by_server: bool,
+    /// scan problem the server knows about (None = nothing reported yet)
+    pub reported_scan_warning: Option<Option<String>>,
+    /// downloads of the last poll cycle were deferred - reported along with the scan warning
+    pub disk_space_warning: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+pub struct Transfer {
+    pub wg_id: i64,
+    pub path: String,
+    pub direction: TransferDirection,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+impl Transfer {
+    /// 0.0 - 1.0 (unknown totals count as done)
+    pub fn ratio(&self) -> f64 {
+        if self.bytes_total == 0 {
+            1.0
+        } else {
+            (self.bytes_done as f64 / self.bytes_total as f64).min(1.0)
+        }
+    }
+}
+
+/// cheap to clone handle shared between the sync loop and whoever observes / steers it
+#[derive(Clone, Default)]
+pub struct StatusHandle {
+    inner: Arc<Mutex<SyncStatus>>,
+    sync_now: Arc<Notify>,
+}
+
+impl StatusHandle {
+    pub fn read<R>(&self, f: impl FnOnce(&SyncStatus) -> R) -> R {
+        f(&self.inner.lock().unwrap())
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut SyncStatus)) {
+        f(&mut self.inner.lock().unwrap())
+    }
+
+    pub fn update_watch_group(&self, wg_id: i64, f: impl FnOnce(&mut WatchGroupStatus)) {
+        self.update(|status| f(status.watch_groups.entry(wg_id).or_default()))
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        self.update(|status| {
+            status.stats.current.errors += 1;
+            push_bounded(&mut status.recent_errors, message.into())
+        })
+    }
+
+    pub fn scanned(&self, files: usize) {
+        self.update(|status| status.stats.current.files_scanned += files as u64)
+    }
+
+    pub fn executed(&self, instructions: usize) {
+        self.update(|status| status.stats.current.instructions_executed += instructions as u64)
+    }
+
+    pub fn deletes_sent(&self, deletes: usize) {
+        self.update(|status| status.stats.current.deletes_sent += deletes as u64)
+    }
+
+    /// see [`SyncStats::finish_cycle`]
+    pub fn finish_cycle(&self, duration: Duration) -> CycleStats {
+        let mut cycle = CycleStats::default();
+        self.update(|status| cycle = status.stats.finish_cycle(duration));
+        cycle
+    }
+
+    pub fn conflict(&self, message: impl Into<String>) {
+        self.update(|status| push_bounded(&mut status.conflicts, message.into()))
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.read(|status| status.paused)
+    }
+
+    pub fn toggle_pause(&self) {
+        self.update(|status| status.paused = !status.paused)
+    }
+
+    /// wakes the sync loop up before its poll interval elapsed
+    pub fn request_sync(&self) {
+        self.sync_now.notify_one()
+    }
+
+    pub async fn sync_requested(&self) {
+        self.sync_now.notified().await
+    }
+
+    /// registers a running transfer - it disappears from the status once the guard is dropped
+    pub fn start_transfer(
+        &self,
+        wg_id: i64,
+        path: String,
+        direction: TransferDirection,
+        bytes_total: u64,
+    ) -> TransferGuard {
+        let mut id = 0;
+        self.update(|status| {
+            id = status.next_transfer_id;
+            status.next_transfer_id += 1;
+            status.transfers.insert(
+                id,
+                Transfer {
+                    wg_id,
+                    path,
+                    direction,
+                    bytes_done: 0,
+                    bytes_total,
+                },
+            );
+        });
+        TransferGuard {
+            handle: self.clone(),
+            id,
+        }
+    }
+}
+
+fn push_bounded(entries: &mut VecDeque<(UtcMillis, String)>, message: String) {
+    entries.push_front((UtcMillis::now(), message));
+    entries.truncate(MAX_RECENT_ENTRIES);
+}
+
+pub struct TransferGuard {
+    handle: StatusHandle,
+    id: u64,
+}
+
+impl TransferGuard {
+    pub fn advance(&self, bytes: u64) {
+        self.handle.update(|status| {
+            if let Some(transfer) = status.transfers.get_mut(&self.id) {
+                transfer.bytes_done += bytes;
+                match transfer.direction {
+                    TransferDirection::Upload => status.stats.current.bytes_uploaded += bytes,
+                    TransferDirection::Download => status.stats.current.bytes_downloaded += bytes,
+                }
+            }
+        })
+    }
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        self.handle.update(|status| {
+            status.transfers.remove(&self.id);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_remove_transfer_when_guard_is_dropped() {
+        let handle = StatusHandle::default();
+        let guard = handle.start_transfer(1, "a.txt".into(), TransferDirection::Upload, 10);
+        guard.advance(4);
+        assert_eq!(
+            Some(0.4),
+            handle.read(|s| s.transfers.values().next().map(Transfer::ratio))
+        );
+        assert_eq!(4, handle.read(|s| s.stats.current.bytes_uploaded));
+
+        drop(guard);
+        assert!(handle.read(|s| s.transfers.is_empty()));
+    }
+
+    #[test]
+    fn should_keep_bounded_number_of_errors() {
+        let handle = StatusHandle::default();
+        (0..MAX_RECENT_ENTRIES + 10).for_each(|i| handle.error(format!("error {i}")));
+
+        handle.read(|s| {
+            assert_eq!(MAX_RECENT_ENTRIES, s.recent_errors.len());
+            assert_eq!(
+                format!("error {}", MAX_RECENT_ENTRIES + 9),
+                s.recent_errors.front().unwrap().1
+            );
+        });
+    }
+}
@@ -0,0 +1,207 @@
+use shared::dtos::FileDescription;
+use shared::matchable_path::MatchablePath;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// in the root of a watch group - one path per line (unix-delimited, relative to the root),
+/// `#` starts a comment; a folder excludes everything below it
+///
+/// local to this client - the file itself is never synced
+pub const SYNCIGNORE_FILE: &str = ".syncignore";
+
+/// paths of a watch group this client neither up- nor downloads (nor deletes) - they stay
+/// untouched on disk and on the server
+#[derive(Debug, Default)]
+pub struct SyncIgnore {
+    paths: Vec<MatchablePath>,
+}
+
+impl SyncIgnore {
+    /// a missing (or unreadable) file ignores nothing
+    pub fn load(root: &Path) -> Self {
+        match fs::read_to_string(root.join(SYNCIGNORE_FILE)) {
+            Ok(content) => Self::parse(&content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("Could not read {SYNCIGNORE_FILE} of {root:?} - {e}");
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(content: &str) -> Self {
+        let paths = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(to_path)
+            .collect();
+        Self { paths }
+    }
+
+    pub fn is_ignored(&self, path: &MatchablePath) -> bool {
+        let segments = path.get();
+        segments.as_slice() == [SYNCIGNORE_FILE]
+            || self
+                .paths
+                .iter()
+                .any(|ignored| segments.starts_with(ignored.get()))
+    }
+
+    /// drops the ignored files of a scan
+    pub fn retain(&self, mut descriptions: Vec<FileDescription>) -> Vec<FileDescription> {
+        descriptions.retain(|d| !self.is_ignored(&d.relative_path));
+        descriptions
+    }
+}
+
+/// adds `path` to the .syncignore of `root` - false if it was ignored already
+pub fn add(root: &Path, path: &MatchablePath) -> io::Result<bool> {
+    let file = root.join(SYNCIGNORE_FILE);
+    let content = read_or_empty(&file)?;
+    if SyncIgnore::parse(&content).paths.contains(path) {
+        return Ok(false);
+    }
+    let mut content = content;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&path.to_serialized_string());
+    content.push('\n');
+    fs::write(file, content)?;
+    Ok(true)
+}
+
+/// removes `path` from the .syncignore of `root` (comments are kept) - false if it wasn't in there
+pub fn remove(root: &Path, path: &MatchablePath) -> io::Result<bool> {
+    let file = root.join(SYNCIGNORE_FILE);
+    let content = read_or_empty(&file)?;
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|line| line.trim().starts_with('#') || to_path(line.trim()) != *path)
+        .collect();
+    if kept.len() == content.lines().count() {
+        return Ok(false);
+    }
+    let mut content = kept.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    fs::write(file, content)?;
+    Ok(true)
+}
+
+/// leftovers of interrupted downloads of `file_path` (or of any file below it if it's a folder) -
+/// they'd be resumed once the path isn't ignored anymore, so they're only dead weight
+pub fn partial_downloads(file_path: &Path) -> Vec<PathBuf> {
+    if file_path.is_dir() {
+        let mut partials: Vec<PathBuf> = files_below(file_path)
+            .into_iter()
+            .filter_map(|entry| {
+                let name = entry.file_name()?.to_str()?;
+                let file = name.strip_suffix(".etag.part")?;
+                Some(entry.with_file_name(file))
+            })
+            .flat_map(|file| partial_downloads(&file))
+            .collect();
+        partials.sort();
+        partials.dedup();
+        return partials;
+    }
+    // see `execute::download` / `execute::keep_both`
+    [
+        ".part",
+        ".etag.part",
+        ".conflict.part",
+        ".conflict.part.part",
+        ".conflict.part.etag.part",
+    ]
+    .iter()
+    .map(|suffix| {
+        let mut name = file_path.as_os_str().to_owned();
+        name.push(suffix);
+        PathBuf::from(name)
+    })
+    .filter(|partial| partial.is_file())
+    .collect()
+}
+
+fn files_below(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .flat_map(|entry| {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => files_below(&path),
+                _ => vec![path],
+            }
+        })
+        .collect()
+}
+
+fn to_path(line: &str) -> MatchablePath {
+    MatchablePath::from(
+        line.split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<&str>>(),
+    )
+}
+
+fn read_or_empty(file: &Path) -> io::Result<String> {
+    match fs::read_to_string(file) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn should_ignore_listed_paths_and_folders() {
+        let ignore = SyncIgnore::parse("# huge\nvideos/raw.mov\n\nbuild/\n");
+        let ignored = |path: &str| ignore.is_ignored(&MatchablePath::from(path));
+
+        assert!(ignored("videos/raw.mov"));
+        assert!(ignored("build/out/app.bin"));
+        assert!(ignored(SYNCIGNORE_FILE));
+        assert!(!ignored("videos/cut.mov"));
+        assert!(!ignored("builds/app.bin"));
+        assert!(!ignored("docs/.syncignore"));
+    }
+
+    #[test]
+    fn should_add_and_remove_paths() {
+        let root = std::env::temp_dir().join(format!("syncignore-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(SYNCIGNORE_FILE), "# keep me\nbuild").unwrap();
+        let path = MatchablePath::from("videos/raw.mov");
+
+        assert!(add(&root, &path).unwrap());
+        assert!(!add(&root, &path).unwrap());
+        assert!(SyncIgnore::load(&root).is_ignored(&path));
+
+        assert!(remove(&root, &path).unwrap());
+        assert!(!remove(&root, &path).unwrap());
+        assert_eq!(
+            "# keep me\nbuild\n",
+            fs::read_to_string(root.join(SYNCIGNORE_FILE)).unwrap()
+        );
+
+        fs::write(root.join("raw.mov.part"), "1234").unwrap();
+        fs::write(root.join("raw.mov.etag.part"), "etag").unwrap();
+        let expected = vec![root.join("raw.mov.etag.part"), root.join("raw.mov.part")];
+        let mut partials = partial_downloads(&root.join("raw.mov"));
+        partials.sort();
+        assert_eq!(expected, partials);
+        assert_eq!(expected, partial_downloads(&root));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use shared::sync_instruction::SyncInstruction;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{Span, debug, field, info_span, warn};
+
+use crate::status::StatusHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Upload,
+    Download,
+    Delete,
+}
+
+impl Operation {
+    pub fn of(instruction: &SyncInstruction) -> Self {
+        match instruction {
+            SyncInstruction::Upload(_) => Operation::Upload,
+            SyncInstruction::Download(_) => Operation::Download,
+            SyncInstruction::Delete(_) => Operation::Delete,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Upload => "upload",
+            Operation::Download => "download",
+            Operation::Delete => "delete",
+        }
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// times one instruction - how long it waited since the sync reply and how long it took - into
+/// its span (`instruction`) and the stats
+pub struct InstructionTimer {
+    operation: Operation,
+    path: String,
+    queued: Duration,
+    started: Instant,
+    bytes_before: u64,
+    span: Span,
+}
+
+impl InstructionTimer {
+    /// `received` - when the instructions came in
+    pub fn start(instruction: &SyncInstruction, received: Instant, status: &StatusHandle) -> Self {
+        let operation = Operation::of(instruction);
+        let path = instruction.path().to_serialized_string();
+        let queued = received.elapsed();
+        let span = info_span!(
+            "instruction",
+            operation = operation.as_str(),
+            path,
+            queue_ms = queued.as_millis() as u64,
+            transfer_ms = field::Empty,
+            bytes = field::Empty,
+            bytes_per_sec = field::Empty,
+        );
+        InstructionTimer {
+            operation,
+            path,
+            queued,
+            started: Instant::now(),
+            bytes_before: transferred(status),
+            span,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span.clone()
+    }
+
+    /// instructions taking `slow_after_ms` or longer are logged at WARN (0 = never)
+    pub fn finish(self, slow_after_ms: u64, status: &StatusHandle) {
+        let took = self.started.elapsed();
+        let bytes = transferred(status).saturating_sub(self.bytes_before);
+        let bytes_per_sec = (bytes as f64 / took.as_secs_f64().max(0.001)) as u64;
+        self.span.record("transfer_ms", took.as_millis() as u64);
+        self.span.record("bytes", bytes);
+        self.span.record("bytes_per_sec", bytes_per_sec);
+
+        let _entered = self.span.enter();
+        let (operation, path, queued) = (self.operation, &self.path, self.queued);
+        if slow_after_ms > 0 && took >= Duration::from_millis(slow_after_ms) {
+            warn!(
+                "Slow {operation} of '{path}': {took:?} for {bytes} B ({bytes_per_sec} B/s) - queued for {queued:?}"
+            );
+        } else {
+            debug!("{operation} of '{path}' took {took:?} - queued for {queued:?}");
+        }
+        status.update(|s| s.stats.record_timing(operation, took));
+    }
+}
+
+/// bytes moved by the cycle so far - instructions run one after another
+fn transferred(status: &StatusHandle) -> u64 {
+    status.read(|s| s.stats.current.bytes_uploaded + s.stats.current.bytes_downloaded)
+}
@@ -0,0 +1,488 @@
+use std::io;
+use std::pin::Pin;
+
+use crate::config::TransportKind;
+use crate::quic::Http3Client;
+use crate::status::TransferGuard;
+use futures_util::{Stream, StreamExt, future, stream};
+use reqwest::header::{CONTENT_RANGE, ETAG, HeaderMap, HeaderValue, IF_RANGE, RANGE};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Body, Client, StatusCode};
+use shared::dtos::FileDescription;
+use shared::endpoint::{
+    CONFLICT_KEPT_HEADER_KEY, DOWNLOAD_BYTES_HEADER_KEY, FILE_CLIENT_HOST_HEADER_KEY,
+    FILE_UTC_MILLIS_HEADER_KEY, SYNC_PAUSED_HEADER_KEY, SysRoute,
+};
+use shared::grpc::sync_service_client::SyncServiceClient;
+use shared::grpc::{
+    DeleteRequest, DownloadRequest, FileDescriptionMessage, SyncRequest, UploadChunk,
+    http_status_of,
+};
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::SyncInstruction;
+use shared::utc_millis::UtcMillis;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue};
+use tonic::service::Interceptor;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+
+/// chunk size used when streaming uploads from disk
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+/// same as the server - sync replies of large watch groups exceed the default of 4 MiB
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+type GrpcClient = SyncServiceClient<InterceptedService<Channel, ClientHeaders>>;
+
+pub type DownloadStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>, String>> + Send>>;
+
+/// reply of [`Transport::sync`]
+pub struct Synced {
+    pub instructions: Vec<SyncInstruction>,
+    /// an admin paused the sync (no instructions then)
+    pub paused: bool,
+    /// total size of the downloads among the instructions - None for older servers
+    pub download_bytes: Option<u64>,
+}
+
+/// reply of [`Transport::download`]
+pub struct Download {
+    /// where `body` starts - 0 unless the server continued a [`Resume`]
+    pub offset: u64,
+    /// of the whole file
+    pub size: u64,
+    /// version of the file - None over grpc
+    pub etag: Option<String>,
+    /// time of the file's latest event - the downloaded file is stamped with it
+    pub utc_millis: Option<UtcMillis>,
+    /// client the latest version came from - names conflicted copies
+    pub client_host: Option<String>,
+    pub body: DownloadStream,
+}
+
+/// bytes of an earlier, interrupted download - the server only continues if the file still
+/// has `etag`, it sends the whole file otherwise
+pub struct Resume {
+    pub offset: u64,
+    pub etag: String,
+}
+
+impl Resume {
+    pub(crate) fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let (Ok(range), Ok(etag)) = (
+            HeaderValue::from_str(&format!("bytes={}-", self.offset)),
+            HeaderValue::from_str(&self.etag),
+        ) {
+            headers.insert(RANGE, range);
+            headers.insert(IF_RANGE, etag);
+        }
+        headers
+    }
+}
+
+/// sync / upload / download / delete over the configured [`TransportKind`]
+pub struct Transport {
+    /// config, bulk transfers, scan warnings, ... are plain http either way
+    pub http: Client,
+    grpc: Option<GrpcClient>,
+    /// uploads / downloads only - the small requests aren't worth a second connection
+    http3: Option<Http3Client>,
+}
+
+/// the default headers of the http client as grpc metadata
+#[derive(Clone)]
+struct ClientHeaders(Vec<(AsciiMetadataKey, AsciiMetadataValue)>);
+
+impl Interceptor for ClientHeaders {
+    fn call(
+        &mut self,
+        mut request: tonic::Request<()>,
+    ) -> Result<tonic::Request<()>, tonic::Status> {
+        for (key, value) in &self.0 {
+            request.metadata_mut().insert(key.clone(), value.clone());
+        }
+        Ok(request)
+    }
+}
+
+impl Transport {
+    pub fn new(
+        kind: TransportKind,
+        server_url: &str,
+        http: Client,
+        headers: &HeaderMap,
+    ) -> Result<Self, String> {
+        let grpc = match kind {
+            TransportKind::Grpc => Some(grpc_client(server_url, headers)?),
+            TransportKind::Http | TransportKind::Http3 => None,
+        };
+        let http3 = match kind {
+            TransportKind::Http3 => Some(Http3Client::new(server_url, headers.clone())?),
+            TransportKind::Http | TransportKind::Grpc => None,
+        };
+        Ok(Transport { http, grpc, http3 })
+    }
+
+    pub async fn sync(
+        &self,
+        base: &str,
+        wg_id: i64,
+        scanned: &[FileDescription],
+    ) -> Result<Synced, String> {
+        let Some(grpc) = &self.grpc else {
+            let response = self
+                .http
+                .post(SysRoute::Sync { wg_id }.to_uri(base))
+                .json(scanned)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let paused = response.headers().contains_key(SYNC_PAUSED_HEADER_KEY);
+            let download_bytes = response
+                .headers()
+                .get(DOWNLOAD_BYTES_HEADER_KEY)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok());
+            return Ok(Synced {
+                instructions: response.json().await.map_err(|e| e.to_string())?,
+                paused,
+                download_bytes,
+            });
+        };
+        let reply = grpc
+            .clone()
+            .sync(SyncRequest {
+                watch_group_id: wg_id,
+                files: scanned.iter().map(FileDescriptionMessage::from).collect(),
+            })
+            .await
+            .map_err(|status| status.to_string())?
+            .into_inner();
+        let instructions = reply
+            .instructions
+            .into_iter()
+            .map(SyncInstruction::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Synced {
+            instructions,
+            paused: reply.paused,
+            download_bytes: reply.download_bytes,
+        })
+    }
+
+    /// status + reply of the server - only failing to reach it is an error
+    ///
+    /// `conflict_kept` - the server's version the client kept as conflicted copy (keep-both)
+    pub async fn upload(
+        &self,
+        base: &str,
+        wg_id: i64,
+        description: &FileDescription,
+        file: File,
+        transfer: TransferGuard,
+        conflict_kept: Option<&UtcMillis>,
+    ) -> Result<(StatusCode, String), String> {
+        if let Some(http3) = &self.http3
+            && let Some(connection) = http3.connection().await
+        {
+            return http3
+                .upload(
+                    connection,
+                    SysRoute::Upload { wg_id }.to_uri(base),
+                    description,
+                    file,
+                    transfer,
+                    conflict_kept,
+                )
+                .await;
+        }
+        let relative_path = description.relative_path.to_serialized_string();
+        let Some(grpc) = &self.grpc else {
+            let file_part = Part::stream_with_length(
+                Body::wrap_stream(progress_stream(file, transfer)),
+                description.size_in_bytes,
+            )
+            .file_name(description.file_name.clone());
+            let form: Form = Form::new()
+                .text(
+                    "utc_millis",
+                    serde_json::to_string(&description.last_updated_utc_millis).unwrap(),
+                )
+                .text("relative_path", relative_path)
+                .part("file", file_part);
+            let mut request = self
+                .http
+                .post(SysRoute::Upload { wg_id }.to_uri(base))
+                .multipart(form);
+            if let Some(kept) = conflict_kept {
+                request = request.header(CONFLICT_KEPT_HEADER_KEY, kept.as_u64());
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| format!("Upload failed - {e}"))?;
+            let status = response.status();
+            let reply = response
+                .text()
+                .await
+                .map_err(|e| format!("BOM sniffing failed - {e}"))?;
+            return Ok((status, reply));
+        };
+
+        let header = UploadChunk {
+            watch_group_id: wg_id,
+            relative_path,
+            utc_millis: description.last_updated_utc_millis.as_u64(),
+            size_in_bytes: description.size_in_bytes,
+            data: Vec::new(),
+            conflict_kept_utc_millis: conflict_kept.map(UtcMillis::as_u64).unwrap_or(0),
+        };
+        // a read error ends the stream early - the server refuses the incomplete upload
+        let chunks = progress_stream(file, transfer)
+            .scan((), |_, chunk| future::ready(chunk.ok()))
+            .map(|data| UploadChunk {
+                data,
+                ..Default::default()
+            });
+        match grpc
+            .clone()
+            .upload(stream::once(future::ready(header)).chain(chunks))
+            .await
+        {
+            Ok(reply) => Ok((StatusCode::OK, reply.into_inner().message)),
+            Err(status) => Ok((
+                StatusCode::from_u16(http_status_of(&status))
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                status.message().to_string(),
+            )),
+        }
+    }
+
+    /// the file (or its rest if the server continues `resume`)
+    pub async fn download(
+        &self,
+        base: &str,
+        wg_id: i64,
+        path: &MatchablePath,
+        resume: Option<&Resume>,
+    ) -> Result<Download, String> {
+        if let Some(http3) = &self.http3
+            && let Some(connection) = http3.connection().await
+        {
+            return http3.download(connection, base, wg_id, path, resume).await;
+        }
+        let Some(grpc) = &self.grpc else {
+            let mut resume = resume;
+            let response = loop {
+                let mut request = self
+                    .http
+                    .get(SysRoute::Download { wg_id }.to_uri(base))
+                    .body(path.to_serialized_string());
+                if let Some(resume) = resume {
+                    request = request.headers(resume.headers());
+                }
+                let response = request.send().await.map_err(|e| e.to_string())?;
+                // the earlier attempt already got everything - start over
+                if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && resume.is_some() {
+                    resume = None;
+                    continue;
+                }
+                break response
+                    .error_for_status()
+                    .map_err(|e| format!("{} - {}", e.status().unwrap(), e))?;
+            };
+            let (offset, size) = served_range(
+                response.status(),
+                response.headers(),
+                response.content_length().unwrap_or(0),
+            )?;
+            let etag = etag_of(response.headers());
+            let utc_millis = utc_millis_of(response.headers());
+            let client_host = client_host_of(response.headers());
+            let body = response
+                .bytes_stream()
+                .map(|chunk| chunk.map(|bytes| bytes.to_vec()).map_err(|e| e.to_string()));
+            return Ok(Download {
+                offset,
+                size,
+                etag,
+                utc_millis,
+                client_host,
+                body: Box::pin(body),
+            });
+        };
+        // no ranges over grpc - always the whole file
+        let mut chunks = grpc
+            .clone()
+            .download(DownloadRequest {
+                watch_group_id: wg_id,
+                path: path.to_serialized_string(),
+            })
+            .await
+            .map_err(|status| status.to_string())?
+            .into_inner();
+        let first = chunks
+            .message()
+            .await
+            .map_err(|status| status.to_string())?
+            .unwrap_or_default();
+        let utc_millis = Some(first.utc_millis)
+            .filter(|millis| *millis > 0)
+            .map(UtcMillis::from);
+        let client_host = Some(first.client_host).filter(|host| !host.is_empty());
+        let body = stream::once(future::ready(Ok(first.data)))
+            .chain(chunks.map(|chunk| chunk.map(|chunk| chunk.data).map_err(|s| s.to_string())));
+        Ok(Download {
+            offset: 0,
+            size: first.size_in_bytes,
+            etag: None,
+            utc_millis,
+            client_host,
+            body: Box::pin(body),
+        })
+    }
+
+    /// reply of the server
+    pub async fn delete(
+        &self,
+        base: &str,
+        wg_id: i64,
+        path: &MatchablePath,
+    ) -> Result<String, String> {
+        let Some(grpc) = &self.grpc else {
+            return self
+                .http
+                .post(SysRoute::Delete { wg_id }.to_uri(base))
+                .body(path.to_serialized_string())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| e.to_string())?
+                .text()
+                .await
+                .map_err(|e| e.to_string());
+        };
+        grpc.clone()
+            .delete(DeleteRequest {
+                watch_group_id: wg_id,
+                path: path.to_serialized_string(),
+            })
+            .await
+            .map(|reply| reply.into_inner().message)
+            .map_err(|status| status.to_string())
+    }
+}
+
+/// connects lazily - the first call fails if the server isn't reachable (like with http)
+fn grpc_client(server_url: &str, headers: &HeaderMap) -> Result<GrpcClient, String> {
+    let mut endpoint = Endpoint::from_shared(server_url.to_string())
+        .map_err(|e| format!("Invalid server_url for grpc - {e}"))?;
+    if server_url.starts_with("https") {
+        endpoint = endpoint
+            .tls_config(ClientTlsConfig::new().with_enabled_roots())
+            .map_err(|e| format!("Could not set up tls for grpc - {e}"))?;
+    }
+    let metadata = headers
+        .iter()
+        .map(|(key, value)| {
+            Ok((
+                AsciiMetadataKey::from_bytes(key.as_str().as_bytes()).map_err(|e| e.to_string())?,
+                AsciiMetadataValue::try_from(value.as_bytes())
+                    .map_err(|e| format!("Invalid {key} - {e}"))?,
+            ))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(
+        SyncServiceClient::with_interceptor(endpoint.connect_lazy(), ClientHeaders(metadata))
+            .max_decoding_message_size(MAX_MESSAGE_BYTES)
+            .max_encoding_message_size(MAX_MESSAGE_BYTES),
+    )
+}
+
+/// offset of the body + size of the whole file - `content_length` is the whole file unless
+/// it's a 206
+pub(crate) fn served_range(
+    status: StatusCode,
+    headers: &HeaderMap,
+    content_length: u64,
+) -> Result<(u64, u64), String> {
+    if status != StatusCode::PARTIAL_CONTENT {
+        return Ok((0, content_length));
+    }
+    // bytes 100-199/200
+    let content_range = headers
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    content_range
+        .strip_prefix("bytes ")
+        .and_then(|v| v.split_once('-'))
+        .and_then(|(start, rest)| {
+            Some((start.parse().ok()?, rest.split_once('/')?.1.parse().ok()?))
+        })
+        .ok_or_else(|| format!("Invalid Content-Range '{content_range}'"))
+}
+
+pub(crate) fn etag_of(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+pub(crate) fn utc_millis_of(headers: &HeaderMap) -> Option<UtcMillis> {
+    headers
+        .get(FILE_UTC_MILLIS_HEADER_KEY)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(UtcMillis::from)
+}
+
+pub(crate) fn client_host_of(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(FILE_CLIENT_HOST_HEADER_KEY)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// reads the file chunk-wise and reports every chunk to the transfer
+pub(crate) fn progress_stream(
+    file: File,
+    transfer: TransferGuard,
+) -> impl Stream<Item = Result<Vec<u8>, io::Error>> {
+    stream::unfold(Some((file, transfer)), |state| async move {
+        let (mut file, transfer) = state?;
+        let mut buf = vec![0u8; UPLOAD_CHUNK_SIZE];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                transfer.advance(n as u64);
+                Some((Ok(buf), Some((file, transfer))))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_offset_and_size_of_partial_content() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Ok((0, 200)), served_range(StatusCode::OK, &headers, 200));
+
+        headers.insert(CONTENT_RANGE, HeaderValue::from_static("bytes 100-199/200"));
+        assert_eq!(
+            Ok((100, 200)),
+            served_range(StatusCode::PARTIAL_CONTENT, &headers, 100)
+        );
+
+        headers.insert(CONTENT_RANGE, HeaderValue::from_static("bytes */200"));
+        assert!(served_range(StatusCode::PARTIAL_CONTENT, &headers, 100).is_err());
+    }
+}
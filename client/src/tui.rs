@@ -0,0 +1,211 @@
+use std::io;
+use std::time::Duration;
+
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, List, ListItem, Paragraph, Row, Table};
+use tracing::{error, info};
+
+use crate::power::LowBatteryAction;
+use crate::status::{StatusHandle, SyncStatus, TransferDirection};
+use crate::transport::Transport;
+use crate::{ClientState, sync_loop};
+
+/// how long to wait for key events before redrawing
+const TICK: Duration = Duration::from_millis(250);
+
+/// which list is shown in the bottom panel
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BottomPanel {
+    Errors,
+    Conflicts,
+}
+
+/// runs the sync loop in the background while rendering its status to the terminal
+pub async fn run(state: ClientState, transport: Transport, status: StatusHandle) {
+    let sync = tokio::spawn(sync_loop(state, transport, status.clone()));
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut terminal = ratatui::init();
+        let result = ui_loop(&mut terminal, &status);
+        ratatui::restore();
+        result
+    })
+    .await;
+
+    sync.abort();
+    match result {
+        Ok(Ok(())) => info!("Dashboard closed"),
+        Ok(Err(e)) => error!("Dashboard failed - {e}"),
+        Err(e) => error!("Dashboard panicked - {e}"),
+    }
+}
+
+fn ui_loop(terminal: &mut DefaultTerminal, status: &StatusHandle) -> io::Result<()> {
+    let mut panel = BottomPanel::Errors;
+    loop {
+        terminal.draw(|frame| status.read(|s| draw(frame, s, panel)))?;
+
+        if !event::poll(TICK)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('p') => status.toggle_pause(),
+                KeyCode::Char('s') => status.request_sync(),
+                KeyCode::Char('c') => {
+                    panel = match panel {
+                        BottomPanel::Errors => BottomPanel::Conflicts,
+                        BottomPanel::Conflicts => BottomPanel::Errors,
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, status: &SyncStatus, panel: BottomPanel) {
+    let [header, watch_groups, transfers, bottom] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(status.watch_groups.len() as u16 + 3),
+        Constraint::Length(status.transfers.len().max(1) as u16 + 2),
+        Constraint::Min(5),
+    ])
+    .areas(frame.area());
+
+    draw_header(frame, header, status, panel);
+    draw_watch_groups(frame, watch_groups, status);
+    draw_transfers(frame, transfers, status);
+    draw_bottom_panel(frame, bottom, status, panel);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, status: &SyncStatus, panel: BottomPanel) {
+    let state = if status.paused {
+        "PAUSED".yellow().bold()
+    } else if status.network_blocked.is_some() {
+        "OFF NETWORK".yellow().bold()
+    } else if status
+        .power
+        .is_some_and(|p| p.action == LowBatteryAction::Pause)
+    {
+        "LOW BATTERY".yellow().bold()
+    } else {
+        "SYNCING".green().bold()
+    };
+    let toggle = match panel {
+        BottomPanel::Errors => "show conflicts",
+        BottomPanel::Conflicts => "show errors",
+    };
+    let line = Line::from(vec![
+        state,
+        format!(
+            "   [p] {}  [s] sync now  [c] {toggle}  [q] quit",
+            if status.paused { "resume" } else { "pause" }
+        )
+        .into(),
+    ]);
+    frame.render_widget(
+        Paragraph::new(line).block(Block::bordered().title(" rust-file-sync ")),
+        area,
+    );
+}
+
+fn draw_watch_groups(frame: &mut Frame, area: Rect, status: &SyncStatus) {
+    let rows = status.watch_groups.iter().map(|(wg_id, wg)| {
+        let state = match &wg.last_error {
+            Some(e) => e.clone().red(),
+            None if wg.paused_by_server => "paused by server".yellow(),
+            None => "ok".green(),
+        };
+        Row::new(vec![
+            Line::from(wg_id.to_string()),
+            Line::from(wg.name.clone()),
+            Line::from(wg.file_count.to_string()),
+            Line::from(wg.last_instruction_count.to_string()),
+            Line::from(
+                wg.last_scan
+                    .as_ref()
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            Line::from(state),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(6),
+            Constraint::Percentage(20),
+            Constraint::Length(8),
+            Constraint::Length(14),
+            Constraint::Length(34),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "ID",
+            "Name",
+            "Files",
+            "Instructions",
+            "Last scan",
+            "State",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title(" Watch groups "));
+    frame.render_widget(table, area);
+}
+
+fn draw_transfers(frame: &mut Frame, area: Rect, status: &SyncStatus) {
+    let block = Block::bordered().title(" Transfers ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if status.transfers.is_empty() {
+        frame.render_widget(Paragraph::new("idle".dark_gray()), inner);
+        return;
+    }
+
+    let rows = Layout::vertical(vec![Constraint::Length(1); status.transfers.len()]).split(inner);
+    for (transfer, row) in status.transfers.values().zip(rows.iter()) {
+        let (arrow, color) = match transfer.direction {
+            TransferDirection::Upload => ("↑", Color::Cyan),
+            TransferDirection::Download => ("↓", Color::Magenta),
+        };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(color))
+            .ratio(transfer.ratio())
+            .label(format!(
+                "{arrow} [{}] {} ({}/{} bytes)",
+                transfer.wg_id, transfer.path, transfer.bytes_done, transfer.bytes_total
+            ));
+        frame.render_widget(gauge, *row);
+    }
+}
+
+fn draw_bottom_panel(frame: &mut Frame, area: Rect, status: &SyncStatus, panel: BottomPanel) {
+    let (title, entries, color) = match panel {
+        BottomPanel::Errors => (" Recent errors ", &status.recent_errors, Color::Red),
+        BottomPanel::Conflicts => (
+            " Conflicts (server holds newer version) ",
+            &status.conflicts,
+            Color::Yellow,
+        ),
+    };
+    let items = entries.iter().map(|(at, message)| {
+        ListItem::new(Line::from(vec![
+            format!("{at}  ").dark_gray(),
+            message.clone().fg(color),
+        ]))
+    });
+    frame.render_widget(List::new(items).block(Block::bordered().title(title)), area);
+}
@@ -0,0 +1,207 @@
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+const HEADER: &str = "# Changelog";
+/// separates the commit messages in the git log output
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+/// sections in the order they are rendered - commits that aren't conventional land in "Other"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Category {
+    Breaking,
+    Features,
+    Fixes,
+    Performance,
+    Other,
+}
+
+impl Category {
+    fn of(commit_type: &str) -> Self {
+        match commit_type {
+            "feat" => Category::Features,
+            "fix" => Category::Fixes,
+            "perf" => Category::Performance,
+            _ => Category::Other,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Category::Breaking => "Breaking changes",
+            Category::Features => "Features",
+            Category::Fixes => "Fixes",
+            Category::Performance => "Performance",
+            Category::Other => "Other",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Entry {
+    category: Category,
+    scope: Option<String>,
+    description: String,
+}
+
+/// messages of all commits since the last tag (all commits if there is none) - newest first
+pub fn commits_since_last_tag(repo: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let last_tag = Command::new("git")
+        .args(["describe", "--tags", "--abbrev=0"])
+        .current_dir(repo)
+        .output()?;
+    let mut args = vec!["log".to_string(), "--format=%B%x1e".to_string()];
+    if last_tag.status.success() {
+        let tag = String::from_utf8(last_tag.stdout)?.trim().to_string();
+        println!("Collecting commits since {tag}");
+        args.push(format!("{tag}..HEAD"));
+    } else {
+        println!("No tag found - collecting all commits");
+    }
+
+    let log = Command::new("git").args(&args).current_dir(repo).output()?;
+    if !log.status.success() {
+        return Err(String::from_utf8_lossy(&log.stderr).into());
+    }
+    Ok(String::from_utf8(log.stdout)?
+        .split(RECORD_SEPARATOR)
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// `## {version} - {date}` followed by one list per category
+pub fn render_section(version: &str, date: &str, commits: &[String]) -> String {
+    let mut entries: Vec<Entry> = commits.iter().map(|message| parse(message)).collect();
+    // stable - keeps the newest first within a category
+    entries.sort_by_key(|e| e.category);
+
+    let mut section = format!("## {version} - {date}\n");
+    let mut current = None;
+    for entry in entries {
+        if current != Some(entry.category) {
+            section.push_str(&format!("\n### {}\n\n", entry.category.title()));
+            current = Some(entry.category);
+        }
+        match entry.scope {
+            Some(scope) => section.push_str(&format!("- **{scope}:** {}\n", entry.description)),
+            None => section.push_str(&format!("- {}\n", entry.description)),
+        }
+    }
+    if current.is_none() {
+        section.push_str("\nNo changes.\n");
+    }
+    section
+}
+
+/// puts the section on top of the existing changelog (below its header)
+pub fn prepend_section(existing: &str, section: &str) -> String {
+    let rest = existing
+        .strip_prefix(HEADER)
+        .unwrap_or(existing)
+        .trim_start();
+    if rest.is_empty() {
+        format!("{HEADER}\n\n{section}")
+    } else {
+        format!("{HEADER}\n\n{section}\n{rest}")
+    }
+}
+
+/// `type(scope)!: description` - `!` or a `BREAKING CHANGE` footer mark breaking changes
+fn parse(message: &str) -> Entry {
+    let subject = message.lines().next().unwrap_or_default().trim();
+    let breaking_footer = message
+        .lines()
+        .any(|line| line.starts_with("BREAKING CHANGE") || line.starts_with("BREAKING-CHANGE"));
+
+    let Some((header, description)) = subject.split_once(": ") else {
+        return other(subject);
+    };
+    let (header, breaking) = match header.strip_suffix('!') {
+        Some(header) => (header, true),
+        None => (header, breaking_footer),
+    };
+    let (commit_type, scope) = match header.split_once('(') {
+        Some((commit_type, scope)) => match scope.strip_suffix(')') {
+            Some(scope) => (commit_type, Some(scope.to_string())),
+            None => return other(subject),
+        },
+        None => (header, None),
+    };
+    if commit_type.is_empty() || !commit_type.chars().all(|c| c.is_ascii_alphabetic()) {
+        return other(subject);
+    }
+
+    Entry {
+        category: if breaking {
+            Category::Breaking
+        } else {
+            Category::of(&commit_type.to_lowercase())
+        },
+        scope,
+        description: description.trim().to_string(),
+    }
+}
+
+fn other(subject: &str) -> Entry {
+    Entry {
+        category: Category::Other,
+        scope: None,
+        description: subject.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_parse_conventional_commits() {
+        assert_eq!(
+            Entry {
+                category: Category::Features,
+                scope: Some("client".to_string()),
+                description: "add seed command".to_string(),
+            },
+            parse("feat(client): add seed command")
+        );
+        assert_eq!(Category::Breaking, parse("fix!: drop csv history").category);
+        assert_eq!(
+            Category::Breaking,
+            parse("refactor: new config\n\nBREAKING CHANGE: renamed keys").category
+        );
+        assert_eq!(other("Update README"), parse("Update README"));
+        assert_eq!(
+            other("Merge branch 'main': sync"),
+            parse("Merge branch 'main': sync")
+        );
+    }
+
+    #[test]
+    fn should_render_categories_in_order() {
+        let commits = vec![
+            "chore: tidy up".to_string(),
+            "fix(server): handle empty prefix".to_string(),
+            "feat: backups".to_string(),
+        ];
+
+        assert_eq!(
+            "## 1.2.3 - 2026-01-01\n\
+             \n### Features\n\n- backups\n\
+             \n### Fixes\n\n- **server:** handle empty prefix\n\
+             \n### Other\n\n- tidy up\n",
+            render_section("1.2.3", "2026-01-01", &commits)
+        );
+    }
+
+    #[test]
+    fn should_prepend_below_header() {
+        let first = prepend_section("", "## 0.1.0\n");
+        assert_eq!("# Changelog\n\n## 0.1.0\n", first);
+        assert_eq!(
+            "# Changelog\n\n## 0.2.0\n\n## 0.1.0\n",
+            prepend_section(&first, "## 0.2.0\n")
+        );
+    }
+}
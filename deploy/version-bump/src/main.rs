@@ -1,3 +1,6 @@
+mod changelog;
+mod workspace;
+
 use clap::Parser;
 use std::error::Error;
 use std::fs;
@@ -8,6 +11,10 @@ use std::str::FromStr;
 /// takes args:
 /// - `--toml ../path/to/Cargo.toml` **mandatory**
 /// - `--semver major | minor | patch` optional (default=patch)
+/// - `--changelog ../CHANGELOG.md` optional - adds a section for the new version
+/// - `--dry-run` optional - prints instead of writing
+/// - `--workspace` optional - `--toml` is the workspace root, bumps all members to the same version
+/// - `--allow-dirty` optional - `--workspace` refuses to run on a dirty git working tree otherwise
 ///
 /// grabs the Cargo.toml of the active directory,
 /// changes the line starting with "version" by bumping the patch version by 1,
@@ -16,14 +23,53 @@ use std::str::FromStr;
 fn main() -> Result<(), Box<dyn Error>> {
     let config = Config::parse();
     let toml_path = Path::new(config.toml.as_str());
+    let repo = toml_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let new_version = if config.workspace {
+        if !config.allow_dirty && !config.dry_run {
+            workspace::ensure_clean(repo)?;
+        }
+        Some(workspace::bump(toml_path, &config.semver, config.dry_run)?)
+    } else {
+        bump_manifest(toml_path, &config)?
+    };
+
+    if let Some(changelog_path) = config.changelog.as_deref() {
+        let version = new_version.ok_or("No version line found")?;
+        let commits = changelog::commits_since_last_tag(repo)?;
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let section = changelog::render_section(&version, &date, &commits);
+        if config.dry_run {
+            println!("{section}");
+        } else {
+            let existing = fs::read_to_string(changelog_path).unwrap_or_default();
+            fs::write(
+                changelog_path,
+                changelog::prepend_section(&existing, &section),
+            )?;
+            println!("Added {} commits to {}", commits.len(), changelog_path);
+        }
+    }
+    Ok(())
+}
+
+/// bumps the first line starting with "version" - returns the new version
+fn bump_manifest(toml_path: &Path, config: &Config) -> Result<Option<String>, Box<dyn Error>> {
     let toml_content = fs::read_to_string(toml_path)?;
     // println!("ORIGINAL:\n{}", toml_content);
 
+    let mut new_version = None;
     let toml_content = toml_content
         .lines()
         .map(|line| {
             if line.starts_with("version") {
-                bump_version(line, &config.semver)
+                let bumped = bump_version(line, &config.semver);
+                let (major, minor, patch) = extract_sem_version(&bumped);
+                new_version = Some(format_version_string(major, minor, patch));
+                bumped
             } else {
                 line.to_string()
             }
@@ -32,8 +78,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         .join("\n");
 
     // println!("UPDATED:\n{}", toml_content);
-    fs::write(toml_path, toml_content)?;
-    Ok(())
+    if !config.dry_run {
+        fs::write(toml_path, toml_content)?;
+    }
+    Ok(new_version)
 }
 
 #[derive(Parser, Debug)]
@@ -46,6 +94,22 @@ struct Config {
     /// Which semantic version part to bump: major, minor, or patch (default: patch)
     #[arg(long, default_value = "patch")]
     semver: SemVersionPart,
+
+    /// Path to the CHANGELOG.md - gets a section with the commits since the last git tag
+    #[arg(long)]
+    changelog: Option<String>,
+
+    /// Print the new version (and changelog section) without writing anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Treat --toml as the workspace root and bump it and all member crates to the same version
+    #[arg(long)]
+    workspace: bool,
+
+    /// Bump the workspace even if the git working tree has uncommitted changes
+    #[arg(long)]
+    allow_dirty: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -71,16 +135,24 @@ impl FromStr for SemVersionPart {
 }
 
 fn bump_version(original_line: &str, part: &SemVersionPart) -> String {
-    let (mut major, mut minor, mut patch) = extract_sem_version(original_line);
+    let (major, minor, patch) = extract_sem_version(original_line);
     let original = format_version_string(major, minor, patch);
+    let (major, minor, patch) = bump_sem_version((major, minor, patch), part);
+    let updated = format_version_string(major, minor, patch);
+    println!("Bumping version from {} -> {}", original, updated);
+    format!("version = \"{}\"", updated)
+}
+
+fn bump_sem_version(
+    (mut major, mut minor, mut patch): (u8, u8, u8),
+    part: &SemVersionPart,
+) -> (u8, u8, u8) {
     match part {
         SemVersionPart::Major => major += 1,
         SemVersionPart::Minor => minor += 1,
         SemVersionPart::Patch => patch += 1,
     }
-    let updated = format_version_string(major, minor, patch);
-    println!("Bumping version from {} -> {}", original, updated);
-    format!("version = \"{}\"", updated)
+    (major, minor, patch)
 }
 
 fn extract_sem_version(value: &str) -> (u8, u8, u8) {
@@ -0,0 +1,235 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{SemVersionPart, bump_sem_version, extract_sem_version, format_version_string};
+
+const DEPENDENCY_SECTIONS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// a Cargo.toml of the workspace with its (possibly) updated content
+struct Manifest {
+    path: PathBuf,
+    content: String,
+}
+
+/// bumps the root and every member to the same new version - returns it
+///
+/// members with `version.workspace = true` follow the root, path dependencies between members
+/// that carry a version requirement get the new version
+pub fn bump(
+    root_toml: &Path,
+    part: &SemVersionPart,
+    dry_run: bool,
+) -> Result<String, Box<dyn Error>> {
+    let root_content = fs::read_to_string(root_toml)?;
+    let root_dir = root_toml.parent().unwrap_or(Path::new("."));
+    let mut manifests = vec![Manifest {
+        path: root_toml.to_path_buf(),
+        content: root_content.clone(),
+    }];
+    for member in members(&root_content) {
+        let path = root_dir.join(member).join("Cargo.toml");
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read member manifest {}: {e}", path.display()))?;
+        manifests.push(Manifest { path, content });
+    }
+
+    let current = manifests
+        .iter()
+        .filter_map(|m| package_version(&m.content))
+        .map(|v| extract_sem_version(&format!("\"{v}\"")))
+        .max()
+        .ok_or("No version found in the workspace")?;
+    let (major, minor, patch) = bump_sem_version(current, part);
+    let new_version = format_version_string(major, minor, patch);
+    println!(
+        "Bumping workspace from {} -> {}",
+        format_version_string(current.0, current.1, current.2),
+        new_version
+    );
+
+    let crate_names: Vec<String> = manifests
+        .iter()
+        .filter_map(|m| package_name(&m.content))
+        .collect();
+    for manifest in &mut manifests {
+        let updated = set_versions(&manifest.content, &crate_names, &new_version);
+        if updated == manifest.content {
+            continue;
+        }
+        println!("Updating {}", manifest.path.display());
+        manifest.content = updated;
+    }
+    if !dry_run {
+        for manifest in manifests {
+            fs::write(&manifest.path, manifest.content)?;
+        }
+    }
+    Ok(new_version)
+}
+
+/// fails with the changed files if git reports any
+pub fn ensure_clean(repo: &Path) -> Result<(), Box<dyn Error>> {
+    let status = Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(repo)
+        .output()?;
+    if !status.status.success() {
+        return Err(String::from_utf8_lossy(&status.stderr).into());
+    }
+    let changes = String::from_utf8(status.stdout)?;
+    if changes.trim().is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Working tree is dirty (use --allow-dirty to bump anyway):\n{changes}").into())
+    }
+}
+
+/// entries of `members = [...]` (may span several lines) - globs are not supported
+fn members(root_content: &str) -> Vec<String> {
+    let Some(start) = root_content.find("members") else {
+        return Vec::new();
+    };
+    let rest = &root_content[start..];
+    let (Some(open), Some(close)) = (rest.find('['), rest.find(']')) else {
+        return Vec::new();
+    };
+    rest[open + 1..close]
+        .split(',')
+        .map(|entry| entry.trim().trim_matches('"').to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+fn package_name(content: &str) -> Option<String> {
+    sections(content)
+        .find(|(section, line)| section == "package" && key(line) == Some("name"))
+        .and_then(|(_, line)| string_value(line))
+}
+
+/// version of `[package]` or `[workspace.package]` - none for `version.workspace = true`
+fn package_version(content: &str) -> Option<String> {
+    sections(content)
+        .find(|(section, line)| is_package(section) && key(line) == Some("version"))
+        .and_then(|(_, line)| string_value(line))
+}
+
+/// package versions and version requirements of path dependencies on workspace crates
+fn set_versions(content: &str, crate_names: &[String], new_version: &str) -> String {
+    let mut updated: Vec<String> = sections(content)
+        .map(|(section, line)| {
+            if is_package(&section) && key(line) == Some("version") && string_value(line).is_some()
+            {
+                return format!("version = \"{new_version}\"");
+            }
+            if !DEPENDENCY_SECTIONS.iter().any(|d| section.ends_with(d)) {
+                // `[dependencies.shared]` tables
+                let table_of_member = DEPENDENCY_SECTIONS.iter().any(|d| {
+                    section
+                        .strip_prefix(&format!("{d}."))
+                        .is_some_and(|name| crate_names.iter().any(|c| c == name))
+                });
+                if table_of_member && key(line) == Some("version") {
+                    return replace_requirement(line, new_version);
+                }
+                return line.to_string();
+            }
+            // `shared = { path = "../shared", version = "0.1.0" }`
+            let is_member = key(line).is_some_and(|k| crate_names.iter().any(|c| c == k));
+            if is_member && line.contains("path") {
+                replace_requirement(line, new_version)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if content.ends_with('\n') {
+        updated.push(String::new());
+    }
+    updated.join("\n")
+}
+
+fn is_package(section: &str) -> bool {
+    section == "package" || section == "workspace.package"
+}
+
+/// every line together with the name of the `[section]` it is in
+fn sections(content: &str) -> impl Iterator<Item = (String, &str)> {
+    let mut section = String::new();
+    content.lines().map(move |line| {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && !trimmed.starts_with("[[") {
+            section = trimmed.trim_matches(['[', ']']).trim().to_string();
+        }
+        (section.clone(), line)
+    })
+}
+
+fn key(line: &str) -> Option<&str> {
+    line.split_once('=').map(|(key, _)| key.trim())
+}
+
+fn string_value(line: &str) -> Option<String> {
+    let (_, value) = line.split_once('=')?;
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(str::to_string)
+}
+
+/// keeps an operator like `^` or `=` in front of the version
+fn replace_requirement(line: &str, new_version: &str) -> String {
+    let Some(start) = line.find("version").and_then(|i| {
+        let quote = line[i..].find('"')? + i + 1;
+        Some(quote)
+    }) else {
+        return line.to_string();
+    };
+    let Some(len) = line[start..].find('"') else {
+        return line.to_string();
+    };
+    let requirement = &line[start..start + len];
+    let operator: String = requirement
+        .chars()
+        .take_while(|c| !c.is_ascii_digit())
+        .collect();
+    format!(
+        "{}{operator}{new_version}{}",
+        &line[..start],
+        &line[start + len..]
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ROOT: &str = "[workspace.package]\nversion = \"0.1.20\"\n\n[workspace]\nmembers = [\n    \"client\",\n    \"deploy/version-bump\",\n]\n\n[workspace.dependencies]\ntokio = { version = \"1\" }\n";
+
+    #[test]
+    fn should_find_members_and_versions() {
+        assert_eq!(vec!["client", "deploy/version-bump"], members(ROOT));
+        assert_eq!(Some("0.1.20".to_string()), package_version(ROOT));
+        assert_eq!(
+            None,
+            package_version("[package]\nname = \"client\"\nversion.workspace = true\n")
+        );
+    }
+
+    #[test]
+    fn should_set_package_and_path_dependency_versions() {
+        let member = "[package]\nname = \"client\"\nversion = \"0.1.20\"\n\n[dependencies]\ntokio = { version = \"1\" }\nshared = { path = \"../shared\", version = \"^0.1.20\" }\n\n[dev-dependencies.shared]\npath = \"../shared\"\nversion = \"0.1.20\"\n";
+        let crates = vec!["client".to_string(), "shared".to_string()];
+
+        assert_eq!(
+            "[package]\nname = \"client\"\nversion = \"0.2.0\"\n\n[dependencies]\ntokio = { version = \"1\" }\nshared = { path = \"../shared\", version = \"^0.2.0\" }\n\n[dev-dependencies.shared]\npath = \"../shared\"\nversion = \"0.2.0\"\n",
+            set_versions(member, &crates, "0.2.0")
+        );
+        assert_eq!(
+            ROOT.replace("0.1.20", "0.2.0"),
+            set_versions(ROOT, &crates, "0.2.0")
+        );
+    }
+}
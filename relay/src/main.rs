@@ -0,0 +1,333 @@
+use clap::{Parser, Subcommand};
+use shared::log_format::LogFormat;
+use shared::relay::{RelayMessage, read_message, verify, write_message};
+use std::collections::HashMap;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+/// home servers have to dial in the data connection within this time
+const DATA_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+/// keeps NATs from dropping idle control connections (home servers give up after 3 missed pings)
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// for the first line of a connection to the control port
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a token for a new home server and print the config for both sides
+    Pair {
+        /// name of the home server (letters, digits, '-' and '_')
+        name: String,
+        /// public port clients of this home server connect to
+        port: u16,
+    },
+}
+
+/// one home server - clients connecting to `public_port` end up there
+#[derive(Debug, Clone, PartialEq)]
+struct Pairing {
+    name: String,
+    token: String,
+    public_port: u16,
+}
+
+/// `RELAY_PAIRINGS=name:token@port,...`
+fn parse_pairings(value: &str) -> Result<Vec<Pairing>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let invalid = || format!("Invalid pairing '{entry}' - expected name:token@port");
+            let (name, rest) = entry.split_once(':').ok_or_else(invalid)?;
+            let (token, port) = rest.rsplit_once('@').ok_or_else(invalid)?;
+            if !is_valid_name(name) || token.is_empty() {
+                return Err(invalid());
+            }
+            Ok(Pairing {
+                name: name.to_string(),
+                token: token.to_string(),
+                public_port: port.parse().map_err(|_| invalid())?,
+            })
+        })
+        .collect()
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(Default)]
+struct RelayState {
+    /// control connections of the online home servers - takes the ids of waiting clients
+    controls: Mutex<HashMap<String, mpsc::Sender<String>>>,
+    /// clients waiting for the data connection of their home server
+    pending: Mutex<HashMap<String, oneshot::Sender<TcpStream>>>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), String> {
+    let log_level = EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("info"));
+    match LogFormat::from_env() {
+        LogFormat::Pretty => tracing_subscriber::fmt().with_env_filter(log_level).init(),
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_env_filter(log_level)
+            .init(),
+    }
+
+    if let Some(Command::Pair { name, port }) = Args::parse().command {
+        if !is_valid_name(&name) {
+            return Err(format!("Invalid name '{name}'"));
+        }
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        println!("relay:       append '{name}:{token}@{port}' to RELAY_PAIRINGS");
+        println!("home server: RELAY_ADDR=<relay host>:<control port>");
+        println!("             RELAY_NAME={name} RELAY_TOKEN={token}");
+        println!("clients:     server_url https://<relay host>:{port}");
+        return Ok(());
+    }
+
+    let pairings = parse_pairings(&env::var("RELAY_PAIRINGS").unwrap_or_default())?;
+    if pairings.is_empty() {
+        return Err(
+            "No RELAY_PAIRINGS set - create one with `relay pair <name> <port>`".to_string(),
+        );
+    }
+    let control_port = match env::var("RELAY_CONTROL_PORT") {
+        Ok(port) => port
+            .parse()
+            .map_err(|_| format!("Invalid RELAY_CONTROL_PORT '{port}'"))?,
+        Err(_) => 3444,
+    };
+
+    let state = Arc::new(RelayState::default());
+    let tokens: Arc<HashMap<String, String>> = Arc::new(
+        pairings
+            .iter()
+            .map(|p| (p.name.clone(), p.token.clone()))
+            .collect(),
+    );
+    for pairing in pairings {
+        let addr = SocketAddr::from(([0, 0, 0, 0], pairing.public_port));
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| format!("Could not bind {addr} - {e}"))?;
+        info!("Relaying {addr} to home server '{}'", pairing.name);
+        tokio::spawn(serve_public(listener, pairing.name, state.clone()));
+    }
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], control_port));
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Could not bind {addr} - {e}"))?;
+    info!("Waiting for home servers on {addr}");
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Accepting home server connection failed - {e}");
+                continue;
+            }
+        };
+        let (state, tokens) = (state.clone(), tokens.clone());
+        tokio::spawn(async move {
+            if let Err(e) = handle_home_server(stream, &tokens, &state).await {
+                debug!("Connection of {peer} closed - {e}");
+            }
+        });
+    }
+}
+
+/// clients of one home server - each is spliced to a fresh data connection
+async fn serve_public(listener: TcpListener, name: String, state: Arc<RelayState>) {
+    loop {
+        let (mut client, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Accepting client connection failed - {e}");
+                continue;
+            }
+        };
+        let Some(control) = state.controls.lock().unwrap().get(&name).cloned() else {
+            debug!("Dropped {peer} - home server '{name}' is offline");
+            continue;
+        };
+        let (name, state) = (name.clone(), state.clone());
+        tokio::spawn(async move {
+            let id = Uuid::new_v4().simple().to_string();
+            let (sender, receiver) = oneshot::channel();
+            state.pending.lock().unwrap().insert(id.clone(), sender);
+            let data = match control.send(id.clone()).await {
+                Ok(()) => tokio::time::timeout(DATA_CONNECTION_TIMEOUT, receiver)
+                    .await
+                    .ok()
+                    .and_then(Result::ok),
+                Err(_) => None,
+            };
+            state.pending.lock().unwrap().remove(&id);
+            let Some(mut data) = data else {
+                return warn!("Home server '{name}' didn't connect for {peer}");
+            };
+            debug!("Tunneling {peer} to home server '{name}'");
+            if let Err(e) = tokio::io::copy_bidirectional(&mut client, &mut data).await {
+                debug!("Tunnel of {peer} closed - {e}");
+            }
+        });
+    }
+}
+
+/// first line decides - control connection (HELLO) or data connection for a waiting client (DATA)
+async fn handle_home_server(
+    mut stream: TcpStream,
+    tokens: &HashMap<String, String>,
+    state: &RelayState,
+) -> Result<(), String> {
+    let challenge = Uuid::new_v4().simple().to_string();
+    write_message(&mut stream, &RelayMessage::Challenge(challenge.clone())).await?;
+    let message = tokio::time::timeout(HANDSHAKE_TIMEOUT, read_message(&mut stream))
+        .await
+        .map_err(|_| "No handshake".to_string())??;
+
+    match message {
+        RelayMessage::Hello { name, signature } => {
+            if !tokens
+                .get(&name)
+                .is_some_and(|token| verify(token, &challenge, &signature))
+            {
+                warn!("Rejected home server '{name}' - unknown name or wrong token");
+                let rejection = RelayMessage::Err("unknown name or wrong token".to_string());
+                return write_message(&mut stream, &rejection).await;
+            }
+            write_message(&mut stream, &RelayMessage::Ok).await?;
+            control_connection(stream, name, state).await
+        }
+        RelayMessage::Data {
+            name,
+            id,
+            signature,
+        } => {
+            if !tokens
+                .get(&name)
+                .is_some_and(|token| verify(token, &id, &signature))
+            {
+                return Err(format!("Data connection for '{name}' with wrong signature"));
+            }
+            match state.pending.lock().unwrap().remove(&id) {
+                Some(waiting) => waiting
+                    .send(stream)
+                    .map_err(|_| format!("Client {id} gave up")),
+                None => Err(format!("No client {id} waiting")),
+            }
+        }
+        other => Err(format!("Unexpected {other:?}")),
+    }
+}
+
+/// forwards waiting clients to the home server until it disconnects (or connects anew)
+async fn control_connection(
+    mut stream: TcpStream,
+    name: String,
+    state: &RelayState,
+) -> Result<(), String> {
+    let (sender, mut clients) = mpsc::channel(64);
+    let replaced = state
+        .controls
+        .lock()
+        .unwrap()
+        .insert(name.clone(), sender.clone());
+    info!(
+        "Home server '{name}' online ({})",
+        stream
+            .peer_addr()
+            .map_or("?".to_string(), |a| a.to_string())
+    );
+    if replaced.is_some() {
+        debug!("Replaced previous control connection of '{name}'");
+    }
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    let mut buf = [0u8; 64];
+    let result = loop {
+        tokio::select! {
+            id = clients.recv() => match id {
+                Some(id) => {
+                    if let Err(e) = write_message(&mut stream, &RelayMessage::Connect(id)).await {
+                        break Err(e);
+                    }
+                }
+                // replaced by a newer control connection
+                None => break Ok(()),
+            },
+            _ = ping.tick() => {
+                if let Err(e) = write_message(&mut stream, &RelayMessage::Ping).await {
+                    break Err(e);
+                }
+            }
+            // home servers don't send anything after HELLO - this just notices the disconnect
+            read = stream.read(&mut buf) => match read {
+                Ok(0) | Err(_) => break Err("disconnected".to_string()),
+                Ok(_) => {}
+            },
+        }
+    };
+
+    let mut controls = state.controls.lock().unwrap();
+    if controls
+        .get(&name)
+        .is_some_and(|current| current.same_channel(&sender))
+    {
+        controls.remove(&name);
+        info!("Home server '{name}' offline");
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_pairings() {
+        assert_eq!(
+            Ok(vec![
+                Pairing {
+                    name: "home".to_string(),
+                    token: "abc".to_string(),
+                    public_port: 4000,
+                },
+                Pairing {
+                    name: "office-nas".to_string(),
+                    token: "d:e@f".to_string(),
+                    public_port: 4001,
+                },
+            ]),
+            parse_pairings("home:abc@4000, office-nas:d:e@f@4001,")
+        );
+        assert_eq!(Ok(vec![]), parse_pairings(""));
+        assert!(parse_pairings("home:abc").is_err());
+        assert!(parse_pairings("home:@4000").is_err());
+        assert!(parse_pairings("ho me:abc@4000").is_err());
+        assert!(parse_pairings("home:abc@port").is_err());
+    }
+}
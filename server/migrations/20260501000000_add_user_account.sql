@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS user_account (
+    id         INTEGER  PRIMARY KEY NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    name       TEXT     NOT NULL UNIQUE
+);
+
+CREATE TRIGGER user_account_updated_at
+AFTER UPDATE ON user_account
+FOR EACH ROW
+BEGIN
+    UPDATE user_account SET updated_at = CURRENT_TIMESTAMP WHERE id = OLD.id;
+END;
+
+-- NULL owner = shared (visible to clients of every user, like before)
+ALTER TABLE client
+    ADD COLUMN owner_id INTEGER REFERENCES user_account(id) ON DELETE SET NULL;
+
+ALTER TABLE server_watch_group
+    ADD COLUMN owner_id INTEGER REFERENCES user_account(id) ON DELETE SET NULL;
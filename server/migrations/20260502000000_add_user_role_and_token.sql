@@ -0,0 +1,9 @@
+-- 'admin' may mutate everything, 'read_only' may only look at configs, events and monitoring
+ALTER TABLE user_account
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'read_only' CHECK (role IN ('admin', 'read_only'));
+
+-- sha256 (hex) of the bearer token - the plain token is only shown once on creation
+ALTER TABLE user_account
+    ADD COLUMN api_token_hash TEXT;
+
+CREATE UNIQUE INDEX user_account_api_token_hash ON user_account (api_token_hash);
@@ -0,0 +1,7 @@
+-- full-text index over the current files of all watch groups
+-- content is only filled for small utf-8 files (see handler::search)
+CREATE VIRTUAL TABLE file_search USING fts5(
+    relative_path,
+    content,
+    watch_group_id UNINDEXED
+);
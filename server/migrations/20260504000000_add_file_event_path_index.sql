@@ -0,0 +1,2 @@
+-- history lookups per path (see SqliteFileHistory)
+CREATE INDEX idx_file_event_wg_path_time ON file_event (watch_group_id, relative_path, utc_millis);
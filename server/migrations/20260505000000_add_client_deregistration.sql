@@ -0,0 +1,7 @@
+-- deregistered clients are kept as tombstone: their file events keep a valid reference
+-- and the id can't silently register again
+ALTER TABLE client ADD COLUMN deregistered_at DATETIME;
+
+-- owner of anonymized file events
+INSERT INTO client (id, host_name, min_poll_interval_in_ms, deregistered_at)
+VALUES ('e5f6a7b8-9c0d-4e1f-8a2b-3c4d5e6f7a8b', 'deleted', 0, CURRENT_TIMESTAMP);
@@ -0,0 +1,17 @@
+-- watch group assignments (and poll interval) given to newly registering clients
+-- whose host name matches the pattern (`*` and `?` wildcards)
+CREATE TABLE registration_template (
+    id                      INTEGER PRIMARY KEY NOT NULL,
+    host_pattern            TEXT    NOT NULL,
+    server_watch_group_id   INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    path_to_monitor         TEXT    NOT NULL,
+    exclude_dot_dirs        BOOLEAN NOT NULL DEFAULT 1,
+    -- NULL = keep the default poll interval
+    min_poll_interval_in_ms INTEGER
+);
+
+CREATE TABLE registration_template_excluded_dir (
+    id                    INTEGER PRIMARY KEY NOT NULL,
+    registration_template INTEGER NOT NULL REFERENCES registration_template(id) ON DELETE CASCADE,
+    exclude_dir           TEXT    NOT NULL
+);
@@ -0,0 +1,4 @@
+-- paused clients (or single watch groups of a client) get no sync instructions
+-- and can't upload or delete until resumed
+ALTER TABLE client ADD COLUMN paused BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE client_watch_group ADD COLUMN paused BOOLEAN NOT NULL DEFAULT 0;
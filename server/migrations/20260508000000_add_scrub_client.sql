@@ -0,0 +1,3 @@
+-- author of the file events recorded when the scrub repairs the history
+INSERT INTO client (id, host_name, min_poll_interval_in_ms)
+VALUES ('a7b8c9d0-1e2f-4a3b-8c4d-5e6f7a8b9c0d', 'scrub', 0);
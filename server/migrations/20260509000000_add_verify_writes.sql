@@ -0,0 +1,2 @@
+-- paranoid mode: uploads get re-hashed on disk and compared to the transfer hash
+ALTER TABLE server_watch_group ADD COLUMN verify_writes BOOLEAN NOT NULL DEFAULT 0;
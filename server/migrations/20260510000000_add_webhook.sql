@@ -0,0 +1,8 @@
+-- outbound webhooks - every change / delete event of the watch group is posted to the url
+CREATE TABLE webhook (
+    id                    INTEGER PRIMARY KEY NOT NULL,
+    server_watch_group_id INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    url                   TEXT    NOT NULL,
+    -- NULL = unsigned payloads
+    secret                TEXT
+);
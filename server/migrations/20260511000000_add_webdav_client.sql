@@ -0,0 +1,3 @@
+-- author of the file events recorded for writes through the webdav endpoint
+INSERT INTO client (id, host_name, min_poll_interval_in_ms)
+VALUES ('b8c9d0e1-2f3a-4b4c-9d5e-6f7a8b9c0d1e', 'webdav', 0);
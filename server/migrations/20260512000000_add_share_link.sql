@@ -0,0 +1,12 @@
+-- public links into a watch group - 'inbox' links accept anonymous uploads into the folder
+CREATE TABLE share_link (
+    token                 TEXT     NOT NULL PRIMARY KEY,
+    server_watch_group_id INTEGER  NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    kind                  TEXT     NOT NULL CHECK (kind IN ('inbox')),
+    folder                TEXT     NOT NULL,
+    created_at            DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- author of the file events recorded for uploads through inbox links
+INSERT INTO client (id, host_name, min_poll_interval_in_ms)
+VALUES ('c9d0e1f2-3a4b-4c5d-8e6f-7a8b9c0d1e2f', 'inbox', 0);
@@ -0,0 +1,2 @@
+-- clients sync watch groups with a higher priority first
+ALTER TABLE server_watch_group ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- last scan problem a client reported for a watch group (limits exceeded or close to them)
+ALTER TABLE client_watch_group ADD COLUMN scan_warning TEXT;
@@ -0,0 +1,10 @@
+-- bytes sent / received per hour, client and watch group (GET /api/stats/bandwidth)
+CREATE TABLE transfer_stats (
+    -- start of the hour (utc millis)
+    bucket_start_millis INTEGER NOT NULL,
+    client_id           TEXT    NOT NULL,
+    watch_group_id      INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    uploaded_bytes      INTEGER NOT NULL DEFAULT 0,
+    downloaded_bytes    INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (bucket_start_millis, client_id, watch_group_id)
+);
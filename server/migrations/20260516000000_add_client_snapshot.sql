@@ -0,0 +1,11 @@
+-- file list of each client's latest sync per watch group (GET /api/file-status/{wg_id})
+CREATE TABLE client_snapshot (
+    client_id          TEXT    NOT NULL REFERENCES client(id) ON DELETE CASCADE,
+    watch_group_id     INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    reported_at_millis INTEGER NOT NULL,
+    -- hash of the file list - an unchanged list only bumps reported_at_millis
+    fingerprint        INTEGER NOT NULL,
+    -- json array of FileDescription
+    files              TEXT    NOT NULL,
+    PRIMARY KEY (client_id, watch_group_id)
+);
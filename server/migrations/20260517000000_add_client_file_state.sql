@@ -0,0 +1,26 @@
+-- one row per file instead of a json list - lookups by path and queries of a client's view
+-- replace the snapshot, which is rebuilt with the next sync of each client
+DROP TABLE client_snapshot;
+
+-- latest sync of each client per watch group
+CREATE TABLE client_scan (
+    client_id          TEXT    NOT NULL REFERENCES client(id) ON DELETE CASCADE,
+    watch_group_id     INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    reported_at_millis INTEGER NOT NULL,
+    -- hash of the file list - an unchanged list only bumps reported_at_millis
+    fingerprint        INTEGER NOT NULL,
+    PRIMARY KEY (client_id, watch_group_id)
+);
+
+-- the files reported with it
+CREATE TABLE client_file_state (
+    client_id               TEXT    NOT NULL,
+    watch_group_id          INTEGER NOT NULL,
+    relative_path           TEXT    NOT NULL,
+    size_in_bytes           INTEGER NOT NULL,
+    last_updated_utc_millis INTEGER NOT NULL,
+    file_type               TEXT    NOT NULL,
+    PRIMARY KEY (client_id, watch_group_id, relative_path),
+    FOREIGN KEY (client_id, watch_group_id)
+        REFERENCES client_scan(client_id, watch_group_id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- publish mode: accepted files are mirrored into this server-local dir (e.g. a web root)
+ALTER TABLE server_watch_group ADD COLUMN publish_path TEXT;
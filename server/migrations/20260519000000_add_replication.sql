@@ -0,0 +1,9 @@
+-- replicas: how far the event stream of the primary was applied (file_event rowid on the primary)
+CREATE TABLE replication_cursor (
+    primary_url TEXT    PRIMARY KEY NOT NULL,
+    applied_seq INTEGER NOT NULL
+);
+
+-- author of the file events a replica pulled from its primary
+INSERT INTO client (id, host_name, min_poll_interval_in_ms)
+VALUES ('f8a9b0c1-2d3e-4f5a-9b6c-7d8e9f0a1b2c', 'replication', 0);
@@ -0,0 +1,14 @@
+-- server instances sharing this DB (refreshed by a heartbeat in cluster mode)
+CREATE TABLE server_instance (
+    instance_id  TEXT    PRIMARY KEY NOT NULL,
+    version      TEXT    NOT NULL,
+    started_at   INTEGER NOT NULL,
+    last_seen_at INTEGER NOT NULL
+);
+
+-- leader election of scheduled jobs: a job runs on the instance holding its unexpired lease
+CREATE TABLE scheduler_lease (
+    name       TEXT    PRIMARY KEY NOT NULL,
+    holder     TEXT    NOT NULL,
+    expires_at INTEGER NOT NULL
+);
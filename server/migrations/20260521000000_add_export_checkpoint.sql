@@ -0,0 +1,7 @@
+-- how far a consumer of the event export got (file_event rowid, like the replication stream) -
+-- set by the consumer over http or by the built-in NATS sink once delivered
+CREATE TABLE export_checkpoint (
+    name       TEXT    PRIMARY KEY NOT NULL,
+    seq        INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
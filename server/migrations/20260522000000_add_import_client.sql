@@ -0,0 +1,3 @@
+-- author of the file events recorded when a directory synced by another tool is imported
+INSERT INTO client (id, host_name, min_poll_interval_in_ms)
+VALUES ('b9c0d1e2-3f4a-4b5c-8d6e-7f8a9b0c1d2e', 'import', 0);
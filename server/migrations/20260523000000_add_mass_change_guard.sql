@@ -0,0 +1,4 @@
+-- mass change guard: per watch group thresholds - NULL falls back to the server defaults
+ALTER TABLE server_watch_group ADD COLUMN mass_change_percent INTEGER;
+ALTER TABLE server_watch_group ADD COLUMN mass_change_window_minutes INTEGER;
+ALTER TABLE server_watch_group ADD COLUMN mass_change_min_files INTEGER;
@@ -0,0 +1,11 @@
+-- two-phase delete: deletes of clients wait here (hidden from sync) until remove_at
+CREATE TABLE pending_delete (
+    id             INTEGER PRIMARY KEY NOT NULL,
+    watch_group_id INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    relative_path  TEXT    NOT NULL,
+    client_id      TEXT    NOT NULL,
+    client_host    TEXT,
+    requested_at   INTEGER NOT NULL,
+    remove_at      INTEGER NOT NULL,
+    UNIQUE (watch_group_id, relative_path)
+);
@@ -0,0 +1,13 @@
+-- times (server local time) a client must not sync, e.g. during nightly renders - windows with
+-- end_minute <= start_minute reach into the next day
+CREATE TABLE client_sync_blackout (
+    id           INTEGER PRIMARY KEY NOT NULL,
+    client_id    TEXT    NOT NULL REFERENCES client(id) ON DELETE CASCADE,
+    -- bit 0 = monday ... bit 6 = sunday
+    weekdays     INTEGER NOT NULL DEFAULT 127,
+    start_minute INTEGER NOT NULL,
+    end_minute   INTEGER NOT NULL,
+    note         TEXT
+);
+
+CREATE INDEX idx_client_sync_blackout_client ON client_sync_blackout(client_id);
@@ -0,0 +1,15 @@
+-- write-ahead journal of uploads being moved into place - recovered on the next start
+CREATE TABLE write_intent (
+    id             TEXT    PRIMARY KEY NOT NULL,
+    instance_id    TEXT    NOT NULL,
+    watch_group_id INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    relative_path  TEXT    NOT NULL,
+    temp_path      TEXT    NOT NULL,
+    target_path    TEXT    NOT NULL,
+    size_in_bytes  INTEGER NOT NULL,
+    utc_millis     INTEGER NOT NULL,
+    client_id      TEXT,
+    client_host    TEXT
+);
+
+CREATE INDEX idx_write_intent_instance ON write_intent(instance_id);
@@ -0,0 +1,2 @@
+-- how hard uploads are pushed to disk: none | fsync-file | fsync-file+dir
+ALTER TABLE server_watch_group ADD COLUMN durability TEXT NOT NULL DEFAULT 'none';
@@ -0,0 +1,8 @@
+-- theme and layout of the web app per account - 0 = the bootstrap admin token (no user row),
+-- rows of users are removed together with the user
+CREATE TABLE ui_settings (
+    account_id   INTEGER PRIMARY KEY NOT NULL,
+    theme        TEXT    NOT NULL DEFAULT 'dark',
+    density      TEXT    NOT NULL DEFAULT 'comfortable',
+    default_page TEXT    NOT NULL DEFAULT 'clients'
+);
@@ -0,0 +1,6 @@
+-- cron expressions of the scheduled jobs changed via the api - they win over SCHEDULE_* env vars,
+-- a NULL expression turns the job off
+CREATE TABLE schedule (
+    job        TEXT PRIMARY KEY NOT NULL,
+    expression TEXT
+);
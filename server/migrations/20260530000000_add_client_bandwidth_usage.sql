@@ -0,0 +1,11 @@
+-- bytes a client transferred per calendar month (utc, 'YYYY-MM') as counted by the client itself,
+-- with the caps of its config.yaml at the time of its last report (0 = none)
+CREATE TABLE client_bandwidth_usage (
+    client_id        TEXT    NOT NULL,
+    month            TEXT    NOT NULL,
+    uploaded_bytes   INTEGER NOT NULL DEFAULT 0,
+    downloaded_bytes INTEGER NOT NULL DEFAULT 0,
+    soft_cap_bytes   INTEGER NOT NULL DEFAULT 0,
+    hard_cap_bytes   INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (client_id, month)
+);
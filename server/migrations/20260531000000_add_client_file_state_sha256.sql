@@ -0,0 +1,2 @@
+-- sha256 of the content as reported by the client - NULL until clients hash their scans
+ALTER TABLE client_file_state ADD COLUMN sha256 TEXT;
@@ -0,0 +1,6 @@
+-- cluster mode is gone until there is a shared database backend
+DROP TABLE server_instance;
+DROP TABLE scheduler_lease;
+
+DROP INDEX idx_write_intent_instance;
+ALTER TABLE write_intent DROP COLUMN instance_id;
@@ -0,0 +1,9 @@
+-- sha256 of the current version of a file, as verified after the upload (verify writes)
+CREATE TABLE file_hash (
+    watch_group_id INTEGER NOT NULL REFERENCES server_watch_group(id) ON DELETE CASCADE,
+    relative_path  TEXT    NOT NULL,
+    -- time of the file event of the hashed version
+    utc_millis     INTEGER NOT NULL,
+    sha256         TEXT    NOT NULL,
+    PRIMARY KEY (watch_group_id, relative_path)
+);
@@ -0,0 +1,248 @@
+use crate::AppState;
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, COOKIE, WWW_AUTHENTICATE};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::MethodRouter;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use sha2::{Digest, Sha256};
+use shared::dtos::UserRole;
+use shared::endpoint::API_TOKEN_COOKIE_KEY;
+use std::env;
+use std::sync::LazyLock;
+use tracing::{error, warn};
+
+/// bootstrap admin token - auth on /api is only enforced if this is set
+static ADMIN_TOKEN: LazyLock<Option<String>> =
+    LazyLock::new(|| env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty()));
+
+/// what a route requires from the caller's role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// view configs, events, monitoring
+    Read,
+    /// create, change or delete anything
+    Write,
+}
+
+/// who sent the request - attached next to the role by [`authenticate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Account {
+    /// holder of ADMIN_TOKEN - or anyone while auth is disabled
+    Bootstrap,
+    User(i64),
+}
+
+impl Account {
+    /// key of per account data (e.g. ui settings) - user ids start at 1
+    pub fn id(&self) -> i64 {
+        match self {
+            Account::Bootstrap => 0,
+            Account::User(id) => *id,
+        }
+    }
+}
+
+fn is_allowed(role: UserRole, permission: Permission) -> bool {
+    match (role, permission) {
+        (UserRole::Admin, _) => true,
+        (UserRole::ReadOnly, Permission::Read) => true,
+        (UserRole::ReadOnly, Permission::Write) => false,
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ADMIN_TOKEN.is_some()
+}
+
+/// only the hash is persisted
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// resolves the account and role of the caller and attaches them to the request (for [`read`] /
+/// [`write`])
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let (account, role) = resolve_caller(&state, req.headers()).await?;
+    req.extensions_mut().insert(account);
+    req.extensions_mut().insert(role);
+    Ok(next.run(req).await)
+}
+
+/// like [`authenticate`] but asks for basic auth on 401 - so file managers prompt for the token
+pub async fn authenticate_basic(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    match resolve_caller(&state, req.headers()).await {
+        Ok((account, role)) => {
+            req.extensions_mut().insert(account);
+            req.extensions_mut().insert(role);
+            next.run(req).await
+        }
+        Err((StatusCode::UNAUTHORIZED, message)) => (
+            StatusCode::UNAUTHORIZED,
+            [(
+                WWW_AUTHENTICATE,
+                HeaderValue::from_static("Basic realm=\"rust-file-sync\""),
+            )],
+            message,
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn resolve_caller(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(Account, UserRole), (StatusCode, String)> {
+    let Some(admin_token) = ADMIN_TOKEN.as_deref() else {
+        return Ok((Account::Bootstrap, UserRole::Admin));
+    };
+    let token =
+        api_token(headers).ok_or((StatusCode::UNAUTHORIZED, "Missing api token".to_string()))?;
+    if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) {
+        return Ok((Account::Bootstrap, UserRole::Admin));
+    }
+    let (user_id, role) = state
+        .db
+        .user()
+        .get_by_api_token_hash(&hash_token(&token))
+        .await
+        .map_err(|e| {
+            error!("Failed to look up api token: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .ok_or_else(|| {
+            warn!("Rejected request with unknown api token");
+            (StatusCode::UNAUTHORIZED, "Invalid api token".to_string())
+        })?;
+    Ok((Account::User(user_id), role))
+}
+
+/// route may be used by every authenticated role
+pub fn read(route: MethodRouter<AppState>) -> MethodRouter<AppState> {
+    route.route_layer(middleware::from_fn(|req: Request, next: Next| {
+        require(Permission::Read, req, next)
+    }))
+}
+
+/// route may only be used by admins
+pub fn write(route: MethodRouter<AppState>) -> MethodRouter<AppState> {
+    route.route_layer(middleware::from_fn(|req: Request, next: Next| {
+        require(Permission::Write, req, next)
+    }))
+}
+
+/// reading methods (incl. webdav PROPFIND) may be used by every role - all others only by admins
+///
+/// `layer` instead of `route_layer` - so it also guards routes made with `any` (method fallback)
+pub fn by_method(route: MethodRouter<AppState>) -> MethodRouter<AppState> {
+    route.layer(middleware::from_fn(|req: Request, next: Next| {
+        let permission = if is_reading(req.method()) {
+            Permission::Read
+        } else {
+            Permission::Write
+        };
+        require(permission, req, next)
+    }))
+}
+
+fn is_reading(method: &Method) -> bool {
+    matches!(method.as_str(), "GET" | "HEAD" | "OPTIONS" | "PROPFIND")
+}
+
+async fn require(
+    permission: Permission,
+    req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let role = req.extensions().get::<UserRole>().copied();
+    match role {
+        Some(role) if is_allowed(role, permission) => Ok(next.run(req).await),
+        Some(role) => Err((
+            StatusCode::FORBIDDEN,
+            format!("Role '{}' may not do this", role.as_str()),
+        )),
+        None => {
+            error!("Route guarded without authentication layer");
+            Err((StatusCode::FORBIDDEN, "Not authenticated".to_string()))
+        }
+    }
+}
+
+/// `Authorization: Bearer <token>`, the api token cookie (browser frontends) or
+/// `Authorization: Basic` with the token as password (webdav - the user name is ignored)
+fn api_token(headers: &HeaderMap) -> Option<String> {
+    let authorization = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok());
+    if let Some(bearer) = authorization.and_then(|v| v.strip_prefix("Bearer ")) {
+        return Some(bearer.trim().to_string());
+    }
+    if let Some(basic) = authorization.and_then(|v| v.strip_prefix("Basic ")) {
+        return BASE64
+            .decode(basic.trim())
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .and_then(|credentials| {
+                credentials
+                    .split_once(':')
+                    .map(|(_, password)| password.to_string())
+            });
+    }
+
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(key, _)| *key == API_TOKEN_COOKIE_KEY)
+        .map(|(_, value)| value.to_string())
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn should_only_allow_admin_to_write() {
+        assert!(is_allowed(UserRole::Admin, Permission::Write));
+        assert!(is_allowed(UserRole::ReadOnly, Permission::Read));
+        assert!(!is_allowed(UserRole::ReadOnly, Permission::Write));
+    }
+
+    #[test]
+    fn should_read_token_from_bearer_or_cookie() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, api_token(&headers));
+
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_static("theme=dark; api_token=abc"),
+        );
+        assert_eq!(Some("abc"), api_token(&headers).as_deref());
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer xyz"));
+        assert_eq!(Some("xyz"), api_token(&headers).as_deref());
+
+        // "anyone:t0ken"
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Basic YW55b25lOnQwa2Vu"),
+        );
+        assert_eq!(Some("t0ken"), api_token(&headers).as_deref());
+    }
+}
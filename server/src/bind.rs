@@ -0,0 +1,171 @@
+use socket2::{Domain, Socket, Type};
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// addresses the server listens on
+#[derive(Debug, Clone, PartialEq)]
+pub enum BindAddr {
+    Tcp(SocketAddr),
+    /// plain http only - meant for a reverse proxy on the same host
+    Unix(PathBuf),
+}
+
+impl FromStr for BindAddr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.strip_prefix("unix:") {
+            Some("") => Err("unix: without a path".to_string()),
+            Some(path) => Ok(BindAddr::Unix(PathBuf::from(path))),
+            None => value.parse().map(BindAddr::Tcp).map_err(|_| {
+                format!("Invalid bind address '{value}' - expected ip:port or unix:path")
+            }),
+        }
+    }
+}
+
+impl Display for BindAddr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindAddr::Tcp(addr) => write!(f, "{addr}"),
+            BindAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// `BIND_ADDRS=0.0.0.0:3000,[::]:3000,unix:/run/rust-file-sync.sock` (default: 0.0.0.0:3000)
+pub fn from_env() -> Result<Vec<BindAddr>, String> {
+    parse_bind_addrs(&env::var("BIND_ADDRS").unwrap_or_else(|_| "0.0.0.0:3000".to_string()))
+}
+
+fn parse_bind_addrs(value: &str) -> Result<Vec<BindAddr>, String> {
+    let addrs = value
+        .split(',')
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .map(BindAddr::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    if addrs.is_empty() {
+        return Err("BIND_ADDRS without any address".to_string());
+    }
+    Ok(addrs)
+}
+
+pub enum Listener {
+    /// std listener - axum-server (tls) and axum (plain) both take it
+    Tcp(std::net::TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+}
+
+impl Listener {
+    pub fn bind(addr: &BindAddr) -> io::Result<Self> {
+        match addr {
+            BindAddr::Tcp(addr) => bind_tcp(*addr).map(Listener::Tcp),
+            #[cfg(unix)]
+            BindAddr::Unix(path) => {
+                use std::os::unix::fs::FileTypeExt;
+                // left behind by the last run - anything else at that path is kept
+                if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+                    std::fs::remove_file(path)?;
+                }
+                tokio::net::UnixListener::bind(path).map(|l| Listener::Unix(l, path.clone()))
+            }
+            #[cfg(not(unix))]
+            BindAddr::Unix(path) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} needs unix sockets", path.display()),
+            )),
+        }
+    }
+
+    /// actual address (port 0 gets one assigned) - `None` for unix sockets
+    pub fn tcp_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Listener::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Listener::Unix(..) => None,
+        }
+    }
+
+    /// e.g. `https://[::]:3000` or `unix:/run/rust-file-sync.sock`
+    pub fn endpoint(&self, tls: bool) -> String {
+        match self {
+            Listener::Tcp(_) => {
+                let scheme = if tls { "https" } else { "http" };
+                let addr = self.tcp_addr().map_or("?".to_string(), |a| a.to_string());
+                format!("{scheme}://{addr}")
+            }
+            #[cfg(unix)]
+            Listener::Unix(_, path) => BindAddr::Unix(path.clone()).to_string(),
+        }
+    }
+}
+
+fn bind_tcp(addr: SocketAddr) -> io::Result<std::net::TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    // [::] would take the ipv4 port as well otherwise - 0.0.0.0 and [::] couldn't be bound together
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
+
+/// where local connections (e.g. relayed ones) reach the server
+pub fn loopback(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(v4) if v4.ip().is_unspecified() => {
+            SocketAddr::from(([127, 0, 0, 1], v4.port()))
+        }
+        SocketAddr::V6(v6) if v6.ip().is_unspecified() => {
+            SocketAddr::from((std::net::Ipv6Addr::LOCALHOST, v6.port()))
+        }
+        addr => addr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_bind_addrs() {
+        assert_eq!(
+            Ok(vec![
+                BindAddr::Tcp(SocketAddr::from(([0, 0, 0, 0], 3000))),
+                BindAddr::Tcp("[::]:3000".parse().unwrap()),
+                BindAddr::Unix(PathBuf::from("/run/rfs.sock")),
+            ]),
+            parse_bind_addrs("0.0.0.0:3000, [::]:3000,unix:/run/rfs.sock,")
+        );
+        assert!(parse_bind_addrs("").is_err());
+        assert!(parse_bind_addrs("localhost:3000").is_err());
+        assert!(parse_bind_addrs("::1:3000").is_err());
+        assert!(parse_bind_addrs("unix:").is_err());
+    }
+
+    #[tokio::test]
+    async fn should_bind_ipv4_and_ipv6_on_the_same_port() {
+        let v4 = Listener::bind(&BindAddr::Tcp(SocketAddr::from(([127, 0, 0, 1], 0)))).unwrap();
+        let port = v4.tcp_addr().unwrap().port();
+        let v6 = Listener::bind(&BindAddr::Tcp(SocketAddr::from((
+            std::net::Ipv6Addr::UNSPECIFIED,
+            port,
+        ))));
+
+        // hosts without ipv6 can't bind [::] at all
+        if let Ok(v6) = v6 {
+            assert_eq!(format!("http://[::]:{port}"), v6.endpoint(false));
+        }
+        assert_eq!(format!("https://127.0.0.1:{port}"), v4.endpoint(true));
+    }
+}
@@ -0,0 +1,196 @@
+//! fault injection into client traffic - only compiled with the `chaos` feature
+//!
+//! turned on via PUT /api/admin/chaos, so the retry & resume logic of clients can be exercised
+
+use crate::AppState;
+use axum::Json;
+use axum::body::{Body, HttpBody};
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::{StreamExt, future};
+use shared::dtos::ChaosConfigDto;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// upper bound of `delay_ms` - longer than any client timeout anyway
+const MAX_DELAY_MS: u64 = 10 * 60 * 1000;
+/// sync, up- & downloads over http(s) / HTTP/3 and grpc
+const CLIENT_PATH_PREFIXES: [&str; 2] = ["/sys/", "/rfs.sync."];
+
+#[derive(Default)]
+pub struct Chaos {
+    config: Mutex<ChaosConfigDto>,
+}
+
+impl Chaos {
+    fn get(&self) -> ChaosConfigDto {
+        self.config.lock().unwrap().clone()
+    }
+}
+
+/// GET /api/admin/chaos
+pub async fn api_get_chaos(State(state): State<AppState>) -> Json<ChaosConfigDto> {
+    Json(state.chaos.get())
+}
+
+/// PUT /api/admin/chaos - all probabilities 0 turns it off again
+pub async fn api_set_chaos(
+    State(state): State<AppState>,
+    Json(config): Json<ChaosConfigDto>,
+) -> Result<Json<ChaosConfigDto>, (StatusCode, String)> {
+    validate(&config).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if is_off(&config) {
+        info!("Chaos layer turned off");
+    } else {
+        warn!("Chaos layer active - {:?}", config);
+    }
+    *state.chaos.config.lock().unwrap() = config.clone();
+    Ok(Json(config))
+}
+
+/// middleware of the whole app - only client traffic is touched
+pub async fn inject(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let path = req.uri().path().to_string();
+    let config = state.chaos.get();
+    if is_off(&config) || !CLIENT_PATH_PREFIXES.iter().any(|p| path.starts_with(p)) {
+        return next.run(req).await;
+    }
+
+    if roll(config.delay_probability) {
+        info!(path, "Chaos: delaying request by {}ms", config.delay_ms);
+        tokio::time::sleep(Duration::from_millis(config.delay_ms)).await;
+    }
+    if roll(config.error_probability) {
+        info!(path, "Chaos: replying 500");
+        return (StatusCode::INTERNAL_SERVER_ERROR, "chaos: injected error").into_response();
+    }
+    let (parts, body) = req.into_parts();
+    let req = Request::from_parts(parts, mangle(body, &config));
+    let (parts, body) = next.run(req).await.into_parts();
+    Response::from_parts(parts, mangle(body, &config))
+}
+
+fn validate(config: &ChaosConfigDto) -> Result<(), String> {
+    let probabilities = [
+        ("error_probability", config.error_probability),
+        ("delay_probability", config.delay_probability),
+        ("truncate_probability", config.truncate_probability),
+        ("drop_chunk_probability", config.drop_chunk_probability),
+    ];
+    if let Some((name, value)) = probabilities
+        .iter()
+        .find(|(_, value)| !(0.0..=1.0).contains(value))
+    {
+        return Err(format!("{name} must be between 0 and 1 (got {value})"));
+    }
+    if config.delay_ms > MAX_DELAY_MS {
+        return Err(format!("delay_ms must not exceed {MAX_DELAY_MS}"));
+    }
+    Ok(())
+}
+
+fn is_off(config: &ChaosConfigDto) -> bool {
+    config.error_probability == 0.0
+        && config.delay_probability == 0.0
+        && config.truncate_probability == 0.0
+        && config.drop_chunk_probability == 0.0
+}
+
+fn roll(probability: f64) -> bool {
+    probability > 0.0 && fastrand::f64() < probability
+}
+
+/// cuts the body off at a random byte and / or drops some of its chunks
+fn mangle(body: Body, config: &ChaosConfigDto) -> Body {
+    let truncate_at = roll(config.truncate_probability).then(|| {
+        let size = body.size_hint().exact().unwrap_or(0);
+        fastrand::u64(0..size.max(1))
+    });
+    let drop_probability = config.drop_chunk_probability;
+    if truncate_at.is_none() && drop_probability == 0.0 {
+        return body;
+    }
+    if let Some(at) = truncate_at {
+        info!("Chaos: cutting body off after {} bytes", at);
+    }
+
+    let stream = body
+        .into_data_stream()
+        .scan(truncate_at, |remaining, chunk| {
+            let chunk = match (chunk, remaining.as_mut()) {
+                // cut off - no more chunks
+                (_, Some(0)) => None,
+                (Ok(mut bytes), Some(left)) => {
+                    let keep = (*left).min(bytes.len() as u64);
+                    bytes.truncate(keep as usize);
+                    *left -= keep;
+                    Some(Ok(bytes))
+                }
+                (chunk, _) => Some(chunk),
+            };
+            future::ready(chunk)
+        })
+        .filter(move |chunk| future::ready(!(chunk.is_ok() && roll(drop_probability))));
+    Body::from_stream(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    #[test]
+    fn should_validate_config() {
+        let config = ChaosConfigDto {
+            error_probability: 0.5,
+            delay_ms: 1000,
+            ..Default::default()
+        };
+        assert_eq!(Ok(()), validate(&config));
+        assert!(is_off(&ChaosConfigDto::default()));
+        assert!(!is_off(&config));
+
+        let config = ChaosConfigDto {
+            truncate_probability: 1.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            Err("truncate_probability must be between 0 and 1 (got 1.5)".to_string()),
+            validate(&config)
+        );
+        let config = ChaosConfigDto {
+            delay_ms: MAX_DELAY_MS + 1,
+            ..Default::default()
+        };
+        assert!(validate(&config).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_mangle_bodies() {
+        let content = vec![7u8; 1000];
+
+        let untouched = mangle(Body::from(content.clone()), &ChaosConfigDto::default());
+        assert_eq!(content, to_bytes(untouched, usize::MAX).await.unwrap());
+
+        let config = ChaosConfigDto {
+            truncate_probability: 1.0,
+            ..Default::default()
+        };
+        let truncated = to_bytes(mangle(Body::from(content.clone()), &config), usize::MAX)
+            .await
+            .unwrap();
+        assert!(truncated.len() < content.len());
+
+        let config = ChaosConfigDto {
+            drop_chunk_probability: 1.0,
+            ..Default::default()
+        };
+        let dropped = to_bytes(mangle(Body::from(content), &config), usize::MAX)
+            .await
+            .unwrap();
+        assert!(dropped.is_empty());
+    }
+}
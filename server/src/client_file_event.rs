@@ -15,14 +15,20 @@ pub struct ClientFileEvent {
     /// the size of the uploaded file
     pub content_size: usize,
     pub watch_group_id: i64,
+    /// sha256 of the received content - only computed for watch groups verifying writes
+    pub content_hash: Option<String>,
 }
 
+#[derive(Debug)]
 pub struct ClientFileEventDto {
     pub utc_millis: Option<UtcMillis>,
     pub relative_path: Option<Vec<String>>,
     pub temp_file_path: Option<PathBuf>,
     pub content_size: Option<usize>,
     pub watch_group_id: i64,
+    pub content_hash: Option<String>,
+    /// set if the upload resolves a keep-both conflict - the server's version the client kept
+    pub conflict_kept: Option<UtcMillis>,
 }
 
 impl From<ClientFileEvent> for FileEvent {
@@ -51,6 +57,7 @@ impl TryFrom<ClientFileEventDto> for ClientFileEvent {
             temp_file_path: dto.temp_file_path,
             content_size: dto.content_size.unwrap_or(0),
             watch_group_id: dto.watch_group_id,
+            content_hash: dto.content_hash,
         })
     }
 }
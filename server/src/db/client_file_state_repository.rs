@@ -0,0 +1,429 @@
+use shared::dtos::{ClientFileDto, FileDescription};
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use sqlx::SqlitePool;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+pub struct ClientFileStateRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+/// what a client assigned to the watch group reported about one path
+pub struct ClientFileSnapshot {
+    pub client_id: String,
+    pub host_name: String,
+    /// None if the client didn't sync the watch group yet
+    pub reported_at: Option<UtcMillis>,
+    /// None if the client doesn't have the file
+    pub file: Option<FileDescription>,
+}
+
+impl<'a> ClientFileStateRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Keeps the file list of the client's latest sync. An unchanged list only updates the time
+    /// of the report - clients send the full list every few seconds.
+    pub async fn save(
+        &self,
+        client_id: &str,
+        watch_group_id: i64,
+        files: &[FileDescription],
+        at: UtcMillis,
+    ) -> Result<()> {
+        let reported_at_millis = at.as_u64() as i64;
+        let fingerprint = fingerprint(files);
+        let unchanged = sqlx::query!(
+            r#"
+            UPDATE client_scan SET reported_at_millis = ?
+            WHERE client_id = ? AND watch_group_id = ? AND fingerprint = ?
+            "#,
+            reported_at_millis,
+            client_id,
+            watch_group_id,
+            fingerprint
+        )
+        .execute(self.pool)
+        .await?;
+        if unchanged.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "DELETE FROM client_scan WHERE client_id = ? AND watch_group_id = ?",
+            client_id,
+            watch_group_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO client_scan (client_id, watch_group_id, reported_at_millis, fingerprint)
+            VALUES (?, ?, ?, ?)
+            "#,
+            client_id,
+            watch_group_id,
+            reported_at_millis,
+            fingerprint
+        )
+        .execute(&mut *tx)
+        .await?;
+        for file in files {
+            let relative_path = file.relative_path.to_serialized_string();
+            let size_in_bytes = file.size_in_bytes as i64;
+            let last_updated_utc_millis = file.last_updated_utc_millis.as_u64() as i64;
+            // first one wins if a client reports a path twice - like the sync diff
+            sqlx::query!(
+                r#"
+                INSERT OR IGNORE INTO client_file_state
+                    (client_id, watch_group_id, relative_path, size_in_bytes,
+                     last_updated_utc_millis, file_type)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+                client_id,
+                watch_group_id,
+                relative_path,
+                size_in_bytes,
+                last_updated_utc_millis,
+                file.file_type
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// one entry per registered client assigned to the watch group - ordered by host name
+    pub async fn get_file(
+        &self,
+        watch_group_id: i64,
+        path: &MatchablePath,
+    ) -> Result<Vec<ClientFileSnapshot>> {
+        let relative_path = path.to_serialized_string();
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                c.id AS "client_id!: String",
+                c.host_name,
+                s.reported_at_millis AS "reported_at_millis?: i64",
+                f.size_in_bytes AS "size_in_bytes?: i64",
+                f.last_updated_utc_millis AS "last_updated_utc_millis?: i64",
+                f.file_type AS "file_type?: String"
+            FROM client_watch_group cwg
+            INNER JOIN client c ON c.id = cwg.client_id
+            LEFT JOIN client_scan s
+                ON s.client_id = cwg.client_id AND s.watch_group_id = cwg.server_watch_group_id
+            LEFT JOIN client_file_state f
+                ON f.client_id = s.client_id
+                AND f.watch_group_id = s.watch_group_id
+                AND f.relative_path = ?
+            WHERE cwg.server_watch_group_id = ? AND c.deregistered_at IS NULL
+            ORDER BY c.host_name
+            "#,
+            relative_path,
+            watch_group_id
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| ClientFileSnapshot {
+                client_id: r.client_id,
+                host_name: r.host_name,
+                reported_at: r
+                    .reported_at_millis
+                    .map(|millis| UtcMillis::from(millis as u64)),
+                file: match (r.size_in_bytes, r.last_updated_utc_millis, r.file_type) {
+                    (Some(size), Some(millis), Some(file_type)) => {
+                        Some(description(path.clone(), size, millis, file_type))
+                    }
+                    _ => None,
+                },
+            })
+            .collect())
+    }
+
+    /// the file list of the client's latest sync of the watch group with its time -
+    /// None if there is none
+    pub async fn get_files(
+        &self,
+        client_id: &str,
+        watch_group_id: i64,
+    ) -> Result<Option<(UtcMillis, Vec<ClientFileDto>)>> {
+        let Some(reported_at_millis) = sqlx::query_scalar!(
+            r#"
+            SELECT reported_at_millis FROM client_scan
+            WHERE client_id = ? AND watch_group_id = ?
+            "#,
+            client_id,
+            watch_group_id
+        )
+        .fetch_optional(self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let files = sqlx::query!(
+            r#"
+            SELECT relative_path, size_in_bytes, last_updated_utc_millis, file_type, sha256
+            FROM client_file_state
+            WHERE client_id = ? AND watch_group_id = ?
+            ORDER BY relative_path
+            "#,
+            client_id,
+            watch_group_id
+        )
+        .fetch_all(self.pool)
+        .await?
+        .into_iter()
+        .map(|r| ClientFileDto {
+            file: description(
+                MatchablePath::from(r.relative_path.as_str()),
+                r.size_in_bytes,
+                r.last_updated_utc_millis,
+                r.file_type,
+            ),
+            sha256: r.sha256,
+        })
+        .collect();
+        Ok(Some((UtcMillis::from(reported_at_millis as u64), files)))
+    }
+
+    /// time of the client's latest sync per watch group
+    pub async fn get_report_times(&self, client_id: &str) -> Result<Vec<(i64, UtcMillis)>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT watch_group_id, reported_at_millis FROM client_scan
+            WHERE client_id = ?
+            "#,
+            client_id
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                (
+                    r.watch_group_id,
+                    UtcMillis::from(r.reported_at_millis as u64),
+                )
+            })
+            .collect())
+    }
+
+    /// Drops all file lists of the client - they're rebuilt with its next syncs. Returns how many
+    /// were dropped.
+    pub async fn delete_of_client(&self, client_id: &str) -> Result<u64> {
+        let result = sqlx::query!("DELETE FROM client_scan WHERE client_id = ?", client_id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Drops the file lists of syncs before `before` and of clients no longer assigned to the
+    /// watch group. Returns how many file lists were dropped.
+    pub async fn delete_stale(&self, before: UtcMillis) -> Result<u64> {
+        let before = before.as_u64() as i64;
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM client_scan
+            WHERE reported_at_millis < ?
+               OR NOT EXISTS (
+                   SELECT 1 FROM client_watch_group cwg
+                   WHERE cwg.client_id = client_scan.client_id
+                     AND cwg.server_watch_group_id = client_scan.watch_group_id
+               )
+            "#,
+            before
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+fn description(
+    relative_path: MatchablePath,
+    size_in_bytes: i64,
+    last_updated_utc_millis: i64,
+    file_type: String,
+) -> FileDescription {
+    FileDescription {
+        file_name: relative_path.tail(),
+        relative_path,
+        size_in_bytes: size_in_bytes as u64,
+        file_type,
+        last_updated_utc_millis: UtcMillis::from(last_updated_utc_millis as u64),
+    }
+}
+
+/// only has to be stable within one server version - a mismatch just rewrites the file list
+fn fingerprint(files: &[FileDescription]) -> i64 {
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        file.relative_path.to_serialized_string().hash(&mut hasher);
+        file.size_in_bytes.hash(&mut hasher);
+        file.last_updated_utc_millis.as_u64().hash(&mut hasher);
+        file.file_type.hash(&mut hasher);
+    }
+    hasher.finish() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use shared::dtos::FileDescription;
+    use shared::matchable_path::MatchablePath;
+    use shared::utc_millis::UtcMillis;
+
+    fn file(path: &str, size: u64) -> FileDescription {
+        FileDescription {
+            file_name: path.rsplit('/').next().unwrap().to_string(),
+            relative_path: MatchablePath::from(path),
+            size_in_bytes: size,
+            file_type: String::new(),
+            last_updated_utc_millis: UtcMillis::from(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_report_latest_snapshot_of_assigned_clients() {
+        let db = test_db().await;
+        db.server_watch_group()
+            .insert_watch_group("wg".to_string())
+            .await
+            .unwrap();
+        let wg_id = 1;
+        for (id, host) in [("c1", "arch"), ("c2", "mac")] {
+            db.client().upsert_client(id, host).await.unwrap();
+            db.client_watch_group()
+                .create(id, wg_id, "/sync", vec![], true)
+                .await
+                .unwrap();
+        }
+        let path = MatchablePath::from("dir/a.txt");
+        let snapshots = db.client_file_state();
+
+        snapshots
+            .save("c1", wg_id, &[file("dir/a.txt", 3)], UtcMillis::from(10))
+            .await
+            .unwrap();
+        // unchanged list - only the time moves
+        snapshots
+            .save("c1", wg_id, &[file("dir/a.txt", 3)], UtcMillis::from(20))
+            .await
+            .unwrap();
+        let status = snapshots.get_file(wg_id, &path).await.unwrap();
+        assert_eq!(2, status.len());
+        assert_eq!(
+            Some(20),
+            status[0].reported_at.as_ref().map(|at| at.as_u64())
+        );
+        assert_eq!(Some(3), status[0].file.as_ref().map(|f| f.size_in_bytes));
+        assert!(status[1].reported_at.is_none());
+
+        snapshots
+            .save("c1", wg_id, &[file("b.txt", 1)], UtcMillis::from(30))
+            .await
+            .unwrap();
+        let status = snapshots.get_file(wg_id, &path).await.unwrap();
+        assert!(status[0].reported_at.is_some());
+        assert!(status[0].file.is_none());
+
+        let (reported_at, files) = snapshots.get_files("c1", wg_id).await.unwrap().unwrap();
+        assert_eq!(30, reported_at.as_u64());
+        assert_eq!(
+            vec!["b.txt".to_string()],
+            files
+                .iter()
+                .map(|f| f.file.file_name.clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(files.iter().all(|f| f.sha256.is_none()));
+        assert!(snapshots.get_files("c2", wg_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_drop_stale_file_lists() {
+        let db = test_db().await;
+        db.server_watch_group()
+            .insert_watch_group("wg".to_string())
+            .await
+            .unwrap();
+        let wg_id = 1;
+        for id in ["c1", "c2", "c3"] {
+            db.client().upsert_client(id, id).await.unwrap();
+            db.client_watch_group()
+                .create(id, wg_id, "/sync", vec![], true)
+                .await
+                .unwrap();
+        }
+        let snapshots = db.client_file_state();
+        snapshots
+            .save("c1", wg_id, &[file("a.txt", 1)], UtcMillis::from(10))
+            .await
+            .unwrap();
+        snapshots
+            .save("c2", wg_id, &[file("a.txt", 1)], UtcMillis::from(50))
+            .await
+            .unwrap();
+        snapshots
+            .save("c3", wg_id, &[file("a.txt", 1)], UtcMillis::from(50))
+            .await
+            .unwrap();
+        db.client_watch_group().delete("c3", wg_id).await.unwrap();
+
+        assert_eq!(
+            2,
+            snapshots.delete_stale(UtcMillis::from(20)).await.unwrap()
+        );
+        assert!(snapshots.get_files("c1", wg_id).await.unwrap().is_none());
+        assert!(snapshots.get_files("c2", wg_id).await.unwrap().is_some());
+        assert!(snapshots.get_files("c3", wg_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_report_and_drop_sync_times_of_client() {
+        let db = test_db().await;
+        for name in ["wg1", "wg2"] {
+            db.server_watch_group()
+                .insert_watch_group(name.to_string())
+                .await
+                .unwrap();
+        }
+        db.client().upsert_client("c1", "arch").await.unwrap();
+        let snapshots = db.client_file_state();
+        for (wg_id, at) in [(1, 10), (2, 20)] {
+            db.client_watch_group()
+                .create("c1", wg_id, &format!("/sync{wg_id}"), vec![], true)
+                .await
+                .unwrap();
+            snapshots
+                .save("c1", wg_id, &[file("a.txt", 1)], UtcMillis::from(at))
+                .await
+                .unwrap();
+        }
+
+        let mut times = snapshots.get_report_times("c1").await.unwrap();
+        times.sort_by_key(|(wg_id, _)| *wg_id);
+        assert_eq!(
+            vec![(1, 10), (2, 20)],
+            times
+                .iter()
+                .map(|(wg_id, at)| (*wg_id, at.as_u64()))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(2, snapshots.delete_of_client("c1").await.unwrap());
+        assert!(snapshots.get_report_times("c1").await.unwrap().is_empty());
+        assert!(snapshots.get_files("c1", 1).await.unwrap().is_none());
+    }
+}
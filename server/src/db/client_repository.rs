@@ -1,4 +1,4 @@
-use shared::dtos::ClientDto;
+use shared::dtos::{ClientClaimedDto, ClientDto};
 use sqlx::SqlitePool;
 
 pub struct ClientRepository<'a> {
@@ -7,6 +7,9 @@ pub struct ClientRepository<'a> {
 
 type Result<T> = sqlx::Result<T>;
 
+/// owner of the file events of deregistered clients that asked for anonymization
+pub const DELETED_CLIENT_ID: &str = "e5f6a7b8-9c0d-4e1f-8a2b-3c4d5e6f7a8b";
+
 impl<'a> ClientRepository<'a> {
     pub fn new(pool: &'a SqlitePool) -> Self {
         Self { pool }
@@ -44,8 +47,11 @@ impl<'a> ClientRepository<'a> {
             SELECT
                 c.id,
                 c.host_name,
-                c.min_poll_interval_in_ms
+                c.min_poll_interval_in_ms,
+                c.owner_id,
+                c.paused as "paused: bool"
             FROM client c
+            WHERE c.deregistered_at IS NULL
             ORDER BY c.host_name
             "#
         )
@@ -59,6 +65,8 @@ impl<'a> ClientRepository<'a> {
                 host_name: r.host_name,
                 min_poll_interval_in_ms: u16::try_from(r.min_poll_interval_in_ms)
                     .expect("should fit"),
+                owner_id: r.owner_id,
+                paused: r.paused,
             })
             .collect())
     }
@@ -76,14 +84,216 @@ impl<'a> ClientRepository<'a> {
         Ok(rows.is_some())
     }
 
-    /// Delete a client by id. Returns false if not found.
-    pub async fn delete(&self, client_id: &str) -> Result<bool> {
-        let result = sqlx::query!("DELETE FROM client WHERE id = ?", client_id)
-            .execute(self.pool)
-            .await?;
+    /// Assign the client to a user (None = no owner). Returns false if client not found.
+    pub async fn set_owner(&self, client_id: &str, owner_id: Option<i64>) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE client SET owner_id = ? WHERE id = ?",
+            owner_id,
+            client_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Pause / resume syncing of the client. Returns false if client not found.
+    pub async fn set_paused(&self, client_id: &str, paused: bool) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE client SET paused = ? WHERE id = ? AND deregistered_at IS NULL",
+            paused,
+            client_id
+        )
+        .execute(self.pool)
+        .await?;
         Ok(result.rows_affected() > 0)
     }
 
+    /// Removes the watch groups of the client and keeps it as tombstone, so it can't register
+    /// again. Anonymizing moves its file events to [`DELETED_CLIENT_ID`] and drops the host name.
+    /// Returns false if not found.
+    pub async fn deregister(&self, client_id: &str, anonymize: bool) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE client SET deregistered_at = CURRENT_TIMESTAMP, owner_id = NULL
+            WHERE id = ? AND deregistered_at IS NULL
+            "#,
+            client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "DELETE FROM client_watch_group WHERE client_id = ?",
+            client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if anonymize {
+            sqlx::query!(
+                "UPDATE file_event SET client_id = ? WHERE client_id = ?",
+                DELETED_CLIENT_ID,
+                client_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                "UPDATE client SET host_name = 'deleted' WHERE id = ?",
+                client_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// The client takes over the watch groups, file events and transfer stats of `old_client_id`,
+    /// which is deregistered afterward (e.g. a machine whose OS was reinstalled). Assignments
+    /// clashing with its own (same watch group or folder) are dropped. Returns None if either
+    /// client isn't registered.
+    pub async fn claim(
+        &self,
+        client_id: &str,
+        old_client_id: &str,
+    ) -> Result<Option<ClientClaimedDto>> {
+        let mut tx = self.pool.begin().await?;
+        let registered = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!: i64" FROM client WHERE id IN (?, ?) AND deregistered_at IS NULL"#,
+            client_id,
+            old_client_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if registered < 2 {
+            return Ok(None);
+        }
+
+        let watch_groups = sqlx::query!(
+            r#"
+            UPDATE client_watch_group SET client_id = ?1, updated_at = CURRENT_TIMESTAMP
+            WHERE client_id = ?2
+              AND NOT EXISTS (
+                  SELECT 1 FROM client_watch_group own
+                  WHERE own.client_id = ?1
+                    AND (own.server_watch_group_id = client_watch_group.server_watch_group_id
+                         OR own.path_to_monitor = client_watch_group.path_to_monitor)
+              )
+            "#,
+            client_id,
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let skipped_watch_groups = sqlx::query!(
+            "DELETE FROM client_watch_group WHERE client_id = ?",
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let file_events = sqlx::query!(
+            "UPDATE file_event SET client_id = ? WHERE client_id = ?",
+            client_id,
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        // buckets both clients have are summed up
+        sqlx::query!(
+            r#"
+            INSERT INTO transfer_stats
+                (bucket_start_millis, client_id, watch_group_id, uploaded_bytes, downloaded_bytes)
+            SELECT bucket_start_millis, ?1, watch_group_id, uploaded_bytes, downloaded_bytes
+            FROM transfer_stats WHERE client_id = ?2
+            ON CONFLICT (bucket_start_millis, client_id, watch_group_id) DO UPDATE SET
+                uploaded_bytes = uploaded_bytes + excluded.uploaded_bytes,
+                downloaded_bytes = downloaded_bytes + excluded.downloaded_bytes
+            "#,
+            client_id,
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM transfer_stats WHERE client_id = ?",
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        // the same machine (and connection) - its usage counts towards the caps of the new id
+        sqlx::query!(
+            r#"
+            INSERT INTO client_bandwidth_usage
+                (client_id, month, uploaded_bytes, downloaded_bytes, soft_cap_bytes, hard_cap_bytes)
+            SELECT ?1, month, uploaded_bytes, downloaded_bytes, soft_cap_bytes, hard_cap_bytes
+            FROM client_bandwidth_usage WHERE client_id = ?2
+            ON CONFLICT (client_id, month) DO UPDATE SET
+                uploaded_bytes = uploaded_bytes + excluded.uploaded_bytes,
+                downloaded_bytes = downloaded_bytes + excluded.downloaded_bytes
+            "#,
+            client_id,
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM client_bandwidth_usage WHERE client_id = ?",
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        // the file lists describe the old installation
+        sqlx::query!("DELETE FROM client_scan WHERE client_id = ?", old_client_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            r#"
+            UPDATE client SET
+                min_poll_interval_in_ms = old.min_poll_interval_in_ms,
+                owner_id = COALESCE(client.owner_id, old.owner_id)
+            FROM (SELECT min_poll_interval_in_ms, owner_id FROM client WHERE id = ?) AS old
+            WHERE client.id = ?
+            "#,
+            old_client_id,
+            client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE client SET deregistered_at = CURRENT_TIMESTAMP, owner_id = NULL
+            WHERE id = ?
+            "#,
+            old_client_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(ClientClaimedDto {
+            watch_groups,
+            skipped_watch_groups,
+            file_events,
+        }))
+    }
+
+    pub async fn is_deregistered(&self, client_id: &str) -> Result<bool> {
+        let deregistered = sqlx::query_scalar!(
+            r#"SELECT deregistered_at IS NOT NULL AS "deregistered!: bool" FROM client WHERE id = ?"#,
+            client_id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(deregistered.unwrap_or(false))
+    }
+
     /// Get single client
     pub async fn get_client_by_id(&self, client_id: &str) -> Result<Option<ClientDto>> {
         let client = sqlx::query!(
@@ -91,9 +301,11 @@ impl<'a> ClientRepository<'a> {
             SELECT
                 c.id,
                 c.host_name,
-                c.min_poll_interval_in_ms
+                c.min_poll_interval_in_ms,
+                c.owner_id,
+                c.paused as "paused: bool"
             FROM client c
-            WHERE c.id = ?
+            WHERE c.id = ? AND c.deregistered_at IS NULL
             "#,
             client_id
         )
@@ -106,31 +318,126 @@ impl<'a> ClientRepository<'a> {
                 host_name: r.host_name,
                 min_poll_interval_in_ms: u16::try_from(r.min_poll_interval_in_ms)
                     .expect("should fit"),
+                owner_id: r.owner_id,
+                paused: r.paused,
             })),
             None => Ok(None),
         }
     }
-
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::ServerDatabase;
-    use sqlx::migrate::Migrator;
-    use sqlx::sqlite::SqlitePoolOptions;
-    use sqlx::{Pool, Sqlite};
+    use crate::db::test_db;
+    use futures_util::TryStreamExt;
+
+    #[tokio::test]
+    async fn should_upsert_and_get_client() {
+        let db = test_db().await;
+        let repo = db.client();
 
-    static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+        repo.upsert_client("client-1", "arch").await.unwrap();
 
-    async fn setup_test_db() -> (Pool<Sqlite>, ServerDatabase) {
-        let pool = SqlitePoolOptions::new()
-            .connect("sqlite::memory:")
+        let client = db.client().get_client_by_id("client-1").await.unwrap();
+        assert_eq!(Some("arch".to_string()), client.map(|c| c.host_name));
+    }
+
+    #[tokio::test]
+    async fn should_deregister_and_anonymize_client() {
+        let db = test_db().await;
+        let repo = db.client();
+        repo.upsert_client("client-1", "arch").await.unwrap();
+        let event = crate::file_event::FileEvent::new(
+            uuid::Uuid::new_v4(),
+            shared::utc_millis::UtcMillis::from(1),
+            shared::matchable_path::MatchablePath::from("file.txt"),
+            1,
+            crate::file_event::FileEventType::ChangeEvent,
+            None,
+            1,
+        );
+        db.file_event().insert(&event, "client-1").await.unwrap();
+
+        assert!(repo.deregister("client-1", true).await.unwrap());
+        assert!(!repo.deregister("client-1", true).await.unwrap());
+
+        assert!(repo.is_deregistered("client-1").await.unwrap());
+        assert!(repo.get_client_by_id("client-1").await.unwrap().is_none());
+        let events: Vec<_> = db
+            .file_event()
+            .stream_all_events()
+            .try_collect()
             .await
-            .expect("Failed to create in-memory database");
+            .unwrap();
+        assert_eq!(Some(DELETED_CLIENT_ID.to_string()), events[0].client_host);
+    }
 
-        MIGRATOR.run(&pool).await.expect("Failed to run migrations");
+    #[tokio::test]
+    async fn should_claim_watch_groups_and_history_of_old_client() {
+        let db = test_db().await;
+        let repo = db.client();
+        for name in ["wg-1", "wg-2"] {
+            db.server_watch_group()
+                .insert_watch_group(name.to_string())
+                .await
+                .unwrap();
+        }
+        repo.upsert_client("old", "arch").await.unwrap();
+        repo.upsert_client("new", "arch").await.unwrap();
+        let assignments = db.client_watch_group();
+        assignments
+            .create("old", 1, "/sync", vec![], true)
+            .await
+            .unwrap();
+        assignments
+            .create("old", 2, "/docs", vec![], true)
+            .await
+            .unwrap();
+        // e.g. by a registration template - stays as it is
+        assignments
+            .create("new", 2, "/home/docs", vec![], true)
+            .await
+            .unwrap();
+        let event = crate::file_event::FileEvent::new(
+            uuid::Uuid::new_v4(),
+            shared::utc_millis::UtcMillis::from(1),
+            shared::matchable_path::MatchablePath::from("file.txt"),
+            1,
+            crate::file_event::FileEventType::ChangeEvent,
+            None,
+            1,
+        );
+        db.file_event().insert(&event, "old").await.unwrap();
+        let at = shared::utc_millis::UtcMillis::from(1);
+        for client in ["old", "new"] {
+            db.stats()
+                .record_transfer(client, 1, 10, 0, at.clone())
+                .await
+                .unwrap();
+        }
+
+        let claimed = repo.claim("new", "old").await.unwrap().unwrap();
+        assert_eq!(1, claimed.watch_groups);
+        assert_eq!(1, claimed.skipped_watch_groups);
+        assert_eq!(1, claimed.file_events);
+
+        assert!(repo.is_deregistered("old").await.unwrap());
+        let watch_groups = assignments.get_for_client("new").await.unwrap();
+        assert_eq!("/sync", watch_groups[&1].path_to_monitor);
+        assert_eq!("/home/docs", watch_groups[&2].path_to_monitor);
+        let events: Vec<_> = db
+            .file_event()
+            .stream_all_events()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(Some("new".to_string()), events[0].client_host);
+        let since = shared::utc_millis::UtcMillis::from(0);
+        let bandwidth = db.stats().get_bandwidth(since, 1).await.unwrap();
+        assert_eq!(1, bandwidth.len());
+        assert_eq!(20, bandwidth[0].uploaded_bytes);
 
-        (pool.clone(), ServerDatabase::new(pool))
+        assert!(repo.claim("new", "old").await.unwrap().is_none());
     }
 }
@@ -1,4 +1,5 @@
-use shared::dtos::{ClientWatchGroupDto, WatchGroupConfigDto};
+use shared::dtos::{ClientWatchGroupDto, WatchGroupConfigDto, WatchGroupMemberDto};
+use shared::utc_millis::UtcMillis;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 
@@ -14,6 +15,7 @@ impl<'a> ClientWatchGroupRepository<'a> {
     }
 
     /// Get all watch groups for a client, keyed by server_watch_group_id.
+    /// Watch groups owned by another user than the client's owner are left out.
     /// Returns an empty map if the client has no assigned watch groups.
     pub async fn get_for_client(
         &self,
@@ -26,11 +28,14 @@ impl<'a> ClientWatchGroupRepository<'a> {
                 cwg.path_to_monitor,
                 cwg.exclude_dot_dirs as "exclude_dot_dirs: bool",
                 swg.name,
+                swg.priority,
                 cwged.exclude_dir as "exclude_dir?"
             FROM client_watch_group cwg
+            INNER JOIN client c ON c.id = cwg.client_id
             INNER JOIN server_watch_group swg ON swg.id = cwg.server_watch_group_id
             LEFT JOIN client_watch_group_excluded_dir cwged ON cwged.client_watch_group = cwg.id
             WHERE cwg.client_id = ?
+              AND (swg.owner_id IS NULL OR swg.owner_id = c.owner_id)
             "#,
             client_id
         )
@@ -46,6 +51,7 @@ impl<'a> ClientWatchGroupRepository<'a> {
                         exclude_dirs: Vec::new(),
                         exclude_dot_dirs: row.exclude_dot_dirs,
                         name: row.name.clone(),
+                        priority: row.priority,
                     });
             if let Some(dir) = row.exclude_dir {
                 entry.exclude_dirs.push(dir);
@@ -55,6 +61,81 @@ impl<'a> ClientWatchGroupRepository<'a> {
         Ok(map)
     }
 
+    /// True if the client is assigned to the watch group and allowed to sync it
+    /// (watch group is shared or owned by the client's owner).
+    pub async fn has_access(&self, client_id: &str, server_watch_group_id: i64) -> Result<bool> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM client_watch_group cwg
+            INNER JOIN client c ON c.id = cwg.client_id
+            INNER JOIN server_watch_group swg ON swg.id = cwg.server_watch_group_id
+            WHERE cwg.client_id = ?
+              AND cwg.server_watch_group_id = ?
+              AND (swg.owner_id IS NULL OR swg.owner_id = c.owner_id)
+            "#,
+            client_id,
+            server_watch_group_id
+        )
+        .fetch_one(self.pool)
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// True if the client or its assignment to the watch group was paused by an admin
+    pub async fn is_paused(&self, client_id: &str, server_watch_group_id: i64) -> Result<bool> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)
+            FROM client c
+            LEFT JOIN client_watch_group cwg
+                ON cwg.client_id = c.id AND cwg.server_watch_group_id = ?
+            WHERE c.id = ? AND (c.paused OR cwg.paused)
+            "#,
+            server_watch_group_id,
+            client_id
+        )
+        .fetch_one(self.pool)
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Pause / resume syncing of a single assignment. Returns false if not found.
+    pub async fn set_paused(
+        &self,
+        client_id: &str,
+        server_watch_group_id: i64,
+        paused: bool,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE client_watch_group SET paused = ? WHERE client_id = ? AND server_watch_group_id = ?",
+            paused,
+            client_id,
+            server_watch_group_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Last scan problem reported by the client (None clears it). Returns false if not assigned.
+    pub async fn set_scan_warning(
+        &self,
+        client_id: &str,
+        server_watch_group_id: i64,
+        scan_warning: Option<&str>,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE client_watch_group SET scan_warning = ? WHERE client_id = ? AND server_watch_group_id = ?",
+            scan_warning,
+            client_id,
+            server_watch_group_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// List all watch group assignments for a client (for the admin API).
     /// Returns a Vec ordered by server_watch_group_id.
     pub async fn list_for_client(&self, client_id: &str) -> Result<Vec<ClientWatchGroupDto>> {
@@ -64,6 +145,8 @@ impl<'a> ClientWatchGroupRepository<'a> {
                 cwg.server_watch_group_id,
                 cwg.path_to_monitor,
                 cwg.exclude_dot_dirs as "exclude_dot_dirs: bool",
+                cwg.paused as "paused: bool",
+                cwg.scan_warning,
                 swg.name,
                 cwged.exclude_dir as "exclude_dir?"
             FROM client_watch_group cwg
@@ -80,15 +163,17 @@ impl<'a> ClientWatchGroupRepository<'a> {
         let mut map: std::collections::BTreeMap<i64, ClientWatchGroupDto> =
             std::collections::BTreeMap::new();
         for row in rows {
-            let entry = map
-                .entry(row.server_watch_group_id)
-                .or_insert_with(|| ClientWatchGroupDto {
-                    server_watch_group_id: row.server_watch_group_id,
-                    server_watch_group_name: row.name.clone(),
-                    path_to_monitor: row.path_to_monitor.clone(),
-                    exclude_dirs: Vec::new(),
-                    exclude_dot_dirs: row.exclude_dot_dirs,
-                });
+            let entry =
+                map.entry(row.server_watch_group_id)
+                    .or_insert_with(|| ClientWatchGroupDto {
+                        server_watch_group_id: row.server_watch_group_id,
+                        server_watch_group_name: row.name.clone(),
+                        path_to_monitor: row.path_to_monitor.clone(),
+                        exclude_dirs: Vec::new(),
+                        exclude_dot_dirs: row.exclude_dot_dirs,
+                        paused: row.paused,
+                        scan_warning: row.scan_warning.clone(),
+                    });
             if let Some(dir) = row.exclude_dir {
                 entry.exclude_dirs.push(dir);
             }
@@ -97,6 +182,44 @@ impl<'a> ClientWatchGroupRepository<'a> {
         Ok(map.into_values().collect())
     }
 
+    /// Clients the watch group is assigned to, with the time of their last reported file list.
+    pub async fn list_for_watch_group(
+        &self,
+        server_watch_group_id: i64,
+    ) -> Result<Vec<WatchGroupMemberDto>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                cwg.client_id,
+                c.host_name,
+                cwg.path_to_monitor,
+                cwg.paused as "paused: bool",
+                cwg.scan_warning,
+                cs.reported_at_millis as "reported_at_millis?"
+            FROM client_watch_group cwg
+            INNER JOIN client c ON c.id = cwg.client_id
+            LEFT JOIN client_scan cs
+                ON cs.client_id = cwg.client_id AND cs.watch_group_id = cwg.server_watch_group_id
+            WHERE cwg.server_watch_group_id = ?
+            ORDER BY c.host_name, cwg.client_id
+            "#,
+            server_watch_group_id
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| WatchGroupMemberDto {
+                client_id: r.client_id,
+                host_name: r.host_name,
+                path_to_monitor: r.path_to_monitor,
+                paused: r.paused,
+                scan_warning: r.scan_warning,
+                last_synced_at: r.reported_at_millis.map(|at| UtcMillis::from(at as u64)),
+            })
+            .collect())
+    }
+
     /// Create a new client watch group assignment.
     pub async fn create(
         &self,
@@ -205,3 +328,91 @@ impl<'a> ClientWatchGroupRepository<'a> {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use shared::utc_millis::UtcMillis;
+
+    #[tokio::test]
+    async fn should_only_grant_access_to_shared_or_own_watch_groups() {
+        let db = test_db().await;
+        let clients = db.client();
+        let wgs = db.server_watch_group();
+        let cwgs = db.client_watch_group();
+        clients.upsert_client("client-1", "arch").await.unwrap();
+        wgs.insert_watch_group("wg".to_string()).await.unwrap();
+        let wg_id = wgs.get_all_watch_groups().await.unwrap()[0].id;
+        cwgs.create("client-1", wg_id, "./sync", vec![], true)
+            .await
+            .unwrap();
+        let alice = db.user().insert("alice").await.unwrap();
+        let bob = db.user().insert("bob").await.unwrap();
+
+        // shared
+        assert!(cwgs.has_access("client-1", wg_id).await.unwrap());
+
+        // owned by someone else
+        wgs.set_owner(wg_id, Some(bob)).await.unwrap();
+        clients.set_owner("client-1", Some(alice)).await.unwrap();
+        assert!(!cwgs.has_access("client-1", wg_id).await.unwrap());
+        assert!(cwgs.get_for_client("client-1").await.unwrap().is_empty());
+
+        // owned by the client's user
+        wgs.set_owner(wg_id, Some(alice)).await.unwrap();
+        assert!(cwgs.has_access("client-1", wg_id).await.unwrap());
+        assert!(!cwgs.has_access("unknown", wg_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_pause_client_or_single_watch_group() {
+        let db = test_db().await;
+        let clients = db.client();
+        let cwgs = db.client_watch_group();
+        clients.upsert_client("client-1", "arch").await.unwrap();
+        cwgs.create("client-1", 1, "./sync", vec![], true)
+            .await
+            .unwrap();
+        assert!(!cwgs.is_paused("client-1", 1).await.unwrap());
+
+        assert!(cwgs.set_paused("client-1", 1, true).await.unwrap());
+        assert!(cwgs.is_paused("client-1", 1).await.unwrap());
+        assert!(cwgs.list_for_client("client-1").await.unwrap()[0].paused);
+        cwgs.set_paused("client-1", 1, false).await.unwrap();
+
+        assert!(clients.set_paused("client-1", true).await.unwrap());
+        assert!(cwgs.is_paused("client-1", 1).await.unwrap());
+        assert!(cwgs.is_paused("client-1", 42).await.unwrap());
+        clients.set_paused("client-1", false).await.unwrap();
+        assert!(!cwgs.is_paused("client-1", 1).await.unwrap());
+        assert!(!clients.set_paused("unknown", true).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_list_members_with_their_last_sync() {
+        let db = test_db().await;
+        let cwgs = db.client_watch_group();
+        for (id, host) in [("client-1", "zeus"), ("client-2", "arch")] {
+            db.client().upsert_client(id, host).await.unwrap();
+            cwgs.create(id, 1, "./sync", vec![], true).await.unwrap();
+        }
+        db.client_file_state()
+            .save("client-1", 1, &[], UtcMillis::from(42))
+            .await
+            .unwrap();
+
+        let members = cwgs.list_for_watch_group(1).await.unwrap();
+
+        assert_eq!(
+            vec![("arch", None), ("zeus", Some(42))],
+            members
+                .iter()
+                .map(|m| (
+                    m.host_name.as_str(),
+                    m.last_synced_at.as_ref().map(UtcMillis::as_u64)
+                ))
+                .collect::<Vec<_>>()
+        );
+        assert!(cwgs.list_for_watch_group(2).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use shared::dtos::{ExportCheckpointDto, ExportEventDto};
+use shared::utc_millis::UtcMillis;
+use sqlx::SqlitePool;
+
+pub struct ExportRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+impl<'a> ExportRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Events recorded after `after_seq`, oldest first - the seq is the rowid of the file event
+    /// (the same stream replicas pull).
+    pub async fn events_after(&self, after_seq: i64, limit: u32) -> Result<Vec<ExportEventDto>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                fe.rowid AS "seq!: i64",
+                fe.id,
+                fe.watch_group_id,
+                fe.relative_path,
+                fe.event_type,
+                fe.size_in_bytes,
+                fe.utc_millis,
+                fe.client_id,
+                c.host_name AS "client_host?"
+            FROM file_event fe
+            LEFT JOIN client c ON c.id = fe.client_id
+            WHERE fe.rowid > ?
+            ORDER BY fe.rowid ASC
+            LIMIT ?
+            "#,
+            after_seq,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| ExportEventDto {
+                seq: r.seq,
+                event_id: r.id,
+                watch_group_id: r.watch_group_id,
+                relative_path: r.relative_path,
+                event_type: r.event_type,
+                size_in_bytes: r.size_in_bytes as u64,
+                utc_millis: UtcMillis::from(r.utc_millis as u64),
+                client_id: r.client_id,
+                client_host: r.client_host,
+            })
+            .collect())
+    }
+
+    /// seq the consumer `name` got to - 0 if it never set one
+    pub async fn checkpoint(&self, name: &str) -> Result<i64> {
+        let seq = sqlx::query_scalar!("SELECT seq FROM export_checkpoint WHERE name = ?", name)
+            .fetch_optional(self.pool)
+            .await?;
+        Ok(seq.unwrap_or(0))
+    }
+
+    pub async fn set_checkpoint(&self, name: &str, seq: i64, now: UtcMillis) -> Result<()> {
+        let now = now.as_u64() as i64;
+        sqlx::query!(
+            r#"
+            INSERT INTO export_checkpoint (name, seq, updated_at) VALUES (?, ?, ?)
+            ON CONFLICT (name) DO UPDATE SET seq = excluded.seq, updated_at = excluded.updated_at
+            "#,
+            name,
+            seq,
+            now
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn checkpoints(&self) -> Result<Vec<ExportCheckpointDto>> {
+        let rows =
+            sqlx::query!("SELECT name, seq, updated_at FROM export_checkpoint ORDER BY name ASC")
+                .fetch_all(self.pool)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| ExportCheckpointDto {
+                name: r.name,
+                seq: r.seq,
+                updated_at: UtcMillis::from(r.updated_at as u64),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use crate::file_event::{FileEvent, FileEventType};
+    use shared::matchable_path::MatchablePath;
+    use shared::utc_millis::UtcMillis;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn should_export_events_after_checkpoint() {
+        let db = test_db().await;
+        db.client().upsert_client("c1", "arch").await.unwrap();
+        for (path, event_type) in [
+            ("a.txt", FileEventType::ChangeEvent),
+            ("b.txt", FileEventType::ChangeEvent),
+            ("a.txt", FileEventType::DeleteEvent),
+        ] {
+            let event = FileEvent::new(
+                Uuid::new_v4(),
+                UtcMillis::from(1),
+                MatchablePath::from(path),
+                1,
+                event_type,
+                None,
+                1,
+            );
+            db.file_event().insert(&event, "c1").await.unwrap();
+        }
+        let export = db.export();
+
+        let all = export.events_after(0, 10).await.unwrap();
+        assert_eq!(3, all.len());
+        assert_eq!("c1", all[0].client_id);
+        assert_eq!(Some("arch".to_string()), all[0].client_host);
+        assert_eq!("delete", all[2].event_type);
+
+        assert_eq!(0, export.checkpoint("etl").await.unwrap());
+        export
+            .set_checkpoint("etl", all[0].seq, UtcMillis::from(5))
+            .await
+            .unwrap();
+        export
+            .set_checkpoint("etl", all[1].seq, UtcMillis::from(6))
+            .await
+            .unwrap();
+        let checkpoint = export.checkpoint("etl").await.unwrap();
+        assert_eq!(all[1].seq, checkpoint);
+        let rest = export.events_after(checkpoint, 10).await.unwrap();
+        assert_eq!(vec![all[2].clone()], rest);
+
+        let checkpoints = export.checkpoints().await.unwrap();
+        assert_eq!(1, checkpoints.len());
+        assert_eq!(UtcMillis::from(6), checkpoints[0].updated_at);
+    }
+}
@@ -1,3 +1,4 @@
+use futures_util::{Stream, StreamExt};
 use shared::matchable_path::MatchablePath;
 use shared::utc_millis::UtcMillis;
 use sqlx::SqlitePool;
@@ -78,8 +79,11 @@ impl<'a> FileEventRepository<'a> {
         Ok(count)
     }
 
-    pub async fn get_all_events(&self) -> Result<Vec<FileEvent>> {
-        let rows = sqlx::query!(
+    /// every event in insertion order, one row at a time - the history can be too large to
+    /// collect
+    pub fn stream_all_events(&self) -> impl Stream<Item = Result<FileEvent>> + 'a {
+        sqlx::query_as!(
+            FileEventRow,
             r#"
             SELECT
                 id,
@@ -90,26 +94,184 @@ impl<'a> FileEventRepository<'a> {
                 client_id,
                 watch_group_id
             FROM file_event
-            ORDER BY utc_millis ASC
+            ORDER BY rowid ASC
             "#
         )
+        .fetch(self.pool)
+        .map(|row| row.map(FileEvent::from))
+    }
+
+    /// all events of one watch group (chronologically = oldest first)
+    pub async fn get_events_of_watch_group(&self, watch_group_id: i64) -> Result<Vec<FileEvent>> {
+        let rows = sqlx::query_as!(
+            FileEventRow,
+            r#"
+            SELECT
+                id,
+                utc_millis,
+                relative_path,
+                size_in_bytes,
+                event_type,
+                client_id,
+                watch_group_id
+            FROM file_event
+            WHERE watch_group_id = ?
+            ORDER BY utc_millis ASC, rowid ASC
+            "#,
+            watch_group_id,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(FileEvent::from).collect())
+    }
+
+    /// all events of one path (chronologically = oldest first)
+    pub async fn get_events_of_path(
+        &self,
+        watch_group_id: i64,
+        relative_path: &MatchablePath,
+    ) -> Result<Vec<FileEvent>> {
+        let relative_path = relative_path.to_serialized_string();
+        let rows = sqlx::query_as!(
+            FileEventRow,
+            r#"
+            SELECT
+                id,
+                utc_millis,
+                relative_path,
+                size_in_bytes,
+                event_type,
+                client_id,
+                watch_group_id
+            FROM file_event
+            WHERE watch_group_id = ? AND relative_path = ?
+            ORDER BY utc_millis ASC, rowid ASC
+            "#,
+            watch_group_id,
+            relative_path,
+        )
         .fetch_all(self.pool)
         .await?;
 
-        Ok(rows
-            .into_iter()
-            .map(|row| {
-                FileEvent::new(
-                    Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::new_v4()),
-                    UtcMillis::from(row.utc_millis as u64),
-                    MatchablePath::from(row.relative_path.as_str()),
-                    row.size_in_bytes as u64,
-                    FileEventType::try_from(row.event_type.as_str())
-                        .unwrap_or(FileEventType::ChangeEvent),
-                    Some(row.client_id),
-                    row.watch_group_id,
-                )
-            })
-            .collect())
+        Ok(rows.into_iter().map(FileEvent::from).collect())
+    }
+
+    pub async fn get_latest_event_of_path(
+        &self,
+        watch_group_id: i64,
+        relative_path: &MatchablePath,
+    ) -> Result<Option<FileEvent>> {
+        let relative_path = relative_path.to_serialized_string();
+        let row = sqlx::query_as!(
+            FileEventRow,
+            r#"
+            SELECT
+                id,
+                utc_millis,
+                relative_path,
+                size_in_bytes,
+                event_type,
+                client_id,
+                watch_group_id
+            FROM file_event
+            WHERE watch_group_id = ? AND relative_path = ?
+            ORDER BY utc_millis DESC, rowid DESC
+            LIMIT 1
+            "#,
+            watch_group_id,
+            relative_path,
+        )
+        .fetch_optional(self.pool)
+        .await?;
+
+        Ok(row.map(FileEvent::from))
+    }
+
+    /// latest event of every path within the watch group
+    pub async fn get_latest_events(&self, watch_group_id: i64) -> Result<Vec<FileEvent>> {
+        let rows = sqlx::query_as!(
+            FileEventRow,
+            r#"
+            SELECT
+                id AS "id!",
+                utc_millis AS "utc_millis!",
+                relative_path AS "relative_path!",
+                size_in_bytes AS "size_in_bytes!",
+                event_type AS "event_type!",
+                client_id AS "client_id!",
+                watch_group_id AS "watch_group_id!"
+            FROM (
+                SELECT
+                    *,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY relative_path ORDER BY utc_millis DESC, rowid DESC
+                    ) AS position
+                FROM file_event
+                WHERE watch_group_id = ?
+            )
+            WHERE position = 1
+            "#,
+            watch_group_id,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(FileEvent::from).collect())
+    }
+
+    /// the latest `limit` events the client caused over all watch groups (newest first)
+    pub async fn get_recent_events_of_client(
+        &self,
+        client_id: &str,
+        limit: u32,
+    ) -> Result<Vec<FileEvent>> {
+        let rows = sqlx::query_as!(
+            FileEventRow,
+            r#"
+            SELECT
+                id,
+                utc_millis,
+                relative_path,
+                size_in_bytes,
+                event_type,
+                client_id,
+                watch_group_id
+            FROM file_event
+            WHERE client_id = ?
+            ORDER BY utc_millis DESC, rowid DESC
+            LIMIT ?
+            "#,
+            client_id,
+            limit,
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(FileEvent::from).collect())
+    }
+}
+
+struct FileEventRow {
+    id: String,
+    utc_millis: i64,
+    relative_path: String,
+    size_in_bytes: i64,
+    event_type: String,
+    client_id: String,
+    watch_group_id: i64,
+}
+
+impl From<FileEventRow> for FileEvent {
+    fn from(row: FileEventRow) -> Self {
+        FileEvent::new(
+            Uuid::parse_str(&row.id).unwrap_or_else(|_| Uuid::new_v4()),
+            UtcMillis::from(row.utc_millis as u64),
+            MatchablePath::from(row.relative_path.as_str()),
+            row.size_in_bytes as u64,
+            FileEventType::try_from(row.event_type.as_str()).unwrap_or(FileEventType::ChangeEvent),
+            Some(row.client_id),
+            row.watch_group_id,
+        )
     }
 }
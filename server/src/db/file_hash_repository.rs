@@ -0,0 +1,92 @@
+use shared::utc_millis::UtcMillis;
+use sqlx::SqlitePool;
+
+/// sha256 of a file as it was verified after its upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileHash {
+    pub relative_path: String,
+    /// time of the file event of the hashed version
+    pub utc_millis: UtcMillis,
+    pub sha256: String,
+}
+
+pub struct FileHashRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+impl<'a> FileHashRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Replaces the hash of an earlier version of the path.
+    pub async fn upsert(
+        &self,
+        wg_id: i64,
+        relative_path: &str,
+        utc_millis: UtcMillis,
+        sha256: &str,
+    ) -> Result<()> {
+        let utc_millis = utc_millis.as_u64() as i64;
+        sqlx::query!(
+            r#"
+            INSERT INTO file_hash (watch_group_id, relative_path, utc_millis, sha256)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (watch_group_id, relative_path)
+                DO UPDATE SET utc_millis = excluded.utc_millis, sha256 = excluded.sha256
+            "#,
+            wg_id,
+            relative_path,
+            utc_millis,
+            sha256
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_all(&self, wg_id: i64) -> Result<Vec<FileHash>> {
+        let rows = sqlx::query!(
+            "SELECT relative_path, utc_millis, sha256 FROM file_hash WHERE watch_group_id = ?",
+            wg_id
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| FileHash {
+                relative_path: r.relative_path,
+                utc_millis: UtcMillis::from(r.utc_millis as u64),
+                sha256: r.sha256,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use shared::utc_millis::UtcMillis;
+
+    #[tokio::test]
+    async fn should_keep_the_hash_of_the_latest_version() {
+        let db = test_db().await;
+        let hashes = db.file_hash();
+        hashes
+            .upsert(1, "a.txt", UtcMillis::from(1), "old")
+            .await
+            .unwrap();
+        hashes
+            .upsert(1, "a.txt", UtcMillis::from(2), "new")
+            .await
+            .unwrap();
+
+        let all = hashes.get_all(1).await.unwrap();
+        assert_eq!(1, all.len());
+        assert_eq!(UtcMillis::from(2), all[0].utc_millis);
+        assert_eq!("new", all[0].sha256);
+        assert!(hashes.get_all(2).await.unwrap().is_empty());
+    }
+}
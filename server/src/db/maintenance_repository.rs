@@ -0,0 +1,85 @@
+use chrono::Local;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+pub struct MaintenanceRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+impl<'a> MaintenanceRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// consistent copy of the live database (`sqlite_{timestamp}.db` within `backup_dir`)
+    pub async fn backup_into(&self, backup_dir: &Path) -> Result<PathBuf> {
+        let target = backup_dir.join(format!(
+            "sqlite_{}.db",
+            Local::now().format("%Y%m%d_%H%M%S")
+        ));
+        sqlx::query("VACUUM INTO ?")
+            .bind(target.to_string_lossy().to_string())
+            .execute(self.pool)
+            .await?;
+        Ok(target)
+    }
+
+    /// rebuilds the database file without the free pages and refreshes the query planner stats
+    pub async fn compact(&self) -> Result<()> {
+        sqlx::query("VACUUM").execute(self.pool).await?;
+        sqlx::query("PRAGMA optimize").execute(self.pool).await?;
+        Ok(())
+    }
+
+    /// problems found by sqlite - empty if the database is fine
+    pub async fn integrity_check(&self) -> Result<Vec<String>> {
+        let messages: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(self.pool)
+            .await?;
+        Ok(messages.into_iter().filter(|m| m != "ok").collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn should_backup_into_readable_file() {
+        // in-memory databases would vacuum into memory as well
+        let dir = std::env::temp_dir().join(format!("db-backup-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = SqlitePoolOptions::new()
+            .connect_with(
+                SqliteConnectOptions::new()
+                    .filename(dir.join("live.db"))
+                    .create_if_missing(true),
+            )
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let repository = MaintenanceRepository::new(&pool);
+
+        assert!(repository.integrity_check().await.unwrap().is_empty());
+        let backup = repository.backup_into(&dir).await.unwrap();
+
+        let copy = SqlitePoolOptions::new()
+            .connect_with(SqliteConnectOptions::new().filename(&backup))
+            .await
+            .unwrap();
+        assert!(
+            MaintenanceRepository::new(&copy)
+                .integrity_check()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        copy.close().await;
+        pool.close().await;
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,19 +1,67 @@
+mod client_file_state_repository;
 mod client_repository;
 mod client_watch_group_repository;
+mod export_repository;
 mod file_event_repository;
+mod file_hash_repository;
 mod link_repository;
 mod link_tag_repository;
+mod maintenance_repository;
+mod pending_delete_repository;
+mod registration_template_repository;
+mod replication_repository;
+mod schedule_repository;
+mod search_repository;
 mod server_watch_group_repository;
+mod share_link_repository;
+mod stats_repository;
+mod sync_blackout_repository;
+mod ui_settings_repository;
+mod user_repository;
+mod webhook_repository;
+mod write_intent_repository;
 
-pub use client_repository::ClientRepository;
+pub use client_file_state_repository::{ClientFileSnapshot, ClientFileStateRepository};
+pub use client_repository::{ClientRepository, DELETED_CLIENT_ID};
 pub use client_watch_group_repository::ClientWatchGroupRepository;
+pub use export_repository::ExportRepository;
 pub use file_event_repository::FileEventRepository;
+pub use file_hash_repository::{FileHash, FileHashRepository};
 pub use link_repository::LinkRepository;
 pub use link_tag_repository::LinkTagRepository;
+pub use maintenance_repository::MaintenanceRepository;
+pub use pending_delete_repository::PendingDeleteRepository;
+pub use registration_template_repository::RegistrationTemplateRepository;
+pub use replication_repository::{REPLICATION_CLIENT_ID, ReplicationRepository};
+pub use schedule_repository::ScheduleRepository;
+pub use search_repository::SearchRepository;
 pub use server_watch_group_repository::ServerWatchGroupRepository;
+pub use share_link_repository::{ShareLink, ShareLinkRepository};
+pub use stats_repository::{SizeEvent, StatsRepository};
+pub use sync_blackout_repository::{SyncBlackout, SyncBlackoutRepository};
+pub use ui_settings_repository::UiSettingsRepository;
+pub use user_repository::UserRepository;
+pub use webhook_repository::{Webhook, WebhookRepository};
+pub use write_intent_repository::{WriteIntent, WriteIntentRepository};
 
 use sqlx::SqlitePool;
 
+/// in-memory database with all migrations applied
+#[cfg(test)]
+pub(crate) async fn test_db() -> ServerDatabase {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .expect("Failed to create in-memory database");
+
+    crate::MIGRATOR
+        .run(&pool)
+        .await
+        .expect("Failed to run migrations");
+
+    ServerDatabase::new(pool)
+}
+
 #[derive(Clone)]
 pub struct ServerDatabase {
     pool: SqlitePool,
@@ -36,6 +84,14 @@ impl ServerDatabase {
         ClientWatchGroupRepository::new(&self.pool)
     }
 
+    pub fn client_file_state(&self) -> ClientFileStateRepository<'_> {
+        ClientFileStateRepository::new(&self.pool)
+    }
+
+    pub fn registration_template(&self) -> RegistrationTemplateRepository<'_> {
+        RegistrationTemplateRepository::new(&self.pool)
+    }
+
     pub fn link(&self) -> LinkRepository<'_> {
         LinkRepository::new(&self.pool)
     }
@@ -47,4 +103,60 @@ impl ServerDatabase {
     pub fn file_event(&self) -> FileEventRepository<'_> {
         FileEventRepository::new(&self.pool)
     }
+
+    pub fn file_hash(&self) -> FileHashRepository<'_> {
+        FileHashRepository::new(&self.pool)
+    }
+
+    pub fn search(&self) -> SearchRepository<'_> {
+        SearchRepository::new(&self.pool)
+    }
+
+    pub fn stats(&self) -> StatsRepository<'_> {
+        StatsRepository::new(&self.pool)
+    }
+
+    pub fn user(&self) -> UserRepository<'_> {
+        UserRepository::new(&self.pool)
+    }
+
+    pub fn ui_settings(&self) -> UiSettingsRepository<'_> {
+        UiSettingsRepository::new(&self.pool)
+    }
+
+    pub fn schedule(&self) -> ScheduleRepository<'_> {
+        ScheduleRepository::new(&self.pool)
+    }
+
+    pub fn maintenance(&self) -> MaintenanceRepository<'_> {
+        MaintenanceRepository::new(&self.pool)
+    }
+
+    pub fn webhook(&self) -> WebhookRepository<'_> {
+        WebhookRepository::new(&self.pool)
+    }
+
+    pub fn sync_blackout(&self) -> SyncBlackoutRepository<'_> {
+        SyncBlackoutRepository::new(&self.pool)
+    }
+
+    pub fn share_link(&self) -> ShareLinkRepository<'_> {
+        ShareLinkRepository::new(&self.pool)
+    }
+
+    pub fn replication(&self) -> ReplicationRepository<'_> {
+        ReplicationRepository::new(&self.pool)
+    }
+
+    pub fn export(&self) -> ExportRepository<'_> {
+        ExportRepository::new(&self.pool)
+    }
+
+    pub fn pending_delete(&self) -> PendingDeleteRepository<'_> {
+        PendingDeleteRepository::new(&self.pool)
+    }
+
+    pub fn write_intent(&self) -> WriteIntentRepository<'_> {
+        WriteIntentRepository::new(&self.pool)
+    }
 }
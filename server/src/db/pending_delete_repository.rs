@@ -0,0 +1,186 @@
+use shared::dtos::PendingDeleteDto;
+use shared::utc_millis::UtcMillis;
+use sqlx::SqlitePool;
+
+pub struct PendingDeleteRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+impl<'a> PendingDeleteRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Keeps an earlier pending delete of the same path (and its `remove_at`).
+    pub async fn insert(
+        &self,
+        wg_id: i64,
+        relative_path: &str,
+        client_id: &str,
+        client_host: Option<&str>,
+        requested_at: UtcMillis,
+        remove_at: UtcMillis,
+    ) -> Result<()> {
+        let requested_at = requested_at.as_u64() as i64;
+        let remove_at = remove_at.as_u64() as i64;
+        sqlx::query!(
+            r#"
+            INSERT INTO pending_delete
+                (watch_group_id, relative_path, client_id, client_host, requested_at, remove_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (watch_group_id, relative_path) DO NOTHING
+            "#,
+            wg_id,
+            relative_path,
+            client_id,
+            client_host,
+            requested_at,
+            remove_at
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// oldest first
+    pub async fn list(&self) -> Result<Vec<PendingDeleteDto>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, watch_group_id, relative_path, client_id, client_host, requested_at, remove_at
+            FROM pending_delete ORDER BY remove_at ASC, id ASC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| PendingDeleteDto {
+                id: r.id,
+                watch_group_id: r.watch_group_id,
+                relative_path: r.relative_path,
+                client_id: r.client_id,
+                client_host: r.client_host,
+                requested_at: UtcMillis::from(r.requested_at as u64),
+                remove_at: UtcMillis::from(r.remove_at as u64),
+            })
+            .collect())
+    }
+
+    /// the deletes whose grace period ended
+    pub async fn due(&self, now: UtcMillis) -> Result<Vec<PendingDeleteDto>> {
+        let now = now.as_u64() as i64;
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, watch_group_id, relative_path, client_id, client_host, requested_at, remove_at
+            FROM pending_delete WHERE remove_at <= ? ORDER BY remove_at ASC, id ASC
+            "#,
+            now
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| PendingDeleteDto {
+                id: r.id,
+                watch_group_id: r.watch_group_id,
+                relative_path: r.relative_path,
+                client_id: r.client_id,
+                client_host: r.client_host,
+                requested_at: UtcMillis::from(r.requested_at as u64),
+                remove_at: UtcMillis::from(r.remove_at as u64),
+            })
+            .collect())
+    }
+
+    /// paths of the watch group with a pending delete
+    pub async fn paths(&self, wg_id: i64) -> Result<Vec<String>> {
+        sqlx::query_scalar!(
+            "SELECT relative_path FROM pending_delete WHERE watch_group_id = ?",
+            wg_id
+        )
+        .fetch_all(self.pool)
+        .await
+    }
+
+    /// Returns false if not found.
+    pub async fn remove(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM pending_delete WHERE id = ?", id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns false if the path had no pending delete.
+    pub async fn remove_path(&self, wg_id: i64, relative_path: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM pending_delete WHERE watch_group_id = ? AND relative_path = ?",
+            wg_id,
+            relative_path
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use shared::utc_millis::UtcMillis;
+
+    #[tokio::test]
+    async fn should_keep_first_pending_delete_of_path_until_due() {
+        let db = test_db().await;
+        let pending = db.pending_delete();
+        pending
+            .insert(
+                1,
+                "a.txt",
+                "c1",
+                Some("arch"),
+                UtcMillis::from(1),
+                UtcMillis::from(10),
+            )
+            .await
+            .unwrap();
+        // deleting again doesn't postpone it
+        pending
+            .insert(
+                1,
+                "a.txt",
+                "c2",
+                None,
+                UtcMillis::from(5),
+                UtcMillis::from(15),
+            )
+            .await
+            .unwrap();
+        pending
+            .insert(
+                1,
+                "b.txt",
+                "c1",
+                None,
+                UtcMillis::from(2),
+                UtcMillis::from(20),
+            )
+            .await
+            .unwrap();
+
+        let all = pending.list().await.unwrap();
+        assert_eq!(2, all.len());
+        assert_eq!("c1", all[0].client_id);
+        assert_eq!(UtcMillis::from(10), all[0].remove_at);
+        let due = pending.due(UtcMillis::from(10)).await.unwrap();
+        assert_eq!(vec![all[0].clone()], due);
+        assert_eq!(2, pending.paths(1).await.unwrap().len());
+        assert!(pending.paths(2).await.unwrap().is_empty());
+
+        assert!(pending.remove_path(1, "b.txt").await.unwrap());
+        assert!(!pending.remove_path(1, "b.txt").await.unwrap());
+        assert!(pending.remove(all[0].id).await.unwrap());
+        assert!(pending.list().await.unwrap().is_empty());
+    }
+}
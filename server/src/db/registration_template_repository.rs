@@ -0,0 +1,218 @@
+use shared::dtos::{RegistrationTemplateCreateDto, RegistrationTemplateDto};
+use shared::glob::matches_glob;
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+
+pub struct RegistrationTemplateRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+impl<'a> RegistrationTemplateRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// All templates ordered by id (= order in which they are applied)
+    pub async fn list(&self) -> Result<Vec<RegistrationTemplateDto>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                rt.id,
+                rt.host_pattern,
+                rt.server_watch_group_id,
+                rt.path_to_monitor,
+                rt.exclude_dot_dirs as "exclude_dot_dirs: bool",
+                rt.min_poll_interval_in_ms,
+                swg.name,
+                rted.exclude_dir as "exclude_dir?"
+            FROM registration_template rt
+            INNER JOIN server_watch_group swg ON swg.id = rt.server_watch_group_id
+            LEFT JOIN registration_template_excluded_dir rted ON rted.registration_template = rt.id
+            ORDER BY rt.id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        let mut map: BTreeMap<i64, RegistrationTemplateDto> = BTreeMap::new();
+        for row in rows {
+            let entry = map
+                .entry(row.id)
+                .or_insert_with(|| RegistrationTemplateDto {
+                    id: row.id,
+                    host_pattern: row.host_pattern.clone(),
+                    server_watch_group_id: row.server_watch_group_id,
+                    server_watch_group_name: row.name.clone(),
+                    path_to_monitor: row.path_to_monitor.clone(),
+                    exclude_dirs: Vec::new(),
+                    exclude_dot_dirs: row.exclude_dot_dirs,
+                    min_poll_interval_in_ms: row
+                        .min_poll_interval_in_ms
+                        .and_then(|ms| u16::try_from(ms).ok()),
+                });
+            if let Some(dir) = row.exclude_dir {
+                entry.exclude_dirs.push(dir);
+            }
+        }
+
+        Ok(map.into_values().collect())
+    }
+
+    pub async fn create(&self, dto: &RegistrationTemplateCreateDto) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+        let min_poll_interval_in_ms = dto.min_poll_interval_in_ms.map(i64::from);
+
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO registration_template
+                (host_pattern, server_watch_group_id, path_to_monitor, exclude_dot_dirs, min_poll_interval_in_ms)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING id
+            "#,
+            dto.host_pattern,
+            dto.server_watch_group_id,
+            dto.path_to_monitor,
+            dto.exclude_dot_dirs,
+            min_poll_interval_in_ms
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for exclude_dir in &dto.exclude_dirs {
+            sqlx::query!(
+                "INSERT INTO registration_template_excluded_dir (registration_template, exclude_dir) VALUES (?, ?)",
+                id,
+                exclude_dir
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Returns false if not found
+    pub async fn delete(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM registration_template WHERE id = ?", id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Assigns the watch groups of all templates matching the host name to the (new) client.
+    /// The poll interval is taken from the first matching template that sets one.
+    ///
+    /// Returns the number of applied templates
+    pub async fn apply(&self, client_id: &str, host_name: &str) -> Result<usize> {
+        let templates: Vec<RegistrationTemplateDto> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|t| matches_glob(&t.host_pattern, host_name))
+            .collect();
+        if templates.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let mut assigned = Vec::new();
+        for template in &templates {
+            // a watch group can only be assigned once - the first template wins
+            if assigned.contains(&template.server_watch_group_id) {
+                continue;
+            }
+            assigned.push(template.server_watch_group_id);
+
+            let client_watch_group_id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO client_watch_group (client_id, server_watch_group_id, path_to_monitor, exclude_dot_dirs)
+                VALUES (?, ?, ?, ?)
+                RETURNING id
+                "#,
+                client_id,
+                template.server_watch_group_id,
+                template.path_to_monitor,
+                template.exclude_dot_dirs
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            for exclude_dir in &template.exclude_dirs {
+                sqlx::query!(
+                    "INSERT INTO client_watch_group_excluded_dir (client_watch_group, exclude_dir) VALUES (?, ?)",
+                    client_watch_group_id,
+                    exclude_dir
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        if let Some(ms) = templates.iter().find_map(|t| t.min_poll_interval_in_ms) {
+            let ms = i64::from(ms);
+            sqlx::query!(
+                "UPDATE client SET min_poll_interval_in_ms = ? WHERE id = ?",
+                ms,
+                client_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(templates.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn should_match_host_patterns() {
+        assert!(matches_glob("*", "arch"));
+        assert!(matches_glob("laptop-*", "Laptop-Elias"));
+        assert!(matches_glob("*-nas-?", "home-nas-1"));
+        assert!(matches_glob("a*b*c", "aXbYbZc"));
+        assert!(!matches_glob("laptop-*", "desktop-elias"));
+        assert!(!matches_glob("nas-?", "nas-10"));
+        assert!(!matches_glob("", "arch"));
+    }
+
+    #[tokio::test]
+    async fn should_apply_matching_templates_to_new_client() {
+        let db = test_db().await;
+        let template =
+            |host_pattern: &str, min_poll_interval_in_ms| RegistrationTemplateCreateDto {
+                host_pattern: host_pattern.to_string(),
+                server_watch_group_id: 1,
+                path_to_monitor: "/sync".to_string(),
+                exclude_dirs: vec!["target".to_string()],
+                exclude_dot_dirs: true,
+                min_poll_interval_in_ms,
+            };
+        let repo = db.registration_template();
+        repo.create(&template("laptop-*", Some(1000)))
+            .await
+            .unwrap();
+        repo.create(&template("*", Some(9000))).await.unwrap();
+        repo.create(&template("nas", None)).await.unwrap();
+        db.client()
+            .upsert_client("client-1", "laptop-1")
+            .await
+            .unwrap();
+
+        assert_eq!(2, repo.apply("client-1", "laptop-1").await.unwrap());
+
+        let assignments = db.client_watch_group().list_for_client("client-1").await;
+        let assignments = assignments.unwrap();
+        assert_eq!(1, assignments.len());
+        assert_eq!(vec!["target".to_string()], assignments[0].exclude_dirs);
+        let client = db.client().get_client_by_id("client-1").await.unwrap();
+        assert_eq!(Some(1000), client.map(|c| c.min_poll_interval_in_ms));
+    }
+}
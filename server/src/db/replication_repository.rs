@@ -0,0 +1,186 @@
+use crate::file_event::FileEvent;
+use shared::dtos::ReplicationEventDto;
+use shared::utc_millis::UtcMillis;
+use sqlx::SqlitePool;
+
+pub struct ReplicationRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+/// author of the file events a replica pulled from its primary
+pub const REPLICATION_CLIENT_ID: &str = "f8a9b0c1-2d3e-4f5a-9b6c-7d8e9f0a1b2c";
+
+impl<'a> ReplicationRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Events recorded after `after_seq`, oldest first. The seq is the rowid of the file event -
+    /// rows are only ever deleted together with their watch group, so it never goes backwards.
+    pub async fn events_after(
+        &self,
+        after_seq: i64,
+        limit: u32,
+    ) -> Result<Vec<ReplicationEventDto>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                fe.rowid AS "seq!: i64",
+                fe.id,
+                fe.watch_group_id,
+                swg.name AS watch_group_name,
+                fe.relative_path,
+                fe.event_type,
+                fe.size_in_bytes,
+                fe.utc_millis
+            FROM file_event fe
+            INNER JOIN server_watch_group swg ON swg.id = fe.watch_group_id
+            WHERE fe.rowid > ?
+            ORDER BY fe.rowid ASC
+            LIMIT ?
+            "#,
+            after_seq,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| ReplicationEventDto {
+                seq: r.seq,
+                event_id: r.id,
+                watch_group_id: r.watch_group_id,
+                watch_group_name: r.watch_group_name,
+                relative_path: r.relative_path,
+                event_type: r.event_type,
+                size_in_bytes: r.size_in_bytes as u64,
+                utc_millis: UtcMillis::from(r.utc_millis as u64),
+            })
+            .collect())
+    }
+
+    /// seq of the newest event - 0 without any
+    pub async fn latest_seq(&self) -> Result<i64> {
+        sqlx::query_scalar!(r#"SELECT COALESCE(MAX(rowid), 0) AS "seq!: i64" FROM file_event"#)
+            .fetch_one(self.pool)
+            .await
+    }
+
+    /// how far the stream of the primary was applied - 0 if never
+    pub async fn cursor(&self, primary_url: &str) -> Result<i64> {
+        let applied_seq = sqlx::query_scalar!(
+            "SELECT applied_seq FROM replication_cursor WHERE primary_url = ?",
+            primary_url
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(applied_seq.unwrap_or(0))
+    }
+
+    pub async fn set_cursor(&self, primary_url: &str, applied_seq: i64) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO replication_cursor (primary_url, applied_seq) VALUES (?, ?)
+            ON CONFLICT (primary_url) DO UPDATE SET applied_seq = excluded.applied_seq
+            "#,
+            primary_url,
+            applied_seq
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records an event of the primary under its original id. Returns false if it was recorded
+    /// already (a pull that broke off after the event but before moving the cursor).
+    pub async fn insert_event(&self, event: &FileEvent) -> Result<bool> {
+        let id = event.id.to_string();
+        let utc_millis = event.utc_millis.as_u64() as i64;
+        let relative_path = event.relative_path.to_serialized_string();
+        let size_in_bytes = event.size_in_bytes as i64;
+        let event_type = event.event_type.serialize_to_string();
+        let result = sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO file_event
+                (id, utc_millis, relative_path, size_in_bytes, event_type, client_id, watch_group_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            utc_millis,
+            relative_path,
+            size_in_bytes,
+            event_type,
+            REPLICATION_CLIENT_ID,
+            event.watch_group_id,
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use crate::file_event::{FileEvent, FileEventType};
+    use shared::matchable_path::MatchablePath;
+    use shared::utc_millis::UtcMillis;
+    use uuid::Uuid;
+
+    fn event(path: &str, event_type: FileEventType) -> FileEvent {
+        FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(1),
+            MatchablePath::from(path),
+            1,
+            event_type,
+            None,
+            1,
+        )
+    }
+
+    #[tokio::test]
+    async fn should_stream_events_after_cursor_and_apply_them_once() {
+        let db = test_db().await;
+        db.client().upsert_client("c1", "arch").await.unwrap();
+        let replication = db.replication();
+        assert_eq!(0, replication.latest_seq().await.unwrap());
+
+        let events = [
+            event("a.txt", FileEventType::ChangeEvent),
+            event("b.txt", FileEventType::ChangeEvent),
+            event("a.txt", FileEventType::DeleteEvent),
+        ];
+        for e in &events {
+            db.file_event().insert(e, "c1").await.unwrap();
+        }
+        let latest_seq = replication.latest_seq().await.unwrap();
+
+        let all = replication.events_after(0, 10).await.unwrap();
+        assert_eq!(3, all.len());
+        assert_eq!(latest_seq, all[2].seq);
+        // watch group 1 comes with the migrations
+        assert_eq!("default", all[0].watch_group_name);
+        assert_eq!("delete", all[2].event_type);
+        let rest = replication.events_after(all[0].seq, 1).await.unwrap();
+        assert_eq!(vec![all[1].clone()], rest);
+
+        // replaying an event recorded already
+        assert!(!replication.insert_event(&events[0]).await.unwrap());
+        assert!(
+            replication
+                .insert_event(&event("c.txt", FileEventType::ChangeEvent))
+                .await
+                .unwrap()
+        );
+
+        let primary = "https://primary:3000";
+        assert_eq!(0, replication.cursor(primary).await.unwrap());
+        replication.set_cursor(primary, 2).await.unwrap();
+        replication.set_cursor(primary, 3).await.unwrap();
+        assert_eq!(3, replication.cursor(primary).await.unwrap());
+    }
+}
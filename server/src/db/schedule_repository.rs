@@ -0,0 +1,69 @@
+use sqlx::SqlitePool;
+
+pub struct ScheduleRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+/// expression of a job as changed via the api - None = turned off
+pub struct SavedSchedule {
+    pub job: String,
+    pub expression: Option<String>,
+}
+
+impl<'a> ScheduleRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<SavedSchedule>> {
+        let rows = sqlx::query!("SELECT job, expression FROM schedule")
+            .fetch_all(self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| SavedSchedule {
+                job: r.job,
+                expression: r.expression,
+            })
+            .collect())
+    }
+
+    pub async fn save(&self, job: &str, expression: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO schedule (job, expression) VALUES (?, ?)
+            ON CONFLICT (job) DO UPDATE SET expression = excluded.expression
+            "#,
+            job,
+            expression
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+
+    #[tokio::test]
+    async fn should_overwrite_and_turn_off_schedules() {
+        let db = test_db().await;
+        let schedule = db.schedule();
+
+        schedule.save("backup", Some("0 3 * * *")).await.unwrap();
+        schedule.save("backup", Some("0 4 * * *")).await.unwrap();
+        schedule.save("scrub", None).await.unwrap();
+
+        let mut saved = schedule.get_all().await.unwrap();
+        saved.sort_by(|a, b| a.job.cmp(&b.job));
+        assert_eq!(2, saved.len());
+        assert_eq!("backup", saved[0].job);
+        assert_eq!(Some("0 4 * * *".to_string()), saved[0].expression);
+        assert_eq!("scrub", saved[1].job);
+        assert_eq!(None, saved[1].expression);
+    }
+}
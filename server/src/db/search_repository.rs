@@ -0,0 +1,172 @@
+use shared::dtos::SearchHitDto;
+use sqlx::SqlitePool;
+
+pub struct SearchRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+/// max number of hits returned per search
+const MAX_HITS: i64 = 50;
+
+impl<'a> SearchRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// (re)indexes a file - replaces a previous entry of the same path
+    pub async fn upsert(
+        &self,
+        wg_id: i64,
+        relative_path: &str,
+        content: Option<&str>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "DELETE FROM file_search WHERE watch_group_id = ? AND relative_path = ?",
+            wg_id,
+            relative_path
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO file_search (relative_path, content, watch_group_id) VALUES (?, ?, ?)",
+            relative_path,
+            content,
+            wg_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+
+    pub async fn remove(&self, wg_id: i64, relative_path: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM file_search WHERE watch_group_id = ? AND relative_path = ?",
+            wg_id,
+            relative_path
+        )
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear(&self) -> Result<()> {
+        sqlx::query!("DELETE FROM file_search")
+            .execute(self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// every whitespace separated term has to match (as prefix) - either in the path only
+    /// or, if `include_content`, in the path or the content
+    pub async fn search(&self, query: &str, include_content: bool) -> Result<Vec<SearchHitDto>> {
+        let Some(match_expr) = to_match_expression(query, include_content) else {
+            return Ok(Vec::new());
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT fs.watch_group_id AS "watch_group_id!: i64",
+                   swg.name AS "watch_group_name!",
+                   fs.relative_path AS "relative_path!: String",
+                   snippet(file_search, 1, '[', ']', '…', 8) AS "snippet: String"
+            FROM file_search fs
+            JOIN server_watch_group swg ON swg.id = fs.watch_group_id
+            WHERE file_search MATCH ?
+            ORDER BY rank
+            LIMIT ?
+            "#,
+            match_expr,
+            MAX_HITS
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SearchHitDto {
+                watch_group_id: row.watch_group_id,
+                watch_group_name: row.watch_group_name,
+                relative_path: row.relative_path,
+                snippet: row.snippet.filter(|s| include_content && !s.is_empty()),
+                deleted: false,
+            })
+            .collect())
+    }
+}
+
+/// quotes every term (so user input can't use fts5 syntax) and turns it into a prefix query
+fn to_match_expression(query: &str, include_content: bool) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+
+    let terms = terms.join(" ");
+    if include_content {
+        Some(terms)
+    } else {
+        Some(format!("relative_path : ({terms})"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn should_escape_user_input() {
+        assert_eq!(None, to_match_expression("  ", true));
+        assert_eq!(
+            Some(r#""a""b"* "c"*"#.to_string()),
+            to_match_expression(r#"a"b c"#, true)
+        );
+        assert_eq!(
+            Some(r#"relative_path : ("notes"*)"#.to_string()),
+            to_match_expression("notes", false)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_find_files_by_name_and_optionally_by_content() {
+        // watch group 1 ('default') is created by the migrations
+        let db = test_db().await;
+        let search = db.search();
+        search
+            .upsert(1, "docs/holiday-notes.md", Some("packing list: tent"))
+            .await
+            .unwrap();
+        search
+            .upsert(
+                1,
+                "docs/holiday-notes.md",
+                Some("packing list: sleeping bag"),
+            )
+            .await
+            .unwrap();
+        search.upsert(1, "photos/beach.jpg", None).await.unwrap();
+
+        let hits = search.search("holi", false).await.unwrap();
+        assert_eq!(1, hits.len());
+        assert_eq!("docs/holiday-notes.md", hits[0].relative_path);
+        assert_eq!("default", hits[0].watch_group_name);
+        assert_eq!(None, hits[0].snippet);
+
+        assert!(search.search("sleeping", false).await.unwrap().is_empty());
+        assert!(search.search("tent", true).await.unwrap().is_empty());
+        let hits = search.search("sleeping", true).await.unwrap();
+        assert_eq!(1, hits.len());
+        assert!(hits[0].snippet.as_ref().unwrap().contains("[sleeping]"));
+
+        search.remove(1, "photos/beach.jpg").await.unwrap();
+        assert!(search.search("beach", false).await.unwrap().is_empty());
+    }
+}
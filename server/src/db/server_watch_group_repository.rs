@@ -1,4 +1,4 @@
-use shared::dtos::ServerWatchGroup;
+use shared::dtos::{Durability, MassChangeGuardDto, ServerWatchGroup};
 use sqlx::SqlitePool;
 
 pub struct ServerWatchGroupRepository<'a> {
@@ -13,12 +13,41 @@ impl<'a> ServerWatchGroupRepository<'a> {
     }
 
     pub async fn get_all_watch_groups(&self) -> Result<Vec<ServerWatchGroup>> {
-        sqlx::query_as!(
-            ServerWatchGroup,
-            "SELECT id, name FROM server_watch_group ORDER BY id"
+        let rows = sqlx::query!(
+            r#"SELECT id, name, owner_id, verify_writes as "verify_writes: bool", durability, priority, publish_path FROM server_watch_group ORDER BY id"#
         )
         .fetch_all(self.pool)
-        .await
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| ServerWatchGroup {
+                id: r.id,
+                name: r.name,
+                owner_id: r.owner_id,
+                verify_writes: r.verify_writes,
+                durability: to_durability(&r.durability),
+                priority: r.priority,
+                publish_path: r.publish_path,
+            })
+            .collect())
+    }
+
+    pub async fn get_watch_group(&self, id: i64) -> Result<Option<ServerWatchGroup>> {
+        let row = sqlx::query!(
+            r#"SELECT id, name, owner_id, verify_writes as "verify_writes: bool", durability, priority, publish_path FROM server_watch_group WHERE id = ?"#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(row.map(|r| ServerWatchGroup {
+            id: r.id,
+            name: r.name,
+            owner_id: r.owner_id,
+            verify_writes: r.verify_writes,
+            durability: to_durability(&r.durability),
+            priority: r.priority,
+            publish_path: r.publish_path,
+        }))
     }
 
     pub async fn insert_watch_group(&self, name: String) -> Result<()> {
@@ -29,6 +58,23 @@ impl<'a> ServerWatchGroupRepository<'a> {
         Ok(())
     }
 
+    /// Creates the watch group under the given id or renames it (replicas keep the ids of the
+    /// primary)
+    pub async fn ensure_watch_group(&self, id: i64, name: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO server_watch_group (id, name) VALUES (?, ?)
+            ON CONFLICT (id) DO UPDATE SET name = excluded.name
+            "#,
+            id,
+            name
+        )
+        .execute(self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn rename_watch_group(&self, id: i64, name: String) -> Result<()> {
         sqlx::query!(
             "UPDATE server_watch_group SET name = ? WHERE id = ?",
@@ -41,21 +87,254 @@ impl<'a> ServerWatchGroupRepository<'a> {
         Ok(())
     }
 
-    pub async fn exists(&self, id: i64) -> Result<bool> {
-        let count = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM server_watch_group WHERE id = ?",
+    /// Assign the watch group to a user (None = shared). Returns false if not found.
+    pub async fn set_owner(&self, id: i64, owner_id: Option<i64>) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE server_watch_group SET owner_id = ? WHERE id = ?",
+            owner_id,
             id
         )
-        .fetch_one(self.pool)
+        .execute(self.pool)
         .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Owner of the watch group - outer None if the watch group doesn't exist
+    pub async fn get_owner(&self, id: i64) -> Result<Option<Option<i64>>> {
+        sqlx::query_scalar!("SELECT owner_id FROM server_watch_group WHERE id = ?", id)
+            .fetch_optional(self.pool)
+            .await
+    }
+
+    /// Enable / disable read-after-write verification of uploads. Returns false if not found.
+    pub async fn set_verify_writes(&self, id: i64, verify_writes: bool) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE server_watch_group SET verify_writes = ? WHERE id = ?",
+            verify_writes,
+            id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns false if not found.
+    pub async fn set_durability(&self, id: i64, durability: Durability) -> Result<bool> {
+        let durability = durability.as_str();
+        let result = sqlx::query!(
+            "UPDATE server_watch_group SET durability = ? WHERE id = ?",
+            durability,
+            id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Set (or clear) the dir accepted files are mirrored into. Returns false if not found.
+    pub async fn set_publish_path(&self, id: i64, publish_path: Option<&str>) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE server_watch_group SET publish_path = ? WHERE id = ?",
+            publish_path,
+            id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// None for unknown watch groups and those without publish target
+    pub async fn publish_path(&self, id: i64) -> Result<Option<String>> {
+        let publish_path = sqlx::query_scalar!(
+            "SELECT publish_path FROM server_watch_group WHERE id = ?",
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(publish_path.flatten())
+    }
+
+    /// Set the sync priority on the clients. Returns false if not found.
+    pub async fn set_priority(&self, id: i64, priority: i64) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE server_watch_group SET priority = ? WHERE id = ?",
+            priority,
+            id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Thresholds configured for the mass change guard - None for unknown watch groups.
+    pub async fn mass_change_guard(&self, id: i64) -> Result<Option<MassChangeGuardDto>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT mass_change_percent, mass_change_window_minutes, mass_change_min_files
+            FROM server_watch_group WHERE id = ?
+            "#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(row.map(|r| MassChangeGuardDto {
+            percent: r.mass_change_percent.map(|v| v as u32),
+            window_minutes: r.mass_change_window_minutes.map(|v| v as u32),
+            min_files: r.mass_change_min_files.map(|v| v as u32),
+        }))
+    }
+
+    /// Returns false if not found.
+    pub async fn set_mass_change_guard(&self, id: i64, guard: &MassChangeGuardDto) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE server_watch_group
+            SET mass_change_percent = ?, mass_change_window_minutes = ?, mass_change_min_files = ?
+            WHERE id = ?
+            "#,
+            guard.percent,
+            guard.window_minutes,
+            guard.min_files,
+            id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// false for unknown watch groups
+    pub async fn verify_writes(&self, id: i64) -> Result<bool> {
+        let verify_writes = sqlx::query_scalar!(
+            r#"SELECT verify_writes as "verify_writes: bool" FROM server_watch_group WHERE id = ?"#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(verify_writes.unwrap_or(false))
+    }
+
+    /// [`Durability::None`] for unknown watch groups
+    pub async fn durability(&self, id: i64) -> Result<Durability> {
+        let durability =
+            sqlx::query_scalar!("SELECT durability FROM server_watch_group WHERE id = ?", id)
+                .fetch_optional(self.pool)
+                .await?;
+        Ok(durability.as_deref().map(to_durability).unwrap_or_default())
+    }
+
+    pub async fn exists(&self, id: i64) -> Result<bool> {
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM server_watch_group WHERE id = ?", id)
+            .fetch_one(self.pool)
+            .await?;
         Ok(count > 0)
     }
 
-    /// Delete a server watch group. Returns false if not found.
+    /// Number of clients syncing the watch group
+    pub async fn count_attached_clients(&self, id: i64) -> Result<i64> {
+        sqlx::query_scalar!(
+            "SELECT COUNT(DISTINCT client_id) FROM client_watch_group WHERE server_watch_group_id = ?",
+            id
+        )
+        .fetch_one(self.pool)
+        .await
+    }
+
+    /// Delete a server watch group together with its client assignments, file events and
+    /// search entries. Returns false if not found.
     pub async fn delete(&self, id: i64) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "DELETE FROM client_watch_group WHERE server_watch_group_id = ?",
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM file_event WHERE watch_group_id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM file_search WHERE watch_group_id = ?", id)
+            .execute(&mut *tx)
+            .await?;
         let result = sqlx::query!("DELETE FROM server_watch_group WHERE id = ?", id)
-            .execute(self.pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
         Ok(result.rows_affected() > 0)
     }
 }
+
+/// unknown values (written by a newer version) count as the strongest level
+fn to_durability(value: &str) -> Durability {
+    Durability::try_from(value).unwrap_or(Durability::FsyncFileAndDir)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use crate::file_event::{FileEvent, FileEventType};
+    use shared::dtos::{Durability, ServerWatchGroup};
+    use shared::matchable_path::MatchablePath;
+    use shared::utc_millis::UtcMillis;
+
+    #[tokio::test]
+    async fn should_delete_watch_group_with_its_events_and_assignments() {
+        let db = test_db().await;
+        db.server_watch_group()
+            .insert_watch_group("photos".to_string())
+            .await
+            .unwrap();
+        let id = db
+            .server_watch_group()
+            .get_all_watch_groups()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|wg| wg.name == "photos")
+            .unwrap()
+            .id;
+        db.client().upsert_client("client-1", "arch").await.unwrap();
+        db.client_watch_group()
+            .create("client-1", id, "/photos", vec!["tmp".to_string()], true)
+            .await
+            .unwrap();
+        let event = FileEvent::new(
+            uuid::Uuid::new_v4(),
+            UtcMillis::from(1),
+            MatchablePath::from("a.jpg"),
+            1,
+            FileEventType::ChangeEvent,
+            None,
+            id,
+        );
+        db.file_event().insert(&event, "client-1").await.unwrap();
+        db.search().upsert(id, "a.jpg", None).await.unwrap();
+
+        let attached = db.server_watch_group().count_attached_clients(id).await;
+        assert_eq!(1, attached.unwrap());
+        assert!(db.server_watch_group().delete(id).await.unwrap());
+        assert!(!db.server_watch_group().delete(id).await.unwrap());
+
+        assert!(!db.server_watch_group().exists(id).await.unwrap());
+        let attached = db.server_watch_group().count_attached_clients(id).await;
+        assert_eq!(0, attached.unwrap());
+        let events = db.file_event().get_events_of_watch_group(id).await;
+        assert!(events.unwrap().is_empty());
+        assert!(db.search().search("a.jpg", false).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_store_durability_of_watch_group() {
+        let db = test_db().await;
+        let repo = db.server_watch_group();
+        repo.insert_watch_group("docs".to_string()).await.unwrap();
+        let docs = |groups: Vec<ServerWatchGroup>| groups.into_iter().find(|wg| wg.name == "docs");
+        let group = docs(repo.get_all_watch_groups().await.unwrap()).unwrap();
+        assert_eq!(Durability::None, group.durability);
+
+        let durability = Durability::FsyncFileAndDir;
+        assert!(repo.set_durability(group.id, durability).await.unwrap());
+        assert_eq!(durability, repo.durability(group.id).await.unwrap());
+        let group = docs(repo.get_all_watch_groups().await.unwrap()).unwrap();
+        assert_eq!(durability, group.durability);
+    }
+}
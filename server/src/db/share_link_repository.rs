@@ -0,0 +1,92 @@
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+
+pub struct ShareLinkRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+#[derive(Debug, Clone)]
+pub struct ShareLink {
+    pub token: String,
+    pub server_watch_group_id: i64,
+    pub kind: String,
+    pub folder: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl<'a> ShareLinkRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_for_watch_group(&self, wg_id: i64) -> Result<Vec<ShareLink>> {
+        sqlx::query_as!(
+            ShareLink,
+            r#"SELECT token, server_watch_group_id, kind, folder, created_at as "created_at: NaiveDateTime"
+               FROM share_link WHERE server_watch_group_id = ? ORDER BY created_at, token"#,
+            wg_id
+        )
+        .fetch_all(self.pool)
+        .await
+    }
+
+    pub async fn get(&self, token: &str) -> Result<Option<ShareLink>> {
+        sqlx::query_as!(
+            ShareLink,
+            r#"SELECT token, server_watch_group_id, kind, folder, created_at as "created_at: NaiveDateTime"
+               FROM share_link WHERE token = ?"#,
+            token
+        )
+        .fetch_optional(self.pool)
+        .await
+    }
+
+    pub async fn create(&self, token: &str, wg_id: i64, kind: &str, folder: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO share_link (token, server_watch_group_id, kind, folder) VALUES (?, ?, ?, ?)",
+            token,
+            wg_id,
+            kind,
+            folder
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns false if the watch group has no such link
+    pub async fn delete(&self, wg_id: i64, token: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM share_link WHERE token = ? AND server_watch_group_id = ?",
+            token,
+            wg_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+
+    #[tokio::test]
+    async fn should_create_look_up_and_delete_links() {
+        let db = test_db().await;
+        let repo = db.share_link();
+        repo.create("abc", 1, "inbox", "inbox/scans").await.unwrap();
+
+        let link = repo.get("abc").await.unwrap().unwrap();
+        assert_eq!(1, link.server_watch_group_id);
+        assert_eq!("inbox/scans", link.folder);
+        assert!(repo.create("xyz", 1, "outbox", "").await.is_err());
+
+        assert!(!repo.delete(2, "abc").await.unwrap());
+        assert_eq!(1, repo.list_for_watch_group(1).await.unwrap().len());
+        assert!(repo.delete(1, "abc").await.unwrap());
+        assert!(repo.get("abc").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,574 @@
+use shared::dtos::{
+    BandwidthBucketDto, BandwidthUsageDto, BandwidthUsageReportDto, ClientActivityDto,
+    DailyEventCountDto, FileSizeDto, TopFileDto, WatchGroupStatsDto,
+};
+use shared::utc_millis::UtcMillis;
+use sqlx::SqlitePool;
+
+pub struct StatsRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+/// how many entries the top-lists hold
+const TOP_N: i64 = 10;
+/// how many days of event counts are returned
+const DAYS: i64 = 30;
+/// resolution of the transfer_stats table
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+
+/// a row of the file_event table, reduced to what storage reports need
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeEvent {
+    pub utc_millis: u64,
+    pub relative_path: String,
+    pub size_in_bytes: u64,
+    pub deleted: bool,
+}
+
+impl<'a> StatsRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Computed from the file_event table - a file counts as present if its latest event is a change
+    pub async fn get_watch_group_stats(&self, watch_group_id: i64) -> Result<WatchGroupStatsDto> {
+        let totals = sqlx::query!(
+            r#"
+            WITH latest AS (
+                SELECT
+                    size_in_bytes,
+                    event_type,
+                    ROW_NUMBER() OVER (PARTITION BY relative_path ORDER BY utc_millis DESC) AS rn
+                FROM file_event
+                WHERE watch_group_id = ?
+            )
+            SELECT
+                COUNT(*) AS "file_count!: i64",
+                COALESCE(SUM(size_in_bytes), 0) AS "total_bytes!: i64"
+            FROM latest
+            WHERE rn = 1 AND event_type = 'change'
+            "#,
+            watch_group_id
+        )
+        .fetch_one(self.pool)
+        .await?;
+
+        let largest_files = sqlx::query!(
+            r#"
+            WITH latest AS (
+                SELECT
+                    relative_path,
+                    size_in_bytes,
+                    event_type,
+                    ROW_NUMBER() OVER (PARTITION BY relative_path ORDER BY utc_millis DESC) AS rn
+                FROM file_event
+                WHERE watch_group_id = ?
+            )
+            SELECT
+                relative_path AS "relative_path!: String",
+                size_in_bytes AS "size_in_bytes!: i64"
+            FROM latest
+            WHERE rn = 1 AND event_type = 'change'
+            ORDER BY size_in_bytes DESC
+            LIMIT ?
+            "#,
+            watch_group_id,
+            TOP_N
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        let most_active_clients = sqlx::query!(
+            r#"
+            SELECT
+                fe.client_id,
+                c.host_name,
+                COUNT(*) AS "event_count!: i64"
+            FROM file_event fe
+            INNER JOIN client c ON c.id = fe.client_id
+            WHERE fe.watch_group_id = ?
+            GROUP BY fe.client_id, c.host_name
+            ORDER BY 3 DESC
+            LIMIT ?
+            "#,
+            watch_group_id,
+            TOP_N
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        let events_per_day = sqlx::query!(
+            r#"
+            SELECT
+                date(utc_millis / 1000, 'unixepoch') AS "day!: String",
+                SUM(event_type = 'change') AS "changes!: i64",
+                SUM(event_type = 'delete') AS "deletes!: i64"
+            FROM file_event
+            WHERE watch_group_id = ?
+            GROUP BY 1
+            ORDER BY 1 DESC
+            LIMIT ?
+            "#,
+            watch_group_id,
+            DAYS
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(WatchGroupStatsDto {
+            file_count: totals.file_count as u64,
+            total_bytes: totals.total_bytes as u64,
+            largest_files: largest_files
+                .into_iter()
+                .map(|r| FileSizeDto {
+                    relative_path: r.relative_path,
+                    size_in_bytes: r.size_in_bytes as u64,
+                })
+                .collect(),
+            most_active_clients: most_active_clients
+                .into_iter()
+                .map(|r| ClientActivityDto {
+                    client_id: r.client_id,
+                    host_name: r.host_name,
+                    event_count: r.event_count as u64,
+                })
+                .collect(),
+            events_per_day: events_per_day
+                .into_iter()
+                .map(|r| DailyEventCountDto {
+                    day: r.day,
+                    changes: r.changes as u64,
+                    deletes: r.deletes as u64,
+                })
+                .collect(),
+        })
+    }
+
+    /// present files (latest event is a change), largest first
+    pub async fn get_top_files(&self, watch_group_id: i64, limit: u32) -> Result<Vec<TopFileDto>> {
+        let limit = limit as i64;
+        let rows = sqlx::query!(
+            r#"
+            WITH latest AS (
+                SELECT
+                    relative_path,
+                    size_in_bytes,
+                    event_type,
+                    utc_millis,
+                    ROW_NUMBER() OVER (PARTITION BY relative_path ORDER BY utc_millis DESC) AS rn
+                FROM file_event
+                WHERE watch_group_id = ?
+            )
+            SELECT
+                relative_path AS "relative_path!: String",
+                size_in_bytes AS "size_in_bytes!: i64",
+                utc_millis AS "utc_millis!: i64"
+            FROM latest
+            WHERE rn = 1 AND event_type = 'change'
+            ORDER BY size_in_bytes DESC, relative_path ASC
+            LIMIT ?
+            "#,
+            watch_group_id,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| TopFileDto {
+                relative_path: r.relative_path,
+                size_in_bytes: r.size_in_bytes as u64,
+                last_changed_utc_millis: r.utc_millis as u64,
+            })
+            .collect())
+    }
+
+    /// the whole event history of a watch group - oldest first
+    pub async fn get_size_events(&self, watch_group_id: i64) -> Result<Vec<SizeEvent>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                utc_millis AS "utc_millis!: i64",
+                relative_path AS "relative_path!: String",
+                size_in_bytes AS "size_in_bytes!: i64",
+                event_type AS "event_type!: String"
+            FROM file_event
+            WHERE watch_group_id = ?
+            ORDER BY utc_millis ASC
+            "#,
+            watch_group_id
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| SizeEvent {
+                utc_millis: r.utc_millis as u64,
+                relative_path: r.relative_path,
+                size_in_bytes: r.size_in_bytes as u64,
+                deleted: r.event_type == "delete",
+            })
+            .collect())
+    }
+
+    /// adds to the hourly bucket of `at`
+    pub async fn record_transfer(
+        &self,
+        client_id: &str,
+        watch_group_id: i64,
+        uploaded_bytes: u64,
+        downloaded_bytes: u64,
+        at: UtcMillis,
+    ) -> Result<()> {
+        let bucket_start_millis = at.as_u64() as i64 / HOUR_MILLIS * HOUR_MILLIS;
+        let uploaded_bytes = uploaded_bytes as i64;
+        let downloaded_bytes = downloaded_bytes as i64;
+        sqlx::query!(
+            r#"
+            INSERT INTO transfer_stats
+                (bucket_start_millis, client_id, watch_group_id, uploaded_bytes, downloaded_bytes)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (bucket_start_millis, client_id, watch_group_id) DO UPDATE SET
+                uploaded_bytes = uploaded_bytes + excluded.uploaded_bytes,
+                downloaded_bytes = downloaded_bytes + excluded.downloaded_bytes
+            "#,
+            bucket_start_millis,
+            client_id,
+            watch_group_id,
+            uploaded_bytes,
+            downloaded_bytes,
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// adds a client's report to its month (the caps are replaced) - returns the new totals
+    pub async fn add_bandwidth_usage(
+        &self,
+        client_id: &str,
+        report: &BandwidthUsageReportDto,
+    ) -> Result<BandwidthUsageDto> {
+        let uploaded_bytes = report.uploaded_bytes as i64;
+        let downloaded_bytes = report.downloaded_bytes as i64;
+        let soft_cap_bytes = report.soft_cap_bytes as i64;
+        let hard_cap_bytes = report.hard_cap_bytes as i64;
+        sqlx::query!(
+            r#"
+            INSERT INTO client_bandwidth_usage
+                (client_id, month, uploaded_bytes, downloaded_bytes, soft_cap_bytes, hard_cap_bytes)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (client_id, month) DO UPDATE SET
+                uploaded_bytes = uploaded_bytes + excluded.uploaded_bytes,
+                downloaded_bytes = downloaded_bytes + excluded.downloaded_bytes,
+                soft_cap_bytes = excluded.soft_cap_bytes,
+                hard_cap_bytes = excluded.hard_cap_bytes
+            "#,
+            client_id,
+            report.month,
+            uploaded_bytes,
+            downloaded_bytes,
+            soft_cap_bytes,
+            hard_cap_bytes,
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(self
+            .get_bandwidth_usage(client_id, &report.month)
+            .await?
+            .expect("row was just written"))
+    }
+
+    pub async fn get_bandwidth_usage(
+        &self,
+        client_id: &str,
+        month: &str,
+    ) -> Result<Option<BandwidthUsageDto>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                month,
+                uploaded_bytes AS "uploaded_bytes!: i64",
+                downloaded_bytes AS "downloaded_bytes!: i64",
+                soft_cap_bytes AS "soft_cap_bytes!: i64",
+                hard_cap_bytes AS "hard_cap_bytes!: i64"
+            FROM client_bandwidth_usage
+            WHERE client_id = ? AND month = ?
+            "#,
+            client_id,
+            month
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(row.map(|r| BandwidthUsageDto {
+            month: r.month,
+            uploaded_bytes: r.uploaded_bytes as u64,
+            downloaded_bytes: r.downloaded_bytes as u64,
+            soft_cap_bytes: r.soft_cap_bytes as u64,
+            hard_cap_bytes: r.hard_cap_bytes as u64,
+        }))
+    }
+
+    /// transferred bytes since `since`, summed up per `bucket_hours`, client and watch group -
+    /// oldest bucket first
+    pub async fn get_bandwidth(
+        &self,
+        since: UtcMillis,
+        bucket_hours: u32,
+    ) -> Result<Vec<BandwidthBucketDto>> {
+        let since = since.as_u64() as i64;
+        let bucket_millis = bucket_hours.max(1) as i64 * HOUR_MILLIS;
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                ts.bucket_start_millis / ?1 * ?1 AS "start_utc_millis!: i64",
+                ts.client_id,
+                c.host_name AS "host_name?: String",
+                ts.watch_group_id,
+                SUM(ts.uploaded_bytes) AS "uploaded_bytes!: i64",
+                SUM(ts.downloaded_bytes) AS "downloaded_bytes!: i64"
+            FROM transfer_stats ts
+            LEFT JOIN client c ON c.id = ts.client_id
+            WHERE ts.bucket_start_millis >= ?2
+            GROUP BY 1, ts.client_id, ts.watch_group_id
+            ORDER BY 1 ASC, ts.client_id ASC, ts.watch_group_id ASC
+            "#,
+            bucket_millis,
+            since
+        )
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| BandwidthBucketDto {
+                start_utc_millis: r.start_utc_millis as u64,
+                client_id: r.client_id,
+                host_name: r.host_name,
+                watch_group_id: r.watch_group_id,
+                uploaded_bytes: r.uploaded_bytes as u64,
+                downloaded_bytes: r.downloaded_bytes as u64,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use crate::file_event::{FileEvent, FileEventType};
+    use shared::dtos::BandwidthUsageReportDto;
+    use shared::matchable_path::MatchablePath;
+    use shared::utc_millis::UtcMillis;
+    use uuid::Uuid;
+
+    fn event(millis: u64, path: &str, size: u64, event_type: FileEventType) -> FileEvent {
+        FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(millis),
+            MatchablePath::from(path),
+            size,
+            event_type,
+            None,
+            1,
+        )
+    }
+
+    #[tokio::test]
+    async fn should_only_count_files_whose_latest_event_is_a_change() {
+        let db = test_db().await;
+        db.client().upsert_client("client-1", "arch").await.unwrap();
+        db.server_watch_group()
+            .insert_watch_group("wg".to_string())
+            .await
+            .unwrap();
+        let events = db.file_event();
+        events
+            .insert(
+                &event(1, "a.txt", 10, FileEventType::ChangeEvent),
+                "client-1",
+            )
+            .await
+            .unwrap();
+        events
+            .insert(
+                &event(2, "a.txt", 30, FileEventType::ChangeEvent),
+                "client-1",
+            )
+            .await
+            .unwrap();
+        events
+            .insert(
+                &event(3, "b.txt", 5, FileEventType::ChangeEvent),
+                "client-1",
+            )
+            .await
+            .unwrap();
+        events
+            .insert(
+                &event(4, "b.txt", 0, FileEventType::DeleteEvent),
+                "client-1",
+            )
+            .await
+            .unwrap();
+
+        let stats = db.stats().get_watch_group_stats(1).await.unwrap();
+
+        assert_eq!(1, stats.file_count);
+        assert_eq!(30, stats.total_bytes);
+        assert_eq!("a.txt", stats.largest_files[0].relative_path);
+        assert_eq!(4, stats.most_active_clients[0].event_count);
+        assert_eq!(3, stats.events_per_day[0].changes);
+        assert_eq!(1, stats.events_per_day[0].deletes);
+    }
+
+    #[tokio::test]
+    async fn should_list_top_files_and_size_events() {
+        let db = test_db().await;
+        db.client().upsert_client("client-1", "arch").await.unwrap();
+        db.server_watch_group()
+            .insert_watch_group("wg".to_string())
+            .await
+            .unwrap();
+        let events = db.file_event();
+        for e in [
+            event(1, "a.txt", 10, FileEventType::ChangeEvent),
+            event(2, "b.txt", 50, FileEventType::ChangeEvent),
+            event(3, "c.txt", 20, FileEventType::ChangeEvent),
+            event(4, "b.txt", 0, FileEventType::DeleteEvent),
+        ] {
+            events.insert(&e, "client-1").await.unwrap();
+        }
+
+        let top = db.stats().get_top_files(1, 1).await.unwrap();
+        assert_eq!(1, top.len());
+        assert_eq!(
+            ("c.txt", 20, 3),
+            (
+                top[0].relative_path.as_str(),
+                top[0].size_in_bytes,
+                top[0].last_changed_utc_millis
+            )
+        );
+
+        let history = db.stats().get_size_events(1).await.unwrap();
+        assert_eq!(
+            vec![false, false, false, true],
+            history.iter().map(|e| e.deleted).collect::<Vec<_>>()
+        );
+        assert_eq!("a.txt", history[0].relative_path);
+    }
+
+    #[tokio::test]
+    async fn should_sum_transfers_per_bucket() {
+        let db = test_db().await;
+        db.client().upsert_client("client-1", "arch").await.unwrap();
+        db.server_watch_group()
+            .insert_watch_group("wg".to_string())
+            .await
+            .unwrap();
+        let stats = db.stats();
+        let hour = 60 * 60 * 1000;
+        stats
+            .record_transfer("client-1", 1, 100, 0, UtcMillis::from(hour + 1))
+            .await
+            .unwrap();
+        stats
+            .record_transfer("client-1", 1, 50, 7, UtcMillis::from(2 * hour - 1))
+            .await
+            .unwrap();
+        stats
+            .record_transfer("client-1", 1, 0, 10, UtcMillis::from(2 * hour))
+            .await
+            .unwrap();
+        stats
+            .record_transfer("gone", 1, 1, 0, UtcMillis::from(3 * hour))
+            .await
+            .unwrap();
+
+        let hourly = stats.get_bandwidth(UtcMillis::from(0), 1).await.unwrap();
+        assert_eq!(3, hourly.len());
+        assert_eq!(
+            (hour, 150, 7),
+            (
+                hourly[0].start_utc_millis,
+                hourly[0].uploaded_bytes,
+                hourly[0].downloaded_bytes
+            )
+        );
+        assert_eq!(Some("arch".to_string()), hourly[0].host_name);
+        assert_eq!(None, hourly[2].host_name);
+
+        let two_hourly = stats.get_bandwidth(UtcMillis::from(0), 2).await.unwrap();
+        assert_eq!(
+            vec![(0, 150, 7), (2 * hour, 0, 10)],
+            two_hourly
+                .iter()
+                .filter(|b| b.client_id == "client-1")
+                .map(|b| (b.start_utc_millis, b.uploaded_bytes, b.downloaded_bytes))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            2,
+            stats
+                .get_bandwidth(UtcMillis::from(2 * hour), 1)
+                .await
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_add_up_bandwidth_usage_per_month() {
+        let db = test_db().await;
+        let stats = db.stats();
+        let report = |month: &str, uploaded_bytes, hard_cap_bytes| BandwidthUsageReportDto {
+            month: month.to_string(),
+            uploaded_bytes,
+            downloaded_bytes: 5,
+            soft_cap_bytes: 0,
+            hard_cap_bytes,
+        };
+        assert_eq!(
+            None,
+            stats
+                .get_bandwidth_usage("client-1", "2026-10")
+                .await
+                .unwrap()
+        );
+
+        stats
+            .add_bandwidth_usage("client-1", &report("2026-10", 100, 1000))
+            .await
+            .unwrap();
+        let usage = stats
+            .add_bandwidth_usage("client-1", &report("2026-10", 50, 2000))
+            .await
+            .unwrap();
+        stats
+            .add_bandwidth_usage("client-1", &report("2026-11", 7, 2000))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            (150, 10, 2000),
+            (
+                usage.uploaded_bytes,
+                usage.downloaded_bytes,
+                usage.hard_cap_bytes
+            )
+        );
+        assert_eq!(160, usage.total_bytes());
+        assert_eq!(
+            Some(usage),
+            stats
+                .get_bandwidth_usage("client-1", "2026-10")
+                .await
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use sqlx::SqlitePool;
+
+pub struct SyncBlackoutRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+/// a time window (server local time) the client must not sync in
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncBlackout {
+    pub id: i64,
+    /// bit 0 = monday ... bit 6 = sunday - the days the window starts on
+    pub weekdays: i64,
+    /// minutes after midnight - an end at or before the start lies on the next day
+    pub start_minute: i64,
+    pub end_minute: i64,
+    pub note: Option<String>,
+}
+
+impl<'a> SyncBlackoutRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_for_client(&self, client_id: &str) -> Result<Vec<SyncBlackout>> {
+        sqlx::query_as!(
+            SyncBlackout,
+            r#"
+            SELECT id, weekdays, start_minute, end_minute, note
+            FROM client_sync_blackout WHERE client_id = ? ORDER BY start_minute, id
+            "#,
+            client_id
+        )
+        .fetch_all(self.pool)
+        .await
+    }
+
+    pub async fn create(
+        &self,
+        client_id: &str,
+        weekdays: i64,
+        start_minute: i64,
+        end_minute: i64,
+        note: Option<&str>,
+    ) -> Result<i64> {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO client_sync_blackout (client_id, weekdays, start_minute, end_minute, note)
+            VALUES (?, ?, ?, ?, ?) RETURNING id
+            "#,
+            client_id,
+            weekdays,
+            start_minute,
+            end_minute,
+            note
+        )
+        .fetch_one(self.pool)
+        .await
+    }
+
+    /// Returns false if the client has no such blackout
+    pub async fn delete(&self, client_id: &str, id: i64) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM client_sync_blackout WHERE id = ? AND client_id = ?",
+            id,
+            client_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+
+    #[tokio::test]
+    async fn should_only_delete_blackouts_of_the_given_client() {
+        let db = test_db().await;
+        db.client().upsert_client("c1", "render-box").await.unwrap();
+        db.client().upsert_client("c2", "laptop").await.unwrap();
+        let repo = db.sync_blackout();
+        let id = repo
+            .create("c1", 0b1111111, 22 * 60, 6 * 60, Some("renders"))
+            .await
+            .unwrap();
+
+        assert!(!repo.delete("c2", id).await.unwrap());
+        let blackouts = repo.list_for_client("c1").await.unwrap();
+        assert_eq!(1, blackouts.len());
+        assert_eq!(22 * 60, blackouts[0].start_minute);
+        assert_eq!(Some("renders".to_string()), blackouts[0].note);
+        assert!(repo.list_for_client("c2").await.unwrap().is_empty());
+
+        assert!(repo.delete("c1", id).await.unwrap());
+        assert!(repo.list_for_client("c1").await.unwrap().is_empty());
+    }
+}
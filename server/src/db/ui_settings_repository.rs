@@ -0,0 +1,105 @@
+use shared::dtos::{DefaultPage, TableDensity, Theme, UiSettingsDto};
+use sqlx::SqlitePool;
+
+pub struct UiSettingsRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+impl<'a> UiSettingsRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Defaults if the account never saved any.
+    /// Unknown values (written by a newer version) fall back to their default.
+    pub async fn get(&self, account_id: i64) -> Result<UiSettingsDto> {
+        let row = sqlx::query!(
+            "SELECT theme, density, default_page FROM ui_settings WHERE account_id = ?",
+            account_id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(row
+            .map(|r| UiSettingsDto {
+                theme: Theme::try_from(r.theme.as_str()).unwrap_or_default(),
+                density: TableDensity::try_from(r.density.as_str()).unwrap_or_default(),
+                default_page: DefaultPage::try_from(r.default_page.as_str()).unwrap_or_default(),
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn save(&self, account_id: i64, settings: &UiSettingsDto) -> Result<()> {
+        let theme = settings.theme.as_str();
+        let density = settings.density.as_str();
+        let default_page = settings.default_page.as_str();
+        sqlx::query!(
+            r#"
+            INSERT INTO ui_settings (account_id, theme, density, default_page)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (account_id) DO UPDATE SET
+                theme = excluded.theme,
+                density = excluded.density,
+                default_page = excluded.default_page
+            "#,
+            account_id,
+            theme,
+            density,
+            default_page
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+    use shared::dtos::{DefaultPage, TableDensity, Theme, UiSettingsDto};
+
+    #[tokio::test]
+    async fn should_keep_settings_per_account() {
+        let db = test_db().await;
+        let alice = db.user().insert("alice").await.unwrap();
+        let light = UiSettingsDto {
+            theme: Theme::Light,
+            density: TableDensity::Compact,
+            default_page: DefaultPage::Monitor,
+        };
+        assert_eq!(
+            UiSettingsDto::default(),
+            db.ui_settings().get(0).await.unwrap()
+        );
+
+        db.ui_settings().save(alice, &light).await.unwrap();
+        db.ui_settings()
+            .save(
+                alice,
+                &UiSettingsDto {
+                    theme: Theme::Light,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        db.ui_settings().save(0, &light).await.unwrap();
+
+        assert_eq!(
+            Theme::Light,
+            db.ui_settings().get(alice).await.unwrap().theme
+        );
+        assert_eq!(
+            DefaultPage::Clients,
+            db.ui_settings().get(alice).await.unwrap().default_page
+        );
+        assert_eq!(light, db.ui_settings().get(0).await.unwrap());
+
+        db.user().delete(alice).await.unwrap();
+        assert_eq!(
+            UiSettingsDto::default(),
+            db.ui_settings().get(alice).await.unwrap()
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use shared::dtos::{UserDto, UserRole};
+use sqlx::SqlitePool;
+
+pub struct UserRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+impl<'a> UserRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<UserDto>> {
+        let rows = sqlx::query!("SELECT id, name, role FROM user_account ORDER BY name")
+            .fetch_all(self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| UserDto {
+                id: r.id,
+                name: r.name,
+                role: UserRole::try_from(r.role.as_str()).unwrap_or(UserRole::ReadOnly),
+            })
+            .collect())
+    }
+
+    /// Returns the id of the new user
+    pub async fn insert(&self, name: &str) -> Result<i64> {
+        sqlx::query_scalar!(
+            "INSERT INTO user_account (name) VALUES (?) RETURNING id",
+            name
+        )
+        .fetch_one(self.pool)
+        .await
+    }
+
+    /// Returns false if not found.
+    pub async fn rename(&self, id: i64, name: &str) -> Result<bool> {
+        let result = sqlx::query!("UPDATE user_account SET name = ? WHERE id = ?", name, id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns false if not found.
+    pub async fn set_role(&self, id: i64, role: UserRole) -> Result<bool> {
+        let role = role.as_str();
+        let result = sqlx::query!("UPDATE user_account SET role = ? WHERE id = ?", role, id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Replaces any previous token of the user. Returns false if not found.
+    pub async fn set_api_token_hash(&self, id: i64, api_token_hash: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE user_account SET api_token_hash = ? WHERE id = ?",
+            api_token_hash,
+            id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Id and role of the user holding the token - None if no user has that token
+    pub async fn get_by_api_token_hash(
+        &self,
+        api_token_hash: &str,
+    ) -> Result<Option<(i64, UserRole)>> {
+        let row = sqlx::query!(
+            "SELECT id, role FROM user_account WHERE api_token_hash = ?",
+            api_token_hash
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(row.map(|r| {
+            let role = UserRole::try_from(r.role.as_str()).unwrap_or(UserRole::ReadOnly);
+            (r.id, role)
+        }))
+    }
+
+    pub async fn exists(&self, id: i64) -> Result<bool> {
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM user_account WHERE id = ?", id)
+            .fetch_one(self.pool)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Delete a user and its ui settings. Owned clients and watch groups fall back to
+    /// "no owner". Returns false if not found.
+    pub async fn delete(&self, id: i64) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM ui_settings WHERE account_id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query!("DELETE FROM user_account WHERE id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,76 @@
+use sqlx::SqlitePool;
+
+pub struct WebhookRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+impl<'a> WebhookRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_for_watch_group(&self, wg_id: i64) -> Result<Vec<Webhook>> {
+        sqlx::query_as!(
+            Webhook,
+            "SELECT id, url, secret FROM webhook WHERE server_watch_group_id = ? ORDER BY id",
+            wg_id
+        )
+        .fetch_all(self.pool)
+        .await
+    }
+
+    pub async fn create(&self, wg_id: i64, url: &str, secret: Option<&str>) -> Result<i64> {
+        sqlx::query_scalar!(
+            "INSERT INTO webhook (server_watch_group_id, url, secret) VALUES (?, ?, ?) RETURNING id",
+            wg_id,
+            url,
+            secret
+        )
+        .fetch_one(self.pool)
+        .await
+    }
+
+    /// Returns false if the watch group has no such webhook
+    pub async fn delete(&self, wg_id: i64, id: i64) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM webhook WHERE id = ? AND server_watch_group_id = ?",
+            id,
+            wg_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db;
+
+    #[tokio::test]
+    async fn should_only_delete_webhooks_of_the_given_watch_group() {
+        let db = test_db().await;
+        let repo = db.webhook();
+        let id = repo
+            .create(1, "https://ci.local/hook", Some("s3cret"))
+            .await
+            .unwrap();
+
+        assert!(!repo.delete(2, id).await.unwrap());
+        let hooks = repo.list_for_watch_group(1).await.unwrap();
+        assert_eq!(1, hooks.len());
+        assert_eq!(Some("s3cret".to_string()), hooks[0].secret);
+
+        assert!(repo.delete(1, id).await.unwrap());
+        assert!(repo.list_for_watch_group(1).await.unwrap().is_empty());
+    }
+}
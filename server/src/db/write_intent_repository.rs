@@ -0,0 +1,71 @@
+use sqlx::SqlitePool;
+
+pub struct WriteIntentRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+type Result<T> = sqlx::Result<T>;
+
+/// an upload about to be moved from its temp file into the watch group
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteIntent {
+    pub id: String,
+    pub watch_group_id: i64,
+    pub relative_path: String,
+    pub temp_path: String,
+    pub target_path: String,
+    pub size_in_bytes: i64,
+    pub utc_millis: i64,
+    pub client_id: Option<String>,
+    pub client_host: Option<String>,
+}
+
+impl<'a> WriteIntentRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn insert(&self, intent: &WriteIntent) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO write_intent
+                (id, watch_group_id, relative_path, temp_path, target_path, size_in_bytes,
+                 utc_millis, client_id, client_host)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            intent.id,
+            intent.watch_group_id,
+            intent.relative_path,
+            intent.temp_path,
+            intent.target_path,
+            intent.size_in_bytes,
+            intent.utc_millis,
+            intent.client_id,
+            intent.client_host
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// the intents left behind - oldest first
+    pub async fn list(&self) -> Result<Vec<WriteIntent>> {
+        sqlx::query_as!(
+            WriteIntent,
+            r#"
+            SELECT id, watch_group_id, relative_path, temp_path, target_path, size_in_bytes,
+                   utc_millis, client_id, client_host
+            FROM write_intent ORDER BY utc_millis ASC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM write_intent WHERE id = ?", id)
+            .execute(self.pool)
+            .await?;
+        Ok(())
+    }
+}
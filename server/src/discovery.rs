@@ -0,0 +1,57 @@
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use shared::endpoint::{MDNS_SCHEME_KEY, MDNS_SERVICE_TYPE, MDNS_VERSION_KEY};
+use std::env;
+use std::sync::LazyLock;
+use tracing::{info, warn};
+
+/// `MDNS_DISCOVERY=true` advertises the server on the LAN - `client init` offers it then
+static MDNS_DISCOVERY_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env::var("MDNS_DISCOVERY").is_ok_and(|v| v == "true"));
+
+/// name shown to clients (default: hostname)
+static SERVER_NAME: LazyLock<String> = LazyLock::new(|| {
+    env::var("SERVER_NAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .or_else(sysinfo::System::host_name)
+        .unwrap_or_else(|| "rust-file-sync".to_string())
+});
+
+/// the returned daemon answers the queries - keep it alive as long as the server runs
+pub fn advertise(port: u16, tls: bool) -> Option<ServiceDaemon> {
+    if !*MDNS_DISCOVERY_ENABLED {
+        return None;
+    }
+    let host = sysinfo::System::host_name().unwrap_or_else(|| "rust-file-sync".to_string());
+    let scheme = if tls { "https" } else { "http" };
+    let properties = [
+        (MDNS_VERSION_KEY, env!("CARGO_PKG_VERSION")),
+        (MDNS_SCHEME_KEY, scheme),
+    ];
+    let advertised = ServiceDaemon::new().and_then(|daemon| {
+        let service = ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &SERVER_NAME,
+            &format!("{host}.local."),
+            "",
+            port,
+            &properties[..],
+        )?
+        .enable_addr_auto();
+        daemon.register(service)?;
+        Ok(daemon)
+    });
+    match advertised {
+        Ok(daemon) => {
+            info!(
+                "Advertising '{}' via mDNS ({MDNS_SERVICE_TYPE})",
+                *SERVER_NAME
+            );
+            Some(daemon)
+        }
+        Err(e) => {
+            warn!("mDNS discovery disabled - {e}");
+            None
+        }
+    }
+}
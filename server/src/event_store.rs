@@ -0,0 +1,227 @@
+use crate::HISTORY_CSV_PATH;
+use crate::db::ServerDatabase;
+use crate::file_event::{FileEvent, FileEventType};
+use crate::file_history::FileHistory;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
+
+const CSV_HEADER: &str =
+    "id;utc_millis;relative_path;size_in_bytes;event_type;client_host;watch_group_id";
+
+/// the one place file events are persisted
+///
+/// the file_event table is the record, the [`FileHistory`] caches it for syncs and the csv
+/// export (`HISTORY_CSV_EXPORT=./data/export/history.csv`, none by default) is a copy for
+/// spreadsheets and scripts - it's never read back
+pub struct EventStore {
+    db: ServerDatabase,
+    history: Arc<dyn FileHistory>,
+    /// held while appending, so lines of concurrent events don't interleave
+    csv_export: Option<Mutex<PathBuf>>,
+}
+
+impl EventStore {
+    pub fn new(
+        db: ServerDatabase,
+        history: Arc<dyn FileHistory>,
+        csv_export: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            db,
+            history,
+            csv_export: csv_export.map(Mutex::new),
+        }
+    }
+
+    pub fn from_env(db: ServerDatabase, history: Arc<dyn FileHistory>) -> Self {
+        let csv_export = env::var("HISTORY_CSV_EXPORT")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .filter(|path| {
+                // the legacy csv would be imported again on the next start
+                let legacy = path.as_path() == *HISTORY_CSV_PATH;
+                if legacy {
+                    warn!("HISTORY_CSV_EXPORT must not be {:?} - not exporting", path);
+                }
+                !legacy
+            });
+        if let Some(path) = &csv_export {
+            info!("Exporting file events to {:?}", path);
+        }
+        Self::new(db, history, csv_export)
+    }
+
+    /// persists the event and hands it to the history - without a client id it's only held by
+    /// the history (every row of the DB has a client)
+    ///
+    /// the history gets the event even if the DB refused it, so syncs still follow the file on
+    /// disk (until it's evicted from the cache)
+    pub async fn record(&self, event: &FileEvent, client_id: Option<&str>) -> sqlx::Result<()> {
+        let result = match client_id {
+            Some(client_id) => self.db.file_event().insert(event, client_id).await,
+            None => Ok(()),
+        };
+        if result.is_ok() && client_id.is_some() {
+            self.export(event);
+        }
+        self.supersede_pending_delete(event).await;
+        self.history.add(event.clone());
+        result
+    }
+
+    /// like [`EventStore::record`] for an event of the primary, under its original id - returns
+    /// false (and leaves the history alone) if it was recorded already
+    pub async fn record_replicated(&self, event: &FileEvent) -> sqlx::Result<bool> {
+        let recorded = self.db.replication().insert_event(event).await?;
+        if recorded {
+            self.export(event);
+            self.supersede_pending_delete(event).await;
+            self.history.add(event.clone());
+        }
+        Ok(recorded)
+    }
+
+    /// a change of the path - whichever way it was written - cancels its pending delete, which
+    /// would remove the newer file once the grace period is over
+    async fn supersede_pending_delete(&self, event: &FileEvent) {
+        if event.event_type != FileEventType::ChangeEvent {
+            return;
+        }
+        let relative_path = event.relative_path.to_serialized_string();
+        match self
+            .db
+            .pending_delete()
+            .remove_path(event.watch_group_id, &relative_path)
+            .await
+        {
+            Ok(true) => info!("Change of {} cancelled its pending delete", relative_path),
+            Ok(false) => {}
+            Err(e) => error!("Failed to cancel pending delete: {}", e),
+        }
+    }
+
+    fn export(&self, event: &FileEvent) {
+        let Some(path) = &self.csv_export else {
+            return;
+        };
+        let path = path.lock().unwrap();
+        if let Err(e) = append_csv_line(&path, &event.serialize_to_csv_line()) {
+            error!("Failed to export file event to {:?}: {}", *path, e);
+        }
+    }
+}
+
+fn append_csv_line(path: &Path, line: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{CSV_HEADER}")?;
+    }
+    writeln!(file, "{line}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+    use crate::file_history::InMemoryFileHistory;
+    use shared::matchable_path::MatchablePath;
+    use shared::utc_millis::UtcMillis;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn should_persist_export_and_cache_recorded_events() {
+        let db = test_db().await;
+        db.client().upsert_client("c1", "laptop").await.unwrap();
+        let history: Arc<dyn FileHistory> = Arc::new(InMemoryFileHistory::from(Vec::new()));
+        let csv = env::temp_dir()
+            .join(format!("event-store-{}", Uuid::new_v4()))
+            .join("history.csv");
+        let store = EventStore::new(db.clone(), history.clone(), Some(csv.clone()));
+
+        let event = |path: &str| {
+            FileEvent::new(
+                Uuid::new_v4(),
+                UtcMillis::now(),
+                MatchablePath::from(path),
+                3,
+                FileEventType::ChangeEvent,
+                Some("laptop".to_string()),
+                1,
+            )
+        };
+        let persisted = event("a.txt");
+        store.record(&persisted, Some("c1")).await.unwrap();
+        // no client - held by the history only
+        store.record(&event("b.txt"), None).await.unwrap();
+
+        assert_eq!(2, history.get_latest_events(1).len());
+        let rows = db.file_event().get_latest_events(1).await.unwrap();
+        assert_eq!(
+            vec![persisted.id],
+            rows.iter().map(|e| e.id).collect::<Vec<_>>()
+        );
+        let lines: Vec<String> = std::fs::read_to_string(&csv)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        assert_eq!(
+            vec![CSV_HEADER.to_string(), persisted.serialize_to_csv_line()],
+            lines
+        );
+        // the export reads like the legacy csv
+        assert_eq!(persisted, FileEvent::try_from(lines[1].as_str()).unwrap());
+
+        std::fs::remove_dir_all(csv.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_cancel_pending_deletes_on_changes_of_any_writer() {
+        let db = test_db().await;
+        db.server_watch_group()
+            .insert_watch_group("wg".to_string())
+            .await
+            .unwrap();
+        // e.g. the inbox of a share link - not a sync client
+        db.client().upsert_client("inbox", "inbox").await.unwrap();
+        let history: Arc<dyn FileHistory> = Arc::new(InMemoryFileHistory::from(Vec::new()));
+        let store = EventStore::new(db.clone(), history, None);
+        let pending = db.pending_delete();
+        for path in ["a.txt", "b.txt"] {
+            pending
+                .insert(1, path, "c1", None, UtcMillis::from(1), UtcMillis::from(2))
+                .await
+                .unwrap();
+        }
+
+        let event = |path: &str, event_type| {
+            FileEvent::new(
+                Uuid::new_v4(),
+                UtcMillis::now(),
+                MatchablePath::from(path),
+                3,
+                event_type,
+                Some("inbox".to_string()),
+                1,
+            )
+        };
+        store
+            .record(&event("a.txt", FileEventType::ChangeEvent), Some("inbox"))
+            .await
+            .unwrap();
+        store
+            .record(&event("b.txt", FileEventType::DeleteEvent), Some("inbox"))
+            .await
+            .unwrap();
+
+        assert_eq!(vec!["b.txt".to_string()], pending.paths(1).await.unwrap());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::db::ServerDatabase;
+use crate::{AppState, supervisor};
+use async_nats::ConnectOptions;
+use async_nats::header::{HeaderMap, NATS_MESSAGE_ID};
+use async_nats::jetstream;
+use bytes::Bytes;
+use rskafka::BackoffConfig;
+use rskafka::chrono::{TimeZone, Utc};
+use rskafka::client::ClientBuilder;
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+use rskafka::record::Record;
+use shared::dtos::ExportEventDto;
+use shared::utc_millis::UtcMillis;
+use std::collections::BTreeMap;
+use std::env;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// checkpoint of the NATS sink - listed in GET /api/export next to those of http consumers
+const NATS_SINK: &str = "nats";
+/// checkpoint of the Kafka sink
+const KAFKA_SINK: &str = "kafka";
+/// events per round trip to the NATS server / the Kafka broker
+const BATCH_SIZE: u32 = 500;
+/// how long a Kafka request is retried before the tick gives up - the next one starts over
+const KAFKA_DEADLINE: Duration = Duration::from_secs(30);
+
+/// `EXPORT_NATS_URL=nats://host:4222` publishes every file event to JetStream, as subject
+/// `{EXPORT_NATS_SUBJECT}.{wg_id}` (default `rust-file-sync.events`) - a stream has to cover it
+///
+/// delivery is at-least-once: the checkpoint only moves past events JetStream acknowledged, and
+/// their event id goes along as `Nats-Msg-Id`, so redelivered ones are dropped by its dedup window
+pub async fn schedule_nats_export(state: AppState) {
+    let Some(url) = env::var("EXPORT_NATS_URL").ok().filter(|u| !u.is_empty()) else {
+        return;
+    };
+    let subject = env::var("EXPORT_NATS_SUBJECT")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "rust-file-sync.events".to_string());
+    let token = env::var("EXPORT_NATS_TOKEN").ok().filter(|t| !t.is_empty());
+    let interval = export_interval();
+    info!("Exporting file events to {} as {}.*", url, subject);
+
+    let mut context: Option<jetstream::Context> = None;
+    let mut last_error: Option<String> = None;
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        supervisor::tick(&mut ticks).await;
+        if context.is_none() {
+            let options = match &token {
+                Some(token) => ConnectOptions::with_token(token.clone()),
+                None => ConnectOptions::new(),
+            };
+            match async_nats::connect_with_options(url.as_str(), options).await {
+                Ok(client) => context = Some(jetstream::new(client)),
+                Err(e) => {
+                    report(&mut last_error, format!("Connecting to {url} failed - {e}"));
+                    continue;
+                }
+            }
+        }
+        let Some(js) = &context else {
+            continue;
+        };
+        let sink = Sink::Nats {
+            js,
+            subject: &subject,
+        };
+        match export_pending(&state.db, &sink).await {
+            Ok(published) => {
+                if published > 0 {
+                    info!("Exported {} file events to NATS", published);
+                }
+                supervisor::succeeded();
+                last_error = None;
+            }
+            // the client reconnects by itself - the next tick retries from the checkpoint
+            Err(e) => report(&mut last_error, e),
+        }
+    }
+}
+
+/// `EXPORT_KAFKA_BROKERS=host:9092[,host:9092]` produces every file event to partition 0 of the
+/// topic `EXPORT_KAFKA_TOPIC` (default `rust-file-sync.events`, has to exist) - one partition keeps
+/// the order of the event log; the key is the watch group id
+///
+/// delivery is at-least-once: the checkpoint only moves past batches the broker acknowledged,
+/// consumers drop redelivered events by their `event-id` header
+pub async fn schedule_kafka_export(state: AppState) {
+    let brokers: Vec<String> = env::var("EXPORT_KAFKA_BROKERS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|broker| broker.trim().to_string())
+        .filter(|broker| !broker.is_empty())
+        .collect();
+    if brokers.is_empty() {
+        return;
+    }
+    let topic = env::var("EXPORT_KAFKA_TOPIC")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| "rust-file-sync.events".to_string());
+    let interval = export_interval();
+    info!(
+        "Exporting file events to Kafka topic {} via {}",
+        topic,
+        brokers.join(",")
+    );
+
+    let mut partition: Option<PartitionClient> = None;
+    let mut last_error: Option<String> = None;
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        supervisor::tick(&mut ticks).await;
+        if partition.is_none() {
+            match connect_kafka(&brokers, &topic).await {
+                Ok(client) => partition = Some(client),
+                Err(e) => {
+                    report(&mut last_error, e);
+                    continue;
+                }
+            }
+        }
+        let Some(client) = &partition else {
+            continue;
+        };
+        match export_pending(&state.db, &Sink::Kafka(client)).await {
+            Ok(published) => {
+                if published > 0 {
+                    info!("Exported {} file events to Kafka", published);
+                }
+                supervisor::succeeded();
+                last_error = None;
+            }
+            // the client reconnects to the partition leader by itself
+            Err(e) => report(&mut last_error, e),
+        }
+    }
+}
+
+/// `EXPORT_INTERVAL_SECS` (default 5) - how often the sinks look for new events
+fn export_interval() -> Duration {
+    let secs = env::var("EXPORT_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(5)
+        .max(1);
+    Duration::from_secs(secs)
+}
+
+async fn connect_kafka(brokers: &[String], topic: &str) -> Result<PartitionClient, String> {
+    let backoff = BackoffConfig {
+        deadline: Some(KAFKA_DEADLINE),
+        ..BackoffConfig::default()
+    };
+    let client = ClientBuilder::new(brokers.to_vec())
+        .client_id("rust-file-sync")
+        .backoff_config(backoff)
+        .build()
+        .await
+        .map_err(|e| format!("Connecting to {} failed - {e}", brokers.join(",")))?;
+    client
+        .partition_client(topic, 0, UnknownTopicHandling::Error)
+        .await
+        .map_err(|e| format!("Topic {topic} isn't available - {e}"))
+}
+
+/// logs a failure once until it changes or the export works again
+fn report(last_error: &mut Option<String>, e: String) {
+    if last_error.as_ref() != Some(&e) {
+        warn!("Event export failed - {}", e);
+    }
+    supervisor::failed(&e);
+    *last_error = Some(e);
+}
+
+enum Sink<'a> {
+    Nats {
+        js: &'a jetstream::Context,
+        subject: &'a str,
+    },
+    Kafka(&'a PartitionClient),
+}
+
+impl Sink<'_> {
+    /// name of its checkpoint
+    fn name(&self) -> &'static str {
+        match self {
+            Sink::Nats { .. } => NATS_SINK,
+            Sink::Kafka(_) => KAFKA_SINK,
+        }
+    }
+
+    /// how many events of the batch were acknowledged (in order) and the failure that stopped
+    /// it, if any
+    async fn publish(&self, events: &[ExportEventDto]) -> (usize, Option<String>) {
+        match self {
+            Sink::Nats { js, subject } => publish(js, subject, events).await,
+            Sink::Kafka(client) => produce(client, events).await,
+        }
+    }
+}
+
+/// hands every event after the checkpoint of the sink to it, batch by batch - returns how many
+/// were acknowledged
+async fn export_pending(db: &ServerDatabase, sink: &Sink<'_>) -> Result<u64, String> {
+    let export = db.export();
+    let mut checkpoint = export
+        .checkpoint(sink.name())
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut published = 0;
+    loop {
+        let events = export
+            .events_after(checkpoint, BATCH_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        let complete = events.len() < BATCH_SIZE as usize;
+
+        let (acknowledged, failure) = sink.publish(&events).await;
+        if let Some(last) = events[..acknowledged].last() {
+            checkpoint = last.seq;
+            published += acknowledged as u64;
+        }
+        if let Err(e) = export
+            .set_checkpoint(sink.name(), checkpoint, UtcMillis::now())
+            .await
+        {
+            error!(
+                "Failed to store the checkpoint of the {} export: {}",
+                sink.name(),
+                e
+            );
+            return Err(e.to_string());
+        }
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        if complete {
+            return Ok(published);
+        }
+    }
+}
+
+/// publishes the batch first, then waits for the acks - one round trip instead of one per event
+async fn publish(
+    js: &jetstream::Context,
+    subject: &str,
+    events: &[ExportEventDto],
+) -> (usize, Option<String>) {
+    let mut acks = Vec::with_capacity(events.len());
+    for event in events {
+        let payload = match serde_json::to_vec(event) {
+            Ok(payload) => payload,
+            Err(e) => return (0, Some(e.to_string())),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(NATS_MESSAGE_ID, event.event_id.as_str());
+        match js
+            .publish_with_headers(
+                format!("{subject}.{}", event.watch_group_id),
+                headers,
+                Bytes::from(payload),
+            )
+            .await
+        {
+            Ok(ack) => acks.push(ack),
+            Err(e) => return (0, Some(format!("Publishing failed - {e}"))),
+        }
+    }
+    let mut acknowledged = 0;
+    for ack in acks {
+        if let Err(e) = ack.await {
+            return (acknowledged, Some(format!("Publishing failed - {e}")));
+        }
+        acknowledged += 1;
+    }
+    (acknowledged, None)
+}
+
+/// produces the batch in one request - the broker acknowledges all of it or nothing
+async fn produce(client: &PartitionClient, events: &[ExportEventDto]) -> (usize, Option<String>) {
+    let mut records = Vec::with_capacity(events.len());
+    for event in events {
+        match record(event) {
+            Ok(record) => records.push(record),
+            Err(e) => return (0, Some(e.to_string())),
+        }
+    }
+    match client.produce(records, Compression::NoCompression).await {
+        Ok(_) => (events.len(), None),
+        Err(e) => (0, Some(format!("Producing failed - {e}"))),
+    }
+}
+
+fn record(event: &ExportEventDto) -> serde_json::Result<Record> {
+    Ok(Record {
+        key: Some(event.watch_group_id.to_string().into_bytes()),
+        value: Some(serde_json::to_vec(event)?),
+        headers: BTreeMap::from([("event-id".to_string(), event.event_id.clone().into_bytes())]),
+        timestamp: Utc
+            .timestamp_millis_opt(event.utc_millis.as_u64() as i64)
+            .single()
+            .unwrap_or_else(Utc::now),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_key_kafka_records_by_watch_group() {
+        let event = ExportEventDto {
+            seq: 7,
+            event_id: "e7".to_string(),
+            watch_group_id: 3,
+            relative_path: "dir/a.txt".to_string(),
+            event_type: "change".to_string(),
+            size_in_bytes: 5,
+            utc_millis: UtcMillis::from(1_700_000_000_123),
+            client_id: "c1".to_string(),
+            client_host: None,
+        };
+
+        let record = record(&event).unwrap();
+        assert_eq!(Some(b"3".to_vec()), record.key);
+        assert_eq!(Some(&b"e7".to_vec()), record.headers.get("event-id"));
+        assert_eq!(1_700_000_000_123, record.timestamp.timestamp_millis());
+        let value: ExportEventDto = serde_json::from_slice(&record.value.unwrap()).unwrap();
+        assert_eq!(7, value.seq);
+    }
+}
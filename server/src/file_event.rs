@@ -1,6 +1,7 @@
 use serde_json::to_string;
 use shared::dtos::FileDescription;
 use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::LatestEvent;
 use shared::utc_millis::UtcMillis;
 use std::fmt::Debug;
 use std::path::Path;
@@ -61,25 +62,43 @@ pub struct FileEvent {
     pub watch_group_id: i64,
 }
 
-impl Into<FileDescription> for FileEvent {
-    fn into(self) -> FileDescription {
-        let file_name = self.relative_path.tail();
+impl From<FileEvent> for FileDescription {
+    fn from(event: FileEvent) -> Self {
+        let file_name = event.relative_path.tail();
         FileDescription {
             file_name: file_name.clone(),
-            relative_path: self.relative_path,
-            size_in_bytes: self.size_in_bytes,
+            relative_path: event.relative_path,
+            size_in_bytes: event.size_in_bytes,
             file_type: Path::new(&file_name)
                 .extension()
                 .and_then(|e| e.to_str())
                 .unwrap_or_default()
                 .to_string(),
-            last_updated_utc_millis: self.utc_millis,
+            last_updated_utc_millis: event.utc_millis,
         }
     }
 }
 
+impl LatestEvent for FileEvent {
+    fn relative_path(&self) -> &MatchablePath {
+        &self.relative_path
+    }
+
+    fn utc_millis(&self) -> &UtcMillis {
+        &self.utc_millis
+    }
+
+    fn size_in_bytes(&self) -> u64 {
+        self.size_in_bytes
+    }
+
+    fn is_delete(&self) -> bool {
+        self.event_type.is_delete()
+    }
+}
+
 impl FileEvent {
-    /// produces csv line with ; as separator
+    /// produces csv line with ; as separator (counterpart of `TryFrom<&str>`)
     pub fn serialize_to_csv_line(&self) -> String {
         let parts = [
             self.id.to_string(),
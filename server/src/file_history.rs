@@ -1,10 +1,15 @@
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::sync::{Arc, Mutex};
 
+use futures_util::StreamExt;
+use lru::LruCache;
 use shared::matchable_path::MatchablePath;
+use tokio::runtime::Handle;
 use tokio::time::Instant;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
+use crate::db::ServerDatabase;
 use crate::file_event::FileEvent;
 
 pub trait FileHistory: Send + Sync {
@@ -16,6 +21,10 @@ pub trait FileHistory: Send + Sync {
     fn get_latest_event(&self, wg_id: i64, path: &MatchablePath) -> Option<FileEvent>;
     /// get the latest event of every path within a watch group
     fn get_latest_events(&self, wg_id: i64) -> Vec<FileEvent>;
+    /// forget all events of a (deleted) watch group
+    fn remove_watch_group(&self, wg_id: i64);
+    /// attribute the held events of a client to another one (after it was claimed)
+    fn reattribute(&self, from_client_id: &str, to_client_id: &str);
     /// check if compliant with rules (chronologically sorted + grouped by path) - may panic
     fn sanity_check(&self);
 }
@@ -23,10 +32,60 @@ pub trait FileHistory: Send + Sync {
 /// outer key = watch_group_id, inner key = rel. file path, value = events (chronological)
 type HistoryStore = HashMap<i64, HashMap<MatchablePath, Vec<FileEvent>>>;
 
+/// log every n events while loading the history
+const LOAD_PROGRESS_INTERVAL: u64 = 100_000;
+
 /// multiple [`FileEvent`]s represent a history which allows to draw conclusions for synchronization of clients
+///
+/// [`InMemoryFileHistory::load`]ed from the DB only the latest event per path is held - older
+/// ones are read from the DB on demand
 #[derive(Default, Clone)]
 pub struct InMemoryFileHistory {
     store: Arc<Mutex<HistoryStore>>,
+    db: Option<ServerDatabase>,
+}
+
+impl InMemoryFileHistory {
+    /// streams the file_event table - memory grows with the number of paths, not of events
+    pub async fn load(db: &ServerDatabase) -> sqlx::Result<Self> {
+        let i = Instant::now();
+        let mut inner: HistoryStore = HashMap::new();
+        let mut count = 0u64;
+        let file_event = db.file_event();
+        let mut events = file_event.stream_all_events();
+        while let Some(event) = events.next().await {
+            let event = event?;
+            let latest = inner
+                .entry(event.watch_group_id)
+                .or_default()
+                .entry(event.relative_path.clone())
+                .or_default();
+            // rows come in insertion order - later rows win ties
+            if latest
+                .last()
+                .is_none_or(|l| l.utc_millis <= event.utc_millis)
+            {
+                *latest = vec![event];
+            }
+            count += 1;
+            if count.is_multiple_of(LOAD_PROGRESS_INTERVAL) {
+                info!(
+                    "Loading history - {count} events read ({}s)",
+                    i.elapsed().as_secs()
+                );
+            }
+        }
+
+        let paths: usize = inner.values().map(HashMap::len).sum();
+        info!(
+            "History loaded - latest events of {paths} paths out of {count} events - took {}ms",
+            i.elapsed().as_millis()
+        );
+        Ok(InMemoryFileHistory {
+            store: Arc::new(Mutex::new(inner)),
+            db: Some(db.clone()),
+        })
+    }
 }
 
 impl From<Vec<FileEvent>> for InMemoryFileHistory {
@@ -48,6 +107,7 @@ impl From<Vec<FileEvent>> for InMemoryFileHistory {
 
         let history = InMemoryFileHistory {
             store: Arc::new(Mutex::new(inner)),
+            db: None,
         };
         history.sanity_check();
         info!(
@@ -62,18 +122,33 @@ impl FileHistory for InMemoryFileHistory {
     fn add(&self, event: FileEvent) {
         let mut guard = self.store.lock().unwrap();
         let wg_map = guard.entry(event.watch_group_id).or_default();
-        wg_map
-            .entry(event.relative_path.clone())
-            .or_default()
-            .push(event);
+        let events = wg_map.entry(event.relative_path.clone()).or_default();
+        // the full history is in the DB
+        if self.db.is_some() {
+            events.clear();
+        }
+        events.push(event);
     }
 
     fn get_events(&self, wg_id: i64, path: &MatchablePath) -> Option<Vec<FileEvent>> {
-        self.store
+        let held = self
+            .store
             .lock()
             .unwrap()
             .get(&wg_id)
-            .and_then(|wg_map| wg_map.get(path).cloned())
+            .and_then(|wg_map| wg_map.get(path).cloned());
+        let Some(db) = &self.db else {
+            return held;
+        };
+        let mut events = block_on(db.file_event().get_events_of_path(wg_id, path))
+            .map_err(|e| error!("Failed to load history of {:?}: {}", path, e))
+            .unwrap_or_default();
+        if let Some(latest) = held.and_then(|held| held.last().cloned())
+            && events.last().is_none_or(|e| e.id != latest.id)
+        {
+            events.push(latest);
+        }
+        (!events.is_empty()).then_some(events)
     }
 
     fn get_latest_event(&self, wg_id: i64, path: &MatchablePath) -> Option<FileEvent> {
@@ -88,13 +163,27 @@ impl FileHistory for InMemoryFileHistory {
             .get(&wg_id)
             .map(|wg_map| {
                 wg_map
-                    .iter()
-                    .filter_map(|(_, events)| events.last().cloned())
+                    .values()
+                    .filter_map(|events| events.last().cloned())
                     .collect()
             })
             .unwrap_or_default()
     }
 
+    fn remove_watch_group(&self, wg_id: i64) {
+        self.store.lock().unwrap().remove(&wg_id);
+    }
+
+    fn reattribute(&self, from_client_id: &str, to_client_id: &str) {
+        let mut guard = self.store.lock().unwrap();
+        let events = guard.values_mut().flat_map(|wg_map| wg_map.values_mut());
+        for event in events.flatten() {
+            if event.client_host.as_deref() == Some(from_client_id) {
+                event.client_host = Some(to_client_id.to_string());
+            }
+        }
+    }
+
     /// might panic if there is a programmatic error (sorting / grouping)
     fn sanity_check(&self) {
         for (_, wg_map) in self.store.lock().unwrap().iter() {
@@ -121,11 +210,140 @@ impl FileHistory for InMemoryFileHistory {
     }
 }
 
+/// history read from the file_event table - memory stays flat no matter how long the history gets
+///
+/// events are persisted by the [`crate::event_store::EventStore`] before they are added, so
+/// [`FileHistory::add`] only updates the cache of latest events per path (events which couldn't
+/// be persisted live on in the cache until evicted)
+///
+/// needs the multi-threaded runtime, since the trait is sync but sqlx isn't
+pub struct SqliteFileHistory {
+    db: ServerDatabase,
+    latest: Mutex<LruCache<(i64, MatchablePath), FileEvent>>,
+}
+
+impl SqliteFileHistory {
+    pub fn new(db: ServerDatabase, cache_size: NonZeroUsize) -> Self {
+        Self {
+            db,
+            latest: Mutex::new(LruCache::new(cache_size)),
+        }
+    }
+
+    fn cached_latest(&self, wg_id: i64, path: &MatchablePath) -> Option<FileEvent> {
+        self.latest
+            .lock()
+            .unwrap()
+            .get(&(wg_id, path.clone()))
+            .cloned()
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| Handle::current().block_on(future))
+}
+
+impl FileHistory for SqliteFileHistory {
+    fn add(&self, event: FileEvent) {
+        self.latest
+            .lock()
+            .unwrap()
+            .put((event.watch_group_id, event.relative_path.clone()), event);
+    }
+
+    fn get_events(&self, wg_id: i64, path: &MatchablePath) -> Option<Vec<FileEvent>> {
+        let mut events = block_on(self.db.file_event().get_events_of_path(wg_id, path))
+            .map_err(|e| error!("Failed to load history of {:?}: {}", path, e))
+            .unwrap_or_default();
+        if let Some(latest) = self.cached_latest(wg_id, path)
+            && events.last().is_none_or(|e| e.id != latest.id)
+        {
+            events.push(latest);
+        }
+        (!events.is_empty()).then_some(events)
+    }
+
+    fn get_latest_event(&self, wg_id: i64, path: &MatchablePath) -> Option<FileEvent> {
+        if let Some(latest) = self.cached_latest(wg_id, path) {
+            return Some(latest);
+        }
+        let latest = block_on(self.db.file_event().get_latest_event_of_path(wg_id, path))
+            .map_err(|e| error!("Failed to load latest event of {:?}: {}", path, e))
+            .ok()
+            .flatten()?;
+        self.add(latest.clone());
+        Some(latest)
+    }
+
+    fn get_latest_events(&self, wg_id: i64) -> Vec<FileEvent> {
+        let mut latest: HashMap<MatchablePath, FileEvent> =
+            block_on(self.db.file_event().get_latest_events(wg_id))
+                .map_err(|e| error!("Failed to load latest events of watch group {wg_id}: {e}"))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| (e.relative_path.clone(), e))
+                .collect();
+        // cached events are never older than the persisted ones
+        for ((cached_wg_id, path), event) in self.latest.lock().unwrap().iter() {
+            if *cached_wg_id == wg_id {
+                latest.insert(path.clone(), event.clone());
+            }
+        }
+        latest.into_values().collect()
+    }
+
+    /// the events are deleted with the watch group - only the cache is left
+    fn remove_watch_group(&self, wg_id: i64) {
+        let mut latest = self.latest.lock().unwrap();
+        let keys: Vec<(i64, MatchablePath)> = latest
+            .iter()
+            .filter(|((cached_wg_id, _), _)| *cached_wg_id == wg_id)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            latest.pop(&key);
+        }
+    }
+
+    fn reattribute(&self, from_client_id: &str, to_client_id: &str) {
+        for (_, event) in self.latest.lock().unwrap().iter_mut() {
+            if event.client_host.as_deref() == Some(from_client_id) {
+                event.client_host = Some(to_client_id.to_string());
+            }
+        }
+    }
+
+    /// ordering and grouping are done by the queries
+    fn sanity_check(&self) {}
+}
+
+/// HISTORY_BACKEND=sqlite reads the history from the DB (cache size: HISTORY_CACHE_SIZE,
+/// default 10000 paths) - otherwise the latest event of every path is held in memory
+pub async fn from_env(db: &ServerDatabase) -> Arc<dyn FileHistory> {
+    if std::env::var("HISTORY_BACKEND").is_ok_and(|v| v.eq_ignore_ascii_case("sqlite")) {
+        let cache_size = std::env::var("HISTORY_CACHE_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<NonZeroUsize>().ok())
+            .unwrap_or(NonZeroUsize::new(10_000).unwrap());
+        info!("Using DB-backed history (caching latest events of {cache_size} paths)");
+        return Arc::new(SqliteFileHistory::new(db.clone(), cache_size));
+    }
+
+    match InMemoryFileHistory::load(db).await {
+        Ok(history) => Arc::new(history),
+        Err(err) => {
+            error!("Failed to load file events from database: {}", err);
+            Arc::new(InMemoryFileHistory::from(Vec::new()))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::file_event::FileEvent;
     use super::super::file_event::FileEventType::ChangeEvent;
     use super::*;
+    use crate::db::test_db;
     use shared::utc_millis::UtcMillis;
     use uuid::Uuid;
 
@@ -266,4 +484,108 @@ mod tests {
         assert_eq!(1, history.get_latest_events(2).len());
         assert_eq!(0, history.get_latest_events(99).len());
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn should_read_history_from_db_and_cache() {
+        let db = test_db().await;
+        db.client().upsert_client("client-1", "arch").await.unwrap();
+        let history = SqliteFileHistory::new(db.clone(), NonZeroUsize::new(1).unwrap());
+        let path = MatchablePath::from(vec!["dir", "file.txt"]);
+        let event = |millis: u64, path: &MatchablePath| {
+            FileEvent::new(
+                Uuid::new_v4(),
+                UtcMillis::from(millis),
+                path.clone(),
+                1024,
+                ChangeEvent,
+                None,
+                WG,
+            )
+        };
+
+        let (e1, e2) = (event(100, &path), event(200, &path));
+        for e in [&e1, &e2] {
+            db.file_event().insert(e, "client-1").await.unwrap();
+        }
+        assert_eq!(
+            Some(e2.id),
+            history.get_latest_event(WG, &path).map(|e| e.id)
+        );
+        assert_eq!(
+            vec![e1.id, e2.id],
+            history
+                .get_events(WG, &path)
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>()
+        );
+
+        // not persisted - only known to the cache
+        let e3 = event(300, &path);
+        history.add(e3.clone());
+        assert_eq!(3, history.get_events(WG, &path).unwrap().len());
+        assert_eq!(
+            Some(e3.id),
+            history.get_latest_event(WG, &path).map(|e| e.id)
+        );
+
+        let other = MatchablePath::from(vec!["other.txt"]);
+        db.file_event()
+            .insert(&event(50, &other), "client-1")
+            .await
+            .unwrap();
+        let latest: Vec<Uuid> = history.get_latest_events(WG).iter().map(|e| e.id).collect();
+        assert_eq!(2, latest.len());
+        assert!(latest.contains(&e3.id));
+        assert_eq!(None, history.get_events(99, &path));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn should_load_latest_events_only() {
+        let db = test_db().await;
+        db.client().upsert_client("client-1", "arch").await.unwrap();
+        let path = MatchablePath::from(vec!["dir", "file.txt"]);
+        let other = MatchablePath::from(vec!["other.txt"]);
+        let event = |millis: u64, path: &MatchablePath| {
+            FileEvent::new(
+                Uuid::new_v4(),
+                UtcMillis::from(millis),
+                path.clone(),
+                1024,
+                ChangeEvent,
+                None,
+                WG,
+            )
+        };
+        // inserted out of order
+        let (e1, e2, e3) = (event(200, &path), event(100, &path), event(50, &other));
+        for e in [&e1, &e2, &e3] {
+            db.file_event().insert(e, "client-1").await.unwrap();
+        }
+
+        let history = InMemoryFileHistory::load(&db).await.unwrap();
+
+        assert_eq!(1, history.store.lock().unwrap()[&WG][&path].len());
+        assert_eq!(
+            Some(e1.id),
+            history.get_latest_event(WG, &path).map(|e| e.id)
+        );
+        assert_eq!(2, history.get_latest_events(WG).len());
+        // older events come from the DB
+        assert_eq!(
+            vec![e2.id, e1.id],
+            history
+                .get_events(WG, &path)
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>()
+        );
+
+        let e4 = event(300, &path);
+        history.add(e4.clone());
+        assert_eq!(1, history.store.lock().unwrap()[&WG][&path].len());
+        assert_eq!(3, history.get_events(WG, &path).unwrap().len());
+    }
 }
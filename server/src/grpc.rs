@@ -0,0 +1,299 @@
+use crate::client_file_event::ClientFileEventDto;
+use crate::handler::{
+    authorize_watch_group_access, client_id_for_log, delete_file, durability, ensure_not_paused,
+    header_value_as_opt_string, header_value_as_string, is_sync_paused, latest_change,
+    open_download, record_transfer, store_upload, sync_instructions, sync_paused_until,
+    verify_writes,
+};
+use crate::load::{Direction, TransferPermit};
+use crate::quiesce::WriteGuard;
+use crate::{AppState, UPLOAD_TMP_PATH, write};
+use axum::http::{HeaderMap, StatusCode};
+use futures_util::{Stream, StreamExt, stream};
+use sha2::{Digest, Sha256};
+use shared::dtos::{FileDescription, PendingKind};
+use shared::endpoint::{CLIENT_HOST_HEADER_KEY, CLIENT_ID_HEADER_KEY};
+use shared::grpc::sync_service_server::{SyncService, SyncServiceServer};
+use shared::grpc::{
+    DeleteReply, DeleteRequest, DownloadChunk, DownloadRequest, SyncInstructionMessage, SyncReply,
+    SyncRequest, UploadChunk, UploadReply, parse_path, status_from_http,
+};
+use shared::utc_millis::UtcMillis;
+use std::fs;
+use std::pin::Pin;
+use std::time::Instant;
+use tokio_util::io::ReaderStream;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{Instrument, info_span, warn};
+use uuid::Uuid;
+
+/// sync instructions of large watch groups easily exceed the default of 4 MiB
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// the sys endpoints (sync / upload / download / delete) as grpc service - same checks and
+/// same core logic as the http handlers, clients identify with the same headers (as metadata)
+pub struct GrpcSync {
+    state: AppState,
+}
+
+impl GrpcSync {
+    pub fn server(state: AppState) -> SyncServiceServer<GrpcSync> {
+        SyncServiceServer::new(GrpcSync { state })
+            .max_decoding_message_size(MAX_MESSAGE_BYTES)
+            .max_encoding_message_size(MAX_MESSAGE_BYTES)
+    }
+
+    async fn authorize_write(
+        &self,
+        headers: &HeaderMap,
+        wg_id: i64,
+    ) -> Result<WriteGuard, (StatusCode, String)> {
+        authorize_watch_group_access(&self.state, headers, wg_id).await?;
+        ensure_not_paused(&self.state, headers, wg_id).await?;
+        self.state.backup_window.try_write(wg_id)
+    }
+
+    /// same limits as the http routes - tonic has no Retry-After, clients retry on their next sync
+    fn acquire_transfer(
+        &self,
+        headers: &HeaderMap,
+        direction: Direction,
+    ) -> Result<TransferPermit, Status> {
+        let client_id = header_value_as_opt_string(headers, CLIENT_ID_HEADER_KEY);
+        self.state
+            .transfers
+            .try_acquire(direction, client_id.as_deref())
+            .map_err(Status::unavailable)
+    }
+}
+
+type DownloadStream = Pin<Box<dyn Stream<Item = Result<DownloadChunk, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl SyncService for GrpcSync {
+    async fn sync(&self, request: Request<SyncRequest>) -> Result<Response<SyncReply>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let request = request.into_inner();
+        let wg_id = request.watch_group_id;
+        let span = info_span!("sync", watch_group_id = wg_id, client_id = %client_id_for_log(&headers), transport = "grpc");
+        async move {
+            authorize_watch_group_access(&self.state, &headers, wg_id)
+                .await
+                .map_err(to_status)?;
+            if is_sync_paused(&self.state, &headers, wg_id)
+                .await
+                .map_err(to_status)?
+            {
+                return Ok(Response::new(SyncReply {
+                    paused: true,
+                    instructions: Vec::new(),
+                    paused_until: sync_paused_until(&self.state, &headers)
+                        .await
+                        .map(|until| until.as_u64()),
+                    download_bytes: None,
+                }));
+            }
+            let files = request
+                .files
+                .into_iter()
+                .map(FileDescription::try_from)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Status::invalid_argument)?;
+            let client_id = header_value_as_opt_string(&headers, CLIENT_ID_HEADER_KEY);
+            let (instructions, download_bytes) =
+                sync_instructions(&self.state, wg_id, client_id.as_deref(), files).await;
+            Ok(Response::new(SyncReply {
+                paused: false,
+                paused_until: None,
+                download_bytes: Some(download_bytes),
+                instructions: instructions
+                    .iter()
+                    .map(SyncInstructionMessage::from)
+                    .collect(),
+            }))
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn upload(
+        &self,
+        request: Request<Streaming<UploadChunk>>,
+    ) -> Result<Response<UploadReply>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let mut chunks = request.into_inner();
+        let first = chunks
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("Empty upload"))?;
+        let wg_id = first.watch_group_id;
+        let span = info_span!("upload", watch_group_id = wg_id, client_id = %client_id_for_log(&headers), transport = "grpc");
+        async move {
+            let _transfer = self.acquire_transfer(&headers, Direction::Upload)?;
+            let _write = self
+                .authorize_write(&headers, wg_id)
+                .await
+                .map_err(to_status)?;
+            self.state
+                .upload_disk
+                .ensure_room_for(first.size_in_bytes)
+                .map_err(to_status)?;
+            let verify_writes = verify_writes(&self.state, wg_id).await.map_err(to_status)?;
+            let durability = durability(&self.state, wg_id).await.map_err(to_status)?;
+            let relative_path =
+                parse_path(&first.relative_path).map_err(Status::invalid_argument)?;
+
+            let started = Instant::now();
+            let temp_path =
+                UPLOAD_TMP_PATH.join(format!("{}_{}", Uuid::new_v4(), relative_path.tail()));
+            let mut hasher = verify_writes.then(Sha256::new);
+            let data = stream::once(async { Ok::<_, Status>(first.data) })
+                .chain(chunks.map(|chunk| chunk.map(|c| c.data)))
+                .boxed();
+            let written = write::write_all_chunks_of_stream(
+                &temp_path,
+                data,
+                hasher.as_mut(),
+                Some(first.size_in_bytes),
+                durability,
+            )
+            .await
+            .map_err(|e| Status::internal(format!("Error writing upload - {e}")))
+            .and_then(|size| match size as u64 == first.size_in_bytes {
+                true => Ok(size),
+                // the client ends the stream early if it can't read the file anymore
+                false => Err(Status::invalid_argument(format!(
+                    "Upload incomplete - received {size} of {} bytes",
+                    first.size_in_bytes
+                ))),
+            });
+            let size = match written {
+                Ok(size) => size,
+                Err(status) => {
+                    if let Err(e) = fs::remove_file(&temp_path) {
+                        warn!("couldn't clean up tmp file - {e}");
+                    }
+                    return Err(status);
+                }
+            };
+
+            record_transfer(&self.state, &headers, wg_id, size as u64, 0).await;
+            let client_id = header_value_as_opt_string(&headers, CLIENT_ID_HEADER_KEY);
+            if let Some(client_id) = &client_id {
+                let elapsed = started.elapsed();
+                self.state
+                    .pending
+                    .observe_upload(client_id, size as u64, elapsed);
+            }
+
+            let dto = ClientFileEventDto {
+                utc_millis: Some(UtcMillis::from(first.utc_millis)),
+                relative_path: Some(relative_path.get().clone()),
+                temp_file_path: Some(temp_path),
+                content_size: Some(size),
+                watch_group_id: wg_id,
+                content_hash: hasher.map(|hasher| format!("{:x}", hasher.finalize())),
+                conflict_kept: Some(first.conflict_kept_utc_millis)
+                    .filter(|millis| *millis > 0)
+                    .map(UtcMillis::from),
+            };
+            let client_host = header_value_as_opt_string(&headers, CLIENT_HOST_HEADER_KEY);
+            store_upload(self.state.clone(), dto, client_host, client_id)
+                .await
+                .map(|message| Response::new(UploadReply { message }))
+                .map_err(to_status)
+        }
+        .instrument(span)
+        .await
+    }
+
+    type DownloadStream = DownloadStream;
+
+    async fn download(
+        &self,
+        request: Request<DownloadRequest>,
+    ) -> Result<Response<DownloadStream>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let request = request.into_inner();
+        let wg_id = request.watch_group_id;
+        let span = info_span!("download", watch_group_id = wg_id, client_id = %client_id_for_log(&headers), transport = "grpc");
+        async move {
+            let transfer = self.acquire_transfer(&headers, Direction::Download)?;
+            authorize_watch_group_access(&self.state, &headers, wg_id)
+                .await
+                .map_err(to_status)?;
+            let path = parse_path(&request.path).map_err(Status::invalid_argument)?;
+            let (file, _) = open_download(wg_id, &path).await.map_err(to_status)?;
+            let size_in_bytes = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+            record_transfer(&self.state, &headers, wg_id, 0, size_in_bytes).await;
+            if let Some(client_id) = header_value_as_opt_string(&headers, CLIENT_ID_HEADER_KEY) {
+                self.state
+                    .pending
+                    .complete(&client_id, wg_id, PendingKind::Download, &path);
+            }
+
+            let latest = latest_change(&self.state, wg_id, &path);
+            let header = DownloadChunk {
+                size_in_bytes,
+                data: Vec::new(),
+                utc_millis: latest.as_ref().map(|e| e.utc_millis.as_u64()).unwrap_or(0),
+                client_host: latest.and_then(|e| e.client_host).unwrap_or_default(),
+            };
+            // the permit lives as long as the stream
+            let chunks = ReaderStream::with_capacity(file, *write::TRANSFER_BUFFER_BYTES).map(
+                move |bytes| {
+                    let _transfer = &transfer;
+                    bytes
+                        .map(|bytes| DownloadChunk {
+                            size_in_bytes: 0,
+                            data: bytes.to_vec(),
+                            utc_millis: 0,
+                            client_host: String::new(),
+                        })
+                        .map_err(|e| Status::internal(format!("Reading file failed - {e}")))
+                },
+            );
+            let stream: DownloadStream = Box::pin(stream::once(async { Ok(header) }).chain(chunks));
+            Ok(Response::new(stream))
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteRequest>,
+    ) -> Result<Response<DeleteReply>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let request = request.into_inner();
+        let wg_id = request.watch_group_id;
+        let span = info_span!("delete", watch_group_id = wg_id, client_id = %client_id_for_log(&headers), transport = "grpc");
+        async move {
+            let _write = self
+                .authorize_write(&headers, wg_id)
+                .await
+                .map_err(to_status)?;
+            let client_id = header_value_as_string(&headers, CLIENT_ID_HEADER_KEY)
+                .map_err(to_status)?
+                .to_string();
+            parse_path(&request.path).map_err(Status::invalid_argument)?;
+            let client_host = header_value_as_opt_string(&headers, CLIENT_HOST_HEADER_KEY);
+            match delete_file(&self.state, wg_id, &request.path, client_host, client_id).await {
+                Ok(()) => Ok(Response::new(DeleteReply {
+                    message: format!("Deleted {}", request.path),
+                })),
+                // nothing to delete
+                Err((status, message)) if status.is_success() => {
+                    Ok(Response::new(DeleteReply { message }))
+                }
+                Err(e) => Err(to_status(e)),
+            }
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+fn to_status((status, message): (StatusCode, String)) -> Status {
+    status_from_http(status.as_u16(), message)
+}
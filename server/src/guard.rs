@@ -0,0 +1,272 @@
+use crate::file_event::FileEvent;
+use crate::{AppState, handler, webhook};
+use shared::dtos::{MassChangeAlertDto, MassChangeGuardDto};
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::sync::Mutex;
+use tracing::{error, warn};
+
+/// alerts kept for GET /api/admin/mass-changes
+const MAX_ALERTS: usize = 100;
+/// how long the number of files of a watch group is reused - counting reads its whole history
+const FILE_COUNT_TTL_MILLIS: u64 = 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub percent: u32,
+    pub window_minutes: u32,
+    pub min_files: u32,
+}
+
+impl Thresholds {
+    /// the configuration of a watch group on top of the server defaults
+    pub fn apply(self, configured: &MassChangeGuardDto) -> Self {
+        Thresholds {
+            percent: configured.percent.unwrap_or(self.percent),
+            window_minutes: configured.window_minutes.unwrap_or(self.window_minutes),
+            min_files: configured.min_files.unwrap_or(self.min_files),
+        }
+    }
+
+    fn window_millis(&self) -> u64 {
+        self.window_minutes as u64 * 60 * 1000
+    }
+
+    fn exceeded(&self, touched: u64, files_in_watch_group: u64) -> bool {
+        self.percent > 0
+            && touched >= self.min_files as u64
+            && touched * 100 >= self.percent as u64 * files_in_watch_group.max(1)
+    }
+}
+
+struct Touch {
+    at: u64,
+    path: MatchablePath,
+    deleted: bool,
+}
+
+/// Flags clients which change or delete a large share of a watch group within a few minutes
+/// (e.g. ransomware encrypting the synced dir) and pauses them, before the damage is synced to
+/// every other client. Defaults: `MASS_CHANGE_PERCENT` (30, 0 turns the guard off),
+/// `MASS_CHANGE_WINDOW_MINUTES` (10) and `MASS_CHANGE_MIN_FILES` (50) - per watch group via
+/// PUT /api/watch-groups/{id}/mass-change-guard.
+pub struct MassChangeGuard {
+    defaults: Thresholds,
+    /// recent changes per client and watch group
+    windows: Mutex<HashMap<(String, i64), VecDeque<Touch>>>,
+    /// watch group -> (counted at, files)
+    file_counts: Mutex<HashMap<i64, (u64, u64)>>,
+    alerts: Mutex<VecDeque<MassChangeAlertDto>>,
+}
+
+impl MassChangeGuard {
+    pub fn new(defaults: Thresholds) -> Self {
+        Self {
+            defaults,
+            windows: Mutex::default(),
+            file_counts: Mutex::default(),
+            alerts: Mutex::default(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(Thresholds {
+            percent: from_env("MASS_CHANGE_PERCENT", 30),
+            window_minutes: from_env("MASS_CHANGE_WINDOW_MINUTES", 10),
+            min_files: from_env("MASS_CHANGE_MIN_FILES", 50),
+        })
+    }
+
+    pub fn defaults(&self) -> Thresholds {
+        self.defaults
+    }
+
+    /// newest first
+    pub fn alerts(&self) -> Vec<MassChangeAlertDto> {
+        self.alerts.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// adds the change to the window of the client - returns the distinct files changed and
+    /// deleted within it (a file counts by its last change)
+    fn record(&self, key: &(String, i64), touch: Touch, window_millis: u64) -> (u64, u64) {
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(key.clone()).or_default();
+        let since = touch.at.saturating_sub(window_millis);
+        while window.front().is_some_and(|t| t.at < since) {
+            window.pop_front();
+        }
+        window.push_back(touch);
+
+        let mut seen = HashSet::new();
+        let (mut changed, mut deleted) = (0, 0);
+        for touch in window.iter().rev() {
+            if seen.insert(&touch.path) {
+                if touch.deleted {
+                    deleted += 1;
+                } else {
+                    changed += 1;
+                }
+            }
+        }
+        (changed, deleted)
+    }
+
+    /// forgets the window once a burst was flagged - false if a concurrent check did already
+    fn take(&self, key: &(String, i64)) -> bool {
+        self.windows.lock().unwrap().remove(key).is_some()
+    }
+
+    fn file_count(&self, state: &AppState, wg_id: i64, now: u64) -> u64 {
+        if let Some((at, count)) = self.file_counts.lock().unwrap().get(&wg_id)
+            && now.saturating_sub(*at) < FILE_COUNT_TTL_MILLIS
+        {
+            return *count;
+        }
+        let count = state
+            .history
+            .get_latest_events(wg_id)
+            .iter()
+            .filter(|e| e.event_type.is_change())
+            .count() as u64;
+        self.file_counts.lock().unwrap().insert(wg_id, (now, count));
+        count
+    }
+
+    fn remember(&self, alert: MassChangeAlertDto) {
+        let mut alerts = self.alerts.lock().unwrap();
+        alerts.push_front(alert);
+        alerts.truncate(MAX_ALERTS);
+    }
+}
+
+fn from_env(key: &str, default: u32) -> u32 {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// checks the (already recorded) change of a client in the background - never blocks the caller
+pub fn observe(state: &AppState, event: &FileEvent, client_id: &str) {
+    if handler::BUILT_IN_CLIENT_IDS.contains(&client_id) {
+        return;
+    }
+    let key = (client_id.to_string(), event.watch_group_id);
+    let touch = Touch {
+        at: UtcMillis::now().as_u64(),
+        path: event.relative_path.clone(),
+        deleted: event.event_type.is_delete(),
+    };
+    let client_host = event.client_host.clone();
+    let state = state.clone();
+    tokio::spawn(async move { check(&state, key, touch, client_host).await });
+}
+
+async fn check(state: &AppState, key: (String, i64), touch: Touch, client_host: Option<String>) {
+    let (client_id, wg_id) = (&key.0, key.1);
+    let configured = match state.db.server_watch_group().mass_change_guard(wg_id).await {
+        Ok(Some(configured)) => configured,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to load the mass change guard of watch group {wg_id}: {e}");
+            return;
+        }
+    };
+    let guard = &state.mass_changes;
+    let thresholds = guard.defaults.apply(&configured);
+    if thresholds.percent == 0 {
+        return;
+    }
+    let now = touch.at;
+    let (changed, deleted) = guard.record(&key, touch, thresholds.window_millis());
+    if changed + deleted < thresholds.min_files as u64 {
+        return;
+    }
+    // the deleted files were part of the watch group when the burst started
+    let files_in_watch_group = guard.file_count(state, wg_id, now) + deleted;
+    if !thresholds.exceeded(changed + deleted, files_in_watch_group) || !guard.take(&key) {
+        return;
+    }
+
+    let paused = match state.db.client().set_paused(client_id, true).await {
+        Ok(paused) => paused,
+        Err(e) => {
+            error!("Failed to pause client {client_id}: {e}");
+            false
+        }
+    };
+    warn!(
+        client_id = %client_id,
+        watch_group_id = wg_id,
+        "Mass change by client {} in watch group {}: {} changed and {} deleted of {} files within {} minutes - {}",
+        client_id,
+        wg_id,
+        changed,
+        deleted,
+        files_in_watch_group,
+        thresholds.window_minutes,
+        if paused { "paused it" } else { "could not pause it" }
+    );
+    let alert = MassChangeAlertDto {
+        client_id: client_id.clone(),
+        client_host,
+        watch_group_id: wg_id,
+        changed,
+        deleted,
+        files_in_watch_group,
+        percent: thresholds.percent,
+        window_minutes: thresholds.window_minutes,
+        detected_at: UtcMillis::from(now),
+        paused,
+    };
+    webhook::alert(state, &alert);
+    guard.remember(alert);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_flag_share_of_distinct_files_within_window() {
+        let thresholds = Thresholds {
+            percent: 50,
+            window_minutes: 1,
+            min_files: 3,
+        };
+        let guard = MassChangeGuard::new(thresholds);
+        let key = ("c1".to_string(), 1);
+        let touch = |at: u64, path: &str, deleted: bool| Touch {
+            at,
+            path: MatchablePath::from(path),
+            deleted,
+        };
+
+        assert_eq!((1, 0), guard.record(&key, touch(0, "a", false), 60_000));
+        // the same file again - still one
+        assert_eq!((1, 0), guard.record(&key, touch(1_000, "a", false), 60_000));
+        assert_eq!((1, 1), guard.record(&key, touch(2_000, "b", true), 60_000));
+        // "a" fell out of the window, its last change counts
+        assert_eq!((0, 2), guard.record(&key, touch(61_500, "a", true), 60_000));
+        assert_eq!(
+            (1, 2),
+            guard.record(&key, touch(62_000, "c", false), 60_000)
+        );
+
+        assert!(!thresholds.exceeded(2, 4));
+        assert!(thresholds.exceeded(3, 6));
+        assert!(!thresholds.exceeded(3, 7));
+        assert!(
+            !Thresholds {
+                percent: 0,
+                ..thresholds
+            }
+            .exceeded(3, 3)
+        );
+
+        assert!(guard.take(&key));
+        assert!(!guard.take(&key));
+    }
+}
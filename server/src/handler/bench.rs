@@ -0,0 +1,89 @@
+use axum::Json;
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use bytes::Bytes;
+use futures_util::{StreamExt, stream};
+use shared::dtos::{BenchDownloadQueryDto, BenchUploadDto};
+use std::sync::LazyLock;
+use std::time::Instant;
+use tracing::{info, instrument};
+
+use super::{authorize_client, client_id_for_log};
+use crate::AppState;
+
+/// upper bound of a single bench transfer (either direction)
+const MAX_BENCH_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+/// served over and over by [`api_bench_download`] - noise, so compressing proxies can't cheat
+static PATTERN: LazyLock<Bytes> = LazyLock::new(|| {
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    (0..256 * 1024)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as u8
+        })
+        .collect::<Vec<u8>>()
+        .into()
+});
+
+fn check_size(bytes: u64) -> Result<(), (StatusCode, String)> {
+    if bytes > MAX_BENCH_BYTES {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Bench is limited to {MAX_BENCH_BYTES} bytes"),
+        ));
+    }
+    Ok(())
+}
+
+/// POST /api/bench/upload - reads the whole body and throws it away
+#[instrument(name = "bench_upload", skip_all, fields(client_id = %client_id_for_log(&headers)))]
+pub async fn api_bench_upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<BenchUploadDto>, (StatusCode, String)> {
+    authorize_client(&state, &headers).await?;
+    let start = Instant::now();
+    let mut received_bytes = 0;
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| (StatusCode::BAD_REQUEST, format!("Bench aborted - {e}")))?;
+        received_bytes += chunk.len() as u64;
+        check_size(received_bytes)?;
+    }
+    let duration_millis = start.elapsed().as_millis() as u64;
+    info!("Bench received {received_bytes} bytes in {duration_millis}ms");
+    Ok(Json(BenchUploadDto {
+        received_bytes,
+        duration_millis,
+    }))
+}
+
+/// GET /api/bench/download?bytes=... - synthetic data, nothing is read from disk
+#[instrument(name = "bench_download", skip_all, fields(client_id = %client_id_for_log(&headers)))]
+pub async fn api_bench_download(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BenchDownloadQueryDto>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize_client(&state, &headers).await?;
+    check_size(query.bytes)?;
+    info!("Bench sending {} bytes", query.bytes);
+
+    let chunk_size = PATTERN.len() as u64;
+    let chunks = stream::iter((0..query.bytes.div_ceil(chunk_size)).map(move |i| {
+        let len = chunk_size.min(query.bytes - i * chunk_size) as usize;
+        Ok::<_, std::io::Error>(PATTERN.slice(..len))
+    }));
+    let headers = [
+        (CONTENT_TYPE, "application/octet-stream".to_string()),
+        (CONTENT_LENGTH, query.bytes.to_string()),
+    ];
+    Ok((headers, Body::from_stream(chunks)))
+}
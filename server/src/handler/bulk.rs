@@ -0,0 +1,298 @@
+use crate::file_event::{FileEvent, FileEventType};
+use crate::{AppState, UPLOAD_PATH, publish, versions, webhook};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use shared::dtos::{BulkPathOperationDto, BulkPathOperationResultDto};
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Component, Path};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::search::unindex_file;
+use super::server_watch_group::WEB_CLIENT_ID;
+
+/// time to confirm a bulk operation
+const CONFIRMATION_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BulkOperation {
+    Prune,
+    Resync,
+}
+
+/// what a confirmation token was issued for
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PendingOperation {
+    operation: BulkOperation,
+    wg_id: i64,
+    path_prefix: MatchablePath,
+}
+
+/// single-use tokens for bulk operations - the caller has to see what is affected first
+#[derive(Default)]
+pub struct ConfirmationTokens {
+    pending: Mutex<HashMap<String, (Instant, PendingOperation)>>,
+}
+
+impl ConfirmationTokens {
+    fn issue(&self, operation: PendingOperation) -> String {
+        let token = Uuid::new_v4().simple().to_string();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, (issued_at, _)| issued_at.elapsed() < CONFIRMATION_TTL);
+        pending.insert(token.clone(), (Instant::now(), operation));
+        token
+    }
+
+    /// consumes the token - even if it was issued for another operation
+    fn redeem(&self, token: &str, operation: &PendingOperation) -> bool {
+        self.pending
+            .lock()
+            .unwrap()
+            .remove(token)
+            .is_some_and(|(issued_at, pending)| {
+                issued_at.elapsed() < CONFIRMATION_TTL && &pending == operation
+            })
+    }
+}
+
+/// POST /api/admin/prune - deletes all files below the prefix and emits delete events
+pub async fn api_prune(
+    State(state): State<AppState>,
+    Json(dto): Json<BulkPathOperationDto>,
+) -> Result<Json<BulkPathOperationResultDto>, (StatusCode, String)> {
+    bulk_operation(state, dto, BulkOperation::Prune).await
+}
+
+/// POST /api/admin/resync - emits fresh change events so clients download the files again
+pub async fn api_resync(
+    State(state): State<AppState>,
+    Json(dto): Json<BulkPathOperationDto>,
+) -> Result<Json<BulkPathOperationResultDto>, (StatusCode, String)> {
+    bulk_operation(state, dto, BulkOperation::Resync).await
+}
+
+fn parse_path_prefix(path_prefix: &str) -> Result<MatchablePath, (StatusCode, String)> {
+    // MatchablePath panics without any normal component
+    let path = Path::new(path_prefix);
+    if !path.components().any(|c| matches!(c, Component::Normal(_))) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "path_prefix must not be empty".to_string(),
+        ));
+    }
+    Ok(MatchablePath::from(path))
+}
+
+async fn bulk_operation(
+    state: AppState,
+    dto: BulkPathOperationDto,
+    operation: BulkOperation,
+) -> Result<Json<BulkPathOperationResultDto>, (StatusCode, String)> {
+    let path_prefix = parse_path_prefix(&dto.path_prefix)?;
+    let wg_id = dto.watch_group_id;
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group existence: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Watch group {wg_id} not found"),
+        ));
+    }
+
+    let affected = affected_files(state.history.get_latest_events(wg_id), &path_prefix);
+    let affected_paths = affected
+        .iter()
+        .map(|e| e.relative_path.to_serialized_string())
+        .collect();
+    let pending = PendingOperation {
+        operation,
+        wg_id,
+        path_prefix,
+    };
+
+    let Some(token) = dto.confirmation_token else {
+        return Ok(Json(BulkPathOperationResultDto {
+            affected_paths,
+            confirmation_token: Some(state.confirmations.issue(pending)),
+            executed: false,
+        }));
+    };
+    // before the token is redeemed - it's still good for a retry after the backup
+    let _write = state.backup_window.try_write(wg_id)?;
+    if !state.confirmations.redeem(&token, &pending) {
+        warn!("Rejected {:?} with invalid confirmation token", operation);
+        return Err((
+            StatusCode::CONFLICT,
+            "Confirmation token is invalid, expired or for another operation".to_string(),
+        ));
+    }
+
+    for event in affected {
+        match operation {
+            BulkOperation::Prune => prune(&state, event).await?,
+            BulkOperation::Resync => resync(&state, event).await,
+        }
+    }
+    info!(
+        watch_group_id = wg_id,
+        "{:?} of '{}' affected {} files",
+        operation,
+        dto.path_prefix,
+        affected_paths.len()
+    );
+    Ok(Json(BulkPathOperationResultDto {
+        affected_paths,
+        confirmation_token: None,
+        executed: true,
+    }))
+}
+
+/// latest events of existing files below the prefix
+pub(super) fn affected_files(
+    latest_events: Vec<FileEvent>,
+    path_prefix: &MatchablePath,
+) -> Vec<FileEvent> {
+    let mut affected: Vec<FileEvent> = latest_events
+        .into_iter()
+        .filter(|e| e.event_type.is_change())
+        .filter(|e| e.relative_path.get().starts_with(path_prefix.get()))
+        .collect();
+    affected.sort_by_key(|e| e.relative_path.to_serialized_string());
+    affected
+}
+
+pub(super) async fn prune(
+    state: &AppState,
+    current: FileEvent,
+) -> Result<(), (StatusCode, String)> {
+    let wg_id = current.watch_group_id;
+    let file = current
+        .relative_path
+        .resolve(&UPLOAD_PATH.join(wg_id.to_string()));
+    versions::keep_current(state.history.as_ref(), wg_id, &current.relative_path, &file);
+    match tokio::fs::remove_file(&file).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => {
+            error!("Failed to prune {:?}: {}", file, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    }
+    unindex_file(state, wg_id, &current.relative_path).await;
+
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        UtcMillis::now(),
+        current.relative_path,
+        0,
+        FileEventType::DeleteEvent,
+        Some("web".to_string()),
+        wg_id,
+    );
+    persist(state, event).await;
+    Ok(())
+}
+
+/// same size but newer than any client copy - see [`super::sync_handler`]
+async fn resync(state: &AppState, current: FileEvent) {
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        UtcMillis::now(),
+        current.relative_path,
+        current.size_in_bytes,
+        FileEventType::ChangeEvent,
+        Some("web".to_string()),
+        current.watch_group_id,
+    );
+    persist(state, event).await;
+}
+
+pub(super) async fn persist(state: &AppState, event: FileEvent) {
+    if let Err(e) = state.events.record(&event, Some(WEB_CLIENT_ID)).await {
+        error!("Failed to persist bulk operation event to DB: {e}");
+    }
+    webhook::notify(state, &event, Some(WEB_CLIENT_ID));
+    publish::mirror(state, &event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(path: &str, event_type: FileEventType) -> FileEvent {
+        FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(1),
+            MatchablePath::from(path),
+            1,
+            event_type,
+            None,
+            1,
+        )
+    }
+
+    #[test]
+    fn should_match_whole_path_segments_of_existing_files() {
+        let latest_events = vec![
+            event("docs/b.txt", FileEventType::ChangeEvent),
+            event("docs/a.txt", FileEventType::ChangeEvent),
+            event("docs/deleted.txt", FileEventType::DeleteEvent),
+            event("docs2/c.txt", FileEventType::ChangeEvent),
+        ];
+
+        let affected: Vec<String> = affected_files(latest_events, &MatchablePath::from("docs"))
+            .iter()
+            .map(|e| e.relative_path.to_serialized_string())
+            .collect();
+
+        assert_eq!(vec!["docs/a.txt", "docs/b.txt"], affected);
+    }
+
+    #[test]
+    fn should_reject_path_prefix_without_segments() {
+        for path_prefix in ["", "/", ".", "..", "./.."] {
+            assert_eq!(
+                StatusCode::BAD_REQUEST,
+                parse_path_prefix(path_prefix).unwrap_err().0,
+                "{path_prefix}"
+            );
+        }
+        assert_eq!(
+            MatchablePath::from("docs"),
+            parse_path_prefix("docs/").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_only_redeem_matching_token_once() {
+        let tokens = ConfirmationTokens::default();
+        let prune = PendingOperation {
+            operation: BulkOperation::Prune,
+            wg_id: 1,
+            path_prefix: MatchablePath::from("docs"),
+        };
+        let resync = PendingOperation {
+            operation: BulkOperation::Resync,
+            ..prune.clone()
+        };
+
+        let token = tokens.issue(prune.clone());
+        assert!(!tokens.redeem(&token, &resync));
+        let token = tokens.issue(prune.clone());
+        assert!(tokens.redeem(&token, &prune));
+        assert!(!tokens.redeem(&token, &prune));
+    }
+}
@@ -1,10 +1,32 @@
 use crate::AppState;
+use crate::db::{DELETED_CLIENT_ID, REPLICATION_CLIENT_ID};
 use axum::Json;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::StatusCode;
-use shared::dtos::{ClientDto, ClientUpdateDto};
+use shared::dtos::{
+    ClientClaimDto, ClientClaimedDto, ClientDto, ClientUpdateDto, OwnerDto, PauseDto,
+};
+use std::collections::HashMap;
 use tracing::{error, info};
 
+use super::dav::WEBDAV_CLIENT_ID;
+use super::import::IMPORT_CLIENT_ID;
+use super::scrub::SCRUB_CLIENT_ID;
+use super::server_watch_group::{PWA_CLIENT_ID, WEB_CLIENT_ID};
+use super::share_link::INBOX_CLIENT_ID;
+
+/// clients of the server itself - they can't be deleted or claimed
+pub(crate) const BUILT_IN_CLIENT_IDS: [&str; 8] = [
+    PWA_CLIENT_ID,
+    WEB_CLIENT_ID,
+    SCRUB_CLIENT_ID,
+    WEBDAV_CLIENT_ID,
+    INBOX_CLIENT_ID,
+    DELETED_CLIENT_ID,
+    REPLICATION_CLIENT_ID,
+    IMPORT_CLIENT_ID,
+];
+
 /// GET /api/clients
 pub async fn api_list_clients(
     State(state): State<AppState>,
@@ -58,20 +80,134 @@ pub async fn api_update_client(
     }
 }
 
-/// DELETE /api/clients/{id}
+/// PUT /api/clients/{id}/owner
+pub async fn api_set_client_owner(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(dto): Json<OwnerDto>,
+) -> Result<String, (StatusCode, String)> {
+    super::user::ensure_user_exists(&state, dto.owner_id).await?;
+    let found = state
+        .db
+        .client()
+        .set_owner(&id, dto.owner_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to set owner of client: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Set owner of client {} to {:?}", id, dto.owner_id);
+        Ok("Client owner updated".to_string())
+    } else {
+        Err((StatusCode::NOT_FOUND, "Client not found".to_string()))
+    }
+}
+
+/// PUT /api/clients/{id}/pause
+pub async fn api_pause_client(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(dto): Json<PauseDto>,
+) -> Result<String, (StatusCode, String)> {
+    let found = state
+        .db
+        .client()
+        .set_paused(&id, dto.paused)
+        .await
+        .map_err(|e| {
+            error!("Failed to pause client: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Set paused of client {} to {}", id, dto.paused);
+        Ok(if dto.paused {
+            "Client paused"
+        } else {
+            "Client resumed"
+        }
+        .to_string())
+    } else {
+        Err((StatusCode::NOT_FOUND, "Client not found".to_string()))
+    }
+}
+
+/// DELETE /api/clients/{id}?anonymize=true - deregisters the client (see `ClientRepository::deregister`)
 pub async fn api_delete_client(
     State(state): State<AppState>,
     axum::extract::Path(id): axum::extract::Path<String>,
+    Query(params): Query<HashMap<String, String>>,
 ) -> Result<StatusCode, (StatusCode, String)> {
-    let found = state.db.client().delete(&id).await.map_err(|e| {
-        error!("Failed to delete client: {}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-    })?;
+    if BUILT_IN_CLIENT_IDS.contains(&id.as_str()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Built-in clients can't be deleted".to_string(),
+        ));
+    }
+    let anonymize = params.get("anonymize").is_some_and(|v| v == "true");
+    let found = state
+        .db
+        .client()
+        .deregister(&id, anonymize)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete client: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
 
     if found {
-        info!("Deleted client {}", id);
+        info!(anonymize, "Deregistered client {}", id);
+        state.pending.forget(&id);
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err((StatusCode::NOT_FOUND, "Client not found".to_string()))
     }
 }
+
+/// POST /api/clients/{id}/claim - the client takes over another one, e.g. after the OS of its
+/// machine was reinstalled (see `ClientRepository::claim`)
+pub async fn api_claim_client(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(dto): Json<ClientClaimDto>,
+) -> Result<Json<ClientClaimedDto>, (StatusCode, String)> {
+    let old_id = dto.old_client_id;
+    if id == old_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A client can't claim itself".to_string(),
+        ));
+    }
+    if [&id, &old_id]
+        .iter()
+        .any(|c| BUILT_IN_CLIENT_IDS.contains(&c.as_str()))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Built-in clients can't be claimed".to_string(),
+        ));
+    }
+    let claimed = state
+        .db
+        .client()
+        .claim(&id, &old_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to claim client: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Client not found".to_string()))?;
+
+    info!(
+        watch_groups = claimed.watch_groups,
+        file_events = claimed.file_events,
+        "Client {} claimed client {}",
+        id,
+        old_id
+    );
+    state.history.reattribute(&old_id, &id);
+    state.pending.forget(&old_id);
+    Ok(Json(claimed))
+}
@@ -0,0 +1,160 @@
+use crate::AppState;
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use shared::dtos::{
+    ClientActivityQueryDto, ClientDetailDto, ClientEventDto, ClientRecentActivityDto,
+    ClientWatchGroupDetailDto,
+};
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use tracing::{error, info};
+
+use super::current_month;
+
+/// default range of the bandwidth of GET /api/clients/{id}/activity
+const ACTIVITY_HOURS: u32 = 7 * 24;
+/// default number of events of GET /api/clients/{id}/activity
+const ACTIVITY_EVENTS: u32 = 50;
+/// more events aren't readable on one page anyway
+const MAX_ACTIVITY_EVENTS: u32 = 500;
+
+/// GET /api/clients/{id}/detail
+pub async fn api_get_client_detail(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ClientDetailDto>, (StatusCode, String)> {
+    let db_error = |e: sqlx::Error| {
+        error!("Failed to load details of client {}: {}", id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    let client = state
+        .db
+        .client()
+        .get_client_by_id(&id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Client not found".to_string()))?;
+    let assignments = state
+        .db
+        .client_watch_group()
+        .list_for_client(&id)
+        .await
+        .map_err(db_error)?;
+    let synced_at: HashMap<i64, UtcMillis> = state
+        .db
+        .client_file_state()
+        .get_report_times(&id)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .collect();
+
+    let bandwidth_usage = state
+        .db
+        .stats()
+        .get_bandwidth_usage(&id, &current_month())
+        .await
+        .map_err(db_error)?;
+
+    let last_seen = synced_at.values().max().cloned();
+    let watch_groups = assignments
+        .into_iter()
+        .map(|assignment| ClientWatchGroupDetailDto {
+            last_synced_at: synced_at.get(&assignment.server_watch_group_id).cloned(),
+            assignment,
+        })
+        .collect();
+    Ok(Json(ClientDetailDto {
+        client,
+        last_seen,
+        watch_groups,
+        bandwidth_usage,
+    }))
+}
+
+/// GET /api/clients/{id}/activity?hours=..&limit=..
+pub async fn api_get_client_activity(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ClientActivityQueryDto>,
+) -> Result<Json<ClientRecentActivityDto>, (StatusCode, String)> {
+    let db_error = |e: sqlx::Error| {
+        error!("Failed to load activity of client {}: {}", id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    let limit = query
+        .limit
+        .unwrap_or(ACTIVITY_EVENTS)
+        .min(MAX_ACTIVITY_EVENTS);
+    let recent_events = state
+        .db
+        .file_event()
+        .get_recent_events_of_client(&id, limit)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|event| ClientEventDto {
+            watch_group_id: event.watch_group_id,
+            relative_path: event.relative_path.to_serialized_string(),
+            utc_millis: event.utc_millis,
+            size_in_bytes: event.size_in_bytes,
+            deleted: event.event_type.is_delete(),
+        })
+        .collect();
+
+    let hours = query.hours.unwrap_or(ACTIVITY_HOURS) as u64;
+    let since = UtcMillis::from(
+        UtcMillis::now()
+            .as_u64()
+            .saturating_sub(hours * 60 * 60 * 1000),
+    );
+    let bandwidth: Vec<_> = state
+        .db
+        .stats()
+        .get_bandwidth(since, 24)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .filter(|bucket| bucket.client_id == id)
+        .collect();
+    Ok(Json(ClientRecentActivityDto {
+        uploaded_bytes: bandwidth.iter().map(|b| b.uploaded_bytes).sum(),
+        downloaded_bytes: bandwidth.iter().map(|b| b.downloaded_bytes).sum(),
+        recent_events,
+        bandwidth,
+    }))
+}
+
+/// POST /api/clients/{id}/resync - forgets the file lists and pending work of the client, its
+/// next syncs report everything afresh
+pub async fn api_resync_client(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<String, (StatusCode, String)> {
+    let exists = state
+        .db
+        .client()
+        .get_client_by_id(&id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get client: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .is_some();
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Client not found".to_string()));
+    }
+    let dropped = state
+        .db
+        .client_file_state()
+        .delete_of_client(&id)
+        .await
+        .map_err(|e| {
+            error!("Failed to drop file lists of client {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    state.pending.forget(&id);
+    info!("Reset sync state of client {} ({} file lists)", id, dropped);
+    Ok("Client resync requested".to_string())
+}
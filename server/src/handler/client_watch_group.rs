@@ -2,7 +2,9 @@ use crate::AppState;
 use axum::Json;
 use axum::extract::State;
 use axum::http::StatusCode;
-use shared::dtos::{ClientWatchGroupCreateDto, ClientWatchGroupDto, ClientWatchGroupUpdateDto};
+use shared::dtos::{
+    ClientWatchGroupCreateDto, ClientWatchGroupDto, ClientWatchGroupUpdateDto, PauseDto,
+};
 use tracing::{error, info};
 
 /// GET /api/clients/{id}/watch-groups
@@ -28,6 +30,7 @@ pub async fn api_create_client_watch_group(
     axum::extract::Path(id): axum::extract::Path<String>,
     Json(dto): Json<ClientWatchGroupCreateDto>,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    ensure_same_owner(&state, &id, dto.server_watch_group_id).await?;
     state
         .db
         .client_watch_group()
@@ -112,3 +115,72 @@ pub async fn api_delete_client_watch_group(
         Err((StatusCode::NOT_FOUND, "Assignment not found".to_string()))
     }
 }
+
+/// PUT /api/clients/{id}/watch-groups/{wg_id}/pause
+pub async fn api_pause_client_watch_group(
+    State(state): State<AppState>,
+    axum::extract::Path((id, wg_id)): axum::extract::Path<(String, i64)>,
+    Json(dto): Json<PauseDto>,
+) -> Result<String, (StatusCode, String)> {
+    let found = state
+        .db
+        .client_watch_group()
+        .set_paused(&id, wg_id, dto.paused)
+        .await
+        .map_err(|e| {
+            error!(
+                "Failed to pause watch group {} for client {}: {}",
+                wg_id, id, e
+            );
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!(
+            "Set paused of watch group {} for client {} to {}",
+            wg_id, id, dto.paused
+        );
+        Ok(if dto.paused {
+            "Watch group paused"
+        } else {
+            "Watch group resumed"
+        }
+        .to_string())
+    } else {
+        Err((StatusCode::NOT_FOUND, "Assignment not found".to_string()))
+    }
+}
+
+/// a client may only be assigned to shared watch groups or those of its own user
+async fn ensure_same_owner(
+    state: &AppState,
+    client_id: &str,
+    wg_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    let to_internal = |e: sqlx::Error| {
+        error!("Failed to check ownership of watch group {}: {}", wg_id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    let client = state
+        .db
+        .client()
+        .get_client_by_id(client_id)
+        .await
+        .map_err(to_internal)?
+        .ok_or((StatusCode::NOT_FOUND, "Client not found".to_string()))?;
+    let wg_owner = state
+        .db
+        .server_watch_group()
+        .get_owner(wg_id)
+        .await
+        .map_err(to_internal)?
+        .ok_or((StatusCode::NOT_FOUND, "Watch group not found".to_string()))?;
+
+    match wg_owner {
+        Some(owner) if client.owner_id != Some(owner) => Err((
+            StatusCode::FORBIDDEN,
+            "Watch group belongs to another user".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
@@ -4,7 +4,7 @@ use axum::extract::State;
 use axum::http::{HeaderMap, StatusCode};
 use shared::dtos::WatchConfigDto;
 use shared::endpoint::{CLIENT_HOST_HEADER_KEY, CLIENT_ID_HEADER_KEY};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use super::header_value_as_string;
 
@@ -19,21 +19,25 @@ pub async fn get_config(
     match state.db.client().get_client_by_id(client_id).await {
         Ok(Some(client)) => {
             debug!("Returning config for client {}", client_id);
-            let watch_groups = state
+            watch_config(&state, client_id, client.min_poll_interval_in_ms).await
+        }
+        Ok(None) => {
+            let deregistered = state
                 .db
-                .client_watch_group()
-                .get_for_client(client_id)
+                .client()
+                .is_deregistered(client_id)
                 .await
                 .map_err(|e| {
-                    error!("Failed to get watch groups for client: {}", e);
+                    error!("Failed to check client registration: {}", e);
                     (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                 })?;
-            Ok(Json(WatchConfigDto {
-                min_poll_interval_in_ms: client.min_poll_interval_in_ms,
-                watch_groups,
-            }))
-        }
-        Ok(None) => {
+            if deregistered {
+                warn!("Refused config for deregistered client {}", client_id);
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    "Client was deregistered - use a new client_id to register again".to_string(),
+                ));
+            }
             info!("No config found for client {} - adding one...", client_id);
             state
                 .db
@@ -44,8 +48,29 @@ pub async fn get_config(
                     error!("Failed to register client: {}", e);
                     (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
                 })?;
-            info!("Registered client {} ({})", client_id, host_name);
-            Ok(Json(WatchConfigDto::default()))
+            let applied = state
+                .db
+                .registration_template()
+                .apply(client_id, host_name)
+                .await
+                .map_err(|e| {
+                    error!("Failed to apply registration templates: {}", e);
+                    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+                })?;
+            info!(
+                "Registered client {} ({}) - {} templates applied",
+                client_id, host_name, applied
+            );
+            match state.db.client().get_client_by_id(client_id).await {
+                Ok(Some(client)) if applied > 0 => {
+                    watch_config(&state, client_id, client.min_poll_interval_in_ms).await
+                }
+                Ok(_) => Ok(Json(WatchConfigDto::default())),
+                Err(e) => {
+                    error!("Failed to get client config: {}", e);
+                    Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+                }
+            }
         }
         Err(e) => {
             error!("Failed to get client config: {}", e);
@@ -53,3 +78,23 @@ pub async fn get_config(
         }
     }
 }
+
+async fn watch_config(
+    state: &AppState,
+    client_id: &str,
+    min_poll_interval_in_ms: u16,
+) -> Result<Json<WatchConfigDto>, (StatusCode, String)> {
+    let watch_groups = state
+        .db
+        .client_watch_group()
+        .get_for_client(client_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get watch groups for client: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(Json(WatchConfigDto {
+        min_poll_interval_in_ms,
+        watch_groups,
+    }))
+}
@@ -0,0 +1,737 @@
+use crate::client_file_event::ClientFileEventDto;
+use crate::file_event::{FileEvent, FileEventType};
+use crate::{AppState, UPLOAD_TMP_PATH, publish, thumbnail, versions, webhook, write};
+use axum::body::Body;
+use axum::extract::{Path, State};
+use axum::http::header::{ALLOW, CONTENT_LENGTH, CONTENT_TYPE, LAST_MODIFIED};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use shared::endpoint::ServerEndpoint;
+use shared::get_files_of_directory::{ScanLimits, get_all_file_descriptions};
+use shared::matchable_path::MatchablePath;
+use shared::temp_files::TempFilePatterns;
+use shared::utc_millis::UtcMillis;
+use std::fs;
+use std::io;
+use std::path::{Path as FsPath, PathBuf};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::content_length;
+use super::search::{index_file, unindex_file};
+use super::sync::{durability, upload_path_for_wg, verify_writes};
+use super::upload::process_upload;
+
+/// author of the file events recorded for writes through webdav
+pub(super) const WEBDAV_CLIENT_ID: &str = "b8c9d0e1-2f3a-4b4c-9d5e-6f7a8b9c0d1e";
+const WEBDAV_HOST: &str = "webdav";
+const ALLOWED_METHODS: &str =
+    "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, PROPPATCH, MKCOL, COPY, MOVE, LOCK, UNLOCK";
+/// path delimiters stay - everything that isn't safe within a url path gets encoded
+const HREF_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b']')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+type DavResult = Result<Response, (StatusCode, String)>;
+
+#[derive(Deserialize)]
+pub struct DavParams {
+    wg_id: i64,
+    /// missing for the root collection
+    path: Option<String>,
+}
+
+/// ANY /dav/{wg_id}/{*path} - the upload tree of a watch group as webdav share
+///
+/// class 1 plus dummy locks (finder and explorer only mount shares writable that support
+/// locking) - writes are recorded as file events just like client uploads
+pub async fn dav_handler(
+    State(state): State<AppState>,
+    Path(params): Path<DavParams>,
+    method: Method,
+    headers: HeaderMap,
+    body: Body,
+) -> DavResult {
+    let wg_id = params.wg_id;
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group existence: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Watch group {wg_id} not found"),
+        ));
+    }
+
+    let _write = match method.as_str() {
+        "PUT" | "DELETE" | "MKCOL" | "COPY" | "MOVE" => Some(state.backup_window.try_write(wg_id)?),
+        _ => None,
+    };
+
+    let segments = segments(params.path.as_deref())?;
+    let root = upload_path_for_wg(wg_id);
+    fs::create_dir_all(&root).map_err(internal)?;
+    let target = resolve(&root, &segments);
+
+    match method.as_str() {
+        "OPTIONS" => Ok(options()),
+        "PROPFIND" => propfind(wg_id, &segments, &target, &headers),
+        // dead properties aren't stored - but explorer insists on setting its timestamps
+        "PROPPATCH" => Ok(multistatus(vec![response_xml(
+            &href(wg_id, &segments, target.is_dir()),
+            "",
+        )])),
+        "GET" => get(wg_id, &segments, &target, false).await,
+        "HEAD" => get(wg_id, &segments, &target, true).await,
+        "PUT" => put(&state, wg_id, &segments, &target, &headers, body).await,
+        "DELETE" => delete(&state, wg_id, &segments, &target).await,
+        "MKCOL" => mkcol(&segments, &target),
+        "COPY" => copy_or_move(&state, wg_id, &segments, &target, &headers, false).await,
+        "MOVE" => copy_or_move(&state, wg_id, &segments, &target, &headers, true).await,
+        "LOCK" => Ok(lock()),
+        "UNLOCK" => Ok(StatusCode::NO_CONTENT.into_response()),
+        _ => Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            format!("{method} is not supported"),
+        )),
+    }
+}
+
+fn options() -> Response {
+    (
+        [
+            (HeaderName::from_static("dav"), "1, 2"),
+            (ALLOW, ALLOWED_METHODS),
+            (HeaderName::from_static("ms-author-via"), "DAV"),
+        ],
+        StatusCode::OK,
+    )
+        .into_response()
+}
+
+/// depth "infinity" is answered like "1" - clients walk the tree one collection at a time anyway
+fn propfind(wg_id: i64, segments: &[String], target: &FsPath, headers: &HeaderMap) -> DavResult {
+    let meta = fs::metadata(target).map_err(|_| not_found())?;
+    let name = segments
+        .last()
+        .cloned()
+        .unwrap_or_else(|| wg_id.to_string());
+    let mut responses = vec![response_xml(
+        &href(wg_id, segments, meta.is_dir()),
+        &props_xml(&name, &meta),
+    )];
+
+    let depth_zero = headers.get("Depth").is_some_and(|v| v == "0");
+    if meta.is_dir() && !depth_zero {
+        let mut entries: Vec<fs::DirEntry> = fs::read_dir(target)
+            .map_err(internal)?
+            .filter_map(Result::ok)
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let (Ok(meta), Ok(name)) = (entry.metadata(), entry.file_name().into_string()) else {
+                continue;
+            };
+            let props = props_xml(&name, &meta);
+            let mut child = segments.to_vec();
+            child.push(name);
+            responses.push(response_xml(&href(wg_id, &child, meta.is_dir()), &props));
+        }
+    }
+    Ok(multistatus(responses))
+}
+
+/// collections are answered with an index page (see [`index_html`])
+async fn get(wg_id: i64, segments: &[String], target: &FsPath, head_only: bool) -> DavResult {
+    let meta = fs::metadata(target).map_err(|_| not_found())?;
+    if meta.is_dir() {
+        let html = index_html(wg_id, segments, target)?;
+        let body = if head_only {
+            Body::empty()
+        } else {
+            Body::from(html)
+        };
+        return Ok(([(CONTENT_TYPE, "text/html; charset=utf-8")], body).into_response());
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(meta.len()));
+    if let Some(modified) = meta
+        .modified()
+        .ok()
+        .and_then(|m| HeaderValue::from_str(&httpdate::fmt_http_date(m)).ok())
+    {
+        headers.insert(LAST_MODIFIED, modified);
+    }
+    let body = if head_only {
+        Body::empty()
+    } else {
+        let file = tokio::fs::File::open(target).await.map_err(internal)?;
+        write::file_body(file)
+    };
+    Ok((headers, body).into_response())
+}
+
+/// nginx style autoindex - parsed by rclone's http backend (`rclone copy :http: ...`), which
+/// gets sizes and mtimes of the linked files via HEAD
+fn index_html(
+    wg_id: i64,
+    segments: &[String],
+    target: &FsPath,
+) -> Result<String, (StatusCode, String)> {
+    let mut entries: Vec<(String, fs::Metadata)> = fs::read_dir(target)
+        .map_err(internal)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            Some((
+                entry.file_name().into_string().ok()?,
+                entry.metadata().ok()?,
+            ))
+        })
+        .collect();
+    // collections first - like nginx
+    entries
+        .sort_by(|(a, a_meta), (b, b_meta)| b_meta.is_dir().cmp(&a_meta.is_dir()).then(a.cmp(b)));
+
+    let title = xml_escape(&href(wg_id, segments, true));
+    let mut html = format!(
+        "<html>\n<head><title>Index of {title}</title></head>\n<body>\n<h1>Index of {title}</h1><hr><pre>"
+    );
+    if !segments.is_empty() {
+        html.push_str("<a href=\"../\">../</a>\n");
+    }
+    for (name, meta) in entries {
+        let modified = meta
+            .modified()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_default();
+        let size = (!meta.is_dir()).then_some(meta.len());
+        html.push_str(&index_line(&name, size, modified));
+    }
+    html.push_str("</pre><hr></body>\n</html>\n");
+    Ok(html)
+}
+
+/// `size` is None for collections
+fn index_line(name: &str, size: Option<u64>, modified: DateTime<Utc>) -> String {
+    let label = match size {
+        Some(_) => name.to_string(),
+        None => format!("{name}/"),
+    };
+    let link = utf8_percent_encode(&label, HREF_ENCODE_SET).to_string();
+    let padding = " ".repeat(51usize.saturating_sub(label.chars().count()).max(1));
+    let size = size.map_or("-".to_string(), |size| size.to_string());
+    format!(
+        "<a href=\"{}\">{}</a>{padding}{} {size:>19}\n",
+        xml_escape(&link),
+        xml_escape(&label),
+        modified.format("%d-%b-%Y %H:%M"),
+    )
+}
+
+/// goes through the same pipeline as client uploads (inspection, versioning, verification)
+async fn put(
+    state: &AppState,
+    wg_id: i64,
+    segments: &[String],
+    target: &FsPath,
+    headers: &HeaderMap,
+    body: Body,
+) -> DavResult {
+    if segments.is_empty() || target.is_dir() {
+        return Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Collections can't be replaced by a file".to_string(),
+        ));
+    }
+    ensure_parent_exists(target)?;
+    state.upload_disk.ensure_room_for(content_length(headers))?;
+    let existed = target.exists();
+    let mut hasher = verify_writes(state, wg_id).await?.then(Sha256::new);
+    let durability = durability(state, wg_id).await?;
+
+    let temp_path = UPLOAD_TMP_PATH.join(Uuid::new_v4().to_string());
+    let written = write::write_all_chunks_of_body(&temp_path, body, hasher.as_mut(), durability);
+    let size = match written.await {
+        Ok(size) => size,
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Failed to receive file - {e}"),
+            ));
+        }
+    };
+
+    let dto = ClientFileEventDto {
+        utc_millis: Some(UtcMillis::now()),
+        relative_path: Some(segments.to_vec()),
+        temp_file_path: Some(temp_path),
+        content_size: Some(size),
+        watch_group_id: wg_id,
+        content_hash: hasher.map(|h| format!("{:x}", h.finalize())),
+        conflict_kept: None,
+    };
+    process_upload(
+        &upload_path_for_wg(wg_id),
+        state.clone(),
+        dto,
+        Some(WEBDAV_HOST.to_string()),
+        Some(WEBDAV_CLIENT_ID.to_string()),
+    )
+    .await
+    .map_err(|(temp_path, status, message)| {
+        if let Some(temp_path) = temp_path {
+            let _ = fs::remove_file(temp_path);
+        }
+        (status, message)
+    })?;
+
+    Ok(if existed {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }
+    .into_response())
+}
+
+/// collections are deleted file by file - so every file gets its delete event
+async fn delete(state: &AppState, wg_id: i64, segments: &[String], target: &FsPath) -> DavResult {
+    if segments.is_empty() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "The watch group root can't be deleted".to_string(),
+        ));
+    }
+    let meta = fs::metadata(target).map_err(|_| not_found())?;
+    if meta.is_dir() {
+        for file in files_below(target)? {
+            let path = joined(segments, &file);
+            delete_file(state, wg_id, path, &file.resolve(target)).await?;
+        }
+        fs::remove_dir_all(target).map_err(internal)?;
+    } else {
+        delete_file(state, wg_id, MatchablePath::from(segments.to_vec()), target).await?;
+    }
+
+    info!(
+        "WebDAV deleted '{}' from watch group {wg_id}",
+        segments.join("/")
+    );
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn mkcol(segments: &[String], target: &FsPath) -> DavResult {
+    if segments.is_empty() || target.exists() {
+        return Err((StatusCode::METHOD_NOT_ALLOWED, "Already exists".to_string()));
+    }
+    ensure_parent_exists(target)?;
+    fs::create_dir(target).map_err(internal)?;
+    Ok(StatusCode::CREATED.into_response())
+}
+
+/// only within the watch group - a move is recorded as delete of the source and change of the
+/// destination (per file)
+async fn copy_or_move(
+    state: &AppState,
+    wg_id: i64,
+    segments: &[String],
+    target: &FsPath,
+    headers: &HeaderMap,
+    is_move: bool,
+) -> DavResult {
+    if segments.is_empty() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "The watch group root can't be copied or moved".to_string(),
+        ));
+    }
+    let meta = fs::metadata(target).map_err(|_| not_found())?;
+    let destination = headers
+        .get("Destination")
+        .and_then(|v| v.to_str().ok())
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            "Missing Destination header".to_string(),
+        ))?;
+    let dest_segments = destination_segments(wg_id, destination)?;
+    if dest_segments.is_empty() || dest_segments.starts_with(segments) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Destination can't be the source or within it".to_string(),
+        ));
+    }
+
+    let root = upload_path_for_wg(wg_id);
+    let dest = resolve(&root, &dest_segments);
+    ensure_parent_exists(&dest)?;
+    let existed = dest.exists();
+    if existed {
+        if headers.get("Overwrite").is_some_and(|v| v == "F") {
+            return Err((
+                StatusCode::PRECONDITION_FAILED,
+                "Destination exists".to_string(),
+            ));
+        }
+        // a replaced file just gets a new version - anything involving collections starts over
+        if meta.is_dir() || dest.is_dir() {
+            delete(state, wg_id, &dest_segments, &dest).await?;
+        }
+    }
+
+    let transfers: Vec<(MatchablePath, MatchablePath)> = if meta.is_dir() {
+        create_collections(target, &dest).map_err(internal)?;
+        files_below(target)?
+            .into_iter()
+            .map(|file| (joined(segments, &file), joined(&dest_segments, &file)))
+            .collect()
+    } else {
+        vec![(
+            MatchablePath::from(segments.to_vec()),
+            MatchablePath::from(dest_segments.clone()),
+        )]
+    };
+
+    for (from, to) in transfers {
+        let from_file = from.resolve(&root);
+        let to_file = to.resolve(&root);
+        versions::keep_current(state.history.as_ref(), wg_id, &to, &to_file);
+        if is_move {
+            versions::keep_current(state.history.as_ref(), wg_id, &from, &from_file);
+            fs::rename(&from_file, &to_file).map_err(internal)?;
+            record(state, wg_id, from, &from_file, FileEventType::DeleteEvent).await;
+        } else {
+            fs::copy(&from_file, &to_file).map_err(internal)?;
+        }
+        record(state, wg_id, to, &to_file, FileEventType::ChangeEvent).await;
+    }
+    if is_move && meta.is_dir() {
+        fs::remove_dir_all(target).map_err(internal)?;
+    }
+
+    info!(
+        "WebDAV {} '{}' to '{}' in watch group {wg_id}",
+        if is_move { "moved" } else { "copied" },
+        segments.join("/"),
+        dest_segments.join("/")
+    );
+    Ok(if existed {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }
+    .into_response())
+}
+
+/// locks aren't enforced - every LOCK succeeds with a fresh token
+fn lock() -> Response {
+    let token = format!("opaquelocktoken:{}", Uuid::new_v4());
+    let body = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<D:prop xmlns:D="DAV:"><D:lockdiscovery><D:activelock><D:locktype><D:write/></D:locktype><D:lockscope><D:exclusive/></D:lockscope><D:depth>infinity</D:depth><D:timeout>Second-3600</D:timeout><D:locktoken><D:href>{token}</D:href></D:locktoken></D:activelock></D:lockdiscovery></D:prop>"#
+    );
+    (
+        [
+            (CONTENT_TYPE, "application/xml; charset=utf-8".to_string()),
+            (HeaderName::from_static("lock-token"), format!("<{token}>")),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+async fn delete_file(
+    state: &AppState,
+    wg_id: i64,
+    path: MatchablePath,
+    file: &FsPath,
+) -> Result<(), (StatusCode, String)> {
+    versions::keep_current(state.history.as_ref(), wg_id, &path, file);
+    fs::remove_file(file).map_err(internal)?;
+    record(state, wg_id, path, file, FileEventType::DeleteEvent).await;
+    Ok(())
+}
+
+/// records a change done through webdav - like the upload / delete of a client
+async fn record(
+    state: &AppState,
+    wg_id: i64,
+    path: MatchablePath,
+    file: &FsPath,
+    event_type: FileEventType,
+) {
+    let size = match event_type {
+        FileEventType::ChangeEvent => fs::metadata(file).map(|m| m.len()).unwrap_or(0),
+        FileEventType::DeleteEvent => 0,
+    };
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        UtcMillis::now(),
+        path,
+        size,
+        event_type,
+        Some(WEBDAV_HOST.to_string()),
+        wg_id,
+    );
+
+    if let Err(e) = state.events.record(&event, Some(WEBDAV_CLIENT_ID)).await {
+        error!("Failed to persist webdav file event to DB: {e}");
+    }
+    match event.event_type {
+        FileEventType::ChangeEvent => {
+            index_file(state, wg_id, &event.relative_path, file).await;
+            thumbnail::render_in_background(wg_id, &event.relative_path, file);
+        }
+        FileEventType::DeleteEvent => unindex_file(state, wg_id, &event.relative_path).await,
+    }
+    webhook::notify(state, &event, Some(WEBDAV_CLIENT_ID));
+    publish::mirror(state, &event);
+}
+
+/// path segments below the watch group root (empty for the root itself)
+fn segments(path: Option<&str>) -> Result<Vec<String>, (StatusCode, String)> {
+    let segments: Vec<String> = path
+        .unwrap_or_default()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+    let is_unsafe = |segment: &String| {
+        matches!(segment.as_str(), "." | ".." | "~")
+            || segment.contains('\\')
+            || segment.trim() != segment
+    };
+    if segments.iter().any(is_unsafe) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid path".to_string()));
+    }
+    Ok(segments)
+}
+
+/// the `Destination` header holds an absolute url (or just its path)
+fn destination_segments(
+    wg_id: i64,
+    destination: &str,
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let path = match destination.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+        None => destination,
+    };
+    let below = path
+        .strip_prefix(&ServerEndpoint::Dav.to_uri_with_wg("", wg_id))
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        .ok_or((
+            StatusCode::FORBIDDEN,
+            "Destination must be within the same watch group".to_string(),
+        ))?;
+    let decoded = percent_decode_str(below)
+        .decode_utf8()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid destination".to_string()))?;
+    segments(Some(&decoded))
+}
+
+fn resolve(root: &FsPath, segments: &[String]) -> PathBuf {
+    root.join(segments.iter().collect::<PathBuf>())
+}
+
+fn joined(segments: &[String], file: &MatchablePath) -> MatchablePath {
+    MatchablePath::from(
+        segments
+            .iter()
+            .chain(file.get())
+            .cloned()
+            .collect::<Vec<String>>(),
+    )
+}
+
+/// files below `dir` - relative to it
+fn files_below(dir: &FsPath) -> Result<Vec<MatchablePath>, (StatusCode, String)> {
+    get_all_file_descriptions(
+        dir,
+        &Vec::new(),
+        false,
+        &TempFilePatterns::none(),
+        ScanLimits::none(),
+    )
+    .map(|descriptions| descriptions.into_iter().map(|d| d.relative_path).collect())
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// recreates the (sub)collections of `src` below `dst` - files are transferred one by one
+fn create_collections(src: &FsPath, dst: &FsPath) -> io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            create_collections(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn ensure_parent_exists(target: &FsPath) -> Result<(), (StatusCode, String)> {
+    if target.parent().is_some_and(FsPath::is_dir) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::CONFLICT,
+            "Parent collection doesn't exist".to_string(),
+        ))
+    }
+}
+
+fn href(wg_id: i64, segments: &[String], is_collection: bool) -> String {
+    let mut href = ServerEndpoint::Dav.to_uri_with_wg("", wg_id);
+    for segment in segments {
+        href.push('/');
+        href.extend(utf8_percent_encode(segment, HREF_ENCODE_SET));
+    }
+    if is_collection {
+        href.push('/');
+    }
+    href
+}
+
+fn props_xml(name: &str, meta: &fs::Metadata) -> String {
+    let mut props = format!("<D:displayname>{}</D:displayname>", xml_escape(name));
+    if meta.is_dir() {
+        props.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+    } else {
+        props.push_str(&format!(
+            "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength>",
+            meta.len()
+        ));
+    }
+    if let Ok(modified) = meta.modified() {
+        props.push_str(&format!(
+            "<D:getlastmodified>{}</D:getlastmodified>",
+            httpdate::fmt_http_date(modified)
+        ));
+    }
+    props
+}
+
+fn response_xml(href: &str, props: &str) -> String {
+    format!(
+        "<D:response><D:href>{}</D:href><D:propstat><D:prop>{props}</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>",
+        xml_escape(href)
+    )
+}
+
+fn multistatus(responses: Vec<String>) -> Response {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">{}</D:multistatus>",
+        responses.concat()
+    );
+    (
+        StatusCode::MULTI_STATUS,
+        [(CONTENT_TYPE, "application/xml; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
+
+fn xml_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn not_found() -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, "Not found".to_string())
+}
+
+fn internal(e: io::Error) -> (StatusCode, String) {
+    error!("WebDAV io failure - {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn should_encode_hrefs() {
+        assert_eq!("/dav/3/", href(3, &[], true));
+        assert_eq!(
+            "/dav/3/my%20docs/%C3%A4%23.txt",
+            href(3, &strings(&["my docs", "ä#.txt"]), false)
+        );
+    }
+
+    #[test]
+    fn should_list_index_lines_like_nginx() {
+        let modified = DateTime::from_timestamp(1_760_000_000, 0).unwrap();
+        assert_eq!(
+            format!(
+                "<a href=\"my%20docs/\">my docs/</a>{}09-Oct-2025 08:53                   -\n",
+                " ".repeat(43)
+            ),
+            index_line("my docs", None, modified)
+        );
+        assert_eq!(
+            format!(
+                "<a href=\"a&amp;b.txt\">a&amp;b.txt</a>{}09-Oct-2025 08:53                5743\n",
+                " ".repeat(44)
+            ),
+            index_line("a&b.txt", Some(5743), modified)
+        );
+    }
+
+    #[test]
+    fn should_only_accept_destinations_within_the_watch_group() {
+        assert_eq!(
+            strings(&["a b", "c.txt"]),
+            destination_segments(3, "https://nas.local:3000/dav/3/a%20b/c.txt").unwrap()
+        );
+        assert_eq!(
+            strings(&["x"]),
+            destination_segments(3, "/dav/3/x/").unwrap()
+        );
+        assert_eq!(
+            StatusCode::FORBIDDEN,
+            destination_segments(3, "/dav/33/x").unwrap_err().0
+        );
+        assert_eq!(
+            StatusCode::FORBIDDEN,
+            destination_segments(3, "http://nas.local/dav/4/x")
+                .unwrap_err()
+                .0
+        );
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            destination_segments(3, "/dav/3/a/%2E%2E/x").unwrap_err().0
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use crate::AppState;
+use crate::db::ServerDatabase;
+use axum::Json;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use futures_util::stream;
+use shared::dtos::{ExportCheckpointUpdateDto, ExportStatusDto};
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::io;
+use tracing::{debug, error};
+
+/// events per DB query while streaming
+pub(crate) const PAGE_SIZE: u32 = 500;
+const MAX_CONSUMER_NAME_LEN: usize = 100;
+
+/// GET /api/export/events?since=<seq>&limit=<n> - every event after the cursor as NDJSON, oldest
+/// first; `consumer=<name>` starts after the checkpoint of that consumer instead
+///
+/// the stream ends after the newest event - the consumer continues with the seq of the last line
+/// and moves its checkpoint once it processed the events (at-least-once)
+pub async fn api_export_events(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let since = match (params.get("since"), params.get("consumer")) {
+        (Some(since), _) => since
+            .parse::<i64>()
+            .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid since".to_string()))?,
+        (None, Some(consumer)) => {
+            check_consumer_name(consumer)?;
+            state
+                .db
+                .export()
+                .checkpoint(consumer)
+                .await
+                .map_err(internal)?
+        }
+        (None, None) => 0,
+    };
+    let limit = match params.get("limit") {
+        Some(limit) => Some(
+            limit
+                .parse::<u64>()
+                .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid limit".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let lines = stream::unfold(Some((state.db.clone(), since, limit)), |page| async move {
+        let (db, cursor, remaining) = page?;
+        let page_size = remaining.map_or(PAGE_SIZE, |r| r.min(PAGE_SIZE as u64) as u32);
+        if page_size == 0 {
+            return None;
+        }
+        match next_page(&db, cursor, page_size).await {
+            Ok((_, 0, _)) => None,
+            Ok((chunk, count, last_seq)) => {
+                let next = (count == page_size as usize)
+                    .then(|| (db, last_seq, remaining.map(|r| r - count as u64)));
+                Some((Ok(chunk), next))
+            }
+            // breaks off the body - the consumer resumes after its last complete line
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    Ok((
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    ))
+}
+
+/// a page of events as NDJSON - with the number of events and the seq of the last one
+async fn next_page(
+    db: &ServerDatabase,
+    cursor: i64,
+    page_size: u32,
+) -> io::Result<(Bytes, usize, i64)> {
+    let events = db
+        .export()
+        .events_after(cursor, page_size)
+        .await
+        .map_err(|e| {
+            error!("Failed to export events: {}", e);
+            io::Error::other(e)
+        })?;
+    let mut chunk = Vec::new();
+    for event in &events {
+        serde_json::to_writer(&mut chunk, event)?;
+        chunk.push(b'\n');
+    }
+    let last_seq = events.last().map_or(cursor, |e| e.seq);
+    Ok((Bytes::from(chunk), events.len(), last_seq))
+}
+
+/// GET /api/export
+pub async fn api_get_export_status(
+    State(state): State<AppState>,
+) -> Result<Json<ExportStatusDto>, (StatusCode, String)> {
+    let latest_seq = state
+        .db
+        .replication()
+        .latest_seq()
+        .await
+        .map_err(internal)?;
+    let checkpoints = state.db.export().checkpoints().await.map_err(internal)?;
+    Ok(Json(ExportStatusDto {
+        latest_seq,
+        checkpoints,
+    }))
+}
+
+/// PUT /api/export/checkpoints/{name} - the consumer processed every event up to `seq`
+pub async fn api_set_export_checkpoint(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(update): Json<ExportCheckpointUpdateDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    check_consumer_name(&name)?;
+    if update.seq < 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "seq must not be negative".to_string(),
+        ));
+    }
+    state
+        .db
+        .export()
+        .set_checkpoint(&name, update.seq, UtcMillis::now())
+        .await
+        .map_err(internal)?;
+    debug!("Export consumer '{}' checkpointed at {}", name, update.seq);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn check_consumer_name(name: &str) -> Result<(), (StatusCode, String)> {
+    if name.trim().is_empty() || name.len() > MAX_CONSUMER_NAME_LEN {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Consumer name must have 1 to {MAX_CONSUMER_NAME_LEN} characters"),
+        ));
+    }
+    Ok(())
+}
+
+fn internal(e: sqlx::Error) -> (StatusCode, String) {
+    error!("Failed to read export state: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
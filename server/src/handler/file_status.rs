@@ -0,0 +1,203 @@
+use crate::AppState;
+use crate::db::ClientFileSnapshot;
+use crate::file_event::FileEvent;
+use crate::supervisor;
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use shared::dtos::{ClientFileStatusDto, ClientFilesDto, FileStatusDto, FileSyncState};
+use shared::sync_instruction::{SyncInstruction, diff};
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::env;
+use std::slice;
+use tracing::{error, info};
+
+use super::file_path_param;
+
+/// GET /api/file-status/{wg_id}?path=dir/file.txt - one entry per client assigned to the
+/// watch group
+pub async fn api_get_file_status(
+    State(state): State<AppState>,
+    axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<FileStatusDto>, (StatusCode, String)> {
+    let path = file_path_param(&params)?;
+    let latest = state.history.get_latest_event(wg_id, &path).ok_or((
+        StatusCode::NOT_FOUND,
+        format!("No history for '{}'", path.to_serialized_string()),
+    ))?;
+    let snapshots = state
+        .db
+        .client_file_state()
+        .get_file(wg_id, &path)
+        .await
+        .map_err(|e| {
+            error!("Failed to read client snapshots: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    Ok(Json(FileStatusDto {
+        relative_path: path.to_serialized_string(),
+        size_in_bytes: latest.size_in_bytes,
+        utc_millis: latest.utc_millis.clone(),
+        deleted: latest.event_type.is_delete(),
+        clients: snapshots
+            .into_iter()
+            .map(|snapshot| ClientFileStatusDto {
+                state: sync_state(&latest, &snapshot),
+                size_in_bytes: snapshot.file.as_ref().map(|f| f.size_in_bytes),
+                last_updated_utc_millis: snapshot
+                    .file
+                    .as_ref()
+                    .map(|f| f.last_updated_utc_millis.clone()),
+                client_id: snapshot.client_id,
+                host_name: snapshot.host_name,
+                reported_at: snapshot.reported_at,
+            })
+            .collect(),
+    }))
+}
+
+/// GET /api/clients/{id}/watch-groups/{wg_id}/files - the client's view of the watch group as of
+/// its latest sync
+pub async fn api_get_client_files(
+    State(state): State<AppState>,
+    axum::extract::Path((client_id, wg_id)): axum::extract::Path<(String, i64)>,
+) -> Result<Json<ClientFilesDto>, (StatusCode, String)> {
+    let reported = state
+        .db
+        .client_file_state()
+        .get_files(&client_id, wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to read client file state: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    match reported {
+        Some((reported_at, files)) => Ok(Json(ClientFilesDto { reported_at, files })),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            format!("Client {client_id} didn't sync watch group {wg_id} yet"),
+        )),
+    }
+}
+
+/// one run of the `gc` job - drops file lists older than `CLIENT_FILE_STATE_RETENTION_DAYS`
+/// (default 30, 0 keeps them) and those of clients no longer assigned to the watch group
+pub async fn run_client_file_state_cleanup(state: &AppState) {
+    let days = env::var("CLIENT_FILE_STATE_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(30);
+    let before = match days {
+        0 => UtcMillis::from(0),
+        days => UtcMillis::from(
+            UtcMillis::now()
+                .as_u64()
+                .saturating_sub(days * 24 * 60 * 60 * 1000),
+        ),
+    };
+    match state.db.client_file_state().delete_stale(before).await {
+        Ok(dropped) => {
+            if dropped > 0 {
+                info!("Dropped {} stale client file lists", dropped);
+            }
+            supervisor::succeeded();
+        }
+        Err(e) => {
+            error!("Client file state cleanup failed: {}", e);
+            supervisor::failed(&e);
+        }
+    }
+}
+
+/// what the client's next sync would have to do with the file
+fn sync_state(latest: &FileEvent, snapshot: &ClientFileSnapshot) -> FileSyncState {
+    if snapshot.reported_at.is_none() {
+        return FileSyncState::NotReported;
+    }
+    let client_file: Vec<_> = snapshot.file.iter().cloned().collect();
+    match diff(slice::from_ref(latest), &client_file).first() {
+        None => FileSyncState::UpToDate,
+        Some(SyncInstruction::Download(_)) if snapshot.file.is_none() => FileSyncState::Missing,
+        Some(SyncInstruction::Download(_) | SyncInstruction::Delete(_)) => FileSyncState::Outdated,
+        Some(SyncInstruction::Upload(_)) => FileSyncState::Newer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_event::FileEventType;
+    use shared::dtos::FileDescription;
+    use shared::matchable_path::MatchablePath;
+    use shared::utc_millis::UtcMillis;
+    use uuid::Uuid;
+
+    fn event(millis: u64, size: u64, event_type: FileEventType) -> FileEvent {
+        FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(millis),
+            MatchablePath::from("a.txt"),
+            size,
+            event_type,
+            None,
+            1,
+        )
+    }
+
+    fn snapshot(file: Option<(u64, u64)>) -> ClientFileSnapshot {
+        ClientFileSnapshot {
+            client_id: "c1".to_string(),
+            host_name: "arch".to_string(),
+            reported_at: Some(UtcMillis::from(100)),
+            file: file.map(|(millis, size)| FileDescription {
+                file_name: "a.txt".to_string(),
+                relative_path: MatchablePath::from("a.txt"),
+                size_in_bytes: size,
+                file_type: "txt".to_string(),
+                last_updated_utc_millis: UtcMillis::from(millis),
+            }),
+        }
+    }
+
+    #[test]
+    fn should_reject_paths_without_segments() {
+        for path in ["..", ".", "/", "", "./.."] {
+            let params = HashMap::from([("path".to_string(), path.to_string())]);
+            assert_eq!(
+                StatusCode::BAD_REQUEST,
+                file_path_param(&params).unwrap_err().0,
+                "{path}"
+            );
+        }
+        let params = HashMap::from([("path".to_string(), "../dir/./a.txt".to_string())]);
+        assert_eq!(
+            MatchablePath::from("dir/a.txt"),
+            file_path_param(&params).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_derive_sync_state_from_snapshot() {
+        let changed = event(10, 3, FileEventType::ChangeEvent);
+        let deleted = event(10, 0, FileEventType::DeleteEvent);
+
+        let cases = [
+            (&changed, snapshot(Some((10, 3))), FileSyncState::UpToDate),
+            (&changed, snapshot(Some((5, 2))), FileSyncState::Outdated),
+            (&changed, snapshot(Some((20, 4))), FileSyncState::Newer),
+            (&changed, snapshot(None), FileSyncState::Missing),
+            (&deleted, snapshot(None), FileSyncState::UpToDate),
+            (&deleted, snapshot(Some((5, 3))), FileSyncState::Outdated),
+        ];
+        for (latest, snapshot, expected) in cases {
+            assert_eq!(expected, sync_state(latest, &snapshot));
+        }
+
+        let mut unknown = snapshot(None);
+        unknown.reported_at = None;
+        assert_eq!(FileSyncState::NotReported, sync_state(&changed, &unknown));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::AppState;
+use crate::file_event::FileEvent;
+use crate::versions;
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use shared::dtos::{DiffDto, DiffLineDto, DiffRowDto, FileVersionDto};
+use shared::matchable_path::MatchablePath;
+use similar::{DiffOp, TextDiff};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use uuid::Uuid;
+
+use super::file_path_param;
+
+/// GET /api/history/{wg_id}?path=dir/file.txt - oldest version first
+pub async fn api_get_file_history(
+    State(state): State<AppState>,
+    axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<FileVersionDto>>, (StatusCode, String)> {
+    let path = file_path_param(&params)?;
+    let events = events_of(&state, wg_id, &path)?;
+
+    let versions = events
+        .iter()
+        .map(|e| FileVersionDto {
+            event_id: e.id.to_string(),
+            utc_millis: e.utc_millis.clone(),
+            size_in_bytes: e.size_in_bytes,
+            deleted: e.event_type.is_delete(),
+            client_host: e.client_host.clone(),
+            content_available: versions::find(wg_id, &path, &events, e.id).is_some(),
+        })
+        .collect();
+    Ok(Json(versions))
+}
+
+/// GET /api/diff/{wg_id}?path=dir/file.txt&from={event_id}&to={event_id}
+///
+/// a deleted version counts as empty file
+pub async fn api_get_diff(
+    State(state): State<AppState>,
+    axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<DiffDto>, (StatusCode, String)> {
+    let path = file_path_param(&params)?;
+    let events = events_of(&state, wg_id, &path)?;
+    let old = version_text(wg_id, &path, &events, event_id_param(&params, "from")?)?;
+    let new = version_text(wg_id, &path, &events, event_id_param(&params, "to")?)?;
+
+    Ok(Json(diff(&path.to_serialized_string(), &old, &new)))
+}
+
+fn event_id_param(
+    params: &HashMap<String, String>,
+    key: &str,
+) -> Result<Uuid, (StatusCode, String)> {
+    params
+        .get(key)
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("Missing or invalid {key} parameter"),
+        ))
+}
+
+fn events_of(
+    state: &AppState,
+    wg_id: i64,
+    path: &MatchablePath,
+) -> Result<Vec<FileEvent>, (StatusCode, String)> {
+    state.history.get_events(wg_id, path).ok_or((
+        StatusCode::NOT_FOUND,
+        format!("No history for '{}'", path.to_serialized_string()),
+    ))
+}
+
+fn version_text(
+    wg_id: i64,
+    path: &MatchablePath,
+    events: &[FileEvent],
+    event_id: Uuid,
+) -> Result<String, (StatusCode, String)> {
+    let event = events
+        .iter()
+        .find(|e| e.id == event_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("Unknown version {event_id}")))?;
+    if event.event_type.is_delete() {
+        return Ok(String::new());
+    }
+
+    let file = versions::find(wg_id, path, events, event_id).ok_or((
+        StatusCode::NOT_FOUND,
+        format!("Content of version {event_id} is not kept"),
+    ))?;
+    let bytes = fs::read(file).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    String::from_utf8(bytes).map_err(|_| {
+        (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Only text files can be diffed".to_string(),
+        )
+    })
+}
+
+fn diff(path: &str, old: &str, new: &str) -> DiffDto {
+    let text_diff = TextDiff::from_lines(old, new);
+    let unified = text_diff
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string();
+
+    let old_lines = text_diff.old_slices();
+    let new_lines = text_diff.new_slices();
+    let line = |lines: &[&str], index: usize| DiffLineDto {
+        number: index + 1,
+        text: lines[index].trim_end_matches(['\r', '\n']).to_string(),
+    };
+    // deleted and inserted lines of one op are shown next to each other
+    let side_by_side = |old_range: Range<usize>, new_range: Range<usize>, changed: bool| {
+        let len = old_range.len().max(new_range.len());
+        (0..len)
+            .map(|i| DiffRowDto {
+                old: (i < old_range.len()).then(|| line(old_lines, old_range.start + i)),
+                new: (i < new_range.len()).then(|| line(new_lines, new_range.start + i)),
+                changed,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let rows = text_diff
+        .ops()
+        .iter()
+        .flat_map(|op| match *op {
+            DiffOp::Equal {
+                old_index,
+                new_index,
+                len,
+            } => side_by_side(
+                old_index..old_index + len,
+                new_index..new_index + len,
+                false,
+            ),
+            _ => side_by_side(op.old_range(), op.new_range(), true),
+        })
+        .collect();
+
+    DiffDto { unified, rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_pair_changed_lines() {
+        let dto = diff("notes.md", "a\nb\nc\n", "a\nB\nc\nd\n");
+
+        assert!(dto.unified.contains("-b\n+B\n"));
+        assert!(dto.unified.contains("+d\n"));
+        let rows: Vec<(Option<&str>, Option<&str>, bool)> = dto
+            .rows
+            .iter()
+            .map(|r| {
+                (
+                    r.old.as_ref().map(|l| l.text.as_str()),
+                    r.new.as_ref().map(|l| l.text.as_str()),
+                    r.changed,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (Some("a"), Some("a"), false),
+                (Some("b"), Some("B"), true),
+                (Some("c"), Some("c"), false),
+                (None, Some("d"), true),
+            ],
+            rows
+        );
+        assert_eq!(4, dto.rows[3].new.as_ref().unwrap().number);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::file_event::{FileEvent, FileEventType};
+use crate::write::hash_file;
+use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH, publish, thumbnail, webhook};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use shared::dtos::{ImportDto, ImportMode, ImportResultDto, ManifestDto, ManifestEntryDto};
+use shared::get_files_of_directory::{ScanLimits, get_all_file_descriptions};
+use shared::matchable_path::MatchablePath;
+use shared::temp_files::TempFilePatterns;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{MAIN_SEPARATOR, Path, PathBuf};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::search::index_file;
+use super::sync::upload_path_for_wg;
+
+/// author of the file events recorded by an import
+pub(super) const IMPORT_CLIENT_ID: &str = "b9c0d1e2-3f4a-4b5c-8d6e-7f8a9b0c1d2e";
+
+/// bookkeeping of other sync tools - never imported
+const TOOL_DIRS: [&str; 4] = [
+    // syncthing: folder marker and versioned copies
+    ".stfolder",
+    ".stversions",
+    // rsync --delay-updates and default --partial-dir
+    ".~tmp~",
+    ".rsync-partial",
+];
+const TOOL_FILES: [&str; 3] = [
+    ".stignore",
+    // syncthing temp files of transfers in progress
+    ".syncthing.*.tmp",
+    "~syncthing~*.tmp",
+];
+
+/// one file of the source directory and what happens to it
+#[derive(Debug)]
+struct Candidate {
+    path: MatchablePath,
+    source: PathBuf,
+    size_in_bytes: u64,
+    utc_millis: UtcMillis,
+    sha256: String,
+    outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// not in the watch group yet
+    Import,
+    /// the same file is on disk but not in the history - only the event is recorded
+    Adopt,
+    Unchanged,
+    Conflict,
+}
+
+/// POST /api/admin/import - files of the directory end up in the watch group with history events
+/// dated to their modification time, so clients holding the same copy (with the same mtime) don't
+/// transfer them again
+pub async fn api_import(
+    State(state): State<AppState>,
+    Json(dto): Json<ImportDto>,
+) -> Result<Json<ImportResultDto>, (StatusCode, String)> {
+    let source_root = validate_source(&dto.source_path)?;
+    let watch_group = state
+        .db
+        .server_watch_group()
+        .get_all_watch_groups()
+        .await
+        .map_err(|e| {
+            error!("Failed to load watch groups: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .into_iter()
+        .find(|wg| wg.id == dto.watch_group_id)
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            format!("Watch group {} not found", dto.watch_group_id),
+        ))?;
+
+    let wg_id = watch_group.id;
+    let latest: HashMap<MatchablePath, FileEvent> = state
+        .history
+        .get_latest_events(wg_id)
+        .into_iter()
+        .map(|e| (e.relative_path.clone(), e))
+        .collect();
+    let wg_root = upload_path_for_wg(wg_id);
+    let candidates = tokio::task::spawn_blocking(move || plan(&source_root, &wg_root, &latest))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let _write = if dto.dry_run {
+        None
+    } else {
+        Some(state.backup_window.try_write(wg_id)?)
+    };
+    if !dto.dry_run && dto.mode == ImportMode::Copy {
+        state.upload_disk.ensure_room_for(
+            candidates
+                .iter()
+                .filter(|c| c.outcome == Outcome::Import)
+                .map(|c| c.size_in_bytes)
+                .sum(),
+        )?;
+    }
+
+    let mut result = ImportResultDto {
+        imported: Vec::new(),
+        unchanged: Vec::new(),
+        conflicts: Vec::new(),
+        imported_bytes: 0,
+        manifest: ManifestDto {
+            watch_group_id: wg_id,
+            watch_group_name: watch_group.name,
+            created: UtcMillis::now(),
+            files: Vec::new(),
+        },
+    };
+    for candidate in candidates {
+        let path = candidate.path.to_serialized_string();
+        match candidate.outcome {
+            Outcome::Conflict => {
+                result.conflicts.push(path);
+                continue;
+            }
+            Outcome::Unchanged => result.unchanged.push(path),
+            Outcome::Import | Outcome::Adopt => {
+                if !dto.dry_run {
+                    import(&state, wg_id, dto.mode, &candidate).await?;
+                }
+                result.imported.push(path);
+                result.imported_bytes += candidate.size_in_bytes;
+            }
+        }
+        result.manifest.files.push(ManifestEntryDto {
+            path: candidate.path,
+            size_in_bytes: candidate.size_in_bytes,
+            sha256: candidate.sha256,
+            utc_millis: candidate.utc_millis,
+        });
+    }
+    info!(
+        "{}Import of {} into watch group {}: {} imported, {} unchanged, {} conflicts",
+        if dto.dry_run { "Dry run - " } else { "" },
+        dto.source_path,
+        wg_id,
+        result.imported.len(),
+        result.unchanged.len(),
+        result.conflicts.len()
+    );
+    Ok(Json(result))
+}
+
+/// the source must be an absolute directory outside of the watch groups (and not contain them)
+fn validate_source(source_path: &str) -> Result<PathBuf, (StatusCode, String)> {
+    let bad_request = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let source = Path::new(source_path);
+    if !source.is_absolute() {
+        return Err(bad_request("source_path must be absolute".to_string()));
+    }
+    let source = fs::canonicalize(source)
+        .map_err(|e| bad_request(format!("Can't read {source_path} - {e}")))?;
+    if !source.is_dir() {
+        return Err(bad_request(format!("{source_path} is not a directory")));
+    }
+    let uploads = fs::canonicalize(*UPLOAD_PATH)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if source.starts_with(&uploads) || uploads.starts_with(&source) {
+        return Err(bad_request(
+            "source_path must be outside of the watch groups".to_string(),
+        ));
+    }
+    Ok(source)
+}
+
+/// hashes every file of the source and compares it with the watch group - touches nothing
+fn plan(
+    source_root: &Path,
+    wg_root: &Path,
+    latest: &HashMap<MatchablePath, FileEvent>,
+) -> Result<Vec<Candidate>, String> {
+    let exclude_dirs = TOOL_DIRS
+        .iter()
+        .map(|dir| format!("{MAIN_SEPARATOR}{dir}"))
+        .collect();
+    let tool_files = TempFilePatterns {
+        builtin: true,
+        patterns: TOOL_FILES.iter().map(|p| p.to_string()).collect(),
+    };
+    let mut descriptions = get_all_file_descriptions(
+        source_root,
+        &exclude_dirs,
+        false,
+        &tool_files,
+        ScanLimits::none(),
+    )?;
+    descriptions.sort_by_key(|d| d.relative_path.to_serialized_string());
+
+    let mut candidates = Vec::with_capacity(descriptions.len());
+    for description in descriptions {
+        let path = description.relative_path;
+        let source = path.resolve(source_root);
+        let sha256 = hash_file(&source).map_err(|e| format!("Can't read {source:?} - {e}"))?;
+        let target = path.resolve(wg_root);
+        let outcome = if !target.exists() {
+            Outcome::Import
+        } else if hash_file(&target).is_ok_and(|hash| hash == sha256) {
+            match latest.get(&path) {
+                Some(event) if event.event_type.is_change() => Outcome::Unchanged,
+                _ => Outcome::Adopt,
+            }
+        } else {
+            Outcome::Conflict
+        };
+        // new events are never older than what the history knows of the path - or a past delete
+        // would win
+        let utc_millis = match latest.get(&path) {
+            Some(event) if outcome == Outcome::Unchanged => event.utc_millis.clone(),
+            Some(event) if event.utc_millis >= description.last_updated_utc_millis => {
+                UtcMillis::from(event.utc_millis.as_u64() + 1)
+            }
+            _ => description.last_updated_utc_millis,
+        };
+        candidates.push(Candidate {
+            path,
+            source,
+            size_in_bytes: description.size_in_bytes,
+            utc_millis,
+            sha256,
+            outcome,
+        });
+    }
+    Ok(candidates)
+}
+
+async fn import(
+    state: &AppState,
+    wg_id: i64,
+    mode: ImportMode,
+    candidate: &Candidate,
+) -> Result<(), (StatusCode, String)> {
+    let target = candidate.path.resolve(&upload_path_for_wg(wg_id));
+    let internal_error = |e: io::Error| {
+        error!("Failed to import {:?}: {}", candidate.source, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    if candidate.outcome == Outcome::Import {
+        let source = candidate.source.clone();
+        let placed = target.clone();
+        tokio::task::spawn_blocking(move || place(&source, &placed, mode))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map_err(internal_error)?;
+    }
+
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        candidate.utc_millis.clone(),
+        candidate.path.clone(),
+        candidate.size_in_bytes,
+        FileEventType::ChangeEvent,
+        Some("import".to_string()),
+        wg_id,
+    );
+    if let Err(e) = state.events.record(&event, Some(IMPORT_CLIENT_ID)).await {
+        error!("Failed to persist import event to DB: {e}");
+    }
+    index_file(state, wg_id, &event.relative_path, &target).await;
+    thumbnail::render_in_background(wg_id, &event.relative_path, &target);
+    webhook::notify(state, &event, Some(IMPORT_CLIENT_ID));
+    publish::mirror(state, &event);
+    Ok(())
+}
+
+/// puts the file in place via the temp dir - swapped in by rename, like uploads
+fn place(source: &Path, target: &Path, mode: ImportMode) -> io::Result<()> {
+    let tmp = UPLOAD_TMP_PATH.join(Uuid::new_v4().to_string());
+    let staged = match mode {
+        // e.g. another file system
+        ImportMode::Link => {
+            fs::hard_link(source, &tmp).or_else(|_| fs::copy(source, &tmp).map(drop))
+        }
+        ImportMode::Copy => fs::copy(source, &tmp).map(drop),
+        ImportMode::Move => fs::rename(source, &tmp).or_else(|_| fs::copy(source, &tmp).map(drop)),
+    };
+    staged?;
+    let moved = target
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::rename(&tmp, target));
+    if let Err(e) = moved {
+        if mode == ImportMode::Move && !source.exists() {
+            // renamed out of the source - put it back
+            let _ = fs::rename(&tmp, source);
+        }
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    if mode == ImportMode::Move && source.exists() {
+        // copied across file systems
+        fs::remove_file(source)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn should_plan_import_without_tool_files() {
+        let dir = env::temp_dir().join(format!("import-{}", Uuid::new_v4()));
+        let source_root = dir.join("syncthing");
+        let wg_root = dir.join("wg");
+        fs::create_dir_all(source_root.join(".stversions")).unwrap();
+        fs::create_dir_all(source_root.join(".stfolder")).unwrap();
+        fs::create_dir_all(source_root.join("docs")).unwrap();
+        fs::create_dir_all(wg_root.join("docs")).unwrap();
+        fs::write(source_root.join(".stignore"), "*.log").unwrap();
+        fs::write(source_root.join(".stversions/old.txt"), "old").unwrap();
+        fs::write(source_root.join(".syncthing.new.txt.tmp"), "part").unwrap();
+        fs::write(source_root.join("new.txt"), "new").unwrap();
+        fs::write(source_root.join("docs/same.txt"), "same").unwrap();
+        fs::write(source_root.join("docs/untracked.txt"), "untracked").unwrap();
+        fs::write(source_root.join("docs/other.txt"), "mine").unwrap();
+        fs::write(wg_root.join("docs/same.txt"), "same").unwrap();
+        fs::write(wg_root.join("docs/untracked.txt"), "untracked").unwrap();
+        fs::write(wg_root.join("docs/other.txt"), "theirs").unwrap();
+
+        let same = MatchablePath::from("docs/same.txt");
+        let latest = HashMap::from([(
+            same.clone(),
+            FileEvent::new(
+                Uuid::new_v4(),
+                UtcMillis::from(1),
+                same,
+                4,
+                FileEventType::ChangeEvent,
+                None,
+                1,
+            ),
+        )]);
+        let outcomes: Vec<(String, Outcome)> = plan(&source_root, &wg_root, &latest)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.path.to_serialized_string(), c.outcome))
+            .collect();
+        assert_eq!(
+            vec![
+                ("docs/other.txt".to_string(), Outcome::Conflict),
+                ("docs/same.txt".to_string(), Outcome::Unchanged),
+                ("docs/untracked.txt".to_string(), Outcome::Adopt),
+                ("new.txt".to_string(), Outcome::Import),
+            ],
+            outcomes
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
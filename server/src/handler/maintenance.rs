@@ -0,0 +1,131 @@
+use crate::write::{self, BACKUP_KEY, ENCRYPTED_SUFFIX};
+use crate::{AppState, BACKUP_PATH};
+use axum::Json;
+use axum::body::Body;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::IntoResponse;
+use shared::dtos::{BackupFileDto, DbBackupDto, DbCheckDto};
+use tokio_util::io::ReaderStream;
+use tracing::{error, info, warn};
+
+use super::server_watch_group::sanitize_filename;
+
+/// POST /api/admin/db/backup - `VACUUM INTO` a timestamped file within the backup dir
+pub async fn api_db_backup(
+    State(state): State<AppState>,
+) -> Result<Json<DbBackupDto>, (StatusCode, String)> {
+    let backup = state
+        .db
+        .maintenance()
+        .backup_into(&BACKUP_PATH)
+        .await
+        .map_err(|e| {
+            error!("Failed to back up database: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    let size_in_bytes = tokio::fs::metadata(&backup)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    info!("Backed up database to {:?}", backup);
+
+    Ok(Json(DbBackupDto {
+        file_name: backup
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size_in_bytes,
+    }))
+}
+
+/// GET /api/admin/backups - archives and database copies (snapshot directories are not listed)
+pub async fn api_list_backups() -> Result<Json<Vec<BackupFileDto>>, (StatusCode, String)> {
+    let mut entries = tokio::fs::read_dir(*BACKUP_PATH)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut backups = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        backups.push(BackupFileDto {
+            encrypted: name.ends_with(ENCRYPTED_SUFFIX),
+            name,
+            size_in_bytes: metadata.len(),
+        });
+    }
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(Json(backups))
+}
+
+/// GET /api/admin/backups/{name} - encrypted archives are decrypted while streaming, so a
+/// restore is the same for both
+pub async fn api_download_backup(
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let name = sanitize_filename(&name)?;
+    let file = tokio::fs::File::open(BACKUP_PATH.join(&name))
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("No backup {name}")))?;
+
+    let (body, file_name) = match name.strip_suffix(ENCRYPTED_SUFFIX) {
+        None => (write::file_body(file), name.clone()),
+        Some(plain_name) => {
+            let key = BACKUP_KEY.as_ref().ok_or((
+                StatusCode::CONFLICT,
+                "BACKUP_ENCRYPTION_KEY is not set - cannot decrypt".to_string(),
+            ))?;
+            let (writer, reader) = tokio::io::duplex(64 * 1024);
+            let name = name.clone();
+            tokio::spawn(async move {
+                // the download breaks off - a corrupt archive never arrives complete
+                if let Err(e) = key.decrypt(file, writer).await {
+                    error!("Decryption of backup {name} failed: {}", e);
+                }
+            });
+            (
+                Body::from_stream(ReaderStream::new(reader)),
+                plain_name.to_string(),
+            )
+        }
+    };
+    info!("Serving backup {name}");
+
+    let headers = [
+        (CONTENT_TYPE, "application/octet-stream".to_string()),
+        (
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", file_name.replace('"', "_")),
+        ),
+    ];
+    Ok((headers, body))
+}
+
+/// GET /api/admin/db/check - `PRAGMA integrity_check`
+pub async fn api_db_check(
+    State(state): State<AppState>,
+) -> Result<Json<DbCheckDto>, (StatusCode, String)> {
+    let problems = state
+        .db
+        .maintenance()
+        .integrity_check()
+        .await
+        .map_err(|e| {
+            error!("Failed to check database integrity: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !problems.is_empty() {
+        warn!("Database integrity check found {} problems", problems.len());
+    }
+
+    Ok(Json(DbCheckDto {
+        ok: problems.is_empty(),
+        problems,
+    }))
+}
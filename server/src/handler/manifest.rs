@@ -0,0 +1,102 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::header::CONTENT_DISPOSITION;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use shared::dtos::{ManifestDto, ManifestEntryDto};
+use shared::utc_millis::UtcMillis;
+use tracing::{error, info, instrument, warn};
+
+use super::sync::upload_path_for_wg;
+use super::{authorize_watch_group_access, client_id_for_log};
+use crate::AppState;
+use crate::file_event::FileEvent;
+use crate::write::hash_file;
+
+/// GET /api/watch-groups/{id}/manifest - paths, sizes and hashes of the current files, for
+/// seeding clients from a copied disk (`client seed`)
+pub async fn api_get_manifest(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let manifest = manifest(&state, wg_id).await?;
+    let disposition = format!(
+        "attachment; filename=\"manifest-{}.json\"",
+        manifest.watch_group_name.replace('"', "")
+    );
+    Ok(([(CONTENT_DISPOSITION, disposition)], Json(manifest)))
+}
+
+/// GET /sys/manifest/{wg_id} - the same for clients of the watch group (`client verify`)
+#[instrument(name = "manifest", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
+pub async fn get_manifest(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<ManifestDto>, (StatusCode, String)> {
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    manifest(&state, wg_id).await.map(Json)
+}
+
+async fn manifest(state: &AppState, wg_id: i64) -> Result<ManifestDto, (StatusCode, String)> {
+    let watch_group = state
+        .db
+        .server_watch_group()
+        .get_all_watch_groups()
+        .await
+        .map_err(|e| {
+            error!("Failed to load watch groups: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .into_iter()
+        .find(|wg| wg.id == wg_id)
+        .ok_or((StatusCode::NOT_FOUND, "Watch group not found".to_string()))?;
+
+    let events: Vec<FileEvent> = state
+        .history
+        .get_latest_events(wg_id)
+        .into_iter()
+        .filter(|e| e.event_type.is_change())
+        .collect();
+    // hashing reads every file - off the async workers
+    let files = tokio::task::spawn_blocking(move || manifest_entries(wg_id, events))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    info!(
+        "Exported manifest of watch group {} ({} files)",
+        wg_id,
+        files.len()
+    );
+
+    Ok(ManifestDto {
+        watch_group_id: wg_id,
+        watch_group_name: watch_group.name,
+        created: UtcMillis::now(),
+        files,
+    })
+}
+
+/// files missing on disk are left out - the scrub reports them
+fn manifest_entries(wg_id: i64, events: Vec<FileEvent>) -> Vec<ManifestEntryDto> {
+    let root = upload_path_for_wg(wg_id);
+    let mut entries: Vec<ManifestEntryDto> = events
+        .into_iter()
+        .filter_map(|event| {
+            let file = event.relative_path.resolve(&root);
+            match hash_file(&file) {
+                Ok(sha256) => Some(ManifestEntryDto {
+                    path: event.relative_path,
+                    size_in_bytes: event.size_in_bytes,
+                    sha256,
+                    utc_millis: event.utc_millis,
+                }),
+                Err(e) => {
+                    warn!("Left {:?} out of the manifest - {}", file, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    entries.sort_by_key(|e| e.path.to_serialized_string());
+    entries
+}
@@ -0,0 +1,77 @@
+use crate::AppState;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use shared::dtos::{MassChangeAlertDto, MassChangeGuardDto, MassChangeGuardSettingsDto};
+use tracing::{error, info};
+
+const MAX_PERCENT: u32 = 100;
+const MAX_WINDOW_MINUTES: u32 = 24 * 60;
+
+/// GET /api/watch-groups/{id}/mass-change-guard
+pub async fn api_get_mass_change_guard(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<MassChangeGuardSettingsDto>, (StatusCode, String)> {
+    let configured = state
+        .db
+        .server_watch_group()
+        .mass_change_guard(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load mass change guard of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Watch group not found".to_string()))?;
+    let effective = state.mass_changes.defaults().apply(&configured);
+    Ok(Json(MassChangeGuardSettingsDto {
+        configured,
+        percent: effective.percent,
+        window_minutes: effective.window_minutes,
+        min_files: effective.min_files,
+    }))
+}
+
+/// PUT /api/watch-groups/{id}/mass-change-guard
+pub async fn api_set_mass_change_guard(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(dto): Json<MassChangeGuardDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if dto.percent.is_some_and(|p| p > MAX_PERCENT) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("percent must be 0 to {MAX_PERCENT}"),
+        ));
+    }
+    if dto
+        .window_minutes
+        .is_some_and(|w| w == 0 || w > MAX_WINDOW_MINUTES)
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("window_minutes must be 1 to {MAX_WINDOW_MINUTES}"),
+        ));
+    }
+    let found = state
+        .db
+        .server_watch_group()
+        .set_mass_change_guard(id, &dto)
+        .await
+        .map_err(|e| {
+            error!("Failed to set mass change guard of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Set mass change guard of watch group {} to {:?}", id, dto);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()))
+    }
+}
+
+/// GET /api/admin/mass-changes - newest first
+pub async fn api_list_mass_changes(State(state): State<AppState>) -> Json<Vec<MassChangeAlertDto>> {
+    Json(state.mass_changes.alerts())
+}
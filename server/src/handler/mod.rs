@@ -1,29 +1,167 @@
 mod app;
+mod bench;
+mod bulk;
 mod client;
+mod client_detail;
 mod client_watch_group;
 mod config;
+mod dav;
+mod export;
+mod file_status;
+mod history;
+mod import;
 pub mod link;
+mod maintenance;
+mod manifest;
+mod mass_change;
+mod note;
+mod pending;
+mod pending_delete;
+mod preview;
 mod pwa;
+mod range;
+mod registration_template;
+mod replication;
+mod report;
+mod schedule;
+mod scrub;
+mod search;
 mod server_watch_group;
+mod share_link;
+mod signed;
+mod stats;
 mod sync;
+mod sync_blackout;
+mod thumbnail;
+mod transfer;
+mod ui_settings;
+mod upload;
+mod user;
+mod watch_group_detail;
+mod webhook;
+mod zip_download;
 
 pub use app::serve_embedded_app;
-pub use client::{api_delete_client, api_get_client, api_list_clients, api_update_client};
+pub use bench::{api_bench_download, api_bench_upload};
+pub use bulk::{ConfirmationTokens, api_prune, api_resync};
+pub(crate) use client::BUILT_IN_CLIENT_IDS;
+pub use client::{
+    api_claim_client, api_delete_client, api_get_client, api_list_clients, api_pause_client,
+    api_set_client_owner, api_update_client,
+};
+pub use client_detail::{api_get_client_activity, api_get_client_detail, api_resync_client};
 pub use client_watch_group::{
     api_create_client_watch_group, api_delete_client_watch_group, api_list_client_watch_groups,
-    api_update_client_watch_group,
+    api_pause_client_watch_group, api_update_client_watch_group,
 };
 pub use config::get_config;
+pub use dav::dav_handler;
+pub use export::{api_export_events, api_get_export_status, api_set_export_checkpoint};
+pub use file_status::{api_get_client_files, api_get_file_status, run_client_file_state_cleanup};
+pub use history::{api_get_diff, api_get_file_history};
+pub use import::api_import;
 pub use link::{delete_link, get_links, post_link, post_link_tag};
+pub use maintenance::{api_db_backup, api_db_check, api_download_backup, api_list_backups};
+pub use manifest::{api_get_manifest, get_manifest};
+pub use mass_change::{
+    api_get_mass_change_guard, api_list_mass_changes, api_set_mass_change_guard,
+};
+pub use note::{api_get_note, api_post_note};
+pub use pending::{PendingWork, api_get_client_pending};
+pub use pending_delete::{
+    api_cancel_pending_delete, api_list_pending_deletes, schedule_pending_deletes,
+};
+pub use preview::api_get_preview;
 pub use pwa::serve_embedded_pwa;
+pub use registration_template::{
+    api_create_registration_template, api_delete_registration_template,
+    api_list_registration_templates,
+};
+pub use replication::{
+    ReplicationStatus, api_get_replication_events, api_get_replication_status,
+    reject_writes_on_replica, schedule_replication,
+};
+pub use report::{api_get_growth, api_get_top_files};
+pub use schedule::{api_get_schedule, api_set_schedule};
+pub use scrub::{
+    ScrubReports, api_get_boot_report, api_get_scrub, api_run_scrub, check_on_boot, run_scrub,
+};
+pub use search::{api_search, rebuild_search_index};
 pub use server_watch_group::{
     api_create_watch_group, api_delete_watch_group, api_delete_watch_group_file,
     api_get_watch_group_files, api_list_watch_groups, api_serve_watch_group_file,
-    api_update_watch_group, api_upload_to_watch_group,
+    api_set_watch_group_durability, api_set_watch_group_owner, api_set_watch_group_priority,
+    api_set_watch_group_publish, api_set_watch_group_verify_writes, api_update_watch_group,
+    api_upload_to_watch_group,
+};
+pub use share_link::{
+    api_create_share_link, api_delete_share_link, api_list_share_links, inbox_form, inbox_upload,
+};
+pub use signed::{api_sign_download, signed_download, verify_signature};
+pub use stats::{
+    StatsCache, api_get_bandwidth_stats, api_get_watch_group_stats, get_bandwidth_usage,
+    post_bandwidth_usage,
+};
+pub(crate) use stats::{current_month, record_transfer};
+pub use sync::{
+    delete, download, download_snapshot, scan_disk, scan_warning, sync_handler,
+    upload_bulk_handler, upload_handler,
+};
+pub(crate) use sync::{
+    delete_file, durability, latest_change, open_download, store_upload, sync_instructions,
+    verify_writes,
 };
-pub use sync::{delete, download, scan_disk, sync_handler, upload_handler};
+pub use sync_blackout::{
+    api_create_sync_blackout, api_delete_sync_blackout, api_list_sync_blackouts,
+};
+pub use thumbnail::api_get_thumbnail;
+pub use transfer::{api_copy, api_move};
+pub use ui_settings::{api_get_ui_settings, api_set_ui_settings};
+pub use user::{
+    api_create_user, api_create_user_token, api_delete_user, api_list_users, api_set_user_role,
+    api_update_user,
+};
+pub use watch_group_detail::api_get_watch_group_detail;
+pub use webhook::{api_create_webhook, api_delete_webhook, api_list_webhooks};
+pub use zip_download::download_zip;
 
+use crate::AppState;
+use crate::auth::Account;
+use axum::http::header::CONTENT_LENGTH;
 use axum::http::{HeaderMap, StatusCode};
+use shared::endpoint::CLIENT_ID_HEADER_KEY;
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use sync_blackout::sync_blackout_until;
+use tracing::{error, warn};
+
+/// `?path=dir/file.txt` - never leaves the watch group (`.` and `..` are dropped)
+pub(crate) fn file_path_param(
+    params: &HashMap<String, String>,
+) -> Result<MatchablePath, (StatusCode, String)> {
+    let segments: Vec<String> = params
+        .get("path")
+        .map(|p| {
+            p.split('/')
+                .filter(|s| !matches!(*s, "" | "." | ".."))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    if segments.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Missing path parameter".to_string(),
+        ));
+    }
+    Ok(MatchablePath::new(segments))
+}
+
+/// path of a file in a watch group - never leaves it (`.` and `..` are dropped)
+pub(crate) fn parse_file_path(path: &str) -> Result<MatchablePath, (StatusCode, String)> {
+    shared::grpc::parse_path(path).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
 
 pub(crate) fn header_value_as_opt_string(headers: &HeaderMap, key: &str) -> Option<String> {
     headers
@@ -32,6 +170,20 @@ pub(crate) fn header_value_as_opt_string(headers: &HeaderMap, key: &str) -> Opti
         .map(String::from)
 }
 
+/// announced request body size (0 if unknown)
+pub(crate) fn content_length(headers: &HeaderMap) -> u64 {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
+
+/// client id for log records ("-" if the header is missing)
+pub(crate) fn client_id_for_log(headers: &HeaderMap) -> String {
+    header_value_as_opt_string(headers, CLIENT_ID_HEADER_KEY).unwrap_or_else(|| "-".to_string())
+}
+
 pub(crate) fn header_value_as_string<'header>(
     headers: &'header HeaderMap,
     key: &str,
@@ -41,3 +193,143 @@ pub(crate) fn header_value_as_string<'header>(
         .and_then(|v| v.to_str().ok())
         .ok_or((StatusCode::BAD_REQUEST, format!("Missing {key} header")))
 }
+
+/// for endpoints of clients (which have no api token) - any registered one passes, returns its id
+pub(crate) async fn authorize_client<'header>(
+    state: &AppState,
+    headers: &'header HeaderMap,
+) -> Result<&'header str, (StatusCode, String)> {
+    let client_id = header_value_as_string(headers, CLIENT_ID_HEADER_KEY)?;
+    match state.db.client().get_client_by_id(client_id).await {
+        Ok(Some(_)) => Ok(client_id),
+        Ok(None) => {
+            warn!("Unknown client {client_id} denied");
+            Err((StatusCode::FORBIDDEN, format!("Unknown client {client_id}")))
+        }
+        Err(e) => {
+            error!("Failed to load client {}: {}", client_id, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
+
+/// sync endpoints are only open to clients that are assigned to the watch group
+/// and whose owner matches the owner of the watch group (if it has one)
+/// users only see the watch groups they own (or nobody owns) - the bootstrap account sees all
+pub(crate) fn can_see(account: &Account, owner_id: Option<i64>) -> bool {
+    match account {
+        Account::Bootstrap => true,
+        Account::User(id) => owner_id.is_none_or(|owner| owner == *id),
+    }
+}
+
+/// 403 unless the account [`can_see`] the watch group - unknown ones are left to the handler
+pub(crate) async fn authorize_account_access(
+    state: &AppState,
+    account: &Account,
+    wg_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    if *account == Account::Bootstrap {
+        return Ok(());
+    }
+    let owner = state
+        .db
+        .server_watch_group()
+        .get_owner(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get owner of watch group {}: {}", wg_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    match owner {
+        Some(owner_id) if !can_see(account, owner_id) => {
+            warn!("User {} denied access to watch group {wg_id}", account.id());
+            Err((
+                StatusCode::FORBIDDEN,
+                format!("No access to watch group {wg_id}"),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(crate) async fn authorize_watch_group_access(
+    state: &AppState,
+    headers: &HeaderMap,
+    wg_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    let client_id = header_value_as_string(headers, CLIENT_ID_HEADER_KEY)?;
+    let allowed = state
+        .db
+        .client_watch_group()
+        .has_access(client_id, wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check access to {}: {}", wg_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if allowed {
+        Ok(())
+    } else {
+        warn!("Client {client_id} denied access to watch group {wg_id}");
+        Err((
+            StatusCode::FORBIDDEN,
+            format!("No access to watch group {wg_id}"),
+        ))
+    }
+}
+
+/// true if an admin paused the client (or only its assignment to the watch group) or it's in
+/// one of its sync blackouts
+pub(crate) async fn is_sync_paused(
+    state: &AppState,
+    headers: &HeaderMap,
+    wg_id: i64,
+) -> Result<bool, (StatusCode, String)> {
+    let client_id = header_value_as_string(headers, CLIENT_ID_HEADER_KEY)?;
+    let db_error = |e: sqlx::Error| {
+        error!("Failed to check pause state of {}: {}", wg_id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    if state
+        .db
+        .client_watch_group()
+        .is_paused(client_id, wg_id)
+        .await
+        .map_err(db_error)?
+    {
+        return Ok(true);
+    }
+    Ok(sync_blackout_until(state, client_id)
+        .await
+        .map_err(db_error)?
+        .is_some())
+}
+
+/// end of the sync blackout the client is in - None outside of them (or if an admin paused it)
+pub(crate) async fn sync_paused_until(state: &AppState, headers: &HeaderMap) -> Option<UtcMillis> {
+    let client_id = header_value_as_string(headers, CLIENT_ID_HEADER_KEY).ok()?;
+    sync_blackout_until(state, client_id)
+        .await
+        .unwrap_or_else(|e| {
+            error!("Failed to check sync blackouts: {}", e);
+            None
+        })
+}
+
+/// paused clients may not change the watch group
+pub(crate) async fn ensure_not_paused(
+    state: &AppState,
+    headers: &HeaderMap,
+    wg_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    if is_sync_paused(state, headers, wg_id).await? {
+        Err((
+            StatusCode::LOCKED,
+            format!("Syncing of watch group {wg_id} is paused"),
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use shared::dtos::NoteDto;
+use shared::matchable_path::MatchablePath;
+use std::collections::HashMap;
+use std::fs;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::server_watch_group::{record_pwa_change, sanitize_filename};
+use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH, versions};
+
+/// used when neither the query nor the body names a file
+const NOTE_FILE_NAME: &str = "notes.txt";
+
+/// GET /api/note/{wg_id}?name=notes.txt - the current text (empty if the note doesn't exist yet)
+pub async fn api_get_note(
+    Path(wg_id): Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<String, (StatusCode, String)> {
+    let name = sanitize_filename(params.get("name").map_or(NOTE_FILE_NAME, String::as_str))?;
+    match fs::read(UPLOAD_PATH.join(wg_id.to_string()).join(name)) {
+        Ok(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+/// POST /api/note/{wg_id} - writes the note like an upload, so clients pick it up on their
+/// next sync
+pub async fn api_post_note(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+    Json(note): Json<NoteDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group existence: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Watch group {wg_id} not found"),
+        ));
+    }
+
+    let name = sanitize_filename(note.name.as_deref().unwrap_or(NOTE_FILE_NAME))?;
+    let internal = |e: std::io::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let target_dir = UPLOAD_PATH.join(wg_id.to_string());
+    fs::create_dir_all(&target_dir).map_err(internal)?;
+    let target = target_dir.join(&name);
+
+    let existing = match note.append {
+        true => fs::read_to_string(&target).ok(),
+        false => None,
+    };
+    let content = note_content(existing, &note.text);
+    let relative_path = MatchablePath::from(name.as_str());
+    versions::keep_current(state.history.as_ref(), wg_id, &relative_path, &target);
+    // written aside first - syncing clients never download half a note
+    let tmp = UPLOAD_TMP_PATH.join(format!("{}_{}", Uuid::new_v4(), name));
+    fs::write(&tmp, &content).map_err(internal)?;
+    if let Err(e) = fs::rename(&tmp, &target) {
+        let _ = fs::remove_file(&tmp);
+        return Err(internal(e));
+    }
+
+    record_pwa_change(&state, wg_id, relative_path, content.len() as u64, &target).await;
+    info!("PWA wrote note '{}' in watch group {wg_id}", name);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `text` on a line of its own below `existing` (or just `text` for a new note)
+fn note_content(existing: Option<String>, text: &str) -> String {
+    match existing {
+        Some(mut content) if !content.is_empty() => {
+            if !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(text);
+            content
+        }
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_replace_or_append_note() {
+        assert_eq!("new", note_content(None, "new"));
+        assert_eq!("new", note_content(Some(String::new()), "new"));
+        assert_eq!("old\nnew", note_content(Some("old".to_string()), "new"));
+        assert_eq!("old\nnew", note_content(Some("old\n".to_string()), "new"));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::AppState;
+use crate::file_event::FileEvent;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use shared::dtos::{
+    FileDescription, PendingItemDto, PendingKind, PendingWatchGroupDto, PendingWorkDto,
+};
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::SyncInstruction;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// smaller uploads are dominated by latency - they'd make the estimate far too pessimistic
+const MIN_MEASURED_BYTES: u64 = 1024 * 1024;
+/// weight of the latest measurement
+const RATE_SMOOTHING: f64 = 0.3;
+
+/// the server's picture of the work each client still has to do - kept in memory only, a
+/// restart forgets it until the clients synced again
+#[derive(Default)]
+pub struct PendingWork {
+    clients: Mutex<HashMap<String, ClientPending>>,
+}
+
+#[derive(Default)]
+struct ClientPending {
+    watch_groups: HashMap<i64, PendingWatchGroupDto>,
+    /// bytes per second - moving average over the client's uploads
+    rate: Option<f64>,
+}
+
+impl PendingWork {
+    /// replaces what was pending in the watch group with the instructions of this sync
+    pub(crate) fn record(
+        &self,
+        client_id: &str,
+        wg_id: i64,
+        instructions: &[SyncInstruction],
+        target: &[FileEvent],
+        client_sync_state: &[FileDescription],
+    ) {
+        let mut clients = self.clients.lock().unwrap();
+        let client = clients.entry(client_id.to_string()).or_default();
+        if instructions.is_empty() {
+            client.watch_groups.remove(&wg_id);
+            return;
+        }
+        let server_sizes: HashMap<&MatchablePath, u64> = target
+            .iter()
+            .map(|e| (&e.relative_path, e.size_in_bytes))
+            .collect();
+        let client_sizes: HashMap<&MatchablePath, u64> = client_sync_state
+            .iter()
+            .map(|d| (&d.relative_path, d.size_in_bytes))
+            .collect();
+        let items = instructions
+            .iter()
+            .map(|instruction| {
+                let (kind, sizes) = match instruction {
+                    SyncInstruction::Upload(_) => (PendingKind::Upload, &client_sizes),
+                    SyncInstruction::Download(_) => (PendingKind::Download, &server_sizes),
+                    SyncInstruction::Delete(_) => (PendingKind::Delete, &client_sizes),
+                };
+                PendingItemDto {
+                    kind,
+                    relative_path: instruction.path().to_serialized_string(),
+                    size_in_bytes: sizes.get(instruction.path()).copied().unwrap_or(0),
+                }
+            })
+            .collect();
+        client.watch_groups.insert(
+            wg_id,
+            PendingWatchGroupDto {
+                watch_group_id: wg_id,
+                synced_at: UtcMillis::now(),
+                items,
+            },
+        );
+    }
+
+    /// an up- / download of `path` went through - deletes wait for the next sync
+    pub(crate) fn complete(
+        &self,
+        client_id: &str,
+        wg_id: i64,
+        kind: PendingKind,
+        path: &MatchablePath,
+    ) {
+        let path = path.to_serialized_string();
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(pending) = clients
+            .get_mut(client_id)
+            .and_then(|client| client.watch_groups.get_mut(&wg_id))
+        {
+            pending
+                .items
+                .retain(|item| item.kind != kind || item.relative_path != path);
+        }
+    }
+
+    pub(crate) fn observe_upload(&self, client_id: &str, bytes: u64, elapsed: Duration) {
+        if bytes < MIN_MEASURED_BYTES || elapsed.is_zero() {
+            return;
+        }
+        let measured = bytes as f64 / elapsed.as_secs_f64();
+        let mut clients = self.clients.lock().unwrap();
+        let client = clients.entry(client_id.to_string()).or_default();
+        client.rate = Some(match client.rate {
+            Some(rate) => rate + RATE_SMOOTHING * (measured - rate),
+            None => measured,
+        });
+    }
+
+    pub(crate) fn forget(&self, client_id: &str) {
+        self.clients.lock().unwrap().remove(client_id);
+    }
+
+    fn to_dto(&self, client_id: &str) -> PendingWorkDto {
+        let clients = self.clients.lock().unwrap();
+        let Some(client) = clients.get(client_id) else {
+            return PendingWorkDto::default();
+        };
+        let mut watch_groups: Vec<PendingWatchGroupDto> = client
+            .watch_groups
+            .values()
+            .filter(|wg| !wg.items.is_empty())
+            .cloned()
+            .collect();
+        watch_groups.sort_by_key(|wg| wg.watch_group_id);
+        let pending_bytes = watch_groups
+            .iter()
+            .flat_map(|wg| &wg.items)
+            .filter(|item| item.kind != PendingKind::Delete)
+            .map(|item| item.size_in_bytes)
+            .sum();
+        PendingWorkDto {
+            watch_groups,
+            pending_bytes,
+            bytes_per_second: client.rate.map(|rate| rate as u64),
+            estimated_seconds: client
+                .rate
+                .map(|rate| (pending_bytes as f64 / rate).ceil() as u64),
+        }
+    }
+}
+
+/// GET /api/clients/{id}/pending
+pub async fn api_get_client_pending(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Json<PendingWorkDto>, (StatusCode, String)> {
+    Ok(Json(state.pending.to_dto(&id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_event::FileEventType;
+    use uuid::Uuid;
+
+    fn file(path: &str, size: u64) -> FileDescription {
+        FileDescription {
+            file_name: path.to_string(),
+            relative_path: MatchablePath::from(path),
+            size_in_bytes: size,
+            file_type: String::new(),
+            last_updated_utc_millis: UtcMillis::from(1),
+        }
+    }
+
+    #[test]
+    fn should_track_pending_work_until_done() {
+        let pending = PendingWork::default();
+        let target = [FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(1),
+            MatchablePath::from("server.txt"),
+            3 * 1024 * 1024,
+            FileEventType::ChangeEvent,
+            None,
+            1,
+        )];
+        let client = [file("client.txt", 1024 * 1024), file("stale.txt", 7)];
+        let instructions = [
+            SyncInstruction::Download(MatchablePath::from("server.txt")),
+            SyncInstruction::Upload(MatchablePath::from("client.txt")),
+            SyncInstruction::Delete(MatchablePath::from("stale.txt")),
+        ];
+
+        pending.record("c1", 1, &instructions, &target, &client);
+        let dto = pending.to_dto("c1");
+        assert_eq!(3, dto.watch_groups[0].items.len());
+        assert_eq!(4 * 1024 * 1024, dto.pending_bytes);
+        assert_eq!(None, dto.estimated_seconds);
+
+        pending.observe_upload("c1", 2 * 1024 * 1024, Duration::from_secs(1));
+        let path = MatchablePath::from("server.txt");
+        pending.complete("c1", 1, PendingKind::Upload, &path);
+        assert_eq!(3, pending.to_dto("c1").watch_groups[0].items.len());
+        pending.complete("c1", 1, PendingKind::Download, &path);
+        let dto = pending.to_dto("c1");
+        assert_eq!(1024 * 1024, dto.pending_bytes);
+        assert_eq!(Some(1), dto.estimated_seconds);
+
+        pending.record("c1", 1, &[], &target, &client);
+        assert!(pending.to_dto("c1").watch_groups.is_empty());
+        assert!(pending.to_dto("unknown").watch_groups.is_empty());
+    }
+}
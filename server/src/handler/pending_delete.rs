@@ -0,0 +1,180 @@
+use crate::file_event::{FileEvent, FileEventType};
+use crate::{AppState, guard, supervisor};
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use shared::dtos::PendingDeleteDto;
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashSet;
+use std::env;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::sync::remove_now;
+
+/// `DELETE_GRACE_MINUTES` (default 0: deletes are carried out at once) - deletes of clients are
+/// held back for that long: the file stays on the server, hidden from sync, and admins can cancel
+/// the delete in the meantime
+static DELETE_GRACE: LazyLock<Duration> = LazyLock::new(|| {
+    let minutes = env::var("DELETE_GRACE_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+    Duration::from_secs(minutes * 60)
+});
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(crate) fn delete_grace() -> Option<Duration> {
+    Some(*DELETE_GRACE).filter(|grace| !grace.is_zero())
+}
+
+/// first phase of a delete - records it as pending, the file is removed by
+/// [`schedule_pending_deletes`] once the grace period is over
+pub(crate) async fn defer_delete(
+    state: &AppState,
+    wg_id: i64,
+    path: MatchablePath,
+    client_host: Option<String>,
+    client_id: &str,
+    grace: Duration,
+) -> Result<(), (StatusCode, String)> {
+    let now = UtcMillis::now();
+    let remove_at = UtcMillis::from(now.as_u64() + grace.as_millis() as u64);
+    let relative_path = path.to_serialized_string();
+    state
+        .db
+        .pending_delete()
+        .insert(
+            wg_id,
+            &relative_path,
+            client_id,
+            client_host.as_deref(),
+            now.clone(),
+            remove_at.clone(),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to record pending delete: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    // the guard judges the delete when the client asks for it - not when it's carried out
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        now,
+        path,
+        0,
+        FileEventType::DeleteEvent,
+        client_host,
+        wg_id,
+    );
+    guard::observe(state, &event, client_id);
+    info!(
+        path = %relative_path,
+        event_type = "delete",
+        "Deleting {} at {} - pending until then",
+        relative_path,
+        remove_at
+    );
+    Ok(())
+}
+
+/// paths of the watch group with a pending delete - no client gets instructions for them
+pub(crate) async fn hidden_paths(state: &AppState, wg_id: i64) -> HashSet<MatchablePath> {
+    match state.db.pending_delete().paths(wg_id).await {
+        Ok(paths) => paths
+            .iter()
+            .map(|p| MatchablePath::from(p.as_str()))
+            .collect(),
+        Err(e) => {
+            error!("Failed to load pending deletes: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+/// second phase - removes the files of the deletes whose grace period ended
+///
+/// runs even without a grace period, so deletes held back before it was turned off are done too
+pub async fn schedule_pending_deletes(state: AppState) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        supervisor::tick(&mut interval).await;
+        let due = match state.db.pending_delete().due(UtcMillis::now()).await {
+            Ok(due) => due,
+            Err(e) => {
+                error!("Failed to load pending deletes: {}", e);
+                supervisor::failed(&e);
+                continue;
+            }
+        };
+        for pending in due {
+            // dropped first - an upload of the path meanwhile would have dropped it already
+            match state.db.pending_delete().remove(pending.id).await {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    error!("Failed to drop pending delete: {}", e);
+                    continue;
+                }
+            }
+            let path = MatchablePath::from(pending.relative_path.as_str());
+            match remove_now(
+                &state,
+                pending.watch_group_id,
+                path,
+                pending.client_host,
+                &pending.client_id,
+            )
+            .await
+            {
+                Ok(_) => {}
+                // gone already - recorded as deleted anyway
+                Err((StatusCode::OK, _)) => {}
+                Err((_, e)) => warn!(
+                    "Failed to carry out pending delete of {}: {}",
+                    pending.relative_path, e
+                ),
+            }
+        }
+    }
+}
+
+/// GET /api/admin/pending-deletes - the next one due first
+pub async fn api_list_pending_deletes(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PendingDeleteDto>>, (StatusCode, String)> {
+    state
+        .db
+        .pending_delete()
+        .list()
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to list pending deletes: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+
+/// DELETE /api/admin/pending-deletes/{id} - keeps the file, the deleting client downloads it again
+pub async fn api_cancel_pending_delete(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state.db.pending_delete().remove(id).await.map_err(|e| {
+        error!("Failed to cancel pending delete: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    if found {
+        info!("Cancelled pending delete {}", id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            "Pending delete not found".to_string(),
+        ))
+    }
+}
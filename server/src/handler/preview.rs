@@ -0,0 +1,165 @@
+use axum::Json;
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use shared::dtos::FilePreviewDto;
+use std::collections::HashMap;
+use tokio::io::AsyncReadExt;
+
+use super::file_path_param;
+use super::sync::upload_path_for_wg;
+
+/// longer files are cut - the preview is meant for a glance, not for reading whole logs
+const PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// GET /api/preview/{wg_id}?path=dir/notes.md - the first [`PREVIEW_BYTES`] of a text file
+pub async fn api_get_preview(
+    Path(wg_id): Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<FilePreviewDto>, (StatusCode, String)> {
+    let path = file_path_param(&params)?;
+    let file = path.resolve(&upload_path_for_wg(wg_id));
+    let mut handle = tokio::fs::File::open(&file)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("File not found: {e}")))?;
+    let size_in_bytes = handle
+        .metadata()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .len();
+
+    let mut bytes = Vec::new();
+    (&mut handle)
+        .take(PREVIEW_BYTES)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let (content, encoding) = decode(&bytes).ok_or((
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        "Not a text file".to_string(),
+    ))?;
+    Ok(Json(FilePreviewDto {
+        content,
+        encoding: encoding.to_string(),
+        language: language_hint(&path.tail()).map(str::to_string),
+        truncated: size_in_bytes > bytes.len() as u64,
+        size_in_bytes,
+    }))
+}
+
+/// text of `bytes` and the name of its encoding - `None` for binaries
+///
+/// byte order marks win, then utf-8 (tolerating a character cut off at the end of the
+/// preview), everything else is read as latin-1
+fn decode(bytes: &[u8]) -> Option<(String, &'static str)> {
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return Some((String::from_utf8_lossy(rest).into_owned(), "utf-8"));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return Some((decode_utf16(rest, u16::from_le_bytes), "utf-16le"));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return Some((decode_utf16(rest, u16::from_be_bytes), "utf-16be"));
+    }
+    if looks_binary(bytes) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some((text.to_string(), "utf-8")),
+        Err(e) if e.error_len().is_none() => Some((
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned(),
+            "utf-8",
+        )),
+        Err(_) => Some((bytes.iter().map(|b| *b as char).collect(), "iso-8859-1")),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| to_unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// any NUL byte or more than 1% control characters other than whitespace
+fn looks_binary(bytes: &[u8]) -> bool {
+    let controls = bytes
+        .iter()
+        .filter(|b| **b < 0x20 && !matches!(**b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    bytes.contains(&0) || controls * 100 > bytes.len()
+}
+
+fn language_hint(file_name: &str) -> Option<&'static str> {
+    let ext = file_name.rsplit_once('.')?.1.to_lowercase();
+    Some(match ext.as_str() {
+        "md" | "markdown" => "markdown",
+        "csv" => "csv",
+        "tsv" => "tsv",
+        "rs" => "rust",
+        "toml" => "toml",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "sh" | "bash" => "shell",
+        "py" => "python",
+        "js" | "mjs" => "javascript",
+        "ts" => "typescript",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "sql" => "sql",
+        "xml" => "xml",
+        "log" => "log",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_detect_encodings() {
+        assert_eq!(
+            Some(("grüße".to_string(), "utf-8")),
+            decode("grüße".as_bytes())
+        );
+        assert_eq!(
+            Some(("bom".to_string(), "utf-8")),
+            decode(b"\xEF\xBB\xBFbom")
+        );
+        assert_eq!(
+            Some(("hi".to_string(), "utf-16le")),
+            decode(b"\xFF\xFEh\0i\0")
+        );
+        assert_eq!(
+            Some(("hi".to_string(), "utf-16be")),
+            decode(b"\xFE\xFF\0h\0i")
+        );
+        assert_eq!(
+            Some(("grüße".to_string(), "iso-8859-1")),
+            decode(b"gr\xFC\xDFe")
+        );
+    }
+
+    #[test]
+    fn should_tolerate_character_cut_by_preview_limit() {
+        let cut = &"aü".as_bytes()[..2];
+        assert_eq!(Some(("a".to_string(), "utf-8")), decode(cut));
+    }
+
+    #[test]
+    fn should_reject_binaries() {
+        assert_eq!(None, decode(&[0x89, b'P', b'N', b'G', 0x00, 0x01]));
+        assert_eq!(None, decode(&[0x01, 0x02, b'a']));
+    }
+
+    #[test]
+    fn should_hint_language_by_extension() {
+        assert_eq!(Some("markdown"), language_hint("README.MD"));
+        assert_eq!(Some("csv"), language_hint("export.csv"));
+        assert_eq!(None, language_hint("notes.txt"));
+        assert_eq!(None, language_hint("Makefile"));
+    }
+}
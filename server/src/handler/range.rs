@@ -0,0 +1,173 @@
+use crate::write;
+use axum::http::header::{
+    ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, RANGE,
+};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::fs::Metadata;
+use std::time::UNIX_EPOCH;
+use tokio::fs::File;
+use tracing::{error, info};
+
+/// what to serve of a file - see [`requested_range`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteRange {
+    Full,
+    Partial {
+        start: u64,
+        len: u64,
+    },
+    /// 416 - the range starts behind the end of the file
+    Unsatisfiable,
+}
+
+/// the file as attachment - or the range of it the request asks for (206 / 416) - plus the
+/// number of bytes served
+pub(crate) async fn serve_file(
+    file: File,
+    file_name: &str,
+    headers: &HeaderMap,
+) -> Result<(Response, u64), (StatusCode, String)> {
+    let metadata = file.metadata().await.map_err(|e| {
+        error!("Failed to read metadata of download: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    let size = metadata.len();
+    let etag = etag(&metadata);
+    let (status, start, len) = match requested_range(headers, size, &etag) {
+        ByteRange::Full => (StatusCode::OK, 0, size),
+        ByteRange::Partial { start, len } => (StatusCode::PARTIAL_CONTENT, start, len),
+        ByteRange::Unsatisfiable => {
+            let headers = [(CONTENT_RANGE, format!("bytes */{size}"))];
+            return Ok((
+                (StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response(),
+                0,
+            ));
+        }
+    };
+    let body = if status == StatusCode::PARTIAL_CONTENT {
+        info!(start, bytes = len, "Resuming download of {}", file_name);
+        write::file_range_body(file, start, len)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        write::file_body(file)
+    };
+
+    let mut response = (
+        status,
+        [
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+            (ACCEPT_RANGES, "bytes".to_string()),
+            (ETAG, etag),
+            (CONTENT_LENGTH, len.to_string()),
+        ],
+        body,
+    )
+        .into_response();
+    if status == StatusCode::PARTIAL_CONTENT {
+        let content_range = format!("bytes {}-{}/{}", start, start + len - 1, size);
+        if let Ok(value) = HeaderValue::from_str(&content_range) {
+            response.headers_mut().insert(CONTENT_RANGE, value);
+        }
+    }
+    Ok((response, len))
+}
+
+/// changes with the size or mtime of the file - a resumed download must not mix two versions
+pub(crate) fn etag(metadata: &Metadata) -> String {
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |mtime| mtime.as_nanos());
+    format!("\"{:x}-{:x}\"", metadata.len(), mtime)
+}
+
+/// the single `bytes=` range of the request - the full file if there's none, it's malformed,
+/// asks for several ranges or `If-Range` names another version
+pub(crate) fn requested_range(headers: &HeaderMap, size: u64, etag: &str) -> ByteRange {
+    let Some(range) = headers.get(RANGE).and_then(|v| v.to_str().ok()) else {
+        return ByteRange::Full;
+    };
+    if headers
+        .get(IF_RANGE)
+        .is_some_and(|if_range| if_range.as_bytes() != etag.as_bytes())
+    {
+        return ByteRange::Full;
+    }
+    parse_range(range, size).unwrap_or(ByteRange::Full)
+}
+
+/// `bytes=10-`, `bytes=10-19` or `bytes=-10` (the last 10) - None if malformed
+fn parse_range(range: &str, size: u64) -> Option<ByteRange> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+    let (start, end) = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return Some(ByteRange::Unsatisfiable);
+        }
+        (size.saturating_sub(suffix), size)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = match end {
+            "" => size,
+            end => end.parse::<u64>().ok()?.checked_add(1)?.min(size),
+        };
+        if end <= start && start < size {
+            // e.g. bytes=20-10
+            return None;
+        }
+        (start, end)
+    };
+    if start >= size {
+        return Some(ByteRange::Unsatisfiable);
+    }
+    Some(ByteRange::Partial {
+        start,
+        len: end - start,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn should_parse_single_byte_ranges() {
+        let partial = |start, len| Some(ByteRange::Partial { start, len });
+        assert_eq!(partial(10, 90), parse_range("bytes=10-", 100));
+        assert_eq!(partial(10, 10), parse_range("bytes=10-19", 100));
+        assert_eq!(partial(90, 10), parse_range("bytes=90-500", 100));
+        assert_eq!(partial(80, 20), parse_range("bytes=-20", 100));
+        assert_eq!(partial(0, 100), parse_range("bytes=-500", 100));
+        assert_eq!(
+            Some(ByteRange::Unsatisfiable),
+            parse_range("bytes=100-", 100)
+        );
+        assert_eq!(Some(ByteRange::Unsatisfiable), parse_range("bytes=-0", 100));
+        assert_eq!(Some(ByteRange::Unsatisfiable), parse_range("bytes=0-", 0));
+        assert_eq!(None, parse_range("bytes=20-10", 100));
+        assert_eq!(None, parse_range("bytes=0-1,5-6", 100));
+        assert_eq!(None, parse_range("items=0-1", 100));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RANGE, HeaderValue::from_static("bytes=10-"));
+        assert_eq!(
+            partial(10, 90).unwrap(),
+            requested_range(&headers, 100, "\"a\"")
+        );
+        // the file changed since the first part was downloaded
+        headers.insert(IF_RANGE, HeaderValue::from_static("\"b\""));
+        assert_eq!(ByteRange::Full, requested_range(&headers, 100, "\"a\""));
+    }
+}
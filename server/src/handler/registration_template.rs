@@ -0,0 +1,84 @@
+use crate::AppState;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use shared::dtos::{RegistrationTemplateCreateDto, RegistrationTemplateDto};
+use tracing::{error, info};
+
+/// GET /api/registration-templates
+pub async fn api_list_registration_templates(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<RegistrationTemplateDto>>, (StatusCode, String)> {
+    let templates = state.db.registration_template().list().await.map_err(|e| {
+        error!("Failed to list registration templates: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    Ok(Json(templates))
+}
+
+/// POST /api/registration-templates
+pub async fn api_create_registration_template(
+    State(state): State<AppState>,
+    Json(dto): Json<RegistrationTemplateCreateDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if dto.host_pattern.trim().is_empty() || dto.path_to_monitor.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Host pattern and path are required".to_string(),
+        ));
+    }
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(dto.server_watch_group_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()));
+    }
+
+    let id = state
+        .db
+        .registration_template()
+        .create(&dto)
+        .await
+        .map_err(|e| {
+            error!("Failed to create registration template: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    info!(
+        "Created registration template {} ('{}' → watch group {})",
+        id, dto.host_pattern, dto.server_watch_group_id
+    );
+    Ok(StatusCode::CREATED)
+}
+
+/// DELETE /api/registration-templates/{id}
+pub async fn api_delete_registration_template(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state
+        .db
+        .registration_template()
+        .delete(id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete registration template: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Deleted registration template {}", id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            "Registration template not found".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,334 @@
+use crate::db::REPLICATION_CLIENT_ID;
+use crate::file_event::{FileEvent, FileEventType};
+use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH, publish, supervisor, webhook};
+use axum::Json;
+use axum::extract::{Query, Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use shared::dtos::{
+    ReplicationEventDto, ReplicationEventsDto, ReplicationRole, ReplicationStatusDto,
+};
+use shared::endpoint::ServerEndpoint;
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use super::search::{index_file, unindex_file};
+
+/// REPLICATE_FROM=https://primary:3000 turns the server into a read-only replica of that server
+pub(crate) static PRIMARY_URL: LazyLock<Option<String>> = LazyLock::new(|| {
+    env::var("REPLICATE_FROM")
+        .ok()
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty())
+});
+
+/// events per request to the primary
+const DEFAULT_LIMIT: u32 = 500;
+const MAX_LIMIT: u32 = 5000;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// progress of the replica - the cursor itself is in the DB
+#[derive(Default)]
+pub struct ReplicationStatus {
+    inner: Mutex<Progress>,
+}
+
+#[derive(Default)]
+struct Progress {
+    primary_seq: Option<i64>,
+    last_pull_at: Option<UtcMillis>,
+    last_error: Option<String>,
+    files_pulled: u64,
+}
+
+/// GET /api/replication/events?after=<seq>&limit=<n> - every recorded event, oldest first
+pub async fn api_get_replication_events(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<ReplicationEventsDto>, (StatusCode, String)> {
+    let after = params
+        .get("after")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+    let limit = params
+        .get("limit")
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_LIMIT)
+        .clamp(1, MAX_LIMIT);
+    let replication = state.db.replication();
+    // read first - an event recorded in between shows up with the next pull
+    let latest_seq = replication.latest_seq().await.map_err(internal)?;
+    let events = replication
+        .events_after(after, limit)
+        .await
+        .map_err(internal)?;
+    Ok(Json(ReplicationEventsDto { latest_seq, events }))
+}
+
+/// GET /api/replication
+pub async fn api_get_replication_status(
+    State(state): State<AppState>,
+) -> Result<Json<ReplicationStatusDto>, (StatusCode, String)> {
+    let replication = state.db.replication();
+    let Some(primary_url) = PRIMARY_URL.clone() else {
+        return Ok(Json(ReplicationStatusDto {
+            role: ReplicationRole::Primary,
+            primary_url: None,
+            applied_seq: replication.latest_seq().await.map_err(internal)?,
+            primary_seq: None,
+            last_pull_at: None,
+            last_error: None,
+            files_pulled: 0,
+        }));
+    };
+    let applied_seq = replication.cursor(&primary_url).await.map_err(internal)?;
+    let progress = state.replication.inner.lock().unwrap();
+    Ok(Json(ReplicationStatusDto {
+        role: ReplicationRole::Replica,
+        primary_url: Some(primary_url),
+        applied_seq,
+        primary_seq: progress.primary_seq,
+        last_pull_at: progress.last_pull_at.clone(),
+        last_error: progress.last_error.clone(),
+        files_pulled: progress.files_pulled,
+    }))
+}
+
+/// replicas only take changes from their primary - everything but reads is refused
+pub async fn reject_writes_on_replica(req: Request, next: Next) -> Response {
+    let Some(primary_url) = PRIMARY_URL.as_deref() else {
+        return next.run(req).await;
+    };
+    let read = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        || req.method().as_str() == "PROPFIND";
+    if read {
+        return next.run(req).await;
+    }
+    debug!("Refused {} {} on replica", req.method(), req.uri().path());
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        format!("Read-only replica of {primary_url} - write to the primary instead"),
+    )
+        .into_response()
+}
+
+/// pulls the event stream of the primary every REPLICATION_INTERVAL_SECS (default 10) - with
+/// REPLICATION_TOKEN as api token if the primary has an ADMIN_TOKEN
+pub async fn schedule_replication(state: AppState) {
+    let Some(primary_url) = PRIMARY_URL.as_deref() else {
+        return;
+    };
+    let secs = env::var("REPLICATION_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(10)
+        .max(1);
+    let token = env::var("REPLICATION_TOKEN").ok().filter(|t| !t.is_empty());
+    let http = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .expect("Should be able to build http client");
+    let primary = Primary {
+        url: primary_url,
+        token: token.as_deref(),
+        http: &http,
+    };
+    info!("Replicating {} every {}s", primary_url, secs);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(secs));
+    loop {
+        supervisor::tick(&mut interval).await;
+        let result = pull(&state, &primary).await;
+        let mut progress = state.replication.inner.lock().unwrap();
+        progress.last_pull_at = Some(UtcMillis::now());
+        match result {
+            Ok(0) => progress.last_error = None,
+            Ok(applied) => {
+                info!("Applied {} events of {}", applied, primary_url);
+                progress.last_error = None;
+            }
+            Err(e) => {
+                // keeps retrying - the cursor only moves past applied events
+                if progress.last_error.as_ref() != Some(&e) {
+                    warn!("Replication of {} failed - {}", primary_url, e);
+                }
+                supervisor::failed(&e);
+                progress.last_error = Some(e);
+            }
+        }
+        if progress.last_error.is_none() {
+            supervisor::succeeded();
+        }
+    }
+}
+
+struct Primary<'a> {
+    url: &'a str,
+    token: Option<&'a str>,
+    http: &'a reqwest::Client,
+}
+
+impl Primary<'_> {
+    fn get(&self, endpoint: ServerEndpoint, params: &[(&str, &str)]) -> reqwest::RequestBuilder {
+        let request = self.http.get(endpoint.to_uri_with(self.url, params));
+        match self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+/// applies every event after the cursor - returns how many
+async fn pull(state: &AppState, primary: &Primary<'_>) -> Result<u64, String> {
+    let replication = state.db.replication();
+    let mut cursor = replication
+        .cursor(primary.url)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut applied = 0;
+    loop {
+        let batch: ReplicationEventsDto = primary
+            .get(ServerEndpoint::ApiReplicationEvents, &[])
+            .query(&[
+                ("after", cursor.to_string()),
+                ("limit", DEFAULT_LIMIT.to_string()),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Fetching events failed - {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("Fetching events failed - {e}"))?;
+        state.replication.inner.lock().unwrap().primary_seq = Some(batch.latest_seq);
+
+        let complete = batch.events.len() < DEFAULT_LIMIT as usize;
+        for event in batch.events {
+            let seq = event.seq;
+            apply(state, primary, event).await?;
+            replication
+                .set_cursor(primary.url, seq)
+                .await
+                .map_err(|e| e.to_string())?;
+            cursor = seq;
+            applied += 1;
+        }
+        if complete {
+            return Ok(applied);
+        }
+    }
+}
+
+/// the file follows the current state on the primary rather than the event - a file changed or
+/// deleted since is caught up with by the later events of the stream
+async fn apply(
+    state: &AppState,
+    primary: &Primary<'_>,
+    event: ReplicationEventDto,
+) -> Result<(), String> {
+    let wg_id = event.watch_group_id;
+    state
+        .db
+        .server_watch_group()
+        .ensure_watch_group(wg_id, &event.watch_group_name)
+        .await
+        .map_err(|e| format!("Creating watch group {wg_id} failed - {e}"))?;
+    // the pull stops here and continues from the cursor with the next one
+    let _write = state.backup_window.try_write(wg_id).map_err(|(_, e)| e)?;
+    let event_type = FileEventType::try_from(event.event_type.as_str())?;
+    let path = MatchablePath::from(event.relative_path.as_str());
+    let target = path.resolve(&UPLOAD_PATH.join(wg_id.to_string()));
+
+    if event_type.is_change() {
+        if download(primary, wg_id, &event.relative_path, &target).await? {
+            index_file(state, wg_id, &path, &target).await;
+            state.replication.inner.lock().unwrap().files_pulled += 1;
+        }
+    } else {
+        match tokio::fs::remove_file(&target).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                return Err(format!("Deleting {} failed - {e}", event.relative_path));
+            }
+            _ => unindex_file(state, wg_id, &path).await,
+        }
+    }
+
+    let event = FileEvent::new(
+        Uuid::parse_str(&event.event_id).map_err(|e| e.to_string())?,
+        event.utc_millis,
+        path,
+        event.size_in_bytes,
+        event_type,
+        None,
+        wg_id,
+    );
+    let recorded = state
+        .events
+        .record_replicated(&event)
+        .await
+        .map_err(|e| format!("Recording event failed - {e}"))?;
+    if recorded {
+        webhook::notify(state, &event, Some(REPLICATION_CLIENT_ID));
+        publish::mirror(state, &event);
+    }
+    Ok(())
+}
+
+/// streams the current file of the primary into `target` (swapped in by rename) - false if it's
+/// gone there
+async fn download(
+    primary: &Primary<'_>,
+    wg_id: i64,
+    relative_path: &str,
+    target: &std::path::Path,
+) -> Result<bool, String> {
+    let response = primary
+        .get(
+            ServerEndpoint::ApiWatchGroupFile,
+            &[("id", &wg_id.to_string())],
+        )
+        .query(&[("path", relative_path)])
+        .send()
+        .await
+        .map_err(|e| format!("Downloading {relative_path} failed - {e}"))?;
+    if response.status() == StatusCode::NOT_FOUND {
+        debug!("{} is gone on the primary - skipped", relative_path);
+        return Ok(false);
+    }
+    let mut response = response
+        .error_for_status()
+        .map_err(|e| format!("Downloading {relative_path} failed - {e}"))?;
+
+    let tmp = UPLOAD_TMP_PATH.join(format!(".replication-{}", Uuid::new_v4()));
+    let written = async {
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        while let Some(chunk) = response.chunk().await.map_err(io::Error::other)? {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(&tmp, target).await
+    }
+    .await;
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(format!("Storing {relative_path} failed - {e}"));
+    }
+    Ok(true)
+}
+
+fn internal(e: sqlx::Error) -> (StatusCode, String) {
+    error!("Failed to read replication state: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
@@ -0,0 +1,162 @@
+use crate::AppState;
+use crate::db::SizeEvent;
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use shared::dtos::{
+    GrowthQueryDto, GrowthReportDto, StorageDayDto, TopFilesQueryDto, TopFilesReportDto,
+};
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use tracing::error;
+
+/// default / max entries of GET /api/reports/top-files/{wg_id}
+const TOP_FILES: u32 = 50;
+const MAX_TOP_FILES: u32 = 500;
+/// default / max range of GET /api/reports/growth/{wg_id}
+const GROWTH_DAYS: u32 = 90;
+const MAX_GROWTH_DAYS: u32 = 3650;
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+async fn ensure_watch_group_exists(
+    state: &AppState,
+    wg_id: i64,
+) -> Result<(), (StatusCode, String)> {
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group existence: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if exists {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("Watch group {wg_id} not found"),
+        ))
+    }
+}
+
+/// GET /api/reports/top-files/{wg_id}?limit=..
+pub async fn api_get_top_files(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+    Query(query): Query<TopFilesQueryDto>,
+) -> Result<Json<TopFilesReportDto>, (StatusCode, String)> {
+    ensure_watch_group_exists(&state, wg_id).await?;
+    let limit = query.limit.unwrap_or(TOP_FILES).clamp(1, MAX_TOP_FILES);
+    let db_error = |e: sqlx::Error| {
+        error!(
+            "Failed to compute top files of watch group {}: {}",
+            wg_id, e
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    let stats = state.db.stats();
+    let files = stats.get_top_files(wg_id, limit).await.map_err(db_error)?;
+    let total_bytes = stats
+        .get_watch_group_stats(wg_id)
+        .await
+        .map_err(db_error)?
+        .total_bytes;
+    Ok(Json(TopFilesReportDto { total_bytes, files }))
+}
+
+/// GET /api/reports/growth/{wg_id}?days=..
+pub async fn api_get_growth(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+    Query(query): Query<GrowthQueryDto>,
+) -> Result<Json<GrowthReportDto>, (StatusCode, String)> {
+    ensure_watch_group_exists(&state, wg_id).await?;
+    let days = query.days.unwrap_or(GROWTH_DAYS).clamp(1, MAX_GROWTH_DAYS) as u64;
+    let events = state.db.stats().get_size_events(wg_id).await.map_err(|e| {
+        error!(
+            "Failed to load event history of watch group {}: {}",
+            wg_id, e
+        );
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    let today = UtcMillis::now().as_u64() / DAY_MILLIS;
+    Ok(Json(GrowthReportDto {
+        days: storage_per_day(&events, today + 1 - days, today),
+    }))
+}
+
+/// replays the (oldest first) event history - one entry per day from `first_day` to `last_day`
+/// (days since the unix epoch), holding the stored bytes and files at the end of that day
+fn storage_per_day(events: &[SizeEvent], first_day: u64, last_day: u64) -> Vec<StorageDayDto> {
+    let mut sizes: HashMap<&str, u64> = HashMap::new();
+    let mut total_bytes: u64 = 0;
+    let mut events = events.iter().peekable();
+    (first_day..=last_day)
+        .map(|day| {
+            let end = (day + 1) * DAY_MILLIS;
+            while let Some(event) = events.next_if(|e| e.utc_millis < end) {
+                let previous = if event.deleted {
+                    sizes.remove(event.relative_path.as_str())
+                } else {
+                    sizes.insert(&event.relative_path, event.size_in_bytes)
+                };
+                total_bytes = total_bytes - previous.unwrap_or(0)
+                    + if event.deleted {
+                        0
+                    } else {
+                        event.size_in_bytes
+                    };
+            }
+            StorageDayDto {
+                day: format_day(day),
+                start_utc_millis: day * DAY_MILLIS,
+                total_bytes,
+                file_count: sizes.len() as u64,
+            }
+        })
+        .collect()
+}
+
+/// YYYY-MM-DD (UTC)
+fn format_day(day: u64) -> String {
+    chrono::DateTime::from_timestamp_millis((day * DAY_MILLIS) as i64)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(day: u64, path: &str, size: u64, deleted: bool) -> SizeEvent {
+        SizeEvent {
+            utc_millis: day * DAY_MILLIS + 1000,
+            relative_path: path.to_string(),
+            size_in_bytes: size,
+            deleted,
+        }
+    }
+
+    #[test]
+    fn should_replay_storage_per_day() {
+        let events = vec![
+            event(1, "a.bin", 100, false),
+            event(2, "b.bin", 50, false),
+            event(4, "a.bin", 300, false),
+            event(4, "b.bin", 0, true),
+            event(6, "c.bin", 1, false),
+        ];
+
+        let days = storage_per_day(&events, 2, 5);
+
+        assert_eq!(
+            vec![(2, 150, 2), (3, 150, 2), (4, 300, 1), (5, 300, 1)],
+            days.iter()
+                .map(|d| (d.start_utc_millis / DAY_MILLIS, d.total_bytes, d.file_count))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("1970-01-03", days[0].day);
+    }
+}
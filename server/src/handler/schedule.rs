@@ -0,0 +1,49 @@
+use crate::AppState;
+use crate::schedule::CronExpression;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use shared::dtos::{ScheduleEntryDto, ScheduleUpdateDto, ScheduledJob};
+use tracing::{error, info};
+
+/// GET /api/admin/schedule - expression and next run of every job
+pub async fn api_get_schedule(State(state): State<AppState>) -> Json<Vec<ScheduleEntryDto>> {
+    Json(
+        ScheduledJob::ALL
+            .into_iter()
+            .map(|job| state.schedules.to_dto(job))
+            .collect(),
+    )
+}
+
+/// PUT /api/admin/schedule/{job} - saved, so it outlasts a restart, and planned right away
+pub async fn api_set_schedule(
+    State(state): State<AppState>,
+    Path(job): Path<String>,
+    Json(dto): Json<ScheduleUpdateDto>,
+) -> Result<Json<ScheduleEntryDto>, (StatusCode, String)> {
+    let job = ScheduledJob::try_from(job.as_str()).map_err(|e| (StatusCode::NOT_FOUND, e))?;
+    let expression = dto
+        .expression
+        .as_deref()
+        .map(str::parse::<CronExpression>)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let source = expression.as_ref().map(|e| e.to_string());
+    state
+        .db
+        .schedule()
+        .save(job.as_str(), source.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to save schedule of {}: {}", job.as_str(), e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    info!(
+        "Schedule of {} set to {}",
+        job.as_str(),
+        source.as_deref().unwrap_or("off")
+    );
+    state.schedules.set(job, expression);
+    Ok(Json(state.schedules.to_dto(job)))
+}
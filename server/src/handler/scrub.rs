@@ -0,0 +1,465 @@
+use crate::db::FileHash;
+use crate::file_event::{FileEvent, FileEventType};
+use crate::write;
+use crate::{AppState, UPLOAD_PATH, publish, supervisor, webhook};
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use shared::dtos::{FileDescription, ScrubFindingDto, ScrubFindingKind, ScrubReportDto};
+use shared::get_files_of_directory::{ScanLimits, get_all_file_descriptions};
+use shared::matchable_path::MatchablePath;
+use shared::temp_files::TempFilePatterns;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::sync::Mutex;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::search::{index_file, unindex_file};
+
+/// author of the file events recorded by a repairing scrub
+pub(super) const SCRUB_CLIENT_ID: &str = "a7b8c9d0-1e2f-4a3b-8c4d-5e6f7a8b9c0d";
+/// files changed more recently are skipped - they might be in the middle of an upload
+const SETTLE_MILLIS: u64 = 60_000;
+
+/// result of the last scrub (scheduled or triggered via api) and of the check on boot
+#[derive(Default)]
+pub struct ScrubReports {
+    last: Mutex<Option<ScrubReportDto>>,
+    boot: Mutex<Option<ScrubReportDto>>,
+}
+
+/// GET /api/admin/scrub - report of the last scrub
+pub async fn api_get_scrub(
+    State(state): State<AppState>,
+) -> Result<Json<ScrubReportDto>, (StatusCode, String)> {
+    state
+        .scrub_reports
+        .last
+        .lock()
+        .unwrap()
+        .clone()
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "No scrub ran yet".to_string()))
+}
+
+/// GET /api/admin/boot-report - what the check on boot found (and corrected)
+pub async fn api_get_boot_report(
+    State(state): State<AppState>,
+) -> Result<Json<ScrubReportDto>, (StatusCode, String)> {
+    state
+        .scrub_reports
+        .boot
+        .lock()
+        .unwrap()
+        .clone()
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "No boot check ran (yet)".to_string()))
+}
+
+/// POST /api/admin/scrub?repair=true&hash=true - scrubs all watch groups now
+pub async fn api_run_scrub(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<ScrubReportDto>, (StatusCode, String)> {
+    let repair = params.get("repair").is_some_and(|v| v == "true");
+    let compare_hashes = params.get("hash").is_some_and(|v| v == "true");
+    scrub(&state, repair, compare_hashes)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to scrub: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+
+/// one run of the `scrub` job - only flags discrepancies unless SCRUB_REPAIR=true
+pub async fn run_scrub(state: &AppState) {
+    let repair = env::var("SCRUB_REPAIR").is_ok_and(|v| v == "true");
+    match scrub(state, repair, compare_hashes_env()).await {
+        Ok(_) => supervisor::succeeded(),
+        Err(e) => {
+            error!("Scheduled scrub failed: {}", e);
+            supervisor::failed(&e);
+        }
+    }
+}
+
+/// compares the history with the disk once on boot and records events for the drift (e.g. files
+/// changed while the server was down) - BOOT_CHECK=false turns it off
+pub async fn check_on_boot(state: AppState) {
+    if env::var("BOOT_CHECK").is_ok_and(|v| v == "false") {
+        info!("Boot check disabled");
+        return;
+    }
+    info!("Checking the history against the disk...");
+    match scrub(&state, true, compare_hashes_env()).await {
+        Ok(report) => *state.scrub_reports.boot.lock().unwrap() = Some(report),
+        Err(e) => error!("Boot check failed: {}", e),
+    }
+}
+
+/// SCRUB_HASH=true - re-reads every file with a verified hash, so it's off by default
+fn compare_hashes_env() -> bool {
+    env::var("SCRUB_HASH").is_ok_and(|v| v == "true")
+}
+
+async fn scrub(
+    state: &AppState,
+    repair: bool,
+    compare_hashes: bool,
+) -> Result<ScrubReportDto, sqlx::Error> {
+    let watch_groups = state.db.server_watch_group().get_all_watch_groups().await?;
+    let mut checked_files = 0;
+    let mut findings = Vec::new();
+
+    for watch_group in watch_groups {
+        let root = UPLOAD_PATH.join(watch_group.id.to_string());
+        let on_disk = if root.exists() {
+            match get_all_file_descriptions(
+                &root,
+                &Vec::new(),
+                false,
+                &TempFilePatterns::none(),
+                ScanLimits::none(),
+            ) {
+                Ok(on_disk) => on_disk,
+                Err(e) => {
+                    warn!("Scrub skipped watch group {} - {}", watch_group.id, e);
+                    continue;
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        checked_files += on_disk.len();
+        let latest = state.history.get_latest_events(watch_group.id);
+        let mut wg_findings =
+            find_discrepancies(watch_group.id, &on_disk, &latest, UtcMillis::now());
+        if compare_hashes {
+            let hashes = state.db.file_hash().get_all(watch_group.id).await?;
+            let wg_id = watch_group.id;
+            let mismatches = tokio::task::spawn_blocking(move || {
+                find_hash_mismatches(
+                    wg_id,
+                    &on_disk,
+                    &latest,
+                    &hashes,
+                    UtcMillis::now(),
+                    |path| write::hash_file(&path.resolve(&root)),
+                )
+            })
+            .await
+            .unwrap_or_else(|e| {
+                error!("Hash comparison of watch group {} failed: {}", wg_id, e);
+                Vec::new()
+            });
+            wg_findings.extend(mismatches);
+        }
+
+        for finding in &wg_findings {
+            warn!(
+                watch_group_id = finding.watch_group_id,
+                path = finding.relative_path,
+                "Scrub found {:?}",
+                finding.kind
+            );
+            if repair {
+                repair_history(state, finding).await?;
+            }
+        }
+        findings.extend(wg_findings);
+    }
+
+    let report = ScrubReportDto {
+        finished_at: UtcMillis::now(),
+        checked_files,
+        repaired: repair,
+        findings,
+    };
+    if report.findings.is_empty() {
+        info!("Scrub checked {} files - no discrepancies", checked_files);
+    } else {
+        warn!(
+            "Scrub checked {} files - {} discrepancies{}",
+            checked_files,
+            report.findings.len(),
+            if repair { " (repaired)" } else { "" }
+        );
+    }
+    *state.scrub_reports.last.lock().unwrap() = Some(report.clone());
+    Ok(report)
+}
+
+/// files changed more recently than [`SETTLE_MILLIS`] might be in the middle of an upload
+fn settled(description: &FileDescription, now: &UtcMillis) -> bool {
+    now.as_u64()
+        .saturating_sub(description.last_updated_utc_millis.as_u64())
+        >= SETTLE_MILLIS
+}
+
+/// existence and size - contents are only compared on request, see [`find_hash_mismatches`]
+fn find_discrepancies(
+    wg_id: i64,
+    on_disk: &[FileDescription],
+    latest: &[FileEvent],
+    now: UtcMillis,
+) -> Vec<ScrubFindingDto> {
+    let on_disk: HashMap<&MatchablePath, &FileDescription> =
+        on_disk.iter().map(|d| (&d.relative_path, d)).collect();
+    let latest: HashMap<&MatchablePath, &FileEvent> =
+        latest.iter().map(|e| (&e.relative_path, e)).collect();
+    let finding = |path: &MatchablePath, kind| ScrubFindingDto {
+        watch_group_id: wg_id,
+        relative_path: path.to_serialized_string(),
+        kind,
+    };
+
+    let mut findings = Vec::new();
+    for (path, description) in &on_disk {
+        if !settled(description, &now) {
+            continue;
+        }
+        match latest.get(path) {
+            Some(event) if event.event_type.is_change() => {
+                if event.size_in_bytes != description.size_in_bytes {
+                    findings.push(finding(
+                        path,
+                        ScrubFindingKind::SizeMismatch {
+                            expected: event.size_in_bytes,
+                            actual: description.size_in_bytes,
+                        },
+                    ));
+                }
+            }
+            _ => findings.push(finding(
+                path,
+                ScrubFindingKind::Untracked {
+                    size_in_bytes: description.size_in_bytes,
+                },
+            )),
+        }
+    }
+    for (path, event) in &latest {
+        if event.event_type.is_change() && !on_disk.contains_key(path) {
+            findings.push(finding(path, ScrubFindingKind::Missing));
+        }
+    }
+    findings.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    findings
+}
+
+/// files whose content differs from the sha256 verified on upload - a hash only counts for the
+/// version it was taken of, so files with a newer event (or a size mismatch) are skipped
+fn find_hash_mismatches(
+    wg_id: i64,
+    on_disk: &[FileDescription],
+    latest: &[FileEvent],
+    hashes: &[FileHash],
+    now: UtcMillis,
+    hash_of: impl Fn(&MatchablePath) -> io::Result<String>,
+) -> Vec<ScrubFindingDto> {
+    let on_disk: HashMap<&MatchablePath, &FileDescription> =
+        on_disk.iter().map(|d| (&d.relative_path, d)).collect();
+    let latest: HashMap<&MatchablePath, &FileEvent> =
+        latest.iter().map(|e| (&e.relative_path, e)).collect();
+
+    let mut findings = Vec::new();
+    for hash in hashes {
+        let path = MatchablePath::from(hash.relative_path.as_str());
+        let (Some(description), Some(event)) = (on_disk.get(&path), latest.get(&path)) else {
+            continue;
+        };
+        let same_version = event.event_type.is_change()
+            && event.utc_millis == hash.utc_millis
+            && event.size_in_bytes == description.size_in_bytes;
+        if !same_version || !settled(description, &now) {
+            continue;
+        }
+        match hash_of(&path) {
+            Ok(actual) if actual != hash.sha256 => findings.push(ScrubFindingDto {
+                watch_group_id: wg_id,
+                relative_path: hash.relative_path.clone(),
+                kind: ScrubFindingKind::HashMismatch {
+                    size_in_bytes: description.size_in_bytes,
+                    expected: hash.sha256.clone(),
+                    actual,
+                },
+            }),
+            Ok(_) => {}
+            Err(e) => warn!("Scrub could not hash {} - {}", hash.relative_path, e),
+        }
+    }
+    findings.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    findings
+}
+
+/// records a file event matching the disk, so clients follow the state on the server
+async fn repair_history(state: &AppState, finding: &ScrubFindingDto) -> Result<(), sqlx::Error> {
+    let path = MatchablePath::from(finding.relative_path.as_str());
+    let (size_in_bytes, event_type) = match finding.kind {
+        ScrubFindingKind::Untracked { size_in_bytes } => {
+            (size_in_bytes, FileEventType::ChangeEvent)
+        }
+        ScrubFindingKind::SizeMismatch { actual, .. } => (actual, FileEventType::ChangeEvent),
+        ScrubFindingKind::HashMismatch { size_in_bytes, .. } => {
+            (size_in_bytes, FileEventType::ChangeEvent)
+        }
+        ScrubFindingKind::Missing => (0, FileEventType::DeleteEvent),
+    };
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        UtcMillis::now(),
+        path.clone(),
+        size_in_bytes,
+        event_type,
+        Some("scrub".to_string()),
+        finding.watch_group_id,
+    );
+    state.events.record(&event, Some(SCRUB_CLIENT_ID)).await?;
+
+    let wg_root = UPLOAD_PATH.join(finding.watch_group_id.to_string());
+    if event.event_type.is_change() {
+        index_file(
+            state,
+            finding.watch_group_id,
+            &path,
+            &path.resolve(&wg_root),
+        )
+        .await;
+    } else {
+        unindex_file(state, finding.watch_group_id, &path).await;
+    }
+    webhook::notify(state, &event, Some(SCRUB_CLIENT_ID));
+    publish::mirror(state, &event);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description(path: &str, size_in_bytes: u64, last_updated: u64) -> FileDescription {
+        FileDescription {
+            file_name: path.to_string(),
+            relative_path: MatchablePath::from(path),
+            size_in_bytes,
+            file_type: "txt".to_string(),
+            last_updated_utc_millis: UtcMillis::from(last_updated),
+        }
+    }
+
+    fn event(path: &str, size_in_bytes: u64, event_type: FileEventType) -> FileEvent {
+        FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(1),
+            MatchablePath::from(path),
+            size_in_bytes,
+            event_type,
+            None,
+            1,
+        )
+    }
+
+    #[test]
+    fn should_find_discrepancies_between_disk_and_history() {
+        let on_disk = vec![
+            description("ok.txt", 1, 0),
+            description("grown.txt", 2, 0),
+            description("untracked.txt", 3, 0),
+            description("deleted-but-there.txt", 4, 0),
+            description("uploading.txt", 5, 100_000),
+        ];
+        let latest = vec![
+            event("ok.txt", 1, FileEventType::ChangeEvent),
+            event("grown.txt", 1, FileEventType::ChangeEvent),
+            event("deleted-but-there.txt", 0, FileEventType::DeleteEvent),
+            event("missing.txt", 1, FileEventType::ChangeEvent),
+            event("gone.txt", 0, FileEventType::DeleteEvent),
+        ];
+
+        let findings = find_discrepancies(1, &on_disk, &latest, UtcMillis::from(100_000));
+
+        let kinds: Vec<(&str, &ScrubFindingKind)> = findings
+            .iter()
+            .map(|f| (f.relative_path.as_str(), &f.kind))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    "deleted-but-there.txt",
+                    &ScrubFindingKind::Untracked { size_in_bytes: 4 }
+                ),
+                (
+                    "grown.txt",
+                    &ScrubFindingKind::SizeMismatch {
+                        expected: 1,
+                        actual: 2
+                    }
+                ),
+                ("missing.txt", &ScrubFindingKind::Missing),
+                (
+                    "untracked.txt",
+                    &ScrubFindingKind::Untracked { size_in_bytes: 3 }
+                ),
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn should_only_compare_hashes_of_the_verified_version() {
+        let on_disk = vec![
+            description("ok.txt", 1, 0),
+            description("corrupt.txt", 1, 0),
+            description("changed-since.txt", 1, 0),
+            description("unreadable.txt", 1, 0),
+        ];
+        let latest = vec![
+            event("ok.txt", 1, FileEventType::ChangeEvent),
+            event("corrupt.txt", 1, FileEventType::ChangeEvent),
+            event("changed-since.txt", 1, FileEventType::ChangeEvent),
+            event("unreadable.txt", 1, FileEventType::ChangeEvent),
+        ];
+        let hash = |path: &str, utc_millis: u64| FileHash {
+            relative_path: path.to_string(),
+            utc_millis: UtcMillis::from(utc_millis),
+            sha256: "verified".to_string(),
+        };
+        let hashes = vec![
+            hash("ok.txt", 1),
+            hash("corrupt.txt", 1),
+            hash("changed-since.txt", 0),
+            hash("unreadable.txt", 1),
+            hash("missing.txt", 1),
+        ];
+
+        let findings = find_hash_mismatches(
+            1,
+            &on_disk,
+            &latest,
+            &hashes,
+            UtcMillis::from(100_000),
+            |path| match path.to_serialized_string().as_str() {
+                "ok.txt" => Ok("verified".to_string()),
+                "unreadable.txt" => Err(io::Error::other("locked")),
+                _ => Ok("rotten".to_string()),
+            },
+        );
+
+        assert_eq!(
+            vec![ScrubFindingDto {
+                watch_group_id: 1,
+                relative_path: "corrupt.txt".to_string(),
+                kind: ScrubFindingKind::HashMismatch {
+                    size_in_bytes: 1,
+                    expected: "verified".to_string(),
+                    actual: "rotten".to_string(),
+                },
+            }],
+            findings
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use crate::AppState;
+use crate::UPLOAD_PATH;
+use crate::auth::Account;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use shared::dtos::{SearchHitDto, ServerWatchGroup};
+use shared::matchable_path::MatchablePath;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::{error, info, warn};
+
+use super::can_see;
+
+/// only files up to this size get their content indexed
+const MAX_INDEXED_CONTENT_BYTES: u64 = 64 * 1024;
+
+/// GET /api/search?q=holiday&content=true
+pub async fn api_search(
+    State(state): State<AppState>,
+    Extension(account): Extension<Account>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<SearchHitDto>>, (StatusCode, String)> {
+    let query = params.get("q").map(|q| q.trim()).unwrap_or_default();
+    if query.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Missing q parameter".to_string()));
+    }
+    let include_content = params.get("content").is_some_and(|c| c == "true");
+    let watch_groups: Vec<ServerWatchGroup> = state
+        .db
+        .server_watch_group()
+        .get_all_watch_groups()
+        .await
+        .map_err(|e| {
+            error!("Failed to get watch groups: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .into_iter()
+        .filter(|wg| can_see(&account, wg.owner_id))
+        .collect();
+
+    let mut hits: Vec<SearchHitDto> = state
+        .db
+        .search()
+        .search(query, include_content)
+        .await
+        .map_err(|e| {
+            error!("Failed to search for '{}': {}", query, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?
+        .into_iter()
+        .filter(|hit| watch_groups.iter().any(|wg| wg.id == hit.watch_group_id))
+        .collect();
+    hits.extend(search_deleted(&state, &watch_groups, query));
+
+    Ok(Json(hits))
+}
+
+/// files that only live on in the history - matched by (case-insensitive) substring of the path
+fn search_deleted(
+    state: &AppState,
+    watch_groups: &[ServerWatchGroup],
+    query: &str,
+) -> Vec<SearchHitDto> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+
+    watch_groups
+        .iter()
+        .flat_map(|wg| {
+            state
+                .history
+                .get_latest_events(wg.id)
+                .into_iter()
+                .filter(|e| !e.event_type.is_change())
+                .map(|e| e.relative_path.to_serialized_string())
+                .filter(|path| {
+                    let path = path.to_lowercase();
+                    terms.iter().all(|term| path.contains(term))
+                })
+                .map(|relative_path| SearchHitDto {
+                    watch_group_id: wg.id,
+                    watch_group_name: wg.name.clone(),
+                    relative_path,
+                    snippet: None,
+                    deleted: true,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// adds an uploaded file to the search index - failures only cost findability, so they are logged
+pub(crate) async fn index_file(state: &AppState, wg_id: i64, path: &MatchablePath, file: &Path) {
+    let relative_path = path.to_serialized_string();
+    let content = indexable_content(file);
+    if let Err(e) = state
+        .db
+        .search()
+        .upsert(wg_id, &relative_path, content.as_deref())
+        .await
+    {
+        warn!("Failed to index '{}' for search: {}", relative_path, e);
+    }
+}
+
+pub(crate) async fn unindex_file(state: &AppState, wg_id: i64, path: &MatchablePath) {
+    let relative_path = path.to_serialized_string();
+    if let Err(e) = state.db.search().remove(wg_id, &relative_path).await {
+        warn!(
+            "Failed to remove '{}' from search index: {}",
+            relative_path, e
+        );
+    }
+}
+
+/// rebuilds the index from the history (which is the source of truth for existing files)
+pub async fn rebuild_search_index(state: AppState) {
+    if let Err(e) = state.db.search().clear().await {
+        error!("Failed to clear search index: {}", e);
+        return;
+    }
+    let watch_groups = match state.db.server_watch_group().get_all_watch_groups().await {
+        Ok(watch_groups) => watch_groups,
+        Err(e) => {
+            error!("Failed to rebuild search index: {}", e);
+            return;
+        }
+    };
+
+    let mut count = 0;
+    for wg in watch_groups {
+        let root = UPLOAD_PATH.join(wg.id.to_string());
+        for event in state.history.get_latest_events(wg.id) {
+            if event.event_type.is_change() {
+                let file = event.relative_path.resolve(&root);
+                index_file(&state, wg.id, &event.relative_path, &file).await;
+                count += 1;
+            }
+        }
+    }
+    info!("Indexed {} files for search", count);
+}
+
+/// content of small utf-8 files - binaries and large files are only searchable by name
+fn indexable_content(file: &Path) -> Option<String> {
+    let size = fs::metadata(file).ok()?.len();
+    if size > MAX_INDEXED_CONTENT_BYTES {
+        return None;
+    }
+    String::from_utf8(fs::read(file).ok()?)
+        .ok()
+        .filter(|content| !content.contains('\0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_index_small_text_files() {
+        let dir = std::env::temp_dir().join(format!("search-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let text = dir.join("notes.md");
+        let binary = dir.join("image.png");
+        let large = dir.join("large.txt");
+        fs::write(&text, "hello").unwrap();
+        fs::write(&binary, [0x89, 0x50, 0x00, 0xff]).unwrap();
+        fs::write(&large, "a".repeat(MAX_INDEXED_CONTENT_BYTES as usize + 1)).unwrap();
+
+        assert_eq!(Some("hello".to_string()), indexable_content(&text));
+        assert_eq!(None, indexable_content(&binary));
+        assert_eq!(None, indexable_content(&large));
+        assert_eq!(None, indexable_content(&dir.join("missing.txt")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_only_show_users_their_own_or_unowned_watch_groups() {
+        assert!(can_see(&Account::Bootstrap, Some(1)));
+        assert!(can_see(&Account::User(1), None));
+        assert!(can_see(&Account::User(1), Some(1)));
+        assert!(!can_see(&Account::User(2), Some(1)));
+    }
+}
@@ -1,29 +1,37 @@
+use crate::auth::Account;
 use crate::file_event::{FileEvent, FileEventType};
-use crate::file_history::FileHistory;
-use crate::write::write_all_chunks_of_field;
-use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH};
+use crate::write::{self, write_all_chunks_of_field};
+use crate::{
+    AppState, BACKUP_PATH, UPLOAD_PATH, UPLOAD_TMP_PATH, VERSIONS_PATH, publish, thumbnail,
+    versions, webhook,
+};
 
 /// UUID of the sentinel 'pwa' client row — must match the migration.
-const PWA_CLIENT_ID: &str = "f4a7b3c2-8d5e-4f6a-9b2c-1e3d5f7a9b0c";
+pub(super) const PWA_CLIENT_ID: &str = "f4a7b3c2-8d5e-4f6a-9b2c-1e3d5f7a9b0c";
 /// UUID of the sentinel 'web' client row — must match the migration.
-const WEB_CLIENT_ID: &str = "c3d4e5f6-7a8b-4c9d-8e2f-1a3b5c7d9e0f";
+pub(super) const WEB_CLIENT_ID: &str = "c3d4e5f6-7a8b-4c9d-8e2f-1a3b5c7d9e0f";
 
-use axum::Json;
-use axum::body::Body;
 use axum::extract::{Multipart, Query, State};
-use axum::http::StatusCode;
 use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
-use shared::dtos::{FileDescription, ServerWatchGroup, WatchGroupNameDto};
+use axum::{Extension, Json};
+use shared::dtos::{
+    Durability, DurabilityDto, FileDescription, OwnerDto, PriorityDto, PublishTargetDto,
+    ServerWatchGroup, VerifyWritesDto, WatchGroupNameDto,
+};
 use shared::matchable_path::MatchablePath;
 use shared::utc_millis::UtcMillis;
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
-use tokio_util::io::ReaderStream;
+use std::path::{Component, Path, PathBuf};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use super::search::{index_file, unindex_file};
+use super::sync::durability;
+use super::{authorize_account_access, content_length};
+
 /// GET /api/watch-groups
 pub async fn api_list_watch_groups(
     State(state): State<AppState>,
@@ -79,23 +87,231 @@ pub async fn api_update_watch_group(
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// DELETE /api/watch-groups/{id}
-pub async fn api_delete_watch_group(
+/// PUT /api/watch-groups/{id}/owner
+pub async fn api_set_watch_group_owner(
     State(state): State<AppState>,
     axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(dto): Json<OwnerDto>,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    super::user::ensure_user_exists(&state, dto.owner_id).await?;
     let found = state
         .db
         .server_watch_group()
-        .delete(id)
+        .set_owner(id, dto.owner_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to set owner of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Set owner of watch group {} to {:?}", id, dto.owner_id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()))
+    }
+}
+
+/// PUT /api/watch-groups/{id}/verify-writes
+pub async fn api_set_watch_group_verify_writes(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(dto): Json<VerifyWritesDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state
+        .db
+        .server_watch_group()
+        .set_verify_writes(id, dto.verify_writes)
+        .await
+        .map_err(|e| {
+            error!("Failed to set verify_writes of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!(
+            "Set verify_writes of watch group {} to {}",
+            id, dto.verify_writes
+        );
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()))
+    }
+}
+
+/// PUT /api/watch-groups/{id}/durability - applies to uploads from now on
+pub async fn api_set_watch_group_durability(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(dto): Json<DurabilityDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state
+        .db
+        .server_watch_group()
+        .set_durability(id, dto.durability)
+        .await
+        .map_err(|e| {
+            error!("Failed to set durability of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!(
+            "Set durability of watch group {} to {}",
+            id,
+            dto.durability.as_str()
+        );
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()))
+    }
+}
+
+/// PUT /api/watch-groups/{id}/publish - current files get mirrored right away
+pub async fn api_set_watch_group_publish(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(dto): Json<PublishTargetDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let publish_path = match dto.publish_path.as_deref().filter(|p| !p.is_empty()) {
+        Some(path) => Some(
+            publish::validate_target(Path::new(path))
+                .map_err(|e| (StatusCode::BAD_REQUEST, e))?
+                .to_string_lossy()
+                .to_string(),
+        ),
+        None => None,
+    };
+    let found = state
+        .db
+        .server_watch_group()
+        .set_publish_path(id, publish_path.as_deref())
         .await
         .map_err(|e| {
-            error!("Failed to delete watch group: {}", e);
+            error!("Failed to set publish target of watch group: {}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
         })?;
 
     if found {
-        info!("Deleted watch group {}", id);
+        info!(
+            "Set publish target of watch group {} to {:?}",
+            id, publish_path
+        );
+        if publish_path.is_some() {
+            tokio::spawn(async move { publish::publish_all(&state, id).await });
+        }
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()))
+    }
+}
+
+/// PUT /api/watch-groups/{id}/priority
+pub async fn api_set_watch_group_priority(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(dto): Json<PriorityDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state
+        .db
+        .server_watch_group()
+        .set_priority(id, dto.priority)
+        .await
+        .map_err(|e| {
+            error!("Failed to set priority of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Set priority of watch group {} to {}", id, dto.priority);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()))
+    }
+}
+
+/// DELETE /api/watch-groups/{id}?force=true
+///
+/// refused while clients are attached (unless forced) - the history is archived as csv
+/// to the backup dir, the files and kept versions are removed
+pub async fn api_delete_watch_group(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db_error = |e: sqlx::Error| {
+        error!("Failed to delete watch group: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    if !state
+        .db
+        .server_watch_group()
+        .exists(id)
+        .await
+        .map_err(db_error)?
+    {
+        return Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()));
+    }
+    let force = params.get("force").is_some_and(|v| v == "true");
+    let attached = state
+        .db
+        .server_watch_group()
+        .count_attached_clients(id)
+        .await
+        .map_err(db_error)?;
+    if attached > 0 && !force {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("{attached} clients are still attached - detach them or use force=true"),
+        ));
+    }
+
+    let events = state
+        .db
+        .file_event()
+        .get_events_of_watch_group(id)
+        .await
+        .map_err(db_error)?;
+    let archive = BACKUP_PATH.join(format!(
+        "watch_group_{}_{}.csv",
+        id,
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    ));
+    let csv: String = events
+        .iter()
+        .map(|e| e.serialize_to_csv_line() + "\n")
+        .collect();
+    tokio::fs::write(&archive, csv).await.map_err(|e| {
+        error!("Failed to archive history of watch group {}: {}", id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    let found = state
+        .db
+        .server_watch_group()
+        .delete(id)
+        .await
+        .map_err(db_error)?;
+    state.history.remove_watch_group(id);
+    for dir in [
+        UPLOAD_PATH.join(id.to_string()),
+        VERSIONS_PATH.join(id.to_string()),
+    ] {
+        match tokio::fs::remove_dir_all(&dir).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove {:?}: {}", dir, e),
+        }
+    }
+
+    if found {
+        info!(
+            "Deleted watch group {} ({} events archived to {:?}, {} clients detached)",
+            id,
+            events.len(),
+            archive,
+            attached
+        );
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()))
@@ -104,9 +320,12 @@ pub async fn api_delete_watch_group(
 
 /// GET /api/watch-groups/{id}/file?path=dir/subdir/file.ext — inline file preview
 pub async fn api_serve_watch_group_file(
+    State(state): State<AppState>,
+    Extension(account): Extension<Account>,
     axum::extract::Path(id): axum::extract::Path<i64>,
     Query(params): Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
+    authorize_account_access(&state, &account, id).await?;
     let path_str = match params.get("path") {
         Some(p) if !p.is_empty() => p.clone(),
         _ => {
@@ -150,14 +369,16 @@ pub async fn api_serve_watch_group_file(
         _ => "application/octet-stream",
     };
 
-    let body = Body::from_stream(ReaderStream::new(file));
+    let body = write::file_body(file);
     Ok(([(CONTENT_TYPE, content_type)], body))
 }
 
 pub async fn api_get_watch_group_files(
     State(state): State<AppState>,
+    Extension(account): Extension<Account>,
     axum::extract::Path(id): axum::extract::Path<i64>,
 ) -> Result<Json<Vec<FileDescription>>, (StatusCode, String)> {
+    authorize_account_access(&state, &account, id).await?;
     let events = state
         .history
         .get_latest_events(id)
@@ -169,12 +390,17 @@ pub async fn api_get_watch_group_files(
     Ok(Json(events))
 }
 
-/// POST /api/watch-groups/{id}/files
+/// POST /api/watch-groups/{id}/files?path=dir - into the root if no dir is given
 pub async fn api_upload_to_watch_group(
     State(state): State<AppState>,
     axum::extract::Path(id): axum::extract::Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
     mut multipart: Multipart,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .upload_disk
+        .ensure_room_for(content_length(&headers))?;
     let exists = state
         .db
         .server_watch_group()
@@ -189,9 +415,19 @@ pub async fn api_upload_to_watch_group(
         return Err((StatusCode::NOT_FOUND, format!("Watch group {id} not found")));
     }
 
-    let (tmp_path, filename, size) = extract_file(&mut multipart).await?;
-
-    let target_dir = UPLOAD_PATH.join(id.to_string());
+    let durability = durability(&state, id).await?;
+    let (tmp_path, filename, size) = extract_file(&mut multipart, durability).await?;
+
+    let dir: PathBuf = params
+        .get("path")
+        .map(|p| {
+            Path::new(p)
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let target_dir = UPLOAD_PATH.join(id.to_string()).join(&dir);
     let target_path = target_dir.join(&filename);
 
     if let Err(e) = fs::create_dir_all(&target_dir) {
@@ -202,7 +438,9 @@ pub async fn api_upload_to_watch_group(
         ));
     }
 
-    if let Err(e) = fs::rename(&tmp_path, &target_path) {
+    let relative_path = MatchablePath::from(dir.join(&filename));
+    versions::keep_current(state.history.as_ref(), id, &relative_path, &target_path);
+    if let Err(e) = write::promote(&tmp_path, &target_path, durability) {
         let _ = fs::remove_file(&tmp_path);
         return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -210,27 +448,42 @@ pub async fn api_upload_to_watch_group(
         ));
     }
 
+    record_pwa_change(&state, id, relative_path, size as u64, &target_path).await;
+
+    info!("PWA uploaded '{}' to watch group {id}", filename);
+    Ok(StatusCode::CREATED)
+}
+
+/// records a file written through the web apps - like the upload of a client
+pub(super) async fn record_pwa_change(
+    state: &AppState,
+    wg_id: i64,
+    relative_path: MatchablePath,
+    size: u64,
+    file: &Path,
+) {
     let event = FileEvent::new(
         Uuid::new_v4(),
         UtcMillis::now(),
-        MatchablePath::from(vec![filename.as_str()]),
-        size as u64,
+        relative_path,
+        size,
         FileEventType::ChangeEvent,
         Some("pwa".to_string()),
-        id,
+        wg_id,
     );
 
-    if let Err(e) = state.db.file_event().insert(&event, PWA_CLIENT_ID).await {
+    if let Err(e) = state.events.record(&event, Some(PWA_CLIENT_ID)).await {
         error!("Failed to persist file event for PWA upload: {e}");
     }
-    state.history.add(event);
-
-    info!("PWA uploaded '{}' to watch group {id}", filename);
-    Ok(StatusCode::CREATED)
+    index_file(state, wg_id, &event.relative_path, file).await;
+    thumbnail::render_in_background(wg_id, &event.relative_path, file);
+    webhook::notify(state, &event, Some(PWA_CLIENT_ID));
+    publish::mirror(state, &event);
 }
 
 async fn extract_file(
     multipart: &mut Multipart,
+    durability: Durability,
 ) -> Result<(PathBuf, String, usize), (StatusCode, String)> {
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
         if field.name() != Some("file") {
@@ -241,7 +494,7 @@ async fn extract_file(
         let filename = sanitize_filename(&raw_name)?;
 
         let tmp_path = UPLOAD_TMP_PATH.join(format!("{}_{}", Uuid::new_v4(), filename));
-        let size = write_all_chunks_of_field(tmp_path.as_path(), field)
+        let size = write_all_chunks_of_field(tmp_path.as_path(), field, None, durability)
             .await
             .map_err(|e| {
                 (
@@ -267,7 +520,12 @@ pub async fn api_delete_watch_group_file(
 ) -> Result<StatusCode, (StatusCode, String)> {
     let path_str = match params.get("path") {
         Some(p) if !p.is_empty() => p.clone(),
-        _ => return Err((StatusCode::BAD_REQUEST, "Missing path parameter".to_string())),
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Missing path parameter".to_string(),
+            ));
+        }
     };
 
     let matchable_path = MatchablePath::from(Path::new(&path_str));
@@ -279,6 +537,7 @@ pub async fn api_delete_watch_group_file(
         return Err((StatusCode::NOT_FOUND, "File not found".to_string()));
     }
 
+    versions::keep_current(state.history.as_ref(), id, &matchable_path, &full_path);
     tokio::fs::remove_file(&full_path)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -293,16 +552,18 @@ pub async fn api_delete_watch_group_file(
         id,
     );
 
-    if let Err(e) = state.db.file_event().insert(&event, WEB_CLIENT_ID).await {
+    if let Err(e) = state.events.record(&event, Some(WEB_CLIENT_ID)).await {
         error!("Failed to persist web delete event to DB: {e}");
     }
-    state.history.add(event);
+    unindex_file(&state, id, &event.relative_path).await;
+    webhook::notify(&state, &event, Some(WEB_CLIENT_ID));
+    publish::mirror(&state, &event);
 
     info!("Web UI deleted file '{}' from watch group {id}", path_str);
     Ok(StatusCode::NO_CONTENT)
 }
 
-fn sanitize_filename(raw: &str) -> Result<String, (StatusCode, String)> {
+pub(super) fn sanitize_filename(raw: &str) -> Result<String, (StatusCode, String)> {
     let name = Path::new(raw)
         .file_name()
         .and_then(|n| n.to_str())
@@ -0,0 +1,337 @@
+use crate::db::ShareLink;
+use crate::file_event::{FileEvent, FileEventType};
+use crate::write::{promote, write_all_chunks_of_field};
+use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH, publish, thumbnail, webhook};
+use axum::Json;
+use axum::extract::{Multipart, Path, State};
+use axum::http::header::ACCEPT;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use shared::dtos::{ShareLinkCreateDto, ShareLinkDto, ShareLinkKind};
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::fs;
+use std::path::{Path as FsPath, PathBuf};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::content_length;
+use super::search::index_file;
+use super::server_watch_group::sanitize_filename;
+use super::sync::durability;
+use super::upload::inspect_upload;
+
+/// author of the file events recorded for uploads through inbox links
+pub(super) const INBOX_CLIENT_ID: &str = "c9d0e1f2-3a4b-4c5d-8e6f-7a8b9c0d1e2f";
+const INBOX_HOST: &str = "inbox";
+
+const INBOX_FORM: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Send files</title>
+</head>
+<body style="font-family: sans-serif; max-width: 32rem; margin: 3rem auto; padding: 0 1rem;">
+<h1>Send files</h1>
+<form method="post" enctype="multipart/form-data">
+<p><input type="file" name="file" multiple required></p>
+<p><button type="submit">Upload</button></p>
+</form>
+</body>
+</html>
+"#;
+
+/// GET /api/watch-groups/{id}/share-links
+pub async fn api_list_share_links(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+) -> Result<Json<Vec<ShareLinkDto>>, (StatusCode, String)> {
+    let links = state
+        .db
+        .share_link()
+        .list_for_watch_group(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list share links: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    links
+        .into_iter()
+        .map(|link| {
+            Ok(ShareLinkDto {
+                kind: ShareLinkKind::try_from(link.kind.as_str())
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+                token: link.token,
+                folder: link.folder,
+                created_at: link.created_at,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Json)
+}
+
+/// POST /api/watch-groups/{id}/share-links - returns the token of the new link
+pub async fn api_create_share_link(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+    Json(dto): Json<ShareLinkCreateDto>,
+) -> Result<(StatusCode, String), (StatusCode, String)> {
+    let folder = normalize_folder(&dto.folder)?;
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()));
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    state
+        .db
+        .share_link()
+        .create(&token, wg_id, dto.kind.as_str(), &folder)
+        .await
+        .map_err(|e| {
+            error!("Failed to create share link: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    info!(
+        "Created {} link into '{}' of watch group {}",
+        dto.kind.as_str(),
+        folder,
+        wg_id
+    );
+    Ok((StatusCode::CREATED, token))
+}
+
+/// DELETE /api/watch-groups/{id}/share-links/{token}
+pub async fn api_delete_share_link(
+    State(state): State<AppState>,
+    Path((wg_id, token)): Path<(i64, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state
+        .db
+        .share_link()
+        .delete(wg_id, &token)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete share link: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Deleted share link of watch group {}", wg_id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Share link not found".to_string()))
+    }
+}
+
+/// GET /inbox/{token} - public upload form
+pub async fn inbox_form(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Html<&'static str>, (StatusCode, String)> {
+    inbox_link(&state, &token).await?;
+    Ok(Html(INBOX_FORM))
+}
+
+/// POST /inbox/{token} - multipart with one or more `file` fields (the form or e.g. `curl -F`)
+///
+/// files never replace existing ones - colliding names get a ` (n)` suffix
+pub async fn inbox_upload(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Response, (StatusCode, String)> {
+    let link = inbox_link(&state, &token).await?;
+    let wg_id = link.server_watch_group_id;
+    let _write = state.backup_window.try_write(wg_id)?;
+    let durability = durability(&state, wg_id).await?;
+    state
+        .upload_disk
+        .ensure_room_for(content_length(&headers))?;
+
+    let folder: Vec<String> = link
+        .folder
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    let target_dir = UPLOAD_PATH
+        .join(wg_id.to_string())
+        .join(folder.iter().collect::<PathBuf>());
+    fs::create_dir_all(&target_dir).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create directory: {e}"),
+        )
+    })?;
+
+    let mut received = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+    {
+        // browsers send an empty part if no file was picked
+        let raw_name = match field.file_name() {
+            Some(name) if field.name() == Some("file") && !name.is_empty() => name.to_string(),
+            _ => continue,
+        };
+        let filename = sanitize_filename(&raw_name)?;
+
+        let tmp_path = UPLOAD_TMP_PATH.join(Uuid::new_v4().to_string());
+        let size = write_all_chunks_of_field(&tmp_path, field, None, durability)
+            .await
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to receive file - {e}"),
+                )
+            })?;
+
+        let name = free_name(&target_dir, &filename);
+        let relative_path = MatchablePath::from(
+            folder
+                .iter()
+                .cloned()
+                .chain([name.clone()])
+                .collect::<Vec<String>>(),
+        );
+        if let Err(e) = inspect_upload(&state, wg_id, &relative_path, &tmp_path).await {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        let target_path = target_dir.join(&name);
+        if let Err(e) = promote(&tmp_path, &target_path, durability) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to move file: {e}"),
+            ));
+        }
+
+        let event = FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::now(),
+            relative_path,
+            size as u64,
+            FileEventType::ChangeEvent,
+            Some(INBOX_HOST.to_string()),
+            wg_id,
+        );
+        if let Err(e) = state.events.record(&event, Some(INBOX_CLIENT_ID)).await {
+            error!("Failed to persist file event for inbox upload: {e}");
+        }
+        index_file(&state, wg_id, &event.relative_path, &target_path).await;
+        thumbnail::render_in_background(wg_id, &event.relative_path, &target_path);
+        webhook::notify(&state, &event, Some(INBOX_CLIENT_ID));
+        publish::mirror(&state, &event);
+
+        info!(
+            "Inbox received '{}' for watch group {wg_id}",
+            target_path.display()
+        );
+        received.push(name);
+    }
+
+    if received.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No files received".to_string()));
+    }
+    let wants_html = headers
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"));
+    let reply = format!("Received {} file(s)", received.len());
+    Ok(if wants_html {
+        (
+            StatusCode::CREATED,
+            Html(INBOX_FORM.replace(
+                "<h1>Send files</h1>",
+                &format!("<p>{reply}</p><h1>Send more files</h1>"),
+            )),
+        )
+            .into_response()
+    } else {
+        (StatusCode::CREATED, reply).into_response()
+    })
+}
+
+/// unknown links look the same as non-inbox links - no hint whether a token exists
+async fn inbox_link(state: &AppState, token: &str) -> Result<ShareLink, (StatusCode, String)> {
+    let link = state.db.share_link().get(token).await.map_err(|e| {
+        error!("Failed to look up share link: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    match link {
+        Some(link) if link.kind == ShareLinkKind::Inbox.as_str() => Ok(link),
+        _ => {
+            warn!("Rejected request with unknown inbox link");
+            Err((StatusCode::NOT_FOUND, "Not found".to_string()))
+        }
+    }
+}
+
+/// "a/b/" -> "a/b" - rejects traversal
+fn normalize_folder(raw: &str) -> Result<String, (StatusCode, String)> {
+    let segments: Vec<&str> = raw
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+    if segments.iter().any(|s| matches!(*s, "." | ".." | "~")) {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid folder: {raw}")));
+    }
+    Ok(segments.join("/"))
+}
+
+/// `name` or the first of `stem (1).ext`, `stem (2).ext`, ... not existing in `dir`
+fn free_name(dir: &FsPath, name: &str) -> String {
+    if !dir.join(name).exists() {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{ext}")),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| format!("{stem} ({n}){ext}"))
+        .find(|candidate| !dir.join(candidate).exists())
+        .expect("Some suffix is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_normalize_folder() {
+        assert_eq!("inbox/scans", normalize_folder(" /inbox//scans/ ").unwrap());
+        assert_eq!("", normalize_folder("").unwrap());
+        assert!(normalize_folder("inbox/../etc").is_err());
+    }
+
+    #[test]
+    fn should_never_pick_an_existing_name() {
+        let dir = std::env::temp_dir().join(format!("inbox-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!("scan.pdf", free_name(&dir, "scan.pdf"));
+
+        fs::write(dir.join("scan.pdf"), "a").unwrap();
+        fs::write(dir.join("scan (1).pdf"), "b").unwrap();
+        fs::write(dir.join(".env"), "c").unwrap();
+        assert_eq!("scan (2).pdf", free_name(&dir, "scan.pdf"));
+        assert_eq!(".env (1)", free_name(&dir, ".env"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
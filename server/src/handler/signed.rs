@@ -0,0 +1,160 @@
+use crate::AppState;
+use crate::auth::{Account, constant_time_eq};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::{Extension, Json};
+use hmac::{Hmac, Mac};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::Deserialize;
+use sha2::Sha256;
+use shared::dtos::{SignDownloadDto, SignedDownloadDto};
+use shared::endpoint::ServerEndpoint;
+use shared::utc_millis::UtcMillis;
+use std::env;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::sync::{download_path, open_download};
+use super::{authorize_account_access, parse_file_path, range};
+
+/// lifetime of a signed url unless the request asks for another one
+const DEFAULT_EXPIRY: Duration = Duration::from_secs(60 * 60);
+/// longer lifetimes are cut down to this
+const MAX_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// `SIGNED_URL_KEY` - random per start if unset, signed urls don't survive a restart then
+static SIGNING_KEY: LazyLock<Vec<u8>> = LazyLock::new(|| {
+    match env::var("SIGNED_URL_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+    {
+        Some(key) => key.into_bytes(),
+        None => {
+            info!("SIGNED_URL_KEY not set - signed urls are valid until the next restart");
+            [Uuid::new_v4().into_bytes(), Uuid::new_v4().into_bytes()].concat()
+        }
+    }
+});
+
+/// query of [`ServerEndpoint::SignedDownload`]
+#[derive(Debug, Deserialize)]
+pub struct SignedQuery {
+    path: String,
+    /// utc millis
+    expires: u64,
+    sig: String,
+}
+
+/// POST /api/sign-download
+pub async fn api_sign_download(
+    State(state): State<AppState>,
+    Extension(account): Extension<Account>,
+    Json(dto): Json<SignDownloadDto>,
+) -> Result<Json<SignedDownloadDto>, (StatusCode, String)> {
+    authorize_account_access(&state, &account, dto.watch_group_id).await?;
+    let path = parse_file_path(&dto.path)?;
+    let is_file = tokio::fs::metadata(download_path(dto.watch_group_id, &path))
+        .await
+        .is_ok_and(|metadata| metadata.is_file());
+    if !is_file {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("File not found: {}", dto.path),
+        ));
+    }
+    let lifetime = dto
+        .expires_in_secs
+        .map_or(DEFAULT_EXPIRY, Duration::from_secs)
+        .min(MAX_EXPIRY);
+    let expires = UtcMillis::now().as_u64() + lifetime.as_millis() as u64;
+    let url = format!(
+        "{}?path={}&expires={}&sig={}",
+        ServerEndpoint::SignedDownload.to_uri_with_wg("", dto.watch_group_id),
+        utf8_percent_encode(&dto.path, NON_ALPHANUMERIC),
+        expires,
+        signature(dto.watch_group_id, &dto.path, expires)
+    );
+    info!(
+        watch_group_id = dto.watch_group_id,
+        "Signed download of {} for {}s",
+        dto.path,
+        lifetime.as_secs()
+    );
+    Ok(Json(SignedDownloadDto {
+        url,
+        expires: UtcMillis::from(expires),
+    }))
+}
+
+/// guards [`ServerEndpoint::SignedDownload`] - 403 unless the signature matches and hasn't expired
+pub async fn verify_signature(
+    Path(wg_id): Path<i64>,
+    Query(query): Query<SignedQuery>,
+    req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    if query.expires < UtcMillis::now().as_u64() {
+        return Err((StatusCode::FORBIDDEN, "Link expired".to_string()));
+    }
+    let expected = signature(wg_id, &query.path, query.expires);
+    if !constant_time_eq(expected.as_bytes(), query.sig.as_bytes()) {
+        warn!("Rejected signed download of {} - bad signature", query.path);
+        return Err((StatusCode::FORBIDDEN, "Invalid signature".to_string()));
+    }
+    Ok(next.run(req).await)
+}
+
+/// GET /signed/{wg_id} - behind [`verify_signature`]
+pub async fn signed_download(
+    Path(wg_id): Path<i64>,
+    Query(query): Query<SignedQuery>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let path = parse_file_path(&query.path)?;
+    let (file, file_name) = open_download(wg_id, &path).await?;
+    range::serve_file(file, &file_name, &headers)
+        .await
+        .map(|(response, _)| response)
+}
+
+/// hex hmac-sha256 of watch group, expiry and path
+fn signature(wg_id: i64, path: &str, expires: u64) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(&SIGNING_KEY).expect("HMAC takes keys of any size");
+    mac.update(format!("{wg_id}\n{expires}\n{path}").as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::matchable_path::MatchablePath;
+
+    #[test]
+    fn should_bind_signature_to_watch_group_path_and_expiry() {
+        let sig = signature(1, "docs/a.txt", 1000);
+
+        assert_eq!(sig, signature(1, "docs/a.txt", 1000));
+        assert_ne!(sig, signature(2, "docs/a.txt", 1000));
+        assert_ne!(sig, signature(1, "docs/b.txt", 1000));
+        assert_ne!(sig, signature(1, "docs/a.txt", 2000));
+    }
+
+    #[test]
+    fn should_reject_paths_without_segments() {
+        for path in ["..", "./..", "/"] {
+            assert_eq!(
+                parse_file_path(path).unwrap_err().0,
+                StatusCode::BAD_REQUEST
+            );
+        }
+        assert_eq!(
+            parse_file_path("docs/../a.txt").unwrap(),
+            MatchablePath::from(vec!["docs", "a.txt"])
+        );
+    }
+}
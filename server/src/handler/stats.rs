@@ -0,0 +1,225 @@
+use crate::AppState;
+use crate::auth::Account;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
+use shared::dtos::{
+    BandwidthQueryDto, BandwidthStatsDto, BandwidthUsageDto, BandwidthUsageQueryDto,
+    BandwidthUsageReportDto, WatchGroupStatsDto,
+};
+use shared::endpoint::CLIENT_ID_HEADER_KEY;
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, trace, warn};
+
+use super::{authorize_account_access, authorize_client, header_value_as_opt_string};
+
+/// stats are aggregated over the whole event table - don't recompute them on every request
+const STATS_TTL: Duration = Duration::from_secs(60);
+/// default range of GET /api/stats/bandwidth
+const BANDWIDTH_HOURS: u32 = 7 * 24;
+
+#[derive(Default)]
+pub struct StatsCache {
+    entries: Mutex<HashMap<i64, (Instant, WatchGroupStatsDto)>>,
+}
+
+impl StatsCache {
+    fn get(&self, wg_id: i64) -> Option<WatchGroupStatsDto> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&wg_id)
+            .filter(|(computed_at, _)| computed_at.elapsed() < STATS_TTL)
+            .map(|(_, stats)| stats.clone())
+    }
+
+    fn insert(&self, wg_id: i64, stats: WatchGroupStatsDto) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(wg_id, (Instant::now(), stats));
+    }
+}
+
+/// GET /api/stats/{wg_id}
+pub async fn api_get_watch_group_stats(
+    State(state): State<AppState>,
+    Extension(account): Extension<Account>,
+    axum::extract::Path(wg_id): axum::extract::Path<i64>,
+) -> Result<Json<WatchGroupStatsDto>, (StatusCode, String)> {
+    authorize_account_access(&state, &account, wg_id).await?;
+    if let Some(stats) = state.stats_cache.get(wg_id) {
+        trace!("Serving cached stats of watch group {}", wg_id);
+        return Ok(Json(stats));
+    }
+
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group existence: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Watch group {wg_id} not found"),
+        ));
+    }
+
+    let stats = state
+        .db
+        .stats()
+        .get_watch_group_stats(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to compute stats of watch group {}: {}", wg_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    state.stats_cache.insert(wg_id, stats.clone());
+    Ok(Json(stats))
+}
+
+/// counts towards GET /api/stats/bandwidth - only transfers of clients (with id header) are
+/// tracked, a failure is logged and never fails the transfer
+pub(crate) async fn record_transfer(
+    state: &AppState,
+    headers: &HeaderMap,
+    wg_id: i64,
+    uploaded_bytes: u64,
+    downloaded_bytes: u64,
+) {
+    let Some(client_id) = header_value_as_opt_string(headers, CLIENT_ID_HEADER_KEY) else {
+        return;
+    };
+    if let Err(e) = state
+        .db
+        .stats()
+        .record_transfer(
+            &client_id,
+            wg_id,
+            uploaded_bytes,
+            downloaded_bytes,
+            UtcMillis::now(),
+        )
+        .await
+    {
+        warn!("Failed to record transfer of client {client_id}: {e}");
+    }
+}
+
+/// GET /api/stats/bandwidth?hours=..&bucket_hours=..
+pub async fn api_get_bandwidth_stats(
+    State(state): State<AppState>,
+    Query(query): Query<BandwidthQueryDto>,
+) -> Result<Json<BandwidthStatsDto>, (StatusCode, String)> {
+    let hours = query.hours.unwrap_or(BANDWIDTH_HOURS) as u64;
+    let bucket_hours = query.bucket_hours.unwrap_or(1).max(1);
+    let since = UtcMillis::from(
+        UtcMillis::now()
+            .as_u64()
+            .saturating_sub(hours * 60 * 60 * 1000),
+    );
+    let buckets = state
+        .db
+        .stats()
+        .get_bandwidth(since, bucket_hours)
+        .await
+        .map_err(|e| {
+            error!("Failed to load bandwidth stats: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(Json(BandwidthStatsDto {
+        bucket_hours,
+        buckets,
+    }))
+}
+
+/// GET /sys/bandwidth-usage?month=YYYY-MM - what the client reported for the month (zero if
+/// nothing), so a restarted client continues counting where it stopped
+pub async fn get_bandwidth_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BandwidthUsageQueryDto>,
+) -> Result<Json<BandwidthUsageDto>, (StatusCode, String)> {
+    let client_id = authorize_client(&state, &headers).await?;
+    validate_month(&query.month)?;
+    let usage = state
+        .db
+        .stats()
+        .get_bandwidth_usage(client_id, &query.month)
+        .await
+        .map_err(|e| {
+            error!("Failed to load bandwidth usage of {}: {}", client_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(Json(usage.unwrap_or(BandwidthUsageDto {
+        month: query.month,
+        uploaded_bytes: 0,
+        downloaded_bytes: 0,
+        soft_cap_bytes: 0,
+        hard_cap_bytes: 0,
+    })))
+}
+
+/// POST /sys/bandwidth-usage - adds the bytes the client transferred since its last report
+pub async fn post_bandwidth_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(report): Json<BandwidthUsageReportDto>,
+) -> Result<Json<BandwidthUsageDto>, (StatusCode, String)> {
+    let client_id = authorize_client(&state, &headers).await?;
+    validate_month(&report.month)?;
+    let usage = state
+        .db
+        .stats()
+        .add_bandwidth_usage(client_id, &report)
+        .await
+        .map_err(|e| {
+            error!("Failed to store bandwidth usage of {}: {}", client_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if usage.hard_cap_bytes > 0 && usage.total_bytes() >= usage.hard_cap_bytes {
+        warn!(
+            "Client {client_id} reached its hard bandwidth cap for {}",
+            usage.month
+        );
+    }
+    Ok(Json(usage))
+}
+
+/// YYYY-MM
+fn validate_month(month: &str) -> Result<(), (StatusCode, String)> {
+    chrono::NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+        .ok()
+        .filter(|_| month.len() == 7)
+        .map(|_| ())
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid month '{month}' - expected YYYY-MM"),
+        ))
+}
+
+/// YYYY-MM of now (UTC)
+pub(crate) fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_accept_months() {
+        assert!(validate_month("2026-10").is_ok());
+        assert!(validate_month("2026-13").is_err());
+        assert!(validate_month("2026-1").is_err());
+        assert!(validate_month("2026-10-01").is_err());
+        assert_eq!(7, current_month().len());
+    }
+}
@@ -1,34 +1,59 @@
-use crate::client_file_event::{ClientFileEvent, ClientFileEventDto};
+use crate::client_file_event::ClientFileEventDto;
 use crate::file_event::{FileEvent, FileEventType};
-use crate::file_history::FileHistory;
-use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH, multipart};
+use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH, guard, multipart, publish, versions, webhook};
 use axum::Json;
 use axum::extract::{Multipart, State};
-use axum::http::{HeaderMap, StatusCode};
-use axum::response::IntoResponse;
-use shared::dtos::FileDescription;
-use shared::endpoint::{CLIENT_HOST_HEADER_KEY, CLIENT_ID_HEADER_KEY};
-use shared::get_files_of_directory::get_all_file_descriptions;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use shared::dtos::{
+    BulkUploadRejectionDto, BulkUploadResultDto, Durability, FileDescription, PendingKind,
+    ScanWarningDto,
+};
+use shared::endpoint::{
+    CLIENT_HOST_HEADER_KEY, CLIENT_ID_HEADER_KEY, CONFLICT_KEPT_HEADER_KEY,
+    DOWNLOAD_BYTES_HEADER_KEY, FILE_CLIENT_HOST_HEADER_KEY, FILE_UTC_MILLIS_HEADER_KEY,
+    SNAPSHOT_FILES_DIR, SNAPSHOT_MANIFEST_ENTRY, SYNC_PAUSED_HEADER_KEY,
+    SYNC_PAUSED_UNTIL_HEADER_KEY,
+};
+use shared::get_files_of_directory::{ScanLimits, get_all_file_descriptions, get_file_description};
 use shared::matchable_path::MatchablePath;
-use shared::sync_instruction::SyncInstruction;
+use shared::sync_instruction::{SyncInstruction, diff};
+use shared::temp_files::TempFilePatterns;
 use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fs;
-use std::fs::create_dir_all;
+use std::io;
 use std::path::{Component, Path, PathBuf};
-use tokio_util::io::ReaderStream;
-use tracing::{debug, error, info, trace, warn};
+use std::time::Instant;
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tracing::{debug, error, info, instrument, trace, warn};
 use uuid::Uuid;
 
-use super::{header_value_as_opt_string, header_value_as_string};
+use super::pending_delete::{defer_delete, delete_grace, hidden_paths};
+use super::range;
+use super::search::unindex_file;
+use super::stats::record_transfer;
+use super::upload::process_upload;
+use super::{
+    authorize_watch_group_access, client_id_for_log, content_length, ensure_not_paused,
+    header_value_as_opt_string, header_value_as_string, is_sync_paused, parse_file_path,
+    sync_paused_until,
+};
 
-fn upload_path_for_wg(wg_id: i64) -> PathBuf {
+pub(super) fn upload_path_for_wg(wg_id: i64) -> PathBuf {
     UPLOAD_PATH.join(wg_id.to_string())
 }
 
 /// returns list of file meta infos
 pub async fn scan_disk(path: &Path) -> Result<Json<Vec<FileDescription>>, StatusCode> {
-    match get_all_file_descriptions(path, &Vec::new(), true) {
+    match get_all_file_descriptions(
+        path,
+        &Vec::new(),
+        true,
+        &TempFilePatterns::none(),
+        ScanLimits::none(),
+    ) {
         Ok(descriptions) => Ok(Json(descriptions)),
         Err(err) => {
             error!("IO Failure - {}", err);
@@ -37,233 +62,508 @@ pub async fn scan_disk(path: &Path) -> Result<Json<Vec<FileDescription>>, Status
     }
 }
 
+#[instrument(name = "upload", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
 pub async fn upload_handler(
     State(state): State<AppState>,
     axum::extract::Path(wg_id): axum::extract::Path<i64>,
     headers: HeaderMap,
     multipart: Multipart,
 ) -> Result<String, (StatusCode, String)> {
-    let upload_root_path = upload_path_for_wg(wg_id);
-    let dto =
-        multipart::parse_multipart_request(&UPLOAD_TMP_PATH, &mut { multipart }, wg_id).await?;
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    ensure_not_paused(&state, &headers, wg_id).await?;
+    state
+        .upload_disk
+        .ensure_room_for(content_length(&headers))?;
+    let verify_writes = verify_writes(&state, wg_id).await?;
+    let durability = durability(&state, wg_id).await?;
+    let started = Instant::now();
+    let dto = multipart::parse_multipart_request(
+        &UPLOAD_TMP_PATH,
+        &mut { multipart },
+        wg_id,
+        verify_writes,
+        durability,
+        Some(content_length(&headers)),
+        &multipart::MULTIPART_LIMITS,
+    )
+    .await
+    .map(|dto| ClientFileEventDto {
+        conflict_kept: header_value_as_opt_string(&headers, CONFLICT_KEPT_HEADER_KEY)
+            .and_then(|millis| millis.parse::<u64>().ok())
+            .map(UtcMillis::from),
+        ..dto
+    })?;
+    record_transfer(&state, &headers, wg_id, content_length(&headers), 0).await;
 
     let client_host = header_value_as_opt_string(&headers, CLIENT_HOST_HEADER_KEY);
     let client_id = header_value_as_string(&headers, CLIENT_ID_HEADER_KEY)
         .map(|s| s.to_string())
         .ok();
+    if let Some(client_id) = &client_id {
+        let elapsed = started.elapsed();
+        state
+            .pending
+            .observe_upload(client_id, content_length(&headers), elapsed);
+    }
 
-    process_upload(&upload_root_path, state, dto, client_host, client_id)
-        .await
-        .map_err(|(tmp_file_path, status, error_msg)| {
-            if let Some(tmp_file) = tmp_file_path
-                && let Err(e) = fs::remove_file(tmp_file)
-            {
-                tracing::warn!("couldn't clean up tmp file - {e}");
-            }
-            (status, error_msg)
-        })
+    store_upload(state, dto, client_host, client_id).await
 }
 
-async fn process_upload(
-    upload_root_path: &Path,
+/// moves the received temp file into the watch group (see [`process_upload`]) - the temp file
+/// is removed if that fails
+pub(crate) async fn store_upload(
     state: AppState,
     dto: ClientFileEventDto,
     client_host: Option<String>,
     client_id: Option<String>,
-) -> Result<String, (Option<PathBuf>, StatusCode, String)> {
-    let tmp_file_path_cpy = dto.temp_file_path.clone();
+) -> Result<String, (StatusCode, String)> {
+    let upload_root_path = upload_path_for_wg(dto.watch_group_id);
     let wg_id = dto.watch_group_id;
-    // map to domain object (FileEvent)
-    match ClientFileEvent::try_from(dto) {
-        Err(e) => Err((tmp_file_path_cpy, StatusCode::BAD_REQUEST, e)),
-        Ok(event) => {
-            let utc_millis_of_latest_history_event = state
-                .history
-                .get_latest_event(wg_id, &event.relative_path)
-                .map(|e| e.utc_millis)
-                .unwrap_or(UtcMillis::from(0));
-
-            if event.utc_millis < utc_millis_of_latest_history_event {
-                warn!(
-                    "Skipping upload & event for {:?} - event ({:?}) older than latest history state event ({:?})",
-                    &event.relative_path, utc_millis_of_latest_history_event, event.utc_millis
-                );
-                return Err((
-                    event.temp_file_path,
-                    StatusCode::BAD_REQUEST,
-                    "not latest".to_string(),
-                ));
-            }
+    let path = dto.relative_path.clone().map(MatchablePath::from);
+    let pending = state.pending.clone();
+    process_upload(
+        &upload_root_path,
+        state,
+        dto,
+        client_host,
+        client_id.clone(),
+    )
+    .await
+    .inspect(|_| {
+        if let (Some(client_id), Some(path)) = (&client_id, &path) {
+            pending.complete(client_id, wg_id, PendingKind::Upload, path);
+        }
+    })
+    .map_err(|(tmp_file_path, status, error_msg)| {
+        if let Some(tmp_file) = tmp_file_path
+            && let Err(e) = fs::remove_file(tmp_file)
+        {
+            tracing::warn!("couldn't clean up tmp file - {e}");
+        }
+        (status, error_msg)
+    })
+}
 
-            let sub_path = event
-                .relative_path
-                .get()
-                .iter()
-                .map(|part| Component::Normal(part.as_ref()));
-            let target_path: PathBuf = upload_root_path.components().chain(sub_path).collect();
-            let temp_path: PathBuf = event.temp_file_path.clone().unwrap();
-            let io_result = {
-                create_dir_all(target_path.parent().unwrap_or(Path::new("./"))).map_err(|e| {
-                    (
-                        event.temp_file_path.clone(),
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Could not create dir - {}", e),
-                    )
-                })?;
-                let result = fs::rename(temp_path.as_path(), target_path.as_path());
-                let was_success = result.is_ok();
-                let temp_path = temp_path.as_path();
-                let target_path = target_path.as_path();
-                if !was_success {
-                    let result_delete_temp = if fs::remove_file(temp_path).is_ok() {
-                        "was successful"
-                    } else {
-                        "failed aswell"
-                    };
-
-                    warn!(
-                        "moving failed - deleting temp file {} - {:?} -> {:?}",
-                        result_delete_temp, temp_path, target_path,
-                    );
-                } else {
-                    info!(
-                        "moving was successful - {:?} -> {:?}",
-                        temp_path, target_path
-                    );
-                }
-                result
-            };
-
-            let path_str = target_path.to_string_lossy();
-            match io_result {
-                Ok(_) => {
-                    let message = format!("Updated {} successfully", path_str);
-                    let mut fe = FileEvent::from(event);
-                    fe.client_host = client_host;
-                    // write to DB
-                    if let Some(ref cid) = client_id {
-                        if let Err(e) = state.db.file_event().insert(&fe, cid).await {
-                            error!("Failed to persist file event to DB: {e}");
-                        }
-                    } else {
-                        warn!("No client_id header — file event not persisted to DB");
-                    }
-                    // add to in-mem state
-                    state.history.clone().add(fe);
-                    info!("{message}");
-                    Ok(message)
+/// initial sync of many files - see [`multipart::parse_bulk_multipart_request`]
+///
+/// every file is processed like a single upload, so rejections (e.g. "not latest") are per file
+#[instrument(name = "upload_bulk", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
+pub async fn upload_bulk_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> Result<Json<BulkUploadResultDto>, (StatusCode, String)> {
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    ensure_not_paused(&state, &headers, wg_id).await?;
+    state
+        .upload_disk
+        .ensure_room_for(content_length(&headers))?;
+    let upload_root_path = upload_path_for_wg(wg_id);
+    let verify_writes = verify_writes(&state, wg_id).await?;
+    let durability = durability(&state, wg_id).await?;
+    let (manifest, archive_path) =
+        multipart::parse_bulk_multipart_request(&UPLOAD_TMP_PATH, &mut { multipart }).await?;
+
+    let archive_path_cpy = archive_path.clone();
+    let unpacked = tokio::task::spawn_blocking(move || {
+        multipart::unpack_bulk_archive(
+            &archive_path_cpy,
+            manifest,
+            &UPLOAD_TMP_PATH,
+            wg_id,
+            verify_writes,
+            durability,
+        )
+    })
+    .await;
+    if let Err(e) = fs::remove_file(&archive_path) {
+        warn!("couldn't clean up bulk archive - {e}");
+    }
+    let (uploads, missing) = match unpacked {
+        Ok(Ok(unpacked)) => unpacked,
+        Ok(Err(e)) => return Err((StatusCode::BAD_REQUEST, format!("Invalid archive - {e}"))),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    };
+    record_transfer(&state, &headers, wg_id, content_length(&headers), 0).await;
+
+    let client_host = header_value_as_opt_string(&headers, CLIENT_HOST_HEADER_KEY);
+    let client_id = header_value_as_string(&headers, CLIENT_ID_HEADER_KEY)
+        .map(|s| s.to_string())
+        .ok();
+
+    let mut result = BulkUploadResultDto::default();
+    result
+        .rejected
+        .extend(missing.into_iter().map(|d| BulkUploadRejectionDto {
+            relative_path: d.relative_path.to_serialized_string(),
+            reason: "missing in archive".to_string(),
+        }));
+    for dto in uploads {
+        let relative_path = dto.relative_path.clone().unwrap_or_default().join("/");
+        match process_upload(
+            &upload_root_path,
+            state.clone(),
+            dto,
+            client_host.clone(),
+            client_id.clone(),
+        )
+        .await
+        {
+            Ok(_) => {
+                if let Some(client_id) = &client_id {
+                    let path = MatchablePath::from(relative_path.as_str());
+                    state
+                        .pending
+                        .complete(client_id, wg_id, PendingKind::Upload, &path);
                 }
-                Err(e) => {
-                    let message = format!("Updating {} failed - {}", path_str, e);
-                    error!("{message}");
-                    Err((None, StatusCode::INTERNAL_SERVER_ERROR, message))
+                result.stored.push(relative_path)
+            }
+            Err((tmp_file_path, _, reason)) => {
+                if let Some(tmp_file) = tmp_file_path
+                    && let Err(e) = fs::remove_file(tmp_file)
+                {
+                    warn!("couldn't clean up tmp file - {e}");
                 }
+                result.rejected.push(BulkUploadRejectionDto {
+                    relative_path,
+                    reason,
+                });
             }
         }
     }
+
+    info!(
+        stored = result.stored.len(),
+        rejected = result.rejected.len(),
+        "Bulk upload processed"
+    );
+    Ok(Json(result))
 }
 
+pub(crate) async fn verify_writes(
+    state: &AppState,
+    wg_id: i64,
+) -> Result<bool, (StatusCode, String)> {
+    state
+        .db
+        .server_watch_group()
+        .verify_writes(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to read verify_writes of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+
+pub(crate) async fn durability(
+    state: &AppState,
+    wg_id: i64,
+) -> Result<Durability, (StatusCode, String)> {
+    state
+        .db
+        .server_watch_group()
+        .durability(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to read durability of watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+#[instrument(name = "sync", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
 pub async fn sync_handler(
     State(state): State<AppState>,
     axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    headers: HeaderMap,
     Json(client_sync_state): Json<Vec<FileDescription>>,
-) -> Result<Json<Vec<SyncInstruction>>, (StatusCode, String)> {
-    trace!("Client state received {:#?}", client_sync_state);
-    let mut instructions = Vec::new();
-    let target = state.history.clone().get_latest_events(wg_id);
-
-    for event in target.clone() {
-        match client_sync_state.iter().find(|client_file_description| {
-            client_file_description.relative_path == event.relative_path
-        }) {
-            // client doesn't have the file at all
-            None => {
-                if event.event_type != FileEventType::DeleteEvent {
-                    instructions.push(SyncInstruction::Download(event.relative_path))
-                }
-            }
-            Some(client_equivalent) => {
-                trace!(
-                    "Server has {} - client has {}",
-                    event.utc_millis, client_equivalent.last_updated_utc_millis
-                );
-
-                if client_equivalent.size_in_bytes == event.size_in_bytes
-                    && event.event_type.is_change()
-                {
-                    // same size - just ignore even if timestamps differ (might have been write-operation without change)
-                    continue;
-                } else if client_equivalent.last_updated_utc_millis < event.utc_millis {
-                    // differs in size and client is outdated
-                    match event.event_type {
-                        FileEventType::ChangeEvent => {
-                            // client outdated needs to download new version
-                            instructions.push(SyncInstruction::Download(event.relative_path))
-                        }
-                        FileEventType::DeleteEvent => {
-                            // client outdated needs to delete his version
-                            instructions.push(SyncInstruction::Delete(event.relative_path))
-                        }
-                    }
-                } else {
-                    instructions.push(SyncInstruction::Upload(event.relative_path))
-                }
-            }
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    if is_sync_paused(&state, &headers, wg_id).await? {
+        debug!("Sync of watch group {} is paused", wg_id);
+        let mut reply_headers = HeaderMap::new();
+        reply_headers.insert(SYNC_PAUSED_HEADER_KEY, HeaderValue::from_static("true"));
+        if let Some(until) = sync_paused_until(&state, &headers).await {
+            reply_headers.insert(
+                SYNC_PAUSED_UNTIL_HEADER_KEY,
+                HeaderValue::from(until.as_u64()),
+            );
         }
+        return Ok((reply_headers, Json(Vec::new())));
     }
+    let client_id = header_value_as_opt_string(&headers, CLIENT_ID_HEADER_KEY);
+    let (instructions, download_bytes) =
+        sync_instructions(&state, wg_id, client_id.as_deref(), client_sync_state).await;
+    let mut reply_headers = HeaderMap::new();
+    reply_headers.insert(DOWNLOAD_BYTES_HEADER_KEY, HeaderValue::from(download_bytes));
+    Ok((reply_headers, Json(instructions)))
+}
 
-    for desc in client_sync_state {
-        if !target.iter().any(|e| e.relative_path == desc.relative_path) {
-            instructions.push(SyncInstruction::Upload(desc.relative_path));
+/// compares the state of the client with the latest events of the watch group - and remembers
+/// the client's file list as well as the outcome as its pending work
+///
+/// also returns the total size of the downloads among the instructions
+pub(crate) async fn sync_instructions(
+    state: &AppState,
+    wg_id: i64,
+    client_id: Option<&str>,
+    client_sync_state: Vec<FileDescription>,
+) -> (Vec<SyncInstruction>, u64) {
+    trace!("Client state received {:#?}", client_sync_state);
+    let target = state.history.clone().get_latest_events(wg_id);
+    let hidden = hidden_paths(state, wg_id).await;
+    let instructions: Vec<_> = diff(&target, &client_sync_state)
+        .into_iter()
+        .filter(|i| !hidden.contains(i.path()))
+        .collect();
+    if let Some(client_id) = client_id {
+        state
+            .pending
+            .record(client_id, wg_id, &instructions, &target, &client_sync_state);
+        if let Err(e) = state
+            .db
+            .client_file_state()
+            .save(client_id, wg_id, &client_sync_state, UtcMillis::now())
+            .await
+        {
+            error!("Failed to persist file list of client: {e}");
         }
     }
 
     if !instructions.is_empty() {
-        info!("Instructions {:#?}", instructions);
+        info!(
+            instructions = instructions.len(),
+            "Instructions {:#?}", instructions
+        );
     }
-    Ok(Json(instructions))
+    let sizes: HashMap<&MatchablePath, u64> = target
+        .iter()
+        .map(|e| (&e.relative_path, e.size_in_bytes))
+        .collect();
+    let download_bytes = instructions
+        .iter()
+        .filter(|i| matches!(i, SyncInstruction::Download(_)))
+        .filter_map(|i| sizes.get(i.path()))
+        .sum();
+    (instructions, download_bytes)
 }
 
 /// expects payload with plain string path (unix-delimiter) like:
 /// `some/path/to/download/file.txt`
+#[instrument(name = "download", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
 pub async fn download(
+    State(state): State<AppState>,
     axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    headers: HeaderMap,
     payload: String,
-) -> impl IntoResponse {
-    let upload_root_path = upload_path_for_wg(wg_id);
-    let sub_path: PathBuf = MatchablePath::from(payload.split('/').collect::<Vec<&str>>())
-        .get()
-        .iter()
-        .map(|part| Component::Normal(OsStr::new(part)))
-        .collect();
-    let p = upload_root_path.join(sub_path);
+) -> Result<Response, (StatusCode, String)> {
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    let path = parse_file_path(&payload)?;
+    let (file, file_name) = open_download(wg_id, &path).await?;
+    let (mut response, len) = range::serve_file(file, &file_name, &headers).await?;
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(response);
+    }
+    if let Some(latest) = latest_change(&state, wg_id, &path) {
+        response.headers_mut().insert(
+            FILE_UTC_MILLIS_HEADER_KEY,
+            HeaderValue::from(latest.utc_millis.as_u64()),
+        );
+        // hostnames that aren't valid header values are left out
+        if let Some(host) = latest
+            .client_host
+            .and_then(|host| HeaderValue::from_str(&host).ok())
+        {
+            response
+                .headers_mut()
+                .insert(FILE_CLIENT_HOST_HEADER_KEY, host);
+        }
+    }
+    record_transfer(&state, &headers, wg_id, 0, len).await;
+    if let Some(client_id) = header_value_as_opt_string(&headers, CLIENT_ID_HEADER_KEY) {
+        state
+            .pending
+            .complete(&client_id, wg_id, PendingKind::Download, &path);
+    }
+    Ok(response)
+}
+
+/// returns the opened file and its name
+pub(crate) async fn open_download(
+    wg_id: i64,
+    path: &MatchablePath,
+) -> Result<(tokio::fs::File, String), (StatusCode, String)> {
+    let p = download_path(wg_id, path);
     let file_name = p.file_name().unwrap().to_string_lossy().to_string();
     let file = match tokio::fs::File::open(p).await {
         Ok(file) => file,
         Err(err) => return Err((StatusCode::NOT_FOUND, format!("File not found: {}", err))),
     };
-    let stream = ReaderStream::new(file);
-    let body = axum::body::Body::from_stream(stream);
+    let bytes = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+    info!(path = %path.to_serialized_string(), bytes, "Serving download of {}", file_name);
+    Ok((file, file_name))
+}
+
+/// the file's latest (change) event - downloads are stamped with its time
+pub(crate) fn latest_change(
+    state: &AppState,
+    wg_id: i64,
+    path: &MatchablePath,
+) -> Option<FileEvent> {
+    state
+        .history
+        .get_latest_event(wg_id, path)
+        .filter(|e| e.event_type.is_change())
+}
+
+/// where the file is stored in the watch group
+pub(crate) fn download_path(wg_id: i64, path: &MatchablePath) -> PathBuf {
+    let sub_path: PathBuf = path
+        .get()
+        .iter()
+        .map(|part| Component::Normal(OsStr::new(part)))
+        .collect();
+    upload_path_for_wg(wg_id).join(sub_path)
+}
+
+/// streams a tar of the current state - [`SNAPSHOT_MANIFEST_ENTRY`] first, then the files
+/// below [`SNAPSHOT_FILES_DIR`]
+///
+/// files vanishing while streaming are skipped, the client picks them up with the next sync
+#[instrument(name = "download_snapshot", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
+pub async fn download_snapshot(
+    State(state): State<AppState>,
+    axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    let upload_root_path = upload_path_for_wg(wg_id);
+    let latest_events = state.history.get_latest_events(wg_id);
+    // roughly - the tar adds headers, files changed meanwhile are skipped
+    let size = latest_events
+        .iter()
+        .filter(|e| e.event_type.is_change())
+        .map(|e| e.size_in_bytes)
+        .sum();
+    record_transfer(&state, &headers, wg_id, 0, size).await;
 
-    let headers = [(
-        axum::http::header::CONTENT_DISPOSITION,
-        format!("attachment; filename=\"{}\"", file_name),
-    )];
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let writer = SyncIoBridge::new(writer);
+    tokio::task::spawn_blocking(move || {
+        match write_snapshot(writer, &upload_root_path, latest_events) {
+            Ok(count) => info!("Served snapshot of {count} files"),
+            Err(e) => warn!("Snapshot aborted - {e}"),
+        }
+    });
 
-    Ok((headers, body))
+    let headers = [
+        (
+            axum::http::header::CONTENT_TYPE,
+            "application/x-tar".to_string(),
+        ),
+        (
+            axum::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"snapshot_{wg_id}.tar\""),
+        ),
+    ];
+    Ok((
+        headers,
+        axum::body::Body::from_stream(ReaderStream::new(reader)),
+    ))
 }
 
+fn write_snapshot(
+    writer: impl io::Write,
+    upload_root_path: &Path,
+    latest_events: Vec<FileEvent>,
+) -> io::Result<usize> {
+    let manifest: Vec<(PathBuf, FileDescription)> = latest_events
+        .into_iter()
+        .filter(|e| e.event_type.is_change())
+        .filter_map(|e| {
+            let file = e.relative_path.resolve(upload_root_path);
+            let description = get_file_description(&file, upload_root_path).ok()?;
+            Some((
+                file,
+                FileDescription {
+                    last_updated_utc_millis: e.utc_millis,
+                    ..description
+                },
+            ))
+        })
+        .collect();
+    let manifest_json = serde_json::to_vec(
+        &manifest
+            .iter()
+            .map(|(_, description)| description)
+            .collect::<Vec<_>>(),
+    )
+    .map_err(io::Error::other)?;
+
+    let mut builder = tar::Builder::new(writer);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(
+        &mut header,
+        SNAPSHOT_MANIFEST_ENTRY,
+        manifest_json.as_slice(),
+    )?;
+
+    let mut count = 0;
+    for (file, description) in &manifest {
+        let name = format!(
+            "{SNAPSHOT_FILES_DIR}/{}",
+            description.relative_path.get().join("/")
+        );
+        match builder.append_path_with_name(file, name) {
+            Ok(()) => count += 1,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                debug!("Skipping vanished file {:?}", file)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    builder.finish()?;
+    Ok(count)
+}
+
+/// the client stopped scanning the watch group (too many files / too deep) or is about to -
+/// shown as warning in the admin ui until the client reports a normal scan again
+#[instrument(name = "scan_warning", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
+pub async fn scan_warning(
+    State(state): State<AppState>,
+    axum::extract::Path(wg_id): axum::extract::Path<i64>,
+    headers: HeaderMap,
+    Json(dto): Json<ScanWarningDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    let client_id = header_value_as_string(&headers, CLIENT_ID_HEADER_KEY)?;
+    match &dto.message {
+        Some(message) => warn!("Client reported scan problem - {message}"),
+        None => info!("Client reported a normal scan again"),
+    }
+    state
+        .db
+        .client_watch_group()
+        .set_scan_warning(client_id, wg_id, dto.message.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to store scan warning: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[instrument(name = "delete", skip_all, fields(watch_group_id = wg_id, client_id = %client_id_for_log(&headers)))]
 pub async fn delete(
     state: State<AppState>,
     axum::extract::Path(wg_id): axum::extract::Path<i64>,
     headers: HeaderMap,
     payload: String,
 ) -> Result<(), (StatusCode, String)> {
-    let upload_path = upload_path_for_wg(wg_id);
-    debug!("Received delete request for '{}'", payload);
-    let matchable_path = MatchablePath::from(payload.as_str());
-    let p = matchable_path.resolve(&upload_path);
-    let millis = UtcMillis::now();
+    authorize_watch_group_access(&state, &headers, wg_id).await?;
+    ensure_not_paused(&state, &headers, wg_id).await?;
     let client_host = header_value_as_opt_string(&headers, CLIENT_HOST_HEADER_KEY);
     let client_id = header_value_as_string(&headers, CLIENT_ID_HEADER_KEY)
         .map(|s| s.to_string())
@@ -273,6 +573,44 @@ pub async fn delete(
                 "Missing X-Client-Id header — delete refused".to_string(),
             )
         })?;
+    delete_file(&state, wg_id, &payload, client_host, client_id).await
+}
+
+/// `payload` is the unix-delimited path - a missing file is recorded as deleted anyway
+/// (replies with an `Err` of status 200 then)
+///
+/// with a grace period the delete is only recorded as pending, see [`defer_delete`]
+pub(crate) async fn delete_file(
+    state: &AppState,
+    wg_id: i64,
+    payload: &str,
+    client_host: Option<String>,
+    client_id: String,
+) -> Result<(), (StatusCode, String)> {
+    debug!(path = %payload, "Received delete request for '{}'", payload);
+    let matchable_path = MatchablePath::from(payload);
+    if let Some(grace) = delete_grace()
+        && matchable_path.resolve(&upload_path_for_wg(wg_id)).exists()
+    {
+        return defer_delete(state, wg_id, matchable_path, client_host, &client_id, grace).await;
+    }
+    let event = remove_now(state, wg_id, matchable_path, client_host, &client_id).await?;
+    guard::observe(state, &event, &client_id);
+    Ok(())
+}
+
+/// removes the file and records the delete event - `Err` with status 200 if it's gone already
+pub(super) async fn remove_now(
+    state: &AppState,
+    wg_id: i64,
+    matchable_path: MatchablePath,
+    client_host: Option<String>,
+    client_id: &str,
+) -> Result<FileEvent, (StatusCode, String)> {
+    let upload_path = upload_path_for_wg(wg_id);
+    let payload = matchable_path.to_serialized_string();
+    let p = matchable_path.resolve(&upload_path);
+    let millis = UtcMillis::now();
 
     let event = FileEvent::new(
         Uuid::new_v4(),
@@ -285,8 +623,11 @@ pub async fn delete(
     );
 
     if !p.exists() {
-        state.history.add(event);
-        info!("Skip delete because file doesn't exist");
+        unindex_file(state, wg_id, &event.relative_path).await;
+        // not persisted - it only tells syncs the file is gone
+        let _ = state.events.record(&event, None).await;
+        publish::mirror(state, &event);
+        info!(path = %payload, event_type = "delete", "Skip delete because file doesn't exist");
         return Err((
             StatusCode::OK,
             "Nothing to do, because file doesn't exist (could've been deleted by someone else)"
@@ -294,15 +635,23 @@ pub async fn delete(
         ));
     }
 
+    versions::keep_current(state.history.as_ref(), wg_id, &event.relative_path, &p);
     match tokio::fs::remove_file(&p).await {
         Ok(()) => {
-            if let Err(e) = state.db.file_event().insert(&event, &client_id).await {
+            if let Err(e) = state.events.record(&event, Some(client_id)).await {
                 error!("Failed to persist delete event to DB: {e}");
             }
-            state.history.add(event);
-            info!("Deleted {} successfully", &p.to_string_lossy());
+            unindex_file(state, wg_id, &event.relative_path).await;
+            webhook::notify(state, &event, Some(client_id));
+            publish::mirror(state, &event);
+            info!(
+                path = %payload,
+                event_type = "delete",
+                "Deleted {} successfully",
+                &p.to_string_lossy()
+            );
             info!("Added delete event with time {} to history", millis);
-            Ok(())
+            Ok(event)
         }
         Err(err) => {
             info!("Failed to delete file: {}", err);
@@ -310,3 +659,107 @@ pub async fn delete(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WG: i64 = 1;
+
+    fn event(path: &str, millis: u64, size: u64, event_type: FileEventType) -> FileEvent {
+        FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(millis),
+            MatchablePath::from(path),
+            size,
+            event_type,
+            None,
+            WG,
+        )
+    }
+
+    fn file(path: &str, millis: u64, size: u64) -> FileDescription {
+        FileDescription {
+            file_name: path.to_string(),
+            relative_path: MatchablePath::from(path),
+            size_in_bytes: size,
+            file_type: String::new(),
+            last_updated_utc_millis: UtcMillis::from(millis),
+        }
+    }
+
+    fn summary(instructions: &[SyncInstruction]) -> Vec<String> {
+        instructions
+            .iter()
+            .map(|i| match i {
+                SyncInstruction::Upload(p) => format!("upload {}", p.to_serialized_string()),
+                SyncInstruction::Download(p) => format!("download {}", p.to_serialized_string()),
+                SyncInstruction::Delete(p) => format!("delete {}", p.to_serialized_string()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_diff_server_and_client_state() {
+        use FileEventType::*;
+        let target = [
+            event("missing.txt", 100, 1, ChangeEvent),
+            event("deleted.txt", 100, 0, DeleteEvent),
+            event("same.txt", 100, 1, ChangeEvent),
+            event("outdated.txt", 200, 2, ChangeEvent),
+            event("deleted-meanwhile.txt", 200, 0, DeleteEvent),
+            event("newer.txt", 100, 1, ChangeEvent),
+        ];
+        let client = [
+            file("same.txt", 100, 1),
+            file("outdated.txt", 100, 1),
+            file("deleted-meanwhile.txt", 100, 1),
+            file("newer.txt", 200, 2),
+            file("new.txt", 100, 1),
+        ];
+
+        assert_eq!(
+            vec![
+                "download missing.txt",
+                "download outdated.txt",
+                "delete deleted-meanwhile.txt",
+                "upload newer.txt",
+                "upload new.txt",
+            ],
+            summary(&diff(&target, &client))
+        );
+    }
+
+    /// `cargo test -p server --release sync_diff_throughput -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn sync_diff_throughput() {
+        const FILES: u64 = 100_000;
+        let target: Vec<FileEvent> = (0..FILES)
+            .map(|i| {
+                event(
+                    &format!("dir/{}/file-{i}.txt", i % 100),
+                    i,
+                    i,
+                    FileEventType::ChangeEvent,
+                )
+            })
+            .collect();
+        // every 10th file changed on the client, every 10th one only known to the client
+        let client: Vec<FileDescription> = (0..FILES)
+            .map(|i| match i % 10 {
+                0 => file(&format!("dir/{}/file-{i}.txt", i % 100), i + 1, i + 1),
+                1 => file(&format!("client-only/file-{i}.txt"), i, i),
+                _ => file(&format!("dir/{}/file-{i}.txt", i % 100), i, i),
+            })
+            .collect();
+
+        let start = Instant::now();
+        let instructions = diff(&target, &client);
+        let elapsed = start.elapsed();
+
+        // 10% uploads of changes, 10% downloads of files the client lacks, 10% client-only uploads
+        assert_eq!(3 * FILES as usize / 10, instructions.len());
+        println!("diffed {FILES} files in {}ms", elapsed.as_millis());
+    }
+}
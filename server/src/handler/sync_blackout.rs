@@ -0,0 +1,255 @@
+use crate::AppState;
+use crate::db::SyncBlackout;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{Datelike, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+use shared::dtos::{SyncBlackoutCreateDto, SyncBlackoutDto};
+use shared::utc_millis::UtcMillis;
+use tracing::{error, info};
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+const EVERY_DAY: i64 = 0b111_1111;
+/// adjoining windows (e.g. 22:00-00:00 and 00:00-06:00) are followed at most that far
+const MAX_CHAINED_WINDOWS: usize = 16;
+const MAX_NOTE_LEN: usize = 200;
+
+/// GET /api/clients/{id}/sync-blackouts
+pub async fn api_list_sync_blackouts(
+    State(state): State<AppState>,
+    Path(client_id): Path<String>,
+) -> Result<Json<Vec<SyncBlackoutDto>>, (StatusCode, String)> {
+    let blackouts = state
+        .db
+        .sync_blackout()
+        .list_for_client(&client_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list sync blackouts: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(Json(blackouts.into_iter().map(to_dto).collect()))
+}
+
+/// POST /api/clients/{id}/sync-blackouts
+pub async fn api_create_sync_blackout(
+    State(state): State<AppState>,
+    Path(client_id): Path<String>,
+    Json(dto): Json<SyncBlackoutCreateDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, message);
+    let start = parse_time(&dto.start).ok_or_else(|| bad_request("Invalid start".to_string()))?;
+    let end = parse_time(&dto.end).ok_or_else(|| bad_request("Invalid end".to_string()))?;
+    let weekdays = weekday_mask(&dto.weekdays)
+        .ok_or_else(|| bad_request("Weekdays must be 1 (monday) to 7 (sunday)".to_string()))?;
+    let note = dto.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if note.is_some_and(|n| n.len() > MAX_NOTE_LEN) {
+        return Err(bad_request(format!(
+            "Note must have at most {MAX_NOTE_LEN} characters"
+        )));
+    }
+
+    let db_error = |e: sqlx::Error| {
+        error!("Failed to create sync blackout: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    if state
+        .db
+        .client()
+        .get_client_by_id(&client_id)
+        .await
+        .map_err(db_error)?
+        .is_none()
+    {
+        return Err((StatusCode::NOT_FOUND, "Client not found".to_string()));
+    }
+    let id = state
+        .db
+        .sync_blackout()
+        .create(&client_id, weekdays, start, end, note)
+        .await
+        .map_err(db_error)?;
+
+    info!(
+        "Created sync blackout {} ({}-{}) for client {}",
+        id, dto.start, dto.end, client_id
+    );
+    Ok(StatusCode::CREATED)
+}
+
+/// DELETE /api/clients/{id}/sync-blackouts/{blackout_id}
+pub async fn api_delete_sync_blackout(
+    State(state): State<AppState>,
+    Path((client_id, blackout_id)): Path<(String, i64)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state
+        .db
+        .sync_blackout()
+        .delete(&client_id, blackout_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete sync blackout: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!(
+            "Deleted sync blackout {} of client {}",
+            blackout_id, client_id
+        );
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Sync blackout not found".to_string()))
+    }
+}
+
+/// end of the blackout the client is in right now - None if it may sync
+pub(crate) async fn sync_blackout_until(
+    state: &AppState,
+    client_id: &str,
+) -> sqlx::Result<Option<UtcMillis>> {
+    let blackouts = state.db.sync_blackout().list_for_client(client_id).await?;
+    if blackouts.is_empty() {
+        return Ok(None);
+    }
+    let Some(end) = blackout_end(&blackouts, Local::now().naive_local()) else {
+        return Ok(None);
+    };
+    // a time skipped by a DST change ends the blackout right away
+    let end = Local
+        .from_local_datetime(&end)
+        .earliest()
+        .map_or_else(UtcMillis::now, |end| {
+            UtcMillis::from(end.timestamp_millis().max(0) as u64)
+        });
+    Ok(Some(end))
+}
+
+/// end of the window(s) covering `now` - adjoining windows count as one
+fn blackout_end(blackouts: &[SyncBlackout], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let mut end: Option<NaiveDateTime> = None;
+    let mut at = now;
+    for _ in 0..MAX_CHAINED_WINDOWS {
+        match blackouts.iter().filter_map(|b| window_end(b, at)).max() {
+            Some(window_end) if end.is_none_or(|end| window_end > end) => {
+                end = Some(window_end);
+                at = window_end;
+            }
+            _ => break,
+        }
+    }
+    end
+}
+
+/// end of the window if it covers `at` - it may have started the day before
+fn window_end(blackout: &SyncBlackout, at: NaiveDateTime) -> Option<NaiveDateTime> {
+    let length = match blackout.end_minute - blackout.start_minute {
+        length if length > 0 => length,
+        length => length + MINUTES_PER_DAY,
+    };
+    let minute = (at.hour() * 60 + at.minute()) as i64;
+    [0, 1].into_iter().find_map(|days_ago| {
+        let start_day = at.date() - Duration::days(days_ago);
+        let since_start = minute + days_ago * MINUTES_PER_DAY - blackout.start_minute;
+        let starts_that_day =
+            blackout.weekdays & (1 << start_day.weekday().num_days_from_monday()) != 0;
+        (starts_that_day && (0..length).contains(&since_start)).then(|| {
+            start_day.and_time(NaiveTime::MIN) + Duration::minutes(blackout.start_minute + length)
+        })
+    })
+}
+
+/// `HH:MM` as minutes after midnight
+fn parse_time(time: &str) -> Option<i64> {
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()?;
+    Some((time.hour() * 60 + time.minute()) as i64)
+}
+
+fn format_time(minutes: i64) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// every day if empty - None for invalid days
+fn weekday_mask(weekdays: &[u8]) -> Option<i64> {
+    if weekdays.is_empty() {
+        return Some(EVERY_DAY);
+    }
+    weekdays.iter().try_fold(0, |mask, day| {
+        (1..=7).contains(day).then(|| mask | 1 << (day - 1))
+    })
+}
+
+fn to_dto(blackout: SyncBlackout) -> SyncBlackoutDto {
+    let weekdays = if blackout.weekdays & EVERY_DAY == EVERY_DAY {
+        Vec::new()
+    } else {
+        (1..=7u8)
+            .filter(|day| blackout.weekdays & 1 << (day - 1) != 0)
+            .collect()
+    };
+    SyncBlackoutDto {
+        id: blackout.id,
+        weekdays,
+        start: format_time(blackout.start_minute),
+        end: format_time(blackout.end_minute),
+        note: blackout.note,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn blackout(weekdays: i64, start: &str, end: &str) -> SyncBlackout {
+        SyncBlackout {
+            id: 1,
+            weekdays,
+            start_minute: parse_time(start).unwrap(),
+            end_minute: parse_time(end).unwrap(),
+            note: None,
+        }
+    }
+
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        // 2026-06-01 is a monday
+        NaiveDate::from_ymd_opt(2026, 6, day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    #[test]
+    fn should_find_end_of_blackout_covering_now() {
+        let nightly_on_monday = blackout(weekday_mask(&[1]).unwrap(), "22:00", "06:00");
+        let blackouts = [nightly_on_monday.clone()];
+
+        assert_eq!(None, blackout_end(&blackouts, at(1, "21:59")));
+        assert_eq!(
+            Some(at(2, "06:00")),
+            blackout_end(&blackouts, at(1, "22:00"))
+        );
+        // started on monday - reaches into tuesday
+        assert_eq!(
+            Some(at(2, "06:00")),
+            blackout_end(&blackouts, at(2, "05:59"))
+        );
+        assert_eq!(None, blackout_end(&blackouts, at(2, "06:00")));
+        // doesn't start on tuesday
+        assert_eq!(None, blackout_end(&blackouts, at(2, "23:00")));
+
+        // adjoining windows are followed
+        let chained = [nightly_on_monday, blackout(EVERY_DAY, "06:00", "08:30")];
+        assert_eq!(Some(at(2, "08:30")), blackout_end(&chained, at(1, "23:00")));
+
+        // same start and end - the whole day
+        let all_day = [blackout(weekday_mask(&[1]).unwrap(), "00:00", "00:00")];
+        assert_eq!(Some(at(2, "00:00")), blackout_end(&all_day, at(1, "12:00")));
+
+        assert_eq!(None, weekday_mask(&[0]));
+        assert_eq!(None, parse_time("24:00"));
+        assert_eq!(
+            vec![1, 7],
+            to_dto(blackout(weekday_mask(&[7, 1]).unwrap(), "01:05", "02:00")).weekdays
+        );
+    }
+}
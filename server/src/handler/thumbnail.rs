@@ -0,0 +1,55 @@
+use crate::thumbnail::{self, DEFAULT_SIZE};
+use axum::extract::{Path, Query};
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use std::collections::HashMap;
+use tracing::warn;
+
+use super::file_path_param;
+use super::sync::upload_path_for_wg;
+
+/// GET /api/thumbnail/{wg_id}?path=dir/photo.jpg&size=256 - jpeg fitting into size x size
+///
+/// rendered on first request (if the upload didn't already) and cached until the image changes
+pub async fn api_get_thumbnail(
+    Path(wg_id): Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let path = file_path_param(&params)?;
+    let size = match params.get("size") {
+        Some(size) => size
+            .parse()
+            .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid size: {size}")))?,
+        None => DEFAULT_SIZE,
+    };
+
+    if !thumbnail::is_thumbnailable(&path) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "No thumbnails for this file type".to_string(),
+        ));
+    }
+    let file = path.resolve(&upload_path_for_wg(wg_id));
+    if !file.is_file() {
+        return Err((StatusCode::NOT_FOUND, "File not found".to_string()));
+    }
+
+    let size = thumbnail::snap_size(size);
+    let rendered = tokio::task::spawn_blocking(move || {
+        thumbnail::get_or_render(wg_id, &path, &file, size).map_err(|e| {
+            warn!("Failed to render thumbnail of {:?} - {}", file, e);
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Failed to render thumbnail: {e}"),
+            )
+        })
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))??;
+
+    let bytes = tokio::fs::read(rendered)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(([(CONTENT_TYPE, "image/jpeg")], bytes))
+}
@@ -0,0 +1,195 @@
+use crate::file_event::{FileEvent, FileEventType};
+use crate::{AppState, UPLOAD_PATH, UPLOAD_TMP_PATH, thumbnail, versions};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use shared::dtos::{TransferPathDto, TransferResultDto};
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::path::{Component, Path};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::bulk::{affected_files, persist, prune};
+use super::search::index_file;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    Copy,
+    Move,
+}
+
+/// POST /api/admin/copy - copies the files below the prefix into another watch group
+pub async fn api_copy(
+    State(state): State<AppState>,
+    Json(dto): Json<TransferPathDto>,
+) -> Result<Json<TransferResultDto>, (StatusCode, String)> {
+    transfer(state, dto, Transfer::Copy).await
+}
+
+/// POST /api/admin/move - like copy, but deletes the files in the source watch group
+pub async fn api_move(
+    State(state): State<AppState>,
+    Json(dto): Json<TransferPathDto>,
+) -> Result<Json<TransferResultDto>, (StatusCode, String)> {
+    transfer(state, dto, Transfer::Move).await
+}
+
+async fn transfer(
+    state: AppState,
+    dto: TransferPathDto,
+    transfer: Transfer,
+) -> Result<Json<TransferResultDto>, (StatusCode, String)> {
+    let path_prefix = validate(&dto)?;
+    for wg_id in [dto.source_watch_group_id, dto.target_watch_group_id] {
+        let exists = state
+            .db
+            .server_watch_group()
+            .exists(wg_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to check watch group existence: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            })?;
+        if !exists {
+            return Err((
+                StatusCode::NOT_FOUND,
+                format!("Watch group {wg_id} not found"),
+            ));
+        }
+    }
+
+    // a move deletes in the source as well
+    let _target_write = state.backup_window.try_write(dto.target_watch_group_id)?;
+    let _source_write = match transfer {
+        Transfer::Move => Some(state.backup_window.try_write(dto.source_watch_group_id)?),
+        Transfer::Copy => None,
+    };
+
+    let affected = affected_files(
+        state.history.get_latest_events(dto.source_watch_group_id),
+        &path_prefix,
+    );
+    if affected.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("No files below '{}'", dto.path_prefix),
+        ));
+    }
+    state
+        .upload_disk
+        .ensure_room_for(affected.iter().map(|e| e.size_in_bytes).sum())?;
+
+    let mut transferred_paths = Vec::with_capacity(affected.len());
+    for event in affected {
+        copy_file(&state, dto.target_watch_group_id, &event).await?;
+        transferred_paths.push(event.relative_path.to_serialized_string());
+        if transfer == Transfer::Move {
+            prune(&state, event).await?;
+        }
+    }
+    info!(
+        "{:?} of '{}' from watch group {} to {} affected {} files",
+        transfer,
+        dto.path_prefix,
+        dto.source_watch_group_id,
+        dto.target_watch_group_id,
+        transferred_paths.len()
+    );
+    Ok(Json(TransferResultDto { transferred_paths }))
+}
+
+fn validate(dto: &TransferPathDto) -> Result<MatchablePath, (StatusCode, String)> {
+    if dto.source_watch_group_id == dto.target_watch_group_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Source and target watch group must differ".to_string(),
+        ));
+    }
+    // MatchablePath panics without any normal component
+    let path = Path::new(&dto.path_prefix);
+    if !path.components().any(|c| matches!(c, Component::Normal(_))) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "path_prefix must not be empty".to_string(),
+        ));
+    }
+    Ok(MatchablePath::from(path))
+}
+
+/// same relative path in the target - clients of the target download it like any upload
+async fn copy_file(
+    state: &AppState,
+    target_wg_id: i64,
+    current: &FileEvent,
+) -> Result<(), (StatusCode, String)> {
+    let source = current
+        .relative_path
+        .resolve(&UPLOAD_PATH.join(current.watch_group_id.to_string()));
+    let target = current
+        .relative_path
+        .resolve(&UPLOAD_PATH.join(target_wg_id.to_string()));
+    let tmp_path = UPLOAD_TMP_PATH.join(Uuid::new_v4().to_string());
+    let internal_error = |e: std::io::Error| {
+        error!("Failed to copy {:?} to {:?}: {}", source, target, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+
+    let size = tokio::fs::copy(&source, &tmp_path)
+        .await
+        .map_err(internal_error)?;
+    if let Some(parent) = target.parent()
+        && let Err(e) = tokio::fs::create_dir_all(parent).await
+    {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(internal_error(e));
+    }
+    versions::keep_current(
+        state.history.as_ref(),
+        target_wg_id,
+        &current.relative_path,
+        &target,
+    );
+    if let Err(e) = tokio::fs::rename(&tmp_path, &target).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(internal_error(e));
+    }
+
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        UtcMillis::now(),
+        current.relative_path.clone(),
+        size,
+        FileEventType::ChangeEvent,
+        Some("web".to_string()),
+        target_wg_id,
+    );
+    index_file(state, target_wg_id, &event.relative_path, &target).await;
+    thumbnail::render_in_background(target_wg_id, &event.relative_path, &target);
+    persist(state, event).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dto(source: i64, target: i64, path_prefix: &str) -> TransferPathDto {
+        TransferPathDto {
+            source_watch_group_id: source,
+            target_watch_group_id: target,
+            path_prefix: path_prefix.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_reject_empty_prefix_and_same_watch_group() {
+        assert!(validate(&dto(1, 2, "")).is_err());
+        assert!(validate(&dto(1, 2, "/")).is_err());
+        assert!(validate(&dto(1, 1, "docs")).is_err());
+        assert_eq!(
+            vec!["docs", "a.txt"],
+            validate(&dto(1, 2, "docs/a.txt")).unwrap().get().clone()
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use crate::AppState;
+use crate::auth::Account;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use shared::dtos::UiSettingsDto;
+use tracing::{error, info};
+
+/// GET /api/settings - of the calling account
+pub async fn api_get_ui_settings(
+    State(state): State<AppState>,
+    Extension(account): Extension<Account>,
+) -> Result<Json<UiSettingsDto>, (StatusCode, String)> {
+    state
+        .db
+        .ui_settings()
+        .get(account.id())
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to load ui settings of {:?}: {}", account, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+
+/// PUT /api/settings - every role may change its own
+pub async fn api_set_ui_settings(
+    State(state): State<AppState>,
+    Extension(account): Extension<Account>,
+    Json(dto): Json<UiSettingsDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state
+        .db
+        .ui_settings()
+        .save(account.id(), &dto)
+        .await
+        .map_err(|e| {
+            error!("Failed to save ui settings of {:?}: {}", account, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    info!("Saved ui settings of {:?}", account);
+    Ok(StatusCode::NO_CONTENT)
+}
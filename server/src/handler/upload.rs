@@ -0,0 +1,512 @@
+//! storing a received upload: validate → stage → persist → commit → notify
+//!
+//! each stage takes what the previous one produced, so further steps (e.g. encryption at rest)
+//! slot in between without touching the others
+
+use crate::client_file_event::{ClientFileEvent, ClientFileEventDto};
+use crate::file_event::FileEvent;
+use crate::{AppState, guard, inspect, publish, thumbnail, versions, webhook, write};
+use axum::http::StatusCode;
+use shared::dtos::Durability;
+use shared::endpoint::QUARANTINED_REPLY_PREFIX;
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use tracing::{error, info, warn};
+
+use super::search::index_file;
+use super::sync::durability;
+
+/// runs the whole pipeline - on failure the temp file (if it still exists) is returned for the
+/// caller to clean up
+pub(super) async fn process_upload(
+    upload_root_path: &Path,
+    state: AppState,
+    dto: ClientFileEventDto,
+    client_host: Option<String>,
+    client_id: Option<String>,
+) -> Result<String, (Option<PathBuf>, StatusCode, String)> {
+    let temp_file_path = dto.temp_file_path.clone();
+    let latest = dto.relative_path.as_ref().and_then(|path| {
+        state
+            .history
+            .get_latest_event(dto.watch_group_id, &MatchablePath::from(path.clone()))
+            .map(|e| e.utc_millis)
+    });
+    let upload = validate(dto, latest).map_err(|e| (temp_file_path, e.status(), e.to_string()))?;
+
+    let temp_path = upload.temp_path.clone();
+    let staged = stage(
+        &state,
+        upload,
+        upload_root_path,
+        client_host.as_deref(),
+        client_id.as_deref(),
+    )
+    .await
+    .map_err(|e| {
+        // quarantined files are gone already
+        let temp_file_path = Some(temp_path).filter(|p| p.exists());
+        (temp_file_path, e.status(), e.to_string())
+    })?;
+
+    if let Err(e) = persist(&staged).await {
+        let message = format!(
+            "Updating {} failed - {}",
+            staged.target_path.to_string_lossy(),
+            e
+        );
+        error!("{message}");
+        write::finish_write(&state.db, staged.journaled).await;
+        return Err((None, StatusCode::INTERNAL_SERVER_ERROR, message));
+    }
+
+    let message = format!(
+        "Updated {} successfully",
+        staged.target_path.to_string_lossy()
+    );
+    let target_path = staged.target_path.clone();
+    let event = commit(&state, staged, client_host, client_id.as_deref()).await;
+    info!(
+        path = %event.relative_path.to_serialized_string(),
+        event_type = event.event_type.serialize_to_string(),
+        bytes = event.size_in_bytes,
+        "{message}"
+    );
+    notify(&state, &event, &target_path, client_id.as_deref()).await;
+    Ok(message)
+}
+
+// validate
+
+/// an upload that may replace the current file of its path
+#[derive(Debug)]
+pub(super) struct ValidatedUpload {
+    event: ClientFileEvent,
+    temp_path: PathBuf,
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) enum ValidateError {
+    Invalid(String),
+    /// the history has a newer event of the path
+    NotLatest {
+        latest: UtcMillis,
+    },
+}
+
+impl ValidateError {
+    fn status(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+impl Display for ValidateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidateError::Invalid(reason) => write!(f, "{reason}"),
+            ValidateError::NotLatest { .. } => write!(f, "not latest"),
+        }
+    }
+}
+
+/// `latest` is the time of the newest event of the path in the history (if any)
+///
+/// an upload resolving a keep-both conflict only replaces the version the client kept as copy -
+/// if another one arrived meanwhile, that's a conflict of its own
+pub(super) fn validate(
+    dto: ClientFileEventDto,
+    latest: Option<UtcMillis>,
+) -> Result<ValidatedUpload, ValidateError> {
+    let conflict_kept = dto.conflict_kept.clone();
+    let event = ClientFileEvent::try_from(dto).map_err(ValidateError::Invalid)?;
+    let latest = latest.unwrap_or(UtcMillis::from(0));
+    if let Some(kept) = conflict_kept
+        && kept != latest
+    {
+        warn!(
+            path = %event.relative_path.to_serialized_string(),
+            "Skipping upload & event for {:?} - kept conflicted version ({:?}) isn't the latest ({:?})",
+            &event.relative_path, kept, latest
+        );
+        return Err(ValidateError::NotLatest { latest });
+    }
+    if event.utc_millis < latest {
+        warn!(
+            path = %event.relative_path.to_serialized_string(),
+            "Skipping upload & event for {:?} - event ({:?}) older than latest history state event ({:?})",
+            &event.relative_path, latest, event.utc_millis
+        );
+        return Err(ValidateError::NotLatest { latest });
+    }
+    let temp_path = event
+        .temp_file_path
+        .clone()
+        .ok_or_else(|| ValidateError::Invalid("Missing file content".to_string()))?;
+    Ok(ValidatedUpload { event, temp_path })
+}
+
+// stage
+
+/// an upload right before it's moved into place - inspected, its directory created, the current
+/// version kept and the write journaled
+#[derive(Debug)]
+pub(super) struct StagedUpload {
+    event: ClientFileEvent,
+    temp_path: PathBuf,
+    target_path: PathBuf,
+    durability: Durability,
+    /// journal entry - dropped once the upload is committed (or failed)
+    journaled: Option<String>,
+    /// kept copy of the current file - restored if the written file turns out corrupt
+    previous_version: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub(super) enum StageError {
+    /// by an inspector or while looking up the watch group
+    Rejected(StatusCode, String),
+    Dir(io::Error),
+}
+
+impl StageError {
+    fn status(&self) -> StatusCode {
+        match self {
+            StageError::Rejected(status, _) => *status,
+            StageError::Dir(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl Display for StageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StageError::Rejected(_, message) => write!(f, "{message}"),
+            StageError::Dir(e) => write!(f, "Could not create dir - {e}"),
+        }
+    }
+}
+
+async fn stage(
+    state: &AppState,
+    upload: ValidatedUpload,
+    upload_root_path: &Path,
+    client_host: Option<&str>,
+    client_id: Option<&str>,
+) -> Result<StagedUpload, StageError> {
+    let ValidatedUpload { event, temp_path } = upload;
+    let wg_id = event.watch_group_id;
+    inspect_upload(state, wg_id, &event.relative_path, &temp_path)
+        .await
+        .map_err(|(status, message)| StageError::Rejected(status, message))?;
+    let durability = durability(state, wg_id)
+        .await
+        .map_err(|(status, message)| StageError::Rejected(status, message))?;
+
+    let target_path = target_path(upload_root_path, &event.relative_path);
+    create_dir_all_for(&target_path).map_err(StageError::Dir)?;
+    versions::keep_current(
+        state.history.as_ref(),
+        wg_id,
+        &event.relative_path,
+        &target_path,
+    );
+    // the new event isn't recorded yet - so the latest one produced the kept version
+    let previous_version = state
+        .history
+        .get_latest_event(wg_id, &event.relative_path)
+        .filter(|e| e.event_type.is_change())
+        .and_then(|e| versions::kept_copy(wg_id, e.id));
+    let journaled =
+        write::begin_write(&state.db, &event, &target_path, client_id, client_host).await;
+    Ok(StagedUpload {
+        event,
+        temp_path,
+        target_path,
+        durability,
+        journaled,
+        previous_version,
+    })
+}
+
+/// where `relative_path` lives below the watch group's root
+pub(super) fn target_path(upload_root_path: &Path, relative_path: &MatchablePath) -> PathBuf {
+    let sub_path = relative_path
+        .get()
+        .iter()
+        .map(|part| Component::Normal(part.as_ref()));
+    upload_root_path.components().chain(sub_path).collect()
+}
+
+fn create_dir_all_for(target_path: &Path) -> io::Result<()> {
+    fs::create_dir_all(target_path.parent().unwrap_or(Path::new("./")))
+}
+
+/// runs the configured upload inspectors - rejected files are moved to the quarantine
+pub(super) async fn inspect_upload(
+    state: &AppState,
+    wg_id: i64,
+    relative_path: &MatchablePath,
+    temp_path: &Path,
+) -> Result<(), (StatusCode, String)> {
+    if state.inspectors.is_empty() {
+        return Ok(());
+    }
+    let inspectors = state.inspectors.clone();
+    let file = temp_path.to_path_buf();
+    let verdict = tokio::task::spawn_blocking(move || inspectors.inspect(&file))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    match verdict {
+        Ok(None) => Ok(()),
+        Ok(Some(reason)) => {
+            match inspect::quarantine(temp_path, wg_id, &relative_path.tail()) {
+                Ok(quarantined) => warn!(
+                    path = %relative_path.to_serialized_string(),
+                    "Upload rejected ({}) - quarantined as {:?}", reason, quarantined
+                ),
+                Err(e) => error!(
+                    path = %relative_path.to_serialized_string(),
+                    "Upload rejected ({}) - quarantine failed: {}", reason, e
+                ),
+            }
+            Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("{QUARANTINED_REPLY_PREFIX} - {reason}"),
+            ))
+        }
+        // e.g. clamd down - the client retries with its next sync
+        Err(e) => {
+            error!("Upload inspection failed - {}", e);
+            Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Upload inspection failed - {e}"),
+            ))
+        }
+    }
+}
+
+// persist
+
+#[derive(Debug)]
+pub(super) enum PersistError {
+    /// the temp file is removed
+    Move(io::Error),
+    /// the previous version is restored (or the file removed)
+    Verification(String),
+}
+
+impl Display for PersistError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistError::Move(e) => write!(f, "{e}"),
+            PersistError::Verification(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// moves the temp file into place and - for watch groups verifying writes - re-reads it
+async fn persist(staged: &StagedUpload) -> Result<(), PersistError> {
+    promote(&staged.temp_path, &staged.target_path, staged.durability)?;
+    match staged.event.content_hash.as_deref() {
+        Some(expected_hash) => {
+            verify_written_file(
+                &staged.target_path,
+                expected_hash,
+                staged.previous_version.as_deref(),
+            )
+            .await
+        }
+        None => Ok(()),
+    }
+}
+
+fn promote(
+    temp_path: &Path,
+    target_path: &Path,
+    durability: Durability,
+) -> Result<(), PersistError> {
+    match write::promote(temp_path, target_path, durability) {
+        Ok(()) => {
+            info!(
+                "moving was successful - {:?} -> {:?}",
+                temp_path, target_path
+            );
+            Ok(())
+        }
+        Err(e) => {
+            let result_delete_temp = if fs::remove_file(temp_path).is_ok() {
+                "was successful"
+            } else {
+                "failed aswell"
+            };
+            warn!(
+                "moving failed - deleting temp file {} - {:?} -> {:?}",
+                result_delete_temp, temp_path, target_path,
+            );
+            Err(PersistError::Move(e))
+        }
+    }
+}
+
+/// re-reads the promoted file and compares it to the transfer hash
+///
+/// a corrupt file is replaced by its kept previous version (or removed if there is none)
+async fn verify_written_file(
+    target_path: &Path,
+    expected_hash: &str,
+    previous_version: Option<&Path>,
+) -> Result<(), PersistError> {
+    let file = target_path.to_path_buf();
+    let actual_hash = tokio::task::spawn_blocking(move || write::hash_file(&file))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()));
+    let reason = match actual_hash {
+        Ok(actual_hash) if actual_hash == expected_hash => return Ok(()),
+        Ok(actual_hash) => format!("expected sha256 {expected_hash} but found {actual_hash}"),
+        Err(e) => format!("could not be re-read - {e}"),
+    };
+
+    let cleanup = match previous_version {
+        Some(kept) => fs::copy(kept, target_path).map(|_| "restored previous version"),
+        None => fs::remove_file(target_path).map(|_| "removed file"),
+    };
+    Err(PersistError::Verification(match cleanup {
+        Ok(action) => format!("Verification failed ({reason}) - {action}"),
+        Err(e) => format!("Verification failed ({reason}) - cleanup failed: {e}"),
+    }))
+}
+
+// commit
+
+/// records the event (and the verified hash) - from here on the upload counts as stored
+async fn commit(
+    state: &AppState,
+    staged: StagedUpload,
+    client_host: Option<String>,
+    client_id: Option<&str>,
+) -> FileEvent {
+    let verified_hash = staged.event.content_hash.clone();
+    let mut event = FileEvent::from(staged.event);
+    event.client_host = client_host;
+    if client_id.is_none() {
+        warn!("No client_id header — file event not persisted to DB");
+    }
+    if let Err(e) = state.events.record(&event, client_id).await {
+        error!("Failed to persist file event to DB: {e}");
+    }
+    if let Some(sha256) = verified_hash {
+        let path = event.relative_path.to_serialized_string();
+        if let Err(e) = state
+            .db
+            .file_hash()
+            .upsert(
+                event.watch_group_id,
+                &path,
+                event.utc_millis.clone(),
+                &sha256,
+            )
+            .await
+        {
+            warn!("Failed to keep the hash of {}: {}", path, e);
+        }
+    }
+    write::finish_write(&state.db, staged.journaled).await;
+    event
+}
+
+// notify
+
+/// search index, thumbnail, webhooks, mass change guard and published copies
+async fn notify(state: &AppState, event: &FileEvent, target_path: &Path, client_id: Option<&str>) {
+    let wg_id = event.watch_group_id;
+    index_file(state, wg_id, &event.relative_path, target_path).await;
+    thumbnail::render_in_background(wg_id, &event.relative_path, target_path);
+    webhook::notify(state, event, client_id);
+    if let Some(client_id) = client_id {
+        guard::observe(state, event, client_id);
+    }
+    publish::mirror(state, event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn dto(utc_millis: Option<u64>) -> ClientFileEventDto {
+        ClientFileEventDto {
+            utc_millis: utc_millis.map(UtcMillis::from),
+            relative_path: Some(vec!["docs".to_string(), "notes.md".to_string()]),
+            temp_file_path: Some(PathBuf::from("/tmp/upload")),
+            content_size: Some(5),
+            watch_group_id: 1,
+            content_hash: None,
+            conflict_kept: None,
+        }
+    }
+
+    #[test]
+    fn should_only_accept_uploads_newer_than_the_history() {
+        assert_eq!(
+            ValidateError::Invalid("Missing field 'utc_millis'".to_string()),
+            validate(dto(None), None).unwrap_err()
+        );
+        assert_eq!(
+            ValidateError::NotLatest {
+                latest: UtcMillis::from(2000)
+            },
+            validate(dto(Some(1000)), Some(UtcMillis::from(2000))).unwrap_err()
+        );
+
+        let resolving = |kept: u64| ClientFileEventDto {
+            conflict_kept: Some(UtcMillis::from(kept)),
+            ..dto(Some(3000))
+        };
+        assert!(validate(resolving(2000), Some(UtcMillis::from(2000))).is_ok());
+        assert_eq!(
+            ValidateError::NotLatest {
+                latest: UtcMillis::from(2500)
+            },
+            validate(resolving(2000), Some(UtcMillis::from(2500))).unwrap_err()
+        );
+
+        let upload = validate(dto(Some(2000)), Some(UtcMillis::from(2000))).unwrap();
+        assert_eq!(PathBuf::from("/tmp/upload"), upload.temp_path);
+        assert_eq!(
+            Path::new("root").join("docs").join("notes.md"),
+            target_path(Path::new("root"), &upload.event.relative_path)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_restore_previous_version_if_verification_fails() {
+        let dir = std::env::temp_dir().join(format!("upload-verify-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("notes.md");
+        let previous = dir.join("previous");
+        fs::write(&previous, "old").unwrap();
+
+        fs::write(&target, "new").unwrap();
+        let expected = write::hash_file(&target).unwrap();
+        assert!(
+            verify_written_file(&target, &expected, Some(&previous))
+                .await
+                .is_ok()
+        );
+
+        let result = verify_written_file(&target, "0000", Some(&previous)).await;
+        assert!(matches!(result, Err(PersistError::Verification(_))));
+        assert_eq!("old", fs::read_to_string(&target).unwrap());
+
+        let result = verify_written_file(&target, "0000", None).await;
+        assert!(matches!(result, Err(PersistError::Verification(_))));
+        assert!(!target.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
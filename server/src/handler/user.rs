@@ -0,0 +1,152 @@
+use crate::{AppState, auth};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use shared::dtos::{UserDto, UserNameDto, UserRole, UserRoleDto, UserTokenDto};
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// GET /api/users
+pub async fn api_list_users(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<UserDto>>, (StatusCode, String)> {
+    let users = state.db.user().get_all().await.map_err(|e| {
+        error!("Failed to get users: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    Ok(Json(users))
+}
+
+/// POST /api/users
+pub async fn api_create_user(
+    State(state): State<AppState>,
+    Json(dto): Json<UserNameDto>,
+) -> Result<(StatusCode, Json<UserDto>), (StatusCode, String)> {
+    let name = validate_name(dto.name)?;
+    let id = state.db.user().insert(&name).await.map_err(|e| {
+        error!("Failed to create user: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    info!("Created user '{}'", name);
+    Ok((
+        StatusCode::CREATED,
+        Json(UserDto {
+            id,
+            name,
+            // column default
+            role: UserRole::ReadOnly,
+        }),
+    ))
+}
+
+/// PUT /api/users/{id}
+pub async fn api_update_user(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(dto): Json<UserNameDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let name = validate_name(dto.name)?;
+    let found = state.db.user().rename(id, &name).await.map_err(|e| {
+        error!("Failed to rename user: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if found {
+        info!("Renamed user {} to '{}'", id, name);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "User not found".to_string()))
+    }
+}
+
+/// PUT /api/users/{id}/role
+pub async fn api_set_user_role(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(dto): Json<UserRoleDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state.db.user().set_role(id, dto.role).await.map_err(|e| {
+        error!("Failed to set role of user: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if found {
+        info!("Set role of user {} to {}", id, dto.role.as_str());
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "User not found".to_string()))
+    }
+}
+
+/// POST /api/users/{id}/token - replaces the previous token of the user
+pub async fn api_create_user_token(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<Json<UserTokenDto>, (StatusCode, String)> {
+    let token = Uuid::new_v4().simple().to_string();
+    let found = state
+        .db
+        .user()
+        .set_api_token_hash(id, &auth::hash_token(&token))
+        .await
+        .map_err(|e| {
+            error!("Failed to store api token of user: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Generated new api token for user {}", id);
+        Ok(Json(UserTokenDto { token }))
+    } else {
+        Err((StatusCode::NOT_FOUND, "User not found".to_string()))
+    }
+}
+
+/// DELETE /api/users/{id}
+pub async fn api_delete_user(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state.db.user().delete(id).await.map_err(|e| {
+        error!("Failed to delete user: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+
+    if found {
+        info!("Deleted user {}", id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "User not found".to_string()))
+    }
+}
+
+fn validate_name(name: String) -> Result<String, (StatusCode, String)> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Name must not be empty".to_string(),
+        ));
+    }
+    Ok(name)
+}
+
+/// `None` (= no owner) is always valid
+pub(super) async fn ensure_user_exists(
+    state: &AppState,
+    owner_id: Option<i64>,
+) -> Result<(), (StatusCode, String)> {
+    let Some(owner_id) = owner_id else {
+        return Ok(());
+    };
+    let exists = state.db.user().exists(owner_id).await.map_err(|e| {
+        error!("Failed to check user: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    })?;
+    if exists {
+        Ok(())
+    } else {
+        Err((StatusCode::NOT_FOUND, "User not found".to_string()))
+    }
+}
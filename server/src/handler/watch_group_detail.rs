@@ -0,0 +1,36 @@
+use crate::AppState;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use shared::dtos::WatchGroupDetailDto;
+use tracing::error;
+
+/// GET /api/watch-groups/{id}/detail - the file stats come from GET /api/stats/{wg_id}, which
+/// is cached
+pub async fn api_get_watch_group_detail(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<WatchGroupDetailDto>, (StatusCode, String)> {
+    let db_error = |e: sqlx::Error| {
+        error!("Failed to load details of watch group {}: {}", id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    };
+    let group = state
+        .db
+        .server_watch_group()
+        .get_watch_group(id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, format!("Watch group {id} not found")))?;
+    let members = state
+        .db
+        .client_watch_group()
+        .list_for_watch_group(id)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(WatchGroupDetailDto {
+        group,
+        members,
+        upload_disk: state.upload_disk.to_dto(),
+    }))
+}
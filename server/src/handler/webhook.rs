@@ -0,0 +1,96 @@
+use crate::AppState;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use shared::dtos::{WebhookCreateDto, WebhookDto};
+use tracing::{error, info};
+
+/// GET /api/watch-groups/{id}/webhooks
+pub async fn api_list_webhooks(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+) -> Result<Json<Vec<WebhookDto>>, (StatusCode, String)> {
+    let webhooks = state
+        .db
+        .webhook()
+        .list_for_watch_group(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to list webhooks: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    Ok(Json(
+        webhooks
+            .into_iter()
+            .map(|webhook| WebhookDto {
+                id: webhook.id,
+                url: webhook.url,
+                signed: webhook.secret.is_some(),
+            })
+            .collect(),
+    ))
+}
+
+/// POST /api/watch-groups/{id}/webhooks
+pub async fn api_create_webhook(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+    Json(dto): Json<WebhookCreateDto>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let url = dto.url.trim();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Webhook url must start with http:// or https://".to_string(),
+        ));
+    }
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()));
+    }
+
+    let secret = dto.secret.as_deref().filter(|s| !s.is_empty());
+    let id = state
+        .db
+        .webhook()
+        .create(wg_id, url, secret)
+        .await
+        .map_err(|e| {
+            error!("Failed to create webhook: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    info!("Created webhook {} for watch group {}", id, wg_id);
+    Ok(StatusCode::CREATED)
+}
+
+/// DELETE /api/watch-groups/{id}/webhooks/{hook_id}
+pub async fn api_delete_webhook(
+    State(state): State<AppState>,
+    Path((wg_id, hook_id)): Path<(i64, i64)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let found = state
+        .db
+        .webhook()
+        .delete(wg_id, hook_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete webhook: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    if found {
+        info!("Deleted webhook {} of watch group {}", hook_id, wg_id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Webhook not found".to_string()))
+    }
+}
@@ -0,0 +1,132 @@
+use crate::AppState;
+use crate::zip::{MAX_ARCHIVE_BYTES, MAX_ENTRIES, ZipWriter, archive_size};
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::response::IntoResponse;
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use tokio_util::io::ReaderStream;
+use tracing::{debug, error, info, warn};
+
+use super::sync::upload_path_for_wg;
+
+struct ZipEntry {
+    name: String,
+    file: PathBuf,
+    size: u64,
+    modified: DateTime<Local>,
+}
+
+/// GET /download/zip/{wg_id}?path=prefix - the folder (the whole watch group without `path`)
+/// as zip, assembled while streaming
+pub async fn download_zip(
+    State(state): State<AppState>,
+    Path(wg_id): Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let exists = state
+        .db
+        .server_watch_group()
+        .exists(wg_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to check watch group: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Watch group not found".to_string()));
+    }
+
+    let prefix: Vec<String> = params
+        .get("path")
+        .map(|p| {
+            p.split('/')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let root = upload_path_for_wg(wg_id);
+    // only what the history knows - never anything outside of the watch group
+    let entries: Vec<ZipEntry> = state
+        .history
+        .get_latest_events(wg_id)
+        .into_iter()
+        .filter(|e| e.event_type.is_change() && e.relative_path.get().starts_with(&prefix))
+        .filter_map(|e| {
+            let file = e.relative_path.resolve(&root);
+            let size = std::fs::metadata(&file).ok()?.len();
+            Some(ZipEntry {
+                name: e.relative_path.get()[prefix.len()..].join("/"),
+                file,
+                size,
+                modified: e.utc_millis.into(),
+            })
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Err((StatusCode::NOT_FOUND, "No files in this folder".to_string()));
+    }
+    let total = archive_size(entries.iter().map(|e| (e.name.as_str(), e.size)));
+    if total > MAX_ARCHIVE_BYTES || entries.len() > MAX_ENTRIES {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Folder too large for a zip download ({} files, {} bytes)",
+                entries.len(),
+                total
+            ),
+        ));
+    }
+
+    let file_name = match prefix.last() {
+        Some(folder) => format!("{folder}.zip"),
+        None => format!("watch_group_{wg_id}.zip"),
+    };
+    info!(
+        "Serving zip of '{}' in watch group {wg_id} ({} files)",
+        prefix.join("/"),
+        entries.len()
+    );
+
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        match write_zip(ZipWriter::new(writer), entries).await {
+            Ok(count) => debug!("Served zip of {count} files"),
+            Err(e) => warn!("Zip download aborted - {e}"),
+        }
+    });
+
+    let headers = [
+        (CONTENT_TYPE, "application/zip".to_string()),
+        (
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", file_name.replace('"', "_")),
+        ),
+        (CONTENT_LENGTH, total.to_string()),
+    ];
+    Ok((headers, Body::from_stream(ReaderStream::new(reader))))
+}
+
+/// files vanishing meanwhile are skipped - the announced length is not reached then and the
+/// browser reports a failed download rather than a broken archive
+async fn write_zip(
+    mut zip: ZipWriter<tokio::io::DuplexStream>,
+    entries: Vec<ZipEntry>,
+) -> io::Result<usize> {
+    for entry in entries {
+        match tokio::fs::File::open(&entry.file).await {
+            Ok(file) => zip.add(&entry.name, entry.modified, file).await?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                debug!("Skipping vanished file {:?}", entry.file)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    zip.finish().await
+}
@@ -0,0 +1,260 @@
+use crate::QUARANTINE_PATH;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// files smaller than this are too short for a meaningful entropy
+const MIN_ENTROPY_SAMPLE_BYTES: u64 = 4 * 1024;
+/// clamd rejects chunks above its StreamMaxLength - keep them small
+const CLAMD_CHUNK_SIZE: usize = 64 * 1024;
+const CLAMD_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Clean,
+    /// reason given by the inspector (e.g. the signature name)
+    Rejected(String),
+}
+
+/// inspects uploaded files after they are received, before they replace the synced file
+pub trait UploadInspector: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn inspect(&self, file: &Path) -> io::Result<Verdict>;
+}
+
+/// all configured inspectors - a file is rejected by the first one flagging it
+#[derive(Default)]
+pub struct UploadInspectors {
+    inspectors: Vec<Box<dyn UploadInspector>>,
+}
+
+impl UploadInspectors {
+    /// CLAMD_SOCKET (path of the clamd unix socket) and UPLOAD_MAX_ENTROPY (bits per byte, 0-8)
+    pub fn from_env() -> Self {
+        let mut inspectors: Vec<Box<dyn UploadInspector>> = Vec::new();
+        if let Ok(socket) = env::var("CLAMD_SOCKET") {
+            info!("Scanning uploads with clamd at {}", socket);
+            inspectors.push(Box::new(ClamdInspector {
+                socket: PathBuf::from(socket),
+            }));
+        }
+        match env::var("UPLOAD_MAX_ENTROPY").map(|v| v.parse::<f64>()) {
+            Ok(Ok(max_bits_per_byte)) => {
+                info!(
+                    "Rejecting uploads above {} bits/byte entropy",
+                    max_bits_per_byte
+                );
+                inspectors.push(Box::new(EntropyInspector { max_bits_per_byte }));
+            }
+            Ok(Err(e)) => warn!("Ignoring invalid UPLOAD_MAX_ENTROPY - {}", e),
+            Err(_) => {}
+        }
+        Self { inspectors }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inspectors.is_empty()
+    }
+
+    /// the first rejection as `"<inspector>: <reason>"`
+    pub fn inspect(&self, file: &Path) -> io::Result<Option<String>> {
+        for inspector in &self.inspectors {
+            if let Verdict::Rejected(reason) = inspector.inspect(file)? {
+                return Ok(Some(format!("{}: {}", inspector.name(), reason)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// moves a rejected upload out of reach of the clients - returns its new location
+pub fn quarantine(file: &Path, wg_id: i64, file_name: &str) -> io::Result<PathBuf> {
+    let dir = QUARANTINE_PATH.join(wg_id.to_string());
+    fs::create_dir_all(&dir)?;
+    let target = dir.join(format!("{}_{}", Uuid::new_v4(), file_name));
+    fs::rename(file, &target)?;
+    Ok(target)
+}
+
+/// virus scan via the clamd INSTREAM command
+pub struct ClamdInspector {
+    socket: PathBuf,
+}
+
+impl UploadInspector for ClamdInspector {
+    fn name(&self) -> &'static str {
+        "clamav"
+    }
+
+    #[cfg(unix)]
+    fn inspect(&self, file: &Path) -> io::Result<Verdict> {
+        let stream = std::os::unix::net::UnixStream::connect(&self.socket)?;
+        stream.set_read_timeout(Some(CLAMD_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLAMD_TIMEOUT))?;
+        clamd_instream(stream, &mut fs::File::open(file)?)
+    }
+
+    #[cfg(not(unix))]
+    fn inspect(&self, _file: &Path) -> io::Result<Verdict> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("clamd socket {:?} needs unix sockets", self.socket),
+        ))
+    }
+}
+
+fn clamd_instream(mut stream: impl Read + Write, file: &mut impl Read) -> io::Result<Verdict> {
+    stream.write_all(b"zINSTREAM\0")?;
+    let mut buffer = vec![0u8; CLAMD_CHUNK_SIZE];
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        stream.write_all(&(read as u32).to_be_bytes())?;
+        stream.write_all(&buffer[..read])?;
+    }
+    stream.write_all(&0u32.to_be_bytes())?;
+    stream.flush()?;
+
+    // replies of z-prefixed commands are null terminated
+    let mut reply = Vec::new();
+    io::BufReader::new(stream).read_until(0, &mut reply)?;
+    if reply.last() == Some(&0) {
+        reply.pop();
+    }
+    parse_clamd_reply(&String::from_utf8_lossy(&reply))
+}
+
+/// `stream: OK`, `stream: <signature> FOUND` or `<message> ERROR`
+fn parse_clamd_reply(reply: &str) -> io::Result<Verdict> {
+    let reply = reply.trim();
+    let result = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+    if result == "OK" {
+        Ok(Verdict::Clean)
+    } else if let Some(signature) = result.strip_suffix(" FOUND") {
+        Ok(Verdict::Rejected(signature.to_string()))
+    } else {
+        Err(io::Error::other(format!("clamd replied '{reply}'")))
+    }
+}
+
+/// flags files looking like random data (e.g. encrypted by ransomware)
+///
+/// compressed formats get close to 8 bits/byte as well - pick the threshold accordingly
+pub struct EntropyInspector {
+    max_bits_per_byte: f64,
+}
+
+impl UploadInspector for EntropyInspector {
+    fn name(&self) -> &'static str {
+        "entropy"
+    }
+
+    fn inspect(&self, file: &Path) -> io::Result<Verdict> {
+        let mut counts = [0u64; 256];
+        let mut buffer = [0u8; 64 * 1024];
+        let mut file = fs::File::open(file)?;
+        loop {
+            let read = match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for byte in &buffer[..read] {
+                counts[*byte as usize] += 1;
+            }
+        }
+        let entropy = shannon_entropy(&counts);
+        match entropy {
+            Some(entropy) if entropy > self.max_bits_per_byte => Ok(Verdict::Rejected(format!(
+                "{entropy:.3} bits/byte exceeds {}",
+                self.max_bits_per_byte
+            ))),
+            _ => Ok(Verdict::Clean),
+        }
+    }
+}
+
+/// bits per byte - None for samples too small to judge
+fn shannon_entropy(counts: &[u64; 256]) -> Option<f64> {
+    let total: u64 = counts.iter().sum();
+    if total < MIN_ENTROPY_SAMPLE_BYTES {
+        return None;
+    }
+    let total = total as f64;
+    Some(
+        counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f64 / total;
+                -p * p.log2()
+            })
+            .sum(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_compute_entropy_of_uniform_and_constant_data() {
+        let mut uniform = [0u64; 256];
+        uniform.iter_mut().for_each(|count| *count = 64);
+        let mut constant = [0u64; 256];
+        constant[b'a' as usize] = 64 * 256;
+
+        assert_eq!(Some(8.0), shannon_entropy(&uniform));
+        assert_eq!(Some(0.0), shannon_entropy(&constant));
+        constant[b'a' as usize] = 10;
+        assert_eq!(None, shannon_entropy(&constant));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_stream_file_to_clamd_and_parse_verdict() {
+        let (client, mut server) = std::os::unix::net::UnixStream::pair().unwrap();
+        let fake_clamd = std::thread::spawn(move || {
+            let mut command = [0u8; 10];
+            server.read_exact(&mut command).unwrap();
+            let mut received = Vec::new();
+            loop {
+                let mut len = [0u8; 4];
+                server.read_exact(&mut len).unwrap();
+                let len = u32::from_be_bytes(len) as usize;
+                if len == 0 {
+                    break;
+                }
+                let mut chunk = vec![0u8; len];
+                server.read_exact(&mut chunk).unwrap();
+                received.extend(chunk);
+            }
+            server
+                .write_all(b"stream: Eicar-Test-Signature FOUND\0")
+                .unwrap();
+            (command, received)
+        });
+
+        let verdict = clamd_instream(client, &mut b"X5O!P%@AP".as_slice()).unwrap();
+
+        let (command, received) = fake_clamd.join().unwrap();
+        assert_eq!(b"zINSTREAM\0", &command);
+        assert_eq!(b"X5O!P%@AP".to_vec(), received);
+        assert_eq!(
+            Verdict::Rejected("Eicar-Test-Signature".to_string()),
+            verdict
+        );
+        assert_eq!(Verdict::Clean, parse_clamd_reply("stream: OK").unwrap());
+        assert!(parse_clamd_reply("INSTREAM size limit exceeded. ERROR").is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use crate::AppState;
+use crate::handler::header_value_as_opt_string;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::http::header::RETRY_AFTER;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
+use shared::dtos::TransfersDto;
+use shared::endpoint::CLIENT_ID_HEADER_KEY;
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+/// a running transfer usually finishes within seconds - clients retry with their next sync
+const RETRY_AFTER_SECS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+/// caps simultaneous uploads / downloads - globally (`MAX_CONCURRENT_UPLOADS`, default 8 and
+/// `MAX_CONCURRENT_DOWNLOADS`, default 16) and per client and direction
+/// (`MAX_CONCURRENT_TRANSFERS_PER_CLIENT`, default 2) - 0 lifts a limit
+pub struct TransferLimits {
+    uploads: Limit,
+    downloads: Limit,
+    max_per_client: usize,
+    rejected: AtomicU64,
+}
+
+struct Limit {
+    max: usize,
+    global: Arc<Semaphore>,
+    per_client: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl Limit {
+    fn new(max: usize) -> Self {
+        Limit {
+            max,
+            global: Arc::new(Semaphore::new(permits(max))),
+            per_client: Arc::default(),
+        }
+    }
+
+    fn in_flight(&self) -> usize {
+        permits(self.max) - self.global.available_permits()
+    }
+}
+
+fn permits(max: usize) -> usize {
+    if max == 0 {
+        Semaphore::MAX_PERMITS
+    } else {
+        max
+    }
+}
+
+fn limit_from_env(key: &str, default: usize) -> usize {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// held for the whole transfer - frees the slots on drop
+pub struct TransferPermit {
+    _client: Option<ClientPermit>,
+    _global: OwnedSemaphorePermit,
+}
+
+struct ClientPermit {
+    client_id: String,
+    per_client: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for ClientPermit {
+    fn drop(&mut self) {
+        let mut per_client = self.per_client.lock().unwrap();
+        drop(self.permit.take());
+        // idle clients don't keep their semaphore around
+        if per_client
+            .get(&self.client_id)
+            .is_some_and(|semaphore| Arc::strong_count(semaphore) == 1)
+        {
+            per_client.remove(&self.client_id);
+        }
+    }
+}
+
+impl TransferLimits {
+    pub fn new(max_uploads: usize, max_downloads: usize, max_per_client: usize) -> Self {
+        TransferLimits {
+            uploads: Limit::new(max_uploads),
+            downloads: Limit::new(max_downloads),
+            max_per_client,
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            limit_from_env("MAX_CONCURRENT_UPLOADS", 8),
+            limit_from_env("MAX_CONCURRENT_DOWNLOADS", 16),
+            limit_from_env("MAX_CONCURRENT_TRANSFERS_PER_CLIENT", 2),
+        )
+    }
+
+    /// Err with the reason if the global or the client's limit is reached
+    pub fn try_acquire(
+        &self,
+        direction: Direction,
+        client_id: Option<&str>,
+    ) -> Result<TransferPermit, String> {
+        let (limit, name) = match direction {
+            Direction::Upload => (&self.uploads, "uploads"),
+            Direction::Download => (&self.downloads, "downloads"),
+        };
+        let Ok(global) = limit.global.clone().try_acquire_owned() else {
+            return Err(self.reject(format!(
+                "Too many concurrent {name} ({}) - retry later",
+                limit.max
+            )));
+        };
+        let client = match client_id {
+            Some(client_id) if self.max_per_client > 0 => {
+                let mut per_client = limit.per_client.lock().unwrap();
+                let semaphore = per_client
+                    .entry(client_id.to_string())
+                    .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_client)))
+                    .clone();
+                let Ok(permit) = semaphore.try_acquire_owned() else {
+                    return Err(self.reject(format!(
+                        "Too many concurrent {name} of client {client_id} ({}) - retry later",
+                        self.max_per_client
+                    )));
+                };
+                Some(ClientPermit {
+                    client_id: client_id.to_string(),
+                    per_client: limit.per_client.clone(),
+                    permit: Some(permit),
+                })
+            }
+            _ => None,
+        };
+        Ok(TransferPermit {
+            _client: client,
+            _global: global,
+        })
+    }
+
+    fn reject(&self, reason: String) -> String {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        debug!("{reason}");
+        reason
+    }
+
+    pub fn to_dto(&self) -> TransfersDto {
+        TransfersDto {
+            uploads: self.uploads.in_flight(),
+            max_uploads: self.uploads.max,
+            downloads: self.downloads.in_flight(),
+            max_downloads: self.downloads.max,
+            max_per_client: self.max_per_client,
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// middleware for upload routes
+pub async fn limit_uploads(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    limit(&state, Direction::Upload, req, next).await
+}
+
+/// middleware for download routes
+pub async fn limit_downloads(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    limit(&state, Direction::Download, req, next).await
+}
+
+async fn limit(state: &AppState, direction: Direction, req: Request, next: Next) -> Response {
+    let client_id = header_value_as_opt_string(req.headers(), CLIENT_ID_HEADER_KEY);
+    let permit = match state.transfers.try_acquire(direction, client_id.as_deref()) {
+        Ok(permit) => permit,
+        Err(reason) => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(RETRY_AFTER, RETRY_AFTER_SECS.to_string())],
+                reason,
+            )
+                .into_response();
+        }
+    };
+    let (parts, body) = next.run(req).await.into_parts();
+    // downloads are streamed after the handler returned - the permit goes with the body
+    let body = Body::from_stream(body.into_data_stream().map(move |chunk| {
+        let _permit = &permit;
+        chunk
+    }));
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_reject_beyond_global_limit() {
+        let limits = TransferLimits::new(2, 1, 0);
+
+        let first = limits.try_acquire(Direction::Upload, None).unwrap();
+        let _second = limits.try_acquire(Direction::Upload, None).unwrap();
+        assert!(limits.try_acquire(Direction::Upload, None).is_err());
+        // directions are limited independently
+        let _download = limits.try_acquire(Direction::Download, None).unwrap();
+
+        drop(first);
+        assert!(limits.try_acquire(Direction::Upload, None).is_ok());
+        let dto = limits.to_dto();
+        assert_eq!((1, 1, 1), (dto.uploads, dto.downloads, dto.rejected));
+    }
+
+    #[test]
+    fn should_reject_beyond_client_limit() {
+        let limits = TransferLimits::new(0, 0, 1);
+
+        let permit = limits.try_acquire(Direction::Download, Some("a")).unwrap();
+        assert!(limits.try_acquire(Direction::Download, Some("a")).is_err());
+        assert!(limits.try_acquire(Direction::Download, Some("b")).is_ok());
+        assert!(limits.try_acquire(Direction::Download, None).is_ok());
+
+        drop(permit);
+        assert!(limits.downloads.per_client.lock().unwrap().is_empty());
+        assert!(limits.try_acquire(Direction::Download, Some("a")).is_ok());
+    }
+}
@@ -1,45 +1,80 @@
 use crate::csv_migration::migrate_csv_history_to_db;
 use crate::db::ServerDatabase;
-use crate::file_history::InMemoryFileHistory;
-use crate::write::{
-    RotatingFileWriter, create_all_paths_if_not_exist, create_file_if_not_exists,
-    schedule_data_backups,
-};
+use crate::file_history::FileHistory;
+use crate::write::{RotatingFileWriter, create_all_paths_if_not_exist, create_file_if_not_exists};
 use axum::extract::{DefaultBodyLimit, Query, State};
-use axum::routing::{post, put};
+use axum::middleware;
+use axum::routing::{any, delete, post, put};
 
 const PWA_UPLOAD_LIMIT_BYTES: usize = 500 * 1024 * 1024; // 500 MB
-use axum::{Router, routing::get};
+use axum::{Json, Router, routing::get};
 use axum_server::tls_rustls::RustlsConfig;
+use clap::{Parser, Subcommand};
+use shared::dtos::{
+    CapabilitiesDto, Durability, DurabilityLevelDto, MonitorQueryDto, ScheduledJob,
+};
 use shared::endpoint::ServerEndpoint;
+use shared::log_format::LogFormat;
 use sqlx::SqlitePool;
 use sqlx::migrate::Migrator;
 use sqlx::sqlite::SqliteConnectOptions;
 use std::env;
-use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::LazyLock;
 use std::sync::Mutex;
-use tracing::{error, info};
+use tokio::task::JoinSet;
+use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
 
+mod auth;
+mod bind;
+#[cfg(feature = "chaos")]
+mod chaos;
 mod client_file_event;
 mod csv_migration;
 mod db;
+mod discovery;
+mod event_store;
+mod export;
 mod file_event;
 mod file_history;
+mod grpc;
+mod guard;
 mod handler;
+mod inspect;
+mod load;
 mod monitor;
 mod multipart;
+mod publish;
+mod quic;
+mod quiesce;
+mod relay;
+mod replay;
+mod schedule;
+mod supervisor;
+mod thumbnail;
+mod timing;
+mod versions;
+mod webhook;
 mod write;
+mod zip;
 
 /// base directory for files synced from clients (subdirs per watch group: upload/{wg_id}/)
 pub(crate) static UPLOAD_PATH: LazyLock<&Path> = LazyLock::new(|| Path::new("./data/upload"));
+/// previous versions of small files (one file per file event id: versions/{wg_id}/{event_id})
+pub(crate) static VERSIONS_PATH: LazyLock<&Path> = LazyLock::new(|| Path::new("./data/versions"));
+/// cached thumbnails of images (thumbnails/{wg_id}/{size}/{relative path}.jpg)
+pub(crate) static THUMBNAIL_PATH: LazyLock<&Path> =
+    LazyLock::new(|| Path::new("./data/thumbnails"));
 /// directory to hold zipped backup files
-static BACKUP_PATH: LazyLock<&Path> = LazyLock::new(|| Path::new("./data/backup"));
+pub(crate) static BACKUP_PATH: LazyLock<&Path> = LazyLock::new(|| Path::new("./data/backup"));
+/// uploads rejected by an upload inspector (subdirs per watch group: quarantine/{wg_id}/)
+pub(crate) static QUARANTINE_PATH: LazyLock<&Path> =
+    LazyLock::new(|| Path::new("./data/quarantine"));
 /// path to legacy CSV history file (used only for one-time migration)
-static HISTORY_CSV_PATH: LazyLock<&Path> = LazyLock::new(|| Path::new("./data/history.csv"));
+pub(crate) static HISTORY_CSV_PATH: LazyLock<&Path> =
+    LazyLock::new(|| Path::new("./data/history.csv"));
 static MONITORING_DIR: LazyLock<&Path> = LazyLock::new(|| Path::new("./data/monitor"));
 /// dir to which multipart-files can be saved to, before being moved to the actual 'mirrored path'
 /// temporary and might be cleaned upon encountering errors or on scheduled intervals
@@ -52,26 +87,76 @@ static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 #[derive(Clone)]
 pub(crate) struct AppState {
-    history: Arc<InMemoryFileHistory>,
+    /// read side of the history - events are recorded through [`AppState::events`]
+    history: Arc<dyn FileHistory>,
+    events: Arc<event_store::EventStore>,
     monitor_writer: Arc<Mutex<RotatingFileWriter>>,
+    upload_disk: Arc<monitor::UploadDiskSpace>,
+    stats_cache: Arc<handler::StatsCache>,
+    pending: Arc<handler::PendingWork>,
+    confirmations: Arc<handler::ConfirmationTokens>,
+    scrub_reports: Arc<handler::ScrubReports>,
+    replication: Arc<handler::ReplicationStatus>,
+    inspectors: Arc<inspect::UploadInspectors>,
+    webhooks: Arc<webhook::WebhookDispatcher>,
+    backup_window: Arc<quiesce::BackupWindow>,
+    transfers: Arc<load::TransferLimits>,
+    timings: Arc<timing::OperationTimings>,
+    mass_changes: Arc<guard::MassChangeGuard>,
+    /// bound endpoints etc. - fixed once the listeners are bound
+    capabilities: Arc<CapabilitiesDto>,
+    /// background tasks - restarted if they panic
+    tasks: Arc<supervisor::TaskSupervisor>,
+    /// cron expressions of the backup, compaction, scrub and gc jobs
+    schedules: Arc<schedule::Schedules>,
+    /// faults injected into client traffic
+    #[cfg(feature = "chaos")]
+    chaos: Arc<chaos::Chaos>,
     db: ServerDatabase,
 }
 
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Replay an event export (GET /api/export/events) against another, fresh instance
+    Replay(replay::ReplayArgs),
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let log_level = EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("info"));
-    tracing_subscriber::fmt().with_env_filter(log_level).init();
+    match LogFormat::from_env() {
+        LogFormat::Pretty => tracing_subscriber::fmt().with_env_filter(log_level).init(),
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_env_filter(log_level)
+            .init(),
+    }
+
+    if let Some(Command::Replay(args)) = Args::parse().command {
+        return Ok(replay::run(args).await?);
+    }
 
     tokio::spawn(async {
         create_all_paths_if_not_exist(vec![
             UPLOAD_PATH.iter().as_path(),
             UPLOAD_TMP_PATH.iter().as_path(),
+            VERSIONS_PATH.iter().as_path(),
+            THUMBNAIL_PATH.iter().as_path(),
             BACKUP_PATH.iter().as_path(),
+            QUARANTINE_PATH.iter().as_path(),
         ])?;
         create_file_if_not_exists(*DB_FILE_PATH)?;
         Ok::<(), std::io::Error>(())
     });
-    tokio::spawn(schedule_data_backups(&UPLOAD_PATH, &BACKUP_PATH));
 
     let db = {
         let opts = SqliteConnectOptions::new()
@@ -83,20 +168,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ServerDatabase::new(pool)
     };
 
+    let tasks = Arc::new(supervisor::TaskSupervisor::default());
+    let schedules = Arc::new(schedule::Schedules::load(&db).await);
+
     // Migrate CSV history to DB (one-time)
     migrate_csv_history_to_db(&db).await;
 
-    // Load history from DB into in-memory store
-    let history = match db.file_event().get_all_events().await {
-        Ok(events) => {
-            info!("Loaded {} file events from database", events.len());
-            InMemoryFileHistory::from(events)
-        }
-        Err(err) => {
-            error!("Failed to load file events from database: {}", err);
-            InMemoryFileHistory::from(Vec::new())
-        }
-    };
+    // Load history from DB into in-memory store (or read it from the DB on demand)
+    let history = file_history::from_env(&db).await;
+    let events = Arc::new(event_store::EventStore::from_env(
+        db.clone(),
+        history.clone(),
+    ));
+
+    // uploads interrupted by the last shutdown - journaled ones are finished,
+    // the other leftovers removed
+    write::recover_interrupted_writes(&db, history.as_ref(), &events).await;
+    if let Err(e) = std::fs::remove_dir_all(*UPLOAD_TMP_PATH)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!("Failed to clean up {:?} - {}", *UPLOAD_TMP_PATH, e);
+    }
+    create_all_paths_if_not_exist(vec![UPLOAD_TMP_PATH.iter().as_path()])?;
 
     // Create rotating file writer for monitoring (4 files, 5MB each)
     let monitor_writer = RotatingFileWriter::new(
@@ -114,92 +207,572 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
     let monitor_writer = Arc::new(Mutex::new(monitor_writer));
 
-    tokio::spawn(monitor::monitor_sys(monitor_writer.clone()));
+    let upload_disk = Arc::new(monitor::UploadDiskSpace::default());
+    tasks.spawn("monitor", {
+        let monitor_writer = monitor_writer.clone();
+        let upload_disk = upload_disk.clone();
+        move || monitor::monitor_sys(monitor_writer.clone(), upload_disk.clone())
+    });
+
+    let tls_paths = match (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
+        (Ok(cert_path), Ok(key_path)) => Some((cert_path, key_path)),
+        _ => None,
+    };
+    let mut listeners = Vec::new();
+    for addr in bind::from_env()? {
+        let listener =
+            bind::Listener::bind(&addr).map_err(|e| format!("Could not bind {addr} - {e}"))?;
+        listeners.push(listener);
+    }
+    let capabilities = CapabilitiesDto {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        endpoints: listeners
+            .iter()
+            .map(|listener| listener.endpoint(tls_paths.is_some()))
+            .collect(),
+        tls: tls_paths.is_some(),
+        http3: tls_paths.is_some() && *quic::HTTP3_ENABLED,
+        durability_levels: Durability::ALL
+            .iter()
+            .map(|durability| DurabilityLevelDto {
+                name: durability.as_str().to_string(),
+                description: durability.description().to_string(),
+            })
+            .collect(),
+    };
 
     let state = AppState {
-        history: Arc::new(history),
+        history,
+        events,
         monitor_writer,
+        upload_disk,
+        stats_cache: Arc::new(handler::StatsCache::default()),
+        pending: Arc::new(handler::PendingWork::default()),
+        confirmations: Arc::new(handler::ConfirmationTokens::default()),
+        scrub_reports: Arc::new(handler::ScrubReports::default()),
+        replication: Arc::new(handler::ReplicationStatus::default()),
+        inspectors: Arc::new(inspect::UploadInspectors::from_env()),
+        webhooks: Arc::new(webhook::WebhookDispatcher::default()),
+        transfers: Arc::new(load::TransferLimits::from_env()),
+        timings: Arc::new(timing::OperationTimings::from_env()),
+        backup_window: Arc::new(quiesce::BackupWindow::default()),
+        mass_changes: Arc::new(guard::MassChangeGuard::from_env()),
+        capabilities: Arc::new(capabilities),
+        tasks,
+        schedules,
+        #[cfg(feature = "chaos")]
+        chaos: Arc::new(chaos::Chaos::default()),
         db,
     };
+    let tasks = state.tasks.clone();
+    tasks.spawn("search-index", {
+        let state = state.clone();
+        move || handler::rebuild_search_index(state.clone())
+    });
+    tasks.spawn("boot-check", {
+        let state = state.clone();
+        move || handler::check_on_boot(state.clone())
+    });
+    for job in ScheduledJob::ALL {
+        tasks.spawn(job.as_str(), {
+            let state = state.clone();
+            move || schedule::run_on_schedule(state.clone(), job)
+        });
+    }
+    tasks.spawn("replication", {
+        let state = state.clone();
+        move || handler::schedule_replication(state.clone())
+    });
+    tasks.spawn("pending-deletes", {
+        let state = state.clone();
+        move || handler::schedule_pending_deletes(state.clone())
+    });
+    tasks.spawn("nats-export", {
+        let state = state.clone();
+        move || export::schedule_nats_export(state.clone())
+    });
+    tasks.spawn("kafka-export", {
+        let state = state.clone();
+        move || export::schedule_kafka_export(state.clone())
+    });
 
-    let app = Router::new()
-        .route(ServerEndpoint::Hello.to_str(), get(|| async { "hello" }))
-        .route(ServerEndpoint::Ping.to_str(), get(|| async { "pong" }))
+    // writes into a watch group wait for its nightly backup
+    let quiesce =
+        middleware::from_fn_with_state(state.clone(), quiesce::reject_writes_while_archiving);
+    // load shedding - 503 beyond the concurrent transfer limits
+    let limit_uploads = middleware::from_fn_with_state(state.clone(), load::limit_uploads);
+    let limit_downloads = middleware::from_fn_with_state(state.clone(), load::limit_downloads);
+
+    // json api - for frontends (guarded by role once ADMIN_TOKEN is set)
+    let api = Router::new()
         .route(
-            ServerEndpoint::Scan.to_str(),
-            get(|| handler::scan_disk(&UPLOAD_PATH)),
+            ServerEndpoint::ApiClients.to_str(),
+            auth::read(get(handler::api_list_clients)),
         )
         .route(
-            ServerEndpoint::Upload.to_str(),
-            post(handler::upload_handler).layer(DefaultBodyLimit::max(
-                10 * 1024 * 1024 * 1024, /* 10gb */
+            ServerEndpoint::ApiClient.to_str(),
+            auth::read(get(handler::api_get_client)).merge(auth::write(
+                put(handler::api_update_client).delete(handler::api_delete_client),
             )),
         )
-        .route(ServerEndpoint::Sync.to_str(), post(handler::sync_handler))
-        .route(ServerEndpoint::Download.to_str(), get(handler::download))
-        .route(ServerEndpoint::Delete.to_str(), post(handler::delete))
         .route(
-            ServerEndpoint::Version.to_str(),
-            get(|| async { env!("CARGO_PKG_VERSION") }),
+            ServerEndpoint::ApiClientOwner.to_str(),
+            auth::write(put(handler::api_set_client_owner)),
         )
-        .route(ServerEndpoint::Config.to_str(), get(handler::get_config))
-        // json api - for frontends
         .route(
-            ServerEndpoint::ApiClients.to_str(),
-            get(handler::api_list_clients),
+            ServerEndpoint::ApiClientPause.to_str(),
+            auth::write(put(handler::api_pause_client)),
         )
         .route(
-            ServerEndpoint::ApiClient.to_str(),
-            get(handler::api_get_client)
-                .put(handler::api_update_client)
-                .delete(handler::api_delete_client),
+            ServerEndpoint::ApiClientClaim.to_str(),
+            auth::write(post(handler::api_claim_client)),
+        )
+        .route(
+            ServerEndpoint::ApiClientSyncBlackouts.to_str(),
+            auth::read(get(handler::api_list_sync_blackouts))
+                .merge(auth::write(post(handler::api_create_sync_blackout))),
+        )
+        .route(
+            ServerEndpoint::ApiClientSyncBlackout.to_str(),
+            auth::write(delete(handler::api_delete_sync_blackout)),
         )
         .route(
             ServerEndpoint::ApiClientWatchGroups.to_str(),
-            get(handler::api_list_client_watch_groups).post(handler::api_create_client_watch_group),
+            auth::read(get(handler::api_list_client_watch_groups))
+                .merge(auth::write(post(handler::api_create_client_watch_group))),
         )
         .route(
             ServerEndpoint::ApiClientWatchGroup.to_str(),
-            put(handler::api_update_client_watch_group)
-                .delete(handler::api_delete_client_watch_group),
+            auth::write(
+                put(handler::api_update_client_watch_group)
+                    .delete(handler::api_delete_client_watch_group),
+            ),
+        )
+        .route(
+            ServerEndpoint::ApiClientWatchGroupPause.to_str(),
+            auth::write(put(handler::api_pause_client_watch_group)),
+        )
+        .route(
+            ServerEndpoint::ApiClientPending.to_str(),
+            auth::read(get(handler::api_get_client_pending)),
+        )
+        .route(
+            ServerEndpoint::ApiClientFiles.to_str(),
+            auth::read(get(handler::api_get_client_files)),
+        )
+        .route(
+            ServerEndpoint::ApiClientDetail.to_str(),
+            auth::read(get(handler::api_get_client_detail)),
+        )
+        .route(
+            ServerEndpoint::ApiClientActivity.to_str(),
+            auth::read(get(handler::api_get_client_activity)),
+        )
+        .route(
+            ServerEndpoint::ApiClientResync.to_str(),
+            auth::write(post(handler::api_resync_client)),
+        )
+        .route(
+            ServerEndpoint::ApiRegistrationTemplates.to_str(),
+            auth::read(get(handler::api_list_registration_templates))
+                .merge(auth::write(post(handler::api_create_registration_template))),
+        )
+        .route(
+            ServerEndpoint::ApiRegistrationTemplate.to_str(),
+            auth::write(delete(handler::api_delete_registration_template)),
         )
         .route(
             ServerEndpoint::ApiWatchGroups.to_str(),
-            get(handler::api_list_watch_groups).post(handler::api_create_watch_group),
+            auth::read(get(handler::api_list_watch_groups))
+                .merge(auth::write(post(handler::api_create_watch_group))),
         )
         .route(
             ServerEndpoint::ApiWatchGroup.to_str(),
-            put(handler::api_update_watch_group).delete(handler::api_delete_watch_group),
+            auth::write(
+                put(handler::api_update_watch_group).delete(handler::api_delete_watch_group),
+            ),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupOwner.to_str(),
+            auth::write(put(handler::api_set_watch_group_owner)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupVerifyWrites.to_str(),
+            auth::write(put(handler::api_set_watch_group_verify_writes)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupDurability.to_str(),
+            auth::write(put(handler::api_set_watch_group_durability)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupPriority.to_str(),
+            auth::write(put(handler::api_set_watch_group_priority)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupMassChangeGuard.to_str(),
+            auth::read(get(handler::api_get_mass_change_guard))
+                .merge(auth::write(put(handler::api_set_mass_change_guard))),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupPublish.to_str(),
+            auth::write(put(handler::api_set_watch_group_publish)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupWebhooks.to_str(),
+            auth::read(get(handler::api_list_webhooks))
+                .merge(auth::write(post(handler::api_create_webhook))),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupWebhook.to_str(),
+            auth::write(delete(handler::api_delete_webhook)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupShareLinks.to_str(),
+            auth::read(get(handler::api_list_share_links))
+                .merge(auth::write(post(handler::api_create_share_link))),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupShareLink.to_str(),
+            auth::write(delete(handler::api_delete_share_link)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupManifest.to_str(),
+            auth::read(get(handler::api_get_manifest)),
+        )
+        .route(
+            ServerEndpoint::ApiWatchGroupDetail.to_str(),
+            auth::read(get(handler::api_get_watch_group_detail)),
         )
         .route(
             ServerEndpoint::ApiWatchGroupFiles.to_str(),
-            get(handler::api_get_watch_group_files)
-                .post(handler::api_upload_to_watch_group)
-                .layer(DefaultBodyLimit::max(PWA_UPLOAD_LIMIT_BYTES)),
+            auth::read(get(handler::api_get_watch_group_files))
+                .merge(auth::write(post(handler::api_upload_to_watch_group)))
+                .layer(DefaultBodyLimit::max(PWA_UPLOAD_LIMIT_BYTES))
+                .layer(quiesce.clone()),
         )
         .route(
             ServerEndpoint::ApiWatchGroupFile.to_str(),
-            get(handler::api_serve_watch_group_file)
-                .delete(handler::api_delete_watch_group_file),
+            auth::read(get(handler::api_serve_watch_group_file))
+                .merge(auth::write(delete(handler::api_delete_watch_group_file)))
+                .layer(quiesce.clone()),
         )
         .route(
             ServerEndpoint::ApiMonitor.to_str(),
-            get(|state: State<AppState>, Query(q): Query<monitor::MonitorQuery>| {
-                let writer = state.monitor_writer.clone();
-                let points = q.points.unwrap_or(monitor::DEFAULT_MAX_POINTS);
-                monitor::api_get_monitoring(writer, points)
-            }),
+            auth::read(get(
+                |state: State<AppState>, Query(q): Query<MonitorQueryDto>| {
+                    let writer = state.monitor_writer.clone();
+                    let transfers = state.transfers.to_dto();
+                    monitor::api_get_monitoring(writer, state.upload_disk.clone(), transfers, q)
+                },
+            )),
+        )
+        .route(
+            ServerEndpoint::ApiStats.to_str(),
+            auth::read(get(handler::api_get_watch_group_stats)),
+        )
+        .route(
+            ServerEndpoint::ApiStatsBandwidth.to_str(),
+            auth::read(get(handler::api_get_bandwidth_stats)),
+        )
+        .route(
+            ServerEndpoint::ApiReportTopFiles.to_str(),
+            auth::read(get(handler::api_get_top_files)),
+        )
+        .route(
+            ServerEndpoint::ApiReportGrowth.to_str(),
+            auth::read(get(handler::api_get_growth)),
+        )
+        .route(
+            ServerEndpoint::ApiCapabilities.to_str(),
+            auth::read(get(|state: State<AppState>| async move {
+                Json(state.capabilities.as_ref().clone())
+            })),
+        )
+        .route(
+            ServerEndpoint::ApiReplication.to_str(),
+            auth::read(get(handler::api_get_replication_status)),
+        )
+        .route(
+            ServerEndpoint::ApiReplicationEvents.to_str(),
+            auth::read(get(handler::api_get_replication_events)),
+        )
+        .route(
+            ServerEndpoint::ApiExport.to_str(),
+            auth::read(get(handler::api_get_export_status)),
+        )
+        .route(
+            ServerEndpoint::ApiExportEvents.to_str(),
+            auth::read(get(handler::api_export_events)),
+        )
+        .route(
+            ServerEndpoint::ApiExportCheckpoint.to_str(),
+            auth::write(put(handler::api_set_export_checkpoint)),
+        )
+        .route(
+            ServerEndpoint::ApiSearch.to_str(),
+            auth::read(get(handler::api_search)),
+        )
+        .route(
+            ServerEndpoint::ApiHistory.to_str(),
+            auth::read(get(handler::api_get_file_history)),
+        )
+        .route(
+            ServerEndpoint::ApiFileStatus.to_str(),
+            auth::read(get(handler::api_get_file_status)),
+        )
+        .route(
+            ServerEndpoint::ApiDiff.to_str(),
+            auth::read(get(handler::api_get_diff)),
+        )
+        .route(
+            ServerEndpoint::ApiThumbnail.to_str(),
+            auth::read(get(handler::api_get_thumbnail)),
+        )
+        .route(
+            ServerEndpoint::ApiPreview.to_str(),
+            auth::read(get(handler::api_get_preview)),
+        )
+        .route(
+            ServerEndpoint::ApiNote.to_str(),
+            auth::read(get(handler::api_get_note))
+                .merge(auth::write(post(handler::api_post_note)))
+                .layer(quiesce.clone()),
+        )
+        .route(
+            ServerEndpoint::ApiAdminPrune.to_str(),
+            auth::write(post(handler::api_prune)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminResync.to_str(),
+            auth::write(post(handler::api_resync)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminCopy.to_str(),
+            auth::write(post(handler::api_copy)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminMove.to_str(),
+            auth::write(post(handler::api_move)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminImport.to_str(),
+            auth::write(post(handler::api_import)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminMassChanges.to_str(),
+            auth::read(get(handler::api_list_mass_changes)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminPendingDeletes.to_str(),
+            auth::read(get(handler::api_list_pending_deletes)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminPendingDelete.to_str(),
+            auth::write(delete(handler::api_cancel_pending_delete)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminDbBackup.to_str(),
+            auth::write(post(handler::api_db_backup)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminDbCheck.to_str(),
+            auth::read(get(handler::api_db_check)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminBackups.to_str(),
+            auth::write(get(handler::api_list_backups)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminBackup.to_str(),
+            auth::write(get(handler::api_download_backup)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminScrub.to_str(),
+            auth::read(get(handler::api_get_scrub))
+                .merge(auth::write(post(handler::api_run_scrub))),
+        )
+        .route(
+            ServerEndpoint::ApiAdminBootReport.to_str(),
+            auth::read(get(handler::api_get_boot_report)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminTasks.to_str(),
+            auth::read(get(|state: State<AppState>| async move {
+                Json(state.tasks.to_dto())
+            })),
+        )
+        .route(
+            ServerEndpoint::ApiAdminTimings.to_str(),
+            auth::read(get(|state: State<AppState>| async move {
+                Json(state.timings.to_dto())
+            })),
+        )
+        .route(
+            ServerEndpoint::ApiAdminSchedule.to_str(),
+            auth::read(get(handler::api_get_schedule)),
+        )
+        .route(
+            ServerEndpoint::ApiAdminScheduleJob.to_str(),
+            auth::write(put(handler::api_set_schedule)),
         )
         .route(
             ServerEndpoint::ApiLinks.to_str(),
-            get(handler::get_links)
-                .post(handler::post_link)
-                .delete(handler::delete_link),
+            auth::read(get(handler::get_links)).merge(auth::write(
+                post(handler::post_link).delete(handler::delete_link),
+            )),
         )
         .route(
             ServerEndpoint::ApiLinkTags.to_str(),
-            post(handler::post_link_tag),
+            auth::write(post(handler::post_link_tag)),
+        )
+        .route(
+            ServerEndpoint::ApiSettings.to_str(),
+            auth::read(get(handler::api_get_ui_settings).put(handler::api_set_ui_settings)),
+        )
+        .route(
+            ServerEndpoint::ApiUsers.to_str(),
+            auth::read(get(handler::api_list_users))
+                .merge(auth::write(post(handler::api_create_user))),
+        )
+        .route(
+            ServerEndpoint::ApiUser.to_str(),
+            auth::write(put(handler::api_update_user).delete(handler::api_delete_user)),
+        )
+        .route(
+            ServerEndpoint::ApiUserRole.to_str(),
+            auth::write(put(handler::api_set_user_role)),
+        )
+        .route(
+            ServerEndpoint::ApiUserToken.to_str(),
+            auth::write(post(handler::api_create_user_token)),
+        )
+        .route(
+            ServerEndpoint::ApiSignDownload.to_str(),
+            auth::read(post(handler::api_sign_download)),
+        )
+        // browser download (no /api prefix) - guarded like the api
+        .route(
+            ServerEndpoint::DownloadZip.to_str(),
+            auth::read(get(handler::download_zip)),
+        );
+    #[cfg(feature = "chaos")]
+    let api = api.route(
+        ServerEndpoint::ApiAdminChaos.to_str(),
+        auth::read(get(chaos::api_get_chaos)).merge(auth::write(put(chaos::api_set_chaos))),
+    );
+    let api = api.layer(middleware::from_fn_with_state(
+        state.clone(),
+        auth::authenticate,
+    ));
+    // webdav - same tokens as the api (as basic auth password)
+    let dav = Router::new()
+        .route(
+            ServerEndpoint::Dav.to_str(),
+            auth::by_method(any(handler::dav_handler)),
+        )
+        .route(
+            &format!("{}/", ServerEndpoint::Dav.to_str()),
+            auth::by_method(any(handler::dav_handler)),
+        )
+        .route(
+            ServerEndpoint::DavPath.to_str(),
+            auth::by_method(any(handler::dav_handler)),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::authenticate_basic,
+        ));
+    if auth::is_enabled() {
+        info!("Api auth enabled (ADMIN_TOKEN set)");
+    } else {
+        warn!("Api auth disabled - set ADMIN_TOKEN to protect /api");
+    }
+
+    let app = Router::new()
+        .route(ServerEndpoint::Hello.to_str(), get(|| async { "hello" }))
+        .route(ServerEndpoint::Ping.to_str(), get(|| async { "pong" }))
+        .route(
+            ServerEndpoint::Scan.to_str(),
+            get(|| handler::scan_disk(&UPLOAD_PATH)),
+        )
+        .route(
+            ServerEndpoint::Upload.to_str(),
+            post(handler::upload_handler)
+                .layer(DefaultBodyLimit::max(
+                    10 * 1024 * 1024 * 1024, /* 10gb */
+                ))
+                .layer(quiesce.clone())
+                .layer(limit_uploads.clone()),
+        )
+        .route(
+            ServerEndpoint::UploadBulk.to_str(),
+            post(handler::upload_bulk_handler)
+                .layer(DefaultBodyLimit::max(
+                    10 * 1024 * 1024 * 1024, /* 10gb */
+                ))
+                .layer(quiesce.clone())
+                .layer(limit_uploads.clone()),
+        )
+        .route(ServerEndpoint::Sync.to_str(), post(handler::sync_handler))
+        .route(
+            ServerEndpoint::Download.to_str(),
+            get(handler::download).layer(limit_downloads.clone()),
+        )
+        .route(
+            ServerEndpoint::DownloadSnapshot.to_str(),
+            get(handler::download_snapshot).layer(limit_downloads.clone()),
+        )
+        .route(
+            ServerEndpoint::Delete.to_str(),
+            post(handler::delete).layer(quiesce),
         )
+        .route(
+            ServerEndpoint::ScanWarning.to_str(),
+            post(handler::scan_warning),
+        )
+        .route(
+            ServerEndpoint::Manifest.to_str(),
+            get(handler::get_manifest),
+        )
+        .route(
+            ServerEndpoint::BandwidthUsage.to_str(),
+            get(handler::get_bandwidth_usage).post(handler::post_bandwidth_usage),
+        )
+        .route_service(
+            ServerEndpoint::Grpc.to_str(),
+            grpc::GrpcSync::server(state.clone()),
+        )
+        .route(
+            ServerEndpoint::Version.to_str(),
+            get(|| async { env!("CARGO_PKG_VERSION") }),
+        )
+        .route(ServerEndpoint::Config.to_str(), get(handler::get_config))
+        // public - the signature is the authorization
+        .route(
+            ServerEndpoint::SignedDownload.to_str(),
+            get(handler::signed_download)
+                .layer(limit_downloads.clone())
+                .route_layer(middleware::from_fn(handler::verify_signature)),
+        )
+        // throughput test of clients - not behind the api auth (clients have no token)
+        .route(
+            ServerEndpoint::ApiBenchUpload.to_str(),
+            post(handler::api_bench_upload).layer(limit_uploads),
+        )
+        .route(
+            ServerEndpoint::ApiBenchDownload.to_str(),
+            get(handler::api_bench_download).layer(limit_downloads),
+        )
+        // public - anyone with the link
+        .route(
+            ServerEndpoint::Inbox.to_str(),
+            get(handler::inbox_form)
+                .post(handler::inbox_upload)
+                .layer(DefaultBodyLimit::max(PWA_UPLOAD_LIMIT_BYTES)),
+        )
+        // json api - for frontends
+        .merge(api)
+        .merge(dav)
         // apps
         .nest_service(
             ServerEndpoint::ServePWA.to_str(),
@@ -209,28 +782,76 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             ServerEndpoint::App.to_str(),
             get(handler::serve_embedded_app),
         )
-        // .layer(tower_http::trace::TraceLayer::new_for_http())
-        .with_state(state);
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            timing::time_operations,
+        ))
+        // replicas only take changes from their primary
+        .layer(middleware::from_fn(handler::reject_writes_on_replica));
+    #[cfg(feature = "chaos")]
+    let app = app.layer(middleware::from_fn_with_state(state.clone(), chaos::inject));
+    // .layer(tower_http::trace::TraceLayer::new_for_http())
+    let app = app.with_state(state);
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    // the first tcp endpoint is advertised via mDNS and the target of relayed connections
+    let first_tcp_addr = listeners.iter().find_map(bind::Listener::tcp_addr);
+    let _discovery =
+        first_tcp_addr.and_then(|addr| discovery::advertise(addr.port(), tls_paths.is_some()));
+    if let Some(addr) = first_tcp_addr {
+        relay::connect(bind::loopback(addr), tls_paths.is_some());
+    }
 
-    match (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
-        (Ok(cert_path), Ok(key_path)) => {
-            tracing::info!("Starting HTTPS server on {addr}");
-            let tls_config = RustlsConfig::from_pem_file(&cert_path, &key_path)
+    let tls_config = match &tls_paths {
+        Some((cert_path, key_path)) => {
+            // reqwest (ring) and axum-server (aws-lc-rs) both bring a provider - rustls can't pick
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            let tls_config = RustlsConfig::from_pem_file(cert_path, key_path)
                 .await
                 .expect("Failed to load TLS certificate/key");
-            axum_server::bind_rustls(addr, tls_config)
-                .serve(app.into_make_service())
-                .await
-                .unwrap();
+            Some(tls_config)
         }
-        _ => {
-            tracing::info!("Starting HTTP server on {addr} (no TLS_CERT_PATH/TLS_KEY_PATH)");
-            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-            axum::serve(listener, app).await.unwrap();
+        None => {
+            if *quic::HTTP3_ENABLED {
+                warn!("HTTP3=true ignored - HTTP/3 needs TLS_CERT_PATH/TLS_KEY_PATH");
+            }
+            None
+        }
+    };
+
+    let mut servers = JoinSet::new();
+    for listener in listeners {
+        let endpoint = listener.endpoint(tls_config.is_some());
+        let app = app.clone();
+        match (listener, &tls_config, &tls_paths) {
+            (bind::Listener::Tcp(listener), Some(tls_config), Some((cert_path, key_path))) => {
+                info!("Starting HTTPS server on {endpoint}");
+                if *quic::HTTP3_ENABLED {
+                    let addr = listener.local_addr()?;
+                    let (cert_path, key_path) = (cert_path.clone(), key_path.clone());
+                    let app = app.clone();
+                    tokio::spawn(
+                        async move { quic::serve(addr, &cert_path, &key_path, app).await },
+                    );
+                }
+                let server = axum_server::from_tcp_rustls(listener, tls_config.clone())?;
+                servers.spawn(server.serve(app.into_make_service()));
+            }
+            (bind::Listener::Tcp(listener), ..) => {
+                info!("Starting HTTP server on {endpoint} (no TLS_CERT_PATH/TLS_KEY_PATH)");
+                let listener = tokio::net::TcpListener::from_std(listener)?;
+                servers.spawn(async move { axum::serve(listener, app).await });
+            }
+            #[cfg(unix)]
+            (bind::Listener::Unix(listener, _), ..) => {
+                info!("Starting HTTP server on {endpoint} (unix sockets are never TLS)");
+                servers.spawn(async move { axum::serve(listener, app).await });
+            }
         }
     }
 
+    // one failing listener stops the whole server - like the single one did before
+    if let Some(result) = servers.join_next().await {
+        result??;
+    }
     Ok(())
 }
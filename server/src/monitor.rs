@@ -1,28 +1,100 @@
+use crate::UPLOAD_PATH;
+use crate::supervisor;
 use crate::write::RotatingFileWriter;
-use axum::response::IntoResponse;
-use std::sync::{Arc, Mutex};
+use axum::Json;
+use axum::http::StatusCode;
+use chrono::{Local, NaiveDateTime, TimeZone};
+use shared::dtos::{
+    MonitorBucketDto, MonitorData, MonitorQueryDto, MonitorSampleDto, TransfersDto, UploadDiskDto,
+};
+use shared::utc_millis::UtcMillis;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
 use sysinfo::{Disks, System};
-use tracing::{error, trace};
+use tracing::{error, trace, warn};
 
 const BACKOFF_MS: u64 = 10_000;
-pub const DEFAULT_MAX_POINTS: usize = 300;
+const DEFAULT_MAX_POINTS: usize = 300;
+/// more buckets than this per request are refused
+const MAX_BUCKETS: u64 = 10_000;
+/// of the samples in the csv - local time
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
 
-#[derive(serde::Deserialize, Default)]
-pub struct MonitorQuery {
-    pub points: Option<usize>,
+/// uploads are refused if they would leave less free space than this on the upload volume
+/// (env MIN_FREE_DISK_MB, default 1 GiB)
+static MIN_FREE_DISK_BYTES: LazyLock<u64> = LazyLock::new(|| {
+    env::var("MIN_FREE_DISK_MB")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(1024)
+        * 1024
+        * 1024
+});
+
+/// free space on the volume holding UPLOAD_PATH - refreshed by [`monitor_sys`]
+#[derive(Default)]
+pub struct UploadDiskSpace {
+    free_bytes: AtomicU64,
+    /// 0 = not measured yet
+    total_bytes: AtomicU64,
+}
+
+impl UploadDiskSpace {
+    fn update(&self, free_bytes: u64, total_bytes: u64) {
+        self.free_bytes.store(free_bytes, Ordering::Relaxed);
+        self.total_bytes.store(total_bytes, Ordering::Relaxed);
+    }
+
+    pub fn to_dto(&self) -> Option<UploadDiskDto> {
+        let total_bytes = self.total_bytes.load(Ordering::Relaxed);
+        (total_bytes > 0).then(|| UploadDiskDto {
+            free_bytes: self.free_bytes.load(Ordering::Relaxed),
+            total_bytes,
+            min_free_bytes: *MIN_FREE_DISK_BYTES,
+        })
+    }
+
+    /// refuses with 507 if storing `incoming_bytes` would drop below the threshold
+    pub fn ensure_room_for(&self, incoming_bytes: u64) -> Result<(), (StatusCode, String)> {
+        self.ensure_room_for_with_min(incoming_bytes, *MIN_FREE_DISK_BYTES)
+    }
+
+    fn ensure_room_for_with_min(
+        &self,
+        incoming_bytes: u64,
+        min_free_bytes: u64,
+    ) -> Result<(), (StatusCode, String)> {
+        // nothing measured yet - don't block uploads because of missing data
+        if self.total_bytes.load(Ordering::Relaxed) == 0 {
+            return Ok(());
+        }
+        let free_bytes = self.free_bytes.load(Ordering::Relaxed);
+        if free_bytes.saturating_sub(incoming_bytes) < min_free_bytes {
+            warn!(
+                "Refusing upload of {} bytes - only {} bytes free (min {})",
+                incoming_bytes, free_bytes, min_free_bytes
+            );
+            return Err((
+                StatusCode::INSUFFICIENT_STORAGE,
+                "Not enough free disk space on server".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 struct CsvRow {
-    timestamp: String,
-    sys_mem: f32,
-    app_mem: f32,
-    sys_cpu: f32,
-    app_cpu: f32,
-    disk_used: f32,
-    disk_free: f32,
+    utc_millis: u64,
+    sample: MonitorSampleDto,
 }
 
-pub async fn monitor_sys(writer: Arc<Mutex<RotatingFileWriter>>) {
+pub async fn monitor_sys(
+    writer: Arc<Mutex<RotatingFileWriter>>,
+    upload_disk: Arc<UploadDiskSpace>,
+) {
     let mut system = System::new_all();
     let pid = sysinfo::get_current_pid().expect("Failed to get current PID");
     system.refresh_memory();
@@ -31,6 +103,7 @@ pub async fn monitor_sys(writer: Arc<Mutex<RotatingFileWriter>>) {
     let backoff = tokio::time::Duration::from_millis(BACKOFF_MS);
 
     loop {
+        supervisor::ran();
         system.refresh_all();
         let used_sys_mem_percentage = system.used_memory() as f32 / total_sys_mem * 100f32;
         let used_own_mem_percentage = system
@@ -44,15 +117,25 @@ pub async fn monitor_sys(writer: Arc<Mutex<RotatingFileWriter>>) {
             .unwrap_or(0.0);
 
         let disks = Disks::new_with_refreshed_list();
+        let upload_path =
+            std::fs::canonicalize(*UPLOAD_PATH).unwrap_or_else(|_| PathBuf::from("/"));
+        let upload_disk_mount =
+            find_mount_point(&upload_path, disks.list().iter().map(|d| d.mount_point()));
         let root_disk = disks
             .list()
             .iter()
-            .find(|d| d.mount_point() == std::path::Path::new("/"));
+            .find(|d| Some(d.mount_point()) == upload_disk_mount);
+        if let Some(d) = root_disk {
+            upload_disk.update(d.available_space(), d.total_space());
+        }
         let (disk_used_pct, disk_free_gib) = root_disk
             .map(|d| {
                 let total = d.total_space() as f32;
                 let available = d.available_space() as f32;
-                ((total - available) / total * 100.0, available / (1024.0_f32.powi(3)))
+                (
+                    (total - available) / total * 100.0,
+                    available / (1024.0_f32.powi(3)),
+                )
             })
             .unwrap_or((0.0, 0.0));
 
@@ -73,7 +156,7 @@ pub async fn monitor_sys(writer: Arc<Mutex<RotatingFileWriter>>) {
         );
         let csv_line = format!(
             "{};{};{};{};{};{};{}",
-            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S"),
+            Local::now().format(TIMESTAMP_FORMAT),
             used_sys_mem_percentage,
             used_own_mem_percentage,
             used_sys_cpu_percentage,
@@ -81,106 +164,258 @@ pub async fn monitor_sys(writer: Arc<Mutex<RotatingFileWriter>>) {
             disk_used_pct,
             disk_free_gib,
         );
-        if let Err(e) = writer.lock().unwrap().write_line(&csv_line) {
-            error!("Failed to write monitoring data: {}", e);
+        match writer.lock().unwrap().write_line(&csv_line) {
+            Ok(()) => supervisor::succeeded(),
+            Err(e) => {
+                error!("Failed to write monitoring data: {}", e);
+                supervisor::failed(&e);
+            }
         }
+        supervisor::next_run_in(backoff);
         tokio::time::sleep(backoff).await;
     }
 }
 
-/// GET /api/monitor?points=N - JSON monitoring data, downsampled to N points (default 300)
+/// most specific mount point containing `path`
+fn find_mount_point<'a>(
+    path: &Path,
+    mount_points: impl Iterator<Item = &'a Path>,
+) -> Option<&'a Path> {
+    mount_points
+        .filter(|mount_point| path.starts_with(mount_point))
+        .max_by_key(|mount_point| mount_point.components().count())
+}
+
+/// GET /api/monitor?from=&to=&resolution= - the kept samples averaged / maxed per bucket
 pub async fn api_get_monitoring(
     writer: Arc<Mutex<RotatingFileWriter>>,
-    max_points: usize,
-) -> impl IntoResponse {
-    let csv_content = match writer.lock().unwrap().read_current_file() {
-        Ok(content) => content,
-        Err(err) => {
-            return (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error reading monitoring data: {}", err),
-            )
-                .into_response();
-        }
-    };
-    let data_json = csv_to_json(&csv_content, max_points);
-    ([("content-type", "application/json")], data_json).into_response()
+    upload_disk: Arc<UploadDiskSpace>,
+    transfers: TransfersDto,
+    query: MonitorQueryDto,
+) -> Result<Json<MonitorData>, (StatusCode, String)> {
+    let files = writer.lock().unwrap().read_all_files().map_err(|err| {
+        error!("Error reading monitoring data: {}", err);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Error reading monitoring data: {}", err),
+        )
+    })?;
+    let rows: Vec<CsvRow> = files.iter().flat_map(|csv| parse_csv_rows(csv)).collect();
+    let (resolution, buckets) = aggregate(&rows, &query, UtcMillis::now().as_u64())?;
+    Ok(Json(MonitorData {
+        resolution,
+        buckets,
+        upload_disk: upload_disk.to_dto(),
+        transfers: Some(transfers),
+    }))
 }
 
+/// the timestamps are local time - lines that don't parse (like the headers) are skipped
 fn parse_csv_rows(csv: &str) -> Vec<CsvRow> {
     csv.lines()
-        .enumerate()
-        .filter(|(i, _)| *i != 0)
-        .filter_map(|(_, line)| {
+        .filter_map(|line| {
             let parts: Vec<&str> = line.split(';').collect();
             if parts.len() < 5 {
                 return None;
             }
+            let timestamp = NaiveDateTime::parse_from_str(parts[0], TIMESTAMP_FORMAT).ok()?;
+            let utc_millis = Local
+                .from_local_datetime(&timestamp)
+                .earliest()?
+                .timestamp_millis();
             Some(CsvRow {
-                timestamp: parts[0].to_string(),
-                sys_mem: parts[1].parse().unwrap_or(0.0),
-                app_mem: parts[2].parse().unwrap_or(0.0),
-                sys_cpu: parts[3].parse().unwrap_or(0.0),
-                app_cpu: parts[4].parse().unwrap_or(0.0),
-                disk_used: parts.get(5).and_then(|v| v.parse().ok()).unwrap_or(0.0),
-                disk_free: parts.get(6).and_then(|v| v.parse().ok()).unwrap_or(0.0),
+                utc_millis: u64::try_from(utc_millis).ok()?,
+                sample: MonitorSampleDto {
+                    sys_mem: parts[1].parse().unwrap_or(0.0),
+                    app_mem: parts[2].parse().unwrap_or(0.0),
+                    sys_cpu: parts[3].parse().unwrap_or(0.0),
+                    app_cpu: parts[4].parse().unwrap_or(0.0),
+                    disk_used: parts.get(5).and_then(|v| v.parse().ok()).unwrap_or(0.0),
+                    disk_free: parts.get(6).and_then(|v| v.parse().ok()).unwrap_or(0.0),
+                },
             })
         })
         .collect()
 }
 
-fn downsample(rows: Vec<CsvRow>, max_points: usize) -> Vec<CsvRow> {
-    let n = rows.len();
-    if n <= max_points {
-        return rows;
+/// (resolution in seconds, buckets) - buckets start at multiples of the resolution so they stay
+/// put between requests
+fn aggregate(
+    rows: &[CsvRow],
+    query: &MonitorQueryDto,
+    now: u64,
+) -> Result<(u64, Vec<MonitorBucketDto>), (StatusCode, String)> {
+    let to = query.to.unwrap_or(now);
+    let from = query
+        .from
+        .or_else(|| rows.iter().map(|row| row.utc_millis).min())
+        .unwrap_or(to);
+    if from > to {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "from must not be after to".to_string(),
+        ));
     }
-    let bucket_size = n as f64 / max_points as f64;
-    (0..max_points)
-        .map(|i| {
-            let start = (i as f64 * bucket_size) as usize;
-            let end = ((i + 1) as f64 * bucket_size) as usize;
-            let end = end.min(n);
-            let bucket = &rows[start..end];
-            let count = bucket.len() as f32;
-            CsvRow {
-                timestamp: bucket[0].timestamp.clone(),
-                sys_mem: bucket.iter().map(|r| r.sys_mem).sum::<f32>() / count,
-                app_mem: bucket.iter().map(|r| r.app_mem).sum::<f32>() / count,
-                sys_cpu: bucket.iter().map(|r| r.sys_cpu).sum::<f32>() / count,
-                app_cpu: bucket.iter().map(|r| r.app_cpu).sum::<f32>() / count,
-                disk_used: bucket.iter().map(|r| r.disk_used).sum::<f32>() / count,
-                disk_free: bucket.iter().map(|r| r.disk_free).sum::<f32>() / count,
-            }
+    let span_secs = (to - from) / 1000 + 1;
+    let resolution = match query.resolution {
+        Some(0) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "resolution must be at least 1 second".to_string(),
+            ));
+        }
+        Some(resolution) => resolution,
+        // finer than the samples makes no sense
+        None => span_secs
+            .div_ceil(query.points.unwrap_or(DEFAULT_MAX_POINTS).max(1) as u64)
+            .max(BACKOFF_MS / 1000),
+    };
+    if span_secs / resolution > MAX_BUCKETS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("More than {MAX_BUCKETS} buckets - pick a coarser resolution"),
+        ));
+    }
+
+    let width = resolution * 1000;
+    let mut per_bucket: BTreeMap<u64, Vec<&MonitorSampleDto>> = BTreeMap::new();
+    for row in rows
+        .iter()
+        .filter(|row| row.utc_millis >= from && row.utc_millis <= to)
+    {
+        per_bucket
+            .entry(row.utc_millis / width * width)
+            .or_default()
+            .push(&row.sample);
+    }
+    let buckets = per_bucket
+        .into_iter()
+        .map(|(start_utc_millis, samples)| MonitorBucketDto {
+            start_utc_millis,
+            avg: combine(&samples, |values| {
+                values.iter().sum::<f32>() / values.len() as f32
+            }),
+            max: combine(&samples, |values| {
+                values.iter().copied().fold(f32::MIN, f32::max)
+            }),
         })
-        .collect()
+        .collect();
+    Ok((resolution, buckets))
 }
 
-fn csv_to_json(csv: &str, max_points: usize) -> String {
-    let rows = downsample(parse_csv_rows(csv), max_points);
-
-    let mut sys_mem = Vec::with_capacity(rows.len());
-    let mut app_mem = Vec::with_capacity(rows.len());
-    let mut sys_cpu = Vec::with_capacity(rows.len());
-    let mut app_cpu = Vec::with_capacity(rows.len());
-    let mut disk_used = Vec::with_capacity(rows.len());
-    let mut disk_free = Vec::with_capacity(rows.len());
-
-    for row in &rows {
-        sys_mem.push(format!(r#"{{"x":"{}","y":{}}}"#, row.timestamp, row.sys_mem));
-        app_mem.push(format!(r#"{{"x":"{}","y":{}}}"#, row.timestamp, row.app_mem));
-        sys_cpu.push(format!(r#"{{"x":"{}","y":{}}}"#, row.timestamp, row.sys_cpu));
-        app_cpu.push(format!(r#"{{"x":"{}","y":{}}}"#, row.timestamp, row.app_cpu));
-        disk_used.push(format!(r#"{{"x":"{}","y":{}}}"#, row.timestamp, row.disk_used));
-        disk_free.push(format!(r#"{{"x":"{}","y":{:.2}}}"#, row.timestamp, row.disk_free));
+/// applies `combine` field by field - `samples` is never empty
+fn combine(samples: &[&MonitorSampleDto], combine: fn(&[f32]) -> f32) -> MonitorSampleDto {
+    let field = |get: fn(&MonitorSampleDto) -> f32| {
+        combine(&samples.iter().map(|s| get(s)).collect::<Vec<_>>())
+    };
+    MonitorSampleDto {
+        sys_mem: field(|s| s.sys_mem),
+        app_mem: field(|s| s.app_mem),
+        sys_cpu: field(|s| s.sys_cpu),
+        app_cpu: field(|s| s.app_cpu),
+        disk_used: field(|s| s.disk_used),
+        disk_free: field(|s| s.disk_free),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_pick_most_specific_mount_point() {
+        let mounts = [Path::new("/"), Path::new("/mnt"), Path::new("/mnt/usb")];
+        assert_eq!(
+            Some(Path::new("/mnt/usb")),
+            find_mount_point(Path::new("/mnt/usb/data/upload"), mounts.into_iter())
+        );
+        assert_eq!(
+            Some(Path::new("/")),
+            find_mount_point(Path::new("/home/pi/data"), mounts.into_iter())
+        );
+    }
+
+    #[test]
+    fn should_refuse_upload_below_min_free_space() {
+        let disk = UploadDiskSpace::default();
+        assert!(disk.ensure_room_for_with_min(500, 100).is_ok());
+
+        disk.update(1_000, 10_000);
+        assert!(disk.ensure_room_for_with_min(500, 100).is_ok());
+        assert_eq!(
+            StatusCode::INSUFFICIENT_STORAGE,
+            disk.ensure_room_for_with_min(950, 100).unwrap_err().0
+        );
+    }
+
+    fn row(utc_millis: u64, sys_cpu: f32) -> CsvRow {
+        CsvRow {
+            utc_millis,
+            sample: MonitorSampleDto {
+                sys_cpu,
+                ..Default::default()
+            },
+        }
     }
 
-    format!(
-        r#"{{"sys_mem":[{}],"app_mem":[{}],"sys_cpu":[{}],"app_cpu":[{}],"disk_used":[{}],"disk_free":[{}]}}"#,
-        sys_mem.join(","),
-        app_mem.join(","),
-        sys_cpu.join(","),
-        app_cpu.join(","),
-        disk_used.join(","),
-        disk_free.join(","),
-    )
+    #[test]
+    fn should_aggregate_samples_per_bucket() {
+        let rows = [
+            row(60_000, 10.0),
+            row(70_000, 30.0),
+            row(130_000, 50.0),
+            row(500_000, 90.0),
+        ];
+        let query = MonitorQueryDto {
+            from: Some(60_000),
+            to: Some(200_000),
+            resolution: Some(60),
+            ..Default::default()
+        };
+
+        let (resolution, buckets) = aggregate(&rows, &query, 1_000_000).unwrap();
+
+        assert_eq!(60, resolution);
+        assert_eq!(2, buckets.len());
+        assert_eq!(60_000, buckets[0].start_utc_millis);
+        assert_eq!(20.0, buckets[0].avg.sys_cpu);
+        assert_eq!(30.0, buckets[0].max.sys_cpu);
+        assert_eq!(120_000, buckets[1].start_utc_millis);
+        assert_eq!(50.0, buckets[1].max.sys_cpu);
+    }
+
+    #[test]
+    fn should_refuse_inverted_span_and_too_many_buckets() {
+        let inverted = MonitorQueryDto {
+            from: Some(2_000),
+            to: Some(1_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            aggregate(&[], &inverted, 0).unwrap_err().0
+        );
+
+        let too_fine = MonitorQueryDto {
+            from: Some(0),
+            to: Some(30 * 24 * 3_600_000),
+            resolution: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            aggregate(&[], &too_fine, 0).unwrap_err().0
+        );
+    }
+
+    #[test]
+    fn should_parse_local_timestamps_and_skip_headers() {
+        let rows = parse_csv_rows(
+            "timestamp;sys_mem;app_mem;sys_cpu;app_cpu;disk_used;disk_free\n\
+             2024-05-01T12:00:00;40.5;1.5;12.0;0.5;70.0;100.0\n",
+        );
+        assert_eq!(1, rows.len());
+        assert_eq!(12.0, rows[0].sample.sys_cpu);
+        assert_eq!(100.0, rows[0].sample.disk_free);
+    }
 }
@@ -1,83 +1,691 @@
 use crate::client_file_event::ClientFileEventDto;
-use crate::write::write_all_chunks_of_field;
+use crate::write::{copy_hashing, write_all_chunks_of_field, write_all_chunks_of_stream};
 use axum::extract::Multipart;
+use axum::extract::multipart::{Field, MultipartError};
 use axum::http::StatusCode;
+use futures_util::stream;
+use sha2::{Digest, Sha256};
+use shared::dtos::{Durability, FileDescription};
+use shared::matchable_path::MatchablePath;
 use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{Display, Formatter};
 use std::fs;
-use std::path::{Path, PathBuf};
-use tracing::error;
-use tracing::log::info;
+use std::fs::File;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tracing::{error, warn};
 use uuid::Uuid;
 
+/// guards of [`parse_multipart_request`] - against clients that stall or send garbage
+#[derive(Debug, Clone)]
+pub struct MultipartLimits {
+    /// `utc_millis` and `relative_path` are tiny - anything larger isn't one of our clients
+    pub max_text_field_bytes: usize,
+    /// no new bytes within this time aborts the upload
+    pub idle_timeout: Duration,
+    /// for the whole request - `UPLOAD_TIMEOUT_SECS` (default: 6h)
+    pub total_timeout: Duration,
+}
+
+pub static MULTIPART_LIMITS: LazyLock<MultipartLimits> = LazyLock::new(|| MultipartLimits {
+    max_text_field_bytes: 4 * 1024,
+    idle_timeout: Duration::from_secs(60),
+    total_timeout: Duration::from_secs(
+        env::var("UPLOAD_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(6 * 60 * 60),
+    ),
+});
+
+/// why an upload request was refused
+#[derive(Debug)]
+pub enum UploadError {
+    /// broken multipart framing (or not multipart at all)
+    Malformed(String),
+    /// fields have to arrive as `utc_millis`, `relative_path`, `file` - nothing else
+    UnexpectedField {
+        expected: &'static str,
+        actual: String,
+    },
+    MissingField(&'static str),
+    InvalidValue {
+        field: &'static str,
+        value: String,
+    },
+    /// a text field above [`MultipartLimits::max_text_field_bytes`] or the body limit
+    TooLarge(String),
+    Timeout,
+    Io(io::Error),
+}
+
+impl UploadError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            UploadError::Malformed(_)
+            | UploadError::UnexpectedField { .. }
+            | UploadError::MissingField(_)
+            | UploadError::InvalidValue { .. } => StatusCode::BAD_REQUEST,
+            UploadError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            UploadError::Timeout => StatusCode::REQUEST_TIMEOUT,
+            UploadError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// chunk errors travel through [`write_all_chunks_of_stream`] as io errors
+    fn from_io(error: io::Error) -> Self {
+        match error.downcast::<UploadError>() {
+            Ok(error) => error,
+            Err(error) => UploadError::Io(error),
+        }
+    }
+}
+
+impl From<MultipartError> for UploadError {
+    fn from(error: MultipartError) -> Self {
+        // NOTE - exceeding the axum body limit (see main.rs) ends up here as well
+        match error.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => UploadError::TooLarge(error.body_text()),
+            _ => UploadError::Malformed(error.body_text()),
+        }
+    }
+}
+
+impl Display for UploadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadError::Malformed(e) => write!(f, "Malformed upload - {e}"),
+            UploadError::UnexpectedField { expected, actual } => {
+                write!(f, "Expected field '{expected}' but got '{actual}'")
+            }
+            UploadError::MissingField(field) => write!(f, "Missing field '{field}'"),
+            UploadError::InvalidValue { field, value } => {
+                write!(f, "Invalid value '{value}' of field '{field}'")
+            }
+            UploadError::TooLarge(e) => write!(f, "Upload too large - {e}"),
+            UploadError::Timeout => write!(f, "Upload timed out"),
+            UploadError::Io(e) => write!(f, "Error writing multipart chunks - {e}"),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+impl From<UploadError> for (StatusCode, String) {
+    fn from(error: UploadError) -> Self {
+        (error.status(), error.to_string())
+    }
+}
+
+/// sys upload of a client - strictly `utc_millis`, `relative_path`, `file` in this order
+///
+/// the temp file is removed again if anything goes wrong - `request_size` (the content length)
+/// is used to preallocate it
 pub async fn parse_multipart_request(
     upload_root_tmp_path: &Path,
     multipart: &mut Multipart,
     watch_group_id: i64,
-) -> Result<ClientFileEventDto, (StatusCode, String)> {
-    let mut utc_millis: Option<UtcMillis> = None;
-    let mut relative_path: Option<Vec<String>> = None;
+    verify_writes: bool,
+    durability: Durability,
+    request_size: Option<u64>,
+    limits: &MultipartLimits,
+) -> Result<ClientFileEventDto, UploadError> {
     let mut temp_file_path: Option<PathBuf> = None;
-    let mut content_size: Option<usize> = None;
+    let parsed = tokio::time::timeout(
+        limits.total_timeout,
+        parse_upload_fields(
+            upload_root_tmp_path,
+            multipart,
+            verify_writes,
+            durability,
+            request_size,
+            limits,
+            &mut temp_file_path,
+        ),
+    )
+    .await
+    .unwrap_or(Err(UploadError::Timeout));
 
-    while let Some(field) = multipart.next_field().await.unwrap() {
-        match field.name() {
-            None => error!("No field name in upload handler!"),
-            Some("utc_millis") => {
-                utc_millis = field
-                    .text()
-                    .await
-                    .map(|t| {
-                        info!("{}", t);
-                        let r = t.parse::<u64>().ok().map(UtcMillis::from);
-                        info!("{:?}", r);
-                        r
-                    })
-                    .ok()
-                    .flatten();
+    match parsed {
+        Ok((utc_millis, relative_path, content_size, content_hash)) => Ok(ClientFileEventDto {
+            utc_millis: Some(utc_millis),
+            relative_path: Some(relative_path),
+            temp_file_path,
+            content_size: Some(content_size),
+            watch_group_id,
+            content_hash,
+            conflict_kept: None,
+        }),
+        Err(e) => {
+            if let Some(temp_path) = temp_file_path
+                && let Err(e) = fs::remove_file(&temp_path)
+                && e.kind() != io::ErrorKind::NotFound
+            {
+                error!("Broken temp-file ({temp_path:?}) couldn't be deleted! Error: ({e})");
             }
-            Some("relative_path") => {
-                relative_path = field
-                    .text()
-                    .await
-                    .map(|t| t.split("/").map(|str| str.to_string()).collect())
-                    .ok();
+            warn!("Refused upload - {e}");
+            Err(e)
+        }
+    }
+}
+
+/// `temp_file_path` is set as soon as the file is created - so the caller can clean up
+async fn parse_upload_fields(
+    upload_root_tmp_path: &Path,
+    multipart: &mut Multipart,
+    verify_writes: bool,
+    durability: Durability,
+    request_size: Option<u64>,
+    limits: &MultipartLimits,
+    temp_file_path: &mut Option<PathBuf>,
+) -> Result<(UtcMillis, Vec<String>, usize, Option<String>), UploadError> {
+    let text = read_text_field(multipart, "utc_millis", limits).await?;
+    let utc_millis =
+        text.parse::<u64>()
+            .map(UtcMillis::from)
+            .map_err(|_| UploadError::InvalidValue {
+                field: "utc_millis",
+                value: text,
+            })?;
+
+    let text = read_text_field(multipart, "relative_path", limits).await?;
+    let relative_path: Vec<String> = text.split('/').map(str::to_string).collect();
+    if relative_path.iter().all(String::is_empty) {
+        return Err(UploadError::InvalidValue {
+            field: "relative_path",
+            value: text,
+        });
+    }
+
+    let field = expect_field(multipart, "file", limits).await?;
+    // only used for debugging - the file ends up at `relative_path`
+    let original_file_name = field
+        .file_name()
+        .unwrap_or("unknown_file")
+        .replace(['/', '\\'], "_");
+    let temp_path = upload_root_tmp_path.join(format!("{}_{}", Uuid::new_v4(), original_file_name));
+    *temp_file_path = Some(temp_path.clone());
+    let idle_timeout = limits.idle_timeout;
+    let chunks = stream::unfold(field, move |mut field| async move {
+        match tokio::time::timeout(idle_timeout, field.chunk()).await {
+            Ok(Ok(Some(bytes))) => Some((Ok(bytes), field)),
+            Ok(Ok(None)) => None,
+            Ok(Err(e)) => Some((Err(UploadError::from(e)), field)),
+            Err(_) => Some((Err(UploadError::Timeout), field)),
+        }
+    });
+    let mut hasher = verify_writes.then(Sha256::new);
+    let content_size = write_all_chunks_of_stream(
+        &temp_path,
+        Box::pin(chunks),
+        hasher.as_mut(),
+        request_size,
+        durability,
+    )
+    .await
+    .map_err(UploadError::from_io)?;
+
+    if let Some(field) = next_field(multipart, limits).await? {
+        return Err(UploadError::UnexpectedField {
+            expected: "end of request",
+            actual: field.name().unwrap_or_default().to_string(),
+        });
+    }
+    Ok((
+        utc_millis,
+        relative_path,
+        content_size,
+        hasher.map(|hasher| format!("{:x}", hasher.finalize())),
+    ))
+}
+
+async fn next_field<'a>(
+    multipart: &'a mut Multipart,
+    limits: &MultipartLimits,
+) -> Result<Option<Field<'a>>, UploadError> {
+    tokio::time::timeout(limits.idle_timeout, multipart.next_field())
+        .await
+        .map_err(|_| UploadError::Timeout)?
+        .map_err(UploadError::from)
+}
+
+async fn expect_field<'a>(
+    multipart: &'a mut Multipart,
+    name: &'static str,
+    limits: &MultipartLimits,
+) -> Result<Field<'a>, UploadError> {
+    let field = next_field(multipart, limits)
+        .await?
+        .ok_or(UploadError::MissingField(name))?;
+    match field.name() {
+        Some(actual) if actual == name => Ok(field),
+        actual => Err(UploadError::UnexpectedField {
+            expected: name,
+            actual: actual.unwrap_or_default().to_string(),
+        }),
+    }
+}
+
+async fn read_text_field(
+    multipart: &mut Multipart,
+    name: &'static str,
+    limits: &MultipartLimits,
+) -> Result<String, UploadError> {
+    let mut field = expect_field(multipart, name, limits).await?;
+    let mut bytes = Vec::new();
+    loop {
+        let chunk = tokio::time::timeout(limits.idle_timeout, field.chunk())
+            .await
+            .map_err(|_| UploadError::Timeout)??;
+        let Some(chunk) = chunk else {
+            break;
+        };
+        if bytes.len() + chunk.len() > limits.max_text_field_bytes {
+            return Err(UploadError::TooLarge(format!(
+                "field '{name}' exceeds {} bytes",
+                limits.max_text_field_bytes
+            )));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    String::from_utf8(bytes).map_err(|e| UploadError::InvalidValue {
+        field: name,
+        value: String::from_utf8_lossy(e.as_bytes()).to_string(),
+    })
+}
+
+/// bulk upload: `manifest` (json list of [`FileDescription`]) + `archive` (tar of these files)
+pub async fn parse_bulk_multipart_request(
+    upload_root_tmp_path: &Path,
+    multipart: &mut Multipart,
+) -> Result<(Vec<FileDescription>, PathBuf), (StatusCode, String)> {
+    let mut manifest: Option<Vec<FileDescription>> = None;
+    let mut archive_path: Option<PathBuf> = None;
+
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        match field.name() {
+            Some("manifest") => {
+                let text = field.text().await.map_err(|e| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Could not read manifest - {e}"),
+                    )
+                })?;
+                manifest =
+                    Some(serde_json::from_str(&text).map_err(|e| {
+                        (StatusCode::BAD_REQUEST, format!("Invalid manifest - {e}"))
+                    })?);
             }
-            Some("file") => {
-                let random_uuid = Uuid::new_v4(); // avoid collision
-                let original_file_name = field.file_name().unwrap_or("unknown_file");
-                let temp_path = upload_root_tmp_path.join(Path::new(
-                    format!("./{}_{}", random_uuid, original_file_name).as_str(),
-                ));
-                let s = write_all_chunks_of_field(temp_path.as_path(), field)
+            Some("archive") => {
+                let temp_path = upload_root_tmp_path.join(format!("{}_bulk.tar", Uuid::new_v4()));
+                // unpacked into temp files of their own - see [`unpack_bulk_archive`]
+                write_all_chunks_of_field(temp_path.as_path(), field, None, Durability::None)
                     .await
-                    // NOTE - generic: Err("Error parsing `multipart/form-data` request") will be
-                    // returned if the axum body limit is exceeded - make sure to adjust the limit
-                    // in main.rs
                     .map_err(|e| {
-                        if let Err(e) = fs::remove_file(&temp_path) {
-                            error!(
-                                "Broken temp-file ({:?}) couldn't be deleted! Error: ({})",
-                                &temp_path, e
-                            );
-                        }
-
+                        let _ = fs::remove_file(&temp_path);
                         (
                             StatusCode::INTERNAL_SERVER_ERROR,
                             format!("Error writing multipart chunks - {}", e),
                         )
                     })?;
-                temp_file_path = Some(temp_path);
-                content_size = Some(s);
+                archive_path = Some(temp_path);
             }
-            Some(other) => error!("Unknown field name '{other}' in upload handler"),
+            other => error!("Unknown field name '{other:?}' in bulk upload handler"),
         }
     }
 
-    Ok(ClientFileEventDto {
-        utc_millis,
-        relative_path,
-        temp_file_path,
-        content_size,
-        watch_group_id,
-    })
+    match (manifest, archive_path) {
+        (Some(manifest), Some(archive_path)) => Ok((manifest, archive_path)),
+        (_, archive_path) => {
+            if let Some(archive_path) = archive_path {
+                let _ = fs::remove_file(archive_path);
+            }
+            Err((
+                StatusCode::BAD_REQUEST,
+                "Bulk upload needs a 'manifest' and an 'archive' field".to_string(),
+            ))
+        }
+    }
+}
+
+/// writes every archived file of the manifest to its own temp file
+///
+/// returns one upload per extracted file plus the manifest entries missing in the archive
+pub fn unpack_bulk_archive(
+    archive_path: &Path,
+    manifest: Vec<FileDescription>,
+    upload_root_tmp_path: &Path,
+    watch_group_id: i64,
+    verify_writes: bool,
+    durability: Durability,
+) -> io::Result<(Vec<ClientFileEventDto>, Vec<FileDescription>)> {
+    let mut pending: HashMap<MatchablePath, FileDescription> = manifest
+        .into_iter()
+        .map(|d| (d.relative_path.clone(), d))
+        .collect();
+    let mut uploads: Vec<ClientFileEventDto> = Vec::new();
+
+    let result = (|| {
+        let mut archive = tar::Archive::new(File::open(archive_path)?);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            // directories (e.g. the `./` of `tar -C dir .`) - MatchablePath panics without any
+            // normal component
+            let entry_path = entry.path()?;
+            if !entry.header().entry_type().is_file()
+                || !entry_path
+                    .components()
+                    .any(|c| matches!(c, Component::Normal(_)))
+            {
+                continue;
+            }
+            // only normal components are kept - an entry can't point outside the watch group
+            let path = MatchablePath::from(entry_path.as_ref());
+            let Some(description) = pending.remove(&path) else {
+                warn!("Skipping archived file {:?} - not in manifest", path);
+                continue;
+            };
+
+            let temp_path =
+                upload_root_tmp_path.join(format!("{}_{}", Uuid::new_v4(), description.file_name));
+            let mut hasher = verify_writes.then(Sha256::new);
+            let mut file = File::create(&temp_path)?;
+            let size = copy_hashing(&mut entry, &mut file, hasher.as_mut())?;
+            if durability.syncs_file() {
+                file.sync_all()?;
+            }
+            uploads.push(ClientFileEventDto {
+                utc_millis: Some(description.last_updated_utc_millis),
+                relative_path: Some(path.get().clone()),
+                temp_file_path: Some(temp_path),
+                content_size: Some(size as usize),
+                watch_group_id,
+                content_hash: hasher.map(|hasher| format!("{:x}", hasher.finalize())),
+                conflict_kept: None,
+            });
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => Ok((uploads, pending.into_values().collect())),
+        Err(e) => {
+            for upload in uploads {
+                if let Some(temp_path) = upload.temp_file_path {
+                    let _ = fs::remove_file(temp_path);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::FromRequest;
+    use axum::http::Request;
+    use axum::http::header::CONTENT_TYPE;
+    use futures_util::StreamExt;
+
+    const BOUNDARY: &str = "XyZ";
+
+    type Fields<'a> = &'a [(&'a str, &'a [u8])];
+
+    fn limits() -> MultipartLimits {
+        MultipartLimits {
+            max_text_field_bytes: 64,
+            idle_timeout: Duration::from_millis(200),
+            total_timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn form(fields: Fields) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (name, content) in fields {
+            let file_name = if *name == "file" {
+                "; filename=\"a.txt\""
+            } else {
+                ""
+            };
+            body.extend_from_slice(
+                format!(
+                    "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"{file_name}\r\n\r\n"
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        body
+    }
+
+    fn valid_form() -> Vec<u8> {
+        form(&[
+            ("utc_millis", b"42"),
+            ("relative_path", b"docs/a.txt"),
+            ("file", b"hello"),
+        ])
+    }
+
+    async fn parse(dir: &Path, body: Body) -> Result<ClientFileEventDto, UploadError> {
+        let request = Request::builder()
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(body)
+            .unwrap();
+        let mut multipart = Multipart::from_request(request, &()).await.unwrap();
+        parse_multipart_request(
+            dir,
+            &mut multipart,
+            1,
+            true,
+            Durability::None,
+            None,
+            &limits(),
+        )
+        .await
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("multipart-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn is_empty(dir: &Path) -> bool {
+        fs::read_dir(dir).unwrap().next().is_none()
+    }
+
+    #[tokio::test]
+    async fn should_parse_upload() {
+        let dir = temp_dir();
+        let dto = parse(&dir, Body::from(valid_form())).await.unwrap();
+
+        assert_eq!(Some(UtcMillis::from(42)), dto.utc_millis);
+        assert_eq!(
+            Some(vec!["docs".to_string(), "a.txt".to_string()]),
+            dto.relative_path
+        );
+        assert_eq!(Some(5), dto.content_size);
+        assert_eq!(
+            Some(format!("{:x}", Sha256::digest(b"hello"))),
+            dto.content_hash
+        );
+        assert_eq!(
+            "hello",
+            fs::read_to_string(dto.temp_file_path.unwrap()).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_refuse_unexpected_fields() {
+        let dir = temp_dir();
+        let cases: [(Fields, StatusCode); 8] = [
+            (&[], StatusCode::BAD_REQUEST),
+            (
+                &[("relative_path", b"a.txt"), ("utc_millis", b"42")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                &[("utc_millis", b"42"), ("utc_millis", b"43")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                &[("utc_millis", b"42"), ("relative_path", b"a.txt")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                &[("utc_millis", b"yesterday"), ("relative_path", b"a.txt")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                &[("utc_millis", b"42"), ("relative_path", b"/")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                &[("utc_millis", &[b'1'; 65])],
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+            (
+                &[
+                    ("utc_millis", b"42"),
+                    ("relative_path", b"a.txt"),
+                    ("file", b"hello"),
+                    ("file", b"again"),
+                ],
+                StatusCode::BAD_REQUEST,
+            ),
+        ];
+
+        for (fields, status) in cases {
+            let error = parse(&dir, Body::from(form(fields))).await.unwrap_err();
+            assert_eq!(status, error.status(), "{error}");
+            assert!(is_empty(&dir), "temp file left after '{error}'");
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_survive_truncated_and_mangled_bodies() {
+        let dir = temp_dir();
+        let valid = valid_form();
+        let mut bodies: Vec<Vec<u8>> = (0..valid.len()).map(|n| valid[..n].to_vec()).collect();
+        // xorshift - reproducible without a rng crate
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed as usize
+        };
+        for _ in 0..300 {
+            let mut body = valid.clone();
+            for _ in 0..1 + next() % 4 {
+                let i = next() % body.len();
+                body[i] = next() as u8;
+            }
+            bodies.push(body);
+        }
+
+        for body in bodies {
+            match parse(&dir, Body::from(body.clone())).await {
+                Ok(dto) => fs::remove_file(dto.temp_file_path.unwrap()).unwrap(),
+                Err(error) => {
+                    assert_ne!(StatusCode::INTERNAL_SERVER_ERROR, error.status(), "{error}");
+                    assert!(is_empty(&dir), "temp file left after '{error}'");
+                }
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_time_out_stalled_uploads() {
+        let dir = temp_dir();
+        let valid = valid_form();
+        let stalled = valid[..valid.len() - 20].to_vec();
+        let body = futures_util::stream::once(async move { Ok::<_, io::Error>(stalled) })
+            .chain(futures_util::stream::pending());
+
+        let error = parse(&dir, Body::from_stream(body)).await.unwrap_err();
+        assert!(matches!(error, UploadError::Timeout), "{error}");
+        assert!(is_empty(&dir));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn description(path: &str, millis: u64) -> FileDescription {
+        let relative_path = MatchablePath::from(path);
+        FileDescription {
+            file_name: relative_path.tail(),
+            relative_path,
+            size_in_bytes: 0,
+            file_type: String::new(),
+            last_updated_utc_millis: UtcMillis::from(millis),
+        }
+    }
+
+    fn append(builder: &mut tar::Builder<File>, path: &str, content: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content).unwrap();
+    }
+
+    #[test]
+    fn should_unpack_files_of_manifest() {
+        let dir = std::env::temp_dir().join(format!("bulk-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("bulk.tar");
+        let mut builder = tar::Builder::new(File::create(&archive_path).unwrap());
+        // `tar -C dir .` starts with the directory itself
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        header.set_cksum();
+        builder.append_data(&mut header, "./", io::empty()).unwrap();
+        append(&mut builder, "./docs/a.txt", b"hello");
+        append(&mut builder, "not-in-manifest.txt", b"?");
+        builder.finish().unwrap();
+        drop(builder);
+
+        let manifest = vec![description("docs/a.txt", 42), description("missing.txt", 1)];
+        let (uploads, missing) =
+            unpack_bulk_archive(&archive_path, manifest, &dir, 1, true, Durability::None).unwrap();
+
+        assert_eq!(1, uploads.len());
+        let upload = &uploads[0];
+        assert_eq!(Some(UtcMillis::from(42)), upload.utc_millis);
+        assert_eq!(
+            Some(vec!["docs".to_string(), "a.txt".to_string()]),
+            upload.relative_path
+        );
+        assert_eq!(Some(5), upload.content_size);
+        assert_eq!(
+            Some(format!("{:x}", Sha256::digest(b"hello"))),
+            upload.content_hash
+        );
+        assert_eq!(
+            "hello",
+            fs::read_to_string(upload.temp_file_path.as_ref().unwrap()).unwrap()
+        );
+        assert_eq!(1, missing.len());
+        assert_eq!(
+            "missing.txt",
+            missing[0].relative_path.to_serialized_string()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,171 @@
+use crate::file_event::FileEvent;
+use crate::{AppState, UPLOAD_PATH};
+use shared::matchable_path::MatchablePath;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// mirrors the file of a recorded event into the publish target of its watch group (if any) -
+/// in the background, like webhooks
+///
+/// the mirror follows the current file rather than the event, so racing events still end up
+/// with the latest state
+pub fn mirror(state: &AppState, event: &FileEvent) {
+    let state = state.clone();
+    let wg_id = event.watch_group_id;
+    let path = event.relative_path.clone();
+    tokio::spawn(async move {
+        let Some(target_root) = publish_target(&state, wg_id).await else {
+            return;
+        };
+        let source_root = UPLOAD_PATH.join(wg_id.to_string());
+        let result =
+            tokio::task::spawn_blocking(move || sync_path(&source_root, &target_root, &path)).await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Failed to publish file of watch group {} - {}", wg_id, e),
+            Err(e) => error!("Publishing panicked - {}", e),
+        }
+    });
+}
+
+/// mirrors all current files of the watch group - after its publish target was set
+pub async fn publish_all(state: &AppState, wg_id: i64) {
+    let Some(target_root) = publish_target(state, wg_id).await else {
+        return;
+    };
+    let paths: Vec<MatchablePath> = state
+        .history
+        .get_latest_events(wg_id)
+        .into_iter()
+        .filter(|e| e.event_type.is_change())
+        .map(|e| e.relative_path)
+        .collect();
+    let source_root = UPLOAD_PATH.join(wg_id.to_string());
+    let result = tokio::task::spawn_blocking(move || {
+        let mut failed = 0;
+        for path in &paths {
+            if let Err(e) = sync_path(&source_root, &target_root, path) {
+                debug!("Failed to publish {} - {}", path.to_serialized_string(), e);
+                failed += 1;
+            }
+        }
+        (paths.len(), failed)
+    })
+    .await;
+    match result {
+        Ok((total, 0)) => info!("Published {} files of watch group {}", total, wg_id),
+        Ok((total, failed)) => error!(
+            "Published watch group {} - {} of {} files failed",
+            wg_id, failed, total
+        ),
+        Err(e) => error!("Publishing panicked - {}", e),
+    }
+}
+
+async fn publish_target(state: &AppState, wg_id: i64) -> Option<PathBuf> {
+    match state.db.server_watch_group().publish_path(wg_id).await {
+        Ok(target) => target.map(PathBuf::from),
+        Err(e) => {
+            error!("Failed to read publish target of watch group: {}", e);
+            None
+        }
+    }
+}
+
+/// the publish target must be an absolute dir outside of the watch groups (and not contain
+/// them) - it's created if missing
+pub fn validate_target(target: &Path) -> Result<PathBuf, String> {
+    if !target.is_absolute() {
+        return Err("Publish target must be an absolute path".to_string());
+    }
+    fs::create_dir_all(target).map_err(|e| format!("Can't create publish target - {e}"))?;
+    let target = fs::canonicalize(target).map_err(|e| e.to_string())?;
+    let uploads = fs::canonicalize(*UPLOAD_PATH).map_err(|e| e.to_string())?;
+    if target.starts_with(&uploads) || uploads.starts_with(&target) {
+        return Err("Publish target must be outside of the watch groups".to_string());
+    }
+    Ok(target)
+}
+
+/// hardlinks (or copies) `path` from `source_root` if it exists there - removes it from
+/// `target_root` otherwise, together with directories left empty
+fn sync_path(source_root: &Path, target_root: &Path, path: &MatchablePath) -> io::Result<()> {
+    let source = path.resolve(source_root);
+    let target = path.resolve(target_root);
+    if source.is_file() {
+        let parent = target.parent().unwrap_or(target_root);
+        fs::create_dir_all(parent)?;
+        // swapped in by rename - readers of the target never see a partial file
+        let tmp = parent.join(format!(".publish-{}", Uuid::new_v4()));
+        if fs::hard_link(&source, &tmp).is_err() {
+            // e.g. another file system
+            fs::copy(&source, &tmp)?;
+        }
+        return fs::rename(&tmp, &target).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        });
+    }
+
+    match fs::remove_file(&target) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let mut dir = target.parent();
+    while let Some(current) = dir.filter(|d| *d != target_root && d.starts_with(target_root)) {
+        // fails once a dir isn't empty
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn should_mirror_changes_and_deletes() {
+        let dir = env::temp_dir().join(format!("publish-{}", Uuid::new_v4()));
+        let source_root = dir.join("wg");
+        let target_root = dir.join("www");
+        fs::create_dir_all(source_root.join("docs/img")).unwrap();
+        fs::create_dir_all(&target_root).unwrap();
+        let path = MatchablePath::from("docs/img/logo.svg");
+
+        fs::write(path.resolve(&source_root), "v1").unwrap();
+        sync_path(&source_root, &target_root, &path).unwrap();
+        assert_eq!(
+            "v1",
+            fs::read_to_string(path.resolve(&target_root)).unwrap()
+        );
+
+        // uploads replace the file - the mirror has to follow
+        fs::remove_file(path.resolve(&source_root)).unwrap();
+        fs::write(path.resolve(&source_root), "v2").unwrap();
+        sync_path(&source_root, &target_root, &path).unwrap();
+        assert_eq!(
+            "v2",
+            fs::read_to_string(path.resolve(&target_root)).unwrap()
+        );
+
+        fs::remove_file(path.resolve(&source_root)).unwrap();
+        sync_path(&source_root, &target_root, &path).unwrap();
+        assert!(!target_root.join("docs").exists());
+        assert!(target_root.exists());
+        // deleting twice is fine
+        sync_path(&source_root, &target_root, &path).unwrap();
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_reject_relative_targets() {
+        assert!(validate_target(Path::new("relative/www")).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use axum::Router;
+use axum::body::{Body, HttpBody};
+use axum::http::header::CONTENT_LENGTH;
+use axum::http::{HeaderValue, Request};
+use bytes::{Buf, Bytes};
+use futures_util::{StreamExt, stream};
+use h3::server::RequestResolver;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock};
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+/// `HTTP3=true` serves the api over QUIC as well (same port, udp) - needs TLS_CERT_PATH + KEY_PATH
+pub static HTTP3_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env::var("HTTP3").is_ok_and(|v| v == "true"));
+
+type H3Connection = h3_quinn::Connection;
+
+/// serves `app` over HTTP/3 until the endpoint fails - the tcp listener keeps running either way
+pub async fn serve(addr: SocketAddr, cert_path: &str, key_path: &str, app: Router) {
+    let endpoint = match server_config(cert_path, key_path)
+        .and_then(|config| quinn::Endpoint::server(config, addr).map_err(|e| e.to_string()))
+    {
+        Ok(endpoint) => endpoint,
+        Err(e) => {
+            warn!("HTTP/3 disabled - {e}");
+            return;
+        }
+    };
+    info!("Starting HTTP/3 server on {addr} (udp)");
+
+    while let Some(incoming) = endpoint.accept().await {
+        let app = app.clone();
+        tokio::spawn(async move {
+            let connection = match incoming.await {
+                Ok(connection) => connection,
+                Err(e) => return debug!("QUIC handshake failed - {e}"),
+            };
+            let mut h3: h3::server::Connection<H3Connection, Bytes> =
+                match h3::server::Connection::new(h3_quinn::Connection::new(connection)).await {
+                    Ok(h3) => h3,
+                    Err(e) => return debug!("HTTP/3 connection failed - {e}"),
+                };
+            loop {
+                match h3.accept().await {
+                    Ok(Some(resolver)) => {
+                        tokio::spawn(handle_request(resolver, app.clone()));
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        debug!("HTTP/3 connection closed - {e}");
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn server_config(cert_path: &str, key_path: &str) -> Result<quinn::ServerConfig, String> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to load TLS certificate - {e}"))?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| format!("Failed to load TLS key - {e}"))?;
+    let mut tls = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_protocol_versions(&[&rustls::version::TLS13])
+    .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+    .map_err(|e| format!("Invalid TLS certificate/key - {e}"))?;
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    let quic = quinn::crypto::rustls::QuicServerConfig::try_from(tls)
+        .map_err(|e| format!("Invalid TLS config for QUIC - {e}"))?;
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(quic)))
+}
+
+/// bridges one request stream to the router - the bodies are streamed both ways
+async fn handle_request(resolver: RequestResolver<H3Connection, Bytes>, app: Router) {
+    let (request, stream) = match resolver.resolve_request().await {
+        Ok(resolved) => resolved,
+        Err(e) => return debug!("Invalid HTTP/3 request - {e}"),
+    };
+    let (mut send, recv) = stream.split();
+    let body = stream::unfold(recv, |mut recv| async move {
+        match recv.recv_data().await {
+            Ok(Some(mut data)) => Some((Ok(data.copy_to_bytes(data.remaining())), recv)),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), recv)),
+        }
+    });
+    let (parts, ()) = request.into_parts();
+    let Ok(response) = app
+        .oneshot(Request::from_parts(parts, Body::from_stream(body)))
+        .await;
+
+    let (mut parts, body) = response.into_parts();
+    // hyper derives it from the body on http/1 + 2 - clients show the download progress with it
+    if let Some(size) = body.size_hint().exact() {
+        parts
+            .headers
+            .entry(CONTENT_LENGTH)
+            .or_insert(HeaderValue::from(size));
+    }
+    let result = async {
+        send.send_response(axum::http::Response::from_parts(parts, ()))
+            .await?;
+        let mut body = body.into_data_stream();
+        while let Some(chunk) = body.next().await {
+            match chunk {
+                Ok(chunk) => send.send_data(chunk).await?,
+                Err(e) => {
+                    warn!("HTTP/3 response body failed - {e}");
+                    break;
+                }
+            }
+        }
+        send.finish().await
+    };
+    if let Err(e) = result.await {
+        debug!("HTTP/3 response not sent - {e}");
+    }
+}
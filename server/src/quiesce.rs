@@ -0,0 +1,169 @@
+use crate::AppState;
+use axum::extract::{Path, Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// archiving a single watch group rarely takes longer - clients retry with their next sync
+const RETRY_AFTER_SECS: u64 = 30;
+/// writes that started before archiving get to finish - a stuck upload must not block backups
+const MAX_DRAIN_WAIT: Duration = Duration::from_secs(5 * 60);
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// pauses writes to a watch group while the nightly backup archives it - so the archive never
+/// holds a half-applied upload
+#[derive(Default)]
+pub struct BackupWindow {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    archiving: HashSet<i64>,
+    writes_in_flight: HashMap<i64, usize>,
+}
+
+impl BackupWindow {
+    /// new writes to `wg_id` get rejected until the guard is dropped - waits for running ones
+    pub async fn quiesce(self: &Arc<Self>, wg_id: i64) -> QuiesceGuard {
+        self.inner.lock().unwrap().archiving.insert(wg_id);
+        let guard = QuiesceGuard {
+            window: self.clone(),
+            wg_id,
+        };
+
+        let started = Instant::now();
+        while self.writes_in_flight(wg_id) > 0 {
+            if started.elapsed() > MAX_DRAIN_WAIT {
+                warn!("Archiving watch group {wg_id} with writes still in flight");
+                break;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+        debug!("Quiesced watch group {wg_id}");
+        guard
+    }
+
+    /// None while the watch group is archived
+    pub(crate) fn begin_write(self: &Arc<Self>, wg_id: i64) -> Option<WriteGuard> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.archiving.contains(&wg_id) {
+            return None;
+        }
+        *inner.writes_in_flight.entry(wg_id).or_default() += 1;
+        Some(WriteGuard {
+            window: self.clone(),
+            wg_id,
+        })
+    }
+
+    /// [`BackupWindow::begin_write`] for writes outside of the `{wg_id}` routes (webdav, inbox,
+    /// admin operations, ...) - 503 while the watch group is archived
+    pub(crate) fn try_write(
+        self: &Arc<Self>,
+        wg_id: i64,
+    ) -> Result<WriteGuard, (StatusCode, String)> {
+        self.begin_write(wg_id).ok_or_else(|| {
+            debug!("Rejected write to watch group {wg_id} during backup");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Watch group {wg_id} is being backed up - retry later"),
+            )
+        })
+    }
+
+    fn writes_in_flight(&self, wg_id: i64) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.writes_in_flight.get(&wg_id).copied().unwrap_or(0)
+    }
+}
+
+pub struct QuiesceGuard {
+    window: Arc<BackupWindow>,
+    wg_id: i64,
+}
+
+impl Drop for QuiesceGuard {
+    fn drop(&mut self) {
+        let mut inner = self.window.inner.lock().unwrap();
+        inner.archiving.remove(&self.wg_id);
+    }
+}
+
+pub(crate) struct WriteGuard {
+    window: Arc<BackupWindow>,
+    wg_id: i64,
+}
+
+impl Drop for WriteGuard {
+    fn drop(&mut self) {
+        let mut inner = self.window.inner.lock().unwrap();
+        if let Some(count) = inner.writes_in_flight.get_mut(&self.wg_id) {
+            *count -= 1;
+            if *count == 0 {
+                inner.writes_in_flight.remove(&self.wg_id);
+            }
+        }
+    }
+}
+
+/// middleware for routes with a `{wg_id}` (or `{id}`) path parameter - reads pass through
+pub async fn reject_writes_while_archiving(
+    State(state): State<AppState>,
+    Path(params): Path<HashMap<String, String>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let wg_id = params
+        .get("wg_id")
+        .or_else(|| params.get("id"))
+        .and_then(|id| id.parse::<i64>().ok());
+    let (Some(wg_id), false) = (wg_id, matches!(*req.method(), Method::GET | Method::HEAD)) else {
+        return next.run(req).await;
+    };
+    let Some(_write) = state.backup_window.begin_write(wg_id) else {
+        debug!("Rejected write to watch group {wg_id} during backup");
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(RETRY_AFTER, RETRY_AFTER_SECS.to_string())],
+            format!("Watch group {wg_id} is being backed up - retry later"),
+        )
+            .into_response();
+    };
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_reject_writes_until_archiving_is_done() {
+        let window = Arc::new(BackupWindow::default());
+
+        let write = window.begin_write(1).unwrap();
+        let quiesce = tokio::spawn({
+            let window = window.clone();
+            async move { window.quiesce(1).await }
+        });
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        assert!(!quiesce.is_finished());
+        assert!(window.begin_write(1).is_none());
+        assert!(window.begin_write(2).is_some());
+
+        drop(write);
+        let guard = quiesce.await.unwrap();
+        assert!(window.begin_write(1).is_none());
+        assert_eq!(
+            Some(StatusCode::SERVICE_UNAVAILABLE),
+            window.try_write(1).err().map(|(status, _)| status)
+        );
+        drop(guard);
+        assert!(window.begin_write(1).is_some());
+    }
+}
@@ -0,0 +1,118 @@
+use shared::relay::{RelayMessage, read_message, sign, write_message};
+use std::convert::Infallible;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+/// `host:port` of the relay's control port - the server dials out, no port-forwarding needed
+static RELAY_ADDR: LazyLock<Option<String>> =
+    LazyLock::new(|| env::var("RELAY_ADDR").ok().filter(|addr| !addr.is_empty()));
+/// pairing created with `relay pair <name> <port>`
+static RELAY_NAME: LazyLock<Option<String>> = LazyLock::new(|| env::var("RELAY_NAME").ok());
+static RELAY_TOKEN: LazyLock<Option<String>> = LazyLock::new(|| env::var("RELAY_TOKEN").ok());
+
+/// the relay pings every 30s - silence for longer means the connection is dead
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(90);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// keeps a control connection to the relay open (if configured) - clients connecting to the
+/// relay get tunneled to `local`
+pub fn connect(local: SocketAddr, tls: bool) {
+    let Some(addr) = RELAY_ADDR.clone() else {
+        return;
+    };
+    let (Some(name), Some(token)) = (RELAY_NAME.clone(), RELAY_TOKEN.clone()) else {
+        return warn!("RELAY_ADDR ignored - RELAY_NAME and RELAY_TOKEN are needed as well");
+    };
+    if !tls {
+        warn!("Relay traffic is unencrypted - set TLS_CERT_PATH/TLS_KEY_PATH for end-to-end TLS");
+    }
+
+    tokio::spawn(async move {
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            let started = Instant::now();
+            let Err(error) = control_connection(&addr, &name, &token, local).await;
+            // connections that lasted a while were fine - start over with short delays
+            if started.elapsed() > MAX_BACKOFF {
+                backoff = Duration::from_secs(1);
+            }
+            warn!("Relay {addr} unavailable ({error}) - retrying in {backoff:?}");
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+/// only returns on errors
+async fn control_connection(
+    addr: &str,
+    name: &str,
+    token: &str,
+    local: SocketAddr,
+) -> Result<Infallible, String> {
+    let mut stream = TcpStream::connect(addr).await.map_err(|e| e.to_string())?;
+    let RelayMessage::Challenge(challenge) = read_message(&mut stream).await? else {
+        return Err("No challenge from relay".to_string());
+    };
+    let hello = RelayMessage::Hello {
+        name: name.to_string(),
+        signature: sign(token, &challenge),
+    };
+    write_message(&mut stream, &hello).await?;
+    match read_message(&mut stream).await? {
+        RelayMessage::Ok => info!("Reachable through relay {addr} as '{name}'"),
+        RelayMessage::Err(e) => return Err(format!("rejected - {e}")),
+        other => return Err(format!("unexpected {other:?}")),
+    }
+
+    loop {
+        let message = tokio::time::timeout(CONTROL_TIMEOUT, read_message(&mut stream))
+            .await
+            .map_err(|_| "no ping".to_string())??;
+        match message {
+            RelayMessage::Connect(id) => {
+                let (addr, name, token) = (addr.to_string(), name.to_string(), token.to_string());
+                tokio::spawn(async move {
+                    if let Err(e) = tunnel(&addr, &name, &token, id, local).await {
+                        warn!("Relayed connection failed - {e}");
+                    }
+                });
+            }
+            RelayMessage::Ping => {}
+            other => return Err(format!("unexpected {other:?}")),
+        }
+    }
+}
+
+/// data connection for one waiting client - spliced to the local listener, so TLS terminates here
+async fn tunnel(
+    addr: &str,
+    name: &str,
+    token: &str,
+    id: String,
+    local: SocketAddr,
+) -> Result<(), String> {
+    let mut relay = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("relay unreachable - {e}"))?;
+    // every connection starts with a challenge - data connections are signed by the id instead
+    read_message(&mut relay).await?;
+    let data = RelayMessage::Data {
+        name: name.to_string(),
+        signature: sign(token, &id),
+        id,
+    };
+    write_message(&mut relay, &data).await?;
+    let mut server = TcpStream::connect(local)
+        .await
+        .map_err(|e| format!("{local} unreachable - {e}"))?;
+    match tokio::io::copy_bidirectional(&mut relay, &mut server).await {
+        Ok((up, down)) => debug!("Relayed connection closed ({up} bytes in, {down} bytes out)"),
+        Err(e) => debug!("Relayed connection closed - {e}"),
+    }
+    Ok(())
+}
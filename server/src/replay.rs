@@ -0,0 +1,472 @@
+//! `server replay <export.ndjson>` - plays an event export (GET /api/export/events) of one
+//! instance against another (fresh) one
+//!
+//! every client of the export becomes a virtual client that syncs like the real one (sync → upload
+//! if asked to / report the delete) - file contents are synthetic, only path, size & order of the
+//! events are kept. Reproduces bug reports from a user's history and measures the sync handler on
+//! realistic data
+
+use bytes::Bytes;
+use futures_util::stream;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Body, Client, RequestBuilder};
+use shared::dtos::{
+    ClientWatchGroupCreateDto, ExportEventDto, FileDescription, ServerWatchGroup, WatchGroupNameDto,
+};
+use shared::endpoint::{CLIENT_HOST_HEADER_KEY, CLIENT_ID_HEADER_KEY, ServerEndpoint, SysRoute};
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::SyncInstruction;
+use shared::utc_millis::UtcMillis;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// synthetic file contents are streamed in chunks of this size
+const CHUNK_BYTES: u64 = 64 * 1024;
+/// client hostname of events exported without one
+const DEFAULT_HOST: &str = "replay";
+
+#[derive(clap::Args)]
+pub struct ReplayArgs {
+    /// NDJSON file written by GET /api/export/events
+    file: PathBuf,
+    /// server to replay against - should be a fresh instance
+    #[arg(long, default_value = "http://localhost:3000")]
+    target: String,
+    /// 1 keeps the original pace, 10 is ten times faster, 0 doesn't wait at all
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+    /// upper bound of a single wait (after scaling) - skips nights & weekends of the history
+    #[arg(long, default_value_t = 60)]
+    max_gap_secs: u64,
+    /// ADMIN_TOKEN of the target (for creating watch groups & assignments)
+    #[arg(long, env = "REPLAY_TOKEN")]
+    token: Option<String>,
+}
+
+/// what the target answered - printed once the export is through
+#[derive(Debug, Default)]
+struct Report {
+    events: usize,
+    uploads: usize,
+    /// the server didn't ask for the change (e.g. an older version than it has)
+    uploads_skipped: usize,
+    deletes: usize,
+    failures: usize,
+    /// instructions other than the upload of the replayed change
+    other_instructions: usize,
+    sync_latencies: Vec<Duration>,
+}
+
+pub async fn run(args: ReplayArgs) -> Result<(), String> {
+    if !(args.speed >= 0.0 && args.speed.is_finite()) {
+        return Err(format!("Invalid speed {} - must be >= 0", args.speed));
+    }
+    let content = std::fs::read_to_string(&args.file)
+        .map_err(|e| format!("Could not read {} - {e}", args.file.display()))?;
+    let events = parse_export(&content)?;
+    info!(
+        "Replaying {} events of {} against {}",
+        events.len(),
+        args.file.display(),
+        args.target
+    );
+
+    let base = args.target.trim_end_matches('/').to_string();
+    let http = Client::new();
+    let api = |request: RequestBuilder| match &args.token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+    let watch_groups = create_watch_groups(&http, &base, &api, &events).await?;
+    register_clients(&http, &base, &api, &events, &watch_groups).await?;
+
+    let max_gap = Duration::from_secs(args.max_gap_secs);
+    let mut state: HashMap<i64, BTreeMap<String, FileDescription>> = HashMap::new();
+    let mut report = Report::default();
+    let mut last_stamp = 0;
+    let started = Instant::now();
+    for (idx, event) in events.iter().enumerate() {
+        if let Some(previous) = idx.checked_sub(1).map(|i| &events[i]) {
+            tokio::time::sleep(pause(previous, event, args.speed, max_gap)).await;
+        }
+        let wg_id = watch_groups[&event.watch_group_id];
+        let files = state.entry(wg_id).or_default();
+        let replayed = ReplayedEvent {
+            http: &http,
+            base: &base,
+            wg_id,
+            event,
+        };
+        report.events += 1;
+        let result = match event.event_type.as_str() {
+            "delete" => replayed.delete(files, &mut report).await,
+            _ => {
+                last_stamp = next_stamp(last_stamp, UtcMillis::now().as_u64());
+                replayed.change(files, last_stamp, &mut report).await
+            }
+        };
+        if let Err(e) = result {
+            warn!("Event {} ({}) failed - {e}", event.seq, event.relative_path);
+            report.failures += 1;
+        }
+    }
+
+    print_report(&report, started.elapsed());
+    Ok(())
+}
+
+/// the export is in `seq` order already - sorted anyway, as files may have been concatenated
+fn parse_export(content: &str) -> Result<Vec<ExportEventDto>, String> {
+    let mut events = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str::<ExportEventDto>(line)
+                .map_err(|e| format!("Invalid event in line {} - {e}", idx + 1))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    events.sort_by_key(|e| e.seq);
+    events.dedup_by_key(|e| e.seq);
+    Ok(events)
+}
+
+/// original gap between the events, scaled & capped
+fn pause(previous: &ExportEventDto, next: &ExportEventDto, speed: f64, max: Duration) -> Duration {
+    if speed == 0.0 {
+        return Duration::ZERO;
+    }
+    let gap = next
+        .utc_millis
+        .as_u64()
+        .saturating_sub(previous.utc_millis.as_u64());
+    Duration::from_millis(gap).div_f64(speed).min(max)
+}
+
+/// changes are stamped with the replay time (deletes get it from the server anyway) - strictly
+/// increasing, so events within the same millisecond keep their order
+fn next_stamp(last: u64, now: u64) -> u64 {
+    now.max(last + 1)
+}
+
+async fn create_watch_groups(
+    http: &Client,
+    base: &str,
+    api: &impl Fn(RequestBuilder) -> RequestBuilder,
+    events: &[ExportEventDto],
+) -> Result<HashMap<i64, i64>, String> {
+    let exported: BTreeSet<i64> = events.iter().map(|e| e.watch_group_id).collect();
+    for wg_id in &exported {
+        let dto = WatchGroupNameDto {
+            name: watch_group_name(*wg_id),
+        };
+        api(http.post(ServerEndpoint::ApiWatchGroups.to_uri(base)))
+            .json(&dto)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Could not create watch group '{}' - {e}", dto.name))?;
+    }
+
+    let created: Vec<ServerWatchGroup> = api(http.get(ServerEndpoint::ApiWatchGroups.to_uri(base)))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Could not list watch groups - {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Could not list watch groups - {e}"))?;
+    exported
+        .into_iter()
+        .map(|wg_id| {
+            let name = watch_group_name(wg_id);
+            created
+                .iter()
+                .find(|group| group.name == name)
+                .map(|group| (wg_id, group.id))
+                .ok_or(format!("Watch group '{name}' missing after creating it"))
+        })
+        .collect()
+}
+
+fn watch_group_name(exported_id: i64) -> String {
+    format!("replay-{exported_id}")
+}
+
+/// registers via GET /sys/config (like a new client) and assigns the watch groups it has events in
+async fn register_clients(
+    http: &Client,
+    base: &str,
+    api: &impl Fn(RequestBuilder) -> RequestBuilder,
+    events: &[ExportEventDto],
+    watch_groups: &HashMap<i64, i64>,
+) -> Result<(), String> {
+    let mut clients: BTreeMap<&str, (&str, BTreeSet<i64>)> = BTreeMap::new();
+    for event in events {
+        let host = event.client_host.as_deref().unwrap_or(DEFAULT_HOST);
+        let (_, groups) = clients
+            .entry(event.client_id.as_str())
+            .or_insert((host, BTreeSet::new()));
+        groups.insert(watch_groups[&event.watch_group_id]);
+    }
+
+    for (client_id, (host, groups)) in clients {
+        http.get(ServerEndpoint::Config.to_uri(base))
+            .header(CLIENT_ID_HEADER_KEY, client_id)
+            .header(CLIENT_HOST_HEADER_KEY, host)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Could not register client {client_id} - {e}"))?;
+        for wg_id in groups {
+            let dto = ClientWatchGroupCreateDto {
+                server_watch_group_id: wg_id,
+                path_to_monitor: format!("replay/{wg_id}"),
+                exclude_dirs: Vec::new(),
+                exclude_dot_dirs: false,
+            };
+            let uri = ServerEndpoint::ApiClientWatchGroups.to_uri_with(base, &[("id", client_id)]);
+            api(http.post(uri))
+                .json(&dto)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| {
+                    format!("Could not assign watch group {wg_id} to {client_id} - {e}")
+                })?;
+        }
+    }
+    Ok(())
+}
+
+/// one event of the export, played by the virtual client that caused it - every client is
+/// assumed to be in sync with the others (holds `files`)
+struct ReplayedEvent<'a> {
+    http: &'a Client,
+    base: &'a str,
+    wg_id: i64,
+    event: &'a ExportEventDto,
+}
+
+impl ReplayedEvent<'_> {
+    fn sys(&self, request: RequestBuilder) -> RequestBuilder {
+        let host = self.event.client_host.as_deref().unwrap_or(DEFAULT_HOST);
+        request
+            .header(CLIENT_ID_HEADER_KEY, &self.event.client_id)
+            .header(CLIENT_HOST_HEADER_KEY, host)
+    }
+
+    async fn change(
+        &self,
+        files: &mut BTreeMap<String, FileDescription>,
+        stamp: u64,
+        report: &mut Report,
+    ) -> Result<(), String> {
+        let path = MatchablePath::from(self.event.relative_path.as_str());
+        let file_name = path.tail();
+        let changed = FileDescription {
+            file_type: file_name
+                .rfind('.')
+                .map(|p| file_name[p..].to_string())
+                .unwrap_or_default(),
+            file_name,
+            relative_path: path.clone(),
+            size_in_bytes: self.event.size_in_bytes,
+            last_updated_utc_millis: UtcMillis::from(stamp),
+        };
+        let mut scanned = files.clone();
+        scanned.insert(self.event.relative_path.clone(), changed.clone());
+
+        let instructions = self
+            .sync(scanned.values().cloned().collect(), report)
+            .await?;
+        let (asked, other): (Vec<_>, Vec<_>) = instructions
+            .into_iter()
+            .partition(|i| matches!(i, SyncInstruction::Upload(p) if *p == path));
+        report.other_instructions += other.len();
+        if asked.is_empty() {
+            report.uploads_skipped += 1;
+            return Ok(());
+        }
+
+        self.upload(&changed).await?;
+        report.uploads += 1;
+        files.insert(self.event.relative_path.clone(), changed);
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        files: &mut BTreeMap<String, FileDescription>,
+        report: &mut Report,
+    ) -> Result<(), String> {
+        files.remove(&self.event.relative_path);
+        self.sys(
+            self.http
+                .post(SysRoute::Delete { wg_id: self.wg_id }.to_uri(self.base)),
+        )
+        .body(self.event.relative_path.clone())
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Delete failed - {e}"))?;
+        report.deletes += 1;
+
+        let instructions = self.sync(files.values().cloned().collect(), report).await?;
+        report.other_instructions += instructions.len();
+        Ok(())
+    }
+
+    async fn sync(
+        &self,
+        scanned: Vec<FileDescription>,
+        report: &mut Report,
+    ) -> Result<Vec<SyncInstruction>, String> {
+        let started = Instant::now();
+        let response = self
+            .sys(
+                self.http
+                    .post(SysRoute::Sync { wg_id: self.wg_id }.to_uri(self.base)),
+            )
+            .json(&scanned)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Sync failed - {e}"))?;
+        let instructions = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid sync reply - {e}"))?;
+        report.sync_latencies.push(started.elapsed());
+        Ok(instructions)
+    }
+
+    async fn upload(&self, description: &FileDescription) -> Result<(), String> {
+        let size = description.size_in_bytes;
+        // differs between versions, so a changed file never uploads the same bytes
+        let fill = (self.event.seq % 251) as u8;
+        let chunks = (0..size.div_ceil(CHUNK_BYTES)).map(move |idx| {
+            let len = CHUNK_BYTES.min(size - idx * CHUNK_BYTES) as usize;
+            Ok::<_, std::io::Error>(Bytes::from(vec![fill; len]))
+        });
+        let file_part = Part::stream_with_length(Body::wrap_stream(stream::iter(chunks)), size)
+            .file_name(description.file_name.clone());
+        let form = Form::new()
+            .text(
+                "utc_millis",
+                serde_json::to_string(&description.last_updated_utc_millis).unwrap(),
+            )
+            .text("relative_path", self.event.relative_path.clone())
+            .part("file", file_part);
+        self.sys(
+            self.http
+                .post(SysRoute::Upload { wg_id: self.wg_id }.to_uri(self.base)),
+        )
+        .multipart(form)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Upload failed - {e}"))?;
+        Ok(())
+    }
+}
+
+/// nearest rank of the sorted latencies
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    match sorted.len() {
+        0 => Duration::ZERO,
+        len => sorted[(len * percent).div_ceil(100).clamp(1, len) - 1],
+    }
+}
+
+fn print_report(report: &Report, elapsed: Duration) {
+    let mut latencies = report.sync_latencies.clone();
+    latencies.sort();
+    println!("events:       {} in {:.1?}", report.events, elapsed);
+    println!(
+        "uploads:      {} ({} not asked for by the server)",
+        report.uploads, report.uploads_skipped
+    );
+    println!("deletes:      {}", report.deletes);
+    println!("failures:     {}", report.failures);
+    println!(
+        "instructions: {} besides the replayed ones",
+        report.other_instructions
+    );
+    println!(
+        "sync:         {} requests - p50 {:.1?}, p95 {:.1?}, p99 {:.1?}, max {:.1?}",
+        latencies.len(),
+        percentile(&latencies, 50),
+        percentile(&latencies, 95),
+        percentile(&latencies, 99),
+        latencies.last().copied().unwrap_or_default()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(seq: i64, utc_millis: u64) -> ExportEventDto {
+        ExportEventDto {
+            seq,
+            event_id: format!("e{seq}"),
+            watch_group_id: 1,
+            relative_path: "dir/a.txt".to_string(),
+            event_type: "change".to_string(),
+            size_in_bytes: 3,
+            utc_millis: UtcMillis::from(utc_millis),
+            client_id: "c".to_string(),
+            client_host: None,
+        }
+    }
+
+    #[test]
+    fn should_parse_export_in_seq_order() {
+        let lines = [event(2, 20), event(1, 10), event(2, 20)]
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let events = parse_export(&format!("{lines}\n\n")).unwrap();
+        assert_eq!(vec![1, 2], events.iter().map(|e| e.seq).collect::<Vec<_>>());
+
+        let invalid = parse_export(&format!("{lines}\n{{")).unwrap_err();
+        assert!(invalid.starts_with("Invalid event in line 4"), "{invalid}");
+    }
+
+    #[test]
+    fn should_scale_and_cap_pauses() {
+        let max = Duration::from_secs(60);
+        let (a, b) = (event(1, 1_000), event(2, 11_000));
+        assert_eq!(Duration::from_secs(10), pause(&a, &b, 1.0, max));
+        assert_eq!(Duration::from_secs(1), pause(&a, &b, 10.0, max));
+        assert_eq!(Duration::ZERO, pause(&a, &b, 0.0, max));
+        assert_eq!(Duration::ZERO, pause(&b, &a, 1.0, max));
+        assert_eq!(
+            Duration::from_secs(5),
+            pause(&a, &b, 1.0, Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn should_stamp_strictly_increasing() {
+        assert_eq!(100, next_stamp(50, 100));
+        assert_eq!(101, next_stamp(100, 100));
+        assert_eq!(101, next_stamp(100, 90));
+    }
+
+    #[test]
+    fn should_pick_nearest_rank() {
+        let sorted: Vec<_> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(Duration::from_millis(50), percentile(&sorted, 50));
+        assert_eq!(Duration::from_millis(95), percentile(&sorted, 95));
+        assert_eq!(Duration::ZERO, percentile(&[], 95));
+        assert_eq!(
+            Duration::from_millis(1),
+            percentile(&[Duration::from_millis(1)], 50)
+        );
+    }
+}
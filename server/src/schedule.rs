@@ -0,0 +1,372 @@
+use crate::db::ServerDatabase;
+use crate::{AppState, BACKUP_PATH, UPLOAD_PATH, handler, supervisor, write};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDateTime, TimeZone, Timelike};
+use shared::dtos::{ScheduleEntryDto, ScheduledJob};
+use shared::utc_millis::UtcMillis;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Mutex;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+/// how far ahead a matching minute is searched - `0 0 30 2 *` never matches
+const MAX_DAYS_AHEAD: u64 = 5 * 366;
+
+/// `minute hour day-of-month month day-of-week` with `*`, `a-b`, `*/n`, `a-b/n` and lists -
+/// sunday is 0 or 7. `@hourly`, `@daily`, `@weekly` and `@monthly` work as well
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpression {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// with both day fields restricted a day matching either runs (like cron)
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl FromStr for CronExpression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "'{source}' needs 5 fields: minute hour day-of-month month day-of-week"
+            ));
+        };
+        let weekdays_bits = parse_field(weekdays, 0, 7)?;
+        Ok(Self {
+            source: source.to_string(),
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days: parse_field(days, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            // 7 is sunday as well
+            weekdays: (weekdays_bits | weekdays_bits >> 7) & 0x7f,
+            days_restricted: days != "*",
+            weekdays_restricted: weekdays != "*",
+        })
+    }
+}
+
+impl Display for CronExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl CronExpression {
+    /// first matching minute after `after` - None if there's none within 5 years
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        for offset in 0..MAX_DAYS_AHEAD {
+            let date = start.date().checked_add_days(Days::new(offset))?;
+            if !self.matches_day(
+                date.day(),
+                date.month(),
+                date.weekday().num_days_from_sunday(),
+            ) {
+                continue;
+            }
+            let first_hour = if offset == 0 { start.hour() } else { 0 };
+            for hour in (first_hour..24).filter(|h| has(self.hours, *h)) {
+                let first_minute = if offset == 0 && hour == start.hour() {
+                    start.minute()
+                } else {
+                    0
+                };
+                if let Some(minute) = (first_minute..60).find(|m| has(self.minutes, *m)) {
+                    return date.and_hms_opt(hour, minute, 0);
+                }
+            }
+        }
+        None
+    }
+
+    /// next run in local time - minutes skipped by a daylight saving change are left out
+    pub fn next_run(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut after = now.naive_local();
+        loop {
+            let next = self.next_after(after)?;
+            match Local.from_local_datetime(&next).earliest() {
+                Some(at) if at > now => return Some(at),
+                _ => after = next,
+            }
+        }
+    }
+
+    fn matches_day(&self, day: u32, month: u32, weekday: u32) -> bool {
+        if !has(self.months, month) {
+            return false;
+        }
+        let day_matches = has(self.days, day);
+        let weekday_matches = has(self.weekdays, weekday);
+        if self.days_restricted && self.weekdays_restricted {
+            day_matches || weekday_matches
+        } else {
+            day_matches && weekday_matches
+        }
+    }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// bit `n` set for every value `n` the field matches
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+        let step = match step {
+            Some(step) => step
+                .parse::<u32>()
+                .ok()
+                .filter(|step| *step > 0)
+                .ok_or_else(|| format!("Invalid step '{step}' in '{field}'"))?,
+            None => 1,
+        };
+        let number = |value: &str| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("'{value}' in '{field}' is not within {min}-{max}"))
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((from, to)) => (number(from)?, number(to)?),
+                // `5/15` = from 5 on every 15
+                None if step > 1 => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if from > to {
+            return Err(format!("Range '{range}' in '{field}' is reversed"));
+        }
+        for value in (from..=to).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+/// cron expressions of the scheduled jobs - `SCHEDULE_BACKUP`, `SCHEDULE_COMPACTION`,
+/// `SCHEDULE_SCRUB` and `SCHEDULE_GC` (`off` turns a job off), changes via the api win
+pub struct Schedules {
+    expressions: Mutex<HashMap<ScheduledJob, Option<CronExpression>>>,
+    /// wakes the jobs up to plan their next run again
+    changed: watch::Sender<()>,
+}
+
+impl Schedules {
+    pub async fn load(db: &ServerDatabase) -> Self {
+        let mut expressions: HashMap<ScheduledJob, Option<CronExpression>> = ScheduledJob::ALL
+            .into_iter()
+            .map(|job| (job, from_env(job)))
+            .collect();
+        match db.schedule().get_all().await {
+            Ok(saved) => {
+                for saved in saved {
+                    let Ok(job) = ScheduledJob::try_from(saved.job.as_str()) else {
+                        continue;
+                    };
+                    match saved.expression.as_deref().map(CronExpression::from_str) {
+                        Some(Err(e)) => warn!("Ignoring saved schedule of {}: {}", saved.job, e),
+                        Some(Ok(expression)) => {
+                            expressions.insert(job, Some(expression));
+                        }
+                        None => {
+                            expressions.insert(job, None);
+                        }
+                    }
+                }
+            }
+            Err(e) => error!("Failed to load saved schedules: {}", e),
+        }
+        Self {
+            expressions: Mutex::new(expressions),
+            changed: watch::Sender::new(()),
+        }
+    }
+
+    pub fn get(&self, job: ScheduledJob) -> Option<CronExpression> {
+        self.expressions
+            .lock()
+            .unwrap()
+            .get(&job)
+            .cloned()
+            .flatten()
+    }
+
+    pub fn set(&self, job: ScheduledJob, expression: Option<CronExpression>) {
+        self.expressions.lock().unwrap().insert(job, expression);
+        self.changed.send_replace(());
+    }
+
+    pub fn to_dto(&self, job: ScheduledJob) -> ScheduleEntryDto {
+        let expression = self.get(job);
+        ScheduleEntryDto {
+            job,
+            next_run: expression
+                .as_ref()
+                .and_then(|e| e.next_run(Local::now()))
+                .map(|at| UtcMillis::from(at.timestamp_millis() as u64)),
+            expression: expression.map(|e| e.to_string()),
+        }
+    }
+}
+
+fn from_env(job: ScheduledJob) -> Option<CronExpression> {
+    let var = format!("SCHEDULE_{}", job.as_str().to_uppercase());
+    let default =
+        || default_expression(job).map(|e| e.parse().expect("Default schedules are valid"));
+    match env::var(&var).ok().filter(|v| !v.is_empty()).as_deref() {
+        None => default(),
+        Some("off") => None,
+        Some(value) => match value.parse() {
+            Ok(expression) => Some(expression),
+            Err(e) => {
+                warn!("{var}: {e} - falling back to the default");
+                default()
+            }
+        },
+    }
+}
+
+fn default_expression(job: ScheduledJob) -> Option<String> {
+    match job {
+        ScheduledJob::Backup => Some("0 2 * * *".to_string()),
+        ScheduledJob::Compaction => Some("30 3 * * 0".to_string()),
+        // SCRUB_INTERVAL_HOURS of older versions
+        ScheduledJob::Scrub => match env::var("SCRUB_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            Some(0) => None,
+            Some(hours) if hours < 24 => Some(format!("0 */{hours} * * *")),
+            _ => Some("0 4 * * *".to_string()),
+        },
+        ScheduledJob::Gc => Some("0 5 * * *".to_string()),
+    }
+}
+
+/// runs `job` whenever its expression matches - planned again when the schedule changes
+pub async fn run_on_schedule(state: AppState, job: ScheduledJob) {
+    let mut changed = state.schedules.changed.subscribe();
+    loop {
+        let next = state
+            .schedules
+            .get(job)
+            .and_then(|e| e.next_run(Local::now()));
+        let wait = next.map(|at| (at - Local::now()).to_std().unwrap_or_default());
+        match wait {
+            Some(wait) => supervisor::next_run_in(wait),
+            None => supervisor::unscheduled(),
+        }
+        tokio::select! {
+            _ = async {
+                match wait {
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                supervisor::ran();
+                run(&state, job).await;
+            }
+            _ = changed.changed() => {}
+        }
+    }
+}
+
+async fn run(state: &AppState, job: ScheduledJob) {
+    match job {
+        ScheduledJob::Backup => {
+            write::run_backup(&UPLOAD_PATH, &BACKUP_PATH, &state.db, &state.backup_window).await
+        }
+        ScheduledJob::Compaction => compact(&state.db).await,
+        ScheduledJob::Scrub => handler::run_scrub(state).await,
+        ScheduledJob::Gc => handler::run_client_file_state_cleanup(state).await,
+    }
+}
+
+async fn compact(db: &ServerDatabase) {
+    match db.maintenance().compact().await {
+        Ok(()) => {
+            info!("Compacted the database");
+            supervisor::succeeded();
+        }
+        Err(e) => {
+            error!("Database compaction failed: {}", e);
+            supervisor::failed(&e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    fn next(expression: &str, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        expression
+            .parse::<CronExpression>()
+            .unwrap()
+            .next_after(after)
+    }
+
+    #[test]
+    fn should_find_next_matching_minute() {
+        // 2024-05-01 is a wednesday
+        let now = at(2024, 5, 1, 2, 0);
+        assert_eq!(Some(at(2024, 5, 2, 2, 0)), next("0 2 * * *", now));
+        assert_eq!(Some(at(2024, 5, 1, 2, 15)), next("*/15 * * * *", now));
+        assert_eq!(Some(at(2024, 5, 5, 3, 30)), next("30 3 * * 0", now));
+        assert_eq!(Some(at(2024, 5, 5, 3, 30)), next("30 3 * * 7", now));
+        assert_eq!(Some(at(2024, 5, 1, 6, 0)), next("0 6-18/6 * * 1-5", now));
+        assert_eq!(Some(at(2024, 6, 1, 0, 0)), next("@monthly", now));
+        // either day field matches if both are restricted
+        assert_eq!(Some(at(2024, 5, 3, 0, 0)), next("0 0 15 * 5", now));
+        assert_eq!(None, next("0 0 30 2 *", now));
+    }
+
+    #[test]
+    fn should_refuse_invalid_expressions() {
+        for invalid in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "0 0 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(invalid.parse::<CronExpression>().is_err(), "{invalid}");
+        }
+    }
+}
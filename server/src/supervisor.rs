@@ -0,0 +1,188 @@
+use shared::dtos::{TaskState, TaskStatusDto};
+use shared::utc_millis::UtcMillis;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::{Instant, Interval};
+use tracing::{error, info};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+/// a task that ran this long before it panicked is restarted with the shortest backoff again
+const HEALTHY_AFTER: Duration = Duration::from_secs(10 * 60);
+
+tokio::task_local! {
+    /// the supervised task running - for the reporting functions below
+    static CURRENT: Current;
+}
+
+#[derive(Clone)]
+struct Current {
+    tasks: Arc<TaskSupervisor>,
+    name: &'static str,
+}
+
+/// background tasks of the server - restarted with backoff when they panic
+#[derive(Default)]
+pub struct TaskSupervisor {
+    tasks: Mutex<BTreeMap<&'static str, TaskStatusDto>>,
+}
+
+impl TaskSupervisor {
+    /// runs `task` until it returns - a panic is recorded and the task started again by calling
+    /// `task` once more after a backoff (1s doubling up to 5min)
+    pub fn spawn<F, Fut>(self: &Arc<Self>, name: &'static str, task: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.update(name, |status| status.state = TaskState::Running);
+        let tasks = self.clone();
+        tokio::spawn(async move {
+            let mut backoff = MIN_BACKOFF;
+            loop {
+                let started = Instant::now();
+                let current = Current {
+                    tasks: tasks.clone(),
+                    name,
+                };
+                let panic = match tokio::spawn(CURRENT.scope(current, task())).await {
+                    Ok(()) => {
+                        tasks.update(name, |status| {
+                            status.state = TaskState::Finished;
+                            status.next_run = None;
+                        });
+                        return;
+                    }
+                    Err(e) if e.is_panic() => panic_message(e.into_panic()),
+                    // the runtime shuts down
+                    Err(_) => return,
+                };
+                if started.elapsed() >= HEALTHY_AFTER {
+                    backoff = MIN_BACKOFF;
+                }
+                error!(
+                    "Task '{}' panicked - restarting in {:?}: {}",
+                    name, backoff, panic
+                );
+                tasks.update(name, |status| {
+                    status.state = TaskState::Restarting;
+                    status.next_run = Some(after(backoff));
+                    status.last_error = Some(format!("panicked: {panic}"));
+                    status.restarts += 1;
+                });
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                info!("Restarting task '{}'", name);
+                tasks.update(name, |status| status.state = TaskState::Running);
+            }
+        });
+    }
+
+    /// sorted by name
+    pub fn to_dto(&self) -> Vec<TaskStatusDto> {
+        self.tasks.lock().unwrap().values().cloned().collect()
+    }
+
+    fn update(&self, name: &'static str, update: impl FnOnce(&mut TaskStatusDto)) {
+        let mut tasks = self.tasks.lock().unwrap();
+        let status = tasks.entry(name).or_insert_with(|| TaskStatusDto {
+            name: name.to_string(),
+            state: TaskState::Running,
+            last_run: None,
+            next_run: None,
+            last_error: None,
+            restarts: 0,
+        });
+        update(status);
+    }
+}
+
+/// waits for the next tick of `interval` - recorded as run of the current task
+pub async fn tick(interval: &mut Interval) {
+    interval.tick().await;
+    ran();
+    next_run_in(interval.period());
+}
+
+/// the current task starts a run now
+pub fn ran() {
+    report(|status| status.last_run = Some(UtcMillis::now()));
+}
+
+/// the current task runs again in `delay`
+pub fn next_run_in(delay: Duration) {
+    report(|status| status.next_run = Some(after(delay)));
+}
+
+/// the current task has no next run planned
+pub fn unscheduled() {
+    report(|status| status.next_run = None);
+}
+
+/// the current run failed - the task logs the error itself
+pub fn failed(e: impl Display) {
+    let e = e.to_string();
+    report(|status| status.last_error = Some(e));
+}
+
+/// the current run went through - clears the last error
+pub fn succeeded() {
+    report(|status| status.last_error = None);
+}
+
+/// no-op outside of supervised tasks
+fn report(update: impl FnOnce(&mut TaskStatusDto)) {
+    let _ = CURRENT.try_with(|current| current.tasks.update(current.name, update));
+}
+
+fn after(delay: Duration) -> UtcMillis {
+    UtcMillis::from(UtcMillis::now().as_u64() + delay.as_millis() as u64)
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn should_restart_panicking_task_and_record_its_runs() {
+        let tasks = Arc::new(TaskSupervisor::default());
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+        tasks.spawn("flaky", move || {
+            let counter = counter.clone();
+            async move {
+                ran();
+                failed("disk full");
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("boom");
+                }
+                succeeded();
+            }
+        });
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+
+        let status = &tasks.to_dto()[0];
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+        assert_eq!("flaky", status.name);
+        assert_eq!(TaskState::Finished, status.state);
+        assert_eq!(2, status.restarts);
+        assert_eq!(None, status.last_error);
+        assert!(status.last_run.is_some());
+    }
+}
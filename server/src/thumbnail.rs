@@ -0,0 +1,147 @@
+use crate::THUMBNAIL_PATH;
+use image::ImageReader;
+use image::codecs::jpeg::JpegEncoder;
+use shared::dtos::is_image;
+use shared::matchable_path::MatchablePath;
+use std::fs;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// edge lengths thumbnails are rendered in - requested sizes are rounded up to one of them
+pub const SIZES: [u32; 3] = [128, 256, 512];
+/// rendered right after an upload, what the web file browser asks for
+pub const DEFAULT_SIZE: u32 = 256;
+const JPEG_QUALITY: u8 = 80;
+
+/// raster images only - svgs are small enough to be shown as they are
+pub fn is_thumbnailable(path: &MatchablePath) -> bool {
+    let name = path.tail().to_lowercase();
+    let ext = name.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+    is_image(ext) && ext != "svg"
+}
+
+/// smallest of [`SIZES`] not smaller than `requested` (the largest one for anything above)
+pub fn snap_size(requested: u32) -> u32 {
+    SIZES
+        .into_iter()
+        .find(|size| *size >= requested)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+/// cached thumbnail of `file` - rendered if missing or older than `file`
+pub fn get_or_render(
+    wg_id: i64,
+    path: &MatchablePath,
+    file: &Path,
+    size: u32,
+) -> io::Result<PathBuf> {
+    get_or_render_in(&THUMBNAIL_PATH, wg_id, path, file, size)
+}
+
+/// renders the [`DEFAULT_SIZE`] thumbnail of a freshly uploaded image without holding up the
+/// upload - failures only mean the thumbnail gets rendered on first request
+pub fn render_in_background(wg_id: i64, path: &MatchablePath, file: &Path) {
+    if !is_thumbnailable(path) {
+        return;
+    }
+    let path = path.clone();
+    let file = file.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = get_or_render(wg_id, &path, &file, DEFAULT_SIZE) {
+            warn!("Failed to render thumbnail of {:?} - {}", file, e);
+        }
+    });
+}
+
+fn get_or_render_in(
+    root: &Path,
+    wg_id: i64,
+    path: &MatchablePath,
+    file: &Path,
+    size: u32,
+) -> io::Result<PathBuf> {
+    let target = thumbnail_path(root, wg_id, path, size);
+    let source_modified = fs::metadata(file)?.modified()?;
+    let up_to_date = fs::metadata(&target)
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|rendered| rendered >= source_modified);
+    if up_to_date {
+        return Ok(target);
+    }
+
+    let image = ImageReader::open(file)?
+        .with_guessed_format()?
+        .decode()
+        .map_err(io::Error::other)?
+        .thumbnail(size, size);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // written next to the target first - concurrent requests never see half a jpeg
+    let tmp = target.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    let writer = BufWriter::new(fs::File::create(&tmp)?);
+    JpegEncoder::new_with_quality(writer, JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(io::Error::other)?;
+    fs::rename(&tmp, &target)?;
+    debug!("Rendered {size}px thumbnail of {:?}", file);
+    Ok(target)
+}
+
+/// thumbnails/{wg_id}/{size}/{relative path}.jpg
+fn thumbnail_path(root: &Path, wg_id: i64, path: &MatchablePath, size: u32) -> PathBuf {
+    let mut target = path.resolve(&root.join(wg_id.to_string()).join(size.to_string()));
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".jpg");
+    target.set_file_name(name);
+    target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn should_snap_sizes() {
+        assert_eq!(128, snap_size(0));
+        assert_eq!(256, snap_size(200));
+        assert_eq!(256, snap_size(256));
+        assert_eq!(512, snap_size(4096));
+    }
+
+    #[test]
+    fn should_only_thumbnail_raster_images() {
+        assert!(is_thumbnailable(&MatchablePath::from("photos/IMG_1.JPG")));
+        assert!(is_thumbnailable(&MatchablePath::from("a.webp")));
+        assert!(!is_thumbnailable(&MatchablePath::from("logo.svg")));
+        assert!(!is_thumbnailable(&MatchablePath::from("notes.txt")));
+    }
+
+    #[test]
+    fn should_render_and_cache_thumbnail() {
+        let dir = std::env::temp_dir().join(format!("thumbnail-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("photo.png");
+        RgbImage::from_pixel(800, 400, Rgb([200, 10, 10]))
+            .save(&file)
+            .unwrap();
+        let root = dir.join("thumbnails");
+        let path = MatchablePath::from("photos/photo.png");
+
+        let thumbnail = get_or_render_in(&root, 1, &path, &file, 128).unwrap();
+        assert_eq!(root.join("1/128/photos/photo.png.jpg"), thumbnail);
+        let rendered = image::open(&thumbnail).unwrap();
+        assert_eq!((128, 64), (rendered.width(), rendered.height()));
+
+        let modified = fs::metadata(&thumbnail).unwrap().modified().unwrap();
+        get_or_render_in(&root, 1, &path, &file, 128).unwrap();
+        assert_eq!(
+            modified,
+            fs::metadata(&thumbnail).unwrap().modified().unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+use crate::AppState;
+use crate::handler::header_value_as_opt_string;
+use axum::body::Body;
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use futures_util::StreamExt;
+use shared::dtos::TimingPercentilesDto;
+use shared::endpoint::{CLIENT_ID_HEADER_KEY, ServerEndpoint};
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// percentiles are taken over the latest this many requests per operation
+const MAX_SAMPLES: usize = 1000;
+
+/// the requests clients sync with - timed by [`time_operations`]
+const OPERATIONS: [(ServerEndpoint, &str); 5] = [
+    (ServerEndpoint::Sync, "sync"),
+    (ServerEndpoint::Upload, "upload"),
+    (ServerEndpoint::UploadBulk, "upload_bulk"),
+    (ServerEndpoint::Download, "download"),
+    (ServerEndpoint::Delete, "delete"),
+];
+
+/// durations of the latest sync requests of clients - slower ones than `SLOW_OPERATION_MS`
+/// (default 10000, 0 = off) are logged at WARN
+pub struct OperationTimings {
+    slow_after: Option<Duration>,
+    samples: Mutex<BTreeMap<&'static str, VecDeque<u64>>>,
+}
+
+impl OperationTimings {
+    pub fn new(slow_after_ms: u64) -> Self {
+        OperationTimings {
+            slow_after: (slow_after_ms > 0).then(|| Duration::from_millis(slow_after_ms)),
+            samples: Mutex::default(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            env::var("SLOW_OPERATION_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+        )
+    }
+
+    fn record(&self, operation: &'static str, request: &str, duration: Duration) {
+        if self.slow_after.is_some_and(|slow| duration >= slow) {
+            warn!(
+                operation,
+                duration_ms = duration.as_millis() as u64,
+                "Slow {operation}: {request} took {duration:?}"
+            );
+        }
+        let mut samples = self.samples.lock().unwrap();
+        let samples = samples.entry(operation).or_default();
+        if samples.len() == MAX_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(duration.as_millis() as u64);
+    }
+
+    pub fn to_dto(&self) -> BTreeMap<String, TimingPercentilesDto> {
+        self.samples
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(operation, samples)| {
+                let timings = TimingPercentilesDto::of(samples.iter().copied());
+                (operation.to_string(), timings)
+            })
+            .collect()
+    }
+}
+
+/// records on drop - once the response body is streamed (or the client hung up)
+struct Timed {
+    timings: Arc<OperationTimings>,
+    operation: &'static str,
+    request: String,
+    started: Instant,
+}
+
+impl Drop for Timed {
+    fn drop(&mut self) {
+        self.timings
+            .record(self.operation, &self.request, self.started.elapsed());
+    }
+}
+
+/// middleware - times the sync requests of clients including the streamed downloads
+pub async fn time_operations(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let operation = req.extensions().get::<MatchedPath>().and_then(|matched| {
+        OPERATIONS
+            .iter()
+            .find(|(endpoint, _)| endpoint.to_str() == matched.as_str())
+            .map(|(_, operation)| *operation)
+    });
+    let Some(operation) = operation else {
+        return next.run(req).await;
+    };
+    let client_id = header_value_as_opt_string(req.headers(), CLIENT_ID_HEADER_KEY);
+    let timed = Timed {
+        timings: state.timings.clone(),
+        operation,
+        request: format!(
+            "{} of client {}",
+            req.uri(),
+            client_id.as_deref().unwrap_or("?")
+        ),
+        started: Instant::now(),
+    };
+    let (parts, body) = next.run(req).await.into_parts();
+    let body = Body::from_stream(body.into_data_stream().map(move |chunk| {
+        let _timed = &timed;
+        chunk
+    }));
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_latest_samples_per_operation() {
+        let timings = OperationTimings::new(0);
+        (1..=MAX_SAMPLES as u64 + 10)
+            .for_each(|ms| timings.record("upload", "/", Duration::from_millis(ms)));
+        timings.record("delete", "/", Duration::from_millis(3));
+
+        let dto = timings.to_dto();
+        assert_eq!(MAX_SAMPLES, dto["upload"].count);
+        assert_eq!(MAX_SAMPLES as u64 + 10, dto["upload"].max_ms);
+        assert_eq!(510, dto["upload"].p50_ms);
+        assert_eq!(3, dto["delete"].p99_ms);
+        assert!(!dto.contains_key("download"));
+    }
+}
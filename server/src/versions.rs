@@ -0,0 +1,143 @@
+use crate::file_event::FileEvent;
+use crate::file_history::FileHistory;
+use crate::{UPLOAD_PATH, VERSIONS_PATH};
+use shared::matchable_path::MatchablePath;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// larger files are replaced without keeping the previous version
+const MAX_VERSIONED_BYTES: u64 = 1024 * 1024;
+/// older versions of a path get pruned
+const MAX_VERSIONS_PER_PATH: usize = 10;
+
+/// keeps a copy of the file at `file` before it gets replaced or deleted
+///
+/// needs to be called before the new event is added to the history, since the latest
+/// event is the one that produced the current file
+pub fn keep_current(history: &dyn FileHistory, wg_id: i64, path: &MatchablePath, file: &Path) {
+    let events = history.get_events(wg_id, path).unwrap_or_default();
+    keep_current_in(&VERSIONS_PATH, wg_id, &events, file);
+}
+
+/// file holding the version produced by `event_id` (if any) - deleted versions have no file
+pub fn find(
+    wg_id: i64,
+    path: &MatchablePath,
+    events: &[FileEvent],
+    event_id: Uuid,
+) -> Option<PathBuf> {
+    version_file(&VERSIONS_PATH, &UPLOAD_PATH, wg_id, path, events, event_id)
+        .filter(|file| file.exists())
+}
+
+/// copy made by [`keep_current`] of the version produced by `event_id` (if any)
+pub fn kept_copy(wg_id: i64, event_id: Uuid) -> Option<PathBuf> {
+    Some(stored_version_path(&VERSIONS_PATH, wg_id, event_id)).filter(|file| file.exists())
+}
+
+fn keep_current_in(versions_root: &Path, wg_id: i64, events: &[FileEvent], file: &Path) {
+    let Some(current) = events.last().filter(|e| e.event_type.is_change()) else {
+        return;
+    };
+    match fs::metadata(file) {
+        Ok(meta) if meta.len() <= MAX_VERSIONED_BYTES => {}
+        _ => return,
+    }
+
+    let target = stored_version_path(versions_root, wg_id, current.id);
+    let result = fs::create_dir_all(versions_root.join(wg_id.to_string()))
+        .and_then(|_| fs::copy(file, &target));
+    match result {
+        Ok(_) => debug!("Kept version {} of {:?}", current.id, file),
+        Err(e) => warn!(
+            "Failed to keep version {} of {:?} - {}",
+            current.id, file, e
+        ),
+    }
+
+    // the current file is one of the kept versions - so only MAX - 1 stored copies
+    let changes: Vec<&FileEvent> = events.iter().filter(|e| e.event_type.is_change()).collect();
+    let outdated = changes.len().saturating_sub(MAX_VERSIONS_PER_PATH - 1);
+    for event in &changes[..outdated] {
+        let _ = fs::remove_file(stored_version_path(versions_root, wg_id, event.id));
+    }
+}
+
+/// the latest version is the file itself, older ones live in the versions dir
+fn version_file(
+    versions_root: &Path,
+    upload_root: &Path,
+    wg_id: i64,
+    path: &MatchablePath,
+    events: &[FileEvent],
+    event_id: Uuid,
+) -> Option<PathBuf> {
+    let position = events.iter().position(|e| e.id == event_id)?;
+    if events[position].event_type.is_delete() {
+        return None;
+    }
+    if position == events.len() - 1 {
+        Some(path.resolve(&upload_root.join(wg_id.to_string())))
+    } else {
+        Some(stored_version_path(versions_root, wg_id, event_id))
+    }
+}
+
+fn stored_version_path(versions_root: &Path, wg_id: i64, event_id: Uuid) -> PathBuf {
+    versions_root
+        .join(wg_id.to_string())
+        .join(event_id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_event::FileEventType;
+    use shared::utc_millis::UtcMillis;
+
+    fn change(millis: u64) -> FileEvent {
+        FileEvent::new(
+            Uuid::new_v4(),
+            UtcMillis::from(millis),
+            MatchablePath::from("notes.md"),
+            5,
+            FileEventType::ChangeEvent,
+            None,
+            1,
+        )
+    }
+
+    #[test]
+    fn should_keep_and_prune_versions() {
+        let root = std::env::temp_dir().join(format!("versions-{}", Uuid::new_v4()));
+        let versions_root = root.join("versions");
+        let upload_root = root.join("upload");
+        let path = MatchablePath::from("notes.md");
+        let file = path.resolve(&upload_root.join("1"));
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+
+        let mut events = Vec::new();
+        for i in 0..=MAX_VERSIONS_PER_PATH as u64 {
+            // same order as an upload: keep the current file, replace it, add the event
+            keep_current_in(&versions_root, 1, &events, &file);
+            fs::write(&file, format!("v{i}")).unwrap();
+            events.push(change(i));
+        }
+
+        let stored = |event: &FileEvent| {
+            version_file(&versions_root, &upload_root, 1, &path, &events, event.id)
+                .and_then(|f| fs::read_to_string(f).ok())
+        };
+        assert_eq!(None, stored(&events[0]));
+        assert_eq!(Some("v1".to_string()), stored(&events[1]));
+        // latest version is read from the upload dir
+        assert_eq!(
+            Some(format!("v{MAX_VERSIONS_PER_PATH}")),
+            stored(events.last().unwrap())
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+use crate::AppState;
+use crate::db::Webhook;
+use crate::file_event::FileEvent;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use shared::dtos::WebhookEventDto;
+use shared::endpoint::WEBHOOK_SIGNATURE_HEADER_KEY;
+use std::env;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+/// pauses between the attempts of one delivery - gives up after the last retry
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(10),
+    Duration::from_secs(60),
+];
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `ALERT_WEBHOOK_URL` (signed with `ALERT_WEBHOOK_SECRET` if set) - receives alerts for admins,
+/// e.g. of the mass change guard
+static ALERT_WEBHOOK: LazyLock<Option<Webhook>> = LazyLock::new(|| {
+    let url = env::var("ALERT_WEBHOOK_URL")
+        .ok()
+        .filter(|u| !u.is_empty())?;
+    Some(Webhook {
+        id: 0,
+        url,
+        secret: env::var("ALERT_WEBHOOK_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty()),
+    })
+});
+
+/// posts file events to the webhooks of their watch group
+pub struct WebhookDispatcher {
+    http: reqwest::Client,
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Should be able to build http client"),
+        }
+    }
+}
+
+/// delivers the (already recorded) event in the background - never blocks the caller
+pub fn notify(state: &AppState, event: &FileEvent, client_id: Option<&str>) {
+    let payload = WebhookEventDto {
+        event_id: event.id.to_string(),
+        watch_group_id: event.watch_group_id,
+        relative_path: event.relative_path.to_serialized_string(),
+        event_type: event.event_type.serialize_to_string(),
+        size_in_bytes: event.size_in_bytes,
+        utc_millis: event.utc_millis.clone(),
+        client_id: client_id.map(str::to_string),
+        client_host: event.client_host.clone(),
+    };
+    let state = state.clone();
+    tokio::spawn(async move {
+        let webhooks = match state
+            .db
+            .webhook()
+            .list_for_watch_group(payload.watch_group_id)
+            .await
+        {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                error!("Failed to load webhooks: {}", e);
+                return;
+            }
+        };
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+        for webhook in webhooks {
+            let http = state.webhooks.http.clone();
+            let body = body.clone();
+            tokio::spawn(async move { deliver(&http, &webhook, body).await });
+        }
+    });
+}
+
+/// posts the alert to the alert webhook in the background - a no-op without one
+pub fn alert<T: Serialize>(state: &AppState, payload: &T) {
+    let Some(webhook) = ALERT_WEBHOOK.as_ref() else {
+        return;
+    };
+    let body = match serde_json::to_vec(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to serialize alert: {}", e);
+            return;
+        }
+    };
+    let http = state.webhooks.http.clone();
+    tokio::spawn(async move { deliver(&http, webhook, body).await });
+}
+
+async fn deliver(http: &reqwest::Client, webhook: &Webhook, body: Vec<u8>) {
+    let signature = webhook.secret.as_deref().map(|secret| sign(secret, &body));
+    let mut delays = RETRY_DELAYS.iter();
+    loop {
+        let mut request = http
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER_KEY, signature);
+        }
+        let failure = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Webhook {} delivered", webhook.id);
+                return;
+            }
+            Ok(response) => format!("replied {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        match delays.next() {
+            Some(delay) => {
+                warn!(
+                    "Webhook {} ({}) failed - {} - retrying in {:?}",
+                    webhook.id, webhook.url, failure, delay
+                );
+                tokio::time::sleep(*delay).await;
+            }
+            None => {
+                error!(
+                    "Webhook {} ({}) failed - {} - giving up",
+                    webhook.id, webhook.url, failure
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// `sha256=<hex hmac>` - like github, so existing verifiers can be reused
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_sign_body_with_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            sign("Jefe", b"what do ya want for nothing?")
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::io;
+use std::sync::LazyLock;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// `BACKUP_ENCRYPTION_KEY` - backup archives are encrypted once it is set
+pub static BACKUP_KEY: LazyLock<Option<BackupKey>> = LazyLock::new(|| {
+    env::var("BACKUP_ENCRYPTION_KEY")
+        .ok()
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| BackupKey::from_secret(&secret))
+});
+
+/// appended to the name of encrypted archives
+pub const ENCRYPTED_SUFFIX: &str = ".enc";
+
+/// start of every encrypted file - also authenticated with every chunk
+const MAGIC: &[u8; 8] = b"RFSENC01";
+const CHUNK_LEN: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+/// random per file - the remaining nonce bytes are the chunk counter and the last-chunk flag
+const NONCE_PREFIX_LEN: usize = NONCE_LEN - 5;
+
+/// AES-256-GCM in chunks (STREAM construction) - so archives of any size never have to fit
+/// into memory and truncation is detected
+///
+/// layout: magic, nonce prefix, then chunks of `CHUNK_LEN` plaintext bytes + tag - the last
+/// chunk is always shorter (possibly empty)
+pub struct BackupKey(LessSafeKey);
+
+impl BackupKey {
+    /// sha-256 of the secret - any string works, a long random one (`openssl rand -hex 32`) is best
+    pub fn from_secret(secret: &str) -> Self {
+        let key = Sha256::digest(secret.as_bytes());
+        let key = UnboundKey::new(&AES_256_GCM, &key).expect("sha-256 has the aes-256 key length");
+        BackupKey(LessSafeKey::new(key))
+    }
+
+    /// returns the number of plaintext bytes
+    pub async fn encrypt(
+        &self,
+        mut input: impl AsyncRead + Unpin,
+        mut output: impl AsyncWrite + Unpin,
+    ) -> io::Result<u64> {
+        let mut prefix = [0u8; NONCE_PREFIX_LEN];
+        SystemRandom::new()
+            .fill(&mut prefix)
+            .map_err(|_| io::Error::other("No randomness for the nonce"))?;
+        output.write_all(MAGIC).await?;
+        output.write_all(&prefix).await?;
+
+        let mut total = 0;
+        let mut buf = Vec::with_capacity(CHUNK_LEN + TAG_LEN);
+        for counter in 0.. {
+            read_up_to(&mut input, &mut buf, CHUNK_LEN).await?;
+            total += buf.len() as u64;
+            let last = buf.len() < CHUNK_LEN;
+            self.0
+                .seal_in_place_append_tag(
+                    nonce(&prefix, counter, last)?,
+                    Aad::from(MAGIC),
+                    &mut buf,
+                )
+                .map_err(|_| io::Error::other("Encryption failed"))?;
+            output.write_all(&buf).await?;
+            if last {
+                break;
+            }
+        }
+        output.shutdown().await?;
+        Ok(total)
+    }
+
+    /// returns the number of plaintext bytes - fails on a wrong key or a modified / truncated file
+    pub async fn decrypt(
+        &self,
+        mut input: impl AsyncRead + Unpin,
+        mut output: impl AsyncWrite + Unpin,
+    ) -> io::Result<u64> {
+        let mut magic = [0u8; MAGIC.len()];
+        input.read_exact(&mut magic).await?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not an encrypted backup",
+            ));
+        }
+        let mut prefix = [0u8; NONCE_PREFIX_LEN];
+        input.read_exact(&mut prefix).await?;
+
+        let mut total = 0;
+        let mut buf = Vec::with_capacity(CHUNK_LEN + TAG_LEN);
+        for counter in 0.. {
+            read_up_to(&mut input, &mut buf, CHUNK_LEN + TAG_LEN).await?;
+            let last = buf.len() < CHUNK_LEN + TAG_LEN;
+            let plain = self
+                .0
+                .open_in_place(nonce(&prefix, counter, last)?, Aad::from(MAGIC), &mut buf)
+                .map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Backup is corrupt or was encrypted with another key",
+                    )
+                })?;
+            total += plain.len() as u64;
+            output.write_all(plain).await?;
+            if last {
+                break;
+            }
+        }
+        output.shutdown().await?;
+        Ok(total)
+    }
+}
+
+fn nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u64, last: bool) -> io::Result<Nonce> {
+    let counter = u32::try_from(counter)
+        .map_err(|_| io::Error::other("Too large for an encrypted backup"))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = u8::from(last);
+    Ok(Nonce::assume_unique_for_key(nonce))
+}
+
+/// replaces the content of `buf` with the next `len` bytes - fewer only at the end of `input`
+async fn read_up_to(
+    input: &mut (impl AsyncRead + Unpin),
+    buf: &mut Vec<u8>,
+    len: usize,
+) -> io::Result<()> {
+    buf.clear();
+    let read = input.take(len as u64).read_to_end(buf).await?;
+    debug_assert_eq!(read, buf.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn encrypt(key: &BackupKey, plain: &[u8]) -> Vec<u8> {
+        let mut encrypted = Vec::new();
+        key.encrypt(plain, &mut encrypted).await.unwrap();
+        encrypted
+    }
+
+    async fn decrypt(key: &BackupKey, encrypted: &[u8]) -> io::Result<Vec<u8>> {
+        let mut plain = Vec::new();
+        key.decrypt(encrypted, &mut plain).await.map(|_| plain)
+    }
+
+    #[tokio::test]
+    async fn should_decrypt_what_was_encrypted() {
+        let key = BackupKey::from_secret("secret");
+        for len in [0, 1, CHUNK_LEN, 2 * CHUNK_LEN + 5] {
+            let plain: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            let encrypted = encrypt(&key, &plain).await;
+
+            assert_ne!(plain, encrypted[MAGIC.len() + NONCE_PREFIX_LEN..]);
+            assert_eq!(plain, decrypt(&key, &encrypted).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reject_wrong_key_and_truncation() {
+        let key = BackupKey::from_secret("secret");
+        let encrypted = encrypt(&key, &[7u8; 2 * CHUNK_LEN]).await;
+
+        assert!(
+            decrypt(&BackupKey::from_secret("other"), &encrypted)
+                .await
+                .is_err()
+        );
+        let full_chunks = MAGIC.len() + NONCE_PREFIX_LEN + 2 * (CHUNK_LEN + TAG_LEN);
+        assert!(decrypt(&key, &encrypted[..full_chunks]).await.is_err());
+        assert!(
+            decrypt(&key, &encrypted[..encrypted.len() - 1])
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::client_file_event::ClientFileEvent;
+use crate::db::{ServerDatabase, WriteIntent};
+use crate::event_store::EventStore;
+use crate::file_event::{FileEvent, FileEventType};
+use crate::file_history::FileHistory;
+use shared::matchable_path::MatchablePath;
+use shared::utc_millis::UtcMillis;
+use std::fs;
+use std::io;
+use std::path::Path;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// journals the move of a received upload into the watch group - before it happens, so
+/// [`recover_interrupted_writes`] can finish it if the server dies before the event is recorded
+///
+/// returns the id to pass to [`finish_write`] - None if the journal couldn't be written (the
+/// upload goes on unjournaled then)
+pub async fn begin_write(
+    db: &ServerDatabase,
+    event: &ClientFileEvent,
+    target: &Path,
+    client_id: Option<&str>,
+    client_host: Option<&str>,
+) -> Option<String> {
+    let temp = event.temp_file_path.as_ref()?;
+    let intent = WriteIntent {
+        id: Uuid::new_v4().to_string(),
+        watch_group_id: event.watch_group_id,
+        relative_path: event.relative_path.to_serialized_string(),
+        temp_path: temp.to_string_lossy().to_string(),
+        target_path: target.to_string_lossy().to_string(),
+        size_in_bytes: event.content_size as i64,
+        utc_millis: event.utc_millis.as_u64() as i64,
+        client_id: client_id.map(str::to_string),
+        client_host: client_host.map(str::to_string),
+    };
+    match db.write_intent().insert(&intent).await {
+        Ok(()) => Some(intent.id),
+        Err(e) => {
+            error!("Failed to journal write of {}: {}", intent.relative_path, e);
+            None
+        }
+    }
+}
+
+/// drops the journal entry - once the event is recorded (or the write failed for good)
+pub async fn finish_write(db: &ServerDatabase, id: Option<String>) {
+    let Some(id) = id else {
+        return;
+    };
+    if let Err(e) = db.write_intent().remove(&id).await {
+        error!("Failed to drop journal entry {}: {}", id, e);
+    }
+}
+
+/// finishes or rolls back the writes left unfinished - has to run before the temp
+/// dir is cleaned up
+///
+/// a complete temp file is moved into place, an incomplete one removed - and a file in place is
+/// recorded unless the history has a newer event of the path already
+pub async fn recover_interrupted_writes(
+    db: &ServerDatabase,
+    history: &dyn FileHistory,
+    events: &EventStore,
+) {
+    let intents = match db.write_intent().list().await {
+        Ok(intents) => intents,
+        Err(e) => {
+            error!("Failed to read the write journal: {}", e);
+            return;
+        }
+    };
+    if intents.is_empty() {
+        return;
+    }
+    warn!("Recovering {} interrupted writes", intents.len());
+    for intent in intents {
+        match complete(&intent) {
+            Ok(true) => record(history, events, &intent).await,
+            Ok(false) => warn!(
+                "Rolled back interrupted write of {} - its temp file is incomplete",
+                intent.relative_path
+            ),
+            Err(e) => error!(
+                "Failed to recover interrupted write of {}: {}",
+                intent.relative_path, e
+            ),
+        }
+        if let Err(e) = db.write_intent().remove(&intent.id).await {
+            error!("Failed to drop journal entry {}: {}", intent.id, e);
+        }
+    }
+}
+
+/// true if the upload is in place now - the temp file is gone either way
+fn complete(intent: &WriteIntent) -> io::Result<bool> {
+    let temp = Path::new(&intent.temp_path);
+    let target = Path::new(&intent.target_path);
+    let size = intent.size_in_bytes as u64;
+    match fs::metadata(temp) {
+        Ok(meta) if meta.len() == size => {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(temp, target)?;
+            Ok(true)
+        }
+        Ok(_) => fs::remove_file(temp).map(|_| false),
+        // moved before the crash
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            Ok(fs::metadata(target).is_ok_and(|meta| meta.len() == size))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn record(history: &dyn FileHistory, events: &EventStore, intent: &WriteIntent) {
+    let path = MatchablePath::from(intent.relative_path.as_str());
+    let utc_millis = UtcMillis::from(intent.utc_millis as u64);
+    if history
+        .get_latest_event(intent.watch_group_id, &path)
+        .is_some_and(|latest| latest.utc_millis >= utc_millis)
+    {
+        info!(
+            "Interrupted write of {} was recorded already",
+            intent.relative_path
+        );
+        return;
+    }
+    let event = FileEvent::new(
+        Uuid::new_v4(),
+        utc_millis,
+        path,
+        intent.size_in_bytes as u64,
+        FileEventType::ChangeEvent,
+        intent.client_host.clone(),
+        intent.watch_group_id,
+    );
+    match events.record(&event, intent.client_id.as_deref()).await {
+        Ok(()) => info!("Finished interrupted write of {}", intent.relative_path),
+        Err(e) => error!(
+            "Failed to record interrupted write of {}: {}",
+            intent.relative_path, e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(dir: &Path, size_in_bytes: i64) -> WriteIntent {
+        WriteIntent {
+            id: Uuid::new_v4().to_string(),
+            watch_group_id: 1,
+            relative_path: "a.txt".to_string(),
+            temp_path: dir.join("tmp/upload").to_string_lossy().to_string(),
+            target_path: dir.join("upload/1/a.txt").to_string_lossy().to_string(),
+            size_in_bytes,
+            utc_millis: 1,
+            client_id: None,
+            client_host: None,
+        }
+    }
+
+    #[test]
+    fn should_finish_complete_and_roll_back_partial_writes() {
+        let dir = std::env::temp_dir().join(format!("journal-{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("tmp")).unwrap();
+        let temp = dir.join("tmp/upload");
+        let target = dir.join("upload/1/a.txt");
+
+        // crashed before the move
+        fs::write(&temp, "abc").unwrap();
+        assert!(complete(&intent(&dir, 3)).unwrap());
+        assert_eq!("abc", fs::read_to_string(&target).unwrap());
+        assert!(!temp.exists());
+
+        // crashed after the move - before the event was recorded
+        assert!(complete(&intent(&dir, 3)).unwrap());
+
+        // the temp file wasn't flushed completely before the crash
+        fs::write(&temp, "ab").unwrap();
+        assert!(!complete(&intent(&dir, 4)).unwrap());
+        assert!(!temp.exists());
+        assert_eq!("abc", fs::read_to_string(&target).unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,66 +1,298 @@
+mod encryption;
+mod journal;
+mod offsite;
 mod rotating;
+mod snapshot;
+mod transfer;
 
+pub use encryption::{BACKUP_KEY, ENCRYPTED_SUFFIX};
+pub use journal::{begin_write, finish_write, recover_interrupted_writes};
 pub use rotating::RotatingFileWriter;
+pub use transfer::{TRANSFER_BUFFER_BYTES, file_body, file_range_body};
 
+use axum::body::{Body, HttpBody};
 use axum::extract::multipart::{Field, MultipartError};
-use chrono::{Local, NaiveTime};
+use chrono::Local;
+use futures_util::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use shared::dtos::Durability;
+use shared::get_files_of_directory::{ScanLimits, get_all_file_descriptions};
+use shared::temp_files::TempFilePatterns;
+use std::env;
 use std::fs::{self, create_dir_all};
-use std::io;
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
 use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
-use tokio::time::{sleep_until, Instant};
-use tracing::{debug, error, info};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tracing::{debug, error, info, warn};
 
-pub async fn schedule_data_backups(data_path: &Path, backup_path: &Path) {
-    info!("Scheduling backups");
-    loop {
-        let backup_time = NaiveTime::from_hms_opt(2, 0, 0).unwrap();
-        let now = Local::now().naive_local();
-        let today = Local::now().date_naive();
-
-        let next_run = if now.time() < backup_time {
-            today.and_time(backup_time)
-        } else {
-            today.succ_opt().unwrap().and_time(backup_time)
-        };
+use crate::db::ServerDatabase;
+use crate::quiesce::BackupWindow;
+use crate::zip::{MAX_ARCHIVE_BYTES, MAX_ENTRIES, ZipWriter, archive_size};
 
-        let next_run_duration = next_run
-            .and_utc()
-            .signed_duration_since(Local::now().with_timezone(&chrono::Utc))
-            .to_std()
-            .unwrap();
+/// older nightly database backups get pruned
+const MAX_DB_BACKUPS: usize = 7;
+/// older nightly archives / snapshots get pruned (per watch group)
+const MAX_DATA_BACKUPS: usize = 7;
 
-        info!(
-            "Next backup scheduled for: {} (in {:?})",
-            next_run, next_run_duration
-        );
+/// how watch groups are backed up - `BACKUP_MODE=snapshot` for large, mostly static trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BackupMode {
+    /// `data_{wg_id}_{timestamp}.zip` - a full copy each night
+    Zip,
+    /// `snapshots/{wg_id}/{timestamp}/` - hard links to the previous snapshot for unchanged files
+    Snapshot,
+}
+
+static BACKUP_MODE: LazyLock<BackupMode> =
+    LazyLock::new(|| match env::var("BACKUP_MODE").as_deref() {
+        Ok("snapshot") => BackupMode::Snapshot,
+        Ok("zip") | Err(_) => BackupMode::Zip,
+        Ok(other) => {
+            warn!("Unknown BACKUP_MODE '{other}' - falling back to zip");
+            BackupMode::Zip
+        }
+    });
+
+/// one run of the `backup` job - archives / snapshots of the watch groups and a copy of the
+/// database, older ones pruned
+pub async fn run_backup(
+    data_path: &Path,
+    backup_path: &Path,
+    db: &ServerDatabase,
+    window: &Arc<BackupWindow>,
+) {
+    perform_backup(data_path, backup_path, window).await;
+    perform_db_backup(db, backup_path).await;
+}
+
+async fn perform_db_backup(db: &ServerDatabase, backup_path: &Path) {
+    match db.maintenance().integrity_check().await {
+        Ok(problems) if problems.is_empty() => debug!("Database integrity check passed"),
+        Ok(problems) => error!("Database integrity check failed: {:?}", problems),
+        Err(e) => error!("Database integrity check could not run: {}", e),
+    }
+    match db.maintenance().backup_into(backup_path).await {
+        Ok(backup) => info!("Backed up database to {:?}", backup),
+        Err(e) => error!("Database backup failed: {}", e),
+    }
+
+    prune_backups(backup_path, "sqlite_", &[".db"], MAX_DB_BACKUPS);
+}
+
+/// timestamped names - so sorting by name is sorting by age
+fn prune_backups(backup_path: &Path, prefix: &str, suffixes: &[&str], keep: usize) {
+    let mut backups: Vec<_> = fs::read_dir(backup_path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| {
+                    p.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+                        n.starts_with(prefix) && suffixes.iter().any(|s| n.ends_with(s))
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort();
+    let outdated = backups.len().saturating_sub(keep);
+    for backup in &backups[..outdated] {
+        if let Err(e) = fs::remove_file(backup) {
+            warn!("Failed to prune backup {:?}: {}", backup, e);
+        }
+    }
+}
+
+/// one zip or snapshot per watch group (see [`BackupMode`]) - writes to the watch group are
+/// paused meanwhile, see [`BackupWindow`]
+async fn perform_backup(data_path: &Path, backup_path: &Path, window: &Arc<BackupWindow>) {
+    info!("Executing backup ({:?})...", *BACKUP_MODE);
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let wg_ids: Vec<i64> = fs::read_dir(data_path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .filter_map(|e| e.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for wg_id in wg_ids {
+        let root = data_path.join(wg_id.to_string());
+        match *BACKUP_MODE {
+            BackupMode::Zip => {
+                let archive = {
+                    let _quiesced = window.quiesce(wg_id).await;
+                    zip_backup(&root, backup_path, wg_id, &timestamp).await
+                };
+                if let Some(archive) = archive {
+                    secure_archive(archive).await;
+                }
+                prune_backups(
+                    backup_path,
+                    &format!("data_{wg_id}_"),
+                    &[".zip", &format!(".zip{ENCRYPTED_SUFFIX}")],
+                    MAX_DATA_BACKUPS,
+                );
+            }
+            BackupMode::Snapshot => {
+                let _quiesced = window.quiesce(wg_id).await;
+                snapshot_backup(root, backup_path, wg_id, &timestamp).await
+            }
+        }
+    }
+}
 
-        sleep_until(Instant::now() + next_run_duration).await;
+async fn zip_backup(
+    root: &Path,
+    backup_path: &Path,
+    wg_id: i64,
+    timestamp: &str,
+) -> Option<PathBuf> {
+    let archive = backup_path.join(format!("data_{wg_id}_{timestamp}.zip"));
+    match archive_directory(root, &archive).await {
+        Ok(count) => {
+            info!(
+                "Backed up {count} files of watch group {wg_id} to {:?}",
+                archive
+            );
+            Some(archive)
+        }
+        Err(e) => {
+            error!("Backup of watch group {wg_id} failed: {}", e);
+            let _ = fs::remove_file(&archive);
+            None
+        }
+    }
+}
 
-        perform_backup(data_path, backup_path).await;
+/// encrypts the archive (`BACKUP_ENCRYPTION_KEY`) and copies it off-site (`BACKUP_OFFSITE_URL`)
+/// - after the watch group was released again
+async fn secure_archive(archive: PathBuf) {
+    let archive = match BACKUP_KEY.as_ref() {
+        Some(key) => match encrypt_archive(key, &archive).await {
+            Ok(encrypted) => encrypted,
+            Err(e) => {
+                error!(
+                    "Encryption of {:?} failed - kept unencrypted: {}",
+                    archive, e
+                );
+                archive
+            }
+        },
+        None => archive,
+    };
+    if let Some(target) = offsite::OFFSITE_TARGET.as_ref() {
+        match target.upload(&archive).await {
+            Ok(()) => info!("Copied {:?} off-site (checksum verified)", archive),
+            Err(e) => error!("Off-site copy of {:?} failed: {}", archive, e),
+        }
     }
 }
 
-async fn perform_backup(_data_path: &Path, _backup_path: &Path) {
-    // TODO impl
-    info!("Executing daily backup...");
+/// replaces the archive with its encrypted version
+async fn encrypt_archive(key: &encryption::BackupKey, archive: &Path) -> io::Result<PathBuf> {
+    let mut encrypted = archive.as_os_str().to_owned();
+    encrypted.push(ENCRYPTED_SUFFIX);
+    let encrypted = PathBuf::from(encrypted);
+    let result = key
+        .encrypt(
+            File::open(archive).await?,
+            BufWriter::new(File::create(&encrypted).await?),
+        )
+        .await;
+    match result {
+        Ok(_) => {
+            tokio::fs::remove_file(archive).await?;
+            Ok(encrypted)
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&encrypted).await;
+            Err(e)
+        }
+    }
+}
+
+async fn snapshot_backup(root: PathBuf, backup_path: &Path, wg_id: i64, timestamp: &str) {
+    let snapshots = backup_path.join("snapshots").join(wg_id.to_string());
+    let name = timestamp.to_string();
+    let result = tokio::task::spawn_blocking({
+        let snapshots = snapshots.clone();
+        move || {
+            create_dir_all(&snapshots)?;
+            snapshot::snapshot_directory(&root, &snapshots, &name)
+        }
+    })
+    .await
+    .unwrap_or_else(|e| Err(io::Error::other(e)));
+    match result {
+        Ok(stats) => info!(
+            "Snapshot {timestamp} of watch group {wg_id}: {} files linked, {} copied",
+            stats.linked, stats.copied
+        ),
+        Err(e) => error!("Snapshot of watch group {wg_id} failed: {}", e),
+    }
+    snapshot::prune_snapshots(&snapshots, MAX_DATA_BACKUPS);
+}
+
+/// returns the number of archived files
+async fn archive_directory(root: &Path, archive: &Path) -> io::Result<usize> {
+    let files = get_all_file_descriptions(
+        root,
+        &Vec::new(),
+        false,
+        &TempFilePatterns::none(),
+        ScanLimits::none(),
+    )
+    .map_err(io::Error::other)?;
+    let names: Vec<String> = files
+        .iter()
+        .map(|f| f.relative_path.to_serialized_string())
+        .collect();
+    let total = archive_size(
+        names
+            .iter()
+            .zip(&files)
+            .map(|(name, f)| (name.as_str(), f.size_in_bytes)),
+    );
+    if total > MAX_ARCHIVE_BYTES || files.len() > MAX_ENTRIES {
+        return Err(io::Error::other(format!(
+            "Too large for a zip ({} files, {} bytes)",
+            files.len(),
+            total
+        )));
+    }
+
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(archive).await?));
+    for (name, f) in names.iter().zip(files) {
+        let content = File::open(f.relative_path.resolve(root)).await?;
+        zip.add(name, f.last_updated_utc_millis.into(), content)
+            .await?;
+    }
+    zip.finish().await
 }
 
 fn map_to_io_error(e: MultipartError) -> io::Error {
     io::Error::other(e)
 }
 
+/// the hasher (if any) is fed with every received chunk - see [`hash_file`]
+///
+/// the file is fsynced once written if the durability asks for it
 pub async fn write_all_chunks_of_field(
     path: &Path,
     mut field: Field<'_>,
+    mut hasher: Option<&mut Sha256>,
+    durability: Durability,
 ) -> Result<usize, io::Error> {
     info!(
         "Trying to progressively write to {} - (content_type = {:?})",
         path.display(),
         field.content_type()
     );
-    let mut file = File::create(path).await?;
+    let mut file = BufWriter::with_capacity(*TRANSFER_BUFFER_BYTES, File::create(path).await?);
     let mut chunk_counter = 0;
     let mut total_size_counter = 0;
     loop {
@@ -71,6 +303,10 @@ pub async fn write_all_chunks_of_field(
             }
             Ok(option) => match option {
                 None => {
+                    file.flush().await?;
+                    if durability.syncs_file() {
+                        file.get_ref().sync_all().await?;
+                    }
                     info!(
                         "File written to {} ({})",
                         path.display(),
@@ -83,6 +319,9 @@ pub async fn write_all_chunks_of_field(
                     let chunk_size = bytes.len();
                     total_size_counter += chunk_size;
                     debug!("{}: chunk-size = {}", chunk_counter, chunk_size);
+                    if let Some(hasher) = hasher.as_mut() {
+                        hasher.update(&bytes);
+                    }
                     file.write_all(&bytes).await?;
                 }
             },
@@ -91,6 +330,63 @@ pub async fn write_all_chunks_of_field(
     Ok(total_size_counter)
 }
 
+/// like [`write_all_chunks_of_field`] but for a plain request body (e.g. webdav PUT)
+pub async fn write_all_chunks_of_body(
+    path: &Path,
+    body: Body,
+    hasher: Option<&mut Sha256>,
+    durability: Durability,
+) -> Result<usize, io::Error> {
+    let expected_size = body.size_hint().exact();
+    let stream = body.into_data_stream();
+    write_all_chunks_of_stream(path, stream, hasher, expected_size, durability).await
+}
+
+/// like [`write_all_chunks_of_field`] but for any stream of chunks (e.g. grpc upload)
+///
+/// with the (approximate) size known, the file is preallocated - and truncated to the received
+/// bytes afterwards
+pub async fn write_all_chunks_of_stream<B, E>(
+    path: &Path,
+    mut stream: impl Stream<Item = Result<B, E>> + Unpin,
+    mut hasher: Option<&mut Sha256>,
+    expected_size: Option<u64>,
+    durability: Durability,
+) -> Result<usize, io::Error>
+where
+    B: AsRef<[u8]>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let file = File::create(path).await?;
+    if let Some(expected_size) = expected_size.filter(|size| *size > 0) {
+        transfer::preallocate(&file, expected_size)?;
+    }
+    let mut file = BufWriter::with_capacity(*TRANSFER_BUFFER_BYTES, file);
+    let mut total_size_counter = 0;
+    while let Some(chunk) = stream.next().await {
+        let bytes = chunk.map_err(io::Error::other)?;
+        let bytes = bytes.as_ref();
+        total_size_counter += bytes.len();
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(bytes);
+        }
+        file.write_all(bytes).await?;
+    }
+    file.flush().await?;
+    if expected_size.is_some() {
+        file.get_ref().set_len(total_size_counter as u64).await?;
+    }
+    if durability.syncs_file() {
+        file.get_ref().sync_all().await?;
+    }
+    info!(
+        "File written to {} ({})",
+        path.display(),
+        total_size_counter
+    );
+    Ok(total_size_counter)
+}
+
 // NOTE: introduce switch flag to try both and measure mem-consumption and speed? would be interesting
 pub async fn _write_all_at_once(path: &Path, field: Field<'_>) -> Result<(), io::Error> {
     info!(
@@ -118,6 +414,65 @@ pub async fn _write_all_at_once(path: &Path, field: Field<'_>) -> Result<(), io:
     }
 }
 
+/// moves a written temp file into place - with [`Durability::FsyncFileAndDir`] the directory is
+/// fsynced afterwards, so the new entry survives a power loss as well
+pub fn promote(temp: &Path, target: &Path, durability: Durability) -> io::Result<()> {
+    fs::rename(temp, target)?;
+    if durability.syncs_dir()
+        && let Some(dir) = target.parent()
+        && let Err(e) = sync_dir(dir)
+    {
+        // the file is in place - failing the upload now would only orphan it
+        warn!("Failed to fsync {:?} - {}", dir, e);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+/// directories can't be opened for syncing - NTFS journals the rename anyway
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// like [`io::copy`] but also feeds the hasher (if any)
+pub fn copy_hashing(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    mut hasher: Option<&mut Sha256>,
+) -> io::Result<u64> {
+    let mut buffer = [0u8; 64 * 1024];
+    let mut total = 0;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(total),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&buffer[..read]);
+        }
+        writer.write_all(&buffer[..read])?;
+        total += read as u64;
+    }
+}
+
+/// hex encoded sha256 of the file content
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    copy_hashing(
+        &mut fs::File::open(path)?,
+        &mut io::sink(),
+        Some(&mut hasher),
+    )?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 /// directories to create (if not existent)
 pub fn create_all_paths_if_not_exist(paths: Vec<&Path>) -> io::Result<()> {
     for path in paths.into_iter() {
@@ -128,7 +483,6 @@ pub fn create_all_paths_if_not_exist(paths: Vec<&Path>) -> io::Result<()> {
     Ok::<(), io::Error>(())
 }
 
-
 pub fn create_file_if_not_exists(path: &Path) -> io::Result<()> {
     if !path.exists() {
         fs::write(path, "")?
@@ -136,3 +490,54 @@ pub fn create_file_if_not_exists(path: &Path) -> io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn should_hash_copied_content_like_the_written_file() {
+        let path = std::env::temp_dir().join(format!("hash-{}", Uuid::new_v4()));
+        let content = vec![7u8; 100 * 1024];
+        let mut hasher = Sha256::new();
+
+        let copied = copy_hashing(
+            &mut content.as_slice(),
+            &mut fs::File::create(&path).unwrap(),
+            Some(&mut hasher),
+        )
+        .unwrap();
+
+        assert_eq!(content.len() as u64, copied);
+        assert_eq!(
+            format!("{:x}", hasher.finalize()),
+            hash_file(&path).unwrap()
+        );
+        assert_eq!(
+            format!("{:x}", Sha256::digest(&content)),
+            hash_file(&path).unwrap()
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_archive_all_files_of_directory() {
+        let dir = std::env::temp_dir().join(format!("backup-{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("data/sub")).unwrap();
+        fs::write(dir.join("data/a.txt"), "a").unwrap();
+        fs::write(dir.join("data/sub/b.txt"), "bb").unwrap();
+        let archive = dir.join("data_1.zip");
+
+        let count = archive_directory(&dir.join("data"), &archive)
+            .await
+            .unwrap();
+
+        assert_eq!(2, count);
+        assert_eq!(
+            archive_size([("a.txt", 1), ("sub/b.txt", 2)]),
+            fs::metadata(&archive).unwrap().len()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
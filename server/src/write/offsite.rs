@@ -0,0 +1,126 @@
+use reqwest::multipart::{Form, Part};
+use reqwest::{Body, Client};
+use shared::dtos::ManifestDto;
+use shared::endpoint::{CLIENT_ID_HEADER_KEY, ServerEndpoint, SysRoute};
+use shared::utc_millis::UtcMillis;
+use std::env;
+use std::path::Path;
+use std::sync::LazyLock;
+use tokio::fs::File;
+use tokio_util::io::ReaderStream;
+use tracing::warn;
+
+use super::hash_file;
+
+/// `BACKUP_OFFSITE_URL` - archives are copied to another rust-file-sync server once it is set
+pub static OFFSITE_TARGET: LazyLock<Option<OffsiteTarget>> = LazyLock::new(OffsiteTarget::from_env);
+
+/// a watch group on another rust-file-sync server - this server uploads like a client that is
+/// registered there and assigned to the watch group
+pub struct OffsiteTarget {
+    url: String,
+    client_id: String,
+    wg_id: i64,
+    /// api token of the other server (if it has `ADMIN_TOKEN` set) - to read the manifest
+    token: Option<String>,
+    client: Client,
+}
+
+impl OffsiteTarget {
+    fn from_env() -> Option<Self> {
+        let url = env::var("BACKUP_OFFSITE_URL")
+            .ok()
+            .filter(|u| !u.is_empty())?;
+        let client_id = env::var("BACKUP_OFFSITE_CLIENT_ID").ok();
+        let wg_id = env::var("BACKUP_OFFSITE_WATCH_GROUP")
+            .ok()
+            .and_then(|id| id.parse().ok());
+        let (Some(client_id), Some(wg_id)) = (client_id, wg_id) else {
+            warn!(
+                "BACKUP_OFFSITE_URL needs BACKUP_OFFSITE_CLIENT_ID and BACKUP_OFFSITE_WATCH_GROUP - no off-site backups"
+            );
+            return None;
+        };
+        Some(OffsiteTarget {
+            url: url.trim_end_matches('/').to_string(),
+            client_id,
+            wg_id,
+            token: env::var("BACKUP_OFFSITE_TOKEN")
+                .ok()
+                .filter(|t| !t.is_empty()),
+            client: Client::new(),
+        })
+    }
+
+    /// uploads the archive into the root of the watch group - then compares the sha-256 the
+    /// other server reports in its manifest
+    pub async fn upload(&self, archive: &Path) -> Result<(), String> {
+        let file_name = archive
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| format!("No file name in {archive:?}"))?;
+        let file = File::open(archive)
+            .await
+            .map_err(|e| format!("Could not open {archive:?} - {e}"))?;
+        let size = file
+            .metadata()
+            .await
+            .map_err(|e| format!("Could not read {archive:?} - {e}"))?
+            .len();
+        let form = Form::new()
+            .text(
+                "utc_millis",
+                serde_json::to_string(&UtcMillis::now()).unwrap(),
+            )
+            .text("relative_path", file_name.clone())
+            .part(
+                "file",
+                Part::stream_with_length(Body::wrap_stream(ReaderStream::new(file)), size)
+                    .file_name(file_name.clone()),
+            );
+        self.client
+            .post(SysRoute::Upload { wg_id: self.wg_id }.to_uri(&self.url))
+            .header(CLIENT_ID_HEADER_KEY, &self.client_id)
+            .multipart(form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Upload failed - {e}"))?;
+
+        let archive = archive.to_path_buf();
+        let local_hash = tokio::task::spawn_blocking(move || hash_file(&archive))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Could not hash archive - {e}"))?;
+        let remote_hash = self.remote_hash(&file_name).await?;
+        if remote_hash != local_hash {
+            return Err(format!(
+                "Off-site copy of {file_name} differs (sha-256 {remote_hash} instead of {local_hash})"
+            ));
+        }
+        Ok(())
+    }
+
+    async fn remote_hash(&self, file_name: &str) -> Result<String, String> {
+        let uri = ServerEndpoint::ApiWatchGroupManifest
+            .to_uri_with(&self.url, &[("id", &self.wg_id.to_string())]);
+        let mut request = self.client.get(uri);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let manifest: ManifestDto = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Could not read manifest - {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("Could not parse manifest - {e}"))?;
+        manifest
+            .files
+            .into_iter()
+            .find(|entry| entry.path.to_serialized_string() == file_name)
+            .map(|entry| entry.sha256)
+            .ok_or_else(|| format!("{file_name} is missing in the off-site manifest"))
+    }
+}
@@ -1,5 +1,5 @@
 use chrono::Local;
-use std::fs::{self, create_dir_all, OpenOptions};
+use std::fs::{self, OpenOptions, create_dir_all};
 use std::io::{self, Write};
 use std::path::PathBuf;
 
@@ -164,11 +164,11 @@ impl RotatingFileWriter {
         self.create_new_file()
     }
 
-    /// Reads the content of the current file.
-    pub fn read_current_file(&self) -> io::Result<String> {
-        match &self.current_file_path {
-            Some(path) => fs::read_to_string(path),
-            None => Ok(String::new()),
-        }
+    /// Reads the content of all kept files, oldest first.
+    pub fn read_all_files(&self) -> io::Result<Vec<String>> {
+        self.list_existing_files()?
+            .iter()
+            .map(fs::read_to_string)
+            .collect()
     }
 }
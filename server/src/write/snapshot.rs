@@ -0,0 +1,168 @@
+use shared::get_files_of_directory::{ScanLimits, get_all_file_descriptions};
+use shared::temp_files::TempFilePatterns;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// suffix of a snapshot still being written - never used as the base of the next one
+const PARTIAL_SUFFIX: &str = ".partial";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SnapshotStats {
+    /// unchanged since the previous snapshot - hard linked
+    pub linked: usize,
+    /// new or changed - copied
+    pub copied: usize,
+}
+
+/// copies `root` into `snapshots/{name}` - files unchanged (same size and mtime) since the
+/// latest snapshot are hard linked to it, so every snapshot is complete but only changes take
+/// up space
+pub fn snapshot_directory(root: &Path, snapshots: &Path, name: &str) -> io::Result<SnapshotStats> {
+    let previous = latest_snapshot(snapshots);
+    let partial = snapshots.join(format!("{name}{PARTIAL_SUFFIX}"));
+    let files = get_all_file_descriptions(
+        root,
+        &Vec::new(),
+        false,
+        &TempFilePatterns::none(),
+        ScanLimits::none(),
+    )
+    .map_err(io::Error::other)?;
+    // an empty watch group still gets its (empty) snapshot
+    fs::create_dir_all(&partial)?;
+
+    let mut stats = SnapshotStats::default();
+    for file in files {
+        let source = file.relative_path.resolve(root);
+        let target = file.relative_path.resolve(&partial);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let unchanged = previous
+            .as_ref()
+            .map(|previous| file.relative_path.resolve(previous))
+            .filter(|base| is_unchanged(&source, base));
+        match unchanged {
+            // e.g. the link limit of the file system is reached
+            Some(base) if fs::hard_link(&base, &target).is_ok() => stats.linked += 1,
+            _ => {
+                copy_keeping_mtime(&source, &target)?;
+                stats.copied += 1;
+            }
+        }
+    }
+    fs::rename(&partial, snapshots.join(name))?;
+    Ok(stats)
+}
+
+/// removes all but the newest `keep` snapshots - and leftovers of failed ones
+pub fn prune_snapshots(snapshots: &Path, keep: usize) {
+    let (partial, mut complete): (Vec<PathBuf>, Vec<PathBuf>) = snapshot_dirs(snapshots)
+        .into_iter()
+        .partition(|p| p.to_string_lossy().ends_with(PARTIAL_SUFFIX));
+    complete.sort();
+    let outdated = complete.len().saturating_sub(keep);
+    for snapshot in partial.iter().chain(&complete[..outdated]) {
+        debug!("Removing snapshot {:?}", snapshot);
+        if let Err(e) = fs::remove_dir_all(snapshot) {
+            warn!("Failed to prune snapshot {:?}: {}", snapshot, e);
+        }
+    }
+}
+
+/// timestamped names - so sorting by name is sorting by age
+fn latest_snapshot(snapshots: &Path) -> Option<PathBuf> {
+    snapshot_dirs(snapshots)
+        .into_iter()
+        .filter(|p| !p.to_string_lossy().ends_with(PARTIAL_SUFFIX))
+        .max()
+}
+
+fn snapshot_dirs(snapshots: &Path) -> Vec<PathBuf> {
+    fs::read_dir(snapshots)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_unchanged(source: &Path, base: &Path) -> bool {
+    match (fs::metadata(source), fs::metadata(base)) {
+        (Ok(source), Ok(base)) => {
+            source.len() == base.len()
+                && source
+                    .modified()
+                    .ok()
+                    .is_some_and(|m| base.modified().ok() == Some(m))
+        }
+        _ => false,
+    }
+}
+
+/// the mtime is what the next snapshot compares against
+fn copy_keeping_mtime(source: &Path, target: &Path) -> io::Result<()> {
+    fs::copy(source, target)?;
+    let modified = fs::metadata(source)?.modified()?;
+    fs::File::options()
+        .write(true)
+        .open(target)?
+        .set_modified(modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn should_link_unchanged_and_copy_changed_files() {
+        let dir = std::env::temp_dir().join(format!("snapshot-{}", Uuid::new_v4()));
+        let root = dir.join("data");
+        let snapshots = dir.join("snapshots");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::create_dir_all(&snapshots).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("sub/b.txt"), "b").unwrap();
+
+        let first = snapshot_directory(&root, &snapshots, "20260101_020000").unwrap();
+        fs::write(root.join("sub/b.txt"), "changed").unwrap();
+        let second = snapshot_directory(&root, &snapshots, "20260102_020000").unwrap();
+
+        assert_eq!(
+            SnapshotStats {
+                linked: 0,
+                copied: 2
+            },
+            first
+        );
+        assert_eq!(
+            SnapshotStats {
+                linked: 1,
+                copied: 1
+            },
+            second
+        );
+        assert_eq!(
+            "b",
+            fs::read_to_string(snapshots.join("20260101_020000/sub/b.txt")).unwrap()
+        );
+        assert_eq!(
+            "changed",
+            fs::read_to_string(snapshots.join("20260102_020000/sub/b.txt")).unwrap()
+        );
+
+        fs::create_dir_all(snapshots.join("20260103_020000.partial")).unwrap();
+        prune_snapshots(&snapshots, 1);
+        assert_eq!(
+            vec![snapshots.join("20260102_020000")],
+            snapshot_dirs(&snapshots)
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
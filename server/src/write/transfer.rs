@@ -0,0 +1,194 @@
+use axum::body::Body;
+use std::env;
+use std::io::{self, SeekFrom};
+use std::sync::LazyLock;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+/// chunk size of downloads and write buffer of uploads - `TRANSFER_BUFFER_KB` (default: 256)
+///
+/// a plain [`ReaderStream`] reads 4 KiB at a time - one trip to the blocking pool per 4 KiB
+pub static TRANSFER_BUFFER_BYTES: LazyLock<usize> = LazyLock::new(|| {
+    env::var("TRANSFER_BUFFER_KB")
+        .ok()
+        .and_then(|kb| kb.parse::<usize>().ok())
+        .filter(|kb| *kb > 0)
+        .unwrap_or(256)
+        * 1024
+});
+
+/// streams the file in [`TRANSFER_BUFFER_BYTES`] chunks (with read-ahead hinted on linux)
+pub fn file_body(file: File) -> Body {
+    advise_sequential(&file);
+    Body::from_stream(ReaderStream::with_capacity(file, *TRANSFER_BUFFER_BYTES))
+}
+
+/// like [`file_body`] for the `len` bytes from `start` on - for range requests
+pub async fn file_range_body(mut file: File, start: u64, len: u64) -> io::Result<Body> {
+    advise_sequential(&file);
+    file.seek(SeekFrom::Start(start)).await?;
+    Ok(Body::from_stream(ReaderStream::with_capacity(
+        file.take(len),
+        *TRANSFER_BUFFER_BYTES,
+    )))
+}
+
+/// reserves `len` bytes up front so large uploads end up in few extents - and run out of disk
+/// before anything is written rather than halfway through
+///
+/// the file is `len` bytes long afterwards - truncate it to the written size once done.
+/// filesystems without `fallocate` (and other platforms) are skipped
+pub fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::AsRawFd;
+        // SAFETY: plain syscall on a descriptor owned by `file` for the duration of the call
+        let result = unsafe { libc::fallocate(file.as_raw_fd(), 0, 0, len as libc::off_t) };
+        if result != 0 {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() == Some(libc::ENOSPC) {
+                return Err(error);
+            }
+            tracing::debug!("Skipped preallocating {len} bytes - {error}");
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (file, len);
+    Ok(())
+}
+
+fn advise_sequential(file: &File) {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::AsRawFd;
+        // SAFETY: plain syscall on a descriptor owned by `file` for the duration of the call
+        unsafe {
+            libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_SEQUENTIAL);
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = file;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write::write_all_chunks_of_stream;
+    use bytes::Bytes;
+    use futures_util::{StreamExt, stream};
+    use shared::dtos::Durability;
+    use std::time::{Duration, Instant};
+    use tokio::io::AsyncWriteExt;
+    use uuid::Uuid;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn should_truncate_preallocated_file_to_written_size() {
+        let path = temp_path("prealloc");
+        let chunks = stream::iter([Bytes::from_static(b"hello "), Bytes::from_static(b"world")])
+            .map(Ok::<_, io::Error>);
+
+        let written = write_all_chunks_of_stream(
+            &path,
+            chunks,
+            None,
+            Some(1024 * 1024),
+            Durability::FsyncFile,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(11, written);
+        assert_eq!("hello world", std::fs::read_to_string(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_stream_whole_file() {
+        let path = temp_path("file-body");
+        let content: Vec<u8> = (0..1024 * 1024 + 7).map(|i| i as u8).collect();
+        std::fs::write(&path, &content).unwrap();
+
+        let body = file_body(File::open(&path).await.unwrap());
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+
+        assert_eq!(content, bytes.to_vec());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    fn mb_per_sec(bytes: usize, elapsed: Duration) -> f64 {
+        bytes as f64 / 1024.0 / 1024.0 / elapsed.as_secs_f64()
+    }
+
+    /// `cargo test -p server --release transfer_throughput -- --ignored --nocapture`
+    ///
+    /// compares the previous implementation (4 KiB reads, unbuffered writes) with the current one
+    #[tokio::test]
+    #[ignore]
+    async fn transfer_throughput() {
+        const SIZE: usize = 512 * 1024 * 1024;
+        // roughly what hyper hands out per multipart chunk
+        const UPLOAD_CHUNK: usize = 16 * 1024;
+        let chunk = Bytes::from(vec![42u8; UPLOAD_CHUNK]);
+        let chunks = || stream::repeat(chunk.clone()).take(SIZE / UPLOAD_CHUNK);
+
+        let plain = temp_path("bench-plain");
+        let start = Instant::now();
+        let mut file = File::create(&plain).await.unwrap();
+        let mut upload = chunks();
+        while let Some(bytes) = upload.next().await {
+            file.write_all(&bytes).await.unwrap();
+        }
+        file.sync_all().await.unwrap();
+        let upload_plain = start.elapsed();
+
+        let tuned = temp_path("bench-tuned");
+        let start = Instant::now();
+        write_all_chunks_of_stream(
+            &tuned,
+            chunks().map(Ok::<_, io::Error>),
+            None,
+            Some(SIZE as u64),
+            Durability::None,
+        )
+        .await
+        .unwrap();
+        File::open(&tuned).await.unwrap().sync_all().await.unwrap();
+        let upload_tuned = start.elapsed();
+
+        let start = Instant::now();
+        let file = File::open(&plain).await.unwrap();
+        let read = ReaderStream::new(file)
+            .fold(0, |n, bytes| async move { n + bytes.unwrap().len() })
+            .await;
+        assert_eq!(SIZE, read);
+        let download_plain = start.elapsed();
+
+        let start = Instant::now();
+        let body = file_body(File::open(&tuned).await.unwrap());
+        let read = body
+            .into_data_stream()
+            .fold(0, |n, bytes| async move { n + bytes.unwrap().len() })
+            .await;
+        assert_eq!(SIZE, read);
+        let download_tuned = start.elapsed();
+
+        println!(
+            "upload:   {:>8.1} MB/s -> {:>8.1} MB/s",
+            mb_per_sec(SIZE, upload_plain),
+            mb_per_sec(SIZE, upload_tuned)
+        );
+        println!(
+            "download: {:>8.1} MB/s -> {:>8.1} MB/s (buffer {} KiB)",
+            mb_per_sec(SIZE, download_plain),
+            mb_per_sec(SIZE, download_tuned),
+            *TRANSFER_BUFFER_BYTES / 1024
+        );
+        std::fs::remove_file(plain).unwrap();
+        std::fs::remove_file(tuned).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::{DateTime, Datelike, Local, Timelike};
+use crc::{CRC_32_ISO_HDLC, Crc};
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x0807_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// 2.0 - data descriptors
+const VERSION_NEEDED: u16 = 20;
+/// unix (upper byte), so the external attributes carry file modes
+const VERSION_MADE_BY: u16 = (3 << 8) | VERSION_NEEDED;
+/// bit 3: sizes and crc follow the data, bit 11: names are utf-8
+const FLAGS: u16 = (1 << 3) | (1 << 11);
+const METHOD_STORED: u16 = 0;
+/// regular file, rw-r--r--
+const EXTERNAL_ATTRIBUTES: u32 = 0o100644 << 16;
+
+/// plain zip (no zip64) - check [`archive_size`] before sending anything
+pub const MAX_ARCHIVE_BYTES: u64 = u32::MAX as u64;
+pub const MAX_ENTRIES: usize = u16::MAX as usize;
+
+/// exact size of the archive [`ZipWriter`] produces for entries of (name, size)
+pub fn archive_size<'a>(entries: impl IntoIterator<Item = (&'a str, u64)>) -> u64 {
+    entries
+        .into_iter()
+        .map(|(name, size)| 30 + 16 + 46 + 2 * name.len() as u64 + size)
+        .sum::<u64>()
+        + 22
+}
+
+/// writes a zip archive front to back without ever seeking - so it can go straight into a
+/// response body
+///
+/// entries are stored (not compressed), the crc of each entry is only known after its bytes
+/// went out and lands in the data descriptor and the central directory
+pub struct ZipWriter<W> {
+    inner: W,
+    offset: u64,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: String,
+    time: u16,
+    date: u16,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+impl<W: AsyncWrite + Unpin> ZipWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// copies `content` into a new entry `name` (unix-delimited)
+    pub async fn add(
+        &mut self,
+        name: &str,
+        modified: DateTime<Local>,
+        mut content: impl AsyncRead + Unpin,
+    ) -> io::Result<()> {
+        if self.entries.len() >= MAX_ENTRIES {
+            return Err(io::Error::other("Too many entries for zip"));
+        }
+        let offset = u32::try_from(self.offset)
+            .map_err(|_| io::Error::other("Archive too large for zip"))?;
+        let (time, date) = dos_date_time(modified);
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        put_u32(&mut header, LOCAL_HEADER_SIGNATURE);
+        put_u16(&mut header, VERSION_NEEDED);
+        put_u16(&mut header, FLAGS);
+        put_u16(&mut header, METHOD_STORED);
+        put_u16(&mut header, time);
+        put_u16(&mut header, date);
+        // crc and sizes follow in the data descriptor
+        put_u32(&mut header, 0);
+        put_u32(&mut header, 0);
+        put_u32(&mut header, 0);
+        put_u16(&mut header, name_len(name)?);
+        put_u16(&mut header, 0);
+        header.extend_from_slice(name.as_bytes());
+        self.write(&header).await?;
+
+        let mut digest = CRC32.digest();
+        let mut size: u64 = 0;
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = content.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            digest.update(&buf[..n]);
+            size += n as u64;
+            self.write(&buf[..n]).await?;
+        }
+        let crc = digest.finalize();
+        let size = u32::try_from(size).map_err(|_| io::Error::other("Entry too large for zip"))?;
+
+        let mut descriptor = Vec::with_capacity(16);
+        put_u32(&mut descriptor, DATA_DESCRIPTOR_SIGNATURE);
+        put_u32(&mut descriptor, crc);
+        put_u32(&mut descriptor, size);
+        put_u32(&mut descriptor, size);
+        self.write(&descriptor).await?;
+
+        self.entries.push(Entry {
+            name: name.to_string(),
+            time,
+            date,
+            crc,
+            size,
+            offset,
+        });
+        Ok(())
+    }
+
+    /// writes the central directory - returns the number of entries
+    pub async fn finish(mut self) -> io::Result<usize> {
+        let directory_offset = u32::try_from(self.offset)
+            .map_err(|_| io::Error::other("Archive too large for zip"))?;
+
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            put_u32(&mut directory, CENTRAL_HEADER_SIGNATURE);
+            put_u16(&mut directory, VERSION_MADE_BY);
+            put_u16(&mut directory, VERSION_NEEDED);
+            put_u16(&mut directory, FLAGS);
+            put_u16(&mut directory, METHOD_STORED);
+            put_u16(&mut directory, entry.time);
+            put_u16(&mut directory, entry.date);
+            put_u32(&mut directory, entry.crc);
+            put_u32(&mut directory, entry.size);
+            put_u32(&mut directory, entry.size);
+            put_u16(&mut directory, name_len(&entry.name)?);
+            // extra field, comment, disk number, internal attributes
+            put_u16(&mut directory, 0);
+            put_u16(&mut directory, 0);
+            put_u16(&mut directory, 0);
+            put_u16(&mut directory, 0);
+            put_u32(&mut directory, EXTERNAL_ATTRIBUTES);
+            put_u32(&mut directory, entry.offset);
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = u32::try_from(directory.len())
+            .map_err(|_| io::Error::other("Archive too large for zip"))?;
+        let count = self.entries.len() as u16;
+
+        put_u32(&mut directory, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        // number of this disk, disk with the central directory
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, count);
+        put_u16(&mut directory, count);
+        put_u32(&mut directory, directory_size);
+        put_u32(&mut directory, directory_offset);
+        // comment
+        put_u16(&mut directory, 0);
+        self.write(&directory).await?;
+        self.inner.shutdown().await?;
+        Ok(self.entries.len())
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.inner.write_all(bytes).await?;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn name_len(name: &str) -> io::Result<u16> {
+    u16::try_from(name.len()).map_err(|_| io::Error::other(format!("Name too long: {name}")))
+}
+
+/// (time, date) in ms-dos format - 2 second resolution, nothing before 1980
+fn dos_date_time(at: DateTime<Local>) -> (u16, u16) {
+    if at.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = (at.hour() << 11) | (at.minute() << 5) | (at.second() / 2);
+    let date = ((at.year() as u32 - 1980).min(127) << 9) | (at.month() << 5) | at.day();
+    (time as u16, date as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn should_encode_dos_date_time() {
+        let at = Local.with_ymd_and_hms(2024, 3, 15, 13, 45, 31).unwrap();
+        let (time, date) = dos_date_time(at);
+        assert_eq!((13 << 11) | (45 << 5) | 15, time);
+        assert_eq!((44 << 9) | (3 << 5) | 15, date);
+
+        let before_dos = Local.with_ymd_and_hms(1970, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!((0, (1 << 5) | 1), dos_date_time(before_dos));
+    }
+
+    #[tokio::test]
+    async fn should_write_stored_entries_with_central_directory() {
+        let mut out = Vec::new();
+        let mut zip = ZipWriter::new(&mut out);
+        let at = Local.with_ymd_and_hms(2024, 3, 15, 13, 45, 30).unwrap();
+        zip.add("a.txt", at, &b"123456789"[..]).await.unwrap();
+        zip.add("dir/b.txt", at, &b""[..]).await.unwrap();
+        assert_eq!(2, zip.finish().await.unwrap());
+
+        // local header of a.txt
+        assert_eq!(LOCAL_HEADER_SIGNATURE, u32_at(&out, 0));
+        assert_eq!(5, u16_at(&out, 26));
+        assert_eq!(b"a.txt", &out[30..35]);
+        assert_eq!(b"123456789", &out[35..44]);
+        // data descriptor - crc32 check value of "123456789"
+        assert_eq!(DATA_DESCRIPTOR_SIGNATURE, u32_at(&out, 44));
+        assert_eq!(0xCBF4_3926, u32_at(&out, 48));
+        assert_eq!(9, u32_at(&out, 52));
+
+        let offset_of_b = 44 + 16;
+        assert_eq!(LOCAL_HEADER_SIGNATURE, u32_at(&out, offset_of_b));
+        let directory_offset = offset_of_b + 30 + 9 + 16;
+        assert_eq!(CENTRAL_HEADER_SIGNATURE, u32_at(&out, directory_offset));
+
+        let end = out.len() - 22;
+        assert_eq!(END_OF_CENTRAL_DIRECTORY_SIGNATURE, u32_at(&out, end));
+        assert_eq!(2, u16_at(&out, end + 10));
+        assert_eq!((end - directory_offset) as u32, u32_at(&out, end + 12));
+        assert_eq!(directory_offset as u32, u32_at(&out, end + 16));
+        // second central header points at the local header of dir/b.txt
+        let second = directory_offset + 46 + 5;
+        assert_eq!(CENTRAL_HEADER_SIGNATURE, u32_at(&out, second));
+        assert_eq!(offset_of_b as u32, u32_at(&out, second + 42));
+        assert_eq!(
+            out.len() as u64,
+            archive_size([("a.txt", 9), ("dir/b.txt", 0)])
+        );
+    }
+}
@@ -0,0 +1,51 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc::compile();
+}
+
+/// the messages are plain prost structs (see `src/grpc.rs`) - only the service is generated,
+/// so no protoc is needed
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    const CODEC: &str = "tonic_prost::ProstCodec";
+
+    fn method(name: &str, route_name: &str, input: &str, output: &str) -> Method {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::grpc::{input}"))
+            .output_type(format!("crate::grpc::{output}"))
+            .codec_path(CODEC)
+            .build()
+    }
+
+    pub fn compile() {
+        let upload = Method::builder()
+            .name("upload")
+            .route_name("Upload")
+            .input_type("crate::grpc::UploadChunk")
+            .output_type("crate::grpc::UploadReply")
+            .codec_path(CODEC)
+            .client_streaming()
+            .build();
+        let download = Method::builder()
+            .name("download")
+            .route_name("Download")
+            .input_type("crate::grpc::DownloadRequest")
+            .output_type("crate::grpc::DownloadChunk")
+            .codec_path(CODEC)
+            .server_streaming()
+            .build();
+        let service = Service::builder()
+            .name("SyncService")
+            .package("rfs.sync")
+            .method(method("sync", "Sync", "SyncRequest", "SyncReply"))
+            .method(upload)
+            .method(download)
+            .method(method("delete", "Delete", "DeleteRequest", "DeleteReply"))
+            .build();
+        Builder::new().build_transport(false).compile(&[service]);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::matchable_path::MatchablePath;
+use crate::utc_millis::UtcMillis;
+use chrono::{DateTime, Utc};
+
+/// marks the name of a conflicted copy - `report (conflicted copy from laptop 2024-05-01).pdf`
+const MARKER: &str = " (conflicted copy from ";
+
+/// where a client with the keep-both conflict policy saves the server's version of `path` instead
+/// of overwriting its own changes - next to it, named after the host and day of that version
+///
+/// `attempt` > 1 appends a counter (for a second conflict of the same file on the same day)
+pub fn conflict_copy_path(
+    path: &MatchablePath,
+    host: &str,
+    utc_millis: &UtcMillis,
+    attempt: usize,
+) -> MatchablePath {
+    let name = path.tail();
+    // a leading dot is a hidden file, not an extension
+    let (stem, extension) = match name.rfind('.') {
+        Some(idx) if idx > 0 => name.split_at(idx),
+        _ => (name.as_str(), ""),
+    };
+    let host: String = host
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c => c,
+        })
+        .collect();
+    let day = DateTime::<Utc>::from_timestamp_millis(utc_millis.as_u64() as i64)
+        .unwrap_or_default()
+        .format("%Y-%m-%d");
+    let counter = match attempt {
+        0 | 1 => String::new(),
+        n => format!(" {n}"),
+    };
+
+    let mut segments = path.get().clone();
+    segments.pop();
+    segments.push(format!("{stem}{MARKER}{host} {day}{counter}){extension}"));
+    MatchablePath::from(segments)
+}
+
+/// conflicted copies never get copies of their own - the server's version simply wins
+pub fn is_conflict_copy(path: &MatchablePath) -> bool {
+    path.tail().contains(MARKER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-05-01 12:00 UTC
+    const MAY_FIRST: u64 = 1_714_564_800_000;
+
+    #[test]
+    fn should_name_conflict_copies_after_host_and_day() {
+        let copy = |path: &str, host: &str, attempt: usize| {
+            conflict_copy_path(
+                &MatchablePath::from(path),
+                host,
+                &UtcMillis::from(MAY_FIRST),
+                attempt,
+            )
+            .to_serialized_string()
+        };
+
+        assert_eq!(
+            "docs/report (conflicted copy from laptop 2024-05-01).pdf",
+            copy("docs/report.pdf", "laptop", 1)
+        );
+        assert_eq!(
+            "archive.tar (conflicted copy from laptop 2024-05-01 2).gz",
+            copy("archive.tar.gz", "laptop", 2)
+        );
+        assert_eq!(
+            ".bashrc (conflicted copy from pc-1 2024-05-01)",
+            copy(".bashrc", "pc/1", 0)
+        );
+        assert_eq!(
+            "README (conflicted copy from laptop 2024-05-01)",
+            copy("README", "laptop", 1)
+        );
+    }
+
+    #[test]
+    fn should_recognize_conflict_copies() {
+        let path = MatchablePath::from("docs/report.pdf");
+        let copy = conflict_copy_path(&path, "laptop", &UtcMillis::from(MAY_FIRST), 1);
+
+        assert!(is_conflict_copy(&copy));
+        assert!(!is_conflict_copy(&path));
+        assert!(!is_conflict_copy(&MatchablePath::from(
+            "conflicted copy from laptop/report.pdf"
+        )));
+    }
+}
@@ -44,6 +44,9 @@ pub struct WatchGroupConfigDto {
     pub exclude_dot_dirs: bool,
     /// for logging
     pub name: String,
+    /// higher priorities are synced first (see [`ServerWatchGroup::priority`])
+    #[serde(default)]
+    pub priority: i64,
 }
 
 fn default_exclude_dot_dirs() -> bool {
@@ -57,6 +60,41 @@ pub struct ClientDto {
     pub id: String,
     pub host_name: String,
     pub min_poll_interval_in_ms: u16,
+    /// None = not owned by any user
+    #[serde(default)]
+    pub owner_id: Option<i64>,
+    /// paused clients don't get any sync instructions
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// PUT /api/clients/{id}/pause & PUT /api/clients/{id}/watch-groups/{wg_id}/pause
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PauseDto {
+    pub paused: bool,
+}
+
+/// GET /api/clients/{id}/sync-blackouts - the client must not sync within these times (server
+/// local time), its syncs are paused until the window ends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncBlackoutDto {
+    pub id: i64,
+    /// 1 = monday ... 7 = sunday - the days the window starts on, empty for every day
+    pub weekdays: Vec<u8>,
+    /// `HH:MM` - an end at or before the start lies on the next day
+    pub start: String,
+    pub end: String,
+    pub note: Option<String>,
+}
+
+/// POST /api/clients/{id}/sync-blackouts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncBlackoutCreateDto {
+    #[serde(default)]
+    pub weekdays: Vec<u8>,
+    pub start: String,
+    pub end: String,
+    pub note: Option<String>,
 }
 
 /// PUT /api/clients/{id}
@@ -65,6 +103,153 @@ pub struct ClientUpdateDto {
     pub min_poll_interval_in_ms: u16,
 }
 
+/// POST /api/clients/{id}/claim - the client takes over another one (e.g. after reinstalling
+/// the OS)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientClaimDto {
+    pub old_client_id: String,
+}
+
+/// what a client took over from the one it claimed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientClaimedDto {
+    pub watch_groups: u64,
+    /// assignments the claiming client already had (same watch group or folder) - its own win
+    pub skipped_watch_groups: u64,
+    pub file_events: u64,
+}
+
+/// GET /api/clients/{id}/detail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientDetailDto {
+    pub client: ClientDto,
+    /// latest sync over all its watch groups - None if there's none on record
+    pub last_seen: Option<UtcMillis>,
+    pub watch_groups: Vec<ClientWatchGroupDetailDto>,
+    /// of the current month - None if the client never reported any
+    pub bandwidth_usage: Option<BandwidthUsageDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientWatchGroupDetailDto {
+    pub assignment: ClientWatchGroupDto,
+    pub last_synced_at: Option<UtcMillis>,
+}
+
+/// GET /api/clients/{id}/activity?hours=..&limit=..
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientActivityQueryDto {
+    /// how far back the bandwidth goes (default: 7 days)
+    pub hours: Option<u32>,
+    /// how many events (default: 50)
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientRecentActivityDto {
+    /// newest first
+    pub recent_events: Vec<ClientEventDto>,
+    /// daily buckets of the client's transfers (oldest first)
+    pub bandwidth: Vec<BandwidthBucketDto>,
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientEventDto {
+    pub watch_group_id: i64,
+    pub relative_path: String,
+    pub utc_millis: UtcMillis,
+    pub size_in_bytes: u64,
+    pub deleted: bool,
+}
+
+/// GET /api/clients/{id}/pending - instructions of the client's last sync per watch group,
+/// minus the up- / downloads done since
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PendingWorkDto {
+    pub watch_groups: Vec<PendingWatchGroupDto>,
+    /// sum of the pending up- and downloads
+    pub pending_bytes: u64,
+    /// measured on the client's recent uploads - None until one was large enough to tell
+    pub bytes_per_second: Option<u64>,
+    pub estimated_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingWatchGroupDto {
+    pub watch_group_id: i64,
+    pub synced_at: UtcMillis,
+    pub items: Vec<PendingItemDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingItemDto {
+    pub kind: PendingKind,
+    pub relative_path: String,
+    pub size_in_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingKind {
+    Upload,
+    Download,
+    /// the client still has a file deleted on the server
+    Delete,
+}
+
+/// file list of a client's latest sync of a watch group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientFilesDto {
+    pub reported_at: UtcMillis,
+    pub files: Vec<ClientFileDto>,
+}
+
+/// one file of a client's latest sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientFileDto {
+    pub file: FileDescription,
+    /// sha256 of the content - None as long as the client doesn't report it
+    pub sha256: Option<String>,
+}
+
+/// which clients have the latest version of one file - according to their latest sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStatusDto {
+    pub relative_path: String,
+    /// latest version on the server
+    pub size_in_bytes: u64,
+    pub utc_millis: UtcMillis,
+    pub deleted: bool,
+    pub clients: Vec<ClientFileStatusDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientFileStatusDto {
+    pub client_id: String,
+    pub host_name: String,
+    pub state: FileSyncState,
+    /// None if the client didn't sync the watch group yet
+    pub reported_at: Option<UtcMillis>,
+    /// the client's copy - None if it doesn't have the file
+    pub size_in_bytes: Option<u64>,
+    pub last_updated_utc_millis: Option<UtcMillis>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSyncState {
+    /// has the latest version (or doesn't have a deleted file)
+    UpToDate,
+    /// has an older version - or still has a deleted file
+    Outdated,
+    Missing,
+    /// has a newer version that isn't uploaded yet
+    Newer,
+    NotReported,
+}
+
 // api - client watch group assignments
 
 /// GET /api/clients/{id}/watch-groups → Vec<ClientWatchGroupDto>
@@ -75,6 +260,17 @@ pub struct ClientWatchGroupDto {
     pub path_to_monitor: String,
     pub exclude_dirs: Vec<String>,
     pub exclude_dot_dirs: bool,
+    #[serde(default)]
+    pub paused: bool,
+    /// last scan problem the client reported (e.g. too many files)
+    #[serde(default)]
+    pub scan_warning: Option<String>,
+}
+
+/// POST /sys/scan-warning/{wg_id} - None = the last scan was fine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanWarningDto {
+    pub message: Option<String>,
 }
 
 /// POST /api/clients/{id}/watch-groups
@@ -94,12 +290,54 @@ pub struct ClientWatchGroupUpdateDto {
     pub exclude_dot_dirs: bool,
 }
 
+// api - registration templates
+
+/// GET /api/registration-templates → Vec<RegistrationTemplateDto>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationTemplateDto {
+    pub id: i64,
+    /// host names of new clients are matched against it (`*` and `?` wildcards)
+    pub host_pattern: String,
+    pub server_watch_group_id: i64,
+    pub server_watch_group_name: String,
+    pub path_to_monitor: String,
+    pub exclude_dirs: Vec<String>,
+    pub exclude_dot_dirs: bool,
+    /// None = default poll interval
+    pub min_poll_interval_in_ms: Option<u16>,
+}
+
+/// POST /api/registration-templates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationTemplateCreateDto {
+    pub host_pattern: String,
+    pub server_watch_group_id: i64,
+    pub path_to_monitor: String,
+    pub exclude_dirs: Vec<String>,
+    pub exclude_dot_dirs: bool,
+    pub min_poll_interval_in_ms: Option<u16>,
+}
+
 // api - server watch groups
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerWatchGroup {
     pub id: i64,
     pub name: String,
+    /// None = shared between all users
+    #[serde(default)]
+    pub owner_id: Option<i64>,
+    /// uploads get re-read after writing and compared to the transfer hash
+    #[serde(default)]
+    pub verify_writes: bool,
+    #[serde(default)]
+    pub durability: Durability,
+    /// clients sync watch groups with a higher priority first (default 0)
+    #[serde(default)]
+    pub priority: i64,
+    /// server-local dir accepted files are mirrored into (e.g. a web root)
+    #[serde(default)]
+    pub publish_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,22 +345,1157 @@ pub struct WatchGroupNameDto {
     pub name: String,
 }
 
-// monitoring
+/// GET /api/watch-groups/{id}/detail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchGroupDetailDto {
+    pub group: ServerWatchGroup,
+    pub members: Vec<WatchGroupMemberDto>,
+    /// volume the files are stored on - None until the server measured it
+    #[serde(default)]
+    pub upload_disk: Option<UploadDiskDto>,
+}
+
+/// a client the watch group is assigned to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchGroupMemberDto {
+    pub client_id: String,
+    pub host_name: String,
+    pub path_to_monitor: String,
+    pub paused: bool,
+    pub scan_warning: Option<String>,
+    /// last file list the client reported for the watch group - None if it never synced it
+    pub last_synced_at: Option<UtcMillis>,
+}
 
+// api - webhooks
+
+/// GET /api/watch-groups/{id}/webhooks - the secret is never sent back
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DataPoint {
-    pub x: String,
-    pub y: f32,
+pub struct WebhookDto {
+    pub id: i64,
+    pub url: String,
+    pub signed: bool,
+}
+
+/// POST /api/watch-groups/{id}/webhooks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookCreateDto {
+    pub url: String,
+    /// payloads are signed with HMAC-SHA256 (see `WEBHOOK_SIGNATURE_HEADER_KEY`)
+    pub secret: Option<String>,
+}
+
+/// body posted to the webhook url for every change / delete event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookEventDto {
+    pub event_id: String,
+    pub watch_group_id: i64,
+    pub relative_path: String,
+    /// "change" or "delete"
+    pub event_type: String,
+    pub size_in_bytes: u64,
+    pub utc_millis: UtcMillis,
+    pub client_id: Option<String>,
+    pub client_host: Option<String>,
+}
+
+// api - share links
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareLinkKind {
+    /// anyone with the link may upload files into the folder (never overwriting)
+    Inbox,
+}
+
+impl ShareLinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShareLinkKind::Inbox => "inbox",
+        }
+    }
+}
+
+impl TryFrom<&str> for ShareLinkKind {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "inbox" => Ok(ShareLinkKind::Inbox),
+            _ => Err(format!("Could not parse '{}'", value)),
+        }
+    }
+}
+
+/// GET /api/watch-groups/{id}/share-links - the link itself is `/inbox/{token}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkDto {
+    pub token: String,
+    pub kind: ShareLinkKind,
+    /// relative to the watch group root ("" = root)
+    pub folder: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// POST /api/watch-groups/{id}/share-links
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkCreateDto {
+    pub kind: ShareLinkKind,
+    pub folder: String,
+}
+
+/// POST /api/sign-download
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignDownloadDto {
+    pub watch_group_id: i64,
+    /// unix-delimited, relative to the watch group root
+    pub path: String,
+    /// lifetime of the link - server default if None
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
+}
+
+/// reply of POST /api/sign-download - anyone with the url can download the file until it expires
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedDownloadDto {
+    /// relative to the server - `/signed/{wg_id}?path=..&expires=..&sig=..`
+    pub url: String,
+    pub expires: UtcMillis,
+}
+
+// api - users
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDto {
+    pub id: i64,
+    pub name: String,
+    pub role: UserRole,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    /// may change watch groups, clients, users, ...
+    Admin,
+    /// may only view configs, events and monitoring
+    ReadOnly,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::ReadOnly => "read_only",
+        }
+    }
+}
+
+impl TryFrom<&str> for UserRole {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "admin" => Ok(UserRole::Admin),
+            "read_only" => Ok(UserRole::ReadOnly),
+            _ => Err(format!("Could not parse '{}'", value)),
+        }
+    }
+}
+
+/// PUT /api/users/{id}/role
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserRoleDto {
+    pub role: UserRole,
+}
+
+/// POST /api/users/{id}/token → plain token, only returned once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserTokenDto {
+    pub token: String,
+}
+
+/// POST /api/users, PUT /api/users/{id}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserNameDto {
+    pub name: String,
+}
+
+// api - ui settings
+
+/// GET / PUT /api/settings - per account (each user, the bootstrap admin token)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiSettingsDto {
+    pub theme: Theme,
+    pub density: TableDensity,
+    /// where /app/ leads to on desktops
+    pub default_page: DefaultPage,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Dark, Theme::Light];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+}
+
+impl TryFrom<&str> for Theme {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Theme::ALL
+            .into_iter()
+            .find(|t| t.as_str() == value)
+            .ok_or_else(|| format!("Unknown theme '{value}'"))
+    }
+}
+
+/// spacing of tables, cards and buttons
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableDensity {
+    #[default]
+    Comfortable,
+    Compact,
+}
+
+impl TableDensity {
+    pub const ALL: [TableDensity; 2] = [TableDensity::Comfortable, TableDensity::Compact];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TableDensity::Comfortable => "comfortable",
+            TableDensity::Compact => "compact",
+        }
+    }
+}
+
+impl TryFrom<&str> for TableDensity {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        TableDensity::ALL
+            .into_iter()
+            .find(|d| d.as_str() == value)
+            .ok_or_else(|| format!("Unknown density '{value}'"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultPage {
+    #[default]
+    Clients,
+    WatchGroups,
+    Links,
+    Monitor,
+    Search,
+}
+
+impl DefaultPage {
+    pub const ALL: [DefaultPage; 5] = [
+        DefaultPage::Clients,
+        DefaultPage::WatchGroups,
+        DefaultPage::Links,
+        DefaultPage::Monitor,
+        DefaultPage::Search,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DefaultPage::Clients => "clients",
+            DefaultPage::WatchGroups => "watch_groups",
+            DefaultPage::Links => "links",
+            DefaultPage::Monitor => "monitor",
+            DefaultPage::Search => "search",
+        }
+    }
+
+    /// route of the web app
+    pub fn path(&self) -> &'static str {
+        match self {
+            DefaultPage::Clients => "/app/clients",
+            DefaultPage::WatchGroups => "/app/watch-groups",
+            DefaultPage::Links => "/app/links",
+            DefaultPage::Monitor => "/app/monitor",
+            DefaultPage::Search => "/app/search",
+        }
+    }
+}
+
+impl TryFrom<&str> for DefaultPage {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        DefaultPage::ALL
+            .into_iter()
+            .find(|p| p.as_str() == value)
+            .ok_or_else(|| format!("Unknown page '{value}'"))
+    }
+}
+
+/// PUT /api/clients/{id}/owner, PUT /api/watch-groups/{id}/owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnerDto {
+    pub owner_id: Option<i64>,
+}
+
+/// PUT /api/watch-groups/{id}/verify-writes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyWritesDto {
+    pub verify_writes: bool,
+}
+
+/// how hard uploads are pushed to disk before they count as stored - stronger levels survive a
+/// power loss, at the cost of throughput
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Durability {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "fsync-file")]
+    FsyncFile,
+    #[serde(rename = "fsync-file+dir")]
+    FsyncFileAndDir,
+}
+
+impl Durability {
+    pub const ALL: [Durability; 3] = [
+        Durability::None,
+        Durability::FsyncFile,
+        Durability::FsyncFileAndDir,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Durability::None => "none",
+            Durability::FsyncFile => "fsync-file",
+            Durability::FsyncFileAndDir => "fsync-file+dir",
+        }
+    }
+
+    /// the trade-off - listed in GET /api/capabilities
+    pub fn description(&self) -> &'static str {
+        match self {
+            Durability::None => {
+                "The OS writes uploads back when it likes - fastest, but a power loss can lose \
+                 uploads clients were told are stored"
+            }
+            Durability::FsyncFile => {
+                "The content is flushed to disk before the upload is moved into place - costs a \
+                 disk round trip per upload; after a power loss the file may still show up under \
+                 its old name or version"
+            }
+            Durability::FsyncFileAndDir => {
+                "Additionally the directory is flushed after the move, so the new file survives a \
+                 power loss too - slowest, noticeably with many small files"
+            }
+        }
+    }
+
+    pub fn syncs_file(&self) -> bool {
+        *self != Durability::None
+    }
+
+    pub fn syncs_dir(&self) -> bool {
+        *self == Durability::FsyncFileAndDir
+    }
+}
+
+impl TryFrom<&str> for Durability {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Durability::ALL
+            .into_iter()
+            .find(|d| d.as_str() == value)
+            .ok_or_else(|| format!("Unknown durability '{value}'"))
+    }
+}
+
+/// PUT /api/watch-groups/{id}/durability
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DurabilityDto {
+    pub durability: Durability,
+}
+
+/// a durability level and what it costs - see [`Durability`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DurabilityLevelDto {
+    pub name: String,
+    pub description: String,
+}
+
+/// PUT /api/watch-groups/{id}/publish - None stops publishing (the mirrored files are kept)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishTargetDto {
+    pub publish_path: Option<String>,
+}
+
+/// PUT /api/watch-groups/{id}/priority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityDto {
+    pub priority: i64,
+}
+
+/// PUT /api/watch-groups/{id}/mass-change-guard - None falls back to the server default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MassChangeGuardDto {
+    /// share of the watch group's files one client may change or delete within the window -
+    /// 0 turns the guard off
+    pub percent: Option<u32>,
+    pub window_minutes: Option<u32>,
+    /// bursts touching fewer files never count (small watch groups)
+    pub min_files: Option<u32>,
+}
+
+/// GET /api/watch-groups/{id}/mass-change-guard - the thresholds in effect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassChangeGuardSettingsDto {
+    pub configured: MassChangeGuardDto,
+    pub percent: u32,
+    pub window_minutes: u32,
+    pub min_files: u32,
+}
+
+/// GET /api/admin/mass-changes - a burst the guard stopped by pausing the client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MassChangeAlertDto {
+    pub client_id: String,
+    pub client_host: Option<String>,
+    pub watch_group_id: i64,
+    /// distinct files changed / deleted within the window
+    pub changed: u64,
+    pub deleted: u64,
+    pub files_in_watch_group: u64,
+    pub percent: u32,
+    pub window_minutes: u32,
+    pub detected_at: UtcMillis,
+    /// false if the client could not be paused (e.g. deregistered meanwhile)
+    pub paused: bool,
+}
+
+/// GET /api/admin/pending-deletes - a delete of a client waiting for its grace period, the file
+/// is kept (and hidden from sync) until `remove_at`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingDeleteDto {
+    pub id: i64,
+    pub watch_group_id: i64,
+    pub relative_path: String,
+    pub client_id: String,
+    pub client_host: Option<String>,
+    pub requested_at: UtcMillis,
+    pub remove_at: UtcMillis,
+}
+
+// api - manifest (offline seeding)
+
+/// GET /api/watch-groups/{id}/manifest - what a copied disk has to contain to count as synced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestDto {
+    pub watch_group_id: i64,
+    pub watch_group_name: String,
+    pub created: UtcMillis,
+    pub files: Vec<ManifestEntryDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntryDto {
+    pub path: MatchablePath,
+    pub size_in_bytes: u64,
+    /// hex encoded
+    pub sha256: String,
+    /// modification time the server knows the file by
+    pub utc_millis: UtcMillis,
+}
+
+// api - stats
+
+/// reply of POST /sys/upload/bulk/{wg_id}
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkUploadResultDto {
+    pub stored: Vec<String>,
+    pub rejected: Vec<BulkUploadRejectionDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkUploadRejectionDto {
+    pub relative_path: String,
+    /// same reply as for a single upload (e.g. "not latest")
+    pub reason: String,
+}
+
+/// GET /api/stats/{wg_id}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchGroupStatsDto {
+    /// files currently present (latest event is not a delete)
+    pub file_count: u64,
+    pub total_bytes: u64,
+    pub largest_files: Vec<FileSizeDto>,
+    pub most_active_clients: Vec<ClientActivityDto>,
+    /// newest day first
+    pub events_per_day: Vec<DailyEventCountDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSizeDto {
+    pub relative_path: String,
+    pub size_in_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientActivityDto {
+    pub client_id: String,
+    pub host_name: String,
+    pub event_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyEventCountDto {
+    /// YYYY-MM-DD (UTC)
+    pub day: String,
+    pub changes: u64,
+    pub deletes: u64,
+}
+
+/// GET /api/stats/bandwidth?hours=..&bucket_hours=..
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BandwidthQueryDto {
+    /// how far back (default: 7 days)
+    pub hours: Option<u32>,
+    /// width of a bucket (default: 1)
+    pub bucket_hours: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthStatsDto {
+    pub bucket_hours: u32,
+    /// oldest bucket first - one entry per bucket, client and watch group with traffic
+    pub buckets: Vec<BandwidthBucketDto>,
+}
+
+/// bytes the client sent (uploaded) to / received (downloaded) from the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthBucketDto {
+    pub start_utc_millis: u64,
+    pub client_id: String,
+    /// None for deregistered clients
+    pub host_name: Option<String>,
+    pub watch_group_id: i64,
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+}
+
+/// GET /api/reports/top-files/{wg_id}?limit=..
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopFilesQueryDto {
+    /// default: 50 (max 500)
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopFilesReportDto {
+    /// of all files currently present
+    pub total_bytes: u64,
+    /// largest first
+    pub files: Vec<TopFileDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopFileDto {
+    pub relative_path: String,
+    pub size_in_bytes: u64,
+    pub last_changed_utc_millis: u64,
+}
+
+/// GET /api/reports/growth/{wg_id}?days=..
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GrowthQueryDto {
+    /// how far back (default: 90, max 3650)
+    pub days: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrowthReportDto {
+    /// oldest day first - one entry per day (also without events)
+    pub days: Vec<StorageDayDto>,
+}
+
+/// state of the watch group at the end of the day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageDayDto {
+    /// YYYY-MM-DD (UTC)
+    pub day: String,
+    pub start_utc_millis: u64,
+    pub total_bytes: u64,
+    pub file_count: u64,
+}
+
+/// GET /sys/bandwidth-usage?month=YYYY-MM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthUsageQueryDto {
+    pub month: String,
+}
+
+/// POST /sys/bandwidth-usage - bytes transferred since the last report, as counted by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthUsageReportDto {
+    /// YYYY-MM (UTC)
+    pub month: String,
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+    /// caps of the client's config.yaml (0 = none)
+    pub soft_cap_bytes: u64,
+    pub hard_cap_bytes: u64,
+}
+
+/// everything a client transferred in a month - reply of GET / POST /sys/bandwidth-usage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BandwidthUsageDto {
+    /// YYYY-MM (UTC)
+    pub month: String,
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+    /// 0 = none - past the soft cap the client warns, past the hard cap it stops syncing
+    pub soft_cap_bytes: u64,
+    pub hard_cap_bytes: u64,
+}
+
+impl BandwidthUsageDto {
+    pub fn total_bytes(&self) -> u64 {
+        self.uploaded_bytes + self.downloaded_bytes
+    }
+}
+
+/// GET /api/search?q=
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHitDto {
+    pub watch_group_id: i64,
+    pub watch_group_name: String,
+    pub relative_path: String,
+    /// matching excerpt of the file content (only for content searches)
+    pub snippet: Option<String>,
+    /// file only exists in the history (latest event is a delete)
+    pub deleted: bool,
+}
+
+/// GET /api/history/{wg_id}?path=
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileVersionDto {
+    /// id of the file event that produced this version
+    pub event_id: String,
+    pub utc_millis: UtcMillis,
+    pub size_in_bytes: u64,
+    pub deleted: bool,
+    pub client_host: Option<String>,
+    /// the server only keeps older versions of small files
+    pub content_available: bool,
+}
+
+/// GET /api/diff/{wg_id}?path=&from=&to=
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffDto {
+    pub unified: String,
+    /// old and new version next to each other
+    pub rows: Vec<DiffRowDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffRowDto {
+    pub old: Option<DiffLineDto>,
+    pub new: Option<DiffLineDto>,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLineDto {
+    /// 1-based
+    pub number: usize,
+    pub text: String,
+}
+
+/// GET /api/preview/{wg_id}?path= - the beginning of a text file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePreviewDto {
+    pub content: String,
+    /// what the content was decoded from, e.g. "utf-8", "utf-16le" or "iso-8859-1"
+    pub encoding: String,
+    /// how to render the content, derived from the extension (e.g. "markdown", "csv", "rust")
+    pub language: Option<String>,
+    /// the file goes on after `content`
+    pub truncated: bool,
+    pub size_in_bytes: u64,
+}
+
+/// POST /api/note/{wg_id} - replaces (or extends) a text file in the watch group root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteDto {
+    /// file name within the watch group root, `notes.txt` if missing
+    pub name: Option<String>,
+    pub text: String,
+    /// add `text` as a new line at the end instead of replacing the file
+    #[serde(default)]
+    pub append: bool,
+}
+
+/// POST /api/admin/prune and /api/admin/resync
+///
+/// without `confirmation_token` nothing changes - the response lists the affected files
+/// and carries the token to repeat the request with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkPathOperationDto {
+    pub watch_group_id: i64,
+    /// unix-delimited directory (matched by whole path segments)
+    pub path_prefix: String,
+    pub confirmation_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkPathOperationResultDto {
+    pub affected_paths: Vec<String>,
+    /// only set on the unconfirmed request
+    pub confirmation_token: Option<String>,
+    pub executed: bool,
+}
+
+/// POST /api/admin/copy and /api/admin/move
+///
+/// files keep their relative path - existing files in the target are replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferPathDto {
+    pub source_watch_group_id: i64,
+    pub target_watch_group_id: i64,
+    /// unix-delimited file or directory (matched by whole path segments)
+    pub path_prefix: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferResultDto {
+    pub transferred_paths: Vec<String>,
+}
+
+/// POST /api/admin/import - takes over a directory on the server previously synced by another
+/// tool (Syncthing, rsync, ...) without transferring the files again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportDto {
+    /// absolute directory on the server - outside of the watch groups
+    pub source_path: String,
+    pub watch_group_id: i64,
+    #[serde(default)]
+    pub mode: ImportMode,
+    /// only report what would be imported
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// hard links (copies across file systems) - the source stays untouched
+    #[default]
+    Link,
+    Copy,
+    /// the source files are removed once imported
+    Move,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResultDto {
+    pub imported: Vec<String>,
+    /// already in the watch group with the same content
+    pub unchanged: Vec<String>,
+    /// a different file is in the watch group already - left alone
+    pub conflicts: Vec<String>,
+    pub imported_bytes: u64,
+    /// hashes of the imported and unchanged files - clients with a copy of the directory seed
+    /// from it (`client seed`) instead of downloading everything
+    pub manifest: ManifestDto,
+}
+
+/// POST /api/admin/db/backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbBackupDto {
+    pub file_name: String,
+    pub size_in_bytes: u64,
+}
+
+/// GET /api/admin/backups
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFileDto {
+    pub name: String,
+    pub size_in_bytes: u64,
+    /// downloads are decrypted by the server (with its `BACKUP_ENCRYPTION_KEY`)
+    pub encrypted: bool,
+}
+
+/// GET /api/admin/db/check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbCheckDto {
+    pub ok: bool,
+    /// as reported by `PRAGMA integrity_check`
+    pub problems: Vec<String>,
+}
+
+/// GET / POST /api/admin/scrub - files on disk compared to the latest file events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrubReportDto {
+    pub finished_at: UtcMillis,
+    pub checked_files: usize,
+    /// true if the history was corrected to match the disk
+    pub repaired: bool,
+    pub findings: Vec<ScrubFindingDto>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScrubFindingDto {
+    pub watch_group_id: i64,
+    pub relative_path: String,
+    pub kind: ScrubFindingKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScrubFindingKind {
+    /// on disk without any (live) file event
+    Untracked { size_in_bytes: u64 },
+    /// the latest file event says it exists, but it's not on disk
+    Missing,
+    /// size on disk differs from the latest file event
+    SizeMismatch { expected: u64, actual: u64 },
+    /// content differs from the sha256 verified on upload - only checked on request
+    HashMismatch {
+        size_in_bytes: u64,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// GET /api/admin/tasks - a background task of the server (backups, scrub, monitor, ...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskStatusDto {
+    pub name: String,
+    pub state: TaskState,
+    /// start of the last run - None for tasks that don't report their runs
+    pub last_run: Option<UtcMillis>,
+    /// when the task runs next (or is restarted after a panic)
+    pub next_run: Option<UtcMillis>,
+    /// panic or failure of the last run - None once a run succeeds again
+    pub last_error: Option<String>,
+    /// restarts after panics since the server started
+    pub restarts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    /// panicked - waiting for the restart
+    Restarting,
+    /// returned - one-off tasks and tasks turned off by config
+    Finished,
+}
+
+/// jobs the server runs on a cron schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledJob {
+    /// archives / snapshots of the watch groups and a copy of the database
+    Backup,
+    /// `VACUUM` of the database
+    Compaction,
+    /// compares the files on disk with the history
+    Scrub,
+    /// drops file lists of clients that stopped reporting
+    Gc,
+}
+
+impl ScheduledJob {
+    pub const ALL: [ScheduledJob; 4] = [
+        ScheduledJob::Backup,
+        ScheduledJob::Compaction,
+        ScheduledJob::Scrub,
+        ScheduledJob::Gc,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduledJob::Backup => "backup",
+            ScheduledJob::Compaction => "compaction",
+            ScheduledJob::Scrub => "scrub",
+            ScheduledJob::Gc => "gc",
+        }
+    }
+}
+
+impl TryFrom<&str> for ScheduledJob {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        ScheduledJob::ALL
+            .into_iter()
+            .find(|j| j.as_str() == value)
+            .ok_or_else(|| format!("Unknown job '{value}'"))
+    }
+}
+
+/// GET /api/admin/schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleEntryDto {
+    pub job: ScheduledJob,
+    /// `minute hour day-of-month month day-of-week` in the server's local time - None = off
+    pub expression: Option<String>,
+    pub next_run: Option<UtcMillis>,
+}
+
+/// PUT /api/admin/schedule/{job} - None turns the job off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleUpdateDto {
+    pub expression: Option<String>,
+}
+
+/// GET / PUT /api/admin/chaos - faults injected into client traffic (`/sys/*` and grpc) of servers
+/// built with the `chaos` feature - probabilities from 0 (never, the default) to 1 (always)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChaosConfigDto {
+    /// reply 500 instead of handling the request
+    pub error_probability: f64,
+    /// hold the request back for `delay_ms`
+    pub delay_probability: f64,
+    pub delay_ms: u64,
+    /// cut the request or reply body off at a random byte
+    pub truncate_probability: f64,
+    /// per chunk of a request or reply body
+    pub drop_chunk_probability: f64,
+}
+
+// capabilities
+
+/// GET /api/capabilities - what this server offers and where it listens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesDto {
+    pub version: String,
+    /// e.g. `https://0.0.0.0:3000`, `https://[::]:3000` or `unix:/run/rust-file-sync.sock`
+    pub endpoints: Vec<String>,
+    pub tls: bool,
+    /// over udp on the ports of the tcp endpoints
+    pub http3: bool,
+    /// what the durability levels of watch groups trade off
+    #[serde(default)]
+    pub durability_levels: Vec<DurabilityLevelDto>,
+}
+
+// replication (primary → replica)
+
+/// GET /api/replication/events?after=<seq>&limit=<n> - the event stream replicas pull
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationEventsDto {
+    /// seq of the newest event on the primary - tells replicas how far behind they are
+    pub latest_seq: i64,
+    /// oldest first
+    pub events: Vec<ReplicationEventDto>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationEventDto {
+    /// position in the event stream - ascending, but with gaps
+    pub seq: i64,
+    pub event_id: String,
+    pub watch_group_id: i64,
+    pub watch_group_name: String,
+    pub relative_path: String,
+    /// "change" or "delete"
+    pub event_type: String,
+    pub size_in_bytes: u64,
+    pub utc_millis: UtcMillis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicationRole {
+    Primary,
+    Replica,
+}
+
+/// GET /api/replication
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationStatusDto {
+    pub role: ReplicationRole,
+    /// set on replicas
+    pub primary_url: Option<String>,
+    /// newest event of this server's own stream (primary) / of the primary's stream applied
+    /// here (replica)
+    pub applied_seq: i64,
+    /// replicas: newest event of the primary as of the last pull
+    pub primary_seq: Option<i64>,
+    pub last_pull_at: Option<UtcMillis>,
+    /// replicas: why the last pull failed - None once one succeeds again
+    pub last_error: Option<String>,
+    /// replicas: files downloaded from the primary since the start
+    pub files_pulled: u64,
+}
+
+// event export (NDJSON / NATS)
+
+/// one line of GET /api/export/events (and one message of the NATS sink)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportEventDto {
+    /// position in the event log - pass the last one as `since` to continue
+    pub seq: i64,
+    pub event_id: String,
+    pub watch_group_id: i64,
+    pub relative_path: String,
+    /// "change" or "delete"
+    pub event_type: String,
+    pub size_in_bytes: u64,
+    pub utc_millis: UtcMillis,
+    pub client_id: String,
+    pub client_host: Option<String>,
+}
+
+/// PUT /api/export/checkpoints/{name}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportCheckpointUpdateDto {
+    /// seq of the last event the consumer processed
+    pub seq: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportCheckpointDto {
+    pub name: String,
+    pub seq: i64,
+    pub updated_at: UtcMillis,
+}
+
+/// GET /api/export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportStatusDto {
+    /// seq of the newest event - consumers are behind by the events in between
+    pub latest_seq: i64,
+    pub checkpoints: Vec<ExportCheckpointDto>,
+}
+
+// bench
+
+/// POST /api/bench/upload - nothing is stored, `duration_millis` is measured by the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchUploadDto {
+    pub received_bytes: u64,
+    pub duration_millis: u64,
+}
+
+/// GET /api/bench/download
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchDownloadQueryDto {
+    pub bytes: u64,
+}
+
+// monitoring
+
+/// GET /api/monitor - the samples between `from` and `to` (unix millis, default: all that are
+/// kept) aggregated to buckets of `resolution` seconds; without a resolution the span is split
+/// into `points` buckets (default 300)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonitorQueryDto {
+    pub points: Option<usize>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub resolution: Option<u64>,
+}
+
+/// one measurement of the server - usages in %, free disk space in GiB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonitorSampleDto {
+    pub sys_mem: f32,
+    pub app_mem: f32,
+    pub sys_cpu: f32,
+    pub app_cpu: f32,
+    pub disk_used: f32,
+    pub disk_free: f32,
+}
+
+/// the samples of `[start_utc_millis, start_utc_millis + resolution)` - averaged and their peaks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorBucketDto {
+    pub start_utc_millis: u64,
+    pub avg: MonitorSampleDto,
+    pub max: MonitorSampleDto,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorData {
-    pub sys_mem: Vec<DataPoint>,
-    pub app_mem: Vec<DataPoint>,
-    pub sys_cpu: Vec<DataPoint>,
-    pub app_cpu: Vec<DataPoint>,
-    pub disk_used: Vec<DataPoint>,
-    pub disk_free: Vec<DataPoint>,
+    /// width of the buckets in seconds
+    #[serde(default)]
+    pub resolution: u64,
+    /// oldest first, buckets without samples are left out
+    #[serde(default)]
+    pub buckets: Vec<MonitorBucketDto>,
+    /// None until the server measured it for the first time
+    #[serde(default)]
+    pub upload_disk: Option<UploadDiskDto>,
+    /// None for servers that don't limit concurrent transfers
+    #[serde(default)]
+    pub transfers: Option<TransfersDto>,
+}
+
+/// volume holding the synced files - uploads are refused below `min_free_bytes`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadDiskDto {
+    pub free_bytes: u64,
+    pub total_bytes: u64,
+    pub min_free_bytes: u64,
+}
+
+/// uploads / downloads in flight - beyond the limits (global or per client) the server answers
+/// 503, `rejected` counts those since start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransfersDto {
+    pub uploads: usize,
+    pub max_uploads: usize,
+    pub downloads: usize,
+    pub max_downloads: usize,
+    pub max_per_client: usize,
+    pub rejected: u64,
+}
+
+/// durations of the most recent operations of a kind (nearest-rank percentiles)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingPercentilesDto {
+    /// operations the percentiles are taken over
+    pub count: usize,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+impl TimingPercentilesDto {
+    pub fn of(samples_ms: impl IntoIterator<Item = u64>) -> Self {
+        let mut sorted: Vec<u64> = samples_ms.into_iter().collect();
+        sorted.sort_unstable();
+        let percentile = |p: usize| match sorted.len() {
+            0 => 0,
+            len => sorted[(len * p).div_ceil(100).max(1) - 1],
+        };
+        TimingPercentilesDto {
+            count: sorted.len(),
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: sorted.last().copied().unwrap_or(0),
+        }
+    }
 }
 
 // links
@@ -175,3 +1548,270 @@ impl LinkDto {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    /// clients and servers of different versions talk to each other - field names are the contract
+    fn assert_schema<T: Serialize + DeserializeOwned>(value: &T, expected: serde_json::Value) {
+        let serialized = serde_json::to_value(value).unwrap();
+        assert_eq!(expected, serialized);
+        let round_trip: T = serde_json::from_value(serialized.clone()).unwrap();
+        assert_eq!(serialized, serde_json::to_value(round_trip).unwrap());
+    }
+
+    #[test]
+    fn should_keep_sys_schema() {
+        assert_schema(
+            &WatchConfigDto {
+                min_poll_interval_in_ms: 5000,
+                watch_groups: HashMap::from([(
+                    1,
+                    WatchGroupConfigDto {
+                        path_to_monitor: "./sync".to_string(),
+                        exclude_dirs: vec!["target".to_string()],
+                        exclude_dot_dirs: true,
+                        name: "main".to_string(),
+                        priority: 2,
+                    },
+                )]),
+            },
+            json!({
+                "min_poll_interval_in_ms": 5000,
+                "watch_groups": {"1": {
+                    "path_to_monitor": "./sync",
+                    "exclude_dirs": ["target"],
+                    "exclude_dot_dirs": true,
+                    "name": "main",
+                    "priority": 2
+                }}
+            }),
+        );
+        assert_schema(&ScanWarningDto { message: None }, json!({"message": null}));
+        assert_schema(
+            &ManifestEntryDto {
+                path: MatchablePath::from("dir/a.txt"),
+                size_in_bytes: 3,
+                sha256: "ab".to_string(),
+                utc_millis: UtcMillis::from(7),
+            },
+            json!({
+                "path": "dir/a.txt",
+                "size_in_bytes": 3,
+                "sha256": "ab",
+                "utc_millis": 7
+            }),
+        );
+    }
+
+    #[test]
+    fn should_keep_api_schema() {
+        assert_schema(
+            &ClientWatchGroupUpdateDto {
+                path_to_monitor: "./sync".to_string(),
+                exclude_dirs: vec![],
+                exclude_dot_dirs: false,
+            },
+            json!({"path_to_monitor": "./sync", "exclude_dirs": [], "exclude_dot_dirs": false}),
+        );
+        assert_schema(
+            &TransferPathDto {
+                source_watch_group_id: 1,
+                target_watch_group_id: 2,
+                path_prefix: "photos".to_string(),
+            },
+            json!({"source_watch_group_id": 1, "target_watch_group_id": 2, "path_prefix": "photos"}),
+        );
+        assert_schema(
+            &ImportDto {
+                source_path: "/srv/syncthing/photos".to_string(),
+                watch_group_id: 2,
+                mode: ImportMode::Move,
+                dry_run: true,
+            },
+            json!({
+                "source_path": "/srv/syncthing/photos",
+                "watch_group_id": 2,
+                "mode": "move",
+                "dry_run": true
+            }),
+        );
+        assert_schema(
+            &MonitorQueryDto {
+                points: Some(10),
+                from: Some(1_000),
+                to: None,
+                resolution: Some(60),
+            },
+            json!({"points": 10, "from": 1000, "to": null, "resolution": 60}),
+        );
+        assert_schema(
+            &TaskStatusDto {
+                name: "scrub".to_string(),
+                state: TaskState::Restarting,
+                last_run: Some(UtcMillis::from(1000)),
+                next_run: None,
+                last_error: Some("panicked: boom".to_string()),
+                restarts: 2,
+            },
+            json!({
+                "name": "scrub",
+                "state": "restarting",
+                "last_run": 1000,
+                "next_run": null,
+                "last_error": "panicked: boom",
+                "restarts": 2
+            }),
+        );
+        assert_schema(
+            &ScheduleEntryDto {
+                job: ScheduledJob::Compaction,
+                expression: Some("30 3 * * 0".to_string()),
+                next_run: Some(UtcMillis::from(1000)),
+            },
+            json!({"job": "compaction", "expression": "30 3 * * 0", "next_run": 1000}),
+        );
+        assert_schema(
+            &ChaosConfigDto {
+                error_probability: 0.1,
+                delay_probability: 0.5,
+                delay_ms: 2000,
+                truncate_probability: 0.0,
+                drop_chunk_probability: 0.01,
+            },
+            json!({
+                "error_probability": 0.1,
+                "delay_probability": 0.5,
+                "delay_ms": 2000,
+                "truncate_probability": 0.0,
+                "drop_chunk_probability": 0.01
+            }),
+        );
+        assert_schema(
+            &MonitorBucketDto {
+                start_utc_millis: 60_000,
+                avg: MonitorSampleDto {
+                    sys_mem: 50.0,
+                    app_mem: 1.5,
+                    sys_cpu: 12.0,
+                    app_cpu: 0.5,
+                    disk_used: 40.0,
+                    disk_free: 100.0,
+                },
+                max: MonitorSampleDto {
+                    sys_mem: 50.0,
+                    app_mem: 1.5,
+                    sys_cpu: 12.0,
+                    app_cpu: 0.5,
+                    disk_used: 40.0,
+                    disk_free: 100.0,
+                },
+            },
+            json!({
+                "start_utc_millis": 60000,
+                "avg": {"sys_mem": 50.0, "app_mem": 1.5, "sys_cpu": 12.0, "app_cpu": 0.5, "disk_used": 40.0, "disk_free": 100.0},
+                "max": {"sys_mem": 50.0, "app_mem": 1.5, "sys_cpu": 12.0, "app_cpu": 0.5, "disk_used": 40.0, "disk_free": 100.0}
+            }),
+        );
+        assert_schema(
+            &DurabilityDto {
+                durability: Durability::FsyncFileAndDir,
+            },
+            json!({"durability": "fsync-file+dir"}),
+        );
+        assert_schema(
+            &BenchUploadDto {
+                received_bytes: 1024,
+                duration_millis: 3,
+            },
+            json!({"received_bytes": 1024, "duration_millis": 3}),
+        );
+        assert_schema(&BenchDownloadQueryDto { bytes: 5 }, json!({"bytes": 5}));
+        assert_schema(
+            &UiSettingsDto {
+                theme: Theme::Light,
+                density: TableDensity::Compact,
+                default_page: DefaultPage::WatchGroups,
+            },
+            json!({"theme": "light", "density": "compact", "default_page": "watch_groups"}),
+        );
+        assert_schema(
+            &CapabilitiesDto {
+                version: "0.1.20".to_string(),
+                endpoints: vec!["https://[::]:3000".to_string()],
+                tls: true,
+                http3: false,
+                durability_levels: vec![DurabilityLevelDto {
+                    name: "fsync-file".to_string(),
+                    description: "...".to_string(),
+                }],
+            },
+            json!({
+                "version": "0.1.20",
+                "endpoints": ["https://[::]:3000"],
+                "tls": true,
+                "http3": false,
+                "durability_levels": [{"name": "fsync-file", "description": "..."}]
+            }),
+        );
+    }
+
+    #[test]
+    fn should_accept_payloads_of_older_versions() {
+        let config: WatchGroupConfigDto = serde_json::from_value(json!({
+            "path_to_monitor": "./sync",
+            "exclude_dirs": [],
+            "name": "main"
+        }))
+        .unwrap();
+        assert!(config.exclude_dot_dirs);
+        assert_eq!(0, config.priority);
+
+        let client: ClientDto = serde_json::from_value(json!({
+            "id": "abc",
+            "host_name": "laptop",
+            "min_poll_interval_in_ms": 1000
+        }))
+        .unwrap();
+        assert_eq!(None, client.owner_id);
+        assert!(!client.paused);
+
+        let monitor: MonitorData = serde_json::from_value(json!({
+            "sys_mem": [], "app_mem": [], "sys_cpu": [],
+            "app_cpu": [], "disk_used": [], "disk_free": []
+        }))
+        .unwrap();
+        assert!(monitor.upload_disk.is_none());
+        assert!(monitor.transfers.is_none());
+
+        let watch_group: ServerWatchGroup =
+            serde_json::from_value(json!({"id": 1, "name": "main"})).unwrap();
+        assert_eq!(Durability::None, watch_group.durability);
+
+        let settings: UiSettingsDto = serde_json::from_value(json!({"theme": "light"})).unwrap();
+        assert_eq!(TableDensity::Comfortable, settings.density);
+    }
+
+    #[test]
+    fn should_take_nearest_rank_percentiles() {
+        let timings = TimingPercentilesDto::of((1..=100).rev());
+        assert_eq!(
+            TimingPercentilesDto {
+                count: 100,
+                p50_ms: 50,
+                p90_ms: 90,
+                p99_ms: 99,
+                max_ms: 100
+            },
+            timings
+        );
+        assert_eq!(7, TimingPercentilesDto::of([7]).p50_ms);
+        assert_eq!(
+            TimingPercentilesDto::default(),
+            TimingPercentilesDto::of([])
+        );
+    }
+}
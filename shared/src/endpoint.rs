@@ -1,5 +1,38 @@
 pub const CLIENT_HOST_HEADER_KEY: &str = "X-Client-Hostname";
 pub const CLIENT_ID_HEADER_KEY: &str = "X-Client-Id";
+/// cookie alternative to `Authorization: Bearer <token>` for browser frontends
+pub const API_TOKEN_COOKIE_KEY: &str = "api_token";
+/// set on sync replies if the client (or its watch group) was paused by an admin
+pub const SYNC_PAUSED_HEADER_KEY: &str = "X-Sync-Paused";
+/// set next to [`SYNC_PAUSED_HEADER_KEY`] during a sync blackout of the client - utc millis of
+/// its end
+pub const SYNC_PAUSED_UNTIL_HEADER_KEY: &str = "X-Sync-Paused-Until";
+/// set on sync replies - total size of the files the client is told to download (lets it check
+/// its free disk space up front)
+pub const DOWNLOAD_BYTES_HEADER_KEY: &str = "X-Download-Bytes";
+/// set on downloads - utc millis of the file's latest event, the client stamps the downloaded
+/// file with it (a download must not look newer than edits made elsewhere meanwhile)
+pub const FILE_UTC_MILLIS_HEADER_KEY: &str = "X-File-Utc-Millis";
+/// set on downloads - hostname of the client the file's latest version came from (names
+/// conflicted copies)
+pub const FILE_CLIENT_HOST_HEADER_KEY: &str = "X-File-Client-Host";
+/// set on uploads resolving a keep-both conflict - utc millis of the server's version the client
+/// kept as conflicted copy; the upload replaces exactly that version (or is refused as not latest)
+pub const CONFLICT_KEPT_HEADER_KEY: &str = "X-Conflict-Kept-Utc-Millis";
+/// first entry of a snapshot archive - json list of the archived files
+pub const SNAPSHOT_MANIFEST_ENTRY: &str = "manifest.json";
+/// files of a snapshot archive are stored below this dir
+pub const SNAPSHOT_FILES_DIR: &str = "files";
+/// start of the reply (422) to uploads rejected by a content scan - the server quarantined them
+pub const QUARANTINED_REPLY_PREFIX: &str = "quarantined";
+/// `sha256=<hex hmac of the body>` on webhook posts with a secret
+pub const WEBHOOK_SIGNATURE_HEADER_KEY: &str = "X-Signature-256";
+/// mDNS service of servers on the LAN (`MDNS_DISCOVERY=true`) - listed by `client init`
+pub const MDNS_SERVICE_TYPE: &str = "_rustfilesync._tcp.local.";
+/// TXT record of the mDNS service - version of the server
+pub const MDNS_VERSION_KEY: &str = "version";
+/// TXT record of the mDNS service - `http` or `https`
+pub const MDNS_SCHEME_KEY: &str = "scheme";
 
 pub enum ServerEndpoint {
     Hello,
@@ -10,9 +43,33 @@ pub enum ServerEndpoint {
     /// SYS
     Sync,
     Upload,
+    /// many files at once as tar archive (initial sync)
+    UploadBulk,
     Download,
+    /// current state of a watch group as tar archive (initial sync)
+    DownloadSnapshot,
     Delete,
     Config,
+    /// scan of a watch group aborted by (or close to) the client's scan limits
+    ScanWarning,
+    /// paths, sizes and hashes of the current files of a watch group (`client verify`)
+    Manifest,
+    /// bytes the client transferred this month (metered connections with bandwidth caps)
+    BandwidthUsage,
+    /// sync / upload / download / delete as grpc service (clients with `transport: grpc`)
+    Grpc,
+
+    /// public upload form / target of an inbox share link
+    Inbox,
+    /// folder of a watch group as zip archive (for browsers - authenticated like the api)
+    DownloadZip,
+    /// single file behind a time-limited signature (see [`ServerEndpoint::ApiSignDownload`])
+    SignedDownload,
+
+    /// WebDAV view of a watch group (root collection)
+    Dav,
+    /// WebDAV view of a watch group (file or collection below the root)
+    DavPath,
 
     /// PWA
     ServePWA,
@@ -27,20 +84,158 @@ pub enum ServerEndpoint {
     ApiClients,
     /// JSON API: single client (GET, PUT, DELETE)
     ApiClient,
+    /// JSON API: owning user of a client (PUT)
+    ApiClientOwner,
+    /// JSON API: pause / resume syncing of a client (PUT)
+    ApiClientPause,
+    /// JSON API: take over watch groups and history of another client (POST)
+    ApiClientClaim,
+    /// JSON API: list / create sync blackout windows of a client
+    ApiClientSyncBlackouts,
+    /// JSON API: single sync blackout window of a client (DELETE)
+    ApiClientSyncBlackout,
     /// JSON API: list / create client watch group assignments
     ApiClientWatchGroups,
     /// JSON API: single client watch group assignment (PUT, DELETE)
     ApiClientWatchGroup,
+    /// JSON API: pause / resume syncing of a single client watch group assignment (PUT)
+    ApiClientWatchGroupPause,
+    /// JSON API: files a client still has to up- / download as of its last syncs (GET)
+    ApiClientPending,
+    /// JSON API: file list of a client's latest sync of a watch group (GET)
+    ApiClientFiles,
+    /// client with the sync state of all its watch groups
+    ApiClientDetail,
+    /// recent events and transfers of a client
+    ApiClientActivity,
+    /// forget the client's file lists and pending work - rebuilt with its next syncs
+    ApiClientResync,
+    /// JSON API: list / create registration templates for new clients
+    ApiRegistrationTemplates,
+    /// JSON API: single registration template (DELETE)
+    ApiRegistrationTemplate,
     /// JSON API: list / create server watch groups
     ApiWatchGroups,
     /// JSON API: single server watch group (PUT, DELETE)
     ApiWatchGroup,
+    /// JSON API: owning user of a server watch group (PUT)
+    ApiWatchGroupOwner,
+    /// JSON API: read-after-write verification of uploads to a server watch group (PUT)
+    ApiWatchGroupVerifyWrites,
+    /// JSON API: durability level (fsync) of uploads to a server watch group (PUT)
+    ApiWatchGroupDurability,
+    /// JSON API: sync priority of a server watch group on the clients (PUT)
+    ApiWatchGroupPriority,
+    /// JSON API: mass change guard thresholds of a server watch group (GET, PUT)
+    ApiWatchGroupMassChangeGuard,
+    /// JSON API: server-local dir a server watch group is mirrored into (PUT)
+    ApiWatchGroupPublish,
+    /// JSON API: list / create outbound webhooks of a server watch group
+    ApiWatchGroupWebhooks,
+    /// JSON API: single webhook of a server watch group (DELETE)
+    ApiWatchGroupWebhook,
+    /// JSON API: list / create share links (e.g. upload inboxes) of a server watch group
+    ApiWatchGroupShareLinks,
+    /// JSON API: single share link of a server watch group (DELETE)
+    ApiWatchGroupShareLink,
     /// JSON API: list / create files within one watch group
     ApiWatchGroupFiles,
     /// Inline file preview for one watch group file
     ApiWatchGroupFile,
+    /// JSON API: paths, sizes and hashes of all files of a watch group (offline seeding)
+    ApiWatchGroupManifest,
+    /// JSON API: member clients with their last sync and the storage of a watch group
+    ApiWatchGroupDetail,
+    /// JSON API: theme and layout of the web app for the calling account
+    ApiSettings,
     /// JSON API: monitoring data
     ApiMonitor,
+    /// JSON API: storage statistics of a watch group
+    ApiStats,
+    /// JSON API: transferred bytes per client and watch group over time
+    ApiStatsBandwidth,
+    /// JSON API: largest files present in a watch group
+    ApiReportTopFiles,
+    /// JSON API: stored bytes of a watch group per day (from its event history)
+    ApiReportGrowth,
+    /// JSON API: version, bound endpoints and transports of the server
+    ApiCapabilities,
+    /// JSON API: role of the server and progress of the replication (GET)
+    ApiReplication,
+    /// JSON API: file events after a position in the stream - pulled by replicas (GET)
+    ApiReplicationEvents,
+    /// JSON API: newest event and checkpoints of the consumers of the event export (GET)
+    ApiExport,
+    /// NDJSON stream of the file events after a cursor (GET)
+    ApiExportEvents,
+    /// JSON API: checkpoint of an export consumer (PUT)
+    ApiExportCheckpoint,
+    /// throughput test (`client bench`) - the body is counted and discarded (POST)
+    ApiBenchUpload,
+    /// throughput test (`client bench`) - `?bytes=` of synthetic data
+    ApiBenchDownload,
+    /// JSON API: search filenames (and small text files) across watch groups
+    ApiSearch,
+    /// JSON API: all versions of one path within a watch group
+    ApiHistory,
+    /// JSON API: which clients have the latest version of one path within a watch group
+    ApiFileStatus,
+    /// JSON API: diff between two versions of a text file
+    ApiDiff,
+    /// downscaled jpeg of an image within a watch group
+    ApiThumbnail,
+    /// JSON API: beginning of a text file for previews
+    ApiPreview,
+    /// JSON API: read / write a notes file (quick text exchange between devices)
+    ApiNote,
+    /// JSON API: delete everything below a path prefix (POST, needs confirmation)
+    ApiAdminPrune,
+    /// JSON API: make clients re-download everything below a path prefix (POST, needs confirmation)
+    ApiAdminResync,
+    /// JSON API: copy everything below a path prefix into another watch group (POST)
+    ApiAdminCopy,
+    /// JSON API: move everything below a path prefix into another watch group (POST)
+    ApiAdminMove,
+    /// JSON API: take over a directory on the server synced by another tool (POST)
+    ApiAdminImport,
+    /// JSON API: bursts of changes the mass change guard stopped (GET)
+    ApiAdminMassChanges,
+    /// JSON API: deletes waiting for their grace period (GET)
+    ApiAdminPendingDeletes,
+    /// JSON API: cancels a pending delete (DELETE)
+    ApiAdminPendingDelete,
+    /// JSON API: consistent copy of the database into the backup dir (POST)
+    ApiAdminDbBackup,
+    /// JSON API: integrity check of the database
+    ApiAdminDbCheck,
+    /// JSON API: files in the backup dir
+    ApiAdminBackups,
+    /// download of a single backup - decrypted if needed
+    ApiAdminBackup,
+    /// JSON API: report of the last scrub (GET) / scrub disk against history now (POST)
+    ApiAdminScrub,
+    /// JSON API: what the check of the history against the disk on boot found (GET)
+    ApiAdminBootReport,
+    /// JSON API: background tasks with their last / next run and last error (GET)
+    ApiAdminTasks,
+    /// JSON API: percentiles of recent sync / upload / download / delete requests (GET)
+    ApiAdminTimings,
+    /// JSON API: cron schedules of the backup, compaction, scrub and gc jobs (GET)
+    ApiAdminSchedule,
+    /// JSON API: change the schedule of a job (PUT)
+    ApiAdminScheduleJob,
+    /// JSON API: faults injected into client traffic - servers built with `chaos` only (GET / PUT)
+    ApiAdminChaos,
+    /// JSON API: list / create users
+    ApiUsers,
+    /// JSON API: single user (PUT, DELETE)
+    ApiUser,
+    /// JSON API: role of a user (PUT)
+    ApiUserRole,
+    /// JSON API: (re)generate the api token of a user (POST)
+    ApiUserToken,
+    /// signed, time-limited url of a file - for browsers / tools without an api token
+    ApiSignDownload,
 }
 
 impl ServerEndpoint {
@@ -55,6 +250,11 @@ impl ServerEndpoint {
         for (key, value) in params {
             uri = uri.replace(&format!("{{{key}}}"), value);
         }
+        debug_assert!(
+            !uri.contains('{'),
+            "Missing path params for {}: {uri}",
+            self.to_str()
+        );
         uri
     }
 
@@ -63,7 +263,7 @@ impl ServerEndpoint {
         self.to_uri_with(base, &[("wg_id", &wg_id.to_string())])
     }
 
-    pub fn to_str(&self) -> &str {
+    pub fn to_str(&self) -> &'static str {
         match self {
             ServerEndpoint::Hello => "/",
             ServerEndpoint::Ping => "/ping",
@@ -72,9 +272,22 @@ impl ServerEndpoint {
             // sys
             ServerEndpoint::Sync => "/sys/sync/{wg_id}",
             ServerEndpoint::Upload => "/sys/upload/{wg_id}",
+            ServerEndpoint::UploadBulk => "/sys/upload/bulk/{wg_id}",
             ServerEndpoint::Download => "/sys/download/{wg_id}",
+            ServerEndpoint::DownloadSnapshot => "/sys/download/snapshot/{wg_id}",
             ServerEndpoint::Delete => "/sys/delete/{wg_id}",
             ServerEndpoint::Config => "/sys/config",
+            ServerEndpoint::ScanWarning => "/sys/scan-warning/{wg_id}",
+            ServerEndpoint::Manifest => "/sys/manifest/{wg_id}",
+            ServerEndpoint::BandwidthUsage => "/sys/bandwidth-usage",
+            ServerEndpoint::Grpc => "/rfs.sync.SyncService/{*method}",
+            // public
+            ServerEndpoint::Inbox => "/inbox/{token}",
+            ServerEndpoint::DownloadZip => "/download/zip/{wg_id}",
+            ServerEndpoint::SignedDownload => "/signed/{wg_id}",
+            // webdav
+            ServerEndpoint::Dav => "/dav/{wg_id}",
+            ServerEndpoint::DavPath => "/dav/{wg_id}/{*path}",
             // apps
             ServerEndpoint::ServePWA => "/pwa",
             ServerEndpoint::App => "/app",
@@ -83,44 +296,247 @@ impl ServerEndpoint {
             ServerEndpoint::ApiLinkTags => "/api/links/tags",
             ServerEndpoint::ApiClients => "/api/clients",
             ServerEndpoint::ApiClient => "/api/clients/{id}",
+            ServerEndpoint::ApiClientOwner => "/api/clients/{id}/owner",
+            ServerEndpoint::ApiClientPause => "/api/clients/{id}/pause",
+            ServerEndpoint::ApiClientClaim => "/api/clients/{id}/claim",
+            ServerEndpoint::ApiClientSyncBlackouts => "/api/clients/{id}/sync-blackouts",
+            ServerEndpoint::ApiClientSyncBlackout => {
+                "/api/clients/{id}/sync-blackouts/{blackout_id}"
+            }
             ServerEndpoint::ApiClientWatchGroups => "/api/clients/{id}/watch-groups",
             ServerEndpoint::ApiClientWatchGroup => "/api/clients/{id}/watch-groups/{wg_id}",
+            ServerEndpoint::ApiClientWatchGroupPause => {
+                "/api/clients/{id}/watch-groups/{wg_id}/pause"
+            }
+            ServerEndpoint::ApiClientPending => "/api/clients/{id}/pending",
+            ServerEndpoint::ApiClientFiles => "/api/clients/{id}/watch-groups/{wg_id}/files",
+            ServerEndpoint::ApiClientDetail => "/api/clients/{id}/detail",
+            ServerEndpoint::ApiClientActivity => "/api/clients/{id}/activity",
+            ServerEndpoint::ApiClientResync => "/api/clients/{id}/resync",
+            ServerEndpoint::ApiRegistrationTemplates => "/api/registration-templates",
+            ServerEndpoint::ApiRegistrationTemplate => "/api/registration-templates/{id}",
             ServerEndpoint::ApiWatchGroups => "/api/watch-groups",
             ServerEndpoint::ApiWatchGroup => "/api/watch-groups/{id}",
+            ServerEndpoint::ApiWatchGroupOwner => "/api/watch-groups/{id}/owner",
+            ServerEndpoint::ApiWatchGroupVerifyWrites => "/api/watch-groups/{id}/verify-writes",
+            ServerEndpoint::ApiWatchGroupDurability => "/api/watch-groups/{id}/durability",
+            ServerEndpoint::ApiWatchGroupPriority => "/api/watch-groups/{id}/priority",
+            ServerEndpoint::ApiWatchGroupMassChangeGuard => {
+                "/api/watch-groups/{id}/mass-change-guard"
+            }
+            ServerEndpoint::ApiWatchGroupPublish => "/api/watch-groups/{id}/publish",
+            ServerEndpoint::ApiWatchGroupWebhooks => "/api/watch-groups/{id}/webhooks",
+            ServerEndpoint::ApiWatchGroupWebhook => "/api/watch-groups/{id}/webhooks/{hook_id}",
+            ServerEndpoint::ApiWatchGroupShareLinks => "/api/watch-groups/{id}/share-links",
+            ServerEndpoint::ApiWatchGroupShareLink => "/api/watch-groups/{id}/share-links/{token}",
             ServerEndpoint::ApiWatchGroupFiles => "/api/watch-groups/{id}/files",
             ServerEndpoint::ApiWatchGroupFile => "/api/watch-groups/{id}/file",
+            ServerEndpoint::ApiWatchGroupManifest => "/api/watch-groups/{id}/manifest",
+            ServerEndpoint::ApiWatchGroupDetail => "/api/watch-groups/{id}/detail",
+            ServerEndpoint::ApiSettings => "/api/settings",
             ServerEndpoint::ApiMonitor => "/api/monitor",
+            ServerEndpoint::ApiStats => "/api/stats/{wg_id}",
+            ServerEndpoint::ApiStatsBandwidth => "/api/stats/bandwidth",
+            ServerEndpoint::ApiReportTopFiles => "/api/reports/top-files/{wg_id}",
+            ServerEndpoint::ApiReportGrowth => "/api/reports/growth/{wg_id}",
+            ServerEndpoint::ApiCapabilities => "/api/capabilities",
+            ServerEndpoint::ApiReplication => "/api/replication",
+            ServerEndpoint::ApiReplicationEvents => "/api/replication/events",
+            ServerEndpoint::ApiExport => "/api/export",
+            ServerEndpoint::ApiExportEvents => "/api/export/events",
+            ServerEndpoint::ApiExportCheckpoint => "/api/export/checkpoints/{name}",
+            ServerEndpoint::ApiBenchUpload => "/api/bench/upload",
+            ServerEndpoint::ApiBenchDownload => "/api/bench/download",
+            ServerEndpoint::ApiSearch => "/api/search",
+            ServerEndpoint::ApiHistory => "/api/history/{wg_id}",
+            ServerEndpoint::ApiFileStatus => "/api/file-status/{wg_id}",
+            ServerEndpoint::ApiDiff => "/api/diff/{wg_id}",
+            ServerEndpoint::ApiThumbnail => "/api/thumbnail/{wg_id}",
+            ServerEndpoint::ApiPreview => "/api/preview/{wg_id}",
+            ServerEndpoint::ApiNote => "/api/note/{wg_id}",
+            ServerEndpoint::ApiAdminPrune => "/api/admin/prune",
+            ServerEndpoint::ApiAdminResync => "/api/admin/resync",
+            ServerEndpoint::ApiAdminCopy => "/api/admin/copy",
+            ServerEndpoint::ApiAdminMove => "/api/admin/move",
+            ServerEndpoint::ApiAdminImport => "/api/admin/import",
+            ServerEndpoint::ApiAdminMassChanges => "/api/admin/mass-changes",
+            ServerEndpoint::ApiAdminPendingDeletes => "/api/admin/pending-deletes",
+            ServerEndpoint::ApiAdminPendingDelete => "/api/admin/pending-deletes/{id}",
+            ServerEndpoint::ApiAdminDbBackup => "/api/admin/db/backup",
+            ServerEndpoint::ApiAdminDbCheck => "/api/admin/db/check",
+            ServerEndpoint::ApiAdminBackups => "/api/admin/backups",
+            ServerEndpoint::ApiAdminBackup => "/api/admin/backups/{name}",
+            ServerEndpoint::ApiAdminScrub => "/api/admin/scrub",
+            ServerEndpoint::ApiAdminBootReport => "/api/admin/boot-report",
+            ServerEndpoint::ApiAdminTasks => "/api/admin/tasks",
+            ServerEndpoint::ApiAdminTimings => "/api/admin/timings",
+            ServerEndpoint::ApiAdminSchedule => "/api/admin/schedule",
+            ServerEndpoint::ApiAdminScheduleJob => "/api/admin/schedule/{job}",
+            ServerEndpoint::ApiAdminChaos => "/api/admin/chaos",
+            ServerEndpoint::ApiUsers => "/api/users",
+            ServerEndpoint::ApiUser => "/api/users/{id}",
+            ServerEndpoint::ApiUserRole => "/api/users/{id}/role",
+            ServerEndpoint::ApiUserToken => "/api/users/{id}/token",
+            ServerEndpoint::ApiSignDownload => "/api/sign-download",
         }
     }
 }
 
+/// sys endpoints together with their path params - so clients can't miss or misspell one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SysRoute {
+    Sync { wg_id: i64 },
+    Upload { wg_id: i64 },
+    UploadBulk { wg_id: i64 },
+    Download { wg_id: i64 },
+    DownloadSnapshot { wg_id: i64 },
+    Delete { wg_id: i64 },
+    ScanWarning { wg_id: i64 },
+    Manifest { wg_id: i64 },
+}
+
+impl SysRoute {
+    pub fn endpoint(&self) -> ServerEndpoint {
+        match self {
+            SysRoute::Sync { .. } => ServerEndpoint::Sync,
+            SysRoute::Upload { .. } => ServerEndpoint::Upload,
+            SysRoute::UploadBulk { .. } => ServerEndpoint::UploadBulk,
+            SysRoute::Download { .. } => ServerEndpoint::Download,
+            SysRoute::DownloadSnapshot { .. } => ServerEndpoint::DownloadSnapshot,
+            SysRoute::Delete { .. } => ServerEndpoint::Delete,
+            SysRoute::ScanWarning { .. } => ServerEndpoint::ScanWarning,
+            SysRoute::Manifest { .. } => ServerEndpoint::Manifest,
+        }
+    }
+
+    /// axum route pattern (the same for every param value)
+    pub fn route(&self) -> &'static str {
+        self.endpoint().to_str()
+    }
+
+    pub fn to_uri(&self, base: &str) -> String {
+        let wg_id = match self {
+            SysRoute::Sync { wg_id }
+            | SysRoute::Upload { wg_id }
+            | SysRoute::UploadBulk { wg_id }
+            | SysRoute::Download { wg_id }
+            | SysRoute::DownloadSnapshot { wg_id }
+            | SysRoute::Delete { wg_id }
+            | SysRoute::ScanWarning { wg_id }
+            | SysRoute::Manifest { wg_id } => wg_id,
+        };
+        self.endpoint()
+            .to_uri_with(base, &[("wg_id", &wg_id.to_string())])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ServerEndpoint::*;
 
-    const ALL_ENDPOINTS: [ServerEndpoint; 21] = [
+    const ALL_ENDPOINTS: [ServerEndpoint; 101] = [
         Hello,
         Ping,
         Version,
         Scan,
         Sync,
         Upload,
+        UploadBulk,
         Download,
+        DownloadSnapshot,
         Delete,
         Config,
+        ScanWarning,
+        Manifest,
+        BandwidthUsage,
+        Grpc,
+        Inbox,
+        DownloadZip,
+        SignedDownload,
+        Dav,
+        DavPath,
         ServePWA,
         App,
         ApiLinks,
         ApiClients,
         ApiClient,
+        ApiClientOwner,
+        ApiClientPause,
+        ApiClientClaim,
+        ApiClientSyncBlackouts,
+        ApiClientSyncBlackout,
         ApiClientWatchGroups,
         ApiClientWatchGroup,
+        ApiClientWatchGroupPause,
+        ApiClientPending,
+        ApiClientFiles,
+        ApiClientDetail,
+        ApiClientActivity,
+        ApiClientResync,
+        ApiRegistrationTemplates,
+        ApiRegistrationTemplate,
         ApiWatchGroups,
         ApiWatchGroup,
+        ApiWatchGroupOwner,
+        ApiWatchGroupVerifyWrites,
+        ApiWatchGroupDurability,
+        ApiWatchGroupPriority,
+        ApiWatchGroupMassChangeGuard,
+        ApiWatchGroupPublish,
+        ApiWatchGroupWebhooks,
+        ApiWatchGroupWebhook,
+        ApiWatchGroupShareLinks,
+        ApiWatchGroupShareLink,
         ApiWatchGroupFiles,
         ApiWatchGroupFile,
+        ApiWatchGroupManifest,
+        ApiWatchGroupDetail,
+        ApiSettings,
         ApiMonitor,
+        ApiStats,
+        ApiStatsBandwidth,
+        ApiReportTopFiles,
+        ApiReportGrowth,
+        ApiCapabilities,
+        ApiReplication,
+        ApiReplicationEvents,
+        ApiExport,
+        ApiExportEvents,
+        ApiExportCheckpoint,
+        ApiBenchUpload,
+        ApiBenchDownload,
+        ApiSearch,
+        ApiHistory,
+        ApiFileStatus,
+        ApiDiff,
+        ApiThumbnail,
+        ApiPreview,
+        ApiNote,
+        ApiAdminPrune,
+        ApiAdminResync,
+        ApiAdminCopy,
+        ApiAdminMove,
+        ApiAdminImport,
+        ApiAdminMassChanges,
+        ApiAdminPendingDeletes,
+        ApiAdminPendingDelete,
+        ApiAdminDbBackup,
+        ApiAdminDbCheck,
+        ApiAdminBackups,
+        ApiAdminBackup,
+        ApiAdminScrub,
+        ApiAdminBootReport,
+        ApiAdminTasks,
+        ApiAdminTimings,
+        ApiAdminSchedule,
+        ApiAdminScheduleJob,
+        ApiAdminChaos,
+        ApiUsers,
+        ApiUser,
+        ApiUserRole,
+        ApiUserToken,
+        ApiSignDownload,
     ];
 
     #[test]
@@ -135,9 +551,23 @@ mod tests {
 
                 Sync => assert_eq!("http://localhost/sys/sync/{wg_id}", actual),
                 Upload => assert_eq!("http://localhost/sys/upload/{wg_id}", actual),
+                UploadBulk => assert_eq!("http://localhost/sys/upload/bulk/{wg_id}", actual),
                 Download => assert_eq!("http://localhost/sys/download/{wg_id}", actual),
+                DownloadSnapshot => {
+                    assert_eq!("http://localhost/sys/download/snapshot/{wg_id}", actual)
+                }
                 Delete => assert_eq!("http://localhost/sys/delete/{wg_id}", actual),
                 Config => assert_eq!("http://localhost/sys/config", actual),
+                ScanWarning => assert_eq!("http://localhost/sys/scan-warning/{wg_id}", actual),
+                Manifest => assert_eq!("http://localhost/sys/manifest/{wg_id}", actual),
+                BandwidthUsage => assert_eq!("http://localhost/sys/bandwidth-usage", actual),
+                Grpc => assert_eq!("http://localhost/rfs.sync.SyncService/{*method}", actual),
+                Inbox => assert_eq!("http://localhost/inbox/{token}", actual),
+                DownloadZip => assert_eq!("http://localhost/download/zip/{wg_id}", actual),
+                SignedDownload => assert_eq!("http://localhost/signed/{wg_id}", actual),
+
+                Dav => assert_eq!("http://localhost/dav/{wg_id}", actual),
+                DavPath => assert_eq!("http://localhost/dav/{wg_id}/{*path}", actual),
 
                 ServePWA => assert_eq!("http://localhost/pwa", actual),
                 App => assert_eq!("http://localhost/app", actual),
@@ -146,6 +576,16 @@ mod tests {
                 ApiLinkTags => assert_eq!("http://localhost/api/links/tags", actual),
                 ApiClients => assert_eq!("http://localhost/api/clients", actual),
                 ApiClient => assert_eq!("http://localhost/api/clients/{id}", actual),
+                ApiClientOwner => assert_eq!("http://localhost/api/clients/{id}/owner", actual),
+                ApiClientPause => assert_eq!("http://localhost/api/clients/{id}/pause", actual),
+                ApiClientClaim => assert_eq!("http://localhost/api/clients/{id}/claim", actual),
+                ApiClientSyncBlackouts => {
+                    assert_eq!("http://localhost/api/clients/{id}/sync-blackouts", actual)
+                }
+                ApiClientSyncBlackout => assert_eq!(
+                    "http://localhost/api/clients/{id}/sync-blackouts/{blackout_id}",
+                    actual
+                ),
                 ApiClientWatchGroups => {
                     assert_eq!("http://localhost/api/clients/{id}/watch-groups", actual)
                 }
@@ -153,15 +593,141 @@ mod tests {
                     "http://localhost/api/clients/{id}/watch-groups/{wg_id}",
                     actual
                 ),
+                ApiClientWatchGroupPause => assert_eq!(
+                    "http://localhost/api/clients/{id}/watch-groups/{wg_id}/pause",
+                    actual
+                ),
+                ApiClientPending => {
+                    assert_eq!("http://localhost/api/clients/{id}/pending", actual)
+                }
+                ApiClientFiles => assert_eq!(
+                    "http://localhost/api/clients/{id}/watch-groups/{wg_id}/files",
+                    actual
+                ),
+                ApiClientDetail => assert_eq!("http://localhost/api/clients/{id}/detail", actual),
+                ApiClientActivity => {
+                    assert_eq!("http://localhost/api/clients/{id}/activity", actual)
+                }
+                ApiClientResync => assert_eq!("http://localhost/api/clients/{id}/resync", actual),
+                ApiRegistrationTemplates => {
+                    assert_eq!("http://localhost/api/registration-templates", actual)
+                }
+                ApiRegistrationTemplate => {
+                    assert_eq!("http://localhost/api/registration-templates/{id}", actual)
+                }
                 ApiWatchGroups => assert_eq!("http://localhost/api/watch-groups", actual),
                 ApiWatchGroup => assert_eq!("http://localhost/api/watch-groups/{id}", actual),
+                ApiWatchGroupOwner => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/owner", actual)
+                }
+                ApiWatchGroupVerifyWrites => assert_eq!(
+                    "http://localhost/api/watch-groups/{id}/verify-writes",
+                    actual
+                ),
+                ApiWatchGroupDurability => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/durability", actual)
+                }
+                ApiWatchGroupPriority => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/priority", actual)
+                }
+                ApiWatchGroupMassChangeGuard => assert_eq!(
+                    "http://localhost/api/watch-groups/{id}/mass-change-guard",
+                    actual
+                ),
+                ApiWatchGroupPublish => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/publish", actual)
+                }
+                ApiWatchGroupWebhooks => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/webhooks", actual)
+                }
+                ApiWatchGroupWebhook => assert_eq!(
+                    "http://localhost/api/watch-groups/{id}/webhooks/{hook_id}",
+                    actual
+                ),
+                ApiWatchGroupShareLinks => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/share-links", actual)
+                }
+                ApiWatchGroupShareLink => assert_eq!(
+                    "http://localhost/api/watch-groups/{id}/share-links/{token}",
+                    actual
+                ),
                 ApiWatchGroupFiles => {
                     assert_eq!("http://localhost/api/watch-groups/{id}/files", actual)
                 }
                 ApiWatchGroupFile => {
                     assert_eq!("http://localhost/api/watch-groups/{id}/file", actual)
                 }
+                ApiWatchGroupManifest => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/manifest", actual)
+                }
+                ApiWatchGroupDetail => {
+                    assert_eq!("http://localhost/api/watch-groups/{id}/detail", actual)
+                }
+                ApiSettings => assert_eq!("http://localhost/api/settings", actual),
                 ApiMonitor => assert_eq!("http://localhost/api/monitor", actual),
+                ApiStats => assert_eq!("http://localhost/api/stats/{wg_id}", actual),
+                ApiStatsBandwidth => {
+                    assert_eq!("http://localhost/api/stats/bandwidth", actual)
+                }
+                ApiReportTopFiles => {
+                    assert_eq!("http://localhost/api/reports/top-files/{wg_id}", actual)
+                }
+                ApiReportGrowth => {
+                    assert_eq!("http://localhost/api/reports/growth/{wg_id}", actual)
+                }
+                ApiCapabilities => assert_eq!("http://localhost/api/capabilities", actual),
+                ApiReplication => assert_eq!("http://localhost/api/replication", actual),
+                ApiReplicationEvents => {
+                    assert_eq!("http://localhost/api/replication/events", actual)
+                }
+                ApiExport => assert_eq!("http://localhost/api/export", actual),
+                ApiExportEvents => assert_eq!("http://localhost/api/export/events", actual),
+                ApiExportCheckpoint => {
+                    assert_eq!("http://localhost/api/export/checkpoints/{name}", actual)
+                }
+                ApiBenchUpload => assert_eq!("http://localhost/api/bench/upload", actual),
+                ApiBenchDownload => assert_eq!("http://localhost/api/bench/download", actual),
+                ApiSearch => assert_eq!("http://localhost/api/search", actual),
+                ApiHistory => assert_eq!("http://localhost/api/history/{wg_id}", actual),
+                ApiFileStatus => assert_eq!("http://localhost/api/file-status/{wg_id}", actual),
+                ApiDiff => assert_eq!("http://localhost/api/diff/{wg_id}", actual),
+                ApiThumbnail => assert_eq!("http://localhost/api/thumbnail/{wg_id}", actual),
+                ApiPreview => assert_eq!("http://localhost/api/preview/{wg_id}", actual),
+                ApiNote => assert_eq!("http://localhost/api/note/{wg_id}", actual),
+                ApiAdminPrune => assert_eq!("http://localhost/api/admin/prune", actual),
+                ApiAdminResync => assert_eq!("http://localhost/api/admin/resync", actual),
+                ApiAdminCopy => assert_eq!("http://localhost/api/admin/copy", actual),
+                ApiAdminMove => assert_eq!("http://localhost/api/admin/move", actual),
+                ApiAdminImport => assert_eq!("http://localhost/api/admin/import", actual),
+                ApiAdminMassChanges => {
+                    assert_eq!("http://localhost/api/admin/mass-changes", actual)
+                }
+                ApiAdminPendingDeletes => {
+                    assert_eq!("http://localhost/api/admin/pending-deletes", actual)
+                }
+                ApiAdminPendingDelete => {
+                    assert_eq!("http://localhost/api/admin/pending-deletes/{id}", actual)
+                }
+                ApiAdminDbBackup => assert_eq!("http://localhost/api/admin/db/backup", actual),
+                ApiAdminDbCheck => assert_eq!("http://localhost/api/admin/db/check", actual),
+                ApiAdminBackups => assert_eq!("http://localhost/api/admin/backups", actual),
+                ApiAdminBackup => assert_eq!("http://localhost/api/admin/backups/{name}", actual),
+                ApiAdminScrub => assert_eq!("http://localhost/api/admin/scrub", actual),
+                ApiAdminBootReport => {
+                    assert_eq!("http://localhost/api/admin/boot-report", actual)
+                }
+                ApiAdminTasks => assert_eq!("http://localhost/api/admin/tasks", actual),
+                ApiAdminTimings => assert_eq!("http://localhost/api/admin/timings", actual),
+                ApiAdminSchedule => assert_eq!("http://localhost/api/admin/schedule", actual),
+                ApiAdminScheduleJob => {
+                    assert_eq!("http://localhost/api/admin/schedule/{job}", actual)
+                }
+                ApiAdminChaos => assert_eq!("http://localhost/api/admin/chaos", actual),
+                ApiUsers => assert_eq!("http://localhost/api/users", actual),
+                ApiUser => assert_eq!("http://localhost/api/users/{id}", actual),
+                ApiUserRole => assert_eq!("http://localhost/api/users/{id}/role", actual),
+                ApiUserToken => assert_eq!("http://localhost/api/users/{id}/token", actual),
+                ApiSignDownload => assert_eq!("http://localhost/api/sign-download", actual),
             }
         })
     }
@@ -185,4 +751,30 @@ mod tests {
             Delete.to_uri_with_wg("http://localhost", 99)
         );
     }
+
+    #[test]
+    fn should_build_sys_routes() {
+        let routes = [
+            SysRoute::Sync { wg_id: 1 },
+            SysRoute::Upload { wg_id: 1 },
+            SysRoute::UploadBulk { wg_id: 1 },
+            SysRoute::Download { wg_id: 1 },
+            SysRoute::DownloadSnapshot { wg_id: 1 },
+            SysRoute::Delete { wg_id: 1 },
+            SysRoute::ScanWarning { wg_id: 1 },
+            SysRoute::Manifest { wg_id: 1 },
+        ];
+        routes.into_iter().for_each(|route| {
+            let uri = route.to_uri("http://localhost");
+            assert_eq!(
+                route.route().replace("{wg_id}", "1"),
+                uri.trim_start_matches("http://localhost")
+            );
+            assert!(!uri.contains('{'), "{uri}");
+        });
+        assert_eq!(
+            "http://localhost/sys/upload/bulk/3",
+            SysRoute::UploadBulk { wg_id: 3 }.to_uri("http://localhost")
+        );
+    }
 }
@@ -1,6 +1,8 @@
 use crate::dtos::FileDescription;
 use crate::matchable_path::MatchablePath;
+use crate::temp_files::TempFilePatterns;
 use crate::utc_millis::UtcMillis;
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::fs::Metadata;
 use std::path::Path;
@@ -40,12 +42,67 @@ pub fn get_file_description(
     }
 }
 
+/// guards against watch groups pointing at `/` or into a node_modules tree (0 = unlimited)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanLimits {
+    /// the scan is aborted once it found more files
+    pub max_files: usize,
+    /// the scan is aborted once it would enter a deeper directory (files in the root = depth 0)
+    pub max_depth: usize,
+}
+
+impl Default for ScanLimits {
+    fn default() -> Self {
+        Self {
+            max_files: 250_000,
+            max_depth: 64,
+        }
+    }
+}
+
+impl ScanLimits {
+    /// no limits - for scans of the server's own storage
+    pub fn none() -> Self {
+        Self {
+            max_files: 0,
+            max_depth: 0,
+        }
+    }
+
+    /// early warning once a scan found 80% of `max_files`
+    pub fn warning(&self, files: usize) -> Option<String> {
+        (self.max_files > 0 && files >= self.max_files / 5 * 4).then(|| {
+            format!(
+                "{files} files - close to the limit of {} (max_files)",
+                self.max_files
+            )
+        })
+    }
+}
+
 pub fn get_all_file_descriptions(
     path: &Path,
     exclude_dirs: &Vec<String>,
     exclude_dot_dirs: bool,
+    temp_files: &TempFilePatterns,
+    limits: ScanLimits,
 ) -> Result<Vec<FileDescription>, String> {
-    inner_get_files_of_dir_rec(path, path, Vec::new(), exclude_dirs, exclude_dot_dirs)
+    let filter = ScanFilter {
+        exclude_dirs,
+        exclude_dot_dirs,
+        temp_files,
+        limits,
+    };
+    inner_get_files_of_dir_rec(path, path, Vec::new(), &filter, 0)
+}
+
+/// what to leave out of a scan - the same for every directory of it
+struct ScanFilter<'a> {
+    exclude_dirs: &'a Vec<String>,
+    exclude_dot_dirs: bool,
+    temp_files: &'a TempFilePatterns,
+    limits: ScanLimits,
 }
 
 fn inner_get_files_of_dir_rec(
@@ -55,8 +112,9 @@ fn inner_get_files_of_dir_rec(
     reference_root_path: &Path,
     // the already collected elements in prev. recursive iterations
     mut descriptions: Vec<FileDescription>,
-    exclude_dirs: &Vec<String>,
-    exclude_dot_dirs: bool,
+    filter: &ScanFilter,
+    // directories between the reference root and the current path
+    depth: usize,
 ) -> Result<Vec<FileDescription>, String> {
     for entry_result in fs::read_dir(current_path).map_err(|e| e.to_string())? {
         let entry = entry_result.map_err(|e| e.to_string())?;
@@ -67,11 +125,12 @@ fn inner_get_files_of_dir_rec(
             .and_then(|n| n.to_str())
             .unwrap_or("");
 
-        if exclude_dot_dirs && entry_name.starts_with('.') {
+        if filter.exclude_dot_dirs && entry_name.starts_with('.') {
             continue;
         }
 
-        if exclude_dirs
+        if filter
+            .exclude_dirs
             .iter()
             .any(|excl| entry_path.to_string_lossy().contains(excl.as_str()))
         {
@@ -88,6 +147,9 @@ fn inner_get_files_of_dir_rec(
             {
                 continue;
             }
+            if filter.temp_files.is_temp_file(entry_name) {
+                continue;
+            }
 
             let relative_path = Path::new("./").join(
                 entry_path
@@ -111,13 +173,27 @@ fn inner_get_files_of_dir_rec(
                 last_updated_utc_millis,
             };
             descriptions.push(description);
+            let max_files = filter.limits.max_files;
+            if max_files > 0 && descriptions.len() > max_files {
+                return Err(format!(
+                    "Scan aborted - more than {} files (max_files) below {:?}",
+                    max_files, reference_root_path
+                ));
+            }
         } else if entry_path.is_dir() {
+            let max_depth = filter.limits.max_depth;
+            if max_depth > 0 && depth >= max_depth {
+                return Err(format!(
+                    "Scan aborted - {:?} is nested deeper than {} directories (max_depth)",
+                    entry_path, max_depth
+                ));
+            }
             descriptions = inner_get_files_of_dir_rec(
                 &entry_path,
                 reference_root_path,
                 descriptions,
-                exclude_dirs,
-                exclude_dot_dirs,
+                filter,
+                depth + 1,
             )?;
         }
     }
@@ -157,7 +233,14 @@ mod tests {
         touch(&root.join(".obsidian").join("workspace.json"));
         touch(&root.join(".git").join("config"));
 
-        let result = get_all_file_descriptions(&root, &vec![], true).unwrap();
+        let result = get_all_file_descriptions(
+            &root,
+            &vec![],
+            true,
+            &TempFilePatterns::none(),
+            ScanLimits::none(),
+        )
+        .unwrap();
 
         assert_eq!(names(&result), vec!["normal.txt"]);
         fs::remove_dir_all(&root).unwrap();
@@ -170,7 +253,14 @@ mod tests {
         touch(&root.join("normal.txt"));
         touch(&root.join(".obsidian").join("workspace.json"));
 
-        let result = get_all_file_descriptions(&root, &vec![], false).unwrap();
+        let result = get_all_file_descriptions(
+            &root,
+            &vec![],
+            false,
+            &TempFilePatterns::none(),
+            ScanLimits::none(),
+        )
+        .unwrap();
 
         assert_eq!(names(&result), vec!["normal.txt", "workspace.json"]);
         fs::remove_dir_all(&root).unwrap();
@@ -184,8 +274,14 @@ mod tests {
         touch(&root.join("node_modules").join("lodash").join("index.js"));
         touch(&root.join("src").join("main.rs"));
 
-        let result =
-            get_all_file_descriptions(&root, &vec!["node_modules".to_string()], false).unwrap();
+        let result = get_all_file_descriptions(
+            &root,
+            &vec!["node_modules".to_string()],
+            false,
+            &TempFilePatterns::none(),
+            ScanLimits::none(),
+        )
+        .unwrap();
 
         assert_eq!(names(&result), vec!["keep.txt", "main.rs"]);
         fs::remove_dir_all(&root).unwrap();
@@ -198,7 +294,14 @@ mod tests {
         touch(&root.join("normal.txt"));
         touch(&root.join(".hidden_file"));
 
-        let result = get_all_file_descriptions(&root, &vec![], true).unwrap();
+        let result = get_all_file_descriptions(
+            &root,
+            &vec![],
+            true,
+            &TempFilePatterns::none(),
+            ScanLimits::none(),
+        )
+        .unwrap();
 
         assert_eq!(names(&result), vec!["normal.txt"]);
         fs::remove_dir_all(&root).unwrap();
@@ -213,10 +316,74 @@ mod tests {
         touch(&root.join("node_modules").join("index.js"));
         touch(&root.join("src").join("lib.rs"));
 
-        let result =
-            get_all_file_descriptions(&root, &vec!["node_modules".to_string()], true).unwrap();
+        let result = get_all_file_descriptions(
+            &root,
+            &vec!["node_modules".to_string()],
+            true,
+            &TempFilePatterns::none(),
+            ScanLimits::none(),
+        )
+        .unwrap();
 
         assert_eq!(names(&result), vec!["keep.txt", "lib.rs"]);
         fs::remove_dir_all(&root).unwrap();
     }
+
+    #[test]
+    fn excludes_temp_files() {
+        let root = std::env::temp_dir().join("rfs_test_temp_files");
+        let _ = fs::remove_dir_all(&root);
+        touch(&root.join("report.docx"));
+        touch(&root.join("~$report.docx"));
+        touch(&root.join("src").join("main.rs.swp"));
+
+        let result = get_all_file_descriptions(
+            &root,
+            &vec![],
+            false,
+            &TempFilePatterns::default(),
+            ScanLimits::none(),
+        )
+        .unwrap();
+
+        assert_eq!(names(&result), vec!["report.docx"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn aborts_scan_beyond_limits() {
+        let root = std::env::temp_dir().join("rfs_test_scan_limits");
+        let _ = fs::remove_dir_all(&root);
+        touch(&root.join("a.txt"));
+        touch(&root.join("b.txt"));
+        touch(&root.join("one").join("two").join("c.txt"));
+        let scan = |max_files, max_depth| {
+            get_all_file_descriptions(
+                &root,
+                &vec![],
+                false,
+                &TempFilePatterns::none(),
+                ScanLimits {
+                    max_files,
+                    max_depth,
+                },
+            )
+        };
+
+        assert_eq!(3, scan(3, 2).unwrap().len());
+        assert!(scan(2, 0).unwrap_err().contains("max_files"));
+        assert!(scan(0, 1).unwrap_err().contains("max_depth"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn warns_close_to_max_files() {
+        let limits = ScanLimits {
+            max_files: 100,
+            max_depth: 0,
+        };
+        assert_eq!(None, limits.warning(79));
+        assert!(limits.warning(80).is_some());
+        assert_eq!(None, ScanLimits::none().warning(1_000_000));
+    }
 }
@@ -0,0 +1,268 @@
+use crate::dtos::FileDescription;
+use crate::matchable_path::MatchablePath;
+use crate::sync_instruction::SyncInstruction;
+use crate::utc_millis::UtcMillis;
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Status};
+
+include!(concat!(env!("OUT_DIR"), "/rfs.sync.SyncService.rs"));
+
+/// http status the server would have replied with - so clients treat both transports alike
+pub const HTTP_STATUS_METADATA_KEY: &str = "x-http-status";
+
+/// POST /sys/sync/{wg_id}
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SyncRequest {
+    #[prost(int64, tag = "1")]
+    pub watch_group_id: i64,
+    #[prost(message, repeated, tag = "2")]
+    pub files: Vec<FileDescriptionMessage>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SyncReply {
+    /// see [`crate::endpoint::SYNC_PAUSED_HEADER_KEY`]
+    #[prost(bool, tag = "1")]
+    pub paused: bool,
+    #[prost(message, repeated, tag = "2")]
+    pub instructions: Vec<SyncInstructionMessage>,
+    /// see [`crate::endpoint::SYNC_PAUSED_UNTIL_HEADER_KEY`]
+    #[prost(uint64, optional, tag = "3")]
+    pub paused_until: Option<u64>,
+    /// see [`crate::endpoint::DOWNLOAD_BYTES_HEADER_KEY`]
+    #[prost(uint64, optional, tag = "4")]
+    pub download_bytes: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FileDescriptionMessage {
+    #[prost(string, tag = "1")]
+    pub file_name: String,
+    /// unix-delimited
+    #[prost(string, tag = "2")]
+    pub relative_path: String,
+    #[prost(uint64, tag = "3")]
+    pub size_in_bytes: u64,
+    #[prost(string, tag = "4")]
+    pub file_type: String,
+    #[prost(uint64, tag = "5")]
+    pub last_updated_utc_millis: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SyncInstructionMessage {
+    #[prost(enumeration = "InstructionKind", tag = "1")]
+    pub kind: i32,
+    /// unix-delimited
+    #[prost(string, tag = "2")]
+    pub path: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+#[repr(i32)]
+pub enum InstructionKind {
+    Upload = 0,
+    Download = 1,
+    Delete = 2,
+}
+
+/// POST /sys/upload/{wg_id} - the first chunk carries the meta data, all of them the content
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct UploadChunk {
+    #[prost(int64, tag = "1")]
+    pub watch_group_id: i64,
+    /// unix-delimited
+    #[prost(string, tag = "2")]
+    pub relative_path: String,
+    #[prost(uint64, tag = "3")]
+    pub utc_millis: u64,
+    /// of the whole file - to check the free disk space up front and to refuse cut-off uploads
+    #[prost(uint64, tag = "4")]
+    pub size_in_bytes: u64,
+    #[prost(bytes = "vec", tag = "5")]
+    pub data: Vec<u8>,
+    /// 0 unless the upload resolves a keep-both conflict - see `CONFLICT_KEPT_HEADER_KEY`
+    #[prost(uint64, tag = "6")]
+    pub conflict_kept_utc_millis: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct UploadReply {
+    #[prost(string, tag = "1")]
+    pub message: String,
+}
+
+/// GET /sys/download/{wg_id}
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DownloadRequest {
+    #[prost(int64, tag = "1")]
+    pub watch_group_id: i64,
+    /// unix-delimited
+    #[prost(string, tag = "2")]
+    pub path: String,
+}
+
+/// the first chunk carries the size of the whole file and the time of its latest event
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DownloadChunk {
+    #[prost(uint64, tag = "1")]
+    pub size_in_bytes: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: Vec<u8>,
+    /// 0 if unknown
+    #[prost(uint64, tag = "3")]
+    pub utc_millis: u64,
+    /// client the latest version came from - empty if unknown
+    #[prost(string, tag = "4")]
+    pub client_host: String,
+}
+
+/// POST /sys/delete/{wg_id}
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DeleteRequest {
+    #[prost(int64, tag = "1")]
+    pub watch_group_id: i64,
+    /// unix-delimited
+    #[prost(string, tag = "2")]
+    pub path: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DeleteReply {
+    #[prost(string, tag = "1")]
+    pub message: String,
+}
+
+/// empty paths (or only `.`, `..`) are rejected instead of panicking in [`MatchablePath`]
+pub fn parse_path(path: &str) -> Result<MatchablePath, String> {
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !matches!(*s, "" | "." | ".."))
+        .map(str::to_string)
+        .collect();
+    if segments.is_empty() {
+        return Err(format!("Invalid path '{path}'"));
+    }
+    Ok(MatchablePath::new(segments))
+}
+
+/// error of a sys endpoint as grpc status - the http status is kept in the metadata
+pub fn status_from_http(http_status: u16, message: String) -> Status {
+    let code = match http_status {
+        400 | 422 => Code::InvalidArgument,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::NotFound,
+        409 | 412 | 423 => Code::FailedPrecondition,
+        413 | 507 => Code::ResourceExhausted,
+        503 => Code::Unavailable,
+        _ => Code::Internal,
+    };
+    let mut status = Status::new(code, message);
+    status
+        .metadata_mut()
+        .insert(HTTP_STATUS_METADATA_KEY, MetadataValue::from(http_status));
+    status
+}
+
+/// counterpart of [`status_from_http`] - 500 for statuses of other origin (e.g. transport errors)
+pub fn http_status_of(status: &Status) -> u16 {
+    status
+        .metadata()
+        .get(HTTP_STATUS_METADATA_KEY)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(500)
+}
+
+impl From<&FileDescription> for FileDescriptionMessage {
+    fn from(description: &FileDescription) -> Self {
+        FileDescriptionMessage {
+            file_name: description.file_name.clone(),
+            relative_path: description.relative_path.to_serialized_string(),
+            size_in_bytes: description.size_in_bytes,
+            file_type: description.file_type.clone(),
+            last_updated_utc_millis: description.last_updated_utc_millis.as_u64(),
+        }
+    }
+}
+
+impl TryFrom<FileDescriptionMessage> for FileDescription {
+    type Error = String;
+
+    fn try_from(message: FileDescriptionMessage) -> Result<Self, Self::Error> {
+        Ok(FileDescription {
+            file_name: message.file_name,
+            relative_path: parse_path(&message.relative_path)?,
+            size_in_bytes: message.size_in_bytes,
+            file_type: message.file_type,
+            last_updated_utc_millis: UtcMillis::from(message.last_updated_utc_millis),
+        })
+    }
+}
+
+impl From<&SyncInstruction> for SyncInstructionMessage {
+    fn from(instruction: &SyncInstruction) -> Self {
+        let kind = match instruction {
+            SyncInstruction::Upload(_) => InstructionKind::Upload,
+            SyncInstruction::Download(_) => InstructionKind::Download,
+            SyncInstruction::Delete(_) => InstructionKind::Delete,
+        };
+        SyncInstructionMessage {
+            kind: kind as i32,
+            path: instruction.path().to_serialized_string(),
+        }
+    }
+}
+
+impl TryFrom<SyncInstructionMessage> for SyncInstruction {
+    type Error = String;
+
+    fn try_from(message: SyncInstructionMessage) -> Result<Self, Self::Error> {
+        let path = parse_path(&message.path)?;
+        match InstructionKind::try_from(message.kind) {
+            Ok(InstructionKind::Upload) => Ok(SyncInstruction::Upload(path)),
+            Ok(InstructionKind::Download) => Ok(SyncInstruction::Download(path)),
+            Ok(InstructionKind::Delete) => Ok(SyncInstruction::Delete(path)),
+            Err(_) => Err(format!("Unknown instruction kind {}", message.kind)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_convert_messages_both_ways() {
+        let description = FileDescription {
+            file_name: "a.txt".to_string(),
+            relative_path: MatchablePath::from("dir/a.txt"),
+            size_in_bytes: 3,
+            file_type: "txt".to_string(),
+            last_updated_utc_millis: UtcMillis::from(7),
+        };
+        let back = FileDescription::try_from(FileDescriptionMessage::from(&description)).unwrap();
+        assert_eq!(description.relative_path, back.relative_path);
+        assert_eq!(
+            description.last_updated_utc_millis,
+            back.last_updated_utc_millis
+        );
+
+        let instruction = SyncInstruction::Delete(MatchablePath::from("dir/a.txt"));
+        let message = SyncInstructionMessage::from(&instruction);
+        assert!(matches!(
+            SyncInstruction::try_from(message),
+            Ok(SyncInstruction::Delete(p)) if p == MatchablePath::from("dir/a.txt")
+        ));
+        assert!(parse_path("../..").is_err());
+    }
+
+    #[test]
+    fn should_keep_http_status() {
+        let status = status_from_http(400, "not latest".to_string());
+        assert_eq!(Code::InvalidArgument, status.code());
+        assert_eq!(400, http_status_of(&status));
+        assert_eq!(500, http_status_of(&Status::unavailable("down")));
+    }
+}
@@ -1,6 +1,14 @@
+pub mod conflict_copy;
 pub mod dtos;
 pub mod endpoint;
 pub mod get_files_of_directory;
+pub mod glob;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod log_format;
 pub mod matchable_path;
+#[cfg(feature = "relay")]
+pub mod relay;
 pub mod sync_instruction;
+pub mod temp_files;
 pub mod utc_millis;
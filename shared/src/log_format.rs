@@ -0,0 +1,60 @@
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// env var to switch the log output of client & server
+pub const LOG_FORMAT_ENV_KEY: &str = "LOG_FORMAT";
+
+/// human readable lines (default) or one json object per line (for Loki/ELK & co.)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+impl LogFormat {
+    /// falls back to [`LogFormat::Pretty`] if the env var is missing or invalid
+    pub fn from_env() -> Self {
+        env::var(LOG_FORMAT_ENV_KEY)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Could not parse '{}' - expected 'pretty' or 'json'",
+                value
+            )),
+        }
+    }
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Pretty => write!(f, "pretty"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_log_format() {
+        assert_eq!(Ok(LogFormat::Json), "JSON".parse());
+        assert_eq!(Ok(LogFormat::Pretty), " pretty".parse());
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
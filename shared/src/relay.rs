@@ -0,0 +1,167 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// longest line of the protocol - anything longer isn't a relay peer
+const MAX_LINE_BYTES: usize = 512;
+
+/// line based protocol between the relay and home servers - tokens never go over the wire,
+/// only hmacs of random challenges / connection ids
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelayMessage {
+    /// relay → server - first line of every connection
+    Challenge(String),
+    /// server → relay - makes the connection the control connection of pairing `name`
+    Hello {
+        name: String,
+        signature: String,
+    },
+    Ok,
+    Err(String),
+    /// relay → server (control connection) - a client is waiting, dial in a data connection
+    Connect(String),
+    /// server → relay - first line of a data connection for client `id`, then raw bytes
+    Data {
+        name: String,
+        id: String,
+        signature: String,
+    },
+    /// relay → server (control connection) - keeps NATs from dropping the idle connection
+    Ping,
+}
+
+impl RelayMessage {
+    pub fn to_line(&self) -> String {
+        match self {
+            RelayMessage::Challenge(nonce) => format!("CHALLENGE {nonce}\n"),
+            RelayMessage::Hello { name, signature } => format!("HELLO {name} {signature}\n"),
+            RelayMessage::Ok => "OK\n".to_string(),
+            RelayMessage::Err(message) => format!("ERR {message}\n"),
+            RelayMessage::Connect(id) => format!("CONNECT {id}\n"),
+            RelayMessage::Data {
+                name,
+                id,
+                signature,
+            } => format!("DATA {name} {id} {signature}\n"),
+            RelayMessage::Ping => "PING\n".to_string(),
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim_end();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args: Vec<&str> = args.split(' ').filter(|a| !a.is_empty()).collect();
+        match (command, args.as_slice()) {
+            ("CHALLENGE", [nonce]) => Ok(RelayMessage::Challenge(nonce.to_string())),
+            ("HELLO", [name, signature]) => Ok(RelayMessage::Hello {
+                name: name.to_string(),
+                signature: signature.to_string(),
+            }),
+            ("OK", []) => Ok(RelayMessage::Ok),
+            ("ERR", _) => Ok(RelayMessage::Err(args.join(" "))),
+            ("CONNECT", [id]) => Ok(RelayMessage::Connect(id.to_string())),
+            ("DATA", [name, id, signature]) => Ok(RelayMessage::Data {
+                name: name.to_string(),
+                id: id.to_string(),
+                signature: signature.to_string(),
+            }),
+            ("PING", []) => Ok(RelayMessage::Ping),
+            _ => Err(format!("Unexpected relay message '{line}'")),
+        }
+    }
+}
+
+/// hex hmac-sha256 of `challenge` with the pairing token
+pub fn sign(token: &str, challenge: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(token.as_bytes()).expect("any key size");
+    mac.update(challenge.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// constant time counterpart of [`sign`]
+pub fn verify(token: &str, challenge: &str, signature: &str) -> bool {
+    let Some(signature) = decode_hex(signature) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(token.as_bytes()).expect("any key size");
+    mac.update(challenge.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// reads byte-wise - nothing after the line is consumed, data connections go on with raw bytes
+pub async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<RelayMessage, String> {
+    let mut line = Vec::new();
+    loop {
+        let byte = reader
+            .read_u8()
+            .await
+            .map_err(|e| format!("Relay connection lost - {e}"))?;
+        if byte == b'\n' {
+            break;
+        }
+        if line.len() == MAX_LINE_BYTES {
+            return Err("Relay message too long".to_string());
+        }
+        line.push(byte);
+    }
+    RelayMessage::parse(&String::from_utf8_lossy(&line))
+}
+
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &RelayMessage,
+) -> Result<(), String> {
+    writer
+        .write_all(message.to_line().as_bytes())
+        .await
+        .map_err(|e| format!("Relay connection lost - {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_what_it_writes() {
+        let messages = [
+            RelayMessage::Challenge("abc".to_string()),
+            RelayMessage::Hello {
+                name: "home".to_string(),
+                signature: sign("token", "abc"),
+            },
+            RelayMessage::Ok,
+            RelayMessage::Err("unknown pairing".to_string()),
+            RelayMessage::Connect("42".to_string()),
+            RelayMessage::Data {
+                name: "home".to_string(),
+                id: "42".to_string(),
+                signature: sign("token", "42"),
+            },
+            RelayMessage::Ping,
+        ];
+        for message in messages {
+            assert_eq!(Ok(message.clone()), RelayMessage::parse(&message.to_line()));
+        }
+        assert!(RelayMessage::parse("HELLO home").is_err());
+        assert!(RelayMessage::parse("GET / HTTP/1.1").is_err());
+    }
+
+    #[test]
+    fn should_verify_signatures() {
+        let signature = sign("token", "challenge");
+        assert!(verify("token", "challenge", &signature));
+        assert!(!verify("other", "challenge", &signature));
+        assert!(!verify("token", "other", &signature));
+        assert!(!verify("token", "challenge", "zz"));
+    }
+}
@@ -1,5 +1,8 @@
+use crate::dtos::FileDescription;
 use crate::matchable_path::MatchablePath;
+use crate::utc_millis::UtcMillis;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub enum SyncInstruction {
@@ -7,3 +10,100 @@ pub enum SyncInstruction {
     Download(MatchablePath),
     Delete(MatchablePath),
 }
+
+impl SyncInstruction {
+    pub fn path(&self) -> &MatchablePath {
+        match self {
+            SyncInstruction::Upload(path)
+            | SyncInstruction::Download(path)
+            | SyncInstruction::Delete(path) => path,
+        }
+    }
+}
+
+/// the latest history event of a path - what the server compares a client's file with
+pub trait LatestEvent {
+    fn relative_path(&self) -> &MatchablePath;
+    /// time of the event on client side
+    fn utc_millis(&self) -> &UtcMillis;
+    fn size_in_bytes(&self) -> u64;
+    fn is_delete(&self) -> bool;
+}
+
+/// what a client has to do so its files match the latest events of the server
+///
+/// files are compared by size and modification time only - no hashing - both sides are indexed
+/// by path, so large watch groups take linear time
+pub fn diff<E: LatestEvent>(
+    target: &[E],
+    client_sync_state: &[FileDescription],
+) -> Vec<SyncInstruction> {
+    let mut client: HashMap<&MatchablePath, &FileDescription> =
+        HashMap::with_capacity(client_sync_state.len());
+    for desc in client_sync_state {
+        // first one wins if a client reports a path twice
+        client.entry(&desc.relative_path).or_insert(desc);
+    }
+    let server: HashSet<&MatchablePath> = target.iter().map(|e| e.relative_path()).collect();
+    let mut instructions = Vec::new();
+
+    for event in target {
+        match client.get(event.relative_path()) {
+            // client doesn't have the file at all
+            None => {
+                if !event.is_delete() {
+                    instructions.push(SyncInstruction::Download(event.relative_path().clone()))
+                }
+            }
+            Some(client_equivalent) => {
+                if client_equivalent.size_in_bytes == event.size_in_bytes()
+                    && !event.is_delete()
+                    && client_equivalent.last_updated_utc_millis >= *event.utc_millis()
+                {
+                    // same size - just ignore even if client is newer (might have been write-operation without change)
+                    // an older client copy gets downloaded again (e.g. after an admin resync)
+                    continue;
+                } else if client_equivalent.last_updated_utc_millis < *event.utc_millis() {
+                    // differs in size and client is outdated
+                    instructions.push(if event.is_delete() {
+                        // client outdated needs to delete his version
+                        SyncInstruction::Delete(event.relative_path().clone())
+                    } else {
+                        // client outdated needs to download new version
+                        SyncInstruction::Download(event.relative_path().clone())
+                    })
+                } else {
+                    instructions.push(SyncInstruction::Upload(event.relative_path().clone()))
+                }
+            }
+        }
+    }
+
+    for desc in client_sync_state {
+        if !server.contains(&desc.relative_path) {
+            instructions.push(SyncInstruction::Upload(desc.relative_path.clone()));
+        }
+    }
+    instructions
+}
+
+/// on os level files are just there or not so we got to keep track of the last state
+/// and diff it with the new one in order to determine file deletion and propagate the event accordingly
+/// [`https://docs.rs/notify/latest/notify/index.html`] could also be an option for later, but
+/// I prefer a more native approach for now
+///
+/// Note: the last state must not contain files deleted on instruction of the server - otherwise
+/// their delete is sent again and might remove a version uploaded by another client meanwhile
+pub fn determine_deleted_files(
+    last: &[FileDescription],
+    curr: &[FileDescription],
+) -> Vec<FileDescription> {
+    last.iter()
+        .filter(|prev_description| {
+            !curr.iter().any(|curr_description| {
+                prev_description.relative_path == curr_description.relative_path
+            })
+        })
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,98 @@
+use crate::glob::matches_glob;
+use serde::{Deserialize, Serialize};
+
+/// files editors and browsers create next to the real one and delete again seconds later
+pub const BUILTIN_TEMP_FILE_PATTERNS: [&str; 11] = [
+    // ms office lock files
+    "~$*",
+    // vim swap files
+    "*.swp",
+    "*.swo",
+    "*.swx",
+    "*.tmp",
+    // backups of emacs, gedit, ...
+    "*~",
+    ".#*",
+    // libreoffice lock files
+    ".~lock.*#",
+    // unfinished browser downloads
+    "*.crdownload",
+    "*.part",
+    "*.partial",
+];
+
+/// file names (not paths) matching these globs are left out when scanning a watch group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TempFilePatterns {
+    /// whether [`BUILTIN_TEMP_FILE_PATTERNS`] apply
+    pub builtin: bool,
+    /// additional globs (`*` and `?`, case-insensitive)
+    pub patterns: Vec<String>,
+}
+
+impl Default for TempFilePatterns {
+    fn default() -> Self {
+        Self {
+            builtin: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl TempFilePatterns {
+    /// nothing is a temp file - for scans of the server's own storage
+    pub fn none() -> Self {
+        Self {
+            builtin: false,
+            patterns: Vec::new(),
+        }
+    }
+
+    pub fn is_temp_file(&self, file_name: &str) -> bool {
+        let builtin: &[&str] = if self.builtin {
+            &BUILTIN_TEMP_FILE_PATTERNS
+        } else {
+            &[]
+        };
+        builtin
+            .iter()
+            .copied()
+            .chain(self.patterns.iter().map(String::as_str))
+            .any(|pattern| matches_glob(pattern, file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_match_builtin_temp_files() {
+        let patterns = TempFilePatterns::default();
+        for name in [
+            "~$report.docx",
+            ".notes.md.swp",
+            "data.TMP",
+            "todo.txt~",
+            ".~lock.table.ods#",
+            "video.mp4.crdownload",
+        ] {
+            assert!(patterns.is_temp_file(name), "{name}");
+        }
+        for name in ["report.docx", "swp.txt", "template.md", "~notes.txt"] {
+            assert!(!patterns.is_temp_file(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn should_apply_extra_patterns_without_builtin() {
+        let patterns = TempFilePatterns {
+            builtin: false,
+            patterns: vec!["*.bak".to_string()],
+        };
+        assert!(patterns.is_temp_file("db.BAK"));
+        assert!(!patterns.is_temp_file("~$report.docx"));
+        assert!(!TempFilePatterns::none().is_temp_file("~$report.docx"));
+    }
+}
@@ -0,0 +1,320 @@
+//! in-memory model of virtual clients syncing with the server
+//!
+//! follows the sync loop of the client (scan → send deletes → ask for instructions → execute) and
+//! the upload/delete handling of the server, using [`diff`] and [`determine_deleted_files`] of
+//! `shared` - so property tests can throw random operations at the real sync algorithm
+//!
+//! there is a single clock: a write stamps the file with the current time, a download with the
+//! time of the server's event - deletes get the time they are reported to the server
+
+use shared::dtos::FileDescription;
+use shared::matchable_path::MatchablePath;
+use shared::sync_instruction::{LatestEvent, SyncInstruction, determine_deleted_files, diff};
+use shared::utc_millis::UtcMillis;
+use std::collections::{HashMap, HashSet};
+
+/// paths the operations pick from - few of them, so clients keep running into each other
+pub const PATHS: [&str; 3] = ["a.txt", "dir/b.txt", "dir/sub/c.txt"];
+/// upper bound of sync rounds until the clients have to agree
+pub const MAX_SETTLE_ROUNDS: usize = 10;
+
+/// the algorithm compares sizes only - so every write produces content of a size never seen
+/// before (the content *is* its size)
+pub type Content = u64;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Op {
+    Write { client: usize, path: usize },
+    Delete { client: usize, path: usize },
+    Sync { client: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LocalFile {
+    content: Content,
+    modified: u64,
+}
+
+#[derive(Debug, Default)]
+struct Client {
+    files: HashMap<MatchablePath, LocalFile>,
+    /// files the user removed (not the server) - only their deletes are versions
+    deleted_by_user: HashSet<MatchablePath>,
+    last_scan: Option<Vec<FileDescription>>,
+}
+
+impl Client {
+    fn scan(&self) -> Vec<FileDescription> {
+        self.files
+            .iter()
+            .map(|(path, file)| FileDescription {
+                file_name: path.tail(),
+                relative_path: path.clone(),
+                size_in_bytes: file.content,
+                file_type: "txt".to_string(),
+                last_updated_utc_millis: UtcMillis::from(file.modified),
+            })
+            .collect()
+    }
+
+    fn contents(&self) -> HashMap<MatchablePath, Content> {
+        self.files
+            .iter()
+            .map(|(path, file)| (path.clone(), file.content))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ServerEvent {
+    relative_path: MatchablePath,
+    utc_millis: UtcMillis,
+    size_in_bytes: u64,
+    deleted: bool,
+}
+
+impl LatestEvent for ServerEvent {
+    fn relative_path(&self) -> &MatchablePath {
+        &self.relative_path
+    }
+
+    fn utc_millis(&self) -> &UtcMillis {
+        &self.utc_millis
+    }
+
+    fn size_in_bytes(&self) -> u64 {
+        self.size_in_bytes
+    }
+
+    fn is_delete(&self) -> bool {
+        self.deleted
+    }
+}
+
+#[derive(Debug, Default)]
+struct Server {
+    latest: HashMap<MatchablePath, ServerEvent>,
+    files: HashMap<MatchablePath, Content>,
+}
+
+impl Server {
+    /// rejected ("not latest") if the history has a newer event of the path
+    fn upload(&mut self, path: &MatchablePath, file: &LocalFile) {
+        let latest = self
+            .latest
+            .get(path)
+            .map(|e| e.utc_millis.as_u64())
+            .unwrap_or(0);
+        if file.modified < latest {
+            return;
+        }
+        self.record(path, file.modified, file.content, false);
+        self.files.insert(path.clone(), file.content);
+    }
+
+    /// recorded even if the file is gone already
+    fn delete(&mut self, path: &MatchablePath, now: u64) {
+        self.record(path, now, 0, true);
+        self.files.remove(path);
+    }
+
+    fn record(&mut self, path: &MatchablePath, utc_millis: u64, size_in_bytes: u64, deleted: bool) {
+        let event = ServerEvent {
+            relative_path: path.clone(),
+            utc_millis: UtcMillis::from(utc_millis),
+            size_in_bytes,
+            deleted,
+        };
+        self.latest.insert(path.clone(), event);
+    }
+}
+
+/// a state of a path some client published - a scanned file or a delete it reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    pub relative_path: MatchablePath,
+    pub utc_millis: u64,
+    /// `None` for a delete
+    pub content: Option<Content>,
+}
+
+#[derive(Debug)]
+pub struct Simulation {
+    clock: u64,
+    server: Server,
+    clients: Vec<Client>,
+    versions: Vec<Version>,
+}
+
+impl Simulation {
+    pub fn new(clients: usize) -> Self {
+        Simulation {
+            clock: 0,
+            server: Server::default(),
+            clients: (0..clients).map(|_| Client::default()).collect(),
+            versions: Vec::new(),
+        }
+    }
+
+    pub fn apply(&mut self, op: Op) {
+        match op {
+            Op::Write { client, path } => {
+                let now = self.tick();
+                let path = MatchablePath::from(PATHS[path]);
+                let client = &mut self.clients[client];
+                client.deleted_by_user.remove(&path);
+                let file = LocalFile {
+                    content: now,
+                    modified: now,
+                };
+                client.files.insert(path, file);
+            }
+            Op::Delete { client, path } => {
+                let path = MatchablePath::from(PATHS[path]);
+                let client = &mut self.clients[client];
+                if client.files.remove(&path).is_some() {
+                    client.deleted_by_user.insert(path);
+                }
+            }
+            Op::Sync { client } => self.sync(client),
+        }
+    }
+
+    /// one poll cycle of the client - see `loop_scan` of the client
+    pub fn sync(&mut self, client_idx: usize) {
+        let scan = self.clients[client_idx].scan();
+        for description in &scan {
+            self.versions.push(Version {
+                relative_path: description.relative_path.clone(),
+                utc_millis: description.last_updated_utc_millis.as_u64(),
+                content: Some(description.size_in_bytes),
+            });
+        }
+
+        let mut deleted_files = Vec::new();
+        if let Some(last) = self.clients[client_idx].last_scan.take() {
+            deleted_files = determine_deleted_files(&last, &scan);
+            for deleted in &deleted_files {
+                let now = self.tick();
+                let path = &deleted.relative_path;
+                if self.clients[client_idx].deleted_by_user.remove(path) {
+                    self.versions.push(Version {
+                        relative_path: path.clone(),
+                        utc_millis: now,
+                        content: None,
+                    });
+                }
+                self.server.delete(path, now);
+            }
+        }
+
+        let latest: Vec<ServerEvent> = self.server.latest.values().cloned().collect();
+        let mut descriptions = scan;
+        for instruction in diff(&latest, &descriptions) {
+            match instruction {
+                SyncInstruction::Upload(path) => {
+                    if let Some(file) = self.clients[client_idx].files.get(&path) {
+                        self.server.upload(&path, file);
+                    }
+                }
+                SyncInstruction::Download(path) => {
+                    if deleted_files.iter().any(|d| d.relative_path == path) {
+                        continue;
+                    }
+                    let (Some(&content), Some(event)) =
+                        (self.server.files.get(&path), self.server.latest.get(&path))
+                    else {
+                        continue;
+                    };
+                    let file = LocalFile {
+                        content,
+                        modified: event.utc_millis.as_u64(),
+                    };
+                    let client = &mut self.clients[client_idx];
+                    client.deleted_by_user.remove(&path);
+                    client.files.insert(path, file);
+                }
+                SyncInstruction::Delete(path) => {
+                    self.clients[client_idx].files.remove(&path);
+                    descriptions.retain(|d| d.relative_path != path);
+                }
+            }
+        }
+        self.clients[client_idx].last_scan = Some(descriptions);
+    }
+
+    /// lets every client sync until a whole round changes nothing - false if that takes more than
+    /// [`MAX_SETTLE_ROUNDS`]
+    pub fn settle(&mut self) -> bool {
+        for _ in 0..MAX_SETTLE_ROUNDS {
+            let before = self.state();
+            for client in 0..self.clients.len() {
+                self.sync(client);
+            }
+            if self.state() == before {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// every client holds exactly the files of the server
+    pub fn check_converged(&self) -> Result<(), String> {
+        for (idx, client) in self.clients.iter().enumerate() {
+            let contents = client.contents();
+            if contents != self.server.files {
+                return Err(format!(
+                    "client {idx} has {contents:?} but the server {:?}",
+                    self.server.files
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// every path ends up in its newest published version - nothing newer was overwritten or
+    /// deleted
+    pub fn check_no_data_loss(&self) -> Result<(), String> {
+        let mut newest: HashMap<&MatchablePath, &Version> = HashMap::new();
+        for version in &self.versions {
+            let entry = newest.entry(&version.relative_path).or_insert(version);
+            if version.utc_millis > entry.utc_millis {
+                *entry = version;
+            }
+        }
+        for (path, version) in &newest {
+            let actual = self.server.files.get(*path).copied();
+            if actual != version.content {
+                return Err(format!(
+                    "{} should be {:?} (published at {}) but is {:?}",
+                    path.to_serialized_string(),
+                    version.content,
+                    version.utc_millis,
+                    actual
+                ));
+            }
+        }
+        match self.server.files.keys().find(|p| !newest.contains_key(p)) {
+            Some(path) => Err(format!(
+                "{} was never published",
+                path.to_serialized_string()
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn state(
+        &self,
+    ) -> (
+        HashMap<MatchablePath, ServerEvent>,
+        Vec<HashMap<MatchablePath, LocalFile>>,
+    ) {
+        let clients = self.clients.iter().map(|c| c.files.clone()).collect();
+        (self.server.latest.clone(), clients)
+    }
+}
@@ -0,0 +1,54 @@
+use proptest::prelude::*;
+use sync_tests::{Op, PATHS, Simulation};
+
+const MAX_CLIENTS: usize = 4;
+
+fn op(clients: usize) -> impl Strategy<Value = Op> {
+    let path = 0..PATHS.len();
+    prop_oneof![
+        3 => (0..clients, path.clone()).prop_map(|(client, path)| Op::Write { client, path }),
+        2 => (0..clients, path).prop_map(|(client, path)| Op::Delete { client, path }),
+        3 => (0..clients).prop_map(|client| Op::Sync { client }),
+    ]
+}
+
+fn scenario() -> impl Strategy<Value = (usize, Vec<Op>)> {
+    (2..=MAX_CLIENTS)
+        .prop_flat_map(|clients| (Just(clients), prop::collection::vec(op(clients), 0..60)))
+}
+
+proptest! {
+    #[test]
+    fn should_converge_without_losing_newer_versions((clients, ops) in scenario()) {
+        let mut simulation = Simulation::new(clients);
+        for op in ops {
+            simulation.apply(op);
+        }
+
+        prop_assert!(simulation.settle(), "clients didn't settle: {:#?}", simulation);
+        prop_assert_eq!(Ok(()), simulation.check_converged());
+        prop_assert_eq!(Ok(()), simulation.check_no_data_loss());
+    }
+}
+
+#[test]
+fn should_not_resend_deletes_executed_on_instruction() {
+    let (a, b) = (0, 1);
+    let mut simulation = Simulation::new(2);
+    simulation.apply(Op::Write { client: a, path: 0 });
+    simulation.apply(Op::Sync { client: a });
+    simulation.apply(Op::Sync { client: b });
+    simulation.apply(Op::Sync { client: b });
+    simulation.apply(Op::Delete { client: b, path: 0 });
+    simulation.apply(Op::Sync { client: b });
+    // a deletes its copy on instruction ...
+    simulation.apply(Op::Sync { client: a });
+    // ... while b writes the file again
+    simulation.apply(Op::Write { client: b, path: 0 });
+    simulation.apply(Op::Sync { client: b });
+    simulation.apply(Op::Sync { client: a });
+
+    assert!(simulation.settle());
+    assert_eq!(Ok(()), simulation.check_converged());
+    assert_eq!(Ok(()), simulation.check_no_data_loss());
+}
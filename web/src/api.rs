@@ -1,12 +1,19 @@
 use gloo_net::http::Request;
 use shared::{
     dtos::{
-        ClientDto, ClientUpdateDto, ClientWatchGroupCreateDto, ClientWatchGroupDto,
-        ClientWatchGroupUpdateDto, FileDescription, LinkCreateDto, LinkDeleteDto, LinkDto,
-        MonitorData, ServerWatchGroup, WatchGroupNameDto,
+        BandwidthStatsDto, ClientDetailDto, ClientDto, ClientRecentActivityDto, ClientUpdateDto,
+        ClientWatchGroupCreateDto, ClientWatchGroupDto, ClientWatchGroupUpdateDto, DiffDto,
+        Durability, DurabilityDto, FileDescription, FilePreviewDto, FileStatusDto, FileVersionDto,
+        GrowthReportDto, LinkCreateDto, LinkDeleteDto, LinkDto, MonitorData, OwnerDto, PauseDto,
+        PendingWorkDto, PriorityDto, RegistrationTemplateCreateDto, RegistrationTemplateDto,
+        SearchHitDto, ServerWatchGroup, ShareLinkCreateDto, ShareLinkDto, SyncBlackoutCreateDto,
+        SyncBlackoutDto, TopFilesReportDto, TransferPathDto, TransferResultDto, UiSettingsDto,
+        UserDto, UserNameDto, UserRole, UserRoleDto, UserTokenDto, VerifyWritesDto,
+        WatchGroupDetailDto, WatchGroupNameDto, WatchGroupStatsDto, WebhookCreateDto, WebhookDto,
     },
-    endpoint::ServerEndpoint,
+    endpoint::{API_TOKEN_COOKIE_KEY, ServerEndpoint},
 };
+use wasm_bindgen::JsCast;
 
 pub async fn fetch_clients() -> Result<Vec<ClientDto>, String> {
     Request::get(ServerEndpoint::ApiClients.to_str())
@@ -54,6 +61,36 @@ pub async fn update_watch_group(id: i64, dto: &WatchGroupNameDto) -> Result<Stri
     if resp.ok() { Ok(text) } else { Err(text) }
 }
 
+/// force = delete even if clients are still attached
+pub async fn delete_watch_group(id: i64, force: bool) -> Result<(), String> {
+    let resp = Request::delete(&format!(
+        "{}?force={force}",
+        ServerEndpoint::ApiWatchGroup
+            .to_str()
+            .replace("{id}", &id.to_string())
+    ))
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_client_pending(client_id: &str) -> Result<PendingWorkDto, String> {
+    let resp = Request::get(
+        &ServerEndpoint::ApiClientPending
+            .to_str()
+            .replace("{id}", client_id),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
 pub async fn fetch_client_watch_groups(
     client_id: &str,
 ) -> Result<Vec<ClientWatchGroupDto>, String> {
@@ -85,15 +122,178 @@ pub async fn update_client(client_id: &str, dto: &ClientUpdateDto) -> Result<(),
     if resp.ok() { Ok(()) } else { Err(text) }
 }
 
-pub async fn delete_client(client_id: &str) -> Result<(), String> {
+pub async fn pause_client(client_id: &str, paused: bool) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiClientPause
+            .to_str()
+            .replace("{id}", client_id),
+    )
+    .json(&PauseDto { paused })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn pause_client_watch_group(
+    client_id: &str,
+    wg_id: i64,
+    paused: bool,
+) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiClientWatchGroupPause
+            .to_str()
+            .replace("{id}", client_id)
+            .replace("{wg_id}", &wg_id.to_string()),
+    )
+    .json(&PauseDto { paused })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_sync_blackouts(client_id: &str) -> Result<Vec<SyncBlackoutDto>, String> {
+    Request::get(
+        &ServerEndpoint::ApiClientSyncBlackouts
+            .to_str()
+            .replace("{id}", client_id),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?
+    .json()
+    .await
+    .map_err(|e| e.to_string())
+}
+
+pub async fn create_sync_blackout(
+    client_id: &str,
+    dto: &SyncBlackoutCreateDto,
+) -> Result<(), String> {
+    let resp = Request::post(
+        &ServerEndpoint::ApiClientSyncBlackouts
+            .to_str()
+            .replace("{id}", client_id),
+    )
+    .json(dto)
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn delete_sync_blackout(client_id: &str, blackout_id: i64) -> Result<(), String> {
     let resp = Request::delete(
-        &ServerEndpoint::ApiClient
+        &ServerEndpoint::ApiClientSyncBlackout
+            .to_str()
+            .replace("{id}", client_id)
+            .replace("{blackout_id}", &blackout_id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+/// anonymize = file events of the client no longer point to it
+pub async fn delete_client(client_id: &str, anonymize: bool) -> Result<(), String> {
+    let resp = Request::delete(&format!(
+        "{}?anonymize={anonymize}",
+        ServerEndpoint::ApiClient
+            .to_str()
+            .replace("{id}", client_id)
+    ))
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_client_detail(client_id: &str) -> Result<ClientDetailDto, String> {
+    let resp = Request::get(
+        &ServerEndpoint::ApiClientDetail
             .to_str()
             .replace("{id}", client_id),
     )
     .send()
     .await
     .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_client_activity(client_id: &str) -> Result<ClientRecentActivityDto, String> {
+    let resp = Request::get(
+        &ServerEndpoint::ApiClientActivity
+            .to_str()
+            .replace("{id}", client_id),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// the server forgets the client's file lists and pending work - rebuilt with its next syncs
+pub async fn resync_client(client_id: &str) -> Result<(), String> {
+    let resp = Request::post(
+        &ServerEndpoint::ApiClientResync
+            .to_str()
+            .replace("{id}", client_id),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_registration_templates() -> Result<Vec<RegistrationTemplateDto>, String> {
+    Request::get(ServerEndpoint::ApiRegistrationTemplates.to_str())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn create_registration_template(
+    dto: &RegistrationTemplateCreateDto,
+) -> Result<(), String> {
+    let resp = Request::post(ServerEndpoint::ApiRegistrationTemplates.to_str())
+        .json(dto)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn delete_registration_template(id: i64) -> Result<(), String> {
+    let resp = Request::delete(
+        &ServerEndpoint::ApiRegistrationTemplate
+            .to_str()
+            .replace("{id}", &id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
     let text = resp.text().await.map_err(|e| e.to_string())?;
     if resp.ok() { Ok(()) } else { Err(text) }
 }
@@ -150,6 +350,189 @@ pub async fn delete_client_watch_group(client_id: &str, wg_id: i64) -> Result<()
     if resp.ok() { Ok(()) } else { Err(text) }
 }
 
+pub async fn set_client_owner(client_id: &str, owner_id: Option<i64>) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiClientOwner
+            .to_str()
+            .replace("{id}", client_id),
+    )
+    .json(&OwnerDto { owner_id })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn set_watch_group_owner(wg_id: i64, owner_id: Option<i64>) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiWatchGroupOwner
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .json(&OwnerDto { owner_id })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn set_watch_group_durability(wg_id: i64, durability: Durability) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiWatchGroupDurability
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .json(&DurabilityDto { durability })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn set_watch_group_verify_writes(wg_id: i64, verify_writes: bool) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiWatchGroupVerifyWrites
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .json(&VerifyWritesDto { verify_writes })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_webhooks(wg_id: i64) -> Result<Vec<WebhookDto>, String> {
+    Request::get(
+        &ServerEndpoint::ApiWatchGroupWebhooks
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?
+    .json()
+    .await
+    .map_err(|e| e.to_string())
+}
+
+pub async fn create_webhook(wg_id: i64, dto: &WebhookCreateDto) -> Result<(), String> {
+    let resp = Request::post(
+        &ServerEndpoint::ApiWatchGroupWebhooks
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .json(dto)
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn delete_webhook(wg_id: i64, hook_id: i64) -> Result<(), String> {
+    let resp = Request::delete(
+        &ServerEndpoint::ApiWatchGroupWebhook
+            .to_str()
+            .replace("{id}", &wg_id.to_string())
+            .replace("{hook_id}", &hook_id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_share_links(wg_id: i64) -> Result<Vec<ShareLinkDto>, String> {
+    Request::get(
+        &ServerEndpoint::ApiWatchGroupShareLinks
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?
+    .json()
+    .await
+    .map_err(|e| e.to_string())
+}
+
+pub async fn create_share_link(wg_id: i64, dto: &ShareLinkCreateDto) -> Result<(), String> {
+    let resp = Request::post(
+        &ServerEndpoint::ApiWatchGroupShareLinks
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .json(dto)
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn delete_share_link(wg_id: i64, token: &str) -> Result<(), String> {
+    let resp = Request::delete(
+        &ServerEndpoint::ApiWatchGroupShareLink
+            .to_str()
+            .replace("{id}", &wg_id.to_string())
+            .replace("{token}", token),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_ui_settings() -> Result<UiSettingsDto, String> {
+    let resp = Request::get(ServerEndpoint::ApiSettings.to_str())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn save_ui_settings(settings: &UiSettingsDto) -> Result<(), String> {
+    let resp = Request::put(ServerEndpoint::ApiSettings.to_str())
+        .json(settings)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn set_watch_group_priority(wg_id: i64, priority: i64) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiWatchGroupPriority
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .json(&PriorityDto { priority })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
 pub async fn delete_watch_group_file(wg_id: i64, path: &str) -> Result<(), String> {
     let encoded = js_sys::encode_uri_component(path);
     let url = format!(
@@ -167,6 +550,29 @@ pub async fn delete_watch_group_file(wg_id: i64, path: &str) -> Result<(), Strin
     if resp.ok() { Ok(()) } else { Err(text) }
 }
 
+/// `move_files` deletes the files in the source watch group after copying
+pub async fn transfer_files(
+    dto: &TransferPathDto,
+    move_files: bool,
+) -> Result<TransferResultDto, String> {
+    let endpoint = if move_files {
+        ServerEndpoint::ApiAdminMove
+    } else {
+        ServerEndpoint::ApiAdminCopy
+    };
+    let resp = Request::post(endpoint.to_str())
+        .json(dto)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if resp.ok() {
+        resp.json().await.map_err(|e| e.to_string())
+    } else {
+        Err(resp.text().await.map_err(|e| e.to_string())?)
+    }
+}
+
 pub async fn fetch_watch_group_files(wg_id: i64) -> Result<Vec<FileDescription>, String> {
     Request::get(
         &ServerEndpoint::ApiWatchGroupFiles
@@ -181,6 +587,94 @@ pub async fn fetch_watch_group_files(wg_id: i64) -> Result<Vec<FileDescription>,
     .map_err(|e| e.to_string())
 }
 
+/// multipart upload into `dir` of the watch group (unix-delimited, empty = root)
+pub async fn upload_watch_group_file(
+    wg_id: i64,
+    dir: &str,
+    file: &web_sys::File,
+) -> Result<(), String> {
+    let form = web_sys::FormData::new().map_err(|e| format!("{e:?}"))?;
+    form.append_with_blob_and_filename("file", file, &file.name())
+        .map_err(|e| format!("{e:?}"))?;
+    let encoded = js_sys::encode_uri_component(dir);
+    let url = format!(
+        "{}?path={}",
+        ServerEndpoint::ApiWatchGroupFiles
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+        String::from(encoded)
+    );
+    let resp = Request::post(&url)
+        .body(form)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn fetch_watch_group_detail(wg_id: i64) -> Result<WatchGroupDetailDto, String> {
+    let resp = Request::get(
+        &ServerEndpoint::ApiWatchGroupDetail
+            .to_str()
+            .replace("{id}", &wg_id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_watch_group_stats(wg_id: i64) -> Result<WatchGroupStatsDto, String> {
+    let resp = Request::get(
+        &ServerEndpoint::ApiStats
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_top_files(wg_id: i64) -> Result<TopFilesReportDto, String> {
+    let resp = Request::get(
+        &ServerEndpoint::ApiReportTopFiles
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_growth(wg_id: i64, days: u32) -> Result<GrowthReportDto, String> {
+    let resp = Request::get(&format!(
+        "{}?days={days}",
+        ServerEndpoint::ApiReportGrowth
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string())
+    ))
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
 pub fn watch_group_file_preview_url(wg_id: i64, path: &str) -> String {
     let encoded = js_sys::encode_uri_component(path);
     format!(
@@ -190,6 +684,149 @@ pub fn watch_group_file_preview_url(wg_id: i64, path: &str) -> String {
     )
 }
 
+/// downscaled jpeg fitting into `size` x `size` (not for svgs)
+pub fn thumbnail_url(wg_id: i64, path: &str, size: u32) -> String {
+    let encoded = js_sys::encode_uri_component(path);
+    format!(
+        "{}?path={}&size={}",
+        ServerEndpoint::ApiThumbnail
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+        String::from(encoded),
+        size
+    )
+}
+
+/// zip of everything below `dir` (unix-delimited, empty = whole watch group)
+pub fn zip_download_url(wg_id: i64, dir: &str) -> String {
+    let encoded = js_sys::encode_uri_component(dir);
+    format!(
+        "{}?path={}",
+        ServerEndpoint::DownloadZip
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+        String::from(encoded)
+    )
+}
+
+/// manifest of the watch group for `client seed` (api token cookie like the zip download)
+pub fn manifest_url(wg_id: i64) -> String {
+    ServerEndpoint::ApiWatchGroupManifest
+        .to_str()
+        .replace("{id}", &wg_id.to_string())
+}
+
+pub async fn search(query: &str, include_content: bool) -> Result<Vec<SearchHitDto>, String> {
+    let encoded = js_sys::encode_uri_component(query);
+    let url = format!(
+        "{}?q={}&content={}",
+        ServerEndpoint::ApiSearch.to_str(),
+        String::from(encoded),
+        include_content
+    );
+    let resp = Request::get(&url).send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_file_history(wg_id: i64, path: &str) -> Result<Vec<FileVersionDto>, String> {
+    let encoded = js_sys::encode_uri_component(path);
+    let url = format!(
+        "{}?path={}",
+        ServerEndpoint::ApiHistory
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+        String::from(encoded)
+    );
+    let resp = Request::get(&url).send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_preview(wg_id: i64, path: &str) -> Result<FilePreviewDto, String> {
+    let encoded = js_sys::encode_uri_component(path);
+    let url = format!(
+        "{}?path={}",
+        ServerEndpoint::ApiPreview
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+        String::from(encoded)
+    );
+    let resp = Request::get(&url).send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_file_status(wg_id: i64, path: &str) -> Result<FileStatusDto, String> {
+    let encoded = js_sys::encode_uri_component(path);
+    let url = format!(
+        "{}?path={}",
+        ServerEndpoint::ApiFileStatus
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+        String::from(encoded)
+    );
+    let resp = Request::get(&url).send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_diff(wg_id: i64, path: &str, from: &str, to: &str) -> Result<DiffDto, String> {
+    let encoded = js_sys::encode_uri_component(path);
+    let url = format!(
+        "{}?path={}&from={}&to={}",
+        ServerEndpoint::ApiDiff
+            .to_str()
+            .replace("{wg_id}", &wg_id.to_string()),
+        String::from(encoded),
+        from,
+        to
+    );
+    let resp = Request::get(&url).send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub fn file_history_url(wg_id: i64, path: &str) -> String {
+    let encoded = js_sys::encode_uri_component(path);
+    format!(
+        "/app/watch-groups/{}/history?path={}",
+        wg_id,
+        String::from(encoded)
+    )
+}
+
+pub fn search_url(query: &str) -> String {
+    let encoded = js_sys::encode_uri_component(query);
+    format!("/app/search?q={}", String::from(encoded))
+}
+
+/// file browser of a watch group, opened at `dir` (unix-delimited, empty = root)
+pub fn watch_group_dir_url(wg_id: i64, dir: &str) -> String {
+    let encoded = js_sys::encode_uri_component(dir);
+    format!("/app/watch-groups/{}?path={}", wg_id, String::from(encoded))
+}
+
+/// phone friendly browser of a watch group, opened at `dir`
+pub fn mobile_dir_url(wg_id: i64, dir: &str) -> String {
+    let encoded = js_sys::encode_uri_component(dir);
+    format!(
+        "/app/m/watch-groups/{}?path={}",
+        wg_id,
+        String::from(encoded)
+    )
+}
+
 pub fn gallery_url(wg_id: i64, path: &str) -> String {
     let encoded = js_sys::encode_uri_component(path);
     format!(
@@ -199,14 +836,28 @@ pub fn gallery_url(wg_id: i64, path: &str) -> String {
     )
 }
 
-pub async fn fetch_monitor_data() -> Result<MonitorData, String> {
-    Request::get(ServerEndpoint::ApiMonitor.to_str())
+/// `from` in utc millis - None for everything the server kept
+pub async fn fetch_monitor_data(from: Option<u64>) -> Result<MonitorData, String> {
+    let url = match from {
+        Some(from) => format!("{}?from={}", ServerEndpoint::ApiMonitor.to_str(), from),
+        None => ServerEndpoint::ApiMonitor.to_str().to_string(),
+    };
+    let resp = Request::get(&url).send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_bandwidth_stats() -> Result<BandwidthStatsDto, String> {
+    let resp = Request::get(ServerEndpoint::ApiStatsBandwidth.to_str())
         .send()
         .await
-        .map_err(|e| e.to_string())?
-        .json()
-        .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.map_err(|e| e.to_string())?);
+    }
+    resp.json().await.map_err(|e| e.to_string())
 }
 
 // links
@@ -243,3 +894,82 @@ pub async fn delete_link(url: &str) -> Result<(), String> {
     let text = resp.text().await.map_err(|e| e.to_string())?;
     if resp.ok() { Ok(()) } else { Err(text) }
 }
+
+// users
+
+pub async fn fetch_users() -> Result<Vec<UserDto>, String> {
+    Request::get(ServerEndpoint::ApiUsers.to_str())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn create_user(dto: &UserNameDto) -> Result<(), String> {
+    let resp = Request::post(ServerEndpoint::ApiUsers.to_str())
+        .json(dto)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn delete_user(id: i64) -> Result<(), String> {
+    let resp = Request::delete(
+        &ServerEndpoint::ApiUser
+            .to_str()
+            .replace("{id}", &id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn set_user_role(id: i64, role: UserRole) -> Result<(), String> {
+    let resp = Request::put(
+        &ServerEndpoint::ApiUserRole
+            .to_str()
+            .replace("{id}", &id.to_string()),
+    )
+    .json(&UserRoleDto { role })
+    .map_err(|e| e.to_string())?
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    if resp.ok() { Ok(()) } else { Err(text) }
+}
+
+pub async fn create_user_token(id: i64) -> Result<UserTokenDto, String> {
+    let resp = Request::post(
+        &ServerEndpoint::ApiUserToken
+            .to_str()
+            .replace("{id}", &id.to_string()),
+    )
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+    if resp.ok() {
+        resp.json().await.map_err(|e| e.to_string())
+    } else {
+        Err(resp.text().await.map_err(|e| e.to_string())?)
+    }
+}
+
+/// the browser sends the cookie along with every /api request (also from the pwa)
+pub fn store_api_token(token: &str) {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.dyn_into::<web_sys::HtmlDocument>().ok());
+    if let Some(document) = document {
+        let _ = document.set_cookie(&format!(
+            "{API_TOKEN_COOKIE_KEY}={token}; path=/; max-age=31536000; SameSite=Strict"
+        ));
+    }
+}
@@ -0,0 +1,384 @@
+use leptos::prelude::*;
+
+use crate::i18n::t;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 260.0;
+const LEFT: f64 = 52.0;
+const RIGHT: f64 = 12.0;
+const TOP: f64 = 10.0;
+const BOTTOM: f64 = 26.0;
+const PLOT_WIDTH: f64 = WIDTH - LEFT - RIGHT;
+const PLOT_HEIGHT: f64 = HEIGHT - TOP - BOTTOM;
+
+/// one line (or bar stack layer) - x in unix millis, sorted ascending
+#[derive(Debug, Clone)]
+pub struct ChartSeries {
+    pub label: &'static str,
+    pub color: &'static str,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// visible time span and value range, maps data to svg coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frame {
+    x_min: f64,
+    x_max: f64,
+    y_max: f64,
+}
+
+impl Frame {
+    fn x(&self, value: f64) -> f64 {
+        LEFT + (value - self.x_min) / (self.x_max - self.x_min) * PLOT_WIDTH
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        TOP + PLOT_HEIGHT - value.clamp(0.0, self.y_max) / self.y_max * PLOT_HEIGHT
+    }
+
+    /// svg x back to a time
+    fn x_value(&self, svg_x: f64) -> f64 {
+        let ratio = ((svg_x - LEFT) / PLOT_WIDTH).clamp(0.0, 1.0);
+        self.x_min + ratio * (self.x_max - self.x_min)
+    }
+}
+
+/// time series as svg - lines or stacked bars; drag across the chart to zoom into a time span
+#[component]
+pub fn Chart(
+    series: Vec<ChartSeries>,
+    /// stacked bars (one per x) instead of lines
+    #[prop(optional)]
+    bars: bool,
+    /// fixed top of the y axis (e.g. 100 for percentages) - else fitted to the visible values
+    #[prop(optional)]
+    y_max: Option<f64>,
+    /// appended to the y axis labels and the hover readout
+    #[prop(optional)]
+    unit: &'static str,
+) -> impl IntoView {
+    let series = StoredValue::new(series);
+    let zoom = RwSignal::new(None::<(f64, f64)>);
+    // drag start and current position - both as time
+    let selecting = RwSignal::new(None::<(f64, f64)>);
+    let hover = RwSignal::new(None::<f64>);
+
+    let frame =
+        Memo::new(move |_| series.with_value(|series| fit_frame(series, zoom.get(), y_max, bars)));
+
+    let svg_x = |ev: &leptos::ev::PointerEvent| {
+        let width = event_target::<web_sys::Element>(ev).client_width().max(1) as f64;
+        ev.offset_x() as f64 / width * WIDTH
+    };
+    let on_down = move |ev: leptos::ev::PointerEvent| {
+        let Some(frame) = frame.get_untracked() else {
+            return;
+        };
+        let at = frame.x_value(svg_x(&ev));
+        selecting.set(Some((at, at)));
+    };
+    let on_move = move |ev: leptos::ev::PointerEvent| {
+        let Some(frame) = frame.get_untracked() else {
+            return;
+        };
+        let at = frame.x_value(svg_x(&ev));
+        if selecting.get_untracked().is_some() {
+            selecting.update(|s| {
+                if let Some((_, end)) = s {
+                    *end = at;
+                }
+            });
+        } else {
+            hover.set(Some(at));
+        }
+    };
+    let on_up = move |_| {
+        if let Some((start, end)) = selecting.get_untracked() {
+            let (from, to) = if start < end {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            // a click is no zoom
+            if let Some(frame) = frame.get_untracked()
+                && (frame.x(to) - frame.x(from)) > 4.0
+            {
+                zoom.set(Some((from, to)));
+            }
+        }
+        selecting.set(None);
+    };
+    let on_leave = move |_| {
+        selecting.set(None);
+        hover.set(None);
+    };
+
+    let plot = move || {
+        let Some(frame) = frame.get() else {
+            return view! {
+                <text x=WIDTH / 2.0 y=HEIGHT / 2.0 text-anchor="middle" class="chart-axis">
+                    {t("No data yet")}
+                </text>
+            }
+            .into_any();
+        };
+        let y_ticks = y_ticks(frame.y_max);
+        let x_ticks = x_ticks(frame.x_min, frame.x_max);
+        let shapes = series.with_value(|series| {
+            if bars {
+                stacked_bars(series, &frame)
+            } else {
+                series
+                    .iter()
+                    .map(|s| {
+                        let d = line_path(&s.points, &frame);
+                        view! { <path d=d fill="none" stroke=s.color stroke-width="1.5" /> }
+                            .into_any()
+                    })
+                    .collect()
+            }
+        });
+        view! {
+            {y_ticks.into_iter().map(|tick| {
+                let y = frame.y(tick);
+                view! {
+                    <line x1=LEFT x2=WIDTH - RIGHT y1=y y2=y class="chart-grid" />
+                    <text x=LEFT - 6.0 y=y + 4.0 text-anchor="end" class="chart-axis">
+                        {format_value(tick, unit)}
+                    </text>
+                }
+            }).collect_view()}
+            {x_ticks.into_iter().map(|(tick, label)| {
+                let x = frame.x(tick);
+                view! {
+                    <line x1=x x2=x y1=TOP y2=TOP + PLOT_HEIGHT class="chart-grid" />
+                    <text x=x y=HEIGHT - 8.0 text-anchor="middle" class="chart-axis">{label}</text>
+                }
+            }).collect_view()}
+            <svg x=LEFT y=TOP width=PLOT_WIDTH height=PLOT_HEIGHT
+                viewBox=format!("{LEFT} {TOP} {PLOT_WIDTH} {PLOT_HEIGHT}")
+            >
+                {shapes}
+            </svg>
+        }
+        .into_any()
+    };
+
+    let selection = move || {
+        let (frame, (start, end)) = (frame.get()?, selecting.get()?);
+        let (from, to) = (frame.x(start.min(end)), frame.x(start.max(end)));
+        Some(view! {
+            <rect x=from y=TOP width=to - from height=PLOT_HEIGHT class="chart-selection" />
+        })
+    };
+
+    // nearest x of the first series and the values of all series there
+    let readout = Memo::new(move |_| {
+        let at = hover.get()?;
+        frame.get()?;
+        series.with_value(|series| {
+            let x = series
+                .first()?
+                .points
+                .iter()
+                .map(|(x, _)| *x)
+                .min_by(|a, b| (a - at).abs().total_cmp(&(b - at).abs()))?;
+            let values = series
+                .iter()
+                .filter_map(|s| {
+                    let (_, y) = s.points.iter().find(|(px, _)| *px == x)?;
+                    Some(format!("{} {}", t(s.label), format_value(*y, unit)))
+                })
+                .collect::<Vec<_>>();
+            Some((
+                x,
+                format!("{} · {}", format_time(x, true), values.join(" · ")),
+            ))
+        })
+    });
+    let hover_line = move || {
+        let (x, _) = readout.get()?;
+        let x = frame.get()?.x(x);
+        Some(view! { <line x1=x x2=x y1=TOP y2=TOP + PLOT_HEIGHT class="chart-hover" /> })
+    };
+
+    view! {
+        <div class="chart">
+            <svg
+                viewBox=format!("0 0 {WIDTH} {HEIGHT}")
+                class="chart-svg"
+                on:pointerdown=on_down
+                on:pointermove=on_move
+                on:pointerup=on_up
+                on:pointerleave=on_leave
+            >
+                {plot}
+                {hover_line}
+                {selection}
+            </svg>
+            <div class="chart-footer">
+                <div class="chart-legend">
+                    {series.with_value(|series| series.iter().map(|s| view! {
+                        <span>
+                            <span class="chart-swatch" style=format!("background: {};", s.color)></span>
+                            {t(s.label)}
+                        </span>
+                    }).collect_view())}
+                </div>
+                <span class="text-xs text-muted">
+                    {move || readout.get().map(|(_, text)| text)}
+                </span>
+                <button class="btn" disabled=move || zoom.get().is_none() on:click=move |_| zoom.set(None)>
+                    {t("Reset Zoom")}
+                </button>
+            </div>
+        </div>
+    }
+}
+
+/// None without points - an empty time span can't be drawn
+fn fit_frame(
+    series: &[ChartSeries],
+    zoom: Option<(f64, f64)>,
+    y_max: Option<f64>,
+    stacked: bool,
+) -> Option<Frame> {
+    let xs = series.iter().flat_map(|s| s.points.iter().map(|(x, _)| *x));
+    let (x_min, x_max) = match zoom {
+        Some(span) => span,
+        None => xs.fold(None, |span: Option<(f64, f64)>, x| match span {
+            Some((min, max)) => Some((min.min(x), max.max(x))),
+            None => Some((x, x)),
+        })?,
+    };
+    // a single point gets a minute on each side
+    let (x_min, x_max) = if x_max > x_min {
+        (x_min, x_max)
+    } else {
+        (x_min - 60_000.0, x_max + 60_000.0)
+    };
+
+    let visible = |x: f64| x >= x_min && x <= x_max;
+    let highest = if stacked {
+        let mut sums: Vec<(f64, f64)> = Vec::new();
+        for (x, y) in series.iter().flat_map(|s| s.points.iter()) {
+            match sums.iter_mut().find(|(sx, _)| sx == x) {
+                Some((_, sum)) => *sum += y,
+                None => sums.push((*x, *y)),
+            }
+        }
+        sums.into_iter()
+            .filter(|(x, _)| visible(*x))
+            .map(|(_, y)| y)
+            .fold(0.0, f64::max)
+    } else {
+        series
+            .iter()
+            .flat_map(|s| s.points.iter())
+            .filter(|(x, _)| visible(*x))
+            .map(|(_, y)| *y)
+            .fold(0.0, f64::max)
+    };
+    let y_max = y_max.unwrap_or_else(|| nice_step(highest / 4.0) * 4.0);
+
+    Some(Frame {
+        x_min,
+        x_max,
+        y_max: if y_max > 0.0 { y_max } else { 1.0 },
+    })
+}
+
+fn line_path(points: &[(f64, f64)], frame: &Frame) -> String {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, (x, y))| {
+            let cmd = if i == 0 { 'M' } else { 'L' };
+            format!("{cmd}{:.1},{:.1}", frame.x(*x), frame.y(*y))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn stacked_bars(series: &[ChartSeries], frame: &Frame) -> Vec<AnyView> {
+    let mut xs: Vec<f64> = series
+        .iter()
+        .flat_map(|s| s.points.iter().map(|(x, _)| *x))
+        .filter(|x| *x >= frame.x_min && *x <= frame.x_max)
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    let width = (PLOT_WIDTH / xs.len().max(1) as f64 * 0.8).max(1.0);
+
+    xs.into_iter()
+        .flat_map(|x| {
+            let mut base = 0.0;
+            series
+                .iter()
+                .filter_map(|s| {
+                    let (_, y) = s.points.iter().find(|(px, _)| *px == x)?;
+                    let (bottom, top) = (frame.y(base), frame.y(base + y));
+                    base += y;
+                    Some(
+                        view! {
+                            <rect x=frame.x(x) - width / 2.0 y=top width=width height=bottom - top
+                                fill=s.color
+                            />
+                        }
+                        .into_any(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// 1, 2 or 5 times a power of ten - at least `raw`
+fn nice_step(raw: f64) -> f64 {
+    if raw <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn y_ticks(y_max: f64) -> Vec<f64> {
+    let step = nice_step(y_max / 4.0);
+    (0..)
+        .map(|i| i as f64 * step)
+        .take_while(|tick| *tick <= y_max + step / 1000.0)
+        .collect()
+}
+
+/// five labels spread over the span - dates only once it covers more than a day
+fn x_ticks(x_min: f64, x_max: f64) -> Vec<(f64, String)> {
+    let with_date = x_max - x_min > 24.0 * 60.0 * 60.0 * 1000.0;
+    (0..5)
+        .map(|i| x_min + (x_max - x_min) * (i as f64 + 0.5) / 5.0)
+        .map(|x| (x, format_time(x, with_date)))
+        .collect()
+}
+
+/// local time - `dd.MM HH:mm` or `HH:mm`
+fn format_time(millis: f64, with_date: bool) -> String {
+    let date = js_sys::Date::new(&millis.into());
+    let time = format!("{:02}:{:02}", date.get_hours(), date.get_minutes());
+    if with_date {
+        format!("{:02}.{:02} {time}", date.get_date(), date.get_month() + 1)
+    } else {
+        time
+    }
+}
+
+fn format_value(value: f64, unit: &str) -> String {
+    let digits = if value != 0.0 && value.abs() < 10.0 {
+        1
+    } else {
+        0
+    };
+    format!("{value:.digits$}{unit}")
+}
@@ -1,10 +1,15 @@
 use leptos::prelude::*;
 
+use crate::i18n::t;
+
 #[component]
 pub fn ConfirmDialog(
     show: RwSignal<bool>,
     message: String,
     on_confirm: impl Fn() + 'static + Clone + Send + Sync,
+    /// extra options shown below the message
+    #[prop(optional)]
+    children: Option<ChildrenFn>,
 ) -> impl IntoView {
     let on_confirm = StoredValue::new(on_confirm);
 
@@ -13,9 +18,10 @@ pub fn ConfirmDialog(
             <div class="dialog-overlay" on:click=move |_| show.set(false)>
                 <div class="dialog" on:click=|e| e.stop_propagation()>
                     <p>{message.clone()}</p>
+                    {children.clone().map(|children| children())}
                     <div class="dialog-actions">
                         <button class="btn btn-secondary" on:click=move |_| show.set(false)>
-                            "Cancel"
+                            {t("Cancel")}
                         </button>
                         <button
                             class="btn btn-danger"
@@ -24,7 +30,7 @@ pub fn ConfirmDialog(
                                 show.set(false);
                             }
                         >
-                            "Delete"
+                            {t("Delete")}
                         </button>
                     </div>
                 </div>
@@ -1,6 +1,8 @@
 use leptos::prelude::*;
 
+use crate::i18n::t;
+
 #[component]
 pub fn Loading() -> impl IntoView {
-    view! { <div class="loading">"Loading..."</div> }
+    view! { <div class="loading">{t("Loading...")}</div> }
 }
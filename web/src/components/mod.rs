@@ -1,4 +1,5 @@
 mod card;
+mod chart;
 mod confirm_dialog;
 mod empty_state;
 mod icons;
@@ -6,9 +7,12 @@ mod loading;
 mod message;
 mod modal;
 mod navbar;
+mod switch;
 mod tag_badge;
+mod theme;
 
 pub use card::Card;
+pub use chart::{Chart, ChartSeries};
 pub use confirm_dialog::ConfirmDialog;
 pub use empty_state::EmptyState;
 pub use icons::*;
@@ -16,4 +20,6 @@ pub use loading::Loading;
 pub use message::*;
 pub use modal::Modal;
 pub use navbar::Navbar;
+pub use switch::Switch;
 pub use tag_badge::TagBadge;
+pub use theme::{ThemeProvider, use_ui_settings};
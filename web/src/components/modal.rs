@@ -4,6 +4,7 @@ use leptos::prelude::*;
 use leptos::task::spawn_local;
 
 use crate::components::{Message, ToastSignal};
+use crate::i18n::t;
 
 #[component]
 pub fn Modal<FnSave, FnSaved, Fut>(
@@ -37,8 +38,8 @@ where
         });
     };
 
-    let confirm_label = confirm_label.unwrap_or("Save".to_string());
-    let cancel_label = cancel_label.unwrap_or("Cancel".to_string());
+    let confirm_label = confirm_label.unwrap_or_else(|| t("Save").to_string());
+    let cancel_label = cancel_label.unwrap_or_else(|| t("Cancel").to_string());
 
     view! {
         <Show when=move || show.get()>
@@ -46,8 +47,8 @@ where
                 <div class="dialog" on:click=|e| e.stop_propagation()>
                     <Message signal=msg />
                     {
-                        if let Some(ref t) = title {
-                            view! {<h2 class="dialog-title">{t.clone()}</h2>}.into_any()
+                        if let Some(ref title) = title {
+                            view! {<h2 class="dialog-title">{title.clone()}</h2>}.into_any()
                         } else {
                             view! {<span />}.into_any()
                         }
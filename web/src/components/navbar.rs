@@ -1,15 +1,52 @@
 use leptos::prelude::*;
 use leptos_router::components::A;
+use leptos_router::hooks::{use_location, use_navigate};
+
+use crate::api;
+use crate::i18n::{self, Lang, t};
+use crate::pages::is_mobile_route;
 
 #[component]
 pub fn Navbar() -> impl IntoView {
+    let query = RwSignal::new(String::new());
+    let navigate = use_navigate();
+    let location = use_location();
+
+    let on_search = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let q = query.get().trim().to_string();
+        if !q.is_empty() {
+            navigate(&api::search_url(&q), Default::default());
+        }
+    };
+
     view! {
-        <nav class="navbar">
-            <A href="/app/" attr:class="brand">"File Sync - Admin"</A>
-            <A href="/app/clients">"Clients"</A>
-            <A href="/app/watch-groups">"Watch Groups"</A>
-            <A href="/app/links">"Links"</A>
-            <A href="/app/monitor">"Monitor"</A>
-        </nav>
+        <Show when=move || !is_mobile_route(&location.pathname.get())>
+            <nav class="navbar">
+                <A href="/app/" attr:class="brand">"File Sync - Admin"</A>
+                <A href="/app/clients">{t("Clients")}</A>
+                <A href="/app/watch-groups">{t("Watch Groups")}</A>
+                <A href="/app/links">{t("Links")}</A>
+                <A href="/app/users">{t("Users")}</A>
+                <A href="/app/monitor">{t("Monitor")}</A>
+                <A href="/app/settings">{t("Settings")}</A>
+                <form class="navbar-search" on:submit=on_search.clone()>
+                    <input type="search" class="form-input" placeholder=t("Search files") bind:value=query />
+                </form>
+                <select
+                    class="form-input navbar-lang"
+                    title=t("Language")
+                    on:change=move |ev| {
+                        if let Some(lang) = Lang::from_code(&event_target_value(&ev)) {
+                            i18n::switch_to(lang);
+                        }
+                    }
+                >
+                    {Lang::ALL.into_iter().map(|lang| view! {
+                        <option value=lang.code() selected=lang == i18n::lang()>{lang.label()}</option>
+                    }).collect_view()}
+                </select>
+            </nav>
+        </Show>
     }
 }
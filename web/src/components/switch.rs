@@ -0,0 +1,21 @@
+use leptos::prelude::*;
+
+/// toggle switch - `on_toggle` gets the new state, `checked` is not changed by the switch itself
+#[component]
+pub fn Switch(
+    #[prop(into)] checked: Signal<bool>,
+    label: &'static str,
+    on_toggle: Callback<bool>,
+) -> impl IntoView {
+    view! {
+        <label class="switch">
+            <input
+                type="checkbox"
+                prop:checked=move || checked.get()
+                on:change=move |e| on_toggle.run(event_target_checked(&e))
+            />
+            <span class="switch-slider"></span>
+            <span class="text-xs">{label}</span>
+        </label>
+    }
+}
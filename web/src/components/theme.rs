@@ -0,0 +1,47 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::dtos::UiSettingsDto;
+
+use crate::api;
+
+/// ui settings of the account the browser is signed in with - None until loaded
+#[derive(Clone, Copy)]
+pub struct UiSettings(RwSignal<Option<UiSettingsDto>>);
+
+impl UiSettings {
+    pub fn get(self) -> Option<UiSettingsDto> {
+        self.0.get()
+    }
+
+    pub fn set(self, settings: UiSettingsDto) {
+        self.0.set(Some(settings));
+    }
+}
+
+pub fn use_ui_settings() -> UiSettings {
+    expect_context::<UiSettings>()
+}
+
+/// loads the ui settings and applies them as `data-theme` / `data-density` on `<html>` - the
+/// stylesheet does the rest
+#[component]
+pub fn ThemeProvider(children: Children) -> impl IntoView {
+    let settings = UiSettings(RwSignal::new(None));
+    provide_context(settings);
+    spawn_local(async move {
+        // e.g. no api token yet - the defaults apply then
+        settings.set(api::fetch_ui_settings().await.unwrap_or_default());
+    });
+    Effect::new(move |_| {
+        let applied = settings.get().unwrap_or_default();
+        let root = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.document_element());
+        if let Some(root) = root {
+            let _ = root.set_attribute("data-theme", applied.theme.as_str());
+            let _ = root.set_attribute("data-density", applied.density.as_str());
+        }
+    });
+
+    children()
+}
@@ -0,0 +1,558 @@
+//! German / English ui strings - keyed by the English text, which [`t`] falls back to
+//!
+//! The language is picked once on start (stored choice, else the browser language), switching
+//! stores the new one and reloads - so the views can render their strings without signals.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    De,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::De];
+
+    /// BCP 47 - also the value in local storage
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::De => "de",
+        }
+    }
+
+    /// in its own language
+    pub fn label(self) -> &'static str {
+        match self {
+            Lang::En => "English",
+            Lang::De => "Deutsch",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Lang> {
+        // "de-AT" counts as German
+        let primary = code.split('-').next().unwrap_or_default().to_lowercase();
+        Lang::ALL.into_iter().find(|lang| lang.code() == primary)
+    }
+}
+
+const STORAGE_KEY: &str = "lang";
+
+static LANG: LazyLock<Lang> = LazyLock::new(|| {
+    let window = web_sys::window();
+    let stored = window
+        .as_ref()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten());
+    let browser = window.as_ref().and_then(|w| w.navigator().language());
+    stored
+        .or(browser)
+        .and_then(|code| Lang::from_code(&code))
+        .unwrap_or(Lang::En)
+});
+
+static GERMAN_BY_ENGLISH: LazyLock<HashMap<&str, &str>> =
+    LazyLock::new(|| GERMAN.iter().copied().collect());
+
+pub fn lang() -> Lang {
+    *LANG
+}
+
+/// the ui string in the current language
+pub fn t(en: &'static str) -> &'static str {
+    match lang() {
+        Lang::En => en,
+        Lang::De => GERMAN_BY_ENGLISH.get(en).copied().unwrap_or(en),
+    }
+}
+
+/// stores the choice and reloads the app in that language
+pub fn switch_to(lang: Lang) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    if let Ok(Some(storage)) = window.local_storage() {
+        let _ = storage.set_item(STORAGE_KEY, lang.code());
+    }
+    let _ = window.location().reload();
+}
+
+/// `<html lang>` - for screen readers and the browser's hyphenation / translation offers
+pub fn apply_to_document() {
+    let root = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.document_element());
+    if let Some(root) = root {
+        let _ = root.set_attribute("lang", lang().code());
+    }
+}
+
+/// (English, German) - the weekdays, theme / density names and durability trade-offs at the
+/// end are looked up by value
+const GERMAN: &[(&str, &str)] = &[
+    (" (exceeded)", " (überschritten)"),
+    (
+        " (reached - sync stopped)",
+        " (erreicht - Synchronisation gestoppt)",
+    ),
+    (" - about {h}h {m}m left", " - noch etwa {h} h {m} min"),
+    (" - about {m}m left", " - noch etwa {m} min"),
+    (" - about {s}s left", " - noch etwa {s} s"),
+    (
+        " · only the beginning is shown",
+        " · nur der Anfang wird angezeigt",
+    ),
+    (" - hard cap {cap}", " - hartes Limit {cap}"),
+    (" - soft cap {cap}", " - weiches Limit {cap}"),
+    ("+ Add Watch Group", "+ Watch Group hinzufügen"),
+    ("- shared -", "- geteilt -"),
+    ("Add", "Hinzufügen"),
+    ("Add link", "Link hinzufügen"),
+    ("Add template", "Vorlage hinzufügen"),
+    ("Admin", "Admin"),
+    (
+        "Also search the content of small text files",
+        "Auch den Inhalt kleiner Textdateien durchsuchen",
+    ),
+    (
+        "Anonymize its file history",
+        "Seinen Dateiverlauf anonymisieren",
+    ),
+    (
+        "Api token (only needed if the server sets ADMIN_TOKEN)",
+        "API-Token (nur nötig, wenn der Server ADMIN_TOKEN setzt)",
+    ),
+    ("App CPU %", "App-CPU %"),
+    ("App Memory %", "App-Speicher %"),
+    ("Attach", "Zuordnen"),
+    ("Bandwidth", "Bandbreite"),
+    (
+        "Both versions are identical.",
+        "Beide Versionen sind identisch.",
+    ),
+    ("Bandwidth this month", "Bandbreite diesen Monat"),
+    ("Cancel", "Abbrechen"),
+    ("Changes", "Änderungen"),
+    ("Client", "Client"),
+    ("Client owners", "Besitzer der Clients"),
+    ("Clients", "Clients"),
+    (
+        "Clients only sync watch groups that are shared (no owner) or owned by their own user.",
+        "Clients synchronisieren nur Watch Groups, die geteilt sind (ohne Besitzer) oder ihrem eigenen Benutzer gehören.",
+    ),
+    ("Close", "Schließen"),
+    (
+        "Copied {count} file(s) to watch group {target}.",
+        "{count} Datei(en) in Watch Group {target} kopiert.",
+    ),
+    ("Copy", "Kopieren"),
+    ("Create", "Anlegen"),
+    ("Create link", "Link erstellen"),
+    ("Daily", "Täglich"),
+    ("Day", "Tag"),
+    ("Default page", "Startseite"),
+    ("Delete", "Löschen"),
+    ("Delete '{name}' assignment?", "Zuordnung '{name}' löschen?"),
+    (
+        "Delete '{name}'? This cannot be undone.",
+        "'{name}' löschen? Das kann nicht rückgängig gemacht werden.",
+    ),
+    (
+        "Delete client '{name}'? It can't register again with the same id.",
+        "Client '{name}' löschen? Er kann sich nicht erneut mit derselben ID registrieren.",
+    ),
+    ("Delete failed:", "Löschen fehlgeschlagen:"),
+    ("Delete image", "Bild löschen"),
+    (
+        "Delete user '{name}'? Their clients and watch groups become unowned.",
+        "Benutzer '{name}' löschen? Seine Clients und Watch Groups sind danach ohne Besitzer.",
+    ),
+    (
+        "Delete watch group '{name}'? Its files are removed from the server, the history is archived.",
+        "Watch Group '{name}' löschen? Ihre Dateien werden vom Server entfernt, der Verlauf wird archiviert.",
+    ),
+    (
+        "Delete {count} file(s)? This cannot be undone.",
+        "{count} Datei(en) löschen? Das kann nicht rückgängig gemacht werden.",
+    ),
+    ("Deleted at {at}", "Gelöscht am {at}"),
+    ("Deleted {count} file(s).", "{count} Datei(en) gelöscht."),
+    ("Deletes", "Löschungen"),
+    ("Detach", "Trennen"),
+    (
+        "Detach '{name}'? It stops syncing this watch group, its local files are kept.",
+        "'{name}' trennen? Er synchronisiert diese Watch Group nicht mehr, seine lokalen Dateien bleiben erhalten.",
+    ),
+    (
+        "Detach clients still syncing it",
+        "Clients trennen, die sie noch synchronisieren",
+    ),
+    ("Disk Free GiB", "Freier Speicher GiB"),
+    ("Disk Free Space", "Freier Speicherplatz"),
+    ("Disk Used %", "Belegter Speicher %"),
+    ("Download folder", "Ordner herunterladen"),
+    ("Downloaded", "Heruntergeladen"),
+    ("Downloaded MiB", "Heruntergeladen MiB"),
+    (
+        "Durability - how hard uploads are pushed to disk",
+        "Beständigkeit - wie konsequent Uploads auf die Platte geschrieben werden",
+    ),
+    ("Durability saved", "Beständigkeit gespeichert"),
+    ("Edit", "Bearbeiten"),
+    (
+        "Edit Client Poll Interval",
+        "Abfrageintervall des Clients bearbeiten",
+    ),
+    (
+        "Edit Watch Group Assignment",
+        "Zuordnung der Watch Group bearbeiten",
+    ),
+    (
+        "Error loading activity: ",
+        "Fehler beim Laden der Aktivität: ",
+    ),
+    ("Error loading client: ", "Fehler beim Laden des Clients: "),
+    ("Error loading clients: ", "Fehler beim Laden der Clients: "),
+    (
+        "Error loading inbox links: ",
+        "Fehler beim Laden der Inbox-Links: ",
+    ),
+    (
+        "Error loading queue: ",
+        "Fehler beim Laden der Warteschlange: ",
+    ),
+    (
+        "Error loading sync blackouts: ",
+        "Fehler beim Laden der Sync-Sperrzeiten: ",
+    ),
+    (
+        "Error loading templates: ",
+        "Fehler beim Laden der Vorlagen: ",
+    ),
+    (
+        "Error loading watch group: ",
+        "Fehler beim Laden der Watch Group: ",
+    ),
+    (
+        "Error loading watch groups: ",
+        "Fehler beim Laden der Watch Groups: ",
+    ),
+    (
+        "Error loading webhooks: ",
+        "Fehler beim Laden der Webhooks: ",
+    ),
+    ("Error: ", "Fehler: "),
+    (
+        "Events per day (last 30 days)",
+        "Ereignisse pro Tag (letzte 30 Tage)",
+    ),
+    ("Exclude dirs", "Ausgeschlossene Ordner"),
+    (
+        "Exclude dirs (one per line)",
+        "Ausgeschlossene Ordner (einer pro Zeile)",
+    ),
+    ("Exclude dot dirs", "Punkt-Ordner ausschließen"),
+    ("Exclude dots", "Punkt-Ordner ausschließen"),
+    ("Excluded", "Ausgeschlossen"),
+    ("File", "Datei"),
+    ("Files", "Dateien"),
+    ("Folder", "Ordner"),
+    ("Folder (e.g. inbox)", "Ordner (z. B. inbox)"),
+    ("Folder on the client", "Ordner auf dem Client"),
+    ("Force resync", "Neu synchronisieren"),
+    (
+        "Forget the client's file lists and queue",
+        "Dateilisten und Warteschlange des Clients verwerfen",
+    ),
+    ("From", "Von"),
+    ("History", "Verlauf"),
+    ("Host pattern", "Hostname-Muster"),
+    ("Image not found", "Bild nicht gefunden"),
+    ("Inbox links", "Inbox-Links"),
+    ("Invalid client URL", "Ungültige Client-URL"),
+    ("Invalid gallery URL", "Ungültige Galerie-URL"),
+    ("Invalid history URL", "Ungültige Verlaufs-URL"),
+    ("Invalid poll interval", "Ungültiges Abfrageintervall"),
+    ("Invalid watch group ID", "Ungültige Watch-Group-ID"),
+    (
+        "Invalid watch group selection",
+        "Ungültige Auswahl der Watch Group",
+    ),
+    ("Its copy", "Seine Kopie"),
+    ("Language", "Sprache"),
+    ("Largest files", "Größte Dateien"),
+    ("Last 7 days:", "Letzte 7 Tage:"),
+    ("Last seen", "Zuletzt gesehen"),
+    ("Last sync", "Letzte Synchronisation"),
+    (
+        "Latest version: {size} from {at}",
+        "Neueste Version: {size} vom {at}",
+    ),
+    ("Links", "Links"),
+    ("List", "Liste"),
+    ("Loading...", "Lädt..."),
+    ("Manifest", "Manifest"),
+    ("Members", "Mitglieder"),
+    ("Monitor", "Monitor"),
+    ("Most active clients", "Aktivste Clients"),
+    ("Move", "Verschieben"),
+    (
+        "Move {count} file(s) to watch group {target}? They are deleted here.",
+        "{count} Datei(en) in Watch Group {target} verschieben? Sie werden hier gelöscht.",
+    ),
+    (
+        "Moved {count} file(s) to watch group {target}.",
+        "{count} Datei(en) in Watch Group {target} verschoben.",
+    ),
+    (
+        "New clients get the watch groups of all templates matching their host name",
+        "Neue Clients bekommen die Watch Groups aller Vorlagen, die zu ihrem Hostnamen passen",
+    ),
+    ("New token", "Neues Token"),
+    (
+        "New token (shown only once):",
+        "Neues Token (wird nur einmal angezeigt):",
+    ),
+    ("New user name", "Name des neuen Benutzers"),
+    ("New watch group name", "Name der neuen Watch Group"),
+    (
+        "No client syncs this watch group yet.",
+        "Noch kein Client synchronisiert diese Watch Group.",
+    ),
+    (
+        "No clients registered yet.",
+        "Noch keine Clients registriert.",
+    ),
+    ("No data yet", "Noch keine Daten"),
+    (
+        "No file events of this client.",
+        "Keine Dateiereignisse dieses Clients.",
+    ),
+    (
+        "No files in this watch group yet.",
+        "Noch keine Dateien in dieser Watch Group.",
+    ),
+    ("No history for this file.", "Kein Verlauf für diese Datei."),
+    ("No inbox links yet.", "Noch keine Inbox-Links."),
+    ("No links saved yet.", "Noch keine Links gespeichert."),
+    (
+        "No users yet - everything is shared.",
+        "Noch keine Benutzer - alles ist geteilt.",
+    ),
+    (
+        "No watch groups configured yet.",
+        "Noch keine Watch Groups eingerichtet.",
+    ),
+    ("No watch groups yet.", "Noch keine Watch Groups."),
+    ("No webhooks yet.", "Noch keine Webhooks."),
+    (
+        "Not assigned to any watch group.",
+        "Keiner Watch Group zugeordnet.",
+    ),
+    ("Note (optional)", "Notiz (optional)"),
+    ("Nothing found.", "Nichts gefunden."),
+    (
+        "Opened on /app/ - phones always get the mobile pages",
+        "Wird unter /app/ geöffnet - Handys bekommen immer die mobilen Seiten",
+    ),
+    ("Page not found", "Seite nicht gefunden"),
+    ("Path", "Pfad"),
+    ("Path is required", "Pfad ist erforderlich"),
+    (
+        "Paths and hashes for seeding a client from a copied disk (client seed)",
+        "Pfade und Hashes, um einen Client von einer kopierten Platte zu befüllen (client seed)",
+    ),
+    ("Paused", "Pausiert"),
+    ("Poll interval", "Abfrageintervall"),
+    ("Poll interval (ms)", "Abfrageintervall (ms)"),
+    (
+        "Poll interval in ms (empty = default)",
+        "Abfrageintervall in ms (leer = Standard)",
+    ),
+    (
+        "Poll interval must be a number between 0 and 65535",
+        "Das Abfrageintervall muss eine Zahl zwischen 0 und 65535 sein",
+    ),
+    ("Preview", "Vorschau"),
+    ("Preview unavailable: ", "Vorschau nicht verfügbar: "),
+    (
+        "Priority - clients sync higher priorities first",
+        "Priorität - Clients synchronisieren höhere Prioritäten zuerst",
+    ),
+    (
+        "Priority must be a whole number",
+        "Die Priorität muss eine ganze Zahl sein",
+    ),
+    (
+        "Priority saved - clients sync higher priorities first",
+        "Priorität gespeichert - Clients synchronisieren höhere Prioritäten zuerst",
+    ),
+    ("Queue: up to date", "Warteschlange: aktuell"),
+    (
+        "Queue: {files} files, {size}",
+        "Warteschlange: {files} Dateien, {size}",
+    ),
+    ("Recent events", "Letzte Ereignisse"),
+    ("Registration templates", "Registrierungsvorlagen"),
+    ("Remove", "Entfernen"),
+    ("Report unavailable: ", "Bericht nicht verfügbar: "),
+    ("Reset Zoom", "Zoom zurücksetzen"),
+    ("Root", "Stammordner"),
+    ("Save", "Speichern"),
+    ("Saved!", "Gespeichert!"),
+    ("Scan warning", "Scan-Warnung"),
+    ("Search", "Suche"),
+    ("Search files", "Dateien suchen"),
+    ("Secret (optional)", "Secret (optional)"),
+    (
+        "Select two versions to compare.",
+        "Zwei Versionen zum Vergleichen auswählen.",
+    ),
+    ("Settings", "Einstellungen"),
+    ("Share", "Anteil"),
+    ("Size", "Größe"),
+    ("State", "Status"),
+    ("Statistics", "Statistik"),
+    ("Stats unavailable: ", "Statistik nicht verfügbar: "),
+    ("Status", "Status"),
+    ("Status unavailable: ", "Status nicht verfügbar: "),
+    ("Storage", "Speicher"),
+    (
+        "Storage stats unavailable",
+        "Speicherstatistik nicht verfügbar",
+    ),
+    (
+        "Storage growth (last 90 days)",
+        "Speicherwachstum (letzte 90 Tage)",
+    ),
+    ("Storage reports", "Speicherberichte"),
+    ("Stored GiB", "Gespeichert GiB"),
+    ("Stored MiB", "Gespeichert MiB"),
+    ("Sync blackouts: none", "Sync-Sperrzeiten: keine"),
+    ("Sync blackouts: {count}", "Sync-Sperrzeiten: {count}"),
+    (
+        "Sync state reset - rebuilt with the next sync of the client",
+        "Sync-Zustand zurückgesetzt - wird mit der nächsten Synchronisation des Clients neu aufgebaut",
+    ),
+    ("System CPU %", "System-CPU %"),
+    ("System CPU % (peak)", "System-CPU % (Spitze)"),
+    (
+        "Averaged over {secs} s per point",
+        "Gemittelt über {secs} s pro Punkt",
+    ),
+    ("Last hour", "Letzte Stunde"),
+    ("Last 24 hours", "Letzte 24 Stunden"),
+    ("Last 7 days", "Letzte 7 Tage"),
+    ("Everything", "Alles"),
+    ("System Memory %", "System-Speicher %"),
+    ("System Monitor", "Systemmonitor"),
+    ("Table density", "Tabellendichte"),
+    ("Tags", "Tags"),
+    ("Target watch group…", "Ziel-Watch-Group…"),
+    ("Theme", "Design"),
+    ("This folder is empty.", "Dieser Ordner ist leer."),
+    ("Tile", "Kacheln"),
+    ("Time", "Zeit"),
+    ("Title", "Titel"),
+    ("To", "Bis"),
+    ("Top files by size", "Top-Dateien nach Größe"),
+    ("Transfer failed:", "Übertragung fehlgeschlagen:"),
+    (
+        "Transferred by clients per hour (last 7 days)",
+        "Von Clients übertragen pro Stunde (letzte 7 Tage)",
+    ),
+    (
+        "Transfers: {uploads} uploads ({max_uploads}), {downloads} downloads ({max_downloads}), {max_per_client} per client - {rejected} refused since start",
+        "Übertragungen: {uploads} Uploads ({max_uploads}), {downloads} Downloads ({max_downloads}), {max_per_client} pro Client - {rejected} seit dem Start abgelehnt",
+    ),
+    (
+        "Upload of {name} failed: {error}",
+        "Upload von {name} fehlgeschlagen: {error}",
+    ),
+    ("Upload photo", "Foto hochladen"),
+    (
+        "Upload volume: {free} GiB free of {total} GiB - uploads are refused below {min} GiB",
+        "Upload-Laufwerk: {free} GiB von {total} GiB frei - unter {min} GiB werden Uploads abgelehnt",
+    ),
+    ("Uploaded", "Hochgeladen"),
+    ("Uploaded MiB", "Hochgeladen MiB"),
+    (
+        "Uploaded {count} file(s).",
+        "{count} Datei(en) hochgeladen.",
+    ),
+    ("Uploading…", "Lädt hoch…"),
+    ("Url", "URL"),
+    ("Use token", "Token verwenden"),
+    ("Users", "Benutzer"),
+    ("Verify writes", "Schreibvorgänge prüfen"),
+    ("Watch Group", "Watch Group"),
+    ("Watch Group Files", "Dateien der Watch Group"),
+    ("Watch Groups", "Watch Groups"),
+    ("Watch group", "Watch Group"),
+    ("Watch group owners", "Besitzer der Watch Groups"),
+    ("Watch groups", "Watch Groups"),
+    ("Webhooks", "Webhooks"),
+    ("active", "aktiv"),
+    ("admin", "Admin"),
+    ("behind", "im Rückstand"),
+    ("content not kept", "Inhalt nicht aufbewahrt"),
+    ("default", "Standard"),
+    ("deleted", "gelöscht"),
+    ("events", "Ereignisse"),
+    ("just now", "gerade eben"),
+    ("max {max}", "max. {max}"),
+    ("missing", "fehlt"),
+    ("never", "nie"),
+    ("newer - not uploaded yet", "neuer - noch nicht hochgeladen"),
+    ("no", "nein"),
+    ("not synced yet", "noch nicht synchronisiert"),
+    ("outdated", "veraltet"),
+    ("paused", "pausiert"),
+    ("read-only", "nur lesen"),
+    ("signed", "signiert"),
+    ("synced", "synchronisiert"),
+    ("unlimited", "unbegrenzt"),
+    ("yes", "ja"),
+    (
+        "{count} files, {size} total",
+        "{count} Dateien, {size} insgesamt",
+    ),
+    (
+        "{left} left before uploads are refused ({free} free of {total} on the upload volume, {reserve} kept in reserve)",
+        "{left} übrig, bevor Uploads abgelehnt werden ({free} von {total} auf dem Upload-Laufwerk frei, {reserve} als Reserve)",
+    ),
+    ("{n} days ago", "vor {n} Tagen"),
+    ("{n} h ago", "vor {n} h"),
+    ("{n} min ago", "vor {n} min"),
+    ("{size} from {at}", "{size} vom {at}"),
+    ("{up} up, {down} down", "{up} hoch, {down} runter"),
+    ("← Back", "← Zurück"),
+    ("← Clients", "← Clients"),
+    ("← Watch Groups", "← Watch Groups"),
+    ("✓ up to date", "✓ aktuell"),
+    ("Mon", "Mo"),
+    ("Tue", "Di"),
+    ("Wed", "Mi"),
+    ("Thu", "Do"),
+    ("Fri", "Fr"),
+    ("Sat", "Sa"),
+    ("Sun", "So"),
+    ("dark", "dunkel"),
+    ("light", "hell"),
+    ("comfortable", "komfortabel"),
+    ("compact", "kompakt"),
+    (
+        "The OS writes uploads back when it likes - fastest, but a power loss can lose uploads clients were told are stored",
+        "Das Betriebssystem schreibt Uploads zurück, wann es will - am schnellsten, aber ein Stromausfall kann Uploads kosten, die den Clients schon als gespeichert gemeldet wurden",
+    ),
+    (
+        "The content is flushed to disk before the upload is moved into place - costs a disk round trip per upload; after a power loss the file may still show up under its old name or version",
+        "Der Inhalt wird auf die Platte geschrieben, bevor der Upload an seinen Platz verschoben wird - kostet einen Plattenzugriff pro Upload; nach einem Stromausfall kann die Datei noch unter ihrem alten Namen oder in der alten Version auftauchen",
+    ),
+    (
+        "Additionally the directory is flushed after the move, so the new file survives a power loss too - slowest, noticeably with many small files",
+        "Zusätzlich wird nach dem Verschieben der Ordner geschrieben, sodass die neue Datei auch einen Stromausfall übersteht - am langsamsten, spürbar bei vielen kleinen Dateien",
+    ),
+];
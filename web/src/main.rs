@@ -4,28 +4,40 @@ use leptos_router::path;
 
 mod api;
 mod components;
+mod i18n;
 mod pages;
 
 fn main() {
+    i18n::apply_to_document();
     leptos::mount::mount_to_body(App);
 }
 
 #[component]
 fn App() -> impl IntoView {
     view! {
-        <Router base="/app">
-            <components::Navbar />
-            <main>
-                <Routes fallback=|| view! { <div class="container"><h1>"Page not found"</h1></div> }>
-                    <Route path=path!("/") view=pages::ClientsPage />
-                    <Route path=path!("/clients") view=pages::ClientsPage />
-                    <Route path=path!("/watch-groups") view=pages::WatchGroupsPage />
-                    <Route path=path!("/watch-groups/:id") view=pages::WatchGroupFilesPage />
-                    <Route path=path!("/watch-groups/:id/gallery") view=pages::ImageGalleryPage />
-                    <Route path=path!("/links") view=pages::LinksPage />
-                    <Route path=path!("/monitor") view=pages::MonitorPage />
-                </Routes>
-            </main>
-        </Router>
+        <components::ThemeProvider>
+            <Router base="/app">
+                <components::Navbar />
+                <main>
+                    <Routes fallback=|| view! { <div class="container"><h1>{i18n::t("Page not found")}</h1></div> }>
+                        <Route path=path!("/") view=pages::HomePage />
+                        <Route path=path!("/clients") view=pages::ClientsPage />
+                        <Route path=path!("/clients/:id") view=pages::ClientDetailPage />
+                        <Route path=path!("/watch-groups") view=pages::WatchGroupsPage />
+                        <Route path=path!("/watch-groups/:id") view=pages::WatchGroupFilesPage />
+                        <Route path=path!("/watch-groups/:id/detail") view=pages::WatchGroupDetailPage />
+                        <Route path=path!("/watch-groups/:id/gallery") view=pages::ImageGalleryPage />
+                        <Route path=path!("/watch-groups/:id/history") view=pages::FileHistoryPage />
+                        <Route path=path!("/links") view=pages::LinksPage />
+                        <Route path=path!("/users") view=pages::UsersPage />
+                        <Route path=path!("/monitor") view=pages::MonitorPage />
+                        <Route path=path!("/search") view=pages::SearchPage />
+                        <Route path=path!("/settings") view=pages::SettingsPage />
+                        <Route path=path!("/m") view=pages::MobileHomePage />
+                        <Route path=path!("/m/watch-groups/:id") view=pages::MobileBrowserPage />
+                    </Routes>
+                </main>
+            </Router>
+        </components::ThemeProvider>
     }
 }
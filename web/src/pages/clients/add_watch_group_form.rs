@@ -4,6 +4,7 @@ use shared::dtos::{ClientWatchGroupCreateDto, ServerWatchGroup};
 
 use crate::api;
 use crate::components::{Message, ToastSignal};
+use crate::i18n::t;
 
 #[component]
 pub fn AddWatchGroupForm(
@@ -31,12 +32,12 @@ pub fn AddWatchGroupForm(
         let id = client_id.get_value();
         let watch_group_id_str = selected_watch_group_id.get_untracked();
         let Ok(watch_group_id) = watch_group_id_str.parse::<i64>() else {
-            msg.error("Invalid watch group selection");
+            msg.error(t("Invalid watch group selection"));
             return;
         };
         let path_str = path.get_untracked();
         if path_str.trim().is_empty() {
-            msg.error("Path is required");
+            msg.error(t("Path is required"));
             return;
         }
         let excl: Vec<String> = exclude_dirs_text
@@ -71,12 +72,12 @@ pub fn AddWatchGroupForm(
                 class="btn btn-secondary"
                 on:click=move |_| show_add.update(|v| *v = !*v)
             >
-                "+ Add Watch Group"
+                {t("+ Add Watch Group")}
             </button>
             <Show when=move || show_add.get()>
                 <div style="margin-top: 0.75rem;">
                     <div class="form-group">
-                        <label>"Watch Group"</label>
+                        <label>{t("Watch Group")}</label>
                         <select class="form-input" bind:value=selected_watch_group_id>
                             {move || server_watch_groups.get_value().into_iter().map(|watch_group| {
                                 view! { <option value=watch_group.id.to_string()>{watch_group.name}</option> }
@@ -84,24 +85,24 @@ pub fn AddWatchGroupForm(
                         </select>
                     </div>
                     <div class="form-group">
-                        <label>"Path"</label>
+                        <label>{t("Path")}</label>
                         <input type="text" class="form-input" bind:value=path />
                     </div>
                     <div class="form-group">
-                        <label>"Exclude dirs (one per line)"</label>
+                        <label>{t("Exclude dirs (one per line)")}</label>
                         <textarea class="form-input" rows="3" bind:value=exclude_dirs_text />
                     </div>
                     <div class="checkbox-group">
                         <input type="checkbox" bind:checked=exclude_dot />
-                        <label>"Exclude dot dirs"</label>
+                        <label>{t("Exclude dot dirs")}</label>
                     </div>
                     <div class="flex gap-1" style="margin-top: 0.75rem;">
-                        <button class="btn btn-success" on:click=do_add>"Add"</button>
+                        <button class="btn btn-success" on:click=do_add>{t("Add")}</button>
                         <button
                             class="btn btn-secondary"
                             on:click=move |_| show_add.set(false)
                         >
-                            "Cancel"
+                            {t("Cancel")}
                         </button>
                     </div>
                     <Message signal=msg />
@@ -4,11 +4,14 @@ use shared::dtos::{ClientDto, ServerWatchGroup};
 
 use crate::api;
 use crate::components::{
-    Card, ConfirmDialog, Loading, Message, PencilIcon, ToastSignal, TrashIcon,
+    Card, ConfirmDialog, Loading, Message, PencilIcon, Switch, ToastSignal, TrashIcon,
 };
+use crate::i18n::t;
 
 use super::add_watch_group_form::AddWatchGroupForm;
 use super::edit_client_modal::EditClientModal;
+use super::pending_queue::PendingQueue;
+use super::sync_blackouts::SyncBlackouts;
 use super::watch_group_assignment::WatchGroupAssignment;
 
 #[component]
@@ -23,7 +26,9 @@ pub fn ClientCard(
     let server_watch_groups = StoredValue::new(server_watch_groups);
     let on_changed_sv = StoredValue::new(on_changed);
 
+    let paused = RwSignal::new(client.paused);
     let confirm_delete = RwSignal::new(false);
+    let anonymize = RwSignal::new(false);
     let show_edit_modal = RwSignal::new(false);
     let watch_group_trigger = RwSignal::new(0u32);
     let msg = ToastSignal::new();
@@ -37,31 +42,48 @@ pub fn ClientCard(
     let do_delete = move || {
         let id = client_id.get_value();
         spawn_local(async move {
-            match api::delete_client(&id).await {
+            match api::delete_client(&id, anonymize.get_untracked()).await {
                 Ok(()) => on_changed_sv.get_value()(),
                 Err(e) => msg.error(e),
             }
         });
     };
 
-    let confirm_msg = format!("Delete client '{}'?", host_name);
+    let on_pause = Callback::new(move |pause: bool| {
+        let id = client_id.get_value();
+        spawn_local(async move {
+            match api::pause_client(&id, pause).await {
+                Ok(()) => paused.set(pause),
+                Err(e) => msg.error(e),
+            }
+        });
+    });
+
+    let confirm_msg = t("Delete client '{name}'? It can't register again with the same id.")
+        .replace("{name}", &host_name);
 
     view! {
         <li>
             <Card>
                 <div class="flex-between">
-                    <span class="text-lg font-semibold">{host_name}</span>
+                    <a
+                        href=move || format!("/app/clients/{}", client_id.get_value())
+                        class="text-lg font-semibold"
+                    >
+                        {host_name}
+                    </a>
                     <div class="flex gap-1">
+                        <Switch checked=paused label=t("Paused") on_toggle=on_pause />
                         <button
                             class="btn btn-icon btn-primary"
-                            title="Edit"
+                            title=t("Edit")
                             on:click=move |_| show_edit_modal.set(true)
                         >
                             <PencilIcon/>
                         </button>
                         <button
                             class="btn btn-icon btn-danger"
-                            title="Delete"
+                            title=t("Delete")
                             on:click=move |_| confirm_delete.set(true)
                         >
                             <TrashIcon/>
@@ -72,12 +94,15 @@ pub fn ClientCard(
                 <div class="detail-grid" style="margin-top: 0.75rem;">
                     <span class="detail-label">"ID"</span>
                     <span class="detail-value text-xs">{move || client_id.get_value()}</span>
-                    <span class="detail-label">"Poll interval"</span>
+                    <span class="detail-label">{t("Poll interval")}</span>
                     <span class="detail-value">{current_poll_ms}"ms"</span>
                 </div>
 
                 <Message signal=msg />
 
+                <PendingQueue client_id=client_id.get_value() />
+                <SyncBlackouts client_id=client_id.get_value() />
+
                 <div class="watch-group-list">
                     <Suspense fallback=Loading>
                         {move || Suspend::new(async move {
@@ -103,7 +128,7 @@ pub fn ClientCard(
                                 }
                                 Err(e) => view! {
                                     <div class="message message-error">
-                                        "Error loading watch groups: " {e}
+                                        {t("Error loading watch groups: ")} {e}
                                     </div>
                                 }.into_any(),
                             }
@@ -118,7 +143,12 @@ pub fn ClientCard(
                     on_saved=move || on_changed_sv.get_value()()
                 />
 
-                <ConfirmDialog show=confirm_delete message=confirm_msg on_confirm=do_delete />
+                <ConfirmDialog show=confirm_delete message=confirm_msg on_confirm=do_delete>
+                    <label class="text-muted">
+                        <input type="checkbox" bind:checked=anonymize />
+                        " " {t("Anonymize its file history")}
+                    </label>
+                </ConfirmDialog>
             </Card>
         </li>
     }
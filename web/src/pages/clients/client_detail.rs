@@ -0,0 +1,289 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::hooks::{use_navigate, use_params_map};
+use shared::dtos::{BandwidthUsageDto, ClientDetailDto, ClientRecentActivityDto};
+use shared::utc_millis::UtcMillis;
+use std::collections::BTreeMap;
+
+use crate::api;
+use crate::components::{Card, ConfirmDialog, EmptyState, Loading, Message, Switch, ToastSignal};
+use crate::i18n::t;
+use crate::pages::watch_group_files::format_size;
+
+use super::pending_queue::PendingQueue;
+
+/// one client: its watch groups with their last sync, recent events and transfers
+#[component]
+pub fn ClientDetailPage() -> impl IntoView {
+    let params = use_params_map();
+    let Some(client_id) = params.with_untracked(|p| p.get("id").filter(|id| !id.is_empty())) else {
+        return view! {
+            <div class="container">
+                <div class="message message-error">{t("Invalid client URL")}</div>
+            </div>
+        }
+        .into_any();
+    };
+    let client_id = StoredValue::new(client_id);
+    let (trigger, set_trigger) = signal(0u32);
+    let detail = LocalResource::new(move || {
+        trigger.get();
+        let id = client_id.get_value();
+        async move { api::fetch_client_detail(&id).await }
+    });
+    let activity = LocalResource::new(move || {
+        trigger.get();
+        let id = client_id.get_value();
+        async move { api::fetch_client_activity(&id).await }
+    });
+
+    view! {
+        <div class="container">
+            <a href="/app/clients" class="btn btn-secondary">{t("← Clients")}</a>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match detail.await {
+                        Ok(detail) => view! {
+                            <ClientOverview
+                                detail
+                                on_changed=move || set_trigger.update(|t| *t += 1)
+                            />
+                        }.into_any(),
+                        Err(e) => view! {
+                            <div class="message message-error">{t("Error loading client: ")} {e}</div>
+                        }.into_any(),
+                    }
+                })}
+            </Suspense>
+            <PendingQueue client_id=client_id.get_value() />
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match activity.await {
+                        Ok(activity) => view! { <ClientActivity activity /> }.into_any(),
+                        Err(e) => view! {
+                            <div class="message message-error">{t("Error loading activity: ")} {e}</div>
+                        }.into_any(),
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+    .into_any()
+}
+
+#[component]
+fn ClientOverview(
+    detail: ClientDetailDto,
+    on_changed: impl Fn() + 'static + Clone + Send + Sync,
+) -> impl IntoView {
+    let client_id = StoredValue::new(detail.client.id.clone());
+    let paused = RwSignal::new(detail.client.paused);
+    let confirm_delete = RwSignal::new(false);
+    let anonymize = RwSignal::new(false);
+    let msg = ToastSignal::new();
+    let navigate = StoredValue::new(use_navigate());
+
+    let on_pause = Callback::new(move |pause: bool| {
+        let id = client_id.get_value();
+        spawn_local(async move {
+            match api::pause_client(&id, pause).await {
+                Ok(()) => paused.set(pause),
+                Err(e) => msg.error(e),
+            }
+        });
+    });
+    let on_resync = move |_| {
+        let id = client_id.get_value();
+        let on_changed = on_changed.clone();
+        spawn_local(async move {
+            match api::resync_client(&id).await {
+                Ok(()) => {
+                    msg.success(t(
+                        "Sync state reset - rebuilt with the next sync of the client",
+                    ));
+                    on_changed();
+                }
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+    let do_delete = move || {
+        let id = client_id.get_value();
+        spawn_local(async move {
+            match api::delete_client(&id, anonymize.get_untracked()).await {
+                Ok(()) => navigate.get_value()("/app/clients", Default::default()),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let confirm_msg = t("Delete client '{name}'? It can't register again with the same id.")
+        .replace("{name}", &detail.client.host_name);
+    let last_seen = detail
+        .last_seen
+        .map(|at| at.to_string())
+        .unwrap_or_else(|| t("never").to_string());
+    let bandwidth = detail
+        .bandwidth_usage
+        .as_ref()
+        .map(bandwidth_usage_text)
+        .unwrap_or_else(|| "-".to_string());
+
+    view! {
+        <div class="flex-between">
+            <h1>{detail.client.host_name.clone()}</h1>
+            <div class="flex gap-1">
+                <Switch checked=paused label=t("Paused") on_toggle=on_pause />
+                <button class="btn btn-secondary" title=t("Forget the client's file lists and queue") on:click=on_resync>
+                    {t("Force resync")}
+                </button>
+                <button class="btn btn-danger" on:click=move |_| confirm_delete.set(true)>
+                    {t("Remove")}
+                </button>
+            </div>
+        </div>
+        <Message signal=msg />
+        <Card>
+            <div class="detail-grid">
+                <span class="detail-label">"ID"</span>
+                <span class="detail-value text-xs">{detail.client.id.clone()}</span>
+                <span class="detail-label">{t("Last seen")}</span>
+                <span class="detail-value">{last_seen}</span>
+                <span class="detail-label">{t("Poll interval")}</span>
+                <span class="detail-value">{detail.client.min_poll_interval_in_ms}"ms"</span>
+                <span class="detail-label">{t("Bandwidth this month")}</span>
+                <span class="detail-value">{bandwidth}</span>
+            </div>
+        </Card>
+
+        <h2>{t("Watch groups")}</h2>
+        {if detail.watch_groups.is_empty() {
+            view! { <EmptyState message=t("Not assigned to any watch group.") /> }.into_any()
+        } else {
+            view! {
+                <table class="history-table">
+                    <thead>
+                        <tr>
+                            <th>{t("Watch group")}</th>
+                            <th>{t("Folder")}</th>
+                            <th>{t("Excluded")}</th>
+                            <th>{t("Last sync")}</th>
+                            <th>{t("State")}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {detail.watch_groups.into_iter().map(|wg| {
+                            let assignment = wg.assignment;
+                            let mut excluded = assignment.exclude_dirs.join(", ");
+                            if assignment.exclude_dot_dirs {
+                                if !excluded.is_empty() {
+                                    excluded.push_str(", ");
+                                }
+                                excluded.push_str(".*");
+                            }
+                            let state = match (&assignment.scan_warning, assignment.paused) {
+                                (Some(warning), _) => warning.clone(),
+                                (None, true) => t("paused").to_string(),
+                                (None, false) => t("active").to_string(),
+                            };
+                            view! {
+                                <tr>
+                                    <td>
+                                        <a href=format!("/app/watch-groups/{}/detail", assignment.server_watch_group_id)>
+                                            {assignment.server_watch_group_name}
+                                        </a>
+                                    </td>
+                                    <td class="text-xs">{assignment.path_to_monitor}</td>
+                                    <td class="text-xs">{excluded}</td>
+                                    <td>{wg.last_synced_at.map(|at| at.to_string()).unwrap_or_else(|| "-".to_string())}</td>
+                                    <td>{state}</td>
+                                </tr>
+                            }
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            }.into_any()
+        }}
+
+        <ConfirmDialog show=confirm_delete message=confirm_msg on_confirm=do_delete>
+            <label class="text-muted">
+                <input type="checkbox" bind:checked=anonymize />
+                " " {t("Anonymize its file history")}
+            </label>
+        </ConfirmDialog>
+    }
+}
+
+#[component]
+fn ClientActivity(activity: ClientRecentActivityDto) -> impl IntoView {
+    let mut per_day: BTreeMap<u64, (u64, u64)> = BTreeMap::new();
+    for bucket in &activity.bandwidth {
+        let (uploaded, downloaded) = per_day.entry(bucket.start_utc_millis).or_default();
+        *uploaded += bucket.uploaded_bytes;
+        *downloaded += bucket.downloaded_bytes;
+    }
+
+    view! {
+        <h2>{t("Bandwidth")}</h2>
+        <p class="text-muted">
+            {t("Last 7 days:")} " ↑ " {format_size(activity.uploaded_bytes)}
+            " ↓ " {format_size(activity.downloaded_bytes)}
+        </p>
+        {(!per_day.is_empty()).then(|| view! {
+            <table class="history-table">
+                <thead>
+                    <tr><th>{t("Day")}</th><th>{t("Uploaded")}</th><th>{t("Downloaded")}</th></tr>
+                </thead>
+                <tbody>
+                    {per_day.into_iter().rev().map(|(start, (uploaded, downloaded))| view! {
+                        <tr>
+                            <td>{UtcMillis::from(start).to_string()}</td>
+                            <td>{format_size(uploaded)}</td>
+                            <td>{format_size(downloaded)}</td>
+                        </tr>
+                    }).collect_view()}
+                </tbody>
+            </table>
+        })}
+
+        <h2>{t("Recent events")}</h2>
+        {if activity.recent_events.is_empty() {
+            view! { <EmptyState message=t("No file events of this client.") /> }.into_any()
+        } else {
+            view! {
+                <ul class="text-xs">
+                    {activity.recent_events.into_iter().map(|event| {
+                        let kind = if event.deleted { "✕" } else { "✎" };
+                        view! {
+                            <li>
+                                {event.utc_millis.to_string()} " " {kind} " "
+                                {t("Watch group")} " " {event.watch_group_id} ": " {event.relative_path}
+                                {(!event.deleted).then(|| format!(" ({})", format_size(event.size_in_bytes)))}
+                            </li>
+                        }
+                    }).collect_view()}
+                </ul>
+            }.into_any()
+        }}
+    }
+}
+
+/// as counted by the client - with its caps from its config.yaml
+fn bandwidth_usage_text(usage: &BandwidthUsageDto) -> String {
+    let mut text = t("{up} up, {down} down")
+        .replace("{up}", &format_size(usage.uploaded_bytes))
+        .replace("{down}", &format_size(usage.downloaded_bytes));
+    let used = usage.total_bytes();
+    if usage.soft_cap_bytes > 0 {
+        text.push_str(&t(" - soft cap {cap}").replace("{cap}", &format_size(usage.soft_cap_bytes)));
+    }
+    if usage.hard_cap_bytes > 0 {
+        text.push_str(&t(" - hard cap {cap}").replace("{cap}", &format_size(usage.hard_cap_bytes)));
+    }
+    if usage.hard_cap_bytes > 0 && used >= usage.hard_cap_bytes {
+        text.push_str(t(" (reached - sync stopped)"));
+    } else if usage.soft_cap_bytes > 0 && used >= usage.soft_cap_bytes {
+        text.push_str(t(" (exceeded)"));
+    }
+    text
+}
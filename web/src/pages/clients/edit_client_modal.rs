@@ -3,6 +3,7 @@ use shared::dtos::ClientUpdateDto;
 
 use crate::api;
 use crate::components::Modal;
+use crate::i18n::t;
 
 #[component]
 pub fn EditClientModal(
@@ -19,7 +20,7 @@ pub fn EditClientModal(
         let ms_str = poll_value.get_untracked();
         async move {
             let Ok(ms) = ms_str.parse::<u16>() else {
-                return Err("Invalid poll interval".to_string());
+                return Err(t("Invalid poll interval").to_string());
             };
             let dto = ClientUpdateDto {
                 min_poll_interval_in_ms: ms,
@@ -29,9 +30,9 @@ pub fn EditClientModal(
     };
 
     view! {
-        <Modal show title="Edit Client Poll Interval" on_save on_saved>
+        <Modal show title=t("Edit Client Poll Interval") on_save on_saved>
             <div class="form-group">
-                <label>"Poll interval (ms)"</label>
+                <label>{t("Poll interval (ms)")}</label>
                 <input
                     type="number"
                     class="form-input"
@@ -3,6 +3,7 @@ use shared::dtos::ClientWatchGroupUpdateDto;
 
 use crate::api;
 use crate::components::Modal;
+use crate::i18n::t;
 
 #[component]
 pub fn EditWatchGroupModal(
@@ -26,7 +27,7 @@ pub fn EditWatchGroupModal(
         let excl_dot = exclude_dot.get_untracked();
         async move {
             if path_str.trim().is_empty() {
-                return Err("Path is required".to_string());
+                return Err(t("Path is required").to_string());
             }
             let excl: Vec<String> = excl_text
                 .lines()
@@ -43,18 +44,18 @@ pub fn EditWatchGroupModal(
     };
 
     view! {
-        <Modal show title="Edit Watch Group Assignment" on_save on_saved>
+        <Modal show title=t("Edit Watch Group Assignment") on_save on_saved>
             <div class="form-group">
-                <label>"Path"</label>
+                <label>{t("Path")}</label>
                 <input type="text" class="form-input" bind:value=path />
             </div>
             <div class="form-group">
-                <label>"Exclude dirs (one per line)"</label>
+                <label>{t("Exclude dirs (one per line)")}</label>
                 <textarea class="form-input" rows="3" bind:value=exclude_dirs_text />
             </div>
             <div class="checkbox-group" style="margin-bottom: 1.25rem;">
                 <input type="checkbox" bind:checked=exclude_dot />
-                <label>"Exclude dot dirs"</label>
+                <label>{t("Exclude dot dirs")}</label>
             </div>
         </Modal>
     }
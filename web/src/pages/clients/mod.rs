@@ -1,13 +1,20 @@
 mod add_watch_group_form;
 mod client_card;
+mod client_detail;
 mod edit_client_modal;
 mod edit_watch_group_modal;
+mod pending_queue;
+mod registration_templates;
+mod sync_blackouts;
 mod watch_group_assignment;
 
 use client_card::ClientCard;
+pub use client_detail::ClientDetailPage;
+use registration_templates::RegistrationTemplates;
 
 use crate::api;
 use crate::components::{EmptyState, Loading};
+use crate::i18n::t;
 use leptos::prelude::*;
 
 #[component]
@@ -21,21 +28,21 @@ pub fn ClientsPage() -> impl IntoView {
 
     view! {
         <div class="container">
-            <h1>"Clients"</h1>
+            <h1>{t("Clients")}</h1>
             <Suspense fallback=Loading>
                 {move || Suspend::new(async move {
                     let clients_res = clients.await;
                     let watch_groups_res = server_watch_groups.await;
                     match (clients_res, watch_groups_res) {
                         (Err(e), _) => view! {
-                            <div class="message message-error">"Error loading clients: " {e}</div>
+                            <div class="message message-error">{t("Error loading clients: ")} {e}</div>
                         }.into_any(),
                         (_, Err(e)) => view! {
-                            <div class="message message-error">"Error loading watch groups: " {e}</div>
+                            <div class="message message-error">{t("Error loading watch groups: ")} {e}</div>
                         }.into_any(),
                         (Ok(clients), Ok(watch_groups)) => {
                             if clients.is_empty() {
-                                view! { <EmptyState message="No clients registered yet." /> }.into_any()
+                                view! { <EmptyState message=t("No clients registered yet.") /> }.into_any()
                             } else {
                                 view! {
                                     <ul style="list-style: none; padding: 0;">
@@ -56,6 +63,13 @@ pub fn ClientsPage() -> impl IntoView {
                     }
                 })}
             </Suspense>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    server_watch_groups.await.ok().map(|watch_groups| view! {
+                        <RegistrationTemplates server_watch_groups=watch_groups />
+                    })
+                })}
+            </Suspense>
         </div>
     }
 }
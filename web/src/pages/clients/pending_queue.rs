@@ -0,0 +1,77 @@
+use leptos::prelude::*;
+use shared::dtos::{PendingKind, PendingWorkDto};
+
+use crate::api;
+use crate::i18n::t;
+use crate::pages::watch_group_files::format_size;
+
+/// files the client still has to transfer according to its last syncs - collapsed by default
+#[component]
+pub fn PendingQueue(client_id: String) -> impl IntoView {
+    let pending = LocalResource::new(move || {
+        let id = client_id.clone();
+        async move { api::fetch_client_pending(&id).await }
+    });
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || Suspend::new(async move {
+                match pending.await {
+                    Ok(pending) if pending.watch_groups.is_empty() => view! {
+                        <p class="text-muted">{t("Queue: up to date")}</p>
+                    }.into_any(),
+                    Ok(pending) => {
+                        let summary = summary(&pending);
+                        view! {
+                            <details>
+                                <summary class="text-muted">{summary}</summary>
+                                {pending.watch_groups.into_iter().map(|wg| view! {
+                                    <p class="text-xs">
+                                        {t("Watch group")} " " {wg.watch_group_id} " - " {t("synced")} " " {wg.synced_at.to_string()}
+                                    </p>
+                                    <ul class="text-xs">
+                                        {wg.items.into_iter().map(|item| view! {
+                                            <li>
+                                                {kind_text(item.kind)} " " {item.relative_path}
+                                                " (" {format_size(item.size_in_bytes)} ")"
+                                            </li>
+                                        }).collect_view()}
+                                    </ul>
+                                }).collect_view()}
+                            </details>
+                        }.into_any()
+                    }
+                    Err(e) => view! {
+                        <div class="message message-error">{t("Error loading queue: ")} {e}</div>
+                    }.into_any(),
+                }
+            })}
+        </Suspense>
+    }
+}
+
+fn kind_text(kind: PendingKind) -> &'static str {
+    match kind {
+        PendingKind::Upload => "↑",
+        PendingKind::Download => "↓",
+        PendingKind::Delete => "✕",
+    }
+}
+
+fn summary(pending: &PendingWorkDto) -> String {
+    let files: usize = pending.watch_groups.iter().map(|wg| wg.items.len()).sum();
+    let eta = match pending.estimated_seconds {
+        Some(secs) if secs >= 60 * 60 => t(" - about {h}h {m}m left")
+            .replace("{h}", &(secs / 3600).to_string())
+            .replace("{m}", &(secs % 3600 / 60).to_string()),
+        Some(secs) if secs >= 60 => {
+            t(" - about {m}m left").replace("{m}", &(secs / 60).to_string())
+        }
+        Some(secs) => t(" - about {s}s left").replace("{s}", &secs.to_string()),
+        None => String::new(),
+    };
+    t("Queue: {files} files, {size}")
+        .replace("{files}", &files.to_string())
+        .replace("{size}", &format_size(pending.pending_bytes))
+        + &eta
+}
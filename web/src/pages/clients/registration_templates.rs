@@ -0,0 +1,183 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::dtos::{RegistrationTemplateCreateDto, RegistrationTemplateDto, ServerWatchGroup};
+
+use crate::api;
+use crate::components::{Card, Loading, Message, ToastSignal, TrashIcon};
+use crate::i18n::t;
+
+/// watch groups (and poll interval) handed to newly registering clients by host name
+#[component]
+pub fn RegistrationTemplates(server_watch_groups: Vec<ServerWatchGroup>) -> impl IntoView {
+    let (trigger, set_trigger) = signal(0u32);
+    let templates = LocalResource::new(move || {
+        trigger.get();
+        api::fetch_registration_templates()
+    });
+    let server_watch_groups = StoredValue::new(server_watch_groups);
+
+    let default_watch_group_id = server_watch_groups
+        .get_value()
+        .first()
+        .map(|watch_group| watch_group.id.to_string())
+        .unwrap_or_default();
+    let host_pattern = RwSignal::new(String::new());
+    let selected_watch_group_id = RwSignal::new(default_watch_group_id);
+    let path = RwSignal::new(String::new());
+    let exclude_dirs_text = RwSignal::new(String::new());
+    let exclude_dot = RwSignal::new(true);
+    let poll_ms = RwSignal::new(String::new());
+    let msg = ToastSignal::new();
+
+    let do_add = move |_| {
+        let Ok(server_watch_group_id) = selected_watch_group_id.get_untracked().parse::<i64>()
+        else {
+            msg.error(t("Invalid watch group selection"));
+            return;
+        };
+        let min_poll_interval_in_ms = match poll_ms.get_untracked().trim() {
+            "" => None,
+            ms => match ms.parse::<u16>() {
+                Ok(ms) => Some(ms),
+                Err(_) => {
+                    msg.error(t("Poll interval must be a number between 0 and 65535"));
+                    return;
+                }
+            },
+        };
+        let dto = RegistrationTemplateCreateDto {
+            host_pattern: host_pattern.get_untracked().trim().to_string(),
+            server_watch_group_id,
+            path_to_monitor: path.get_untracked(),
+            exclude_dirs: exclude_dirs_text
+                .get_untracked()
+                .lines()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            exclude_dot_dirs: exclude_dot.get_untracked(),
+            min_poll_interval_in_ms,
+        };
+        spawn_local(async move {
+            match api::create_registration_template(&dto).await {
+                Ok(()) => {
+                    host_pattern.set(String::new());
+                    path.set(String::new());
+                    exclude_dirs_text.set(String::new());
+                    poll_ms.set(String::new());
+                    set_trigger.update(|t| *t += 1);
+                }
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let do_delete = move |id: i64| {
+        spawn_local(async move {
+            match api::delete_registration_template(id).await {
+                Ok(()) => set_trigger.update(|t| *t += 1),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    view! {
+        <h2>{t("Registration templates")}</h2>
+        <p class="text-muted">
+            {t("New clients get the watch groups of all templates matching their host name")}
+            " (" <code>"*"</code> ", " <code>"?"</code> ")."
+        </p>
+        <Suspense fallback=Loading>
+            {move || Suspend::new(async move {
+                match templates.await {
+                    Ok(templates) => view! {
+                        <ul style="list-style: none; padding: 0;">
+                            {templates.into_iter().map(|template| view! {
+                                <TemplateRow template=template on_delete=do_delete />
+                            }).collect_view()}
+                        </ul>
+                    }.into_any(),
+                    Err(e) => view! {
+                        <div class="message message-error">{t("Error loading templates: ")} {e}</div>
+                    }.into_any(),
+                }
+            })}
+        </Suspense>
+        <Card dashed=true>
+            <div class="form-group">
+                <label>{t("Host pattern")}</label>
+                <input type="text" class="form-input" placeholder="laptop-*" bind:value=host_pattern />
+            </div>
+            <div class="form-group">
+                <label>{t("Watch Group")}</label>
+                <select class="form-input" bind:value=selected_watch_group_id>
+                    {move || server_watch_groups.get_value().into_iter().map(|watch_group| {
+                        view! { <option value=watch_group.id.to_string()>{watch_group.name}</option> }
+                    }).collect_view()}
+                </select>
+            </div>
+            <div class="form-group">
+                <label>{t("Path")}</label>
+                <input type="text" class="form-input" bind:value=path />
+            </div>
+            <div class="form-group">
+                <label>{t("Exclude dirs (one per line)")}</label>
+                <textarea class="form-input" rows="3" bind:value=exclude_dirs_text />
+            </div>
+            <div class="checkbox-group">
+                <input type="checkbox" bind:checked=exclude_dot />
+                <label>{t("Exclude dot dirs")}</label>
+            </div>
+            <div class="form-group">
+                <label>{t("Poll interval in ms (empty = default)")}</label>
+                <input type="number" class="form-input" bind:value=poll_ms />
+            </div>
+            <button class="btn btn-success" on:click=do_add>{t("Add template")}</button>
+            <Message signal=msg />
+        </Card>
+    }
+}
+
+#[component]
+fn TemplateRow(
+    template: RegistrationTemplateDto,
+    on_delete: impl Fn(i64) + 'static + Copy + Send + Sync,
+) -> impl IntoView {
+    let id = template.id;
+    let poll = template
+        .min_poll_interval_in_ms
+        .map(|ms| format!("{ms}ms"))
+        .unwrap_or_else(|| t("default").to_string());
+    let excludes = if template.exclude_dirs.is_empty() {
+        "-".to_string()
+    } else {
+        template.exclude_dirs.join(", ")
+    };
+
+    view! {
+        <li>
+            <Card>
+                <div class="flex-between">
+                    <span class="text-lg font-semibold">{template.host_pattern}</span>
+                    <button
+                        class="btn btn-icon btn-danger"
+                        title=t("Delete")
+                        on:click=move |_| on_delete(id)
+                    >
+                        <TrashIcon/>
+                    </button>
+                </div>
+                <div class="detail-grid" style="margin-top: 0.75rem;">
+                    <span class="detail-label">{t("Watch group")}</span>
+                    <span class="detail-value">{template.server_watch_group_name}</span>
+                    <span class="detail-label">{t("Path")}</span>
+                    <span class="detail-value">{template.path_to_monitor}</span>
+                    <span class="detail-label">{t("Excluded")}</span>
+                    <span class="detail-value">{excludes}</span>
+                    <span class="detail-label">{t("Poll interval")}</span>
+                    <span class="detail-value">{poll}</span>
+                </div>
+            </Card>
+        </li>
+    }
+}
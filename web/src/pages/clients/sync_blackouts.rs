@@ -0,0 +1,148 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::dtos::{SyncBlackoutCreateDto, SyncBlackoutDto};
+
+use crate::api;
+use crate::components::{Loading, Message, ToastSignal, TrashIcon};
+use crate::i18n::t;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// times (server local time) the client must not sync - its syncs are paused until they end
+#[component]
+pub fn SyncBlackouts(client_id: String) -> impl IntoView {
+    let client_id = StoredValue::new(client_id);
+    let (trigger, set_trigger) = signal(0u32);
+    let blackouts = LocalResource::new(move || {
+        trigger.get();
+        let id = client_id.get_value();
+        async move { api::fetch_sync_blackouts(&id).await }
+    });
+    let start = RwSignal::new("22:00".to_string());
+    let end = RwSignal::new("06:00".to_string());
+    let note = RwSignal::new(String::new());
+    let weekdays = RwSignal::new([false; 7]);
+    let msg = ToastSignal::new();
+
+    let do_add = move |_| {
+        let dto = SyncBlackoutCreateDto {
+            weekdays: (1..=7u8)
+                .filter(|day| weekdays.get_untracked()[*day as usize - 1])
+                .collect(),
+            start: start.get_untracked(),
+            end: end.get_untracked(),
+            note: Some(note.get_untracked()).filter(|n| !n.trim().is_empty()),
+        };
+        let id = client_id.get_value();
+        spawn_local(async move {
+            match api::create_sync_blackout(&id, &dto).await {
+                Ok(()) => {
+                    note.set(String::new());
+                    weekdays.set([false; 7]);
+                    set_trigger.update(|t| *t += 1);
+                }
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let do_delete = move |blackout_id: i64| {
+        let id = client_id.get_value();
+        spawn_local(async move {
+            match api::delete_sync_blackout(&id, blackout_id).await {
+                Ok(()) => set_trigger.update(|t| *t += 1),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    view! {
+        <details>
+            <summary class="text-muted">
+                {move || match blackouts.get() {
+                    Some(Ok(blackouts)) if !blackouts.is_empty() => {
+                        t("Sync blackouts: {count}").replace("{count}", &blackouts.len().to_string())
+                    }
+                    _ => t("Sync blackouts: none").to_string(),
+                }}
+            </summary>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match blackouts.await {
+                        Ok(blackouts) => view! {
+                            <ul style="list-style: none; padding: 0;">
+                                {blackouts.into_iter().map(|blackout| {
+                                    let blackout_id = blackout.id;
+                                    view! {
+                                        <li class="flex-between">
+                                            <span class="detail-value text-xs">
+                                                {describe(&blackout)}
+                                            </span>
+                                            <button
+                                                class="btn btn-icon btn-danger"
+                                                title=t("Delete")
+                                                on:click=move |_| do_delete(blackout_id)
+                                            >
+                                                <TrashIcon/>
+                                            </button>
+                                        </li>
+                                    }
+                                }).collect_view()}
+                            </ul>
+                        }.into_any(),
+                        Err(e) => view! {
+                            <div class="message message-error">{t("Error loading sync blackouts: ")} {e}</div>
+                        }.into_any(),
+                    }
+                })}
+            </Suspense>
+            <div class="flex gap-1 text-xs">
+                {WEEKDAYS.into_iter().enumerate().map(|(i, day)| view! {
+                    <label>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || weekdays.get()[i]
+                            on:change=move |e| {
+                                let checked = event_target_checked(&e);
+                                weekdays.update(|days| days[i] = checked);
+                            }
+                        />
+                        {t(day)}
+                    </label>
+                }).collect_view()}
+            </div>
+            <div class="flex gap-2">
+                <input type="time" class="form-input" bind:value=start />
+                <input type="time" class="form-input" bind:value=end />
+                <input type="text" class="form-input" placeholder=t("Note (optional)")
+                    style="flex: 1;"
+                    bind:value=note
+                />
+                <button class="btn btn-success" on:click=do_add>{t("Add")}</button>
+            </div>
+            <Message signal=msg />
+        </details>
+    }
+}
+
+/// e.g. `Mon, Fri 22:00 - 06:00 (renders)` - no days means every day
+fn describe(blackout: &SyncBlackoutDto) -> String {
+    let days = if blackout.weekdays.is_empty() {
+        t("Daily").to_string()
+    } else {
+        blackout
+            .weekdays
+            .iter()
+            .filter_map(|day| WEEKDAYS.get((*day as usize).wrapping_sub(1)))
+            .copied()
+            .map(t)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let note = blackout
+        .note
+        .as_ref()
+        .map(|note| format!(" ({note})"))
+        .unwrap_or_default();
+    format!("{days} {} - {}{note}", blackout.start, blackout.end)
+}
@@ -3,7 +3,8 @@ use leptos::task::spawn_local;
 use shared::dtos::ClientWatchGroupDto;
 
 use crate::api;
-use crate::components::{ConfirmDialog, Message, PencilIcon, ToastSignal, TrashIcon};
+use crate::components::{ConfirmDialog, Message, PencilIcon, Switch, ToastSignal, TrashIcon};
+use crate::i18n::t;
 
 use super::edit_watch_group_modal::EditWatchGroupModal;
 
@@ -22,6 +23,8 @@ pub fn WatchGroupAssignment(
     let client_id_sv = StoredValue::new(client_id);
     let on_changed_sv = StoredValue::new(on_changed);
 
+    let scan_warning = assignment.scan_warning;
+    let paused = RwSignal::new(assignment.paused);
     let confirm_delete = RwSignal::new(false);
     let show_edit_modal = RwSignal::new(false);
     let msg = ToastSignal::new();
@@ -36,7 +39,17 @@ pub fn WatchGroupAssignment(
         });
     };
 
-    let confirm_msg = format!("Delete '{}' assignment?", watch_group_name);
+    let on_pause = Callback::new(move |pause: bool| {
+        let id = client_id_sv.get_value();
+        spawn_local(async move {
+            match api::pause_client_watch_group(&id, watch_group_id, pause).await {
+                Ok(()) => paused.set(pause),
+                Err(e) => msg.error(e),
+            }
+        });
+    });
+
+    let confirm_msg = t("Delete '{name}' assignment?").replace("{name}", &watch_group_name);
     let exclude_dirs_display = if initial_exclude_dirs.is_empty() {
         "—".to_string()
     } else {
@@ -51,18 +64,22 @@ pub fn WatchGroupAssignment(
                     {watch_group_name}
                     " "
                     <span class="text-muted text-xs">"(#" {watch_group_id} ")"</span>
+                    {scan_warning.map(|warning| view! {
+                        <span class="tag warning" title=warning>{t("Scan warning")}</span>
+                    })}
                 </span>
                 <div class="flex gap-1">
+                    <Switch checked=paused label=t("Paused") on_toggle=on_pause />
                     <button
                         class="btn btn-icon btn-primary"
-                        title="Edit"
+                        title=t("Edit")
                         on:click=move |_| show_edit_modal.set(true)
                     >
                         <PencilIcon/>
                     </button>
                     <button
                         class="btn btn-icon btn-danger"
-                        title="Delete"
+                        title=t("Delete")
                         on:click=move |_| confirm_delete.set(true)
                     >
                         <TrashIcon/>
@@ -71,12 +88,12 @@ pub fn WatchGroupAssignment(
             </div>
 
             <div class="detail-grid" style="margin-top: 0.5rem; font-size: 0.85rem;">
-                <span class="detail-label">"Path"</span>
+                <span class="detail-label">{t("Path")}</span>
                 <span class="detail-value">{path_for_display}</span>
-                <span class="detail-label">"Exclude dirs"</span>
+                <span class="detail-label">{t("Exclude dirs")}</span>
                 <span class="detail-value">{exclude_dirs_display}</span>
-                <span class="detail-label">"Exclude dots"</span>
-                <span class="detail-value">{if initial_exclude_dot { "yes" } else { "no" }}</span>
+                <span class="detail-label">{t("Exclude dots")}</span>
+                <span class="detail-value">{if initial_exclude_dot { t("yes") } else { t("no") }}</span>
             </div>
 
             <Message signal=msg />
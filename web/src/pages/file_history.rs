@@ -0,0 +1,188 @@
+use leptos::prelude::*;
+use leptos_router::hooks::{use_params_map, use_query_map};
+use shared::dtos::{DiffDto, DiffLineDto, FileVersionDto};
+
+use crate::api;
+use crate::components::{Card, EmptyState, Loading};
+use crate::i18n::t;
+
+/// a deleted version diffs like an empty file
+fn is_diffable(version: &FileVersionDto) -> bool {
+    version.content_available || version.deleted
+}
+
+#[component]
+pub fn FileHistoryPage() -> impl IntoView {
+    let params = use_params_map();
+    let query = use_query_map();
+
+    let wg_id: Option<i64> = params.with_untracked(|p| p.get("id").and_then(|s| s.parse().ok()));
+    let path: Option<String> = query.with_untracked(|q| q.get("path").filter(|s| !s.is_empty()));
+
+    let (Some(id), Some(path)) = (wg_id, path) else {
+        return view! {
+            <div class="container">
+                <div class="message message-error">{t("Invalid history URL")}</div>
+            </div>
+        }
+        .into_any();
+    };
+
+    let path_sv = StoredValue::new(path.clone());
+    let versions = LocalResource::new(move || {
+        let path = path_sv.get_value();
+        async move { api::fetch_file_history(id, &path).await }
+    });
+    let dir = path
+        .rsplit_once('/')
+        .map(|(dir, _)| dir.to_string())
+        .unwrap_or_default();
+
+    view! {
+        <div class="container">
+            <a href=api::watch_group_dir_url(id, &dir) class="btn btn-secondary">{t("← Back")}</a>
+            <h1>{t("History")}</h1>
+            <p class="text-muted">{path}</p>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match versions.await {
+                        Ok(versions) if versions.is_empty() => {
+                            view! { <EmptyState message=t("No history for this file.") /> }.into_any()
+                        }
+                        Ok(versions) => view! { <VersionList wg_id=id path=path_sv.get_value() versions /> }.into_any(),
+                        Err(e) => view! { <div class="message message-error">{t("Error: ")} {e}</div> }.into_any(),
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+    .into_any()
+}
+
+#[component]
+fn VersionList(wg_id: i64, path: String, versions: Vec<FileVersionDto>) -> impl IntoView {
+    // compare the two latest diffable versions by default
+    let mut diffable = versions.iter().filter(|v| is_diffable(v)).rev();
+    let to = RwSignal::new(diffable.next().map(|v| v.event_id.clone()));
+    let from = RwSignal::new(diffable.next().map(|v| v.event_id.clone()));
+
+    let path_sv = StoredValue::new(path);
+    let diff = LocalResource::new(move || {
+        let selection = from.get().zip(to.get());
+        async move {
+            match selection {
+                Some((from, to)) => api::fetch_diff(wg_id, &path_sv.get_value(), &from, &to)
+                    .await
+                    .map(Some),
+                None => Ok(None),
+            }
+        }
+    });
+
+    view! {
+        <Card>
+            <table class="history-table">
+                <thead>
+                    <tr>
+                        <th>{t("From")}</th>
+                        <th>{t("To")}</th>
+                        <th>{t("Time")}</th>
+                        <th>{t("Client")}</th>
+                        <th>{t("Size")}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {versions
+                        .into_iter()
+                        .rev()
+                        .map(|version| {
+                            let diffable = is_diffable(&version);
+                            let id_from = version.event_id.clone();
+                            let id_to = version.event_id.clone();
+                            let id_from_checked = version.event_id.clone();
+                            let id_to_checked = version.event_id.clone();
+                            view! {
+                                <tr>
+                                    <td>
+                                        <input type="radio" name="from" disabled=!diffable
+                                            prop:checked=move || from.get().as_ref() == Some(&id_from_checked)
+                                            on:change=move |_| from.set(Some(id_from.clone()))
+                                        />
+                                    </td>
+                                    <td>
+                                        <input type="radio" name="to" disabled=!diffable
+                                            prop:checked=move || to.get().as_ref() == Some(&id_to_checked)
+                                            on:change=move |_| to.set(Some(id_to.clone()))
+                                        />
+                                    </td>
+                                    <td>{version.utc_millis.to_string()}</td>
+                                    <td>{version.client_host.unwrap_or_else(|| "-".to_string())}</td>
+                                    <td>
+                                        {if version.deleted {
+                                            t("deleted").to_string()
+                                        } else {
+                                            format!("{} B", version.size_in_bytes)
+                                        }}
+                                        {(!diffable).then(|| format!(" ({})", t("content not kept")))}
+                                    </td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </Card>
+        <Suspense fallback=Loading>
+            {move || Suspend::new(async move {
+                match diff.await {
+                    Ok(Some(diff)) => view! { <DiffView diff /> }.into_any(),
+                    Ok(None) => view! { <EmptyState message=t("Select two versions to compare.") /> }.into_any(),
+                    Err(e) => view! { <div class="message message-error">{t("Error: ")} {e}</div> }.into_any(),
+                }
+            })}
+        </Suspense>
+    }
+}
+
+/// old version left, new version right
+#[component]
+fn DiffView(diff: DiffDto) -> impl IntoView {
+    if diff.rows.iter().all(|row| !row.changed) {
+        return view! { <EmptyState message=t("Both versions are identical.") /> }.into_any();
+    }
+
+    let cells = |line: Option<DiffLineDto>| match line {
+        Some(line) => view! {
+            <td class="diff-line-number">{line.number}</td>
+            <td class="diff-text">{line.text}</td>
+        }
+        .into_any(),
+        None => view! {
+            <td class="diff-line-number"></td>
+            <td class="diff-text"></td>
+        }
+        .into_any(),
+    };
+
+    view! {
+        <Card>
+            <table class="diff-table">
+                <tbody>
+                    {diff
+                        .rows
+                        .into_iter()
+                        .map(|row| {
+                            view! {
+                                <tr class:diff-changed=row.changed>
+                                    {cells(row.old)}
+                                    {cells(row.new)}
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </Card>
+    }
+    .into_any()
+}
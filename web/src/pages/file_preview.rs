@@ -0,0 +1,173 @@
+use leptos::prelude::*;
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, html};
+use shared::dtos::FilePreviewDto;
+
+use super::watch_group_files::format_size;
+use crate::api;
+use crate::components::Loading;
+use crate::i18n::t;
+
+/// beginning of a text file next to the file list - markdown rendered, csv/tsv as table
+#[component]
+pub fn FilePreview(wg_id: i64, path: String, on_close: Callback<()>) -> impl IntoView {
+    let preview = LocalResource::new({
+        let path = path.clone();
+        move || {
+            let path = path.clone();
+            async move { api::fetch_preview(wg_id, &path).await }
+        }
+    });
+
+    view! {
+        <aside class="preview-pane">
+            <div class="flex-between">
+                <span class="preview-title">{path}</span>
+                <button class="btn btn-secondary" on:click=move |_| on_close.run(())>
+                    {t("Close")}
+                </button>
+            </div>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match preview.await {
+                        Ok(preview) => view! { <PreviewBody preview /> }.into_any(),
+                        Err(e) => {
+                            view! {
+                                <div class="message message-error">{t("Preview unavailable: ")} {e}</div>
+                            }
+                                .into_any()
+                        }
+                    }
+                })}
+            </Suspense>
+        </aside>
+    }
+}
+
+#[component]
+fn PreviewBody(preview: FilePreviewDto) -> impl IntoView {
+    let meta = format!(
+        "{} · {}{}",
+        preview.encoding,
+        format_size(preview.size_in_bytes),
+        if preview.truncated {
+            t(" · only the beginning is shown")
+        } else {
+            ""
+        }
+    );
+    let body = match preview.language.as_deref() {
+        Some("markdown") => view! {
+            <div class="preview-markdown" inner_html=markdown_to_html(&preview.content)></div>
+        }
+        .into_any(),
+        Some("csv") => view! { <CsvTable rows=parse_csv(&preview.content, ',') /> }.into_any(),
+        Some("tsv") => view! { <CsvTable rows=parse_csv(&preview.content, '\t') /> }.into_any(),
+        _ => view! { <pre class="preview-text">{preview.content}</pre> }.into_any(),
+    };
+
+    view! {
+        <p class="text-muted">{meta}</p>
+        {body}
+    }
+}
+
+#[component]
+fn CsvTable(rows: Vec<Vec<String>>) -> impl IntoView {
+    let mut rows = rows.into_iter();
+    let header = rows.next().unwrap_or_default();
+    view! {
+        <div class="preview-table-wrapper">
+            <table class="preview-table">
+                <thead>
+                    <tr>{header.into_iter().map(|cell| view! { <th>{cell}</th> }).collect_view()}</tr>
+                </thead>
+                <tbody>
+                    {rows
+                        .map(|row| {
+                            view! {
+                                <tr>{row.into_iter().map(|cell| view! { <td>{cell}</td> }).collect_view()}</tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+/// synced files are not trusted - embedded html is shown as text and script links are dropped
+fn markdown_to_html(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        other => other,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    let scheme = url.trim_start().to_lowercase();
+    if scheme.starts_with("javascript:")
+        || scheme.starts_with("vbscript:")
+        || scheme.starts_with("data:")
+    {
+        CowStr::Borrowed("#")
+    } else {
+        url
+    }
+}
+
+/// quoted fields may contain delimiters, line breaks and doubled quotes
+fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
@@ -0,0 +1,105 @@
+use leptos::prelude::*;
+use shared::dtos::{FileStatusDto, FileSyncState};
+
+use super::watch_group_files::format_size;
+use crate::api;
+use crate::components::Loading;
+use crate::i18n::t;
+
+/// which machines have the latest version of a file - next to the file list
+#[component]
+pub fn FileStatusPanel(wg_id: i64, path: String, on_close: Callback<()>) -> impl IntoView {
+    let status = LocalResource::new({
+        let path = path.clone();
+        move || {
+            let path = path.clone();
+            async move { api::fetch_file_status(wg_id, &path).await }
+        }
+    });
+
+    view! {
+        <aside class="preview-pane">
+            <div class="flex-between">
+                <span class="preview-title">{path}</span>
+                <button class="btn btn-secondary" on:click=move |_| on_close.run(())>
+                    {t("Close")}
+                </button>
+            </div>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match status.await {
+                        Ok(status) => view! { <StatusTable status /> }.into_any(),
+                        Err(e) => {
+                            view! {
+                                <div class="message message-error">{t("Status unavailable: ")} {e}</div>
+                            }
+                                .into_any()
+                        }
+                    }
+                })}
+            </Suspense>
+        </aside>
+    }
+}
+
+#[component]
+fn StatusTable(status: FileStatusDto) -> impl IntoView {
+    let latest = if status.deleted {
+        t("Deleted at {at}").replace("{at}", &status.utc_millis.to_string())
+    } else {
+        t("Latest version: {size} from {at}")
+            .replace("{size}", &format_size(status.size_in_bytes))
+            .replace("{at}", &status.utc_millis.to_string())
+    };
+
+    view! {
+        <p class="text-muted">{latest}</p>
+        <table>
+            <thead>
+                <tr>
+                    <th>{t("Client")}</th>
+                    <th>{t("State")}</th>
+                    <th>{t("Its copy")}</th>
+                    <th>{t("Last sync")}</th>
+                </tr>
+            </thead>
+            <tbody>
+                {status
+                    .clients
+                    .into_iter()
+                    .map(|client| {
+                        let copy = match (client.size_in_bytes, client.last_updated_utc_millis) {
+                            (Some(size), Some(at)) => t("{size} from {at}")
+                                .replace("{size}", &format_size(size))
+                                .replace("{at}", &at.to_string()),
+                            _ => "-".to_string(),
+                        };
+                        view! {
+                            <tr>
+                                <td>{client.host_name}</td>
+                                <td>{state_text(client.state)}</td>
+                                <td>{copy}</td>
+                                <td>
+                                    {client
+                                        .reported_at
+                                        .map(|at| at.to_string())
+                                        .unwrap_or_else(|| t("never").to_string())}
+                                </td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+    }
+}
+
+fn state_text(state: FileSyncState) -> &'static str {
+    match state {
+        FileSyncState::UpToDate => t("✓ up to date"),
+        FileSyncState::Outdated => t("outdated"),
+        FileSyncState::Missing => t("missing"),
+        FileSyncState::Newer => t("newer - not uploaded yet"),
+        FileSyncState::NotReported => t("not synced yet"),
+    }
+}
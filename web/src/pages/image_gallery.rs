@@ -1,10 +1,11 @@
 use leptos::prelude::*;
 use leptos::task::spawn_local;
 use leptos_router::hooks::{use_navigate, use_params_map, use_query_map};
-use shared::dtos::{is_image, FileDescription};
+use shared::dtos::{FileDescription, is_image};
 
 use crate::api;
 use crate::components::{Loading, Message, ToastSignal, TrashIcon};
+use crate::i18n::t;
 
 fn images_in_same_dir(all: &[FileDescription], current_path: &str) -> Vec<FileDescription> {
     let current_segments: Vec<&str> = current_path.split('/').collect();
@@ -43,7 +44,7 @@ pub fn ImageGalleryPage() -> impl IntoView {
     let (Some(id), Some(current_path)) = (wg_id, path) else {
         return view! {
             <div class="gallery-container">
-                <div class="message message-error">"Invalid gallery URL"</div>
+                <div class="message message-error">{t("Invalid gallery URL")}</div>
             </div>
         }
         .into_any();
@@ -70,7 +71,7 @@ pub fn ImageGalleryPage() -> impl IntoView {
                     Err(e) => {
                         view! {
                             <div class="gallery-container">
-                                <div class="message message-error">"Error: " {e}</div>
+                                <div class="message message-error">{t("Error: ")} {e}</div>
                             </div>
                         }
                         .into_any()
@@ -103,7 +104,7 @@ fn GalleryViewer(
             let Some(idx) = current_idx else {
                 return view! {
                     <div class="gallery-container">
-                        <div class="message message-error">"Image not found"</div>
+                        <div class="message message-error">{t("Image not found")}</div>
                     </div>
                 }
                 .into_any();
@@ -135,9 +136,10 @@ fn GalleryViewer(
             let on_delete_click = move |_| {
                 let ok = web_sys::window()
                     .unwrap()
-                    .confirm_with_message(&format!(
-                        "Delete '{file_name_for_delete}'? This cannot be undone."
-                    ))
+                    .confirm_with_message(
+                        &t("Delete '{name}'? This cannot be undone.")
+                            .replace("{name}", &file_name_for_delete),
+                    )
                     .unwrap_or(false);
                 if !ok {
                     return;
@@ -178,7 +180,7 @@ fn GalleryViewer(
                         <Message signal=msg />
                         <button
                             class="btn btn-icon btn-danger gallery-delete-btn"
-                            title="Delete image"
+                            title=t("Delete image")
                             on:click=on_delete_click
                         >
                             <TrashIcon />
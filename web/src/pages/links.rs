@@ -2,6 +2,7 @@ use crate::api;
 use crate::components::{
     EmptyState, Loading, Message, Modal, PencilIcon, PlusIcon, TagBadge, ToastSignal, TrashIcon,
 };
+use crate::i18n::t;
 use itertools::Itertools;
 use leptos::prelude::*;
 use leptos::reactive::spawn_local;
@@ -30,13 +31,13 @@ pub fn LinksPage() -> impl IntoView {
 
     view! {
         <div class="container">
-            <h1>"Links"</h1>
+            <h1>{t("Links")}</h1>
             <Message signal=msg />
 
             <Suspense fallback=Loading>
                 {move || Suspend::new(async move {
                     match links.await {
-                        Err(e) => view! { <div class="message message-error">"Error: " {e}</div> }.into_any(),
+                        Err(e) => view! { <div class="message message-error">{t("Error: ")} {e}</div> }.into_any(),
                         Ok(links) => {
                             let tags: Vec<String> = {
                                 let mut seen = std::collections::HashSet::new();
@@ -48,7 +49,7 @@ pub fn LinksPage() -> impl IntoView {
                             distinct_tags.set(tags.clone());
 
                             if links.is_empty() {
-                                view! { <EmptyState message="No links saved yet." /> }.into_any()
+                                view! { <EmptyState message=t("No links saved yet.") /> }.into_any()
                             } else {
                                 let links = StoredValue::new(links);
 
@@ -93,7 +94,7 @@ pub fn LinksPage() -> impl IntoView {
             <AddOrEditLinkModal show=show_add distinct_tags=Signal::from(distinct_tags) on_saved=move||reload_links.update(|v| *v += 1) />
             <button
                 class="btn btn-icon btn-primary"
-                title="Add"
+                title=t("Add")
                 on:click=move |_| show_add.set(true)
             >
                 <PlusIcon/>
@@ -133,7 +134,7 @@ pub fn Link(
 
                 <button
                     class="btn btn-icon btn-primary"
-                    title="Edit"
+                    title=t("Edit")
                     on:click=move |_| {
                         println!("edit");
                         show_edit.set(!show_edit.get());
@@ -143,7 +144,7 @@ pub fn Link(
                 </button>
                 <button
                     class="btn btn-icon btn-danger"
-                    title="Delete"
+                    title=t("Delete")
                     on:click=move |_| {
                         let url = link.url.clone();
                         spawn_local(async move {
@@ -221,13 +222,13 @@ pub fn AddOrEditLinkModal(
     };
 
     view! {
-        <Modal show title="Add link" on_save on_saved>
+        <Modal show title=t("Add link") on_save on_saved>
             <div class="form-group">
-                <label>"Url"</label>
+                <label>{t("Url")}</label>
                 <input type="text" class="form-input" bind:value=url/>
             </div>
             <div class="form-group">
-                <label>"Title"</label>
+                <label>{t("Title")}</label>
                 <input type="text" class="form-input" bind:value=title/>
             </div>
 
@@ -243,7 +244,7 @@ pub fn AddOrEditLinkModal(
            }).collect_view()}
 
             <div class="form-group">
-                <label>"Tags"</label>
+                <label>{t("Tags")}</label>
                 <div style="display: flex; flex-wrap: wrap; gap: 4px; margin-bottom: 4px;">
                     {move || selected_tags.get().into_iter().map(|tag| {
                         view! {
@@ -0,0 +1,244 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::components::{A, Redirect};
+use leptos_router::hooks::{use_params_map, use_query_map};
+use shared::dtos::DefaultPage;
+
+use super::ClientsPage;
+use super::watch_group_files::{files_at_depth, format_size};
+use crate::api;
+use crate::components::{
+    EmptyState, FileIcon, FolderIcon, Loading, Message, ToastSignal, use_ui_settings,
+};
+use crate::i18n::t;
+
+/// below this width the app opens the phone pages instead of the admin dashboard
+const PHONE_MAX_WIDTH: f64 = 700.0;
+
+fn is_phone() -> bool {
+    web_sys::window()
+        .and_then(|w| w.inner_width().ok())
+        .and_then(|w| w.as_f64())
+        .is_some_and(|w| w < PHONE_MAX_WIDTH)
+}
+
+/// `/app/m/...` - the phone pages bring their own header instead of the navbar
+pub fn is_mobile_route(pathname: &str) -> bool {
+    let path = pathname.strip_prefix("/app").unwrap_or(pathname);
+    path == "/m" || path.starts_with("/m/")
+}
+
+/// "a/b" -> ["a", "b"]
+fn dir_segments(raw: &str) -> Vec<String> {
+    raw.split('/')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// phones get the mobile pages, desktops the default page of the account's ui settings
+#[component]
+pub fn HomePage() -> impl IntoView {
+    if is_phone() {
+        return view! { <Redirect path="/app/m" /> }.into_any();
+    }
+    let ui_settings = use_ui_settings();
+    (move || match ui_settings.get().map(|s| s.default_page) {
+        None => view! { <Loading /> }.into_any(),
+        Some(DefaultPage::Clients) => view! { <ClientsPage /> }.into_any(),
+        Some(page) => view! { <Redirect path=page.path() /> }.into_any(),
+    })
+    .into_any()
+}
+
+#[component]
+pub fn MobileHomePage() -> impl IntoView {
+    let groups = LocalResource::new(api::fetch_watch_groups);
+
+    view! {
+        <div class="mobile">
+            <header class="mobile-header">
+                <span class="mobile-title">"File Sync"</span>
+                <A href="/app/clients" attr:class="mobile-header-link">{t("Admin")}</A>
+            </header>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match groups.await {
+                        Ok(groups) if groups.is_empty() => view! {
+                            <EmptyState message=t("No watch groups yet.") />
+                        }.into_any(),
+                        Ok(groups) => view! {
+                            <ul class="mobile-list">
+                                {groups.into_iter().map(|group| view! {
+                                    <li>
+                                        <a class="mobile-row" href=api::mobile_dir_url(group.id, "")>
+                                            <FolderIcon />
+                                            <span>{group.name}</span>
+                                        </a>
+                                    </li>
+                                }).collect_view()}
+                            </ul>
+                        }.into_any(),
+                        Err(e) => view! {
+                            <div class="message message-error">{t("Error: ")} {e}</div>
+                        }.into_any(),
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+/// browse folders, download files and upload photos from a phone
+#[component]
+pub fn MobileBrowserPage() -> impl IntoView {
+    let params = use_params_map();
+    let query = use_query_map();
+    let wg_id: Option<i64> = params.with_untracked(|p| p.get("id").and_then(|s| s.parse().ok()));
+
+    let Some(id) = wg_id else {
+        return view! {
+            <div class="mobile">
+                <div class="message message-error">{t("Invalid watch group ID")}</div>
+            </div>
+        }
+        .into_any();
+    };
+
+    let dir = Memo::new(move |_| {
+        query.with(|q| q.get("path").map(|p| dir_segments(&p)).unwrap_or_default())
+    });
+    let (trigger, set_trigger) = signal(0u32);
+    let files = LocalResource::new(move || {
+        trigger.get();
+        api::fetch_watch_group_files(id)
+    });
+    let uploading = RwSignal::new(false);
+    let msg = ToastSignal::new();
+
+    let on_pick = move |ev: leptos::ev::Event| {
+        let input = event_target::<web_sys::HtmlInputElement>(&ev);
+        let Some(picked) = input.files() else {
+            return;
+        };
+        let picked: Vec<web_sys::File> =
+            (0..picked.length()).filter_map(|i| picked.get(i)).collect();
+        input.set_value("");
+        if picked.is_empty() {
+            return;
+        }
+        let target = dir.get_untracked().join("/");
+        uploading.set(true);
+        spawn_local(async move {
+            for file in &picked {
+                if let Err(e) = api::upload_watch_group_file(id, &target, file).await {
+                    msg.error(
+                        t("Upload of {name} failed: {error}")
+                            .replace("{name}", &file.name())
+                            .replace("{error}", &e),
+                    );
+                    uploading.set(false);
+                    set_trigger.update(|t| *t += 1);
+                    return;
+                }
+            }
+            uploading.set(false);
+            set_trigger.update(|t| *t += 1);
+            msg.success(
+                t("Uploaded {count} file(s).").replace("{count}", &picked.len().to_string()),
+            );
+        });
+    };
+
+    let up_href = move || {
+        let dir = dir.get();
+        match dir.split_last() {
+            Some((_, parent)) => api::mobile_dir_url(id, &parent.join("/")),
+            None => "/app/m".to_string(),
+        }
+    };
+
+    view! {
+        <div class="mobile">
+            <header class="mobile-header">
+                <a class="mobile-header-link" href=up_href>{t("← Back")}</a>
+                <span class="mobile-title">
+                    {move || dir.get().last().cloned().unwrap_or_else(|| t("Files").to_string())}
+                </span>
+            </header>
+            <Message signal=msg />
+            <label class="btn btn-success mobile-action" class:disabled=move || uploading.get()>
+                {move || if uploading.get() { t("Uploading…") } else { t("Upload photo") }}
+                <input
+                    type="file"
+                    accept="image/*"
+                    multiple
+                    hidden
+                    prop:disabled=move || uploading.get()
+                    on:change=on_pick
+                />
+            </label>
+            <a
+                class="btn btn-secondary mobile-action"
+                href=move || api::zip_download_url(id, &dir.get().join("/"))
+                download
+            >
+                {t("Download folder")}
+            </a>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    let here = dir.get();
+                    match files.await {
+                        Ok(all) => {
+                            let (dirs, files_here) = files_at_depth(&all, &here);
+                            if dirs.is_empty() && files_here.is_empty() {
+                                return view! { <EmptyState message=t("This folder is empty.") /> }
+                                    .into_any();
+                            }
+                            view! {
+                                <ul class="mobile-list">
+                                    {dirs.into_iter().map(|name| {
+                                        let href = api::mobile_dir_url(
+                                            id,
+                                            &here.iter().chain([&name]).cloned().collect::<Vec<_>>().join("/"),
+                                        );
+                                        view! {
+                                            <li>
+                                                <a class="mobile-row" href=href>
+                                                    <FolderIcon />
+                                                    <span>{name}</span>
+                                                </a>
+                                            </li>
+                                        }
+                                    }).collect_view()}
+                                    {files_here.into_iter().map(|file| {
+                                        let href = api::watch_group_file_preview_url(
+                                            id,
+                                            &file.relative_path.to_serialized_string(),
+                                        );
+                                        let download = file.file_name.clone();
+                                        view! {
+                                            <li>
+                                                <a class="mobile-row" href=href download=download>
+                                                    <FileIcon />
+                                                    <span>{file.file_name}</span>
+                                                    <span class="mobile-row-meta">{format_size(file.size_in_bytes)}</span>
+                                                </a>
+                                            </li>
+                                        }
+                                    }).collect_view()}
+                                </ul>
+                            }
+                            .into_any()
+                        }
+                        Err(e) => view! {
+                            <div class="message message-error">{t("Error: ")} {e}</div>
+                        }
+                        .into_any(),
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+    .into_any()
+}
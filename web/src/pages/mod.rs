@@ -1,13 +1,29 @@
 mod clients;
+mod file_history;
+mod file_preview;
+mod file_status;
 mod image_gallery;
 mod links;
+mod mobile;
 mod monitor;
+mod search;
+mod settings;
+mod users;
+mod watch_group_detail;
 mod watch_group_files;
+mod watch_group_share_links;
+mod watch_group_webhooks;
 mod watch_groups;
 
-pub use clients::ClientsPage;
+pub use clients::{ClientDetailPage, ClientsPage};
+pub use file_history::FileHistoryPage;
 pub use image_gallery::ImageGalleryPage;
 pub use links::LinksPage;
+pub use mobile::{HomePage, MobileBrowserPage, MobileHomePage, is_mobile_route};
 pub use monitor::MonitorPage;
+pub use search::SearchPage;
+pub use settings::SettingsPage;
+pub use users::UsersPage;
+pub use watch_group_detail::WatchGroupDetailPage;
 pub use watch_group_files::WatchGroupFilesPage;
 pub use watch_groups::WatchGroupsPage;
@@ -1,131 +1,171 @@
 use leptos::prelude::*;
-use wasm_bindgen::prelude::*;
+use shared::dtos::{BandwidthStatsDto, MonitorBucketDto};
+use std::collections::BTreeMap;
 
 use crate::api;
-use crate::components::Loading;
-
-#[wasm_bindgen(inline_js = r#"
-export function renderChart(canvasId, dataJson) {
-    const rawData = JSON.parse(dataJson);
-    const ctx = document.getElementById(canvasId);
-    if (!ctx) return;
-    if (ctx._chartInstance) ctx._chartInstance.destroy();
-    const chart = new Chart(ctx, {
-        type: 'line',
-        data: {
-            datasets: [
-                { label: 'System Memory %', data: rawData.sys_mem, borderColor: '#e94560', backgroundColor: 'rgba(233,69,96,0.1)', tension: 0.3 },
-                { label: 'App Memory %',    data: rawData.app_mem, borderColor: '#0f3460', backgroundColor: 'rgba(15,52,96,0.1)',  tension: 0.3 },
-                { label: 'System CPU %',    data: rawData.sys_cpu, borderColor: '#00b4d8', backgroundColor: 'rgba(0,180,216,0.1)', tension: 0.3 },
-                { label: 'App CPU %',       data: rawData.app_cpu, borderColor: '#90be6d', backgroundColor: 'rgba(144,190,109,0.1)', tension: 0.3 },
-                { label: 'Disk Used %',     data: rawData.disk_used, borderColor: '#f4a261', backgroundColor: 'rgba(244,162,97,0.1)', tension: 0.3 }
-            ]
-        },
-        options: {
-            responsive: true,
-            plugins: {
-                legend: { labels: { color: '#eee' } },
-                zoom: {
-                    zoom:   { wheel: { enabled: true }, pinch: { enabled: true }, mode: 'x' },
-                    pan:    { enabled: true, mode: 'x' },
-                    limits: { x: { min: 'original', max: 'original' } }
-                }
-            },
-            scales: {
-                x: { type: 'time', time: { displayFormats: { hour: 'HH:mm', minute: 'HH:mm', second: 'HH:mm:ss' } }, ticks: { color: '#aaa' }, grid: { color: '#333' } },
-                y: { min: 0, max: 100, ticks: { color: '#aaa' }, grid: { color: '#333' } }
-            }
-        }
-    });
-    ctx._chartInstance = chart;
-}
-
-export function renderDiskFreeChart(canvasId, dataJson) {
-    const rawData = JSON.parse(dataJson);
-    const ctx = document.getElementById(canvasId);
-    if (!ctx) return;
-    if (ctx._chartInstance) ctx._chartInstance.destroy();
-    const chart = new Chart(ctx, {
-        type: 'line',
-        data: {
-            datasets: [
-                { label: 'Disk Free GiB', data: rawData.disk_free, borderColor: '#f4a261', backgroundColor: 'rgba(244,162,97,0.1)', tension: 0.3 }
-            ]
-        },
-        options: {
-            responsive: true,
-            plugins: {
-                legend: { labels: { color: '#eee' } },
-                zoom: {
-                    zoom:   { wheel: { enabled: true }, pinch: { enabled: true }, mode: 'x' },
-                    pan:    { enabled: true, mode: 'x' },
-                    limits: { x: { min: 'original', max: 'original' } }
-                }
-            },
-            scales: {
-                x: { type: 'time', time: { displayFormats: { hour: 'HH:mm', minute: 'HH:mm', second: 'HH:mm:ss' } }, ticks: { color: '#aaa' }, grid: { color: '#333' } },
-                y: { min: 0, ticks: { color: '#aaa', callback: v => v + ' GiB' }, grid: { color: '#333' } }
-            }
-        }
-    });
-    ctx._chartInstance = chart;
-}
-
-export function resetChartZoom(canvasId) {
-    const ctx = document.getElementById(canvasId);
-    if (ctx && ctx._chartInstance) ctx._chartInstance.resetZoom();
-}
-"#)]
-extern "C" {
-    #[wasm_bindgen(js_name = renderChart)]
-    fn render_chart(canvas_id: &str, data_json: &str);
-
-    #[wasm_bindgen(js_name = renderDiskFreeChart)]
-    fn render_disk_free_chart(canvas_id: &str, data_json: &str);
-
-    #[wasm_bindgen(js_name = resetChartZoom)]
-    fn reset_chart_zoom(canvas_id: &str);
-}
+use crate::components::{Chart, ChartSeries, Loading};
+use crate::i18n::t;
 
 #[component]
 pub fn MonitorPage() -> impl IntoView {
-    let monitor_data = LocalResource::new(api::fetch_monitor_data);
+    let range_hours = RwSignal::new(24u64);
+    let monitor_data = LocalResource::new(move || {
+        let from = match range_hours.get() {
+            0 => None,
+            hours => Some((js_sys::Date::now() as u64).saturating_sub(hours * 3_600_000)),
+        };
+        api::fetch_monitor_data(from)
+    });
+    let bandwidth = LocalResource::new(api::fetch_bandwidth_stats);
 
     view! {
         <div class="container">
-            <h1>"System Monitor"</h1>
+            <h1>{t("System Monitor")}</h1>
+            <select
+                class="form-input monitor-range"
+                on:change=move |ev| {
+                    range_hours.set(event_target_value(&ev).parse().unwrap_or(24))
+                }
+            >
+                {RANGES
+                    .into_iter()
+                    .map(|(hours, label)| {
+                        view! {
+                            <option value=hours.to_string() selected=hours == range_hours.get_untracked()>
+                                {t(label)}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
             <Suspense fallback=Loading>
                 {move || Suspend::new(async move {
+                    let bandwidth = bandwidth.await.map(|stats| bandwidth_series(&stats));
                     match monitor_data.await {
                         Ok(data) => {
-                            let json = serde_json::to_string(&data).unwrap_or_default();
-                            let json2 = json.clone();
-                            request_animation_frame(move || {
-                                render_chart("monitor-chart", &json);
-                                render_disk_free_chart("disk-free-chart", &json2);
+                            let upload_disk = data.upload_disk.clone().map(|disk| {
+                                t("Upload volume: {free} GiB free of {total} GiB - uploads are refused below {min} GiB")
+                                    .replace("{free}", &format!("{:.2}", to_gib(disk.free_bytes)))
+                                    .replace("{total}", &format!("{:.2}", to_gib(disk.total_bytes)))
+                                    .replace("{min}", &format!("{:.2}", to_gib(disk.min_free_bytes)))
                             });
+                            let transfers = data.transfers.clone().map(|transfers| {
+                                t("Transfers: {uploads} uploads ({max_uploads}), {downloads} downloads ({max_downloads}), {max_per_client} per client - {rejected} refused since start")
+                                    .replace("{uploads}", &transfers.uploads.to_string())
+                                    .replace("{max_uploads}", &limit_text(transfers.max_uploads))
+                                    .replace("{downloads}", &transfers.downloads.to_string())
+                                    .replace("{max_downloads}", &limit_text(transfers.max_downloads))
+                                    .replace("{max_per_client}", &limit_text(transfers.max_per_client))
+                                    .replace("{rejected}", &transfers.rejected.to_string())
+                            });
+                            let usage = vec![
+                                series("System Memory %", "#e94560", &data.buckets, |s| s.avg.sys_mem),
+                                series("App Memory %", "#0f3460", &data.buckets, |s| s.avg.app_mem),
+                                series("System CPU %", "#00b4d8", &data.buckets, |s| s.avg.sys_cpu),
+                                series("System CPU % (peak)", "rgba(0,180,216,0.4)", &data.buckets, |s| s.max.sys_cpu),
+                                series("App CPU %", "#90be6d", &data.buckets, |s| s.avg.app_cpu),
+                                series("Disk Used %", "#f4a261", &data.buckets, |s| s.avg.disk_used),
+                            ];
+                            let disk_free = vec![series("Disk Free GiB", "#f4a261", &data.buckets, |s| s.avg.disk_free)];
+                            let resolution = t("Averaged over {secs} s per point")
+                                .replace("{secs}", &data.resolution.to_string());
                             view! {
+                                <p class="text-muted">{resolution}</p>
                                 <div class="chart-wrapper">
-                                    <canvas id="monitor-chart"></canvas>
+                                    <Chart series=usage y_max=100.0 unit="%" />
                                 </div>
-                                <button
-                                    class="btn"
-                                    on:click=|_| reset_chart_zoom("monitor-chart")
-                                >"Reset Zoom"</button>
-                                <h2>"Disk Free Space"</h2>
+                                {transfers.map(|text| view! { <p class="text-muted">{text}</p> })}
+                                <h2>{t("Disk Free Space")}</h2>
+                                {upload_disk.map(|text| view! { <p class="text-muted">{text}</p> })}
                                 <div class="chart-wrapper">
-                                    <canvas id="disk-free-chart"></canvas>
+                                    <Chart series=disk_free unit=" GiB" />
                                 </div>
-                                <button
-                                    class="btn"
-                                    on:click=|_| reset_chart_zoom("disk-free-chart")
-                                >"Reset Zoom"</button>
+                                <h2>{t("Bandwidth")}</h2>
+                                <p class="text-muted">{t("Transferred by clients per hour (last 7 days)")}</p>
+                                {match bandwidth {
+                                    Ok(bandwidth) => view! {
+                                        <div class="chart-wrapper">
+                                            <Chart series=bandwidth bars=true unit=" MiB" />
+                                        </div>
+                                    }.into_any(),
+                                    Err(e) => view! {
+                                        <div class="message message-error">{t("Error: ")} {e}</div>
+                                    }.into_any(),
+                                }}
                             }.into_any()
                         }
-                        Err(e) => view! { <div class="message message-error">"Error: " {e}</div> }.into_any(),
+                        Err(e) => view! { <div class="message message-error">{t("Error: ")} {e}</div> }.into_any(),
                     }
                 })}
             </Suspense>
         </div>
     }
 }
+
+/// hours back from now - 0 for everything the server kept
+const RANGES: [(u64, &str); 4] = [
+    (1, "Last hour"),
+    (24, "Last 24 hours"),
+    (24 * 7, "Last 7 days"),
+    (0, "Everything"),
+];
+
+fn series(
+    label: &'static str,
+    color: &'static str,
+    buckets: &[MonitorBucketDto],
+    select: fn(&MonitorBucketDto) -> f32,
+) -> ChartSeries {
+    ChartSeries {
+        label,
+        color,
+        points: buckets
+            .iter()
+            .map(|bucket| (bucket.start_utc_millis as f64, select(bucket) as f64))
+            .collect(),
+    }
+}
+
+/// all clients and watch groups summed up per bucket - uploaded and downloaded MiB
+fn bandwidth_series(stats: &BandwidthStatsDto) -> Vec<ChartSeries> {
+    let mut per_bucket: BTreeMap<u64, (u64, u64)> = BTreeMap::new();
+    for bucket in &stats.buckets {
+        let (uploaded, downloaded) = per_bucket.entry(bucket.start_utc_millis).or_default();
+        *uploaded += bucket.uploaded_bytes;
+        *downloaded += bucket.downloaded_bytes;
+    }
+    let points = |select: fn(&(u64, u64)) -> u64| {
+        per_bucket
+            .iter()
+            .map(|(x, sums)| (*x as f64, to_mib(select(sums))))
+            .collect()
+    };
+    vec![
+        ChartSeries {
+            label: "Uploaded MiB",
+            color: "rgba(233,69,96,0.7)",
+            points: points(|(uploaded, _)| *uploaded),
+        },
+        ChartSeries {
+            label: "Downloaded MiB",
+            color: "rgba(0,180,216,0.7)",
+            points: points(|(_, downloaded)| *downloaded),
+        },
+    ]
+}
+
+fn to_mib(bytes: u64) -> f64 {
+    bytes as f64 / 1024f64.powi(2)
+}
+
+fn to_gib(bytes: u64) -> f64 {
+    bytes as f64 / 1024f64.powi(3)
+}
+
+/// 0 means unlimited
+fn limit_text(max: usize) -> String {
+    match max {
+        0 => t("unlimited").to_string(),
+        max => t("max {max}").replace("{max}", &max.to_string()),
+    }
+}
@@ -0,0 +1,80 @@
+use leptos::prelude::*;
+use leptos_router::components::A;
+use leptos_router::hooks::use_query_map;
+use shared::dtos::SearchHitDto;
+
+use crate::api;
+use crate::components::{Card, EmptyState, Loading};
+use crate::i18n::t;
+
+#[component]
+pub fn SearchPage() -> impl IntoView {
+    let query = use_query_map();
+    let include_content = RwSignal::new(false);
+
+    let hits = LocalResource::new(move || {
+        let q = query.with(|q| q.get("q").unwrap_or_default());
+        let include_content = include_content.get();
+        async move {
+            if q.trim().is_empty() {
+                return Ok(Vec::new());
+            }
+            api::search(&q, include_content).await
+        }
+    });
+
+    view! {
+        <div class="container">
+            <h1>{t("Search")}</h1>
+            <label class="text-muted">
+                <input type="checkbox" bind:checked=include_content />
+                " " {t("Also search the content of small text files")}
+            </label>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match hits.await {
+                        Ok(hits) if hits.is_empty() => {
+                            view! { <EmptyState message=t("Nothing found.") /> }.into_any()
+                        }
+                        Ok(hits) => {
+                            view! {
+                                <ul style="list-style: none; padding: 0;">
+                                    {hits.into_iter().map(|hit| view! { <SearchHit hit /> }).collect_view()}
+                                </ul>
+                            }
+                            .into_any()
+                        }
+                        Err(e) => view! { <div class="message message-error">{t("Error: ")} {e}</div> }.into_any(),
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn SearchHit(hit: SearchHitDto) -> impl IntoView {
+    let dir = hit
+        .relative_path
+        .rsplit_once('/')
+        .map(|(dir, _)| dir.to_string())
+        .unwrap_or_default();
+    let href = api::watch_group_dir_url(hit.watch_group_id, &dir);
+
+    view! {
+        <li>
+            <Card>
+                <div class="search-hit">
+                    <div class="flex-between">
+                        <A href=href>{hit.relative_path}</A>
+                        <span class="text-muted">
+                            {hit.watch_group_name}
+                            {hit.deleted.then(|| format!(" ({})", t("deleted")))}
+                        </span>
+                    </div>
+                    {hit.snippet.map(|snippet| view! { <div class="search-snippet text-muted">{snippet}</div> })}
+                </div>
+            </Card>
+        </li>
+    }
+}
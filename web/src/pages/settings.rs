@@ -0,0 +1,87 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::dtos::{DefaultPage, TableDensity, Theme, UiSettingsDto};
+
+use crate::api;
+use crate::components::{Card, Loading, Message, ToastSignal, use_ui_settings};
+use crate::i18n::t;
+
+/// theme and layout of the signed in account - applied right away, saved on the server
+#[component]
+pub fn SettingsPage() -> impl IntoView {
+    let ui_settings = use_ui_settings();
+    let msg = ToastSignal::new();
+
+    let save = move |settings: UiSettingsDto| {
+        let previous = ui_settings.get();
+        ui_settings.set(settings);
+        spawn_local(async move {
+            match api::save_ui_settings(&settings).await {
+                Ok(()) => msg.success(t("Saved!")),
+                Err(e) => {
+                    if let Some(previous) = previous {
+                        ui_settings.set(previous);
+                    }
+                    msg.error(e);
+                }
+            }
+        });
+    };
+
+    view! {
+        <div class="container">
+            <h1>{t("Settings")}</h1>
+            <Message signal=msg />
+            {move || match ui_settings.get() {
+                None => view! { <Loading /> }.into_any(),
+                Some(settings) => view! {
+                    <Card>
+                        <div class="form-group">
+                            <label>{t("Theme")}</label>
+                            <select class="form-input" on:change=move |ev| {
+                                if let Ok(theme) = Theme::try_from(event_target_value(&ev).as_str()) {
+                                    save(UiSettingsDto { theme, ..settings });
+                                }
+                            }>
+                                {Theme::ALL.into_iter().map(|theme| view! {
+                                    <option value=theme.as_str() selected=theme == settings.theme>
+                                        {t(theme.as_str())}
+                                    </option>
+                                }).collect_view()}
+                            </select>
+                        </div>
+                        <div class="form-group">
+                            <label>{t("Table density")}</label>
+                            <select class="form-input" on:change=move |ev| {
+                                if let Ok(density) = TableDensity::try_from(event_target_value(&ev).as_str()) {
+                                    save(UiSettingsDto { density, ..settings });
+                                }
+                            }>
+                                {TableDensity::ALL.into_iter().map(|density| view! {
+                                    <option value=density.as_str() selected=density == settings.density>
+                                        {t(density.as_str())}
+                                    </option>
+                                }).collect_view()}
+                            </select>
+                        </div>
+                        <div class="form-group">
+                            <label>{t("Default page")}</label>
+                            <select class="form-input" on:change=move |ev| {
+                                if let Ok(default_page) = DefaultPage::try_from(event_target_value(&ev).as_str()) {
+                                    save(UiSettingsDto { default_page, ..settings });
+                                }
+                            }>
+                                {DefaultPage::ALL.into_iter().map(|page| view! {
+                                    <option value=page.as_str() selected=page == settings.default_page>
+                                        {page.path()}
+                                    </option>
+                                }).collect_view()}
+                            </select>
+                            <p class="help-text">{t("Opened on /app/ - phones always get the mobile pages")}</p>
+                        </div>
+                    </Card>
+                }.into_any(),
+            }}
+        </div>
+    }
+}
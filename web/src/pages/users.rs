@@ -0,0 +1,250 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::dtos::{UserDto, UserNameDto, UserRole};
+
+use crate::api;
+use crate::components::{
+    Card, ConfirmDialog, EmptyState, Loading, Message, ToastSignal, TrashIcon,
+};
+use crate::i18n::t;
+
+#[component]
+pub fn UsersPage() -> impl IntoView {
+    let (trigger, set_trigger) = signal(0u32);
+    let data = LocalResource::new(move || {
+        trigger.get();
+        async move {
+            let users = api::fetch_users().await?;
+            let clients = api::fetch_clients().await?;
+            let watch_groups = api::fetch_watch_groups().await?;
+            Ok::<_, String>((users, clients, watch_groups))
+        }
+    });
+
+    let new_name = RwSignal::new(String::new());
+    let msg = ToastSignal::new();
+
+    let on_create = move |_| {
+        let name = new_name.get().trim().to_string();
+        if name.is_empty() {
+            return;
+        }
+        let dto = UserNameDto { name };
+        spawn_local(async move {
+            match api::create_user(&dto).await {
+                Ok(()) => {
+                    new_name.set(String::new());
+                    set_trigger.update(|t| *t += 1);
+                }
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let api_token = RwSignal::new(String::new());
+    let on_use_token = move |_| {
+        api::store_api_token(api_token.get().trim());
+        api_token.set(String::new());
+        set_trigger.update(|t| *t += 1);
+    };
+
+    view! {
+        <div class="container">
+            <h1>{t("Users")}</h1>
+            <p class="text-muted">
+                {t("Clients only sync watch groups that are shared (no owner) or owned by their own user.")}
+            </p>
+
+            <Card>
+                <div class="flex gap-2">
+                    <input type="password" class="form-input" placeholder=t("Api token (only needed if the server sets ADMIN_TOKEN)")
+                        style="flex: 1;"
+                        bind:value=api_token
+                    />
+                    <button class="btn btn-primary" on:click=on_use_token>{t("Use token")}</button>
+                </div>
+            </Card>
+
+            <Card dashed=true>
+                <div class="flex gap-2">
+                    <input type="text" class="form-input" placeholder=t("New user name")
+                        style="flex: 1;"
+                        bind:value=new_name
+                    />
+                    <button class="btn btn-success" on:click=on_create>{t("Create")}</button>
+                </div>
+            </Card>
+            <Message signal=msg />
+
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match data.await {
+                        Ok((users, clients, watch_groups)) => {
+                            let users_sv = StoredValue::new(users.clone());
+                            view! {
+                                {if users.is_empty() {
+                                    view! { <EmptyState message=t("No users yet - everything is shared.") /> }.into_any()
+                                } else {
+                                    view! {
+                                        <ul style="list-style: none; padding: 0;">
+                                            {users.into_iter().map(|user| {
+                                                view! { <UserCard user set_trigger /> }
+                                            }).collect_view()}
+                                        </ul>
+                                    }.into_any()
+                                }}
+
+                                <h2>{t("Client owners")}</h2>
+                                <Card>
+                                    {clients.into_iter().map(|client| {
+                                        let client_id = client.id.clone();
+                                        view! {
+                                            <div class="flex-between">
+                                                <span>{client.host_name}</span>
+                                                <OwnerSelect
+                                                    users=users_sv.get_value()
+                                                    current=client.owner_id
+                                                    on_select=move |owner_id| {
+                                                        let client_id = client_id.clone();
+                                                        spawn_local(async move {
+                                                            match api::set_client_owner(&client_id, owner_id).await {
+                                                                Ok(()) => msg.success(t("Saved!")),
+                                                                Err(e) => msg.error(e),
+                                                            }
+                                                        });
+                                                    }
+                                                />
+                                            </div>
+                                        }
+                                    }).collect_view()}
+                                </Card>
+
+                                <h2>{t("Watch group owners")}</h2>
+                                <Card>
+                                    {watch_groups.into_iter().map(|watch_group| {
+                                        let wg_id = watch_group.id;
+                                        view! {
+                                            <div class="flex-between">
+                                                <span>{watch_group.name}</span>
+                                                <OwnerSelect
+                                                    users=users_sv.get_value()
+                                                    current=watch_group.owner_id
+                                                    on_select=move |owner_id| {
+                                                        spawn_local(async move {
+                                                            match api::set_watch_group_owner(wg_id, owner_id).await {
+                                                                Ok(()) => msg.success(t("Saved!")),
+                                                                Err(e) => msg.error(e),
+                                                            }
+                                                        });
+                                                    }
+                                                />
+                                            </div>
+                                        }
+                                    }).collect_view()}
+                                </Card>
+                            }.into_any()
+                        }
+                        Err(e) => view! { <div class="message message-error">{t("Error: ")} {e}</div> }.into_any(),
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn UserCard(user: UserDto, set_trigger: WriteSignal<u32>) -> impl IntoView {
+    let confirm_delete = RwSignal::new(false);
+    let msg = ToastSignal::new();
+    let user_id = user.id;
+
+    let do_delete = move || {
+        spawn_local(async move {
+            match api::delete_user(user_id).await {
+                Ok(()) => set_trigger.update(|t| *t += 1),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let on_role = move |ev: leptos::ev::Event| {
+        let role = match event_target_value(&ev).as_str() {
+            "admin" => UserRole::Admin,
+            _ => UserRole::ReadOnly,
+        };
+        spawn_local(async move {
+            match api::set_user_role(user_id, role).await {
+                Ok(()) => msg.success(t("Saved!")),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let on_new_token = move |_| {
+        spawn_local(async move {
+            match api::create_user_token(user_id).await {
+                Ok(dto) => msg.success(format!(
+                    "{} {}",
+                    t("New token (shown only once):"),
+                    dto.token
+                )),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let confirm_msg = t("Delete user '{name}'? Their clients and watch groups become unowned.")
+        .replace("{name}", &user.name);
+
+    view! {
+        <li>
+            <Card>
+                <div class="flex-between">
+                    <div>
+                        <span class="text-lg font-semibold">{user.name}</span>
+                        <div class="text-xs text-muted">"ID: " {user_id}</div>
+                    </div>
+                    <div class="flex gap-1">
+                        <select class="form-input" style="width: auto;" on:change=on_role>
+                            <option value="admin" selected=user.role == UserRole::Admin>{t("admin")}</option>
+                            <option value="read_only" selected=user.role == UserRole::ReadOnly>{t("read-only")}</option>
+                        </select>
+                        <button class="btn btn-secondary" on:click=on_new_token>{t("New token")}</button>
+                        <button
+                            class="btn btn-icon btn-danger"
+                            title=t("Delete")
+                            on:click=move |_| confirm_delete.set(true)
+                        >
+                            <TrashIcon/>
+                        </button>
+                    </div>
+                </div>
+                <Message signal=msg />
+                <ConfirmDialog show=confirm_delete message=confirm_msg on_confirm=do_delete />
+            </Card>
+        </li>
+    }
+}
+
+/// dropdown of all users plus "shared" (= no owner)
+#[component]
+fn OwnerSelect(
+    users: Vec<UserDto>,
+    current: Option<i64>,
+    on_select: impl Fn(Option<i64>) + 'static,
+) -> impl IntoView {
+    view! {
+        <select class="form-input" style="width: auto;"
+            on:change=move |ev| on_select(event_target_value(&ev).parse::<i64>().ok())
+        >
+            <option value="" selected=current.is_none()>{t("- shared -")}</option>
+            {users.into_iter().map(|user| {
+                view! {
+                    <option value=user.id.to_string() selected=current == Some(user.id)>
+                        {user.name}
+                    </option>
+                }
+            }).collect_view()}
+        </select>
+    }
+}
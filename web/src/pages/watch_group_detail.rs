@@ -0,0 +1,304 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::components::A;
+use leptos_router::hooks::use_params_map;
+use shared::dtos::{
+    ClientDto, ClientWatchGroupCreateDto, UploadDiskDto, WatchGroupDetailDto, WatchGroupMemberDto,
+};
+use shared::utc_millis::UtcMillis;
+
+use crate::api;
+use crate::components::{
+    Card, ConfirmDialog, EmptyState, Loading, Message, ToastSignal, TrashIcon,
+};
+use crate::i18n::t;
+
+use super::watch_group_files::format_size;
+
+/// members older than this are shown as behind
+const STALE_AFTER_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// one watch group: its storage and which clients sync it how recently
+#[component]
+pub fn WatchGroupDetailPage() -> impl IntoView {
+    let params = use_params_map();
+    let Some(wg_id) = params.with_untracked(|p| p.get("id").and_then(|s| s.parse::<i64>().ok()))
+    else {
+        return view! {
+            <div class="container">
+                <div class="message message-error">{t("Invalid watch group ID")}</div>
+            </div>
+        }
+        .into_any();
+    };
+    let (trigger, set_trigger) = signal(0u32);
+    let detail = LocalResource::new(move || {
+        trigger.get();
+        api::fetch_watch_group_detail(wg_id)
+    });
+    let stats = LocalResource::new(move || api::fetch_watch_group_stats(wg_id));
+    let clients = LocalResource::new(api::fetch_clients);
+
+    view! {
+        <div class="container">
+            <A href="/app/watch-groups" attr:class="btn btn-secondary">{t("← Watch Groups")}</A>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    let detail = match detail.await {
+                        Ok(detail) => detail,
+                        Err(e) => {
+                            return view! {
+                                <div class="message message-error">{t("Error loading watch group: ")} {e}</div>
+                            }
+                            .into_any();
+                        }
+                    };
+                    let group_bytes = stats.await.ok().map(|stats| (stats.file_count, stats.total_bytes));
+                    let clients = clients.await.unwrap_or_default();
+                    view! {
+                        <WatchGroupOverview
+                            detail
+                            group_bytes
+                            clients
+                            on_changed=move || set_trigger.update(|t| *t += 1)
+                        />
+                    }
+                    .into_any()
+                })}
+            </Suspense>
+        </div>
+    }
+    .into_any()
+}
+
+#[component]
+fn WatchGroupOverview(
+    detail: WatchGroupDetailDto,
+    /// file count and bytes - None if the stats couldn't be loaded
+    group_bytes: Option<(u64, u64)>,
+    clients: Vec<ClientDto>,
+    on_changed: impl Fn() + 'static + Clone + Send + Sync,
+) -> impl IntoView {
+    let wg_id = detail.group.id;
+    let candidates: Vec<ClientDto> = clients
+        .into_iter()
+        .filter(|client| !detail.members.iter().any(|m| m.client_id == client.id))
+        .collect();
+    let now = js_sys::Date::now() as u64;
+
+    view! {
+        <div class="flex-between">
+            <h1>{detail.group.name.clone()}</h1>
+            <div class="flex gap-1">
+                <A href=format!("/app/watch-groups/{wg_id}") attr:class="btn btn-secondary">{t("Files")}</A>
+                <A href=format!("/app/watch-groups/{wg_id}/history") attr:class="btn btn-secondary">{t("History")}</A>
+            </div>
+        </div>
+        <div class="text-xs text-muted">"ID: " {wg_id}</div>
+
+        <StorageCard group_bytes upload_disk=detail.upload_disk />
+
+        <h2>{t("Members")}</h2>
+        {if detail.members.is_empty() {
+            view! { <EmptyState message=t("No client syncs this watch group yet.") /> }.into_any()
+        } else {
+            view! {
+                <table class="history-table">
+                    <thead>
+                        <tr>
+                            <th>{t("Client")}</th>
+                            <th>{t("Folder")}</th>
+                            <th>{t("Last sync")}</th>
+                            <th>{t("State")}</th>
+                            <th></th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {detail.members.into_iter().map(|member| view! {
+                            <MemberRow wg_id member now on_changed=on_changed.clone() />
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            }.into_any()
+        }}
+
+        <AttachClientForm wg_id candidates on_changed />
+    }
+}
+
+#[component]
+fn StorageCard(
+    group_bytes: Option<(u64, u64)>,
+    upload_disk: Option<UploadDiskDto>,
+) -> impl IntoView {
+    let Some((file_count, total_bytes)) = group_bytes else {
+        return view! {
+            <Card>
+                <div class="message message-error">{t("Storage stats unavailable")}</div>
+            </Card>
+        }
+        .into_any();
+    };
+    // there are no per group quotas - the group can grow until the upload volume hits its
+    // reserve, past that uploads are refused
+    let headroom = upload_disk.map(|disk| {
+        let left = disk.free_bytes.saturating_sub(disk.min_free_bytes);
+        view! {
+            <progress style="width: 100%;" max=total_bytes + left value=total_bytes></progress>
+            <p class="text-xs text-muted">
+                {t("{left} left before uploads are refused ({free} free of {total} on the upload volume, {reserve} kept in reserve)")
+                    .replace("{left}", &format_size(left))
+                    .replace("{free}", &format_size(disk.free_bytes))
+                    .replace("{total}", &format_size(disk.total_bytes))
+                    .replace("{reserve}", &format_size(disk.min_free_bytes))}
+            </p>
+        }
+    });
+
+    view! {
+        <Card>
+            <h2>{t("Storage")}</h2>
+            <p>
+                {t("{count} files, {size} total")
+                    .replace("{count}", &file_count.to_string())
+                    .replace("{size}", &format_size(total_bytes))}
+            </p>
+            {headroom}
+        </Card>
+    }
+    .into_any()
+}
+
+#[component]
+fn MemberRow(
+    wg_id: i64,
+    member: WatchGroupMemberDto,
+    now: u64,
+    on_changed: impl Fn() + 'static + Clone + Send + Sync,
+) -> impl IntoView {
+    let client_id = StoredValue::new(member.client_id.clone());
+    let confirm_detach = RwSignal::new(false);
+    let msg = ToastSignal::new();
+
+    let do_detach = move || {
+        let id = client_id.get_value();
+        let on_changed = on_changed.clone();
+        spawn_local(async move {
+            match api::delete_client_watch_group(&id, wg_id).await {
+                Ok(()) => on_changed(),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let (freshness, stale) = freshness(member.last_synced_at.as_ref(), now);
+    let exact = member
+        .last_synced_at
+        .map(|at| at.to_string())
+        .unwrap_or_default();
+    let state = match (member.scan_warning, member.paused) {
+        (Some(warning), _) => warning,
+        (None, true) => t("paused").to_string(),
+        (None, false) if stale => t("behind").to_string(),
+        (None, false) => t("active").to_string(),
+    };
+    let confirm_msg =
+        t("Detach '{name}'? It stops syncing this watch group, its local files are kept.")
+            .replace("{name}", &member.host_name);
+
+    view! {
+        <tr>
+            <td>
+                <a href=format!("/app/clients/{}", member.client_id)>{member.host_name}</a>
+            </td>
+            <td class="text-xs">{member.path_to_monitor}</td>
+            <td title=exact>{freshness}</td>
+            <td>{state}</td>
+            <td>
+                <button
+                    class="btn btn-icon btn-danger"
+                    title=t("Detach")
+                    on:click=move |_| confirm_detach.set(true)
+                >
+                    <TrashIcon/>
+                </button>
+                <Message signal=msg />
+                <ConfirmDialog show=confirm_detach message=confirm_msg on_confirm=do_detach />
+            </td>
+        </tr>
+    }
+}
+
+#[component]
+fn AttachClientForm(
+    wg_id: i64,
+    candidates: Vec<ClientDto>,
+    on_changed: impl Fn() + 'static + Clone + Send + Sync,
+) -> impl IntoView {
+    if candidates.is_empty() {
+        return ().into_any();
+    }
+    let selected = RwSignal::new(candidates[0].id.clone());
+    let path = RwSignal::new(String::new());
+    let msg = ToastSignal::new();
+
+    let on_attach = move |_| {
+        let client_id = selected.get_untracked();
+        let path_to_monitor = path.get_untracked().trim().to_string();
+        if path_to_monitor.is_empty() {
+            msg.error(t("Path is required"));
+            return;
+        }
+        let dto = ClientWatchGroupCreateDto {
+            server_watch_group_id: wg_id,
+            path_to_monitor,
+            exclude_dirs: vec![],
+            exclude_dot_dirs: true,
+        };
+        let on_changed = on_changed.clone();
+        spawn_local(async move {
+            match api::create_client_watch_group(&client_id, &dto).await {
+                Ok(()) => {
+                    path.set(String::new());
+                    on_changed();
+                }
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    view! {
+        <Card dashed=true>
+            <div class="flex gap-2">
+                <select class="form-input" style="width: auto;" bind:value=selected>
+                    {candidates.into_iter().map(|client| view! {
+                        <option value=client.id>{client.host_name}</option>
+                    }).collect_view()}
+                </select>
+                <input type="text" class="form-input" placeholder=t("Folder on the client")
+                    style="flex: 1;"
+                    bind:value=path
+                />
+                <button class="btn btn-success" on:click=on_attach>{t("Attach")}</button>
+            </div>
+            <Message signal=msg />
+        </Card>
+    }
+    .into_any()
+}
+
+/// "3 h ago" and whether that's longer than [`STALE_AFTER_MS`]
+fn freshness(last_synced_at: Option<&UtcMillis>, now: u64) -> (String, bool) {
+    let Some(at) = last_synced_at else {
+        return (t("never").to_string(), true);
+    };
+    let age = now.saturating_sub(at.as_u64());
+    let minutes = age / (60 * 1000);
+    let text = match minutes {
+        0 => t("just now").to_string(),
+        1..60 => t("{n} min ago").replace("{n}", &minutes.to_string()),
+        60..1440 => t("{n} h ago").replace("{n}", &(minutes / 60).to_string()),
+        _ => t("{n} days ago").replace("{n}", &(minutes / 1440).to_string()),
+    };
+    (text, age > STALE_AFTER_MS)
+}
@@ -1,15 +1,27 @@
 use leptos::prelude::*;
 use leptos::task::spawn_local;
 use leptos_router::components::A;
-use leptos_router::hooks::use_params_map;
-use shared::dtos::{is_image, FileDescription};
+use leptos_router::hooks::{use_params_map, use_query_map};
+use shared::dtos::{
+    FileDescription, GrowthReportDto, ServerWatchGroup, TopFilesReportDto, TransferPathDto,
+    WatchGroupStatsDto, is_image,
+};
 use std::collections::HashSet;
 
+use super::file_preview::FilePreview;
+use super::file_status::FileStatusPanel;
 use crate::api;
 use crate::components::{
-    EmptyState, FileIcon, FileIconLarge, FolderIcon, FolderIconLarge, Loading, Message,
-    TextFileIconLarge, ToastSignal, TrashIcon,
+    Card, Chart, ChartSeries, EmptyState, FileIcon, FileIconLarge, FolderIcon, FolderIconLarge,
+    Loading, Message, TextFileIconLarge, ToastSignal, TrashIcon,
 };
+use crate::i18n::t;
+
+/// range of the storage growth chart
+const GROWTH_DAYS: u32 = 90;
+
+/// matches the size the server renders right after an upload
+const TILE_THUMBNAIL_SIZE: u32 = 256;
 
 #[derive(Clone, PartialEq)]
 enum ViewMode {
@@ -17,7 +29,10 @@ enum ViewMode {
     Tile,
 }
 
-fn files_at_depth(all: &[FileDescription], dir: &[String]) -> (Vec<String>, Vec<FileDescription>) {
+pub(super) fn files_at_depth(
+    all: &[FileDescription],
+    dir: &[String],
+) -> (Vec<String>, Vec<FileDescription>) {
     let depth = dir.len();
     let files: Vec<FileDescription> = all
         .iter()
@@ -44,11 +59,11 @@ fn files_at_depth(all: &[FileDescription], dir: &[String]) -> (Vec<String>, Vec<
 fn is_text(ext: &str) -> bool {
     matches!(
         ext,
-        "txt" | "md" | "rs" | "toml" | "json" | "yaml" | "yml" | "sh" | "log"
+        "txt" | "md" | "rs" | "toml" | "json" | "yaml" | "yml" | "sh" | "log" | "csv" | "tsv"
     )
 }
 
-fn format_size(bytes: u64) -> String {
+pub(super) fn format_size(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
@@ -63,12 +78,13 @@ fn format_size(bytes: u64) -> String {
 #[component]
 pub fn WatchGroupFilesPage() -> impl IntoView {
     let params = use_params_map();
+    let query = use_query_map();
     let wg_id: Option<i64> = params.with_untracked(|p| p.get("id").and_then(|s| s.parse().ok()));
 
     let Some(id) = wg_id else {
         return view! {
             <div class="container">
-                <div class="message message-error">"Invalid watch group ID"</div>
+                <div class="message message-error">{t("Invalid watch group ID")}</div>
             </div>
         }
         .into_any();
@@ -77,8 +93,23 @@ pub fn WatchGroupFilesPage() -> impl IntoView {
     let (refresh_trigger, set_refresh_trigger) = signal(0u32);
     let selected: RwSignal<HashSet<String>> = RwSignal::new(HashSet::new());
     let msg = ToastSignal::new();
-    let current_path: RwSignal<Vec<String>> = RwSignal::new(vec![]);
+    // deep links (e.g. from search) open a sub directory
+    let initial_path: Vec<String> = query.with_untracked(|q| {
+        q.get("path")
+            .map(|p| {
+                p.split('/')
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+    let current_path: RwSignal<Vec<String>> = RwSignal::new(initial_path);
     let view_mode: RwSignal<ViewMode> = RwSignal::new(ViewMode::List);
+    // relative path of the text file shown next to the tree
+    let preview: RwSignal<Option<String>> = RwSignal::new(None);
+    // relative path of the file whose sync status is shown next to the tree
+    let status: RwSignal<Option<String>> = RwSignal::new(None);
 
     // Clear selection whenever the user navigates into a different directory
     Effect::new(move |_| {
@@ -98,10 +129,10 @@ pub fn WatchGroupFilesPage() -> impl IntoView {
         }
         let ok = web_sys::window()
             .unwrap()
-            .confirm_with_message(&format!(
-                "Delete {} file(s)? This cannot be undone.",
-                paths.len()
-            ))
+            .confirm_with_message(
+                &t("Delete {count} file(s)? This cannot be undone.")
+                    .replace("{count}", &paths.len().to_string()),
+            )
             .unwrap_or(false);
         if !ok {
             return;
@@ -109,29 +140,79 @@ pub fn WatchGroupFilesPage() -> impl IntoView {
         spawn_local(async move {
             for path in &paths {
                 if let Err(e) = api::delete_watch_group_file(id, path).await {
-                    msg.error(format!("Delete failed: {e}"));
+                    msg.error(format!("{} {e}", t("Delete failed:")));
                     return;
                 }
             }
             let count = paths.len();
             selected.update(|s| s.clear());
             set_refresh_trigger.update(|t| *t += 1);
-            msg.success(format!("Deleted {count} file(s)."));
+            msg.success(t("Deleted {count} file(s).").replace("{count}", &count.to_string()));
         });
     };
 
+    let on_transfer = Callback::new(move |(target_wg_id, move_files): (i64, bool)| {
+        let paths: Vec<String> = selected.get_untracked().into_iter().collect();
+        if paths.is_empty() {
+            return;
+        }
+        if move_files {
+            let ok = web_sys::window()
+                .unwrap()
+                .confirm_with_message(
+                    &t("Move {count} file(s) to watch group {target}? They are deleted here.")
+                        .replace("{count}", &paths.len().to_string())
+                        .replace("{target}", &target_wg_id.to_string()),
+                )
+                .unwrap_or(false);
+            if !ok {
+                return;
+            }
+        }
+        spawn_local(async move {
+            let mut count = 0;
+            for path in paths {
+                let dto = TransferPathDto {
+                    source_watch_group_id: id,
+                    target_watch_group_id: target_wg_id,
+                    path_prefix: path,
+                };
+                match api::transfer_files(&dto, move_files).await {
+                    Ok(result) => count += result.transferred_paths.len(),
+                    Err(e) => {
+                        msg.error(format!("{} {e}", t("Transfer failed:")));
+                        return;
+                    }
+                }
+            }
+            selected.update(|s| s.clear());
+            set_refresh_trigger.update(|t| *t += 1);
+            let done = if move_files {
+                t("Moved {count} file(s) to watch group {target}.")
+            } else {
+                t("Copied {count} file(s) to watch group {target}.")
+            };
+            msg.success(
+                done.replace("{count}", &count.to_string())
+                    .replace("{target}", &target_wg_id.to_string()),
+            );
+        });
+    });
+
     view! {
         <div class="container">
-            <A href="/app/watch-groups" attr:class="btn btn-secondary">"← Back"</A>
-            <h1>"Watch Group Files"</h1>
+            <A href="/app/watch-groups" attr:class="btn btn-secondary">{t("← Back")}</A>
+            <h1>{t("Watch Group Files")}</h1>
             <Message signal=msg />
+            <StatsCard wg_id=id />
+            <ReportsCard wg_id=id />
             <Suspense fallback=Loading>
                 {move || Suspend::new(async move {
                     match files.await {
                         Ok(file_list) => {
                             if file_list.is_empty() {
                                 view! {
-                                    <EmptyState message="No files in this watch group yet." />
+                                    <EmptyState message=t("No files in this watch group yet.") />
                                 }
                                 .into_any()
                             } else {
@@ -145,38 +226,78 @@ pub fn WatchGroupFilesPage() -> impl IntoView {
                                                     on:click=on_delete_click
                                                 >
                                                     <TrashIcon />
-                                                    " Delete ("
+                                                    " " {t("Delete")} " ("
                                                     {move || selected.get().len()}
                                                     ")"
                                                 </button>
+                                                <TransferControls wg_id=id on_transfer />
                                             </Show>
+                                            <a
+                                                class="btn btn-secondary"
+                                                href=move || api::zip_download_url(id, &current_path.get().join("/"))
+                                                download
+                                            >
+                                                {t("Download folder")}
+                                            </a>
                                             <button
                                                 class="btn btn-secondary"
                                                 on:click=move |_| view_mode.set(ViewMode::List)
                                             >
-                                                "List"
+                                                {t("List")}
                                             </button>
                                             <button
                                                 class="btn btn-secondary"
                                                 on:click=move |_| view_mode.set(ViewMode::Tile)
                                             >
-                                                "Tile"
+                                                {t("Tile")}
                                             </button>
                                         </div>
                                     </div>
-                                    <FiletreeView
-                                        all_files=file_list
-                                        current_path
-                                        view_mode
-                                        wg_id=id
-                                        selected
-                                    />
+                                    <div class="files-with-preview">
+                                        <div class="files-with-preview-tree">
+                                            <FiletreeView
+                                                all_files=file_list
+                                                current_path
+                                                view_mode
+                                                wg_id=id
+                                                selected
+                                                preview
+                                                status
+                                            />
+                                        </div>
+                                        {move || {
+                                            preview
+                                                .get()
+                                                .map(|path| {
+                                                    view! {
+                                                        <FilePreview
+                                                            wg_id=id
+                                                            path
+                                                            on_close=Callback::new(move |_| preview.set(None))
+                                                        />
+                                                    }
+                                                })
+                                        }}
+                                        {move || {
+                                            status
+                                                .get()
+                                                .map(|path| {
+                                                    view! {
+                                                        <FileStatusPanel
+                                                            wg_id=id
+                                                            path
+                                                            on_close=Callback::new(move |_| status.set(None))
+                                                        />
+                                                    }
+                                                })
+                                        }}
+                                    </div>
                                 }
                                 .into_any()
                             }
                         }
                         Err(e) => {
-                            view! { <div class="message message-error">"Error: " {e}</div> }
+                            view! { <div class="message message-error">{t("Error: ")} {e}</div> }
                                 .into_any()
                         }
                     }
@@ -187,6 +308,264 @@ pub fn WatchGroupFilesPage() -> impl IntoView {
     .into_any()
 }
 
+/// copy / move the selected files into another watch group
+#[component]
+fn TransferControls(wg_id: i64, on_transfer: Callback<(i64, bool)>) -> impl IntoView {
+    let watch_groups = LocalResource::new(api::fetch_watch_groups);
+    let target: RwSignal<Option<i64>> = RwSignal::new(None);
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || Suspend::new(async move {
+                let others: Vec<ServerWatchGroup> = watch_groups
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|wg| wg.id != wg_id)
+                    .collect();
+                if others.is_empty() {
+                    return ().into_any();
+                }
+                view! {
+                    <select on:change=move |ev| target.set(event_target_value(&ev).parse().ok())>
+                        <option value="" selected>{t("Target watch group…")}</option>
+                        {others
+                            .into_iter()
+                            .map(|wg| view! { <option value=wg.id.to_string()>{wg.name}</option> })
+                            .collect_view()}
+                    </select>
+                    <button
+                        class="btn btn-secondary"
+                        disabled=move || target.get().is_none()
+                        on:click=move |_| {
+                            if let Some(t) = target.get_untracked() {
+                                on_transfer.run((t, false))
+                            }
+                        }
+                    >
+                        {t("Copy")}
+                    </button>
+                    <button
+                        class="btn btn-secondary"
+                        disabled=move || target.get().is_none()
+                        on:click=move |_| {
+                            if let Some(t) = target.get_untracked() {
+                                on_transfer.run((t, true))
+                            }
+                        }
+                    >
+                        {t("Move")}
+                    </button>
+                }
+                .into_any()
+            })}
+        </Suspense>
+    }
+}
+
+#[component]
+fn StatsCard(wg_id: i64) -> impl IntoView {
+    let stats = LocalResource::new(move || api::fetch_watch_group_stats(wg_id));
+
+    view! {
+        <Suspense fallback=Loading>
+            {move || Suspend::new(async move {
+                match stats.await {
+                    Ok(stats) => view! { <StatsView stats /> }.into_any(),
+                    Err(e) => {
+                        view! { <div class="message message-error">{t("Stats unavailable: ")} {e}</div> }
+                            .into_any()
+                    }
+                }
+            })}
+        </Suspense>
+    }
+}
+
+#[component]
+fn StatsView(stats: WatchGroupStatsDto) -> impl IntoView {
+    view! {
+        <Card>
+            <h2>{t("Statistics")}</h2>
+            <p>
+                {t("{count} files, {size} total")
+                    .replace("{count}", &stats.file_count.to_string())
+                    .replace("{size}", &format_size(stats.total_bytes))}
+            </p>
+            <div class="flex gap-2">
+                <div style="flex: 1;">
+                    <h3>{t("Largest files")}</h3>
+                    <ul>
+                        {stats
+                            .largest_files
+                            .into_iter()
+                            .map(|f| {
+                                view! {
+                                    <li>
+                                        {f.relative_path}
+                                        <span class="text-muted">
+                                            " (" {format_size(f.size_in_bytes)} ")"
+                                        </span>
+                                    </li>
+                                }
+                            })
+                            .collect_view()}
+                    </ul>
+                </div>
+                <div style="flex: 1;">
+                    <h3>{t("Most active clients")}</h3>
+                    <ul>
+                        {stats
+                            .most_active_clients
+                            .into_iter()
+                            .map(|c| {
+                                view! {
+                                    <li>
+                                        {c.host_name}
+                                        <span class="text-muted">" (" {c.event_count} " " {t("events")} ")"</span>
+                                    </li>
+                                }
+                            })
+                            .collect_view()}
+                    </ul>
+                </div>
+            </div>
+            <h3>{t("Events per day (last 30 days)")}</h3>
+            <table>
+                <thead>
+                    <tr>
+                        <th>{t("Day")}</th>
+                        <th>{t("Changes")}</th>
+                        <th>{t("Deletes")}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {stats
+                        .events_per_day
+                        .into_iter()
+                        .map(|d| {
+                            view! {
+                                <tr>
+                                    <td>{d.day}</td>
+                                    <td>{d.changes}</td>
+                                    <td>{d.deletes}</td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()}
+                </tbody>
+            </table>
+        </Card>
+    }
+}
+
+/// what is eating the disk - largest files and the stored bytes over time
+#[component]
+fn ReportsCard(wg_id: i64) -> impl IntoView {
+    let top_files = LocalResource::new(move || api::fetch_top_files(wg_id));
+    let growth = LocalResource::new(move || api::fetch_growth(wg_id, GROWTH_DAYS));
+
+    view! {
+        <Card>
+            <h2>{t("Storage reports")}</h2>
+            <h3>{t("Storage growth (last 90 days)")}</h3>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match growth.await {
+                        Ok(report) => {
+                            view! {
+                                <div class="chart-wrapper">
+                                    <GrowthChart report />
+                                </div>
+                            }
+                                .into_any()
+                        }
+                        Err(e) => {
+                            view! { <div class="message message-error">{t("Report unavailable: ")} {e}</div> }
+                                .into_any()
+                        }
+                    }
+                })}
+            </Suspense>
+            <h3>{t("Top files by size")}</h3>
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match top_files.await {
+                        Ok(report) => view! { <TopFilesList report /> }.into_any(),
+                        Err(e) => {
+                            view! { <div class="message message-error">{t("Report unavailable: ")} {e}</div> }
+                                .into_any()
+                        }
+                    }
+                })}
+            </Suspense>
+        </Card>
+    }
+}
+
+/// MiB for small watch groups, GiB once they pass one
+#[component]
+fn GrowthChart(report: GrowthReportDto) -> impl IntoView {
+    let max = report.days.iter().map(|d| d.total_bytes).max().unwrap_or(0);
+    let (label, unit, divisor) = if max >= 1024u64.pow(3) {
+        ("Stored GiB", " GiB", 1024f64.powi(3))
+    } else {
+        ("Stored MiB", " MiB", 1024f64.powi(2))
+    };
+    let series = vec![ChartSeries {
+        label,
+        color: "rgba(0,180,216,0.7)",
+        points: report
+            .days
+            .iter()
+            .map(|d| (d.start_utc_millis as f64, d.total_bytes as f64 / divisor))
+            .collect(),
+    }];
+    view! { <Chart series unit /> }
+}
+
+/// bar width relative to the largest file, share relative to the whole watch group
+#[component]
+fn TopFilesList(report: TopFilesReportDto) -> impl IntoView {
+    if report.files.is_empty() {
+        return view! { <EmptyState message=t("No files in this watch group yet.") /> }.into_any();
+    }
+    let largest = report.files[0].size_in_bytes.max(1) as f64;
+    let total = report.total_bytes.max(1) as f64;
+    view! {
+        <table>
+            <thead>
+                <tr>
+                    <th>{t("File")}</th>
+                    <th>{t("Size")}</th>
+                    <th>{t("Share")}</th>
+                </tr>
+            </thead>
+            <tbody>
+                {report
+                    .files
+                    .into_iter()
+                    .map(|f| {
+                        let width = f.size_in_bytes as f64 / largest * 100.0;
+                        let share = f.size_in_bytes as f64 / total * 100.0;
+                        view! {
+                            <tr>
+                                <td>
+                                    {f.relative_path}
+                                    <div class="size-bar" style=format!("width: {width:.1}%;")></div>
+                                </td>
+                                <td>{format_size(f.size_in_bytes)}</td>
+                                <td>{format!("{share:.1}%")}</td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+    }
+        .into_any()
+}
+
 #[component]
 fn Breadcrumb(current_path: RwSignal<Vec<String>>) -> impl IntoView {
     view! {
@@ -195,7 +574,7 @@ fn Breadcrumb(current_path: RwSignal<Vec<String>>) -> impl IntoView {
                 class="breadcrumb-item"
                 on:click=move |_| current_path.set(vec![])
             >
-                "Root"
+                {t("Root")}
             </button>
             {move || {
                 let path = current_path.get();
@@ -227,6 +606,8 @@ fn FiletreeView(
     view_mode: RwSignal<ViewMode>,
     wg_id: i64,
     selected: RwSignal<HashSet<String>>,
+    preview: RwSignal<Option<String>>,
+    status: RwSignal<Option<String>>,
 ) -> impl IntoView {
     view! {
         {move || {
@@ -266,6 +647,10 @@ fn FiletreeView(
                                     };
                                     let file_name = file.file_name.clone();
                                     let size = format_size(file.size_in_bytes);
+                                    let history_href = api::file_history_url(wg_id, &path_str);
+                                    let text_file = is_text(&file.file_type);
+                                    let p_preview = path_str.clone();
+                                    let p_status = path_str.clone();
                                     let p_class = path_str.clone();
                                     let p_check = path_str.clone();
                                     let p_toggle = path_str;
@@ -301,6 +686,26 @@ fn FiletreeView(
                                                     <span>{file_name}</span>
                                                     <span class="filetree-row-meta">{size}</span>
                                                 </a>
+                                                <Show when=move || text_file>
+                                                    <button
+                                                        class="btn btn-secondary"
+                                                        on:click={
+                                                            let p_preview = p_preview.clone();
+                                                            move |_| preview.set(Some(p_preview.clone()))
+                                                        }
+                                                    >
+                                                        {t("Preview")}
+                                                    </button>
+                                                </Show>
+                                                <a class="btn btn-secondary" href=history_href>
+                                                    {t("History")}
+                                                </a>
+                                                <button
+                                                    class="btn btn-secondary"
+                                                    on:click=move |_| status.set(Some(p_status.clone()))
+                                                >
+                                                    {t("Status")}
+                                                </button>
                                             </div>
                                         </li>
                                     }
@@ -356,6 +761,11 @@ fn FiletreeView(
                                     };
                                     if is_image(&ext) {
                                         let gallery_href = api::gallery_url(wg_id, &path_str);
+                                        let preview_url = if ext == "svg" {
+                                            raw_url
+                                        } else {
+                                            api::thumbnail_url(wg_id, &path_str, TILE_THUMBNAIL_SIZE)
+                                        };
                                         view! {
                                             <div
                                                 class="filetree-tile-wrapper"
@@ -373,7 +783,7 @@ fn FiletreeView(
                                                     target="_blank"
                                                 >
                                                     <img
-                                                        src=raw_url
+                                                        src=preview_url
                                                         class="filetree-tile-img"
                                                         loading="lazy"
                                                     />
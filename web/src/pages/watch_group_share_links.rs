@@ -0,0 +1,101 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::dtos::{ShareLinkCreateDto, ShareLinkKind};
+use shared::endpoint::ServerEndpoint;
+
+use crate::api;
+use crate::components::{Loading, Message, ToastSignal, TrashIcon};
+use crate::i18n::t;
+
+/// public links accepting anonymous uploads into a folder of the watch group
+#[component]
+pub fn WatchGroupShareLinks(group_id: i64) -> impl IntoView {
+    let (trigger, set_trigger) = signal(0u32);
+    let links = LocalResource::new(move || {
+        trigger.get();
+        api::fetch_share_links(group_id)
+    });
+    let folder = RwSignal::new("inbox".to_string());
+    let msg = ToastSignal::new();
+    let origin = web_sys::window()
+        .and_then(|w| w.location().origin().ok())
+        .unwrap_or_default();
+
+    let do_add = move |_| {
+        let dto = ShareLinkCreateDto {
+            kind: ShareLinkKind::Inbox,
+            folder: folder.get_untracked().trim().to_string(),
+        };
+        spawn_local(async move {
+            match api::create_share_link(group_id, &dto).await {
+                Ok(()) => set_trigger.update(|t| *t += 1),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let do_delete = move |token: String| {
+        spawn_local(async move {
+            match api::delete_share_link(group_id, &token).await {
+                Ok(()) => set_trigger.update(|t| *t += 1),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    view! {
+        <div style="margin-top: 0.75rem;">
+            <Suspense fallback=Loading>
+                {move || {
+                    let origin = origin.clone();
+                    Suspend::new(async move {
+                        match links.await {
+                            Ok(links) if links.is_empty() => view! {
+                                <p class="text-muted">{t("No inbox links yet.")}</p>
+                            }.into_any(),
+                            Ok(links) => view! {
+                                <ul style="list-style: none; padding: 0;">
+                                    {links.into_iter().map(|link| {
+                                        let url = format!(
+                                            "{}{}",
+                                            origin,
+                                            ServerEndpoint::Inbox.to_str().replace("{token}", &link.token)
+                                        );
+                                        let href = url.clone();
+                                        let token = link.token.clone();
+                                        view! {
+                                            <li class="flex-between">
+                                                <span class="detail-value">
+                                                    <a href=href target="_blank">{url}</a>
+                                                    " → /" {link.folder}
+                                                </span>
+                                                <button
+                                                    class="btn btn-icon btn-danger"
+                                                    title=t("Delete")
+                                                    on:click=move |_| do_delete(token.clone())
+                                                >
+                                                    <TrashIcon/>
+                                                </button>
+                                            </li>
+                                        }
+                                    }).collect_view()}
+                                </ul>
+                            }.into_any(),
+                            Err(e) => view! {
+                                <div class="message message-error">{t("Error loading inbox links: ")} {e}</div>
+                            }.into_any(),
+                        }
+                    })
+                }}
+            </Suspense>
+            <div class="flex gap-2">
+                <input type="text" class="form-input" placeholder=t("Folder (e.g. inbox)")
+                    style="flex: 1;"
+                    bind:value=folder
+                />
+                <button class="btn btn-success" on:click=do_add>{t("Create link")}</button>
+            </div>
+            <Message signal=msg />
+        </div>
+    }
+}
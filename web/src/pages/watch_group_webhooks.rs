@@ -0,0 +1,97 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::dtos::WebhookCreateDto;
+
+use crate::api;
+use crate::components::{Loading, Message, ToastSignal, TrashIcon};
+use crate::i18n::t;
+
+/// urls receiving a POST for every change / delete within the watch group
+#[component]
+pub fn WatchGroupWebhooks(group_id: i64) -> impl IntoView {
+    let (trigger, set_trigger) = signal(0u32);
+    let webhooks = LocalResource::new(move || {
+        trigger.get();
+        api::fetch_webhooks(group_id)
+    });
+    let url = RwSignal::new(String::new());
+    let secret = RwSignal::new(String::new());
+    let msg = ToastSignal::new();
+
+    let do_add = move |_| {
+        let dto = WebhookCreateDto {
+            url: url.get_untracked().trim().to_string(),
+            secret: Some(secret.get_untracked()).filter(|s| !s.is_empty()),
+        };
+        spawn_local(async move {
+            match api::create_webhook(group_id, &dto).await {
+                Ok(()) => {
+                    url.set(String::new());
+                    secret.set(String::new());
+                    set_trigger.update(|t| *t += 1);
+                }
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let do_delete = move |hook_id: i64| {
+        spawn_local(async move {
+            match api::delete_webhook(group_id, hook_id).await {
+                Ok(()) => set_trigger.update(|t| *t += 1),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    view! {
+        <div style="margin-top: 0.75rem;">
+            <Suspense fallback=Loading>
+                {move || Suspend::new(async move {
+                    match webhooks.await {
+                        Ok(webhooks) if webhooks.is_empty() => view! {
+                            <p class="text-muted">{t("No webhooks yet.")}</p>
+                        }.into_any(),
+                        Ok(webhooks) => view! {
+                            <ul style="list-style: none; padding: 0;">
+                                {webhooks.into_iter().map(|webhook| {
+                                    let hook_id = webhook.id;
+                                    view! {
+                                        <li class="flex-between">
+                                            <span class="detail-value">
+                                                {webhook.url}
+                                                {webhook.signed.then(|| format!(" ({})", t("signed")))}
+                                            </span>
+                                            <button
+                                                class="btn btn-icon btn-danger"
+                                                title=t("Delete")
+                                                on:click=move |_| do_delete(hook_id)
+                                            >
+                                                <TrashIcon/>
+                                            </button>
+                                        </li>
+                                    }
+                                }).collect_view()}
+                            </ul>
+                        }.into_any(),
+                        Err(e) => view! {
+                            <div class="message message-error">{t("Error loading webhooks: ")} {e}</div>
+                        }.into_any(),
+                    }
+                })}
+            </Suspense>
+            <div class="flex gap-2">
+                <input type="url" class="form-input" placeholder="https://ci.local/hook"
+                    style="flex: 2;"
+                    bind:value=url
+                />
+                <input type="password" class="form-input" placeholder=t("Secret (optional)")
+                    style="flex: 1;"
+                    bind:value=secret
+                />
+                <button class="btn btn-success" on:click=do_add>{t("Add")}</button>
+            </div>
+            <Message signal=msg />
+        </div>
+    }
+}
@@ -1,9 +1,15 @@
 use leptos::prelude::*;
 use leptos_router::components::A;
-use shared::dtos::WatchGroupNameDto;
+use shared::dtos::{Durability, WatchGroupNameDto};
 
 use crate::api;
-use crate::components::{Card, EmptyState, Loading, Message, ToastSignal};
+use crate::components::{
+    Card, ConfirmDialog, EmptyState, Loading, Message, Switch, ToastSignal, TrashIcon,
+};
+use crate::i18n::t;
+
+use super::watch_group_share_links::WatchGroupShareLinks;
+use super::watch_group_webhooks::WatchGroupWebhooks;
 
 #[component]
 pub fn WatchGroupsPage() -> impl IntoView {
@@ -35,15 +41,15 @@ pub fn WatchGroupsPage() -> impl IntoView {
 
     view! {
         <div class="container">
-            <h1>"Watch Groups"</h1>
+            <h1>{t("Watch Groups")}</h1>
 
             <Card dashed=true>
                 <div class="flex gap-2">
-                    <input type="text" class="form-input" placeholder="New watch group name"
+                    <input type="text" class="form-input" placeholder=t("New watch group name")
                         style="flex: 1;"
                         bind:value=new_name
                     />
-                    <button class="btn btn-success" on:click=on_create>"Create"</button>
+                    <button class="btn btn-success" on:click=on_create>{t("Create")}</button>
                 </div>
             </Card>
             <Message signal=msg />
@@ -53,18 +59,18 @@ pub fn WatchGroupsPage() -> impl IntoView {
                     match groups.await {
                         Ok(group_list) => {
                             if group_list.is_empty() {
-                                view! { <EmptyState message="No watch groups configured yet." /> }.into_any()
+                                view! { <EmptyState message=t("No watch groups configured yet.") /> }.into_any()
                             } else {
                                 view! {
                                     <ul style="list-style: none; padding: 0;">
                                         {group_list.into_iter().map(|group| {
-                                            view! { <WatchGroupCard group_id=group.id group_name=group.name.clone() set_trigger /> }
+                                            view! { <WatchGroupCard group_id=group.id group_name=group.name.clone() verify_writes=group.verify_writes durability=group.durability priority=group.priority set_trigger /> }
                                         }).collect_view()}
                                     </ul>
                                 }.into_any()
                             }
                         }
-                        Err(e) => view! { <div class="message message-error">"Error: " {e}</div> }.into_any(),
+                        Err(e) => view! { <div class="message message-error">{t("Error: ")} {e}</div> }.into_any(),
                     }
                 })}
             </Suspense>
@@ -76,11 +82,20 @@ pub fn WatchGroupsPage() -> impl IntoView {
 fn WatchGroupCard(
     group_id: i64,
     group_name: String,
+    verify_writes: bool,
+    durability: Durability,
+    priority: i64,
     set_trigger: WriteSignal<u32>,
 ) -> impl IntoView {
     let editing = RwSignal::new(false);
     let edit_name = RwSignal::new(group_name.clone());
-    let display_name = RwSignal::new(group_name);
+    let display_name = RwSignal::new(group_name.clone());
+    let confirm_delete = RwSignal::new(false);
+    let force = RwSignal::new(false);
+    let verify_writes = RwSignal::new(verify_writes);
+    let priority = RwSignal::new(priority.to_string());
+    let show_webhooks = RwSignal::new(false);
+    let show_share_links = RwSignal::new(false);
     let msg = ToastSignal::new();
 
     let on_edit = move |_| {
@@ -101,7 +116,7 @@ fn WatchGroupCard(
                 Ok(_) => {
                     display_name.set(edit_name.get_untracked());
                     editing.set(false);
-                    msg.success("Saved!");
+                    msg.success(t("Saved!"));
                     set_trigger.update(|t| *t += 1);
                 }
                 Err(e) => {
@@ -111,13 +126,65 @@ fn WatchGroupCard(
         });
     };
 
+    let do_delete = move || {
+        leptos::task::spawn_local(async move {
+            match api::delete_watch_group(group_id, force.get_untracked()).await {
+                Ok(()) => set_trigger.update(|t| *t += 1),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let on_verify_writes = Callback::new(move |verify: bool| {
+        leptos::task::spawn_local(async move {
+            match api::set_watch_group_verify_writes(group_id, verify).await {
+                Ok(()) => verify_writes.set(verify),
+                Err(e) => msg.error(e),
+            }
+        });
+    });
+
+    let on_durability = move |ev| {
+        let Ok(durability) = Durability::try_from(event_target_value(&ev).as_str()) else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match api::set_watch_group_durability(group_id, durability).await {
+                Ok(()) => msg.success(format!(
+                    "{} - {}",
+                    t("Durability saved"),
+                    t(durability.description())
+                )),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let on_priority = move |_| {
+        let Ok(value) = priority.get_untracked().trim().parse::<i64>() else {
+            msg.error(t("Priority must be a whole number"));
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            match api::set_watch_group_priority(group_id, value).await {
+                Ok(()) => msg.success(t("Priority saved - clients sync higher priorities first")),
+                Err(e) => msg.error(e),
+            }
+        });
+    };
+
+    let confirm_msg = t("Delete watch group '{name}'? Its files are removed from the server, the history is archived.")
+        .replace("{name}", &group_name);
+
     view! {
         <li>
             <Card>
                 <div class="flex-between">
                     <div>
                         <Show when=move || !editing.get()>
-                            <span class="text-lg font-semibold">{move || display_name.get()}</span>
+                            <A href=format!("/app/watch-groups/{}/detail", group_id) attr:class="text-lg font-semibold">
+                                {move || display_name.get()}
+                            </A>
                         </Show>
                         <Show when=move || editing.get()>
                             <input type="text" class="name-input"
@@ -127,17 +194,62 @@ fn WatchGroupCard(
                         <div class="text-xs text-muted">"ID: " {group_id}</div>
                     </div>
                     <div class="flex gap-1">
+                        <Switch checked=verify_writes label=t("Verify writes") on_toggle=on_verify_writes />
+                        <select class="form-input" style="width: auto;"
+                            title=t("Durability - how hard uploads are pushed to disk")
+                            on:change=on_durability
+                        >
+                            {Durability::ALL.into_iter().map(|level| view! {
+                                <option value=level.as_str() selected=level == durability>
+                                    {level.as_str()}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                        <input type="number" class="form-input" style="width: 5rem;"
+                            title=t("Priority - clients sync higher priorities first")
+                            bind:value=priority
+                            on:change=on_priority
+                        />
                         <Show when=move || !editing.get()>
-                            <A href=format!("/app/watch-groups/{}", group_id) attr:class="btn btn-secondary">"Files"</A>
-                            <button class="btn btn-primary" on:click=on_edit>"Edit"</button>
+                            <A href=format!("/app/watch-groups/{}", group_id) attr:class="btn btn-secondary">{t("Files")}</A>
+                            <a class="btn btn-secondary" href=api::manifest_url(group_id) download
+                                title=t("Paths and hashes for seeding a client from a copied disk (client seed)")>
+                                {t("Manifest")}
+                            </a>
+                            <button class="btn btn-secondary" on:click=move |_| show_webhooks.update(|s| *s = !*s)>
+                                {t("Webhooks")}
+                            </button>
+                            <button class="btn btn-secondary" on:click=move |_| show_share_links.update(|s| *s = !*s)>
+                                {t("Inbox links")}
+                            </button>
+                            <button class="btn btn-primary" on:click=on_edit>{t("Edit")}</button>
+                            <button
+                                class="btn btn-icon btn-danger"
+                                title=t("Delete")
+                                on:click=move |_| confirm_delete.set(true)
+                            >
+                                <TrashIcon/>
+                            </button>
                         </Show>
                         <Show when=move || editing.get()>
-                            <button class="btn btn-success" on:click=on_save>"Save"</button>
-                            <button class="btn btn-secondary" on:click=on_cancel>"Cancel"</button>
+                            <button class="btn btn-success" on:click=on_save>{t("Save")}</button>
+                            <button class="btn btn-secondary" on:click=on_cancel>{t("Cancel")}</button>
                         </Show>
                     </div>
                 </div>
+                <Show when=move || show_webhooks.get()>
+                    <WatchGroupWebhooks group_id=group_id />
+                </Show>
+                <Show when=move || show_share_links.get()>
+                    <WatchGroupShareLinks group_id=group_id />
+                </Show>
                 <Message signal=msg />
+                <ConfirmDialog show=confirm_delete message=confirm_msg on_confirm=do_delete>
+                    <label class="text-muted">
+                        <input type="checkbox" bind:checked=force />
+                        " " {t("Detach clients still syncing it")}
+                    </label>
+                </ConfirmDialog>
             </Card>
         </li>
     }
@@ -0,0 +1,52 @@
+// sw.js — offline support for the phone pages and the monitor of the admin app.
+// Network first; the last good response is served while offline.
+
+const CACHE = 'file-sync-app';
+const SHELL = ['/app/', '/app/index.html'];
+
+// only the listings and the monitor series are kept - file contents can be arbitrarily large
+const CACHED_API = [
+  /^\/api\/watch-groups$/,
+  /^\/api\/watch-groups\/\d+\/files$/,
+  /^\/api\/monitor$/,
+  /^\/api\/stats\/bandwidth$/,
+];
+
+self.addEventListener('install', (event) => {
+  event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(SHELL)));
+  self.skipWaiting();
+});
+
+self.addEventListener('activate', () => {
+  self.clients.claim();
+});
+
+function isCached(url) {
+  if (url.origin !== self.location.origin) return false;
+  if (url.pathname.startsWith('/app/')) return true;
+  return CACHED_API.some((pattern) => pattern.test(url.pathname));
+}
+
+self.addEventListener('fetch', (event) => {
+  const request = event.request;
+  const url = new URL(request.url);
+  if (request.method !== 'GET' || !isCached(url)) return;
+
+  event.respondWith(
+    caches.open(CACHE).then((cache) =>
+      fetch(request)
+        .then((response) => {
+          if (response.ok) cache.put(request, response.clone());
+          return response;
+        })
+        .catch(() =>
+          cache.match(request).then((cached) => {
+            if (cached) return cached;
+            // client side routes (/app/m/watch-groups/1?path=...) all render index.html
+            if (request.mode === 'navigate') return cache.match('/app/index.html');
+            return Response.error();
+          })
+        )
+    )
+  );
+});